@@ -0,0 +1,228 @@
+//! 命令行入口
+//!
+//! 目前仅支持无界面导出用量数据，供脚本或定时任务使用：
+//! `cc-switch export-usage <logs|summary|trends|providers|models> [选项]`
+
+use std::io::{BufWriter, Write};
+
+use chrono::{Local, NaiveDate, TimeZone};
+
+use crate::database::Database;
+use crate::error::AppError;
+use crate::services::usage_export::{UsageExportKind, UsageExportRequest, UsageFileFormat};
+use crate::services::usage_stats::LogFilters;
+
+const EXPORT_USAGE_COMMAND: &str = "export-usage";
+
+const EXPORT_USAGE_HELP: &str = "\
+用法: cc-switch export-usage <logs|summary|trends|providers|models> [选项]
+
+选项:
+  --format <csv|jsonl>   输出格式（默认 csv）
+  --output <PATH>        输出文件（默认写到标准输出）
+  --start <DATE>         起始时间，YYYY-MM-DD（本地时间）或 Unix 秒
+  --end <DATE>           结束时间，YYYY-MM-DD 表示当天结束
+  --app <APP>            应用类型（claude / codex / gemini ...）
+  --provider <NAME>      供应商名称（模糊匹配）
+  --model <MODEL>        模型名称（模糊匹配）
+  --status <CODE>        HTTP 状态码
+  --source <SOURCE>      用量来源（proxy / transcript）
+  --session <ID>         会话 ID
+  --project <DIR>        项目目录
+  --currency <CODE>      报表币种（默认使用设置中的报表币种）
+";
+
+/// 处理命令行子命令
+///
+/// 参数不是已知子命令时返回 `None`，由调用方继续启动界面；否则返回进程退出码
+pub fn try_run_cli(args: &[String]) -> Option<i32> {
+    if args.get(1).map(String::as_str) != Some(EXPORT_USAGE_COMMAND) {
+        return None;
+    }
+    attach_parent_console();
+    let options = &args[2..];
+    if options.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{EXPORT_USAGE_HELP}");
+        return Some(0);
+    }
+
+    let code = match parse_export_args(options) {
+        Ok((request, output)) => match run_export(&request, output.as_deref()) {
+            Ok(rows) => {
+                eprintln!("已导出 {rows} 行");
+                0
+            }
+            Err(e) => {
+                eprintln!("导出失败: {e}");
+                1
+            }
+        },
+        Err(e) => {
+            eprintln!("{e}\n\n{EXPORT_USAGE_HELP}");
+            2
+        }
+    };
+    Some(code)
+}
+
+/// Windows 发布版使用 GUI 子系统，进程默认没有控制台；
+/// 从终端调用时附加到父进程的控制台，否则帮助信息与导出结果不会输出
+#[cfg(windows)]
+fn attach_parent_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // 已有控制台（调试版）或父进程没有控制台时调用失败，忽略即可
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_parent_console() {}
+
+fn run_export(request: &UsageExportRequest, output: Option<&str>) -> Result<u64, AppError> {
+    let db = Database::init()?;
+    match output {
+        Some(path) => {
+            let file = std::fs::File::create(path).map_err(|e| AppError::io(path, e))?;
+            db.export_usage(request, BufWriter::new(file))
+        }
+        None => {
+            let stdout = std::io::stdout();
+            let rows = db.export_usage(request, BufWriter::new(stdout.lock()))?;
+            std::io::stdout().flush().ok();
+            Ok(rows)
+        }
+    }
+}
+
+fn parse_export_args(args: &[String]) -> Result<(UsageExportRequest, Option<String>), String> {
+    let mut iter = args.iter();
+    let kind = iter.next().ok_or("缺少导出内容")?;
+    let kind: UsageExportKind = parse_enum(kind).ok_or(format!("未知的导出内容: {kind}"))?;
+
+    let mut format = UsageFileFormat::default();
+    let mut output = None;
+    let mut currency = None;
+    let mut filters = LogFilters::default();
+    while let Some(flag) = iter.next() {
+        let value = iter
+            .next()
+            .ok_or(format!("选项 {flag} 缺少参数"))?
+            .to_string();
+        match flag.as_str() {
+            "--format" => {
+                format = parse_enum(&value).ok_or(format!("未知的输出格式: {value}"))?;
+            }
+            "--output" => output = Some(value),
+            "--start" => filters.start_date = Some(parse_time(&value, false)?),
+            "--end" => filters.end_date = Some(parse_time(&value, true)?),
+            "--app" => filters.app_type = Some(value),
+            "--provider" => filters.provider_name = Some(value),
+            "--model" => filters.model = Some(value),
+            "--status" => {
+                filters.status_code = Some(
+                    value
+                        .parse()
+                        .map_err(|_| format!("无效的状态码: {value}"))?,
+                );
+            }
+            "--source" => filters.source = Some(value),
+            "--session" => filters.session_id = Some(value),
+            "--project" => filters.project_dir = Some(value),
+            "--currency" => currency = Some(value),
+            _ => return Err(format!("未知选项: {flag}")),
+        }
+    }
+
+    Ok((
+        UsageExportRequest {
+            kind,
+            format,
+            filters,
+            currency,
+        },
+        output,
+    ))
+}
+
+/// 复用 serde 的小写枚举名解析
+fn parse_enum<T: serde::de::DeserializeOwned>(value: &str) -> Option<T> {
+    serde_json::from_value(serde_json::Value::String(value.to_ascii_lowercase())).ok()
+}
+
+/// 解析 Unix 秒或本地日期；`end_of_day` 为 true 时日期取当天最后一秒
+fn parse_time(value: &str, end_of_day: bool) -> Result<i64, String> {
+    if let Ok(ts) = value.parse::<i64>() {
+        return Ok(ts);
+    }
+    let date =
+        NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| format!("无效的时间: {value}"))?;
+    let time = if end_of_day {
+        date.and_hms_opt(23, 59, 59)
+    } else {
+        date.and_hms_opt(0, 0, 0)
+    }
+    .ok_or(format!("无效的时间: {value}"))?;
+    Local
+        .from_local_datetime(&time)
+        .earliest()
+        .map(|t| t.timestamp())
+        .ok_or(format!("无效的时间: {value}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_export_args() {
+        let (request, output) = parse_export_args(&args(&[
+            "logs",
+            "--format",
+            "JSONL",
+            "--output",
+            "/tmp/out.jsonl",
+            "--start",
+            "1700000000",
+            "--project",
+            "/repo/a",
+            "--status",
+            "200",
+        ]))
+        .unwrap();
+        assert_eq!(request.kind, UsageExportKind::Logs);
+        assert_eq!(request.format, UsageFileFormat::Jsonl);
+        assert_eq!(output.as_deref(), Some("/tmp/out.jsonl"));
+        assert_eq!(request.filters.start_date, Some(1_700_000_000));
+        assert_eq!(request.filters.project_dir.as_deref(), Some("/repo/a"));
+        assert_eq!(request.filters.status_code, Some(200));
+
+        assert!(parse_export_args(&args(&["bogus"])).is_err());
+        assert!(parse_export_args(&args(&["summary", "--model"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_time_dates() {
+        let start = parse_time("2025-03-01", false).unwrap();
+        let end = parse_time("2025-03-01", true).unwrap();
+        assert_eq!(end - start, 86_399);
+        assert!(parse_time("03/01/2025", false).is_err());
+    }
+
+    #[test]
+    fn test_non_cli_args_fall_through() {
+        assert_eq!(try_run_cli(&args(&["cc-switch"])), None);
+        assert_eq!(
+            try_run_cli(&args(&["cc-switch", "ccswitch://import"])),
+            None
+        );
+    }
+}
@@ -0,0 +1,60 @@
+//! 余额对账命令
+
+use crate::error::AppError;
+use crate::services::balance_reconciliation::{
+    self, BalanceReconciliation, BalanceReconciliationConfig, BalanceSnapshot,
+};
+use crate::store::AppState;
+use tauri::State;
+
+/// 获取对账配置
+#[tauri::command]
+pub fn get_balance_reconciliation_config(
+    state: State<'_, AppState>,
+) -> Result<BalanceReconciliationConfig, AppError> {
+    state.db.get_balance_reconciliation_config()
+}
+
+/// 保存对账配置
+#[tauri::command]
+pub fn save_balance_reconciliation_config(
+    state: State<'_, AppState>,
+    config: BalanceReconciliationConfig,
+) -> Result<(), AppError> {
+    state.db.save_balance_reconciliation_config(&config)
+}
+
+/// 获取供应商套餐的余额快照
+#[tauri::command]
+pub fn get_balance_snapshots(
+    state: State<'_, AppState>,
+    app_type: String,
+    provider_id: String,
+    plan_name: Option<String>,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+) -> Result<Vec<BalanceSnapshot>, AppError> {
+    state.db.list_balance_snapshots(
+        &app_type,
+        &provider_id,
+        plan_name.as_deref().unwrap_or(""),
+        start_date,
+        end_date,
+    )
+}
+
+/// 生成余额对账报告
+#[tauri::command]
+pub async fn get_balance_reconciliation(
+    state: State<'_, AppState>,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+    app_type: Option<String>,
+) -> Result<Vec<BalanceReconciliation>, AppError> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        balance_reconciliation::build_report(&db, start_date, end_date, app_type.as_deref())
+    })
+    .await
+    .map_err(|e| AppError::Message(format!("生成对账报告失败: {e}")))?
+}
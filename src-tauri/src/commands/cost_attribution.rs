@@ -0,0 +1,77 @@
+//! 会话与项目成本归属命令
+
+use crate::error::AppError;
+use crate::services::cost_attribution::{
+    refresh_session_projects_in_background, ProjectCostStats, ProjectMonthlyCost, SessionCostStats,
+};
+use crate::store::AppState;
+use tauri::State;
+
+/// 默认返回的会话数量
+const DEFAULT_TOP_SESSIONS: u32 = 20;
+
+fn spawn_error(e: impl std::fmt::Display) -> AppError {
+    AppError::Message(format!("统计会话成本失败: {e}"))
+}
+
+/// 获取成本最高的会话
+///
+/// 会话与项目的对应关系由会话管理器在后台定期刷新，本次查询使用已有结果
+#[tauri::command]
+pub async fn get_top_session_costs(
+    state: State<'_, AppState>,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+    limit: Option<u32>,
+    currency: Option<String>,
+) -> Result<Vec<SessionCostStats>, AppError> {
+    let db = state.db.clone();
+    refresh_session_projects_in_background(db.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+        let currency = db.resolve_reporting_currency(currency.as_deref())?;
+        db.get_top_sessions_by_cost(
+            start_date,
+            end_date,
+            limit.unwrap_or(DEFAULT_TOP_SESSIONS),
+            &currency,
+        )
+    })
+    .await
+    .map_err(spawn_error)?
+}
+
+/// 按项目目录汇总成本
+#[tauri::command]
+pub async fn get_project_costs(
+    state: State<'_, AppState>,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+    currency: Option<String>,
+) -> Result<Vec<ProjectCostStats>, AppError> {
+    let db = state.db.clone();
+    refresh_session_projects_in_background(db.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+        let currency = db.resolve_reporting_currency(currency.as_deref())?;
+        db.get_project_cost_stats(start_date, end_date, &currency)
+    })
+    .await
+    .map_err(spawn_error)?
+}
+
+/// 按项目目录与月份汇总成本
+#[tauri::command]
+pub async fn get_project_monthly_costs(
+    state: State<'_, AppState>,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+    currency: Option<String>,
+) -> Result<Vec<ProjectMonthlyCost>, AppError> {
+    let db = state.db.clone();
+    refresh_session_projects_in_background(db.clone());
+    tauri::async_runtime::spawn_blocking(move || {
+        let currency = db.resolve_reporting_currency(currency.as_deref())?;
+        db.get_project_monthly_costs(start_date, end_date, &currency)
+    })
+    .await
+    .map_err(spawn_error)?
+}
@@ -0,0 +1,52 @@
+//! 后台健康探测命令
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::services::health_probe::{
+    HealthHistoryPoint, HealthProbeConfig, HealthProbeService, ProviderProbeResult,
+};
+use crate::store::AppState;
+use tauri::State;
+
+/// 获取后台健康探测配置
+#[tauri::command]
+pub fn get_health_probe_config(state: State<'_, AppState>) -> Result<HealthProbeConfig, AppError> {
+    state.db.get_health_probe_config()
+}
+
+/// 保存后台健康探测配置
+#[tauri::command]
+pub fn save_health_probe_config(
+    state: State<'_, AppState>,
+    config: HealthProbeConfig,
+) -> Result<(), AppError> {
+    state.db.save_health_probe_config(&config)
+}
+
+/// 立即执行一轮探测（忽略安静模式）
+#[tauri::command]
+pub async fn run_health_probes_now(
+    state: State<'_, AppState>,
+) -> Result<Vec<ProviderProbeResult>, AppError> {
+    let config = state.db.get_health_probe_config()?;
+    HealthProbeService::run_once(&state, &config, true).await
+}
+
+/// 查询供应商的延迟/可用率历史
+#[tauri::command]
+pub fn get_provider_health_history(
+    state: State<'_, AppState>,
+    app_type: AppType,
+    provider_id: String,
+    start_ts: i64,
+    end_ts: i64,
+    bucket_secs: Option<i64>,
+) -> Result<Vec<HealthHistoryPoint>, AppError> {
+    state.db.get_provider_health_history(
+        app_type.as_str(),
+        &provider_id,
+        start_ts,
+        end_ts,
+        bucket_secs.unwrap_or(3600),
+    )
+}
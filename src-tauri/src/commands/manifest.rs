@@ -0,0 +1,35 @@
+#![allow(non_snake_case)]
+
+use std::path::PathBuf;
+use tauri::State;
+
+use crate::commands::skill::SkillServiceState;
+use crate::services::manifest::{ManifestApplyResult, ManifestPlan, ManifestService};
+use crate::store::AppState;
+
+/// 预览配置清单与当前状态的差异
+#[tauri::command]
+pub async fn plan_config_manifest(
+    filePath: String,
+    prune: Option<bool>,
+    state: State<'_, AppState>,
+) -> Result<ManifestPlan, String> {
+    let manifest = ManifestService::load(&PathBuf::from(&filePath)).map_err(|e| e.to_string())?;
+    ManifestService::plan(&state.db, &manifest, prune)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// 应用配置清单（幂等）
+#[tauri::command]
+pub async fn apply_config_manifest(
+    filePath: String,
+    prune: Option<bool>,
+    state: State<'_, AppState>,
+    skill_service: State<'_, SkillServiceState>,
+) -> Result<ManifestApplyResult, String> {
+    let manifest = ManifestService::load(&PathBuf::from(&filePath)).map_err(|e| e.to_string())?;
+    ManifestService::apply(&state, &skill_service.0, &manifest, prune)
+        .await
+        .map_err(|e| e.to_string())
+}
@@ -1009,19 +1009,11 @@ fn launch_linux_terminal(config_file: &std::path::Path) -> Result<(), String> {
     use std::os::unix::fs::PermissionsExt;
     use std::process::Command;
 
-    let preferred = crate::settings::get_preferred_terminal();
+    use crate::session_manager::terminal::linux::{
+        candidate_terminals, find_in_path, terminal_argv,
+    };
 
-    // Default terminal list with their arguments
-    let default_terminals = [
-        ("gnome-terminal", vec!["--"]),
-        ("konsole", vec!["-e"]),
-        ("xfce4-terminal", vec!["-e"]),
-        ("mate-terminal", vec!["--"]),
-        ("lxterminal", vec!["-e"]),
-        ("alacritty", vec!["-e"]),
-        ("kitty", vec!["-e"]),
-        ("ghostty", vec!["-e"]),
-    ];
+    let preferred = crate::settings::get_preferred_terminal();
 
     // Create temp script file
     let temp_dir = std::env::temp_dir();
@@ -1045,51 +1037,28 @@ exec bash --norc --noprofile
     std::fs::set_permissions(&script_file, std::fs::Permissions::from_mode(0o755))
         .map_err(|e| format!("设置脚本权限失败: {e}"))?;
 
-    // Build terminal list: preferred terminal first (if specified), then defaults
-    let terminals_to_try: Vec<(&str, Vec<&str>)> = if let Some(ref pref) = preferred {
-        // Find the preferred terminal's args from default list
-        let pref_args = default_terminals
-            .iter()
-            .find(|(name, _)| *name == pref.as_str())
-            .map(|(_, args)| args.iter().map(|s| *s).collect::<Vec<&str>>())
-            .unwrap_or_else(|| vec!["-e"]); // Default args for unknown terminals
-
-        let mut list = vec![(pref.as_str(), pref_args)];
-        // Add remaining terminals as fallbacks
-        for (name, args) in &default_terminals {
-            if *name != pref.as_str() {
-                list.push((*name, args.iter().map(|s| *s).collect()));
-            }
-        }
-        list
-    } else {
-        default_terminals
-            .iter()
-            .map(|(name, args)| (*name, args.iter().map(|s| *s).collect()))
-            .collect()
-    };
-
+    let script_path = script_file.to_string_lossy();
+    let program = ["bash", script_path.as_ref()];
     let mut last_error = String::from("未找到可用的终端");
 
-    for (terminal, args) in terminals_to_try {
-        // Check if terminal exists in common paths
-        let terminal_exists = std::path::Path::new(&format!("/usr/bin/{}", terminal)).exists()
-            || std::path::Path::new(&format!("/bin/{}", terminal)).exists()
-            || std::path::Path::new(&format!("/usr/local/bin/{}", terminal)).exists()
-            || which_command(terminal);
-
-        if terminal_exists {
-            let result = Command::new(terminal)
-                .args(&args)
-                .arg("bash")
-                .arg(script_file.to_string_lossy().as_ref())
-                .spawn();
-
-            match result {
-                Ok(_) => return Ok(()),
-                Err(e) => {
-                    last_error = format!("执行 {} 失败: {}", terminal, e);
-                }
+    // 首选终端优先，其余按共享的终端表回退
+    for terminal in candidate_terminals(preferred.as_deref()) {
+        let Some(terminal_path) = find_in_path(&terminal) else {
+            continue;
+        };
+        // 未知的首选终端按 `-e` 传参
+        let argv = terminal_argv(&terminal, None, &program).unwrap_or_else(|| {
+            [terminal.as_str(), "-e"]
+                .into_iter()
+                .chain(program)
+                .map(String::from)
+                .collect()
+        });
+
+        match Command::new(terminal_path).args(&argv[1..]).spawn() {
+            Ok(_) => return Ok(()),
+            Err(e) => {
+                last_error = format!("执行 {} 失败: {}", terminal, e);
             }
         }
     }
@@ -1100,17 +1069,6 @@ exec bash --norc --noprofile
     Err(last_error)
 }
 
-/// Check if a command exists using `which`
-#[cfg(target_os = "linux")]
-fn which_command(cmd: &str) -> bool {
-    use std::process::Command;
-    Command::new("which")
-        .arg(cmd)
-        .output()
-        .map(|o| o.status.success())
-        .unwrap_or(false)
-}
-
 /// Windows: 根据用户首选终端启动
 #[cfg(target_os = "windows")]
 fn launch_windows_terminal(
@@ -1,13 +1,19 @@
 #![allow(non_snake_case)]
 
+mod balance_reconciliation;
 mod config;
+mod cost_attribution;
 mod deeplink;
 mod env;
 mod failover;
 mod global_proxy;
+mod health_probe;
 mod import_export;
+mod manifest;
 mod mcp;
 mod misc;
+mod model_fidelity;
+mod notification;
 mod omo;
 mod openclaw;
 mod plugin;
@@ -19,18 +25,28 @@ mod settings;
 pub mod skill;
 mod stream_check;
 mod sync_support;
+mod transcript_usage;
 mod usage;
+mod usage_export;
+mod usage_forecast;
+mod usage_retention;
 mod webdav_sync;
 mod workspace;
 
+pub use balance_reconciliation::*;
 pub use config::*;
+pub use cost_attribution::*;
 pub use deeplink::*;
 pub use env::*;
 pub use failover::*;
 pub use global_proxy::*;
+pub use health_probe::*;
 pub use import_export::*;
+pub use manifest::*;
 pub use mcp::*;
 pub use misc::*;
+pub use model_fidelity::*;
+pub use notification::*;
 pub use omo::*;
 pub use openclaw::*;
 pub use plugin::*;
@@ -41,6 +57,10 @@ pub use session_manager::*;
 pub use settings::*;
 pub use skill::*;
 pub use stream_check::*;
+pub use transcript_usage::*;
 pub use usage::*;
+pub use usage_export::*;
+pub use usage_forecast::*;
+pub use usage_retention::*;
 pub use webdav_sync::*;
 pub use workspace::*;
@@ -0,0 +1,70 @@
+//! 模型替换检测命令
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::services::model_fidelity::{
+    self, ModelFidelityConfig, ModelFidelityStats, ModelProbeResult,
+};
+use crate::store::AppState;
+use tauri::State;
+
+/// 获取模型替换检测配置
+#[tauri::command]
+pub fn get_model_fidelity_config(
+    state: State<'_, AppState>,
+) -> Result<ModelFidelityConfig, AppError> {
+    state.db.get_model_fidelity_config()
+}
+
+/// 保存模型替换检测配置
+#[tauri::command]
+pub fn save_model_fidelity_config(
+    state: State<'_, AppState>,
+    config: ModelFidelityConfig,
+) -> Result<(), AppError> {
+    state.db.save_model_fidelity_config(&config)
+}
+
+/// 按供应商统计模型一致率
+#[tauri::command]
+pub async fn get_model_fidelity_stats(
+    state: State<'_, AppState>,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+    app_type: Option<String>,
+) -> Result<Vec<ModelFidelityStats>, AppError> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        db.get_model_fidelity_stats(start_date, end_date, app_type.as_deref())
+    })
+    .await
+    .map_err(|e| AppError::Message(format!("统计模型一致率失败: {e}")))?
+}
+
+/// 获取供应商最近的指纹探测结果
+#[tauri::command]
+pub fn get_model_probe_results(
+    state: State<'_, AppState>,
+    app_type: AppType,
+    provider_id: String,
+    limit: Option<u32>,
+) -> Result<Vec<ModelProbeResult>, AppError> {
+    state
+        .db
+        .list_model_probe_results(app_type.as_str(), &provider_id, limit.unwrap_or(30))
+}
+
+/// 立即对供应商执行一轮指纹探测
+#[tauri::command]
+pub async fn run_model_fingerprint_probe(
+    state: State<'_, AppState>,
+    app_type: AppType,
+    provider_id: String,
+) -> Result<Vec<ModelProbeResult>, AppError> {
+    let providers = state.db.get_all_providers(app_type.as_str())?;
+    let provider = providers
+        .get(&provider_id)
+        .ok_or_else(|| AppError::Message(format!("供应商 {provider_id} 不存在")))?;
+
+    model_fidelity::probe_provider(&state.db, &app_type, provider).await
+}
@@ -0,0 +1,30 @@
+//! 通知渠道命令
+
+use crate::error::AppError;
+use crate::services::notification::{self, NotificationConfig, NotificationSink};
+use crate::store::AppState;
+use tauri::{AppHandle, State};
+
+/// 获取通知配置
+#[tauri::command]
+pub fn get_notification_config(state: State<'_, AppState>) -> Result<NotificationConfig, AppError> {
+    state.db.get_notification_config()
+}
+
+/// 保存通知配置
+#[tauri::command]
+pub fn save_notification_config(
+    state: State<'_, AppState>,
+    config: NotificationConfig,
+) -> Result<(), AppError> {
+    state.db.save_notification_config(&config)
+}
+
+/// 向指定渠道发送测试通知
+#[tauri::command]
+pub async fn test_notification_sink(
+    app: AppHandle,
+    sink: NotificationSink,
+) -> Result<(), AppError> {
+    notification::send_test(&app, &sink).await
+}
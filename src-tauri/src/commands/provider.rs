@@ -4,6 +4,7 @@ use tauri::State;
 use crate::app_config::AppType;
 use crate::error::AppError;
 use crate::provider::Provider;
+use crate::services::quota_guard::{QuotaExhaustion, QuotaGuardService};
 use crate::services::{
     EndpointLatency, ProviderService, ProviderSortUpdate, SpeedtestService, SwitchResult,
 };
@@ -139,12 +140,47 @@ pub fn import_default_config(state: State<'_, AppState>, app: String) -> Result<
 #[allow(non_snake_case)]
 #[tauri::command]
 pub async fn queryProviderUsage(
+    app_handle: tauri::AppHandle,
     state: State<'_, AppState>,
     #[allow(non_snake_case)] providerId: String, // 使用 camelCase 匹配前端
     app: String,
 ) -> Result<crate::provider::UsageResult, String> {
     let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
-    ProviderService::query_usage(state.inner(), app_type, &providerId)
+    let result = ProviderService::query_usage(state.inner(), app_type.clone(), &providerId)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // 额度守护：查询结果同时作为路由容量信号
+    if let Err(e) =
+        QuotaGuardService::apply_usage_result(&app_handle, &app_type, &providerId, &result).await
+    {
+        log::warn!("[QuotaGuard] 应用额度结果失败: {e}");
+    }
+    Ok(result)
+}
+
+/// 获取额度耗尽的供应商列表
+#[tauri::command]
+pub fn get_quota_exhaustions(
+    state: State<'_, AppState>,
+    app: Option<String>,
+) -> Result<Vec<QuotaExhaustion>, String> {
+    state
+        .db
+        .list_quota_exhaustions(app.as_deref())
+        .map_err(|e| e.to_string())
+}
+
+/// 手动清除额度耗尽状态（撤销守护动作）
+#[allow(non_snake_case)]
+#[tauri::command]
+pub async fn clear_quota_exhaustion(
+    app_handle: tauri::AppHandle,
+    app: String,
+    #[allow(non_snake_case)] providerId: String,
+) -> Result<(), String> {
+    let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+    QuotaGuardService::clear(&app_handle, &app_type, &providerId)
         .await
         .map_err(|e| e.to_string())
 }
@@ -189,12 +225,30 @@ pub fn read_live_provider_settings(app: String) -> Result<serde_json::Value, Str
 
 #[tauri::command]
 pub async fn test_api_endpoints(
+    state: State<'_, AppState>,
     urls: Vec<String>,
     #[allow(non_snake_case)] timeoutSecs: Option<u64>,
+    app: Option<String>,
+    #[allow(non_snake_case)] providerId: Option<String>,
 ) -> Result<Vec<EndpointLatency>, String> {
-    SpeedtestService::test_endpoints(urls, timeoutSecs)
+    let results = SpeedtestService::test_endpoints(urls, timeoutSecs)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?;
+
+    // 传入供应商时持久化测速结果，供代理端点池排序使用
+    if let (Some(app), Some(provider_id)) = (app, providerId) {
+        let app_type = AppType::from_str(&app).map_err(|e| e.to_string())?;
+        if let Err(e) = ProviderService::record_endpoint_latencies(
+            state.inner(),
+            app_type,
+            &provider_id,
+            &results,
+        ) {
+            log::warn!("保存端点测速结果失败: {e}");
+        }
+    }
+
+    Ok(results)
 }
 
 #[tauri::command]
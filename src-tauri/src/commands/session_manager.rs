@@ -1,6 +1,8 @@
 #![allow(non_snake_case)]
 
 use crate::session_manager;
+use crate::store::AppState;
+use tauri::State;
 
 #[tauri::command]
 pub async fn list_sessions() -> Result<Vec<session_manager::SessionMeta>, String> {
@@ -57,3 +59,295 @@ pub async fn launch_session_terminal(
 
     Ok(true)
 }
+
+#[tauri::command]
+pub async fn search_sessions(
+    state: State<'_, AppState>,
+    query: session_manager::search::SessionSearchQuery,
+) -> Result<Vec<session_manager::search::SessionSearchHit>, String> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || session_manager::search::search(&db, &query))
+        .await
+        .map_err(|e| format!("Failed to search sessions: {e}"))?
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn refresh_session_index(
+    state: State<'_, AppState>,
+    rebuild: Option<bool>,
+) -> Result<session_manager::search::SessionIndexStats, String> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if rebuild.unwrap_or(false) {
+            db.clear_session_index()?;
+        }
+        session_manager::search::refresh_index(&db)
+    })
+    .await
+    .map_err(|e| format!("Failed to refresh session index: {e}"))?
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn export_session(
+    providerId: String,
+    sourcePath: String,
+    options: session_manager::export::ExportOptions,
+    filePath: String,
+) -> Result<session_manager::export::SessionExportResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let bundle = session_manager::export::build_bundle(&providerId, &sourcePath, &options)?;
+        let rendered = session_manager::export::render(&bundle, &options)?;
+        std::fs::write(&filePath, &rendered)
+            .map_err(|e| format!("Failed to write export file: {e}"))?;
+        Ok(session_manager::export::SessionExportResult {
+            path: filePath,
+            bytes: rendered.len(),
+            message_count: bundle.messages.len(),
+        })
+    })
+    .await
+    .map_err(|e| format!("Failed to export session: {e}"))?
+}
+
+/// Render a session without writing it, e.g. for copying to the clipboard.
+#[tauri::command]
+pub async fn render_session_export(
+    providerId: String,
+    sourcePath: String,
+    options: session_manager::export::ExportOptions,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let bundle = session_manager::export::build_bundle(&providerId, &sourcePath, &options)?;
+        session_manager::export::render(&bundle, &options)
+    })
+    .await
+    .map_err(|e| format!("Failed to export session: {e}"))?
+}
+
+#[tauri::command]
+pub async fn import_session_bundle(
+    filePath: String,
+) -> Result<session_manager::export::SessionBundle, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let data = std::fs::read_to_string(&filePath)
+            .map_err(|e| format!("Failed to read session bundle: {e}"))?;
+        session_manager::export::import_bundle(&data)
+    })
+    .await
+    .map_err(|e| format!("Failed to import session bundle: {e}"))?
+}
+
+/// Convert a session into another CLI's format and write it where that CLI resumes from.
+#[tauri::command]
+pub async fn convert_session(
+    request: session_manager::convert::ConvertRequest,
+) -> Result<session_manager::convert::ConvertResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        session_manager::convert::convert_session(&request)
+    })
+    .await
+    .map_err(|e| format!("Failed to convert session: {e}"))?
+}
+
+#[tauri::command]
+pub async fn get_session_annotations(
+    state: State<'_, AppState>,
+) -> Result<Vec<session_manager::manage::SessionAnnotation>, String> {
+    state
+        .db
+        .list_session_annotations()
+        .map_err(|e| e.to_string())
+}
+
+/// Set a custom title and tags; clearing both removes the annotation.
+#[tauri::command]
+pub async fn set_session_annotation(
+    state: State<'_, AppState>,
+    providerId: String,
+    sessionId: String,
+    title: Option<String>,
+    tags: Vec<String>,
+) -> Result<Option<session_manager::manage::SessionAnnotation>, String> {
+    session_manager::manage::set_annotation(&state.db, &providerId, &sessionId, title, tags)
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_sessions(
+    state: State<'_, AppState>,
+    sessions: Vec<session_manager::manage::SessionRef>,
+) -> Result<session_manager::manage::SessionActionReport, String> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        session_manager::manage::delete_sessions(&db, &sessions)
+    })
+    .await
+    .map_err(|e| format!("Failed to delete sessions: {e}"))
+}
+
+#[tauri::command]
+pub async fn archive_sessions(
+    state: State<'_, AppState>,
+    sessions: Vec<session_manager::manage::SessionRef>,
+) -> Result<session_manager::manage::SessionActionReport, String> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        session_manager::manage::archive_sessions(&db, &sessions)
+    })
+    .await
+    .map_err(|e| format!("Failed to archive sessions: {e}"))
+}
+
+#[tauri::command]
+pub async fn list_archived_sessions(
+    state: State<'_, AppState>,
+) -> Result<Vec<session_manager::manage::ArchivedSession>, String> {
+    state.db.list_session_archives().map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn restore_archived_session(
+    state: State<'_, AppState>,
+    archiveId: String,
+) -> Result<session_manager::manage::ArchivedSession, String> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        session_manager::manage::restore_session(&db, &archiveId)
+    })
+    .await
+    .map_err(|e| format!("Failed to restore session: {e}"))?
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn delete_archived_session(
+    state: State<'_, AppState>,
+    archiveId: String,
+) -> Result<bool, String> {
+    session_manager::manage::delete_archive(&state.db, &archiveId).map_err(|e| e.to_string())?;
+    Ok(true)
+}
+
+/// Bulk prune by age, message count or missing project; dry-run by default.
+#[tauri::command]
+pub async fn prune_sessions(
+    state: State<'_, AppState>,
+    rules: session_manager::manage::SessionPruneRules,
+) -> Result<session_manager::manage::SessionPruneReport, String> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || session_manager::manage::prune(&db, &rules))
+        .await
+        .map_err(|e| format!("Failed to prune sessions: {e}"))?
+        .map_err(|e| e.to_string())
+}
+
+/// Start tailing a session; appended messages arrive as `session-live-messages` events.
+#[tauri::command]
+pub async fn watch_session(
+    providerId: String,
+    sourcePath: String,
+) -> Result<session_manager::live::LiveSessionState, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        session_manager::live::watch(&providerId, &sourcePath)
+    })
+    .await
+    .map_err(|e| format!("Failed to watch session: {e}"))?
+}
+
+#[tauri::command]
+pub async fn unwatch_session(sourcePath: String) -> Result<bool, String> {
+    tauri::async_runtime::spawn_blocking(move || session_manager::live::unwatch(&sourcePath))
+        .await
+        .map_err(|e| format!("Failed to unwatch session: {e}"))
+}
+
+#[tauri::command]
+pub async fn get_live_sessions() -> Result<Vec<session_manager::live::LiveSessionState>, String> {
+    tauri::async_runtime::spawn_blocking(session_manager::live::states)
+        .await
+        .map_err(|e| format!("Failed to get live sessions: {e}"))
+}
+
+/// Watch for new and newly active Claude Code / Codex sessions.
+#[tauri::command]
+pub async fn set_live_session_discovery(enabled: bool) -> Result<bool, String> {
+    tauri::async_runtime::spawn_blocking(move || session_manager::live::set_discovery(enabled))
+        .await
+        .map_err(|e| format!("Failed to toggle session discovery: {e}"))?;
+    Ok(enabled)
+}
+
+/// Duration, turns, tool usage, files touched and per-turn cost of one session.
+#[tauri::command]
+pub async fn get_session_analytics(
+    state: State<'_, AppState>,
+    providerId: String,
+    sourcePath: String,
+) -> Result<session_manager::analytics::SessionAnalytics, String> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        session_manager::analytics::session_analytics(&db, &providerId, &sourcePath)
+    })
+    .await
+    .map_err(|e| format!("Failed to analyze session: {e}"))?
+    .map_err(|e| e.to_string())
+}
+
+/// Session stats summed per project directory.
+#[tauri::command]
+pub async fn get_project_analytics(
+    state: State<'_, AppState>,
+    query: Option<session_manager::analytics::ProjectAnalyticsQuery>,
+) -> Result<Vec<session_manager::analytics::ProjectAnalytics>, String> {
+    let db = state.db.clone();
+    let query = query.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        session_manager::analytics::project_analytics(&db, &query)
+    })
+    .await
+    .map_err(|e| format!("Failed to analyze projects: {e}"))?
+    .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_session_summaries(
+    state: State<'_, AppState>,
+) -> Result<Vec<session_manager::summarize::SessionSummary>, String> {
+    state.db.list_session_summaries().map_err(|e| e.to_string())
+}
+
+/// Generate a title, summary and keywords for one session now (spend cap still applies).
+#[tauri::command]
+pub async fn summarize_session(
+    state: State<'_, AppState>,
+    providerId: String,
+    sourcePath: String,
+) -> Result<session_manager::summarize::SessionSummary, String> {
+    session_manager::summarize::summarize_session(&state.db, &providerId, &sourcePath)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn get_session_summary_config(
+    state: State<'_, AppState>,
+) -> Result<session_manager::summarize::SessionSummaryConfig, String> {
+    state
+        .db
+        .get_session_summary_config()
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub async fn save_session_summary_config(
+    state: State<'_, AppState>,
+    config: session_manager::summarize::SessionSummaryConfig,
+) -> Result<bool, String> {
+    state
+        .db
+        .save_session_summary_config(&config)
+        .map_err(|e| e.to_string())?;
+    Ok(true)
+}
@@ -76,6 +76,7 @@ pub async fn stream_check_all_providers(
                 response_time_ms: None,
                 http_status: None,
                 model_used: String::new(),
+                returned_model: None,
                 tested_at: chrono::Utc::now().timestamp(),
                 retry_count: 0,
             });
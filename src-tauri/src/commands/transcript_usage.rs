@@ -0,0 +1,36 @@
+//! 会话记录用量导入命令
+
+use crate::error::AppError;
+use crate::services::transcript_usage::{
+    TranscriptIngestConfig, TranscriptIngestResult, TranscriptUsageService,
+};
+use crate::store::AppState;
+use tauri::State;
+
+/// 获取会话记录导入配置
+#[tauri::command]
+pub fn get_transcript_ingest_config(
+    state: State<'_, AppState>,
+) -> Result<TranscriptIngestConfig, AppError> {
+    state.db.get_transcript_ingest_config()
+}
+
+/// 保存会话记录导入配置
+#[tauri::command]
+pub fn save_transcript_ingest_config(
+    state: State<'_, AppState>,
+    config: TranscriptIngestConfig,
+) -> Result<(), AppError> {
+    state.db.save_transcript_ingest_config(&config)
+}
+
+/// 立即导入一轮会话记录用量（不受开关限制）
+#[tauri::command]
+pub async fn run_transcript_ingest_now(
+    state: State<'_, AppState>,
+) -> Result<TranscriptIngestResult, AppError> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || TranscriptUsageService::run_once(&db))
+        .await
+        .map_err(|e| AppError::Message(format!("导入会话用量失败: {e}")))?
+}
@@ -1,18 +1,31 @@
 //! 使用统计相关命令
 
 use crate::error::AppError;
+use crate::proxy::usage::calculator::PricingTierConfig;
+use crate::services::currency::ExchangeRate;
+use crate::services::pricing::{
+    parse_tiers, ModelPricingAlias, ModelPricingVersion, RecomputeCostsResult,
+};
+use crate::services::pricing_catalog::{
+    parse_catalog, PricingCatalog, PricingCatalogFormat, PricingImportOptions,
+    PricingImportPreview, PricingImportResult,
+};
 use crate::services::usage_stats::*;
 use crate::store::AppState;
 use tauri::State;
 
 /// 获取使用量汇总
+///
+/// `currency` 为报表币种，未指定时使用默认报表币种
 #[tauri::command]
 pub fn get_usage_summary(
     state: State<'_, AppState>,
     start_date: Option<i64>,
     end_date: Option<i64>,
+    currency: Option<String>,
 ) -> Result<UsageSummary, AppError> {
-    state.db.get_usage_summary(start_date, end_date)
+    let currency = state.db.resolve_reporting_currency(currency.as_deref())?;
+    state.db.get_usage_summary(start_date, end_date, &currency)
 }
 
 /// 获取每日趋势
@@ -21,20 +34,30 @@ pub fn get_usage_trends(
     state: State<'_, AppState>,
     start_date: Option<i64>,
     end_date: Option<i64>,
+    currency: Option<String>,
 ) -> Result<Vec<DailyStats>, AppError> {
-    state.db.get_daily_trends(start_date, end_date)
+    let currency = state.db.resolve_reporting_currency(currency.as_deref())?;
+    state.db.get_daily_trends(start_date, end_date, &currency)
 }
 
 /// 获取 Provider 统计
 #[tauri::command]
-pub fn get_provider_stats(state: State<'_, AppState>) -> Result<Vec<ProviderStats>, AppError> {
-    state.db.get_provider_stats()
+pub fn get_provider_stats(
+    state: State<'_, AppState>,
+    currency: Option<String>,
+) -> Result<Vec<ProviderStats>, AppError> {
+    let currency = state.db.resolve_reporting_currency(currency.as_deref())?;
+    state.db.get_provider_stats(&currency)
 }
 
 /// 获取模型统计
 #[tauri::command]
-pub fn get_model_stats(state: State<'_, AppState>) -> Result<Vec<ModelStats>, AppError> {
-    state.db.get_model_stats()
+pub fn get_model_stats(
+    state: State<'_, AppState>,
+    currency: Option<String>,
+) -> Result<Vec<ModelStats>, AppError> {
+    let currency = state.db.resolve_reporting_currency(currency.as_deref())?;
+    state.db.get_model_stats(&currency)
 }
 
 /// 获取请求日志列表
@@ -82,7 +105,8 @@ pub fn get_model_pricing(state: State<'_, AppState>) -> Result<Vec<ModelPricingI
 
     let mut stmt = conn.prepare(
         "SELECT model_id, display_name, input_cost_per_million, output_cost_per_million,
-                cache_read_cost_per_million, cache_creation_cost_per_million
+                cache_read_cost_per_million, cache_creation_cost_per_million,
+                cache_creation_1h_cost_per_million, tiers, effective_from
          FROM model_pricing
          ORDER BY display_name",
     )?;
@@ -95,6 +119,9 @@ pub fn get_model_pricing(state: State<'_, AppState>) -> Result<Vec<ModelPricingI
             output_cost_per_million: row.get(3)?,
             cache_read_cost_per_million: row.get(4)?,
             cache_creation_cost_per_million: row.get(5)?,
+            cache_creation_1h_cost_per_million: row.get(6)?,
+            tiers: parse_tiers(row.get(7)?),
+            effective_from: row.get(8)?,
         })
     })?;
 
@@ -108,7 +135,10 @@ pub fn get_model_pricing(state: State<'_, AppState>) -> Result<Vec<ModelPricingI
 }
 
 /// 更新模型定价
+///
+/// 价格变化时旧定价自动归档为历史版本，`effective_from` 为新价格的生效时间（秒）
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub fn update_model_pricing(
     state: State<'_, AppState>,
     model_id: String,
@@ -117,27 +147,168 @@ pub fn update_model_pricing(
     output_cost: String,
     cache_read_cost: String,
     cache_creation_cost: String,
+    cache_creation_1h_cost: Option<String>,
+    tiers: Option<Vec<PricingTierConfig>>,
+    effective_from: Option<i64>,
 ) -> Result<(), AppError> {
-    let db = state.db.clone();
-    let conn = crate::database::lock_conn!(db.conn);
+    let version = ModelPricingVersion {
+        id: None,
+        model_id,
+        input_cost_per_million: input_cost,
+        output_cost_per_million: output_cost,
+        cache_read_cost_per_million: cache_read_cost,
+        cache_creation_cost_per_million: cache_creation_cost,
+        cache_creation_1h_cost_per_million: cache_creation_1h_cost.filter(|s| !s.trim().is_empty()),
+        tiers: tiers.unwrap_or_default(),
+        effective_from: 0,
+        effective_to: None,
+    };
+    state
+        .db
+        .save_model_pricing_version(&display_name, version, effective_from)
+}
 
-    conn.execute(
-        "INSERT OR REPLACE INTO model_pricing (
-            model_id, display_name, input_cost_per_million, output_cost_per_million,
-            cache_read_cost_per_million, cache_creation_cost_per_million
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        rusqlite::params![
-            model_id,
-            display_name,
-            input_cost,
-            output_cost,
-            cache_read_cost,
-            cache_creation_cost
-        ],
-    )
-    .map_err(|e| AppError::Database(format!("更新模型定价失败: {e}")))?;
+/// 获取模型的全部定价版本（历史在前，当前在后）
+#[tauri::command]
+pub fn get_model_pricing_versions(
+    state: State<'_, AppState>,
+    model_id: String,
+) -> Result<Vec<ModelPricingVersion>, AppError> {
+    state.db.list_model_pricing_versions(&model_id)
+}
 
-    Ok(())
+/// 补录一段历史定价
+#[tauri::command]
+pub fn add_model_pricing_history(
+    state: State<'_, AppState>,
+    version: ModelPricingVersion,
+) -> Result<i64, AppError> {
+    state.db.add_model_pricing_history(&version)
+}
+
+/// 删除一条历史定价
+#[tauri::command]
+pub fn delete_model_pricing_history(state: State<'_, AppState>, id: i64) -> Result<(), AppError> {
+    state.db.delete_model_pricing_history(id)
+}
+
+/// 按请求发生时生效的定价重新计算历史成本
+#[tauri::command]
+pub fn recompute_usage_costs(
+    state: State<'_, AppState>,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+) -> Result<RecomputeCostsResult, AppError> {
+    state.db.recompute_request_costs(start_date, end_date)
+}
+
+/// 为尚未计价的历史请求补算成本
+#[tauri::command]
+pub fn backfill_unpriced_usage_costs(
+    state: State<'_, AppState>,
+) -> Result<RecomputeCostsResult, AppError> {
+    state.db.backfill_unpriced_request_costs()
+}
+
+fn read_pricing_catalog(
+    file_path: &str,
+    format: Option<PricingCatalogFormat>,
+) -> Result<PricingCatalog, AppError> {
+    let content = std::fs::read_to_string(file_path).map_err(|e| AppError::io(file_path, e))?;
+    parse_catalog(&content, format)
+}
+
+/// 预览价目文件导入的差异
+///
+/// `format` 为空时根据文件内容自动识别
+#[tauri::command]
+pub fn preview_pricing_catalog(
+    state: State<'_, AppState>,
+    file_path: String,
+    format: Option<PricingCatalogFormat>,
+) -> Result<PricingImportPreview, AppError> {
+    let catalog = read_pricing_catalog(&file_path, format)?;
+    state.db.preview_pricing_import(catalog)
+}
+
+/// 导入价目文件
+#[tauri::command]
+pub fn import_pricing_catalog(
+    state: State<'_, AppState>,
+    file_path: String,
+    format: Option<PricingCatalogFormat>,
+    options: Option<PricingImportOptions>,
+) -> Result<PricingImportResult, AppError> {
+    let catalog = read_pricing_catalog(&file_path, format)?;
+    state
+        .db
+        .apply_pricing_import(catalog, &options.unwrap_or_default())
+}
+
+/// 获取模型定价别名
+#[tauri::command]
+pub fn get_model_pricing_aliases(
+    state: State<'_, AppState>,
+) -> Result<Vec<ModelPricingAlias>, AppError> {
+    state.db.list_model_pricing_aliases()
+}
+
+/// 新增或更新模型定价别名
+#[tauri::command]
+pub fn save_model_pricing_alias(
+    state: State<'_, AppState>,
+    alias: ModelPricingAlias,
+) -> Result<(), AppError> {
+    state.db.save_model_pricing_alias(&alias)
+}
+
+/// 删除模型定价别名
+#[tauri::command]
+pub fn delete_model_pricing_alias(
+    state: State<'_, AppState>,
+    pattern: String,
+) -> Result<(), AppError> {
+    state.db.delete_model_pricing_alias(&pattern)
+}
+
+/// 获取汇率列表
+#[tauri::command]
+pub fn get_exchange_rates(
+    state: State<'_, AppState>,
+    currency: Option<String>,
+) -> Result<Vec<ExchangeRate>, AppError> {
+    state.db.list_exchange_rates(currency.as_deref())
+}
+
+/// 新增或覆盖汇率
+#[tauri::command]
+pub fn save_exchange_rate(state: State<'_, AppState>, rate: ExchangeRate) -> Result<(), AppError> {
+    state.db.save_exchange_rate(&rate)
+}
+
+/// 删除汇率
+#[tauri::command]
+pub fn delete_exchange_rate(
+    state: State<'_, AppState>,
+    currency: String,
+    effective_from: i64,
+) -> Result<(), AppError> {
+    state.db.delete_exchange_rate(&currency, effective_from)
+}
+
+/// 获取默认报表币种
+#[tauri::command]
+pub fn get_reporting_currency(state: State<'_, AppState>) -> Result<String, AppError> {
+    state.db.get_reporting_currency()
+}
+
+/// 设置默认报表币种
+#[tauri::command]
+pub fn set_reporting_currency(
+    state: State<'_, AppState>,
+    currency: String,
+) -> Result<(), AppError> {
+    state.db.set_reporting_currency(&currency)
 }
 
 /// 检查 Provider 使用限额
@@ -153,15 +324,7 @@ pub fn check_provider_limits(
 /// 删除模型定价
 #[tauri::command]
 pub fn delete_model_pricing(state: State<'_, AppState>, model_id: String) -> Result<(), AppError> {
-    let db = state.db.clone();
-    let conn = crate::database::lock_conn!(db.conn);
-
-    conn.execute(
-        "DELETE FROM model_pricing WHERE model_id = ?1",
-        rusqlite::params![model_id],
-    )
-    .map_err(|e| AppError::Database(format!("删除模型定价失败: {e}")))?;
-
+    state.db.delete_model_pricing(&model_id)?;
     log::info!("已删除模型定价: {model_id}");
     Ok(())
 }
@@ -176,4 +339,11 @@ pub struct ModelPricingInfo {
     pub output_cost_per_million: String,
     pub cache_read_cost_per_million: String,
     pub cache_creation_cost_per_million: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_creation_1h_cost_per_million: Option<String>,
+    #[serde(default)]
+    pub tiers: Vec<PricingTierConfig>,
+    /// 当前定价的生效时间（秒），0 表示不限
+    #[serde(default)]
+    pub effective_from: i64,
 }
@@ -0,0 +1,27 @@
+//! 用量导出命令
+
+use crate::error::AppError;
+use crate::services::usage_export::{UsageExportRequest, UsageExportResult};
+use crate::store::AppState;
+use std::io::BufWriter;
+use tauri::State;
+
+/// 导出请求日志或统计视图到文件（CSV / JSONL）
+#[tauri::command]
+pub async fn export_usage(
+    state: State<'_, AppState>,
+    request: UsageExportRequest,
+    file_path: String,
+) -> Result<UsageExportResult, AppError> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let file = std::fs::File::create(&file_path).map_err(|e| AppError::io(&file_path, e))?;
+        let rows = db.export_usage(&request, BufWriter::new(file))?;
+        Ok(UsageExportResult {
+            rows,
+            path: file_path,
+        })
+    })
+    .await
+    .map_err(|e| AppError::Message(format!("导出用量失败: {e}")))?
+}
@@ -0,0 +1,32 @@
+//! 花费预测与预警命令
+
+use crate::error::AppError;
+use crate::services::usage_forecast::{self, UsageForecast, UsageForecastConfig};
+use crate::store::AppState;
+use tauri::State;
+
+/// 获取预测与预警配置
+#[tauri::command]
+pub fn get_usage_forecast_config(
+    state: State<'_, AppState>,
+) -> Result<UsageForecastConfig, AppError> {
+    state.db.get_usage_forecast_config()
+}
+
+/// 保存预测与预警配置
+#[tauri::command]
+pub fn save_usage_forecast_config(
+    state: State<'_, AppState>,
+    config: UsageForecastConfig,
+) -> Result<(), AppError> {
+    state.db.save_usage_forecast_config(&config)
+}
+
+/// 立即计算花费预测与预警（不受开关限制）
+#[tauri::command]
+pub async fn get_usage_forecast(state: State<'_, AppState>) -> Result<UsageForecast, AppError> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || usage_forecast::run_once(&db))
+        .await
+        .map_err(|e| AppError::Message(format!("计算花费预测失败: {e}")))?
+}
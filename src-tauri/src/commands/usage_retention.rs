@@ -0,0 +1,34 @@
+//! 用量日志保留命令
+
+use crate::error::AppError;
+use crate::services::usage_rollup::{self, UsageRetentionConfig, UsageRetentionResult};
+use crate::store::AppState;
+use tauri::State;
+
+/// 获取日志保留配置
+#[tauri::command]
+pub fn get_usage_retention_config(
+    state: State<'_, AppState>,
+) -> Result<UsageRetentionConfig, AppError> {
+    state.db.get_usage_retention_config()
+}
+
+/// 保存日志保留配置
+#[tauri::command]
+pub fn save_usage_retention_config(
+    state: State<'_, AppState>,
+    config: UsageRetentionConfig,
+) -> Result<(), AppError> {
+    state.db.save_usage_retention_config(&config)
+}
+
+/// 立即按当前配置归档并清理过期日志（不受开关限制）
+#[tauri::command]
+pub async fn run_usage_retention_now(
+    state: State<'_, AppState>,
+) -> Result<UsageRetentionResult, AppError> {
+    let db = state.db.clone();
+    tauri::async_runtime::spawn_blocking(move || usage_rollup::run_once(&db))
+        .await
+        .map_err(|e| AppError::Message(format!("执行日志保留失败: {e}")))?
+}
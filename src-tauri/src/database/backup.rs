@@ -91,6 +91,7 @@ impl Database {
                 .step(-1)
                 .map_err(|e| AppError::Database(e.to_string()))?;
         }
+        self.cache.clear();
 
         let backup_id = backup_path
             .and_then(|p| p.file_stem().map(|s| s.to_string_lossy().to_string()))
@@ -289,12 +290,21 @@ impl Database {
         while let Some(row) = rows.next().map_err(|e| AppError::Database(e.to_string()))? {
             let obj_type: String = row.get(0).map_err(|e| AppError::Database(e.to_string()))?;
             let name: String = row.get(1).map_err(|e| AppError::Database(e.to_string()))?;
+            let tbl_name: String = row.get(2).map_err(|e| AppError::Database(e.to_string()))?;
             let sql: String = row.get(3).map_err(|e| AppError::Database(e.to_string()))?;
 
             // 跳过 SQLite 内部对象（如 sqlite_sequence）
             if name.starts_with("sqlite_") {
                 continue;
             }
+            // 会话全文索引是本机缓存（含 FTS5 影子表），导入后按需重建
+            if tbl_name.starts_with("session_index_") {
+                continue;
+            }
+            // 汇总触发器在导入后由建表流程重建，避免导入日志时重复计入汇总表
+            if obj_type == "trigger" && name.starts_with("usage_rollup_") {
+                continue;
+            }
 
             output.push_str(&sql);
             output.push_str(";\n");
@@ -464,6 +474,7 @@ impl Database {
         self.create_tables()?;
         self.apply_schema_migrations()?;
         self.ensure_model_pricing_seeded()?;
+        self.cache.clear();
 
         log::info!("Database restored from backup: {filename}, safety backup: {safety_id}");
         Ok(safety_id)
@@ -0,0 +1,29 @@
+//! 热路径读取的内存缓存
+//!
+//! 代理每个请求都会读取的少量状态缓存在内存中，由对应的写入方法负责失效；
+//! 导入 SQL / 从备份恢复后整体清空。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::RwLock;
+
+use crate::services::model_fidelity::ModelFidelityConfig;
+
+#[derive(Default)]
+pub(crate) struct DbCache {
+    /// app_type → 额度耗尽的供应商 ID
+    pub(crate) quota_exhausted: RwLock<HashMap<String, HashSet<String>>>,
+    /// 模型替换检测配置（每条请求日志都会读取）
+    pub(crate) model_fidelity: RwLock<Option<ModelFidelityConfig>>,
+}
+
+impl DbCache {
+    /// 清空全部缓存
+    pub(crate) fn clear(&self) {
+        if let Ok(mut cache) = self.quota_exhausted.write() {
+            cache.clear();
+        }
+        if let Ok(mut cache) = self.model_fidelity.write() {
+            *cache = None;
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! 余额快照与对账 DAO
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::services::balance_reconciliation::{
+    BalanceReconciliationConfig, BalanceSnapshot, ConsumptionInterval,
+};
+use crate::services::currency::{ensure_reportable, report_cost_expr, BASE_CURRENCY};
+use rusqlite::{params, OptionalExtension};
+
+const BALANCE_RECONCILIATION_CONFIG_KEY: &str = "balance_reconciliation_config";
+
+/// 快照对应的供应商套餐
+#[derive(Debug, Clone, PartialEq)]
+pub struct BalanceSnapshotKey {
+    pub app_type: String,
+    pub provider_id: String,
+    pub plan_name: String,
+    pub provider_name: Option<String>,
+}
+
+/// 对账区间内的计算成本
+#[derive(Debug, Clone, PartialEq)]
+pub struct IntervalCosts {
+    pub currency: String,
+    pub request_count: u64,
+    pub computed_cost: f64,
+    pub base_cost: f64,
+}
+
+impl Database {
+    /// 保存余额快照
+    ///
+    /// 同一套餐最近一条快照的余额相同且间隔小于 `dedupe_secs` 时跳过，返回是否写入
+    pub fn insert_balance_snapshot(
+        &self,
+        snapshot: &BalanceSnapshot,
+        dedupe_secs: i64,
+    ) -> Result<bool, AppError> {
+        let conn = lock_conn!(self.conn);
+        let last: Option<(Option<f64>, Option<f64>, i64)> = conn
+            .query_row(
+                "SELECT used, remaining, created_at FROM provider_balance_snapshots
+                 WHERE app_type = ?1 AND provider_id = ?2 AND plan_name = ?3
+                 ORDER BY created_at DESC, id DESC LIMIT 1",
+                params![snapshot.app_type, snapshot.provider_id, snapshot.plan_name],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+        if let Some((used, remaining, created_at)) = last {
+            if used == snapshot.used
+                && remaining == snapshot.remaining
+                && snapshot.created_at - created_at < dedupe_secs
+            {
+                return Ok(false);
+            }
+        }
+
+        conn.execute(
+            "INSERT INTO provider_balance_snapshots (
+                app_type, provider_id, plan_name, used, remaining, total, unit, created_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                snapshot.app_type,
+                snapshot.provider_id,
+                snapshot.plan_name,
+                snapshot.used,
+                snapshot.remaining,
+                snapshot.total,
+                snapshot.unit,
+                snapshot.created_at,
+            ],
+        )
+        .map_err(|e| AppError::Database(format!("保存余额快照失败: {e}")))?;
+        Ok(true)
+    }
+
+    /// 有快照的供应商套餐
+    pub fn list_balance_snapshot_keys(
+        &self,
+        app_type: Option<&str>,
+    ) -> Result<Vec<BalanceSnapshotKey>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn.prepare(
+            "SELECT DISTINCT s.app_type, s.provider_id, s.plan_name, p.name
+             FROM provider_balance_snapshots s
+             LEFT JOIN providers p ON p.id = s.provider_id AND p.app_type = s.app_type
+             WHERE ?1 IS NULL OR s.app_type = ?1
+             ORDER BY s.app_type, s.provider_id, s.plan_name",
+        )?;
+        let rows = stmt.query_map([app_type], |row| {
+            Ok(BalanceSnapshotKey {
+                app_type: row.get(0)?,
+                provider_id: row.get(1)?,
+                plan_name: row.get(2)?,
+                provider_name: row.get(3)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 某个供应商套餐的快照（按时间升序）
+    pub fn list_balance_snapshots(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        plan_name: &str,
+        start_date: Option<i64>,
+        end_date: Option<i64>,
+    ) -> Result<Vec<BalanceSnapshot>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn.prepare(
+            "SELECT id, app_type, provider_id, plan_name, used, remaining, total, unit, created_at
+             FROM provider_balance_snapshots
+             WHERE app_type = ?1 AND provider_id = ?2 AND plan_name = ?3
+               AND (?4 IS NULL OR created_at >= ?4) AND (?5 IS NULL OR created_at <= ?5)
+             ORDER BY created_at ASC, id ASC",
+        )?;
+        let rows = stmt.query_map(
+            params![app_type, provider_id, plan_name, start_date, end_date],
+            |row| {
+                Ok(BalanceSnapshot {
+                    id: row.get(0)?,
+                    app_type: row.get(1)?,
+                    provider_id: row.get(2)?,
+                    plan_name: row.get(3)?,
+                    used: row.get(4)?,
+                    remaining: row.get(5)?,
+                    total: row.get(6)?,
+                    unit: row.get(7)?,
+                    created_at: row.get(8)?,
+                })
+            },
+        )?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 汇总各消耗区间内的计算成本
+    ///
+    /// `currency` 为用量脚本单位对应的币种，未配置汇率或无法识别时按 USD 计算
+    pub fn sum_interval_costs(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        intervals: &[ConsumptionInterval],
+        currency: Option<&str>,
+    ) -> Result<IntervalCosts, AppError> {
+        let conn = lock_conn!(self.conn);
+        let currency = currency
+            .and_then(|c| ensure_reportable(&conn, c).ok())
+            .unwrap_or_else(|| BASE_CURRENCY.to_string());
+        let cost_expr = report_cost_expr("l.", &currency);
+        let mut stmt = conn.prepare(&format!(
+            "SELECT COUNT(*),
+                    COALESCE(SUM({cost_expr}), 0),
+                    COALESCE(SUM(({cost_expr}) /
+                        COALESCE(NULLIF(CAST(l.cost_multiplier AS REAL), 0), 1)), 0)
+             FROM proxy_request_logs l
+             WHERE l.app_type = ?1 AND l.provider_id = ?2
+               AND l.created_at > ?3 AND l.created_at <= ?4"
+        ))?;
+
+        let mut costs = IntervalCosts {
+            currency: currency.clone(),
+            request_count: 0,
+            computed_cost: 0.0,
+            base_cost: 0.0,
+        };
+        for interval in intervals {
+            let (count, computed, base): (i64, f64, f64) = stmt.query_row(
+                params![app_type, provider_id, interval.start, interval.end],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )?;
+            costs.request_count += count as u64;
+            costs.computed_cost += computed;
+            costs.base_cost += base;
+        }
+        Ok(costs)
+    }
+
+    /// 删除早于 `before` 的快照
+    pub fn prune_balance_snapshots(&self, before: i64) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM provider_balance_snapshots WHERE created_at < ?1",
+            [before],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 获取对账配置
+    pub fn get_balance_reconciliation_config(
+        &self,
+    ) -> Result<BalanceReconciliationConfig, AppError> {
+        match self.get_setting(BALANCE_RECONCILIATION_CONFIG_KEY)? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| AppError::Message(format!("解析配置失败: {e}"))),
+            None => Ok(BalanceReconciliationConfig::default()),
+        }
+    }
+
+    /// 保存对账配置
+    pub fn save_balance_reconciliation_config(
+        &self,
+        config: &BalanceReconciliationConfig,
+    ) -> Result<(), AppError> {
+        config.validate()?;
+        let json = serde_json::to_string(config)
+            .map_err(|e| AppError::Message(format!("序列化配置失败: {e}")))?;
+        self.set_setting(BALANCE_RECONCILIATION_CONFIG_KEY, &json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::balance_reconciliation::{
+        build_report, consumption_intervals, ReconciliationStatus,
+    };
+
+    fn snapshot(remaining: f64, created_at: i64) -> BalanceSnapshot {
+        BalanceSnapshot {
+            id: 0,
+            app_type: "claude".to_string(),
+            provider_id: "p1".to_string(),
+            plan_name: String::new(),
+            used: None,
+            remaining: Some(remaining),
+            total: Some(100.0),
+            unit: Some("USD".to_string()),
+            created_at,
+        }
+    }
+
+    fn insert_log(
+        db: &Database,
+        request_id: &str,
+        cost: &str,
+        created_at: i64,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(db.conn);
+        conn.execute(
+            "INSERT INTO proxy_request_logs (
+                request_id, provider_id, app_type, model, input_tokens, output_tokens,
+                total_cost_usd, cost_multiplier, latency_ms, status_code, created_at
+            ) VALUES (?1, 'p1', 'claude', 'm', 100, 10, ?2, '2', 100, 200, ?3)",
+            params![request_id, cost, created_at],
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_snapshot_dedupe_and_report() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        assert!(db.insert_balance_snapshot(&snapshot(100.0, 1000), 300)?);
+        // 余额未变且间隔过短：跳过
+        assert!(!db.insert_balance_snapshot(&snapshot(100.0, 1100), 300)?);
+        assert!(db.insert_balance_snapshot(&snapshot(94.0, 2000), 300)?);
+
+        // 区间 (1000, 2000] 内计算成本 4（倍率 2，原价 2）；区间外的日志不计入
+        insert_log(&db, "r1", "3", 1500)?;
+        insert_log(&db, "r2", "1", 2000)?;
+        insert_log(&db, "r3", "50", 2500)?;
+
+        let snapshots = db.list_balance_snapshots("claude", "p1", "", None, None)?;
+        assert_eq!(snapshots.len(), 2);
+        let (intervals, _) = consumption_intervals(&snapshots);
+        let costs = db.sum_interval_costs("claude", "p1", &intervals, Some("USD"))?;
+        assert_eq!(costs.request_count, 2);
+        assert_eq!((costs.computed_cost, costs.base_cost), (4.0, 2.0));
+
+        let report = build_report(&db, None, None, None)?;
+        assert_eq!(report.len(), 1);
+        assert_eq!(report[0].reported_usage, 6.0);
+        assert_eq!(report[0].status, ReconciliationStatus::Overcharged);
+        assert_eq!(report[0].effective_multiplier, Some(3.0));
+
+        assert_eq!(db.prune_balance_snapshots(1500)?, 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,320 @@
+//! 会话与项目成本归属 DAO
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::proxy::session::cli_session_id_sql;
+use crate::services::cost_attribution::{
+    ProjectCostStats, ProjectMonthlyCost, SessionCostStats, SessionProject, PROJECT_SOURCE_HEADER,
+};
+use crate::services::currency::report_cost_expr;
+use rusqlite::params;
+
+/// 请求日志与会话项目的关联子句（session_projects 按 CLI 原始会话 ID 记录）
+fn session_project_join() -> String {
+    format!(
+        "LEFT JOIN session_projects sp
+         ON sp.session_id = {} AND sp.app_type = l.app_type",
+        cli_session_id_sql("l.session_id")
+    )
+}
+
+/// 生成时间范围过滤条件（作用于别名 l）
+fn time_range_clause(start_date: Option<i64>, end_date: Option<i64>) -> (String, Vec<i64>) {
+    let mut conditions = Vec::new();
+    let mut params = Vec::new();
+    if let Some(start) = start_date {
+        conditions.push("l.created_at >= ?");
+        params.push(start);
+    }
+    if let Some(end) = end_date {
+        conditions.push("l.created_at <= ?");
+        params.push(end);
+    }
+    if conditions.is_empty() {
+        (String::new(), params)
+    } else {
+        (format!("AND {}", conditions.join(" AND ")), params)
+    }
+}
+
+impl Database {
+    /// 批量写入会话与项目的对应关系
+    ///
+    /// 请求头声明的项目优先：扫描结果只会补充标题，不会覆盖请求头写入的目录
+    pub fn save_session_projects(&self, projects: &[SessionProject]) -> Result<(), AppError> {
+        if projects.is_empty() {
+            return Ok(());
+        }
+        let mut conn = lock_conn!(self.conn);
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let now = chrono::Utc::now().timestamp();
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO session_projects (
+                    session_id, app_type, project_dir, title, source, updated_at
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                ON CONFLICT(session_id, app_type) DO UPDATE SET
+                    project_dir = CASE
+                        WHEN session_projects.source = ?7 AND excluded.source != ?7
+                        THEN session_projects.project_dir
+                        ELSE excluded.project_dir END,
+                    source = CASE
+                        WHEN session_projects.source = ?7 THEN ?7
+                        ELSE excluded.source END,
+                    title = COALESCE(excluded.title, session_projects.title),
+                    updated_at = excluded.updated_at",
+            )?;
+            for project in projects {
+                stmt.execute(params![
+                    project.session_id,
+                    project.app_type,
+                    project.project_dir,
+                    project.title,
+                    project.source,
+                    now,
+                    PROJECT_SOURCE_HEADER,
+                ])
+                .map_err(|e| AppError::Database(format!("保存会话项目失败: {e}")))?;
+            }
+        }
+        tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// 按成本排序的会话列表
+    pub fn get_top_sessions_by_cost(
+        &self,
+        start_date: Option<i64>,
+        end_date: Option<i64>,
+        limit: u32,
+        currency: &str,
+    ) -> Result<Vec<SessionCostStats>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let (range, mut range_params) = time_range_clause(start_date, end_date);
+        let cost_expr = report_cost_expr("l.", currency);
+        let join = session_project_join();
+        let sql = format!(
+            "SELECT
+                l.session_id,
+                l.app_type,
+                sp.project_dir,
+                sp.title,
+                COUNT(*) as request_count,
+                COALESCE(SUM(l.input_tokens), 0),
+                COALESCE(SUM(l.output_tokens), 0),
+                COALESCE(SUM(l.cache_read_tokens), 0),
+                COALESCE(SUM(l.cache_creation_tokens), 0),
+                COALESCE(SUM({cost_expr}), 0) as total_cost,
+                MIN(l.created_at),
+                MAX(l.created_at)
+             FROM proxy_request_logs l
+             {join}
+             WHERE l.session_id IS NOT NULL AND l.session_id != '' {range}
+             GROUP BY l.session_id, l.app_type
+             ORDER BY total_cost DESC, request_count DESC
+             LIMIT ?"
+        );
+        range_params.push(i64::from(limit));
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(range_params), |row| {
+            Ok(SessionCostStats {
+                session_id: row.get(0)?,
+                app_type: row.get(1)?,
+                project_dir: row.get(2)?,
+                title: row.get(3)?,
+                request_count: row.get::<_, i64>(4)? as u64,
+                total_input_tokens: row.get::<_, i64>(5)? as u64,
+                total_output_tokens: row.get::<_, i64>(6)? as u64,
+                total_cache_read_tokens: row.get::<_, i64>(7)? as u64,
+                total_cache_creation_tokens: row.get::<_, i64>(8)? as u64,
+                total_cost: format!("{:.6}", row.get::<_, f64>(9)?),
+                currency: currency.to_string(),
+                first_request_at: row.get(10)?,
+                last_request_at: row.get(11)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 按项目目录汇总成本（无法归属的请求汇总到 project_dir 为空的一行）
+    pub fn get_project_cost_stats(
+        &self,
+        start_date: Option<i64>,
+        end_date: Option<i64>,
+        currency: &str,
+    ) -> Result<Vec<ProjectCostStats>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let (range, range_params) = time_range_clause(start_date, end_date);
+        let cost_expr = report_cost_expr("l.", currency);
+        let join = session_project_join();
+        let sql = format!(
+            "SELECT
+                sp.project_dir,
+                COUNT(DISTINCT l.session_id),
+                COUNT(*),
+                COALESCE(SUM(l.input_tokens + l.output_tokens), 0),
+                COALESCE(SUM({cost_expr}), 0) as total_cost
+             FROM proxy_request_logs l
+             {join}
+             WHERE 1 = 1 {range}
+             GROUP BY sp.project_dir
+             ORDER BY total_cost DESC"
+        );
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(range_params), |row| {
+            Ok(ProjectCostStats {
+                project_dir: row.get(0)?,
+                session_count: row.get::<_, i64>(1)? as u64,
+                request_count: row.get::<_, i64>(2)? as u64,
+                total_tokens: row.get::<_, i64>(3)? as u64,
+                total_cost: format!("{:.6}", row.get::<_, f64>(4)?),
+                currency: currency.to_string(),
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 按项目目录与月份（本地时间）汇总成本
+    pub fn get_project_monthly_costs(
+        &self,
+        start_date: Option<i64>,
+        end_date: Option<i64>,
+        currency: &str,
+    ) -> Result<Vec<ProjectMonthlyCost>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let (range, range_params) = time_range_clause(start_date, end_date);
+        let cost_expr = report_cost_expr("l.", currency);
+        let join = session_project_join();
+        let sql = format!(
+            "SELECT
+                strftime('%Y-%m', l.created_at, 'unixepoch', 'localtime') as month,
+                sp.project_dir,
+                COUNT(*),
+                COALESCE(SUM(l.input_tokens + l.output_tokens), 0),
+                COALESCE(SUM({cost_expr}), 0) as total_cost
+             FROM proxy_request_logs l
+             {join}
+             WHERE 1 = 1 {range}
+             GROUP BY month, sp.project_dir
+             ORDER BY month DESC, total_cost DESC"
+        );
+
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(range_params), |row| {
+            Ok(ProjectMonthlyCost {
+                month: row.get(0)?,
+                project_dir: row.get(1)?,
+                request_count: row.get::<_, i64>(2)? as u64,
+                total_tokens: row.get::<_, i64>(3)? as u64,
+                total_cost: format!("{:.6}", row.get::<_, f64>(4)?),
+                currency: currency.to_string(),
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::cost_attribution::PROJECT_SOURCE_SESSION;
+
+    fn insert_log(
+        db: &Database,
+        request_id: &str,
+        session_id: &str,
+        cost: &str,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(db.conn);
+        conn.execute(
+            "INSERT INTO proxy_request_logs (
+                request_id, provider_id, app_type, model, input_tokens, output_tokens,
+                total_cost_usd, latency_ms, status_code, session_id, created_at
+            ) VALUES (?1, 'p1', 'claude', 'm', 100, 10, ?2, 100, 200, ?3, 1000)",
+            params![request_id, cost, session_id],
+        )?;
+        Ok(())
+    }
+
+    fn project(session_id: &str, dir: &str, source: &'static str) -> SessionProject {
+        SessionProject {
+            session_id: session_id.to_string(),
+            app_type: "claude".to_string(),
+            project_dir: dir.to_string(),
+            title: None,
+            source,
+        }
+    }
+
+    #[test]
+    fn test_session_and_project_costs() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        insert_log(&db, "r1", "s1", "1.5")?;
+        insert_log(&db, "r2", "s1", "0.5")?;
+        insert_log(&db, "r3", "s2", "0.25")?;
+        insert_log(&db, "r4", "s3", "3")?;
+
+        db.save_session_projects(&[project("s1", "/repo/a", PROJECT_SOURCE_HEADER)])?;
+        // 扫描结果不能覆盖请求头声明的项目
+        db.save_session_projects(&[
+            project("s1", "/repo/other", PROJECT_SOURCE_SESSION),
+            project("s2", "/repo/a", PROJECT_SOURCE_SESSION),
+        ])?;
+
+        let top = db.get_top_sessions_by_cost(None, None, 2, "USD")?;
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].session_id, "s3");
+        assert_eq!(top[0].project_dir, None);
+        assert_eq!(top[1].session_id, "s1");
+        assert_eq!(top[1].request_count, 2);
+        assert_eq!(top[1].total_cost, "2.000000");
+        assert_eq!(top[1].project_dir.as_deref(), Some("/repo/a"));
+
+        let projects = db.get_project_cost_stats(None, None, "USD")?;
+        let repo_a = projects
+            .iter()
+            .find(|p| p.project_dir.as_deref() == Some("/repo/a"))
+            .unwrap();
+        assert_eq!((repo_a.session_count, repo_a.request_count), (2, 3));
+        assert_eq!(repo_a.total_cost, "2.250000");
+
+        let monthly = db.get_project_monthly_costs(None, None, "USD")?;
+        assert_eq!(monthly.len(), 2);
+        assert!(monthly.iter().all(|m| m.month.len() == 7));
+        Ok(())
+    }
+
+    #[test]
+    fn test_codex_proxy_sessions_join_scanned_projects() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        {
+            let conn = lock_conn!(db.conn);
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model, total_cost_usd,
+                    latency_ms, status_code, session_id, created_at
+                ) VALUES ('r1', 'p1', 'codex', 'm', '1', 100, 200, 'codex_0199-abc', 1000)",
+                [],
+            )?;
+        }
+        // 会话扫描得到的是 Codex 会话文件中的原始 ID
+        db.save_session_projects(&[SessionProject {
+            session_id: "0199-abc".to_string(),
+            app_type: "codex".to_string(),
+            project_dir: "/repo/codex".to_string(),
+            title: None,
+            source: PROJECT_SOURCE_SESSION,
+        }])?;
+
+        let top = db.get_top_sessions_by_cost(None, None, 10, "USD")?;
+        assert_eq!(top[0].project_dir.as_deref(), Some("/repo/codex"));
+        Ok(())
+    }
+}
@@ -0,0 +1,145 @@
+//! 汇率与报表币种 DAO
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::services::currency::{
+    ensure_reportable, normalize_currency, ExchangeRate, BASE_CURRENCY,
+};
+use rusqlite::params;
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+const REPORTING_CURRENCY_KEY: &str = "usage_reporting_currency";
+
+impl Database {
+    /// 获取汇率列表（按币种、生效时间排序）
+    pub fn list_exchange_rates(
+        &self,
+        currency: Option<&str>,
+    ) -> Result<Vec<ExchangeRate>, AppError> {
+        let currency = currency.map(normalize_currency).transpose()?;
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn.prepare(
+            "SELECT currency, rate, effective_from, source FROM exchange_rates
+             WHERE ?1 IS NULL OR currency = ?1
+             ORDER BY currency ASC, effective_from ASC",
+        )?;
+        let rows = stmt.query_map([currency], |row| {
+            Ok(ExchangeRate {
+                currency: row.get(0)?,
+                rate: row.get(1)?,
+                effective_from: row.get(2)?,
+                source: row.get(3)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 新增或覆盖一条汇率（同币种同生效时间视为同一条）
+    pub fn save_exchange_rate(&self, rate: &ExchangeRate) -> Result<(), AppError> {
+        let currency = normalize_currency(&rate.currency)?;
+        if currency == BASE_CURRENCY {
+            return Err(AppError::InvalidInput(
+                "USD 为基准币种，无需配置汇率".to_string(),
+            ));
+        }
+        let value = Decimal::from_str(rate.rate.trim())
+            .map_err(|e| AppError::InvalidInput(format!("汇率无效: {e}")))?;
+        if value <= Decimal::ZERO {
+            return Err(AppError::InvalidInput("汇率必须大于 0".to_string()));
+        }
+
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT OR REPLACE INTO exchange_rates (currency, rate, effective_from, source)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                currency,
+                value.normalize().to_string(),
+                rate.effective_from,
+                rate.source
+            ],
+        )
+        .map_err(|e| AppError::Database(format!("保存汇率失败: {e}")))?;
+        Ok(())
+    }
+
+    /// 删除一条汇率
+    pub fn delete_exchange_rate(
+        &self,
+        currency: &str,
+        effective_from: i64,
+    ) -> Result<(), AppError> {
+        let currency = normalize_currency(currency)?;
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM exchange_rates WHERE currency = ?1 AND effective_from = ?2",
+            params![currency, effective_from],
+        )
+        .map_err(|e| AppError::Database(format!("删除汇率失败: {e}")))?;
+        Ok(())
+    }
+
+    /// 获取默认报表币种（未设置时为 USD）
+    pub fn get_reporting_currency(&self) -> Result<String, AppError> {
+        Ok(self
+            .get_setting(REPORTING_CURRENCY_KEY)?
+            .and_then(|value| normalize_currency(&value).ok())
+            .unwrap_or_else(|| BASE_CURRENCY.to_string()))
+    }
+
+    /// 设置默认报表币种
+    pub fn set_reporting_currency(&self, currency: &str) -> Result<(), AppError> {
+        let currency = {
+            let conn = lock_conn!(self.conn);
+            ensure_reportable(&conn, currency)?
+        };
+        self.set_setting(REPORTING_CURRENCY_KEY, &currency)
+    }
+
+    /// 解析统计接口使用的报表币种：显式指定优先，否则使用默认设置
+    pub(crate) fn resolve_reporting_currency(
+        &self,
+        currency: Option<&str>,
+    ) -> Result<String, AppError> {
+        let currency = match currency {
+            Some(code) => code.to_string(),
+            None => self.get_reporting_currency()?,
+        };
+        let conn = lock_conn!(self.conn);
+        ensure_reportable(&conn, &currency)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reporting_currency_requires_rate() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        assert_eq!(db.get_reporting_currency()?, "USD");
+        assert!(db.set_reporting_currency("CNY").is_err());
+
+        db.save_exchange_rate(&ExchangeRate {
+            currency: "cny".to_string(),
+            rate: "7.20".to_string(),
+            effective_from: 0,
+            source: None,
+        })?;
+        db.set_reporting_currency("CNY")?;
+        assert_eq!(db.resolve_reporting_currency(None)?, "CNY");
+        assert_eq!(db.list_exchange_rates(Some("CNY"))?[0].rate, "7.2");
+
+        assert!(db
+            .save_exchange_rate(&ExchangeRate {
+                currency: "USD".to_string(),
+                rate: "1".to_string(),
+                effective_from: 0,
+                source: None,
+            })
+            .is_err());
+        Ok(())
+    }
+}
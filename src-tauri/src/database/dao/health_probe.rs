@@ -0,0 +1,149 @@
+//! 后台健康探测 DAO
+//!
+//! 端点测速记录写入 `endpoint_probe_logs`，流式检查记录复用 `stream_check_logs`。
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::services::health_probe::{HealthHistoryPoint, HealthProbeConfig};
+use crate::services::speedtest::EndpointLatency;
+
+const HEALTH_PROBE_CONFIG_KEY: &str = "health_probe_config";
+
+impl Database {
+    /// 批量保存端点探测结果
+    pub fn save_endpoint_probe_logs(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        results: &[EndpointLatency],
+        tested_at: i64,
+    ) -> Result<(), AppError> {
+        let mut conn = lock_conn!(self.conn);
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO endpoint_probe_logs
+                     (provider_id, app_type, url, latency_ms, http_status, success, error, tested_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                )
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            for result in results {
+                stmt.execute(rusqlite::params![
+                    provider_id,
+                    app_type,
+                    result.url.trim().trim_end_matches('/'),
+                    result.latency.map(|v| v as i64),
+                    result.status.map(|v| v as i64),
+                    result.latency.is_some(),
+                    result.error,
+                    tested_at,
+                ])
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            }
+        }
+        tx.commit().map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 按时间桶聚合供应商的延迟/可用率历史
+    ///
+    /// 每个端点各自成一条序列；`endpoint` 为空的点来自流式检查。
+    pub fn get_provider_health_history(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        start_ts: i64,
+        end_ts: i64,
+        bucket_secs: i64,
+    ) -> Result<Vec<HealthHistoryPoint>, AppError> {
+        let bucket_secs = bucket_secs.max(60);
+        let conn = lock_conn!(self.conn);
+
+        let mut stmt = conn
+            .prepare(
+                "SELECT bucket, endpoint, samples, successes, avg_latency FROM (
+                    SELECT (tested_at / ?5) * ?5 AS bucket, url AS endpoint, COUNT(*) AS samples,
+                           SUM(success) AS successes, AVG(latency_ms) AS avg_latency
+                    FROM endpoint_probe_logs
+                    WHERE app_type = ?1 AND provider_id = ?2 AND tested_at >= ?3 AND tested_at < ?4
+                    GROUP BY bucket, url
+                    UNION ALL
+                    SELECT (tested_at / ?5) * ?5 AS bucket, NULL AS endpoint, COUNT(*) AS samples,
+                           SUM(success) AS successes, AVG(response_time_ms) AS avg_latency
+                    FROM stream_check_logs
+                    WHERE app_type = ?1 AND provider_id = ?2 AND tested_at >= ?3 AND tested_at < ?4
+                    GROUP BY bucket
+                 )
+                 ORDER BY bucket ASC, endpoint ASC",
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let rows = stmt
+            .query_map(
+                rusqlite::params![app_type, provider_id, start_ts, end_ts, bucket_secs],
+                |row| {
+                    let samples: i64 = row.get(2)?;
+                    let successes: i64 = row.get::<_, Option<i64>>(3)?.unwrap_or(0);
+                    Ok(HealthHistoryPoint {
+                        bucket_start: row.get(0)?,
+                        endpoint: row.get(1)?,
+                        samples: samples as u32,
+                        successes: successes as u32,
+                        availability: if samples > 0 {
+                            successes as f64 / samples as f64
+                        } else {
+                            0.0
+                        },
+                        avg_latency_ms: row.get::<_, Option<f64>>(4)?.map(|v| v.round() as u64),
+                    })
+                },
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 获取供应商最近一次真实代理请求的时间（秒）
+    pub fn get_last_proxy_request_at(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+    ) -> Result<Option<i64>, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row(
+            "SELECT MAX(created_at) FROM proxy_request_logs WHERE app_type = ?1 AND provider_id = ?2",
+            rusqlite::params![app_type, provider_id],
+            |row| row.get(0),
+        )
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 清理早于指定时间的端点探测日志
+    pub fn cleanup_endpoint_probe_logs(&self, before_ts: i64) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM endpoint_probe_logs WHERE tested_at < ?1",
+            [before_ts],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 获取后台健康探测配置
+    pub fn get_health_probe_config(&self) -> Result<HealthProbeConfig, AppError> {
+        match self.get_setting(HEALTH_PROBE_CONFIG_KEY)? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| AppError::Message(format!("解析配置失败: {e}"))),
+            None => Ok(HealthProbeConfig::default()),
+        }
+    }
+
+    /// 保存后台健康探测配置
+    pub fn save_health_probe_config(&self, config: &HealthProbeConfig) -> Result<(), AppError> {
+        let json = serde_json::to_string(config)
+            .map_err(|e| AppError::Message(format!("序列化配置失败: {e}")))?;
+        self.set_setting(HEALTH_PROBE_CONFIG_KEY, &json)
+    }
+}
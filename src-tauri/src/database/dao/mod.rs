@@ -2,15 +2,32 @@
 //!
 //! Database access operations for each domain
 
+pub mod balance_reconciliation;
+pub mod cost_attribution;
+pub mod exchange_rates;
 pub mod failover;
+pub mod health_probe;
 pub mod mcp;
+pub mod model_fidelity;
+pub mod model_pricing;
+pub mod notification;
+pub mod pricing_catalog;
 pub mod prompts;
 pub mod providers;
 pub mod proxy;
+pub mod quota_guard;
+pub mod session_analytics;
+pub mod session_annotations;
+pub mod session_archives;
+pub mod session_index;
+pub mod session_summaries;
 pub mod settings;
 pub mod skills;
 pub mod stream_check;
+pub mod transcript_usage;
 pub mod universal_providers;
+pub mod usage_forecast;
+pub mod usage_rollup;
 
 // 所有 DAO 方法都通过 Database impl 提供，无需单独导出
 // 导出 FailoverQueueItem 供外部使用
@@ -0,0 +1,285 @@
+//! 模型替换检测数据访问
+
+use std::collections::BTreeMap;
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::services::model_fidelity::{
+    ModelFidelityConfig, ModelFidelityStats, ModelProbeResult, ModelSubstitution,
+};
+use rusqlite::params;
+
+const MODEL_FIDELITY_CONFIG_KEY: &str = "model_fidelity_config";
+
+impl Database {
+    /// 保存指纹探测结果
+    pub fn save_model_probe_result(&self, result: &ModelProbeResult) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO model_fidelity_probes (
+                app_type, provider_id, prompt_index, expected_model, returned_model,
+                success, matched, message, tested_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                result.app_type,
+                result.provider_id,
+                result.prompt_index,
+                result.expected_model,
+                result.returned_model,
+                result.success,
+                result.matched,
+                result.message,
+                result.tested_at,
+            ],
+        )
+        .map_err(|e| AppError::Database(format!("保存指纹探测结果失败: {e}")))?;
+        Ok(())
+    }
+
+    /// 某个供应商最近的指纹探测结果（按时间倒序）
+    pub fn list_model_probe_results(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        limit: u32,
+    ) -> Result<Vec<ModelProbeResult>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn.prepare(
+            "SELECT app_type, provider_id, prompt_index, expected_model, returned_model,
+                    success, matched, message, tested_at
+             FROM model_fidelity_probes
+             WHERE app_type = ?1 AND provider_id = ?2
+             ORDER BY tested_at DESC, id DESC LIMIT ?3",
+        )?;
+        let rows = stmt.query_map(params![app_type, provider_id, limit], |row| {
+            Ok(ModelProbeResult {
+                app_type: row.get(0)?,
+                provider_id: row.get(1)?,
+                prompt_index: row.get(2)?,
+                expected_model: row.get(3)?,
+                returned_model: row.get(4)?,
+                success: row.get(5)?,
+                matched: row.get(6)?,
+                message: row.get(7)?,
+                tested_at: row.get(8)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 删除早于 `before` 的探测结果
+    pub fn prune_model_probe_results(&self, before: i64) -> Result<usize, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM model_fidelity_probes WHERE tested_at < ?1",
+            [before],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 按供应商汇总请求比对与指纹探测结果
+    pub fn get_model_fidelity_stats(
+        &self,
+        start_date: Option<i64>,
+        end_date: Option<i64>,
+        app_type: Option<&str>,
+    ) -> Result<Vec<ModelFidelityStats>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stats: BTreeMap<(String, String), ModelFidelityStats> = BTreeMap::new();
+
+        let mut stmt = conn.prepare(
+            "SELECT app_type, provider_id, COUNT(*), COALESCE(SUM(model_mismatch), 0)
+             FROM proxy_request_logs
+             WHERE expected_model IS NOT NULL
+               AND (?1 IS NULL OR created_at >= ?1) AND (?2 IS NULL OR created_at <= ?2)
+               AND (?3 IS NULL OR app_type = ?3)
+             GROUP BY app_type, provider_id",
+        )?;
+        let mut rows = stmt.query(params![start_date, end_date, app_type])?;
+        while let Some(row) = rows.next()? {
+            let key: (String, String) = (row.get(0)?, row.get(1)?);
+            let entry = stats
+                .entry(key.clone())
+                .or_insert_with(|| ModelFidelityStats::new(key.0, key.1));
+            entry.checked_requests = row.get::<_, i64>(2)?.max(0) as u64;
+            entry.substituted_requests = row.get::<_, i64>(3)?.max(0) as u64;
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT app_type, provider_id, expected_model, model, COUNT(*), MAX(created_at)
+             FROM proxy_request_logs
+             WHERE model_mismatch = 1
+               AND (?1 IS NULL OR created_at >= ?1) AND (?2 IS NULL OR created_at <= ?2)
+               AND (?3 IS NULL OR app_type = ?3)
+             GROUP BY app_type, provider_id, expected_model, model
+             ORDER BY COUNT(*) DESC",
+        )?;
+        let mut rows = stmt.query(params![start_date, end_date, app_type])?;
+        while let Some(row) = rows.next()? {
+            let key: (String, String) = (row.get(0)?, row.get(1)?);
+            let entry = stats
+                .entry(key.clone())
+                .or_insert_with(|| ModelFidelityStats::new(key.0, key.1));
+            entry.substitutions.push(ModelSubstitution {
+                expected_model: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                returned_model: row.get(3)?,
+                request_count: row.get::<_, i64>(4)?.max(0) as u64,
+                last_seen_at: row.get(5)?,
+            });
+        }
+
+        let mut stmt = conn.prepare(
+            "SELECT app_type, provider_id, COUNT(*),
+                    COALESCE(SUM(CASE WHEN matched IS NOT NULL THEN 1 ELSE 0 END), 0),
+                    COALESCE(SUM(CASE WHEN matched = 0 THEN 1 ELSE 0 END), 0),
+                    MAX(tested_at)
+             FROM model_fidelity_probes
+             WHERE (?1 IS NULL OR tested_at >= ?1) AND (?2 IS NULL OR tested_at <= ?2)
+               AND (?3 IS NULL OR app_type = ?3)
+             GROUP BY app_type, provider_id",
+        )?;
+        let mut rows = stmt.query(params![start_date, end_date, app_type])?;
+        while let Some(row) = rows.next()? {
+            let key: (String, String) = (row.get(0)?, row.get(1)?);
+            let entry = stats
+                .entry(key.clone())
+                .or_insert_with(|| ModelFidelityStats::new(key.0, key.1));
+            entry.probe_count = row.get::<_, i64>(2)?.max(0) as u64;
+            entry.probe_verified = row.get::<_, i64>(3)?.max(0) as u64;
+            entry.probe_mismatches = row.get::<_, i64>(4)?.max(0) as u64;
+            entry.last_probe_at = row.get(5)?;
+        }
+
+        let mut name_stmt =
+            conn.prepare("SELECT name FROM providers WHERE id = ?1 AND app_type = ?2")?;
+        let mut result = Vec::with_capacity(stats.len());
+        for ((app, provider_id), mut entry) in stats {
+            entry.provider_name = name_stmt
+                .query_row(params![provider_id, app], |row| row.get(0))
+                .ok();
+            entry.finish();
+            result.push(entry);
+        }
+        Ok(result)
+    }
+
+    /// 获取模型替换检测配置（缓存在内存中，保存时失效）
+    pub fn get_model_fidelity_config(&self) -> Result<ModelFidelityConfig, AppError> {
+        if let Some(config) = self
+            .cache
+            .model_fidelity
+            .read()
+            .ok()
+            .and_then(|cache| cache.clone())
+        {
+            return Ok(config);
+        }
+
+        // 持有缓存写锁加载，避免与并发的保存交错写入旧配置
+        let mut cache = self
+            .cache
+            .model_fidelity
+            .write()
+            .map_err(|e| AppError::Database(format!("Cache lock failed: {e}")))?;
+        let config = match self.get_setting(MODEL_FIDELITY_CONFIG_KEY)? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| AppError::Message(format!("解析配置失败: {e}")))?,
+            None => ModelFidelityConfig::default(),
+        };
+        *cache = Some(config.clone());
+        Ok(config)
+    }
+
+    /// 保存模型替换检测配置
+    pub fn save_model_fidelity_config(&self, config: &ModelFidelityConfig) -> Result<(), AppError> {
+        config.validate()?;
+        let json = serde_json::to_string(config)
+            .map_err(|e| AppError::Message(format!("序列化配置失败: {e}")))?;
+        self.set_setting(MODEL_FIDELITY_CONFIG_KEY, &json)?;
+        if let Ok(mut cache) = self.cache.model_fidelity.write() {
+            *cache = Some(config.clone());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insert_log(
+        db: &Database,
+        request_id: &str,
+        model: &str,
+        expected_model: Option<&str>,
+        mismatch: bool,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(db.conn);
+        conn.execute(
+            "INSERT INTO proxy_request_logs (
+                request_id, provider_id, app_type, model, expected_model, model_mismatch,
+                latency_ms, status_code, created_at
+            ) VALUES (?1, 'p1', 'claude', ?2, ?3, ?4, 100, 200, 1000)",
+            params![request_id, model, expected_model, mismatch],
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_cache_follows_save() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        assert_eq!(
+            db.get_model_fidelity_config()?,
+            ModelFidelityConfig::default()
+        );
+
+        let config = ModelFidelityConfig {
+            detect: !ModelFidelityConfig::default().detect,
+            ..Default::default()
+        };
+        db.save_model_fidelity_config(&config)?;
+        assert_eq!(db.get_model_fidelity_config()?, config);
+
+        db.cache.clear();
+        assert_eq!(db.get_model_fidelity_config()?, config);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fidelity_stats() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        insert_log(&db, "r1", "claude-opus-4-1", Some("claude-opus-4-1"), false)?;
+        insert_log(&db, "r2", "claude-3-5-haiku", Some("claude-opus-4-1"), true)?;
+        insert_log(&db, "r3", "claude-3-5-haiku", Some("claude-opus-4-1"), true)?;
+        // 未参与比对的历史日志不计入
+        insert_log(&db, "r4", "claude-opus-4-1", None, false)?;
+
+        db.save_model_probe_result(&ModelProbeResult {
+            app_type: "claude".to_string(),
+            provider_id: "p1".to_string(),
+            prompt_index: 0,
+            expected_model: Some("claude-opus-4-1".to_string()),
+            returned_model: Some("claude-opus-4-1-20250805".to_string()),
+            success: true,
+            matched: Some(true),
+            message: "Check succeeded".to_string(),
+            tested_at: 2000,
+        })?;
+
+        let stats = db.get_model_fidelity_stats(None, None, None)?;
+        assert_eq!(stats.len(), 1);
+        let p1 = &stats[0];
+        assert_eq!((p1.checked_requests, p1.substituted_requests), (3, 2));
+        assert_eq!(p1.substitutions.len(), 1);
+        assert_eq!(p1.substitutions[0].request_count, 2);
+        assert_eq!((p1.probe_count, p1.probe_verified), (1, 1));
+        assert_eq!(p1.last_probe_at, Some(2000));
+        assert_eq!(p1.fidelity, Some(0.5));
+
+        let probes = db.list_model_probe_results("claude", "p1", 10)?;
+        assert_eq!(probes[0].matched, Some(true));
+        assert_eq!(db.prune_model_probe_results(3000)?, 1);
+        Ok(())
+    }
+}
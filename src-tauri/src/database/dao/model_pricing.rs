@@ -0,0 +1,493 @@
+//! 模型定价版本 DAO
+//!
+//! 当前定价与历史定价的维护，以及按历史定价重新计算请求成本
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::proxy::usage::calculator::{CostCalculator, UsageDialect};
+use crate::proxy::usage::parser::TokenUsage;
+use crate::services::currency::{load_billing_profile, settle, BillingProfile};
+use crate::services::pricing::{
+    load_current_version, load_history_versions, tiers_to_column, validate_tiers,
+    ModelPricingVersion, PricingLookup, RecomputeCostsResult,
+};
+use rusqlite::{params, Connection, OptionalExtension};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// 待重新计算的请求
+struct RecomputeRow {
+    request_id: String,
+    provider_id: String,
+    app_type: String,
+    model: String,
+    request_model: Option<String>,
+    usage: TokenUsage,
+    cost_multiplier: String,
+    created_at: i64,
+    total_cost_usd: String,
+    billing_currency: String,
+    billing_cost: String,
+}
+
+impl Database {
+    /// 保存模型的当前定价
+    ///
+    /// 价格有变化时，旧定价归档到历史表，区间为 [旧 effective_from, 新 effective_from)；
+    /// `effective_from` 为空时，新模型从 0 开始生效，已有模型从当前时间开始生效
+    pub fn save_model_pricing_version(
+        &self,
+        display_name: &str,
+        version: ModelPricingVersion,
+        effective_from: Option<i64>,
+    ) -> Result<(), AppError> {
+        let mut conn = lock_conn!(self.conn);
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Self::write_pricing_version(&tx, display_name, version, effective_from)?;
+        tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(())
+    }
+
+    /// 在调用方的事务内写入定价版本（规则同 `save_model_pricing_version`）
+    pub(crate) fn write_pricing_version(
+        tx: &Connection,
+        display_name: &str,
+        mut version: ModelPricingVersion,
+        effective_from: Option<i64>,
+    ) -> Result<(), AppError> {
+        validate_tiers(&version.tiers)?;
+        version.to_pricing()?;
+
+        let existing = load_current_version(tx, &version.model_id)?;
+        version.effective_from = match &existing {
+            Some(current) if current.same_prices(&version) => current.effective_from,
+            Some(current) => {
+                let from = effective_from.unwrap_or_else(|| chrono::Utc::now().timestamp());
+                if from < current.effective_from {
+                    return Err(AppError::InvalidInput(format!(
+                        "新定价生效时间不能早于当前定价的生效时间 ({})",
+                        current.effective_from
+                    )));
+                }
+                if from > current.effective_from {
+                    Self::insert_pricing_history(tx, current, from)?;
+                }
+                from
+            }
+            None => effective_from.unwrap_or(0),
+        };
+
+        tx.execute(
+            "INSERT OR REPLACE INTO model_pricing (
+                model_id, display_name, input_cost_per_million, output_cost_per_million,
+                cache_read_cost_per_million, cache_creation_cost_per_million,
+                cache_creation_1h_cost_per_million, tiers, effective_from
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                version.model_id,
+                display_name,
+                version.input_cost_per_million,
+                version.output_cost_per_million,
+                version.cache_read_cost_per_million,
+                version.cache_creation_cost_per_million,
+                version.cache_creation_1h_cost_per_million,
+                tiers_to_column(&version.tiers)?,
+                version.effective_from,
+            ],
+        )
+        .map_err(|e| AppError::Database(format!("更新模型定价失败: {e}")))?;
+        Ok(())
+    }
+
+    /// 手动补录一段历史定价（区间不得与已有历史重叠）
+    pub fn add_model_pricing_history(
+        &self,
+        version: &ModelPricingVersion,
+    ) -> Result<i64, AppError> {
+        let effective_to = version
+            .effective_to
+            .ok_or_else(|| AppError::InvalidInput("历史定价必须指定失效时间".to_string()))?;
+        if version.effective_from >= effective_to {
+            return Err(AppError::InvalidInput(
+                "历史定价的生效时间必须早于失效时间".to_string(),
+            ));
+        }
+        validate_tiers(&version.tiers)?;
+        version.to_pricing()?;
+
+        let conn = lock_conn!(self.conn);
+        let overlapping: Option<i64> = conn
+            .query_row(
+                "SELECT id FROM model_pricing_history
+                 WHERE model_id = ?1 AND effective_from < ?3 AND effective_to > ?2
+                 LIMIT 1",
+                params![version.model_id, version.effective_from, effective_to],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        if overlapping.is_some() {
+            return Err(AppError::InvalidInput(
+                "历史定价区间与已有记录重叠".to_string(),
+            ));
+        }
+
+        Self::insert_pricing_history(&conn, version, effective_to)?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    /// 获取模型的定价版本（历史版本在前，当前定价在最后）
+    pub fn list_model_pricing_versions(
+        &self,
+        model_id: &str,
+    ) -> Result<Vec<ModelPricingVersion>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut versions = load_history_versions(&conn, model_id)?;
+        if let Some(current) = load_current_version(&conn, model_id)? {
+            versions.push(current);
+        }
+        Ok(versions)
+    }
+
+    /// 删除一条历史定价
+    pub fn delete_model_pricing_history(&self, id: i64) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute("DELETE FROM model_pricing_history WHERE id = ?1", [id])
+            .map_err(|e| AppError::Database(format!("删除历史定价失败: {e}")))?;
+        Ok(())
+    }
+
+    /// 删除模型定价（含全部历史版本）
+    pub fn delete_model_pricing(&self, model_id: &str) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM model_pricing WHERE model_id = ?1",
+            params![model_id],
+        )
+        .map_err(|e| AppError::Database(format!("删除模型定价失败: {e}")))?;
+        conn.execute(
+            "DELETE FROM model_pricing_history WHERE model_id = ?1",
+            params![model_id],
+        )
+        .map_err(|e| AppError::Database(format!("删除历史定价失败: {e}")))?;
+        Ok(())
+    }
+
+    /// 按请求发生时生效的定价重新计算成本
+    ///
+    /// 使用请求记录中保存的倍率，计费模型与计费币种按供应商当前配置选择
+    pub fn recompute_request_costs(
+        &self,
+        start_date: Option<i64>,
+        end_date: Option<i64>,
+    ) -> Result<RecomputeCostsResult, AppError> {
+        self.recompute_costs(start_date, end_date, false)
+    }
+
+    /// 为尚未计价（成本为 0）的历史请求补算成本，已有成本的请求保持不变
+    pub fn backfill_unpriced_request_costs(&self) -> Result<RecomputeCostsResult, AppError> {
+        self.recompute_costs(None, None, true)
+    }
+
+    fn recompute_costs(
+        &self,
+        start_date: Option<i64>,
+        end_date: Option<i64>,
+        unpriced_only: bool,
+    ) -> Result<RecomputeCostsResult, AppError> {
+        let mut conn = lock_conn!(self.conn);
+
+        let rows = {
+            let mut stmt = conn.prepare(
+                "SELECT request_id, provider_id, app_type, model, request_model,
+                        input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens,
+                        cache_creation_1h_tokens, cost_multiplier, created_at, total_cost_usd,
+                        billing_currency, billing_cost
+                 FROM proxy_request_logs
+                 WHERE (?1 IS NULL OR created_at >= ?1) AND (?2 IS NULL OR created_at <= ?2)
+                   AND (?3 = 0 OR CAST(total_cost_usd AS REAL) = 0)
+                   AND (input_tokens > 0 OR output_tokens > 0
+                        OR cache_read_tokens > 0 OR cache_creation_tokens > 0)",
+            )?;
+            let rows = stmt.query_map(params![start_date, end_date, unpriced_only], |row| {
+                Ok(RecomputeRow {
+                    request_id: row.get(0)?,
+                    provider_id: row.get(1)?,
+                    app_type: row.get(2)?,
+                    model: row.get(3)?,
+                    request_model: row.get(4)?,
+                    usage: TokenUsage {
+                        input_tokens: row.get::<_, i64>(5)? as u32,
+                        output_tokens: row.get::<_, i64>(6)? as u32,
+                        cache_read_tokens: row.get::<_, i64>(7)? as u32,
+                        cache_creation_tokens: row.get::<_, i64>(8)? as u32,
+                        cache_creation_1h_tokens: row.get::<_, i64>(9)? as u32,
+                        model: None,
+                    },
+                    cost_multiplier: row.get(10)?,
+                    created_at: row.get(11)?,
+                    total_cost_usd: row.get(12)?,
+                    billing_currency: row.get(13)?,
+                    billing_cost: row.get(14)?,
+                })
+            })?;
+            rows.collect::<Result<Vec<_>, _>>()?
+        };
+
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let mut result = RecomputeCostsResult::default();
+        let mut lookup = PricingLookup::default();
+        let mut provider_cache: HashMap<(String, String), (bool, BillingProfile)> = HashMap::new();
+
+        for row in rows {
+            result.scanned += 1;
+
+            let key = (row.provider_id.clone(), row.app_type.clone());
+            if !provider_cache.contains_key(&key) {
+                let use_request_model = Self::uses_request_model_pricing(&tx, &key.0, &key.1)?;
+                let profile = load_billing_profile(&tx, &key.0, &key.1)?;
+                provider_cache.insert(key.clone(), (use_request_model, profile));
+            }
+            let (use_request_model, profile) = &provider_cache[&key];
+            let pricing_model = match (&row.request_model, *use_request_model) {
+                (Some(request_model), true) if !request_model.is_empty() => request_model,
+                _ => &row.model,
+            };
+
+            let Some(pricing) = lookup.pricing_at(&tx, pricing_model, row.created_at)? else {
+                result.missing_pricing += 1;
+                continue;
+            };
+            let multiplier = Decimal::from_str(&row.cost_multiplier).unwrap_or(Decimal::ONE);
+            let cost = CostCalculator::calculate(
+                &row.usage,
+                &pricing,
+                multiplier,
+                UsageDialect::for_app_type(&row.app_type),
+            );
+
+            let settlement = settle(&tx, cost.total_cost, profile, row.created_at)?;
+
+            let previous_total = Decimal::from_str(&row.total_cost_usd).unwrap_or(Decimal::ZERO);
+            let previous_billing = Decimal::from_str(&row.billing_cost).unwrap_or(Decimal::ZERO);
+            if previous_total == settlement.total_usd
+                && previous_billing == settlement.billing_cost
+                && row.billing_currency == settlement.currency
+            {
+                continue;
+            }
+
+            tx.execute(
+                "UPDATE proxy_request_logs
+                 SET input_cost_usd = ?1, output_cost_usd = ?2, cache_read_cost_usd = ?3,
+                     cache_creation_cost_usd = ?4, total_cost_usd = ?5,
+                     billing_currency = ?6, billing_cost = ?7
+                 WHERE request_id = ?8",
+                params![
+                    settlement
+                        .scale_usd(cost.total_cost, cost.input_cost)
+                        .to_string(),
+                    settlement
+                        .scale_usd(cost.total_cost, cost.output_cost)
+                        .to_string(),
+                    settlement
+                        .scale_usd(cost.total_cost, cost.cache_read_cost)
+                        .to_string(),
+                    settlement
+                        .scale_usd(cost.total_cost, cost.cache_creation_cost)
+                        .to_string(),
+                    settlement.total_usd.to_string(),
+                    settlement.currency,
+                    settlement.billing_cost.to_string(),
+                    row.request_id,
+                ],
+            )
+            .map_err(|e| AppError::Database(format!("更新请求成本失败: {e}")))?;
+            result.updated += 1;
+        }
+
+        tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
+        log::info!(
+            "{}请求成本完成: 扫描 {}, 更新 {}, 缺少定价 {}",
+            if unpriced_only {
+                "补算"
+            } else {
+                "重新计算"
+            },
+            result.scanned,
+            result.updated,
+            result.missing_pricing
+        );
+        Ok(result)
+    }
+
+    fn insert_pricing_history(
+        conn: &rusqlite::Connection,
+        version: &ModelPricingVersion,
+        effective_to: i64,
+    ) -> Result<(), AppError> {
+        conn.execute(
+            "INSERT INTO model_pricing_history (
+                model_id, input_cost_per_million, output_cost_per_million,
+                cache_read_cost_per_million, cache_creation_cost_per_million,
+                cache_creation_1h_cost_per_million, tiers, effective_from, effective_to
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+            params![
+                version.model_id,
+                version.input_cost_per_million,
+                version.output_cost_per_million,
+                version.cache_read_cost_per_million,
+                version.cache_creation_cost_per_million,
+                version.cache_creation_1h_cost_per_million,
+                tiers_to_column(&version.tiers)?,
+                version.effective_from,
+                effective_to,
+            ],
+        )
+        .map_err(|e| AppError::Database(format!("归档历史定价失败: {e}")))?;
+        Ok(())
+    }
+
+    /// 供应商（或应用默认）是否按请求模型计费
+    fn uses_request_model_pricing(
+        conn: &rusqlite::Connection,
+        provider_id: &str,
+        app_type: &str,
+    ) -> Result<bool, AppError> {
+        let provider_source = conn
+            .query_row(
+                "SELECT meta FROM providers WHERE id = ?1 AND app_type = ?2",
+                params![provider_id, app_type],
+                |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()?
+            .flatten()
+            .and_then(|meta| serde_json::from_str::<Value>(&meta).ok())
+            .and_then(|meta| {
+                meta.get("pricingModelSource")
+                    .and_then(|v| v.as_str())
+                    .map(str::to_string)
+            })
+            .filter(|source| matches!(source.as_str(), "response" | "request"));
+
+        let source = match provider_source {
+            Some(source) => source,
+            None => conn
+                .query_row(
+                    "SELECT pricing_model_source FROM proxy_config WHERE app_type = ?1",
+                    [app_type],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()?
+                .unwrap_or_else(|| "response".to_string()),
+        };
+        Ok(source == "request")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(model_id: &str, input: &str) -> ModelPricingVersion {
+        ModelPricingVersion {
+            id: None,
+            model_id: model_id.to_string(),
+            input_cost_per_million: input.to_string(),
+            output_cost_per_million: "0".to_string(),
+            cache_read_cost_per_million: "0".to_string(),
+            cache_creation_cost_per_million: "0".to_string(),
+            cache_creation_1h_cost_per_million: None,
+            tiers: Vec::new(),
+            effective_from: 0,
+            effective_to: None,
+        }
+    }
+
+    fn insert_log(db: &Database, request_id: &str, created_at: i64) -> Result<(), AppError> {
+        let conn = lock_conn!(db.conn);
+        conn.execute(
+            "INSERT INTO proxy_request_logs (
+                request_id, provider_id, app_type, model, input_tokens,
+                latency_ms, status_code, created_at
+            ) VALUES (?1, 'p1', 'claude', 'test-model', 1000000, 100, 200, ?2)",
+            params![request_id, created_at],
+        )?;
+        Ok(())
+    }
+
+    fn total_cost(db: &Database, request_id: &str) -> Result<Decimal, AppError> {
+        let conn = lock_conn!(db.conn);
+        let value: String = conn.query_row(
+            "SELECT total_cost_usd FROM proxy_request_logs WHERE request_id = ?1",
+            [request_id],
+            |row| row.get(0),
+        )?;
+        Ok(Decimal::from_str(&value).unwrap())
+    }
+
+    #[test]
+    fn test_price_change_archives_previous_version() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        db.save_model_pricing_version("Test", version("test-model", "1"), None)?;
+        db.save_model_pricing_version("Test", version("test-model", "2"), Some(5000))?;
+
+        let versions = db.list_model_pricing_versions("test-model")?;
+        assert_eq!(versions.len(), 2);
+        assert_eq!(versions[0].input_cost_per_million, "1");
+        assert_eq!(versions[0].effective_to, Some(5000));
+        assert_eq!(versions[1].input_cost_per_million, "2");
+        assert_eq!(versions[1].effective_from, 5000);
+
+        // 价格不变时不产生新版本
+        db.save_model_pricing_version("Renamed", version("test-model", "2"), Some(9000))?;
+        assert_eq!(db.list_model_pricing_versions("test-model")?.len(), 2);
+
+        // 不允许早于当前版本生效
+        assert!(db
+            .save_model_pricing_version("Test", version("test-model", "3"), Some(4000))
+            .is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_recompute_uses_price_at_request_time() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        db.save_model_pricing_version("Test", version("test-model", "1"), None)?;
+        db.save_model_pricing_version("Test", version("test-model", "2"), Some(5000))?;
+        insert_log(&db, "old", 1000)?;
+        insert_log(&db, "new", 6000)?;
+
+        let result = db.recompute_request_costs(None, None)?;
+        assert_eq!(result.scanned, 2);
+        assert_eq!(result.updated, 2);
+        assert_eq!(total_cost(&db, "old")?, Decimal::from(1));
+        assert_eq!(total_cost(&db, "new")?, Decimal::from(2));
+
+        // 再次执行时成本不变，不会重复更新
+        assert_eq!(db.recompute_request_costs(None, None)?.updated, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_history_overlap_rejected() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        db.save_model_pricing_version("Test", version("test-model", "2"), Some(5000))?;
+
+        let mut past = version("test-model", "1");
+        past.effective_from = 1000;
+        past.effective_to = Some(3000);
+        db.add_model_pricing_history(&past)?;
+
+        past.effective_from = 2000;
+        past.effective_to = Some(4000);
+        assert!(db.add_model_pricing_history(&past).is_err());
+        Ok(())
+    }
+}
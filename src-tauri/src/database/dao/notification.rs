@@ -0,0 +1,25 @@
+//! 通知配置 DAO
+
+use crate::database::Database;
+use crate::error::AppError;
+use crate::services::notification::NotificationConfig;
+
+const NOTIFICATION_CONFIG_KEY: &str = "notification_config";
+
+impl Database {
+    /// 获取通知配置
+    pub fn get_notification_config(&self) -> Result<NotificationConfig, AppError> {
+        match self.get_setting(NOTIFICATION_CONFIG_KEY)? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| AppError::Message(format!("解析配置失败: {e}"))),
+            None => Ok(NotificationConfig::default()),
+        }
+    }
+
+    /// 保存通知配置
+    pub fn save_notification_config(&self, config: &NotificationConfig) -> Result<(), AppError> {
+        let json = serde_json::to_string(config)
+            .map_err(|e| AppError::Message(format!("序列化配置失败: {e}")))?;
+        self.set_setting(NOTIFICATION_CONFIG_KEY, &json)
+    }
+}
@@ -0,0 +1,332 @@
+//! 定价目录导入与模型别名 DAO
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::services::pricing::{compile_alias_regex, load_current_version, ModelPricingAlias};
+use crate::services::pricing_catalog::{
+    PricingCatalog, PricingChangeKind, PricingImportChange, PricingImportOptions,
+    PricingImportPreview, PricingImportResult,
+};
+use crate::services::usage_stats::clean_model_id;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::collections::{HashMap, HashSet};
+
+impl Database {
+    /// 预览导入价目目录的差异（不写入数据库）
+    pub fn preview_pricing_import(
+        &self,
+        catalog: PricingCatalog,
+    ) -> Result<PricingImportPreview, AppError> {
+        let conn = lock_conn!(self.conn);
+        let unpriced = Self::unpriced_request_counts(&conn)?;
+
+        let mut preview = PricingImportPreview {
+            format: catalog.format,
+            added: 0,
+            updated: 0,
+            unchanged: 0,
+            changes: Vec::with_capacity(catalog.entries.len()),
+            skipped: catalog.skipped,
+        };
+        for entry in catalog.entries {
+            let current = load_current_version(&conn, &entry.pricing.model_id)?;
+            let kind = match &current {
+                None => PricingChangeKind::Added,
+                Some(current) if current.same_prices(&entry.pricing) => {
+                    PricingChangeKind::Unchanged
+                }
+                Some(_) => PricingChangeKind::Updated,
+            };
+            match kind {
+                PricingChangeKind::Added => preview.added += 1,
+                PricingChangeKind::Updated => preview.updated += 1,
+                PricingChangeKind::Unchanged => preview.unchanged += 1,
+            }
+            preview.changes.push(PricingImportChange {
+                model_id: entry.pricing.model_id.clone(),
+                display_name: entry.display_name,
+                kind,
+                current,
+                unpriced_requests: unpriced.get(&entry.pricing.model_id).copied().unwrap_or(0),
+                incoming: entry.pricing,
+            });
+        }
+        Ok(preview)
+    }
+
+    /// 应用价目目录
+    ///
+    /// 新模型从 0 开始生效（覆盖全部历史请求）；已有模型仅在开启覆盖时更新，
+    /// 旧价格按 `save_model_pricing_version` 的规则归档
+    pub fn apply_pricing_import(
+        &self,
+        catalog: PricingCatalog,
+        options: &PricingImportOptions,
+    ) -> Result<PricingImportResult, AppError> {
+        let selected: Option<HashSet<&str>> = options
+            .model_ids
+            .as_ref()
+            .map(|ids| ids.iter().map(String::as_str).collect());
+
+        // 判定与写入在同一事务内完成，任一模型失败则整体回滚
+        let mut result = PricingImportResult::default();
+        {
+            let mut conn = lock_conn!(self.conn);
+            let tx = conn
+                .transaction()
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            for entry in catalog.entries {
+                if selected
+                    .as_ref()
+                    .is_some_and(|ids| !ids.contains(entry.pricing.model_id.as_str()))
+                {
+                    continue;
+                }
+                match load_current_version(&tx, &entry.pricing.model_id)? {
+                    None => {
+                        result.added += 1;
+                        Self::write_pricing_version(&tx, &entry.display_name, entry.pricing, None)?;
+                    }
+                    Some(current) if current.same_prices(&entry.pricing) => result.unchanged += 1,
+                    Some(_) if !options.overwrite_existing => result.kept_existing += 1,
+                    Some(_) => {
+                        // 覆盖价格时保留用户自定义的显示名称
+                        let display_name = tx
+                            .query_row(
+                                "SELECT display_name FROM model_pricing WHERE model_id = ?1",
+                                [&entry.pricing.model_id],
+                                |row| row.get::<_, String>(0),
+                            )
+                            .optional()?
+                            .unwrap_or(entry.display_name);
+                        result.updated += 1;
+                        Self::write_pricing_version(
+                            &tx,
+                            &display_name,
+                            entry.pricing,
+                            options.effective_from,
+                        )?;
+                    }
+                }
+            }
+            tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
+        }
+        log::info!(
+            "导入定价目录完成: 新增 {}, 更新 {}, 未变化 {}, 保留已有 {}",
+            result.added,
+            result.updated,
+            result.unchanged,
+            result.kept_existing
+        );
+
+        if options.backfill && result.added + result.updated > 0 {
+            result.backfill = Some(self.backfill_unpriced_request_costs()?);
+        }
+        Ok(result)
+    }
+
+    /// 获取全部定价别名（按优先级降序）
+    pub fn list_model_pricing_aliases(&self) -> Result<Vec<ModelPricingAlias>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn.prepare(
+            "SELECT pattern, model_id, is_regex, priority, created_at
+             FROM model_pricing_aliases
+             ORDER BY priority DESC, pattern ASC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(ModelPricingAlias {
+                pattern: row.get(0)?,
+                model_id: row.get(1)?,
+                is_regex: row.get::<_, i64>(2)? != 0,
+                priority: row.get(3)?,
+                created_at: row.get(4)?,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 新增或更新定价别名（目标模型必须已有定价）
+    pub fn save_model_pricing_alias(&self, alias: &ModelPricingAlias) -> Result<(), AppError> {
+        let pattern = alias.pattern.trim();
+        if pattern.is_empty() {
+            return Err(AppError::InvalidInput("别名不能为空".to_string()));
+        }
+        if alias.is_regex {
+            compile_alias_regex(pattern)?;
+        }
+
+        let conn = lock_conn!(self.conn);
+        if load_current_version(&conn, &alias.model_id)?.is_none() {
+            return Err(AppError::InvalidInput(format!(
+                "模型 {} 尚未配置定价",
+                alias.model_id
+            )));
+        }
+        conn.execute(
+            "INSERT OR REPLACE INTO model_pricing_aliases (
+                pattern, model_id, is_regex, priority, created_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                pattern,
+                alias.model_id,
+                alias.is_regex,
+                alias.priority,
+                chrono::Utc::now().timestamp()
+            ],
+        )
+        .map_err(|e| AppError::Database(format!("保存定价别名失败: {e}")))?;
+        Ok(())
+    }
+
+    /// 删除定价别名
+    pub fn delete_model_pricing_alias(&self, pattern: &str) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM model_pricing_aliases WHERE pattern = ?1",
+            [pattern],
+        )
+        .map_err(|e| AppError::Database(format!("删除定价别名失败: {e}")))?;
+        Ok(())
+    }
+
+    /// 统计尚未计价的请求数（按清洗后的模型名聚合）
+    fn unpriced_request_counts(conn: &Connection) -> Result<HashMap<String, u64>, AppError> {
+        let mut stmt = conn.prepare(
+            "SELECT model, COUNT(*) FROM proxy_request_logs
+             WHERE CAST(total_cost_usd AS REAL) = 0
+               AND (input_tokens > 0 OR output_tokens > 0
+                    OR cache_read_tokens > 0 OR cache_creation_tokens > 0)
+             GROUP BY model",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        let mut counts = HashMap::new();
+        for row in rows {
+            let (model, count) = row?;
+            *counts.entry(clean_model_id(&model)).or_insert(0) += count as u64;
+        }
+        Ok(counts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::pricing::find_model_pricing_at;
+    use crate::services::pricing_catalog::{parse_catalog, PricingCatalogFormat};
+
+    const CSV: &str = "model_id,input,output\nglm-4.6,0.6,2.2\nclaude-sonnet-4-5-20250929,1,1\n";
+
+    fn insert_log(db: &Database, request_id: &str, model: &str) -> Result<(), AppError> {
+        let conn = lock_conn!(db.conn);
+        conn.execute(
+            "INSERT INTO proxy_request_logs (
+                request_id, provider_id, app_type, model, input_tokens,
+                latency_ms, status_code, created_at
+            ) VALUES (?1, 'p1', 'claude', ?2, 1000000, 100, 200, 1000)",
+            params![request_id, model],
+        )?;
+        Ok(())
+    }
+
+    fn catalog() -> PricingCatalog {
+        parse_catalog(CSV, Some(PricingCatalogFormat::Csv)).unwrap()
+    }
+
+    #[test]
+    fn test_preview_and_import_backfills_unpriced_logs() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        insert_log(&db, "r1", "z-ai/glm-4.6")?;
+
+        let preview = db.preview_pricing_import(catalog())?;
+        assert_eq!(preview.added, 1);
+        assert_eq!(preview.updated, 1);
+        let glm = preview
+            .changes
+            .iter()
+            .find(|c| c.model_id == "glm-4.6")
+            .unwrap();
+        assert_eq!(glm.kind, PricingChangeKind::Added);
+        assert_eq!(glm.unpriced_requests, 1);
+
+        let result = db.apply_pricing_import(
+            catalog(),
+            &PricingImportOptions {
+                backfill: true,
+                ..Default::default()
+            },
+        )?;
+        assert_eq!(result.added, 1);
+        assert_eq!(result.kept_existing, 1);
+        assert_eq!(result.backfill.unwrap().updated, 1);
+
+        let conn = lock_conn!(db.conn);
+        let cost: String = conn.query_row(
+            "SELECT total_cost_usd FROM proxy_request_logs WHERE request_id = 'r1'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(cost.parse::<f64>().unwrap(), 0.6);
+        // 未开启覆盖时保留已有定价
+        let sonnet = load_current_version(&conn, "claude-sonnet-4-5-20250929")?.unwrap();
+        assert_ne!(sonnet.input_cost_per_million, "1");
+        Ok(())
+    }
+
+    #[test]
+    fn test_failed_import_rolls_back_all_models() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        // 覆盖已有模型时生效时间早于当前定价，整个导入失败
+        let result = db.apply_pricing_import(
+            catalog(),
+            &PricingImportOptions {
+                overwrite_existing: true,
+                effective_from: Some(-1),
+                ..Default::default()
+            },
+        );
+        assert!(result.is_err());
+
+        let conn = lock_conn!(db.conn);
+        assert!(load_current_version(&conn, "glm-4.6")?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_alias_resolves_unmatched_model() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        db.apply_pricing_import(catalog(), &PricingImportOptions::default())?;
+
+        assert!(db
+            .save_model_pricing_alias(&ModelPricingAlias {
+                pattern: "glm-(".to_string(),
+                model_id: "glm-4.6".to_string(),
+                is_regex: true,
+                priority: 0,
+                created_at: 0,
+            })
+            .is_err());
+        db.save_model_pricing_alias(&ModelPricingAlias {
+            pattern: r"glm-4\.6-.*".to_string(),
+            model_id: "glm-4.6".to_string(),
+            is_regex: true,
+            priority: 0,
+            created_at: 0,
+        })?;
+        db.save_model_pricing_alias(&ModelPricingAlias {
+            pattern: "GLM-Latest".to_string(),
+            model_id: "glm-4.6".to_string(),
+            is_regex: false,
+            priority: 0,
+            created_at: 0,
+        })?;
+
+        let conn = lock_conn!(db.conn);
+        assert!(find_model_pricing_at(&conn, "glm-4.6-air-0925", 0)?.is_some());
+        assert!(find_model_pricing_at(&conn, "vendor/glm-latest", 0)?.is_some());
+        assert!(find_model_pricing_at(&conn, "glm-5", 0)?.is_none());
+        Ok(())
+    }
+}
@@ -1,8 +1,8 @@
 use crate::database::{lock_conn, Database};
 use crate::error::AppError;
-use crate::provider::{Provider, ProviderMeta};
+use crate::provider::{PrimaryEndpointLatency, Provider, ProviderMeta};
 use indexmap::IndexMap;
-use rusqlite::params;
+use rusqlite::{params, OptionalExtension};
 use std::collections::HashMap;
 
 type OmoProviderRow = (
@@ -73,19 +73,22 @@ impl Database {
             provider.id = id.clone();
 
             let mut stmt_endpoints = conn.prepare(
-                "SELECT url, added_at FROM provider_endpoints WHERE provider_id = ?1 AND app_type = ?2 ORDER BY added_at ASC, url ASC"
+                "SELECT url, added_at, latency_ms, last_tested_at FROM provider_endpoints WHERE provider_id = ?1 AND app_type = ?2 ORDER BY added_at ASC, url ASC"
             ).map_err(|e| AppError::Database(e.to_string()))?;
 
             let endpoints_iter = stmt_endpoints
                 .query_map(params![id, app_type], |row| {
                     let url: String = row.get(0)?;
                     let added_at: Option<i64> = row.get(1)?;
+                    let latency_ms: Option<i64> = row.get(2)?;
                     Ok((
                         url,
                         crate::settings::CustomEndpoint {
                             url: "".to_string(),
                             added_at: added_at.unwrap_or(0),
                             last_used: None,
+                            latency_ms: latency_ms.map(|v| v as u64),
+                            last_tested_at: row.get(3)?,
                         },
                     ))
                 })
@@ -265,9 +268,16 @@ impl Database {
 
             for (url, endpoint) in endpoints {
                 tx.execute(
-                    "INSERT INTO provider_endpoints (provider_id, app_type, url, added_at)
-                     VALUES (?1, ?2, ?3, ?4)",
-                    params![provider.id, app_type, url, endpoint.added_at],
+                    "INSERT INTO provider_endpoints (provider_id, app_type, url, added_at, latency_ms, last_tested_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                    params![
+                        provider.id,
+                        app_type,
+                        url,
+                        endpoint.added_at,
+                        endpoint.latency_ms.map(|v| v as i64),
+                        endpoint.last_tested_at
+                    ],
                 )
                 .map_err(|e| AppError::Database(e.to_string()))?;
             }
@@ -345,6 +355,69 @@ impl Database {
         Ok(())
     }
 
+    /// 记录端点测速结果（`latency_ms` 为 None 表示测速失败）
+    pub fn update_custom_endpoint_latency(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        url: &str,
+        latency_ms: Option<u64>,
+    ) -> Result<bool, AppError> {
+        let conn = lock_conn!(self.conn);
+        let tested_at = chrono::Utc::now().timestamp_millis();
+        let affected = conn
+            .execute(
+                "UPDATE provider_endpoints SET latency_ms = ?1, last_tested_at = ?2
+                 WHERE provider_id = ?3 AND app_type = ?4 AND url = ?5",
+                params![
+                    latency_ms.map(|v| v as i64),
+                    tested_at,
+                    provider_id,
+                    app_type,
+                    url
+                ],
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(affected > 0)
+    }
+
+    /// 记录主地址的测速结果（写入 meta，不进入自定义端点表）
+    pub fn update_primary_endpoint_latency(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+        url: &str,
+        latency_ms: Option<u64>,
+    ) -> Result<bool, AppError> {
+        let conn = lock_conn!(self.conn);
+        let meta_str: Option<String> = conn
+            .query_row(
+                "SELECT meta FROM providers WHERE id = ?1 AND app_type = ?2",
+                params![provider_id, app_type],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let Some(meta_str) = meta_str else {
+            return Ok(false);
+        };
+
+        let mut meta: ProviderMeta = serde_json::from_str(&meta_str).unwrap_or_default();
+        meta.primary_endpoint_latency = Some(PrimaryEndpointLatency {
+            url: url.to_string(),
+            latency_ms,
+            tested_at: chrono::Utc::now().timestamp_millis(),
+        });
+        let meta_str = serde_json::to_string(&meta)
+            .map_err(|e| AppError::Database(format!("Failed to serialize meta: {e}")))?;
+        conn.execute(
+            "UPDATE providers SET meta = ?1 WHERE id = ?2 AND app_type = ?3",
+            params![meta_str, provider_id, app_type],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(true)
+    }
+
     pub fn remove_custom_endpoint(
         &self,
         app_type: &str,
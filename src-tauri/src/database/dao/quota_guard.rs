@@ -0,0 +1,160 @@
+//! 额度守护状态 DAO
+
+use std::collections::HashSet;
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::services::quota_guard::QuotaExhaustion;
+
+impl Database {
+    /// 记录供应商额度耗尽状态
+    pub fn upsert_quota_exhaustion(&self, state: &QuotaExhaustion) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT OR REPLACE INTO provider_quota_state
+             (app_type, provider_id, reason, removed_from_queue, switched_to, exhausted_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+                state.app_type,
+                state.provider_id,
+                state.reason,
+                state.removed_from_queue,
+                state.switched_to,
+                state.exhausted_at,
+            ],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        drop(conn);
+        self.invalidate_exhausted_cache(&state.app_type);
+        Ok(())
+    }
+
+    /// 获取供应商额度耗尽状态
+    pub fn get_quota_exhaustion(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+    ) -> Result<Option<QuotaExhaustion>, AppError> {
+        Ok(self
+            .list_quota_exhaustions(Some(app_type))?
+            .into_iter()
+            .find(|s| s.provider_id == provider_id))
+    }
+
+    /// 列出额度耗尽的供应商
+    pub fn list_quota_exhaustions(
+        &self,
+        app_type: Option<&str>,
+    ) -> Result<Vec<QuotaExhaustion>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn
+            .prepare(
+                "SELECT app_type, provider_id, reason, removed_from_queue, switched_to, exhausted_at
+                 FROM provider_quota_state
+                 WHERE ?1 IS NULL OR app_type = ?1
+                 ORDER BY exhausted_at DESC",
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        let rows = stmt
+            .query_map([app_type], |row| {
+                Ok(QuotaExhaustion {
+                    app_type: row.get(0)?,
+                    provider_id: row.get(1)?,
+                    reason: row.get(2)?,
+                    removed_from_queue: row.get(3)?,
+                    switched_to: row.get(4)?,
+                    exhausted_at: row.get(5)?,
+                })
+            })
+            .map_err(|e| AppError::Database(e.to_string()))?;
+
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 获取指定应用中额度耗尽的供应商 ID
+    ///
+    /// 代理每次路由都会调用，结果缓存在内存中，写入耗尽状态时失效。
+    pub fn get_exhausted_provider_ids(&self, app_type: &str) -> Result<HashSet<String>, AppError> {
+        if let Some(ids) = self
+            .cache
+            .quota_exhausted
+            .read()
+            .ok()
+            .and_then(|cache| cache.get(app_type).cloned())
+        {
+            return Ok(ids);
+        }
+
+        // 持有缓存写锁加载，避免与并发的失效交错写入旧数据
+        let mut cache = self
+            .cache
+            .quota_exhausted
+            .write()
+            .map_err(|e| AppError::Database(format!("Cache lock failed: {e}")))?;
+        let ids: HashSet<String> = self
+            .list_quota_exhaustions(Some(app_type))?
+            .into_iter()
+            .map(|s| s.provider_id)
+            .collect();
+        cache.insert(app_type.to_string(), ids.clone());
+        Ok(ids)
+    }
+
+    fn invalidate_exhausted_cache(&self, app_type: &str) {
+        if let Ok(mut cache) = self.cache.quota_exhausted.write() {
+            cache.remove(app_type);
+        }
+    }
+
+    /// 清除供应商额度耗尽状态
+    pub fn delete_quota_exhaustion(
+        &self,
+        app_type: &str,
+        provider_id: &str,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "DELETE FROM provider_quota_state WHERE app_type = ?1 AND provider_id = ?2",
+            rusqlite::params![app_type, provider_id],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        drop(conn);
+        self.invalidate_exhausted_cache(app_type);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exhaustion(provider_id: &str) -> QuotaExhaustion {
+        QuotaExhaustion {
+            app_type: "claude".to_string(),
+            provider_id: provider_id.to_string(),
+            reason: "剩余额度低于 1".to_string(),
+            removed_from_queue: false,
+            switched_to: None,
+            exhausted_at: 1000,
+        }
+    }
+
+    #[test]
+    fn test_exhausted_ids_cache_follows_writes() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        assert!(db.get_exhausted_provider_ids("claude")?.is_empty());
+
+        db.upsert_quota_exhaustion(&exhaustion("p1"))?;
+        db.upsert_quota_exhaustion(&exhaustion("p2"))?;
+        let ids = db.get_exhausted_provider_ids("claude")?;
+        assert_eq!(ids.len(), 2);
+        assert!(db.get_exhausted_provider_ids("codex")?.is_empty());
+
+        db.delete_quota_exhaustion("claude", "p1")?;
+        let ids = db.get_exhausted_provider_ids("claude")?;
+        assert_eq!(ids, HashSet::from(["p2".to_string()]));
+        Ok(())
+    }
+}
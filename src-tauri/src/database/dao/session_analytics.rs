@@ -0,0 +1,138 @@
+//! 会话统计缓存数据访问
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::session_manager::analytics::SessionStats;
+use rusqlite::{params, OptionalExtension};
+use std::collections::HashMap;
+
+/// 缓存行：(文件大小, 修改时间, 统计结果)
+pub type CachedSessionStats = (i64, i64, SessionStats);
+
+impl Database {
+    /// 指定版本的全部会话统计缓存，按会话文件路径索引
+    pub fn get_session_stats_cache(
+        &self,
+        version: u32,
+    ) -> Result<HashMap<String, CachedSessionStats>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn.prepare(
+            "SELECT source_path, file_size, modified_at, stats
+             FROM session_analytics WHERE version = ?1",
+        )?;
+        let rows = stmt.query_map([version], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?;
+
+        let mut cache = HashMap::new();
+        for row in rows {
+            let (source_path, file_size, modified_at, stats) = row?;
+            // 无法解析的缓存视为未命中，重新计算后覆盖
+            if let Ok(stats) = serde_json::from_str(&stats) {
+                cache.insert(source_path, (file_size, modified_at, stats));
+            }
+        }
+        Ok(cache)
+    }
+
+    /// 单个会话的统计缓存
+    pub fn get_session_stats(
+        &self,
+        source_path: &str,
+        version: u32,
+    ) -> Result<Option<CachedSessionStats>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let row: Option<(i64, i64, String)> = conn
+            .query_row(
+                "SELECT file_size, modified_at, stats FROM session_analytics
+                 WHERE source_path = ?1 AND version = ?2",
+                params![source_path, version],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .optional()?;
+        Ok(row.and_then(|(file_size, modified_at, stats)| {
+            let stats = serde_json::from_str(&stats).ok()?;
+            Some((file_size, modified_at, stats))
+        }))
+    }
+
+    /// 写入或覆盖会话统计缓存
+    pub fn save_session_stats(
+        &self,
+        provider_id: &str,
+        source_path: &str,
+        file_size: i64,
+        modified_at: i64,
+        version: u32,
+        stats: &SessionStats,
+    ) -> Result<(), AppError> {
+        let stats = serde_json::to_string(stats)
+            .map_err(|e| AppError::Database(format!("序列化会话统计失败: {e}")))?;
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT OR REPLACE INTO session_analytics
+             (source_path, provider_id, file_size, modified_at, version, stats, computed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                source_path,
+                provider_id,
+                file_size,
+                modified_at,
+                version,
+                stats,
+                chrono::Utc::now().timestamp_millis(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 删除已不存在的会话的统计缓存，返回删除行数
+    pub fn remove_session_stats(&self, source_paths: &[String]) -> Result<usize, AppError> {
+        if source_paths.is_empty() {
+            return Ok(0);
+        }
+        let mut conn = lock_conn!(self.conn);
+        let tx = conn.transaction()?;
+        let mut removed = 0;
+        for path in source_paths {
+            removed += tx.execute(
+                "DELETE FROM session_analytics WHERE source_path = ?1",
+                [path],
+            )?;
+        }
+        tx.commit()?;
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_session_stats_cache_roundtrip() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let stats = SessionStats {
+            user_turns: 3,
+            wall_time_ms: 60_000,
+            ..Default::default()
+        };
+        db.save_session_stats("claude", "/s/a.jsonl", 10, 20, 1, &stats)?;
+
+        assert_eq!(
+            db.get_session_stats("/s/a.jsonl", 1)?,
+            Some((10, 20, stats.clone()))
+        );
+        assert_eq!(db.get_session_stats("/s/a.jsonl", 2)?, None);
+        assert_eq!(db.get_session_stats_cache(1)?.len(), 1);
+
+        assert_eq!(db.remove_session_stats(&["/s/a.jsonl".to_string()])?, 1);
+        assert!(db.get_session_stats_cache(1)?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,111 @@
+//! 会话自定义标题与标签数据访问
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::session_manager::manage::SessionAnnotation;
+use rusqlite::{params, OptionalExtension};
+
+fn row_to_annotation(row: &rusqlite::Row<'_>) -> rusqlite::Result<SessionAnnotation> {
+    let tags: String = row.get(3)?;
+    Ok(SessionAnnotation {
+        provider_id: row.get(0)?,
+        session_id: row.get(1)?,
+        title: row.get(2)?,
+        tags: serde_json::from_str(&tags).unwrap_or_default(),
+        updated_at: row.get(4)?,
+    })
+}
+
+impl Database {
+    /// 全部会话标注
+    pub fn list_session_annotations(&self) -> Result<Vec<SessionAnnotation>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn.prepare(
+            "SELECT provider_id, session_id, title, tags, updated_at
+             FROM session_annotations ORDER BY updated_at DESC",
+        )?;
+        let rows = stmt.query_map([], row_to_annotation)?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 单个会话的标注
+    pub fn get_session_annotation(
+        &self,
+        provider_id: &str,
+        session_id: &str,
+    ) -> Result<Option<SessionAnnotation>, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row(
+            "SELECT provider_id, session_id, title, tags, updated_at
+             FROM session_annotations WHERE provider_id = ?1 AND session_id = ?2",
+            params![provider_id, session_id],
+            row_to_annotation,
+        )
+        .optional()
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 写入或覆盖会话标注
+    pub fn upsert_session_annotation(
+        &self,
+        annotation: &SessionAnnotation,
+    ) -> Result<(), AppError> {
+        let tags = serde_json::to_string(&annotation.tags)
+            .map_err(|e| AppError::Database(format!("序列化会话标签失败: {e}")))?;
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT OR REPLACE INTO session_annotations
+             (provider_id, session_id, title, tags, updated_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                annotation.provider_id,
+                annotation.session_id,
+                annotation.title,
+                tags,
+                annotation.updated_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 删除会话标注，返回是否存在
+    pub fn delete_session_annotation(
+        &self,
+        provider_id: &str,
+        session_id: &str,
+    ) -> Result<bool, AppError> {
+        let conn = lock_conn!(self.conn);
+        let removed = conn.execute(
+            "DELETE FROM session_annotations WHERE provider_id = ?1 AND session_id = ?2",
+            params![provider_id, session_id],
+        )?;
+        Ok(removed > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_annotation_round_trip() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let annotation = SessionAnnotation {
+            provider_id: "claude".to_string(),
+            session_id: "s1".to_string(),
+            title: Some("Fix flaky migration".to_string()),
+            tags: vec!["bug".to_string(), "db".to_string()],
+            updated_at: 1_000,
+        };
+        db.upsert_session_annotation(&annotation)?;
+        assert_eq!(
+            db.get_session_annotation("claude", "s1")?,
+            Some(annotation.clone())
+        );
+        assert_eq!(db.list_session_annotations()?, vec![annotation]);
+
+        assert!(db.delete_session_annotation("claude", "s1")?);
+        assert!(db.get_session_annotation("claude", "s1")?.is_none());
+        Ok(())
+    }
+}
@@ -0,0 +1,80 @@
+//! 已归档会话数据访问
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::session_manager::manage::ArchivedSession;
+use rusqlite::{params, OptionalExtension};
+
+const COLUMNS: &str = "id, provider_id, session_id, title, project_dir, source_path, archive_path,
+    file_count, original_bytes, archived_bytes, last_active_at, archived_at";
+
+fn row_to_archive(row: &rusqlite::Row<'_>) -> rusqlite::Result<ArchivedSession> {
+    Ok(ArchivedSession {
+        id: row.get(0)?,
+        provider_id: row.get(1)?,
+        session_id: row.get(2)?,
+        title: row.get(3)?,
+        project_dir: row.get(4)?,
+        source_path: row.get(5)?,
+        archive_path: row.get(6)?,
+        file_count: row.get(7)?,
+        original_bytes: row.get::<_, i64>(8)? as u64,
+        archived_bytes: row.get::<_, i64>(9)? as u64,
+        last_active_at: row.get(10)?,
+        archived_at: row.get(11)?,
+    })
+}
+
+impl Database {
+    /// 记录一个已归档会话
+    pub fn insert_session_archive(&self, archive: &ArchivedSession) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            &format!("INSERT INTO session_archives ({COLUMNS}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)"),
+            params![
+                archive.id,
+                archive.provider_id,
+                archive.session_id,
+                archive.title,
+                archive.project_dir,
+                archive.source_path,
+                archive.archive_path,
+                archive.file_count,
+                archive.original_bytes as i64,
+                archive.archived_bytes as i64,
+                archive.last_active_at,
+                archive.archived_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 全部已归档会话（最近归档的在前）
+    pub fn list_session_archives(&self) -> Result<Vec<ArchivedSession>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {COLUMNS} FROM session_archives ORDER BY archived_at DESC"
+        ))?;
+        let rows = stmt.query_map([], row_to_archive)?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    pub fn get_session_archive(&self, id: &str) -> Result<Option<ArchivedSession>, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row(
+            &format!("SELECT {COLUMNS} FROM session_archives WHERE id = ?1"),
+            [id],
+            row_to_archive,
+        )
+        .optional()
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 删除归档记录（不删除压缩包），返回是否存在
+    pub fn delete_session_archive(&self, id: &str) -> Result<bool, AppError> {
+        let conn = lock_conn!(self.conn);
+        let removed = conn.execute("DELETE FROM session_archives WHERE id = ?1", [id])?;
+        Ok(removed > 0)
+    }
+}
@@ -0,0 +1,351 @@
+//! 会话全文索引数据访问
+
+use std::collections::HashMap;
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::session_manager::search::{
+    parse_marked_snippet, IndexedSession, SessionSearchHit, SessionSearchQuery, HIGHLIGHT_END,
+    HIGHLIGHT_START,
+};
+use crate::session_manager::SessionMessage;
+use rusqlite::{params, params_from_iter, types::Value as SqlValue};
+
+const SESSION_INDEX_VERSION_KEY: &str = "session_index_version";
+
+/// 查询条数上限
+const MAX_SEARCH_LIMIT: u32 = 200;
+/// snippet 中命中词前后保留的 token 数（trigram 下约等于字符数）
+const SNIPPET_TOKENS: i64 = 48;
+
+impl Database {
+    /// 已索引文件的指纹：路径 → (文件大小, 修改时间)
+    pub fn get_session_index_fingerprints(&self) -> Result<HashMap<String, (i64, i64)>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt =
+            conn.prepare("SELECT source_path, file_size, modified_at FROM session_index_files")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, (row.get(1)?, row.get(2)?)))
+        })?;
+        rows.collect::<Result<HashMap<_, _>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 替换一个会话文件的索引内容
+    pub fn replace_session_index(
+        &self,
+        session: &IndexedSession,
+        messages: &[SessionMessage],
+    ) -> Result<(), AppError> {
+        let mut conn = lock_conn!(self.conn);
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM session_index_messages WHERE source_path = ?1",
+            [&session.source_path],
+        )?;
+        {
+            let mut insert = tx.prepare(
+                "INSERT INTO session_index_messages (content, source_path, message_index, role, ts)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for (index, message) in messages.iter().enumerate() {
+                insert.execute(params![
+                    message.content,
+                    session.source_path,
+                    index as i64,
+                    message.role,
+                    message.ts,
+                ])?;
+            }
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO session_index_files (
+                source_path, provider_id, session_id, title, project_dir, created_at,
+                last_active_at, file_size, modified_at, message_count, indexed_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                session.source_path,
+                session.provider_id,
+                session.session_id,
+                session.title,
+                session.project_dir,
+                session.created_at,
+                session.last_active_at,
+                session.file_size,
+                session.modified_at,
+                messages.len() as i64,
+                chrono::Utc::now().timestamp(),
+            ],
+        )?;
+        tx.commit()
+            .map_err(|e| AppError::Database(format!("保存会话索引失败: {e}")))
+    }
+
+    /// 已索引会话的消息数：路径 → 消息数
+    pub fn get_session_index_message_counts(&self) -> Result<HashMap<String, u32>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt =
+            conn.prepare("SELECT source_path, message_count FROM session_index_files")?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))?;
+        rows.collect::<Result<HashMap<_, _>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 删除已不存在的会话文件的索引
+    pub fn remove_session_index(&self, source_paths: &[String]) -> Result<usize, AppError> {
+        if source_paths.is_empty() {
+            return Ok(0);
+        }
+        let mut conn = lock_conn!(self.conn);
+        let tx = conn.transaction()?;
+        let mut removed = 0;
+        for path in source_paths {
+            tx.execute(
+                "DELETE FROM session_index_messages WHERE source_path = ?1",
+                [path],
+            )?;
+            removed += tx.execute(
+                "DELETE FROM session_index_files WHERE source_path = ?1",
+                [path],
+            )?;
+        }
+        tx.commit()?;
+        Ok(removed)
+    }
+
+    /// 索引格式版本变化时清空索引，返回是否清空
+    pub fn reset_session_index_if_outdated(&self, version: u32) -> Result<bool, AppError> {
+        let current = self
+            .get_setting(SESSION_INDEX_VERSION_KEY)?
+            .and_then(|v| v.parse::<u32>().ok());
+        if current == Some(version) {
+            return Ok(false);
+        }
+        self.clear_session_index()?;
+        self.set_setting(SESSION_INDEX_VERSION_KEY, &version.to_string())?;
+        Ok(true)
+    }
+
+    /// 清空会话索引（下次刷新时全量重建）
+    pub fn clear_session_index(&self) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute_batch("DELETE FROM session_index_messages; DELETE FROM session_index_files;")
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 全文搜索会话消息
+    ///
+    /// 长度不少于 3 个字符的词走 FTS5 索引并按相关度排序；
+    /// 更短的词（trigram 无法索引）退化为 LIKE 子串匹配，按时间倒序
+    pub fn search_session_index(
+        &self,
+        query: &SessionSearchQuery,
+    ) -> Result<Vec<SessionSearchHit>, AppError> {
+        let terms = query.terms();
+        if terms.is_empty() {
+            return Err(AppError::InvalidInput("搜索关键词不能为空".to_string()));
+        }
+        let (long_terms, short_terms): (Vec<&String>, Vec<&String>) =
+            terms.iter().partition(|t| t.chars().count() >= 3);
+
+        let mut conditions = Vec::new();
+        let mut values: Vec<SqlValue> = Vec::new();
+
+        if !long_terms.is_empty() {
+            let expr = long_terms
+                .iter()
+                .map(|t| format!("\"{}\"", t.replace('"', "\"\"")))
+                .collect::<Vec<_>>()
+                .join(" AND ");
+            conditions.push("session_index_fts MATCH ?".to_string());
+            values.push(SqlValue::Text(expr));
+        }
+        for term in &short_terms {
+            conditions.push("m.content LIKE ? ESCAPE '\\'".to_string());
+            values.push(SqlValue::Text(format!("%{}%", escape_like(term))));
+        }
+        if let Some(provider_id) = query.provider_id.as_deref().filter(|p| !p.is_empty()) {
+            conditions.push("f.provider_id = ?".to_string());
+            values.push(SqlValue::Text(provider_id.to_string()));
+        }
+        if let Some(dir) = query.project_dir.as_deref() {
+            let dir = dir.trim_end_matches(['/', '\\']);
+            if !dir.is_empty() {
+                // 目录本身或其子目录
+                conditions.push(
+                    "(f.project_dir = ? OR f.project_dir LIKE ? ESCAPE '\\' OR f.project_dir LIKE ? ESCAPE '\\')"
+                        .to_string(),
+                );
+                values.push(SqlValue::Text(dir.to_string()));
+                values.push(SqlValue::Text(format!("{}/%", escape_like(dir))));
+                values.push(SqlValue::Text(format!("{}\\\\%", escape_like(dir))));
+            }
+        }
+        let ts_expr = "COALESCE(m.ts, f.last_active_at, f.created_at)";
+        if let Some(start) = query.start_ms {
+            conditions.push(format!("{ts_expr} >= ?"));
+            values.push(SqlValue::Integer(start));
+        }
+        if let Some(end) = query.end_ms {
+            conditions.push(format!("{ts_expr} <= ?"));
+            values.push(SqlValue::Integer(end));
+        }
+
+        let (snippet_expr, rank_expr, order) = if long_terms.is_empty() {
+            ("m.content".to_string(), "0.0", format!("{ts_expr} DESC"))
+        } else {
+            (
+                format!(
+                    "snippet(session_index_fts, 0, '{HIGHLIGHT_START}', '{HIGHLIGHT_END}', '…', {SNIPPET_TOKENS})"
+                ),
+                "bm25(session_index_fts)",
+                format!("bm25(session_index_fts), {ts_expr} DESC"),
+            )
+        };
+
+        let limit = query.limit.unwrap_or(50).clamp(1, MAX_SEARCH_LIMIT);
+        values.push(SqlValue::Integer(i64::from(limit)));
+        values.push(SqlValue::Integer(i64::from(query.offset.unwrap_or(0))));
+
+        let sql = format!(
+            "SELECT f.provider_id, f.session_id, m.source_path, f.title, f.project_dir,
+                    f.last_active_at, m.message_index, m.role, m.ts, {snippet_expr}, {rank_expr}
+             FROM session_index_fts
+             JOIN session_index_messages m ON m.id = session_index_fts.rowid
+             JOIN session_index_files f ON f.source_path = m.source_path
+             WHERE {}
+             ORDER BY {order}
+             LIMIT ? OFFSET ?",
+            conditions.join(" AND ")
+        );
+
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params_from_iter(values.iter()), |row| {
+            Ok((
+                SessionSearchHit {
+                    provider_id: row.get(0)?,
+                    session_id: row.get(1)?,
+                    source_path: row.get(2)?,
+                    title: row.get(3)?,
+                    project_dir: row.get(4)?,
+                    last_active_at: row.get(5)?,
+                    message_index: row.get::<_, i64>(6)?.max(0) as u32,
+                    role: row.get(7)?,
+                    ts: row.get(8)?,
+                    snippet: String::new(),
+                    highlights: Vec::new(),
+                    score: -row.get::<_, f64>(10)?,
+                },
+                row.get::<_, String>(9)?,
+            ))
+        })?;
+
+        let mut hits = Vec::new();
+        for row in rows {
+            let (mut hit, raw) = row?;
+            let marked = if long_terms.is_empty() {
+                crate::session_manager::search::mark_snippet(&raw, &short_terms, 80)
+            } else {
+                raw
+            };
+            (hit.snippet, hit.highlights) = parse_marked_snippet(&marked);
+            hits.push(hit);
+        }
+        Ok(hits)
+    }
+}
+
+/// 转义 LIKE 通配符
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(path: &str, provider_id: &str, project_dir: &str) -> IndexedSession {
+        IndexedSession {
+            source_path: path.to_string(),
+            provider_id: provider_id.to_string(),
+            session_id: format!("{provider_id}-session"),
+            title: None,
+            project_dir: Some(project_dir.to_string()),
+            created_at: Some(1_000),
+            last_active_at: Some(5_000),
+            file_size: 10,
+            modified_at: 1,
+        }
+    }
+
+    fn message(role: &str, content: &str, ts: i64) -> SessionMessage {
+        SessionMessage {
+            role: role.to_string(),
+            content: content.to_string(),
+            ts: Some(ts),
+            ..Default::default()
+        }
+    }
+
+    fn query(text: &str) -> SessionSearchQuery {
+        SessionSearchQuery {
+            query: text.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_index_and_search() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        db.replace_session_index(
+            &session("/a.jsonl", "claude", "/work/app"),
+            &[
+                message("user", "The migration test is flaky on CI", 1_000),
+                message("assistant", "修复了数据库迁移的竞态问题", 2_000),
+            ],
+        )?;
+        db.replace_session_index(
+            &session("/b.jsonl", "codex", "/work/other"),
+            &[message("user", "unrelated chat about lunch", 3_000)],
+        )?;
+
+        let hits = db.search_session_index(&query("flaky migration"))?;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].source_path, "/a.jsonl");
+        assert_eq!(hits[0].message_index, 0);
+        assert!(!hits[0].highlights.is_empty());
+        assert!(hits[0].snippet.contains("flaky"));
+
+        // 两个字的中文词走 LIKE 回退
+        let hits = db.search_session_index(&query("迁移"))?;
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].message_index, 1);
+        assert_eq!(hits[0].highlights.len(), 1);
+
+        let mut filtered = query("chat");
+        filtered.provider_id = Some("claude".to_string());
+        assert!(db.search_session_index(&filtered)?.is_empty());
+        filtered.provider_id = None;
+        filtered.project_dir = Some("/wor".to_string());
+        assert!(db.search_session_index(&filtered)?.is_empty());
+        filtered.project_dir = Some("/work/other/".to_string());
+        assert_eq!(db.search_session_index(&filtered)?.len(), 1);
+        filtered.start_ms = Some(4_000);
+        assert!(db.search_session_index(&filtered)?.is_empty());
+
+        // 重新索引替换旧内容，删除后不再命中
+        db.replace_session_index(
+            &session("/a.jsonl", "claude", "/work/app"),
+            &[message("user", "nothing here", 1_000)],
+        )?;
+        assert!(db.search_session_index(&query("flaky"))?.is_empty());
+        assert_eq!(db.get_session_index_fingerprints()?.len(), 2);
+        assert_eq!(db.remove_session_index(&["/b.jsonl".to_string()])?, 1);
+        assert!(db.search_session_index(&query("lunch"))?.is_empty());
+        Ok(())
+    }
+}
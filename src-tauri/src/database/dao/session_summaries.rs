@@ -0,0 +1,234 @@
+//! 会话自动摘要数据访问
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::session_manager::summarize::{SessionSummary, SessionSummaryConfig, REQUEST_ID_PREFIX};
+use rusqlite::{params, OptionalExtension};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const SESSION_SUMMARY_CONFIG_KEY: &str = "session_summary_config";
+
+fn row_to_summary(row: &rusqlite::Row<'_>) -> rusqlite::Result<SessionSummary> {
+    let keywords: String = row.get(5)?;
+    Ok(SessionSummary {
+        provider_id: row.get(0)?,
+        session_id: row.get(1)?,
+        source_path: row.get(2)?,
+        title: row.get(3)?,
+        summary: row.get(4)?,
+        keywords: serde_json::from_str(&keywords).unwrap_or_default(),
+        model: row.get(6)?,
+        cost_usd: row.get(7)?,
+        file_size: row.get(8)?,
+        modified_at: row.get(9)?,
+        generated_at: row.get(10)?,
+    })
+}
+
+impl Database {
+    /// 全部已生成的会话摘要（不含因提问过少而跳过的会话）
+    pub fn list_session_summaries(&self) -> Result<Vec<SessionSummary>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn.prepare(
+            "SELECT provider_id, session_id, source_path, title, summary, keywords, model,
+                    cost_usd, file_size, modified_at, generated_at
+             FROM session_summaries WHERE title != '' ORDER BY generated_at DESC",
+        )?;
+        let rows = stmt.query_map([], row_to_summary)?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 生成摘要时会话文件的指纹，按文件路径索引
+    pub fn get_session_summary_fingerprints(
+        &self,
+    ) -> Result<HashMap<String, (i64, i64)>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt =
+            conn.prepare("SELECT source_path, file_size, modified_at FROM session_summaries")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, (row.get(1)?, row.get(2)?)))
+        })?;
+        rows.collect::<Result<HashMap<_, _>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 写入或覆盖会话摘要
+    pub fn upsert_session_summary(&self, summary: &SessionSummary) -> Result<(), AppError> {
+        let keywords = serde_json::to_string(&summary.keywords)
+            .map_err(|e| AppError::Database(format!("序列化摘要关键词失败: {e}")))?;
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT OR REPLACE INTO session_summaries (
+                source_path, provider_id, session_id, title, summary, keywords, model,
+                cost_usd, file_size, modified_at, generated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                summary.source_path,
+                summary.provider_id,
+                summary.session_id,
+                summary.title,
+                summary.summary,
+                keywords,
+                summary.model,
+                summary.cost_usd,
+                summary.file_size,
+                summary.modified_at,
+                summary.generated_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 记录不需要摘要的会话，文件变化前不再检查
+    ///
+    /// 已有的摘要保留内容，只更新指纹
+    pub fn mark_session_summary_skipped(
+        &self,
+        provider_id: &str,
+        session_id: &str,
+        source_path: &str,
+        file_size: i64,
+        modified_at: i64,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT INTO session_summaries (
+                source_path, provider_id, session_id, title, summary, keywords, model,
+                cost_usd, file_size, modified_at, generated_at
+            ) VALUES (?1, ?2, ?3, '', '', '[]', '', '0', ?4, ?5, ?6)
+            ON CONFLICT(source_path) DO UPDATE SET
+                file_size = excluded.file_size,
+                modified_at = excluded.modified_at",
+            params![
+                source_path,
+                provider_id,
+                session_id,
+                file_size,
+                modified_at,
+                chrono::Utc::now().timestamp_millis(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 指定时间（秒）以来摘要请求的总花费（USD），与请求日志中记录的成本一致
+    pub fn get_session_summary_spend_since(&self, since: i64) -> Result<Decimal, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn.prepare(
+            "SELECT total_cost_usd FROM proxy_request_logs
+             WHERE request_id LIKE ?1 AND created_at >= ?2",
+        )?;
+        let rows = stmt.query_map(params![format!("{REQUEST_ID_PREFIX}%"), since], |row| {
+            row.get::<_, String>(0)
+        })?;
+        let mut total = Decimal::ZERO;
+        for cost in rows {
+            total += Decimal::from_str(&cost?).unwrap_or(Decimal::ZERO);
+        }
+        Ok(total)
+    }
+
+    /// 单个请求记录的总成本（USD）
+    pub fn get_request_total_cost(&self, request_id: &str) -> Result<Option<String>, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row(
+            "SELECT total_cost_usd FROM proxy_request_logs WHERE request_id = ?1",
+            [request_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 获取会话摘要配置
+    pub fn get_session_summary_config(&self) -> Result<SessionSummaryConfig, AppError> {
+        match self.get_setting(SESSION_SUMMARY_CONFIG_KEY)? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| AppError::Message(format!("解析配置失败: {e}"))),
+            None => Ok(SessionSummaryConfig::default()),
+        }
+    }
+
+    /// 保存会话摘要配置
+    pub fn save_session_summary_config(
+        &self,
+        config: &SessionSummaryConfig,
+    ) -> Result<(), AppError> {
+        config.validate()?;
+        let json = serde_json::to_string(config)
+            .map_err(|e| AppError::Message(format!("序列化配置失败: {e}")))?;
+        self.set_setting(SESSION_SUMMARY_CONFIG_KEY, &json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summary(title: &str) -> SessionSummary {
+        SessionSummary {
+            provider_id: "claude".to_string(),
+            session_id: "s1".to_string(),
+            source_path: "/s/s1.jsonl".to_string(),
+            title: title.to_string(),
+            summary: "Fixed the login redirect.".to_string(),
+            keywords: vec!["auth".to_string()],
+            model: "claude-haiku-4-5".to_string(),
+            cost_usd: "0.001200".to_string(),
+            file_size: 10,
+            modified_at: 20,
+            generated_at: 30,
+        }
+    }
+
+    #[test]
+    fn test_skipped_sessions_keep_existing_summary() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        db.mark_session_summary_skipped("claude", "s2", "/s/s2.jsonl", 1, 2)?;
+        assert!(db.list_session_summaries()?.is_empty());
+
+        db.upsert_session_summary(&summary("Fix login redirect"))?;
+        db.mark_session_summary_skipped("claude", "s1", "/s/s1.jsonl", 11, 21)?;
+        let summaries = db.list_session_summaries()?;
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].title, "Fix login redirect");
+
+        let fingerprints = db.get_session_summary_fingerprints()?;
+        assert_eq!(fingerprints.get("/s/s1.jsonl"), Some(&(11, 21)));
+        assert_eq!(fingerprints.get("/s/s2.jsonl"), Some(&(1, 2)));
+        Ok(())
+    }
+
+    #[test]
+    fn test_spend_counts_only_summary_requests() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        {
+            let conn = lock_conn!(db.conn);
+            for (request_id, cost, created_at) in [
+                ("session-summary-a", "0.25", 100),
+                ("session-summary-b", "0.10", 50),
+                ("proxy-c", "5", 100),
+            ] {
+                conn.execute(
+                    "INSERT INTO proxy_request_logs (
+                        request_id, provider_id, app_type, model, total_cost_usd,
+                        latency_ms, status_code, created_at
+                    ) VALUES (?1, 'p1', 'claude', 'm', ?2, 0, 200, ?3)",
+                    params![request_id, cost, created_at],
+                )?;
+            }
+        }
+        assert_eq!(
+            db.get_session_summary_spend_since(60)?,
+            Decimal::from_str("0.25").unwrap()
+        );
+        assert_eq!(
+            db.get_request_total_cost("session-summary-b")?.as_deref(),
+            Some("0.10")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,350 @@
+//! 会话记录用量导入 DAO
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::proxy::session::{cli_session_id, cli_session_id_sql};
+use crate::proxy::usage::calculator::{CostCalculator, UsageDialect};
+use crate::services::currency::{settle, BillingProfile};
+use crate::services::pricing::PricingLookup;
+use crate::services::transcript_usage::{
+    TranscriptCursor, TranscriptIngestConfig, TranscriptIngestResult, TranscriptUsageRecord,
+};
+use crate::services::usage_stats::{
+    TRANSCRIPT_PROVIDER_ID, USAGE_SOURCE_PROXY, USAGE_SOURCE_TRANSCRIPT,
+};
+use rusqlite::{params, OptionalExtension};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+
+const TRANSCRIPT_INGEST_CONFIG_KEY: &str = "transcript_ingest_config";
+
+/// 判定「已由代理记录」时允许的时间误差（秒）
+const PROXY_MATCH_WINDOW_SECS: i64 = 120;
+
+impl Database {
+    /// 写入会话记录中的用量
+    ///
+    /// 同一消息 ID 只记录一次；流式消息可能多次落盘，保留输出 token 最多的一次
+    pub fn save_transcript_usage(
+        &self,
+        records: &[TranscriptUsageRecord],
+    ) -> Result<TranscriptIngestResult, AppError> {
+        let mut result = TranscriptIngestResult::default();
+        if records.is_empty() {
+            return Ok(result);
+        }
+
+        // 同一批次内先按消息 ID 合并
+        let mut latest: HashMap<String, &TranscriptUsageRecord> = HashMap::new();
+        for record in records {
+            let entry = latest.entry(record.request_id()).or_insert(record);
+            if record.usage.output_tokens > entry.usage.output_tokens {
+                *entry = record;
+            }
+        }
+
+        let mut conn = lock_conn!(self.conn);
+        let tx = conn
+            .transaction()
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        let mut lookup = PricingLookup::default();
+        let profile = BillingProfile::default();
+
+        for (request_id, record) in latest {
+            let existing: Option<i64> = tx
+                .query_row(
+                    "SELECT output_tokens FROM proxy_request_logs WHERE request_id = ?1",
+                    [&request_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if existing.is_some_and(|output| output >= i64::from(record.usage.output_tokens)) {
+                continue;
+            }
+
+            if existing.is_none() {
+                if let Some(session_id) = &record.session_id {
+                    // 代理日志中的 Codex 会话 ID 带有前缀，两侧统一还原为原始 ID 再比较
+                    let proxied: bool = tx.query_row(
+                        &format!(
+                            "SELECT EXISTS(
+                                SELECT 1 FROM proxy_request_logs
+                                WHERE source = ?1 AND {} = ?2 AND output_tokens = ?3
+                                  AND ABS(created_at - ?4) <= ?5
+                            )",
+                            cli_session_id_sql("session_id")
+                        ),
+                        params![
+                            USAGE_SOURCE_PROXY,
+                            cli_session_id(session_id),
+                            record.usage.output_tokens,
+                            record.created_at,
+                            PROXY_MATCH_WINDOW_SECS
+                        ],
+                        |row| row.get(0),
+                    )?;
+                    if proxied {
+                        result.proxied += 1;
+                        continue;
+                    }
+                }
+            }
+
+            // 直连使用按官方价格计费，缺少定价时记为 0，导入定价后可补算
+            let cost = lookup
+                .pricing_at(&tx, &record.model, record.created_at)?
+                .map(|pricing| {
+                    CostCalculator::calculate(
+                        &record.usage,
+                        &pricing,
+                        Decimal::ONE,
+                        UsageDialect::for_app_type(record.app_type),
+                    )
+                });
+            let (input_cost, output_cost, cache_read_cost, cache_creation_cost, total_cost) =
+                match &cost {
+                    Some(cost) => (
+                        cost.input_cost,
+                        cost.output_cost,
+                        cost.cache_read_cost,
+                        cost.cache_creation_cost,
+                        cost.total_cost,
+                    ),
+                    None => Default::default(),
+                };
+            let settlement = settle(&tx, total_cost, &profile, record.created_at)?;
+
+            tx.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model, request_model,
+                    input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens,
+                    cache_creation_1h_tokens, input_cost_usd, output_cost_usd,
+                    cache_read_cost_usd, cache_creation_cost_usd, total_cost_usd,
+                    billing_currency, billing_cost, latency_ms, status_code, session_id,
+                    is_streaming, cost_multiplier, created_at, source
+                ) VALUES (?1, ?2, ?3, ?4, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14,
+                          ?15, ?16, 0, 200, ?17, 0, '1', ?18, ?19)
+                ON CONFLICT(request_id) DO UPDATE SET
+                    input_tokens = excluded.input_tokens,
+                    output_tokens = excluded.output_tokens,
+                    cache_read_tokens = excluded.cache_read_tokens,
+                    cache_creation_tokens = excluded.cache_creation_tokens,
+                    cache_creation_1h_tokens = excluded.cache_creation_1h_tokens,
+                    input_cost_usd = excluded.input_cost_usd,
+                    output_cost_usd = excluded.output_cost_usd,
+                    cache_read_cost_usd = excluded.cache_read_cost_usd,
+                    cache_creation_cost_usd = excluded.cache_creation_cost_usd,
+                    total_cost_usd = excluded.total_cost_usd,
+                    billing_cost = excluded.billing_cost",
+                params![
+                    request_id,
+                    TRANSCRIPT_PROVIDER_ID,
+                    record.app_type,
+                    record.model,
+                    record.usage.input_tokens,
+                    record.usage.output_tokens,
+                    record.usage.cache_read_tokens,
+                    record.usage.cache_creation_tokens,
+                    record.usage.cache_creation_1h_tokens,
+                    settlement.scale_usd(total_cost, input_cost).to_string(),
+                    settlement.scale_usd(total_cost, output_cost).to_string(),
+                    settlement
+                        .scale_usd(total_cost, cache_read_cost)
+                        .to_string(),
+                    settlement
+                        .scale_usd(total_cost, cache_creation_cost)
+                        .to_string(),
+                    settlement.total_usd.to_string(),
+                    settlement.currency,
+                    settlement.billing_cost.to_string(),
+                    record.session_id,
+                    record.created_at,
+                    USAGE_SOURCE_TRANSCRIPT,
+                ],
+            )
+            .map_err(|e| AppError::Database(format!("写入会话用量失败: {e}")))?;
+
+            if existing.is_some() {
+                result.updated += 1;
+            } else {
+                result.inserted += 1;
+            }
+        }
+
+        tx.commit().map_err(|e| AppError::Database(e.to_string()))?;
+        Ok(result)
+    }
+
+    /// 获取会话文件的读取进度
+    pub fn get_transcript_cursor(&self, path: &str) -> Result<Option<TranscriptCursor>, AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row(
+            "SELECT offset, file_size, modified_at, context
+             FROM transcript_ingest_state WHERE path = ?1",
+            [path],
+            |row| {
+                Ok(TranscriptCursor {
+                    offset: row.get::<_, i64>(0)? as u64,
+                    file_size: row.get::<_, i64>(1)? as u64,
+                    modified_at: row.get(2)?,
+                    context: row.get(3)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 保存会话文件的读取进度
+    pub fn save_transcript_cursor(
+        &self,
+        path: &str,
+        app_type: &str,
+        cursor: &TranscriptCursor,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.execute(
+            "INSERT OR REPLACE INTO transcript_ingest_state
+             (path, app_type, offset, file_size, modified_at, context, updated_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                path,
+                app_type,
+                cursor.offset as i64,
+                cursor.file_size as i64,
+                cursor.modified_at,
+                cursor.context,
+                chrono::Utc::now().timestamp(),
+            ],
+        )
+        .map_err(|e| AppError::Database(format!("保存导入进度失败: {e}")))?;
+        Ok(())
+    }
+
+    /// 获取会话记录导入配置
+    pub fn get_transcript_ingest_config(&self) -> Result<TranscriptIngestConfig, AppError> {
+        match self.get_setting(TRANSCRIPT_INGEST_CONFIG_KEY)? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| AppError::Message(format!("解析配置失败: {e}"))),
+            None => Ok(TranscriptIngestConfig::default()),
+        }
+    }
+
+    /// 保存会话记录导入配置
+    pub fn save_transcript_ingest_config(
+        &self,
+        config: &TranscriptIngestConfig,
+    ) -> Result<(), AppError> {
+        let json = serde_json::to_string(config)
+            .map_err(|e| AppError::Message(format!("序列化配置失败: {e}")))?;
+        self.set_setting(TRANSCRIPT_INGEST_CONFIG_KEY, &json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::usage::parser::TokenUsage;
+
+    fn record(message_id: &str, output_tokens: u32, created_at: i64) -> TranscriptUsageRecord {
+        TranscriptUsageRecord {
+            app_type: "claude",
+            message_id: message_id.to_string(),
+            session_id: Some("s1".to_string()),
+            model: "claude-sonnet-4-5-20250929".to_string(),
+            usage: TokenUsage {
+                input_tokens: 1000,
+                output_tokens,
+                ..Default::default()
+            },
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_transcript_usage_dedup_and_proxy_skip() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        {
+            let conn = lock_conn!(db.conn);
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model, output_tokens,
+                    latency_ms, status_code, session_id, created_at
+                ) VALUES ('proxied', 'p1', 'claude', 'm', 77, 100, 200, 's1', 1000)",
+                [],
+            )?;
+        }
+
+        let result = db.save_transcript_usage(&[
+            record("msg_1", 10, 900),
+            record("msg_1", 50, 900),
+            record("msg_2", 77, 1030),
+        ])?;
+        assert_eq!(result.inserted, 1);
+        assert_eq!(result.proxied, 1);
+
+        // 重复导入不会重复计数，输出增长时更新
+        let again = db.save_transcript_usage(&[record("msg_1", 50, 900)])?;
+        assert_eq!((again.inserted, again.updated), (0, 0));
+        let grown = db.save_transcript_usage(&[record("msg_1", 60, 900)])?;
+        assert_eq!(grown.updated, 1);
+
+        let conn = lock_conn!(db.conn);
+        let (output, source, cost): (i64, String, String) = conn.query_row(
+            "SELECT output_tokens, source, total_cost_usd FROM proxy_request_logs
+             WHERE request_id = 'transcript:claude:msg_1'",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        assert_eq!(output, 60);
+        assert_eq!(source, USAGE_SOURCE_TRANSCRIPT);
+        assert!(cost.parse::<f64>().unwrap() > 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_codex_transcript_skips_prefixed_proxy_session() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        {
+            let conn = lock_conn!(db.conn);
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model, output_tokens,
+                    latency_ms, status_code, session_id, created_at
+                ) VALUES ('proxied', 'p1', 'codex', 'm', 42, 100, 200, 'codex_0199-abc', 1000)",
+                [],
+            )?;
+        }
+
+        let codex = TranscriptUsageRecord {
+            app_type: "codex",
+            message_id: "0199-abc:1042".to_string(),
+            session_id: Some("0199-abc".to_string()),
+            model: "gpt-5-codex".to_string(),
+            usage: TokenUsage {
+                input_tokens: 1000,
+                output_tokens: 42,
+                ..Default::default()
+            },
+            created_at: 1010,
+        };
+        let result = db.save_transcript_usage(&[codex])?;
+        assert_eq!((result.inserted, result.proxied), (0, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_transcript_cursor_roundtrip() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        assert!(db.get_transcript_cursor("/tmp/a.jsonl")?.is_none());
+        let cursor = TranscriptCursor {
+            offset: 10,
+            file_size: 20,
+            modified_at: 30,
+            context: Some("{}".to_string()),
+        };
+        db.save_transcript_cursor("/tmp/a.jsonl", "codex", &cursor)?;
+        assert_eq!(db.get_transcript_cursor("/tmp/a.jsonl")?, Some(cursor));
+        Ok(())
+    }
+}
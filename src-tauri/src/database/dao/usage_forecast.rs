@@ -0,0 +1,133 @@
+//! 花费预测数据访问
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::services::usage_forecast::{DailyUsagePoint, ProviderMonthlyLimit, UsageForecastConfig};
+
+const USAGE_FORECAST_CONFIG_KEY: &str = "usage_forecast_config";
+
+impl Database {
+    /// 读取日汇总中 `since` 之后的数据点（合并币种维度）
+    pub fn get_daily_usage_points(&self, since: i64) -> Result<Vec<DailyUsagePoint>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn.prepare(
+            "SELECT r.bucket_start, r.app_type, r.provider_id, MAX(p.name), r.model,
+                    COALESCE(SUM(r.total_cost_usd), 0),
+                    COALESCE(SUM(r.request_count), 0),
+                    COALESCE(SUM(r.success_count), 0)
+             FROM usage_rollup_daily r
+             LEFT JOIN providers p ON p.id = r.provider_id AND p.app_type = r.app_type
+             WHERE r.bucket_start >= ?1
+             GROUP BY r.bucket_start, r.app_type, r.provider_id, r.model
+             ORDER BY r.bucket_start",
+        )?;
+        let rows = stmt.query_map([since], |row| {
+            Ok(DailyUsagePoint {
+                bucket_start: row.get(0)?,
+                app_type: row.get(1)?,
+                provider_id: row.get(2)?,
+                provider_name: row.get(3)?,
+                model: row.get(4)?,
+                cost_usd: row.get(5)?,
+                request_count: row.get::<_, i64>(6)?.max(0) as u64,
+                success_count: row.get::<_, i64>(7)?.max(0) as u64,
+            })
+        })?;
+        rows.collect::<Result<Vec<_>, _>>()
+            .map_err(|e| AppError::Database(e.to_string()))
+    }
+
+    /// 设置了月度限额（meta.limitMonthlyUsd）的供应商
+    pub fn get_provider_monthly_limits(&self) -> Result<Vec<ProviderMonthlyLimit>, AppError> {
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn.prepare("SELECT id, app_type, name, meta FROM providers")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?;
+
+        let mut limits = Vec::new();
+        for row in rows {
+            let (provider_id, app_type, provider_name, meta) = row?;
+            let limit = serde_json::from_str::<serde_json::Value>(&meta)
+                .ok()
+                .and_then(|meta| {
+                    meta.get("limitMonthlyUsd")
+                        .and_then(|v| v.as_str())
+                        .and_then(|s| s.parse::<f64>().ok())
+                });
+            if let Some(limit_usd) = limit.filter(|l| *l > 0.0) {
+                limits.push(ProviderMonthlyLimit {
+                    app_type,
+                    provider_id,
+                    provider_name,
+                    limit_usd,
+                });
+            }
+        }
+        Ok(limits)
+    }
+
+    /// 获取预测与预警配置
+    pub fn get_usage_forecast_config(&self) -> Result<UsageForecastConfig, AppError> {
+        match self.get_setting(USAGE_FORECAST_CONFIG_KEY)? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| AppError::Message(format!("解析配置失败: {e}"))),
+            None => Ok(UsageForecastConfig::default()),
+        }
+    }
+
+    /// 保存预测与预警配置
+    pub fn save_usage_forecast_config(&self, config: &UsageForecastConfig) -> Result<(), AppError> {
+        config.validate()?;
+        let json = serde_json::to_string(config)
+            .map_err(|e| AppError::Message(format!("序列化配置失败: {e}")))?;
+        self.set_setting(USAGE_FORECAST_CONFIG_KEY, &json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    #[test]
+    fn test_daily_points_and_limits() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        {
+            let conn = lock_conn!(db.conn);
+            conn.execute(
+                "INSERT INTO providers (id, app_type, name, settings_config, meta)
+                 VALUES ('p1', 'claude', 'Relay', '{}', ?1), ('p2', 'claude', 'Other', '{}', '{}')",
+                params![r#"{"limitMonthlyUsd":"150"}"#],
+            )?;
+            for (request_id, status, cost) in [("r1", 200, "1.5"), ("r2", 500, "0")] {
+                conn.execute(
+                    "INSERT INTO proxy_request_logs (
+                        request_id, provider_id, app_type, model, input_tokens, output_tokens,
+                        total_cost_usd, latency_ms, status_code, created_at
+                    ) VALUES (?1, 'p1', 'claude', 'opus', 100, 10, ?2, 100, ?3, 1000)",
+                    params![request_id, cost, status],
+                )?;
+            }
+        }
+
+        let points = db.get_daily_usage_points(0)?;
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].provider_name.as_deref(), Some("Relay"));
+        assert_eq!((points[0].request_count, points[0].success_count), (2, 1));
+        assert_eq!(points[0].cost_usd, 1.5);
+
+        let limits = db.get_provider_monthly_limits()?;
+        assert_eq!(limits.len(), 1);
+        assert_eq!(
+            (limits[0].provider_id.as_str(), limits[0].limit_usd),
+            ("p1", 150.0)
+        );
+        Ok(())
+    }
+}
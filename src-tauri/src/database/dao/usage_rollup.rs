@@ -0,0 +1,329 @@
+//! 用量汇总与日志保留 DAO
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::services::usage_rollup::{
+    local_day_start, write_archive, UsageRetentionConfig, UsageRetentionResult,
+    ARCHIVED_BEFORE_KEY, HOURLY_FLOOR_KEY,
+};
+use rusqlite::types::Value;
+use rusqlite::{params, Connection};
+
+const USAGE_RETENTION_CONFIG_KEY: &str = "usage_retention_config";
+/// 归档时每批读取的日志行数
+const ARCHIVE_BATCH_SIZE: i64 = 2000;
+
+/// 汇总表与对应的桶起点表达式（`{row}` 替换为 NEW / OLD）
+const ROLLUP_BUCKETS: [(&str, &str); 2] = [
+    (
+        "usage_rollup_hourly",
+        "{row}.created_at - {row}.created_at % 3600",
+    ),
+    (
+        "usage_rollup_daily",
+        "CAST(strftime('%s', date({row}.created_at, 'unixepoch', 'localtime'), 'utc') AS INTEGER)",
+    ),
+];
+
+/// 生成把一行请求日志计入（`sign` 为 "+"）或移出（"-"）汇总表的 UPSERT 语句
+fn rollup_upsert_sql(table: &str, bucket_expr: &str, row: &str, sign: &str) -> String {
+    let bucket = bucket_expr.replace("{row}", row);
+    format!(
+        "INSERT INTO {table} (
+            bucket_start, app_type, provider_id, model, billing_currency,
+            request_count, success_count, input_tokens, output_tokens,
+            cache_read_tokens, cache_creation_tokens, total_cost_usd, billing_cost, latency_ms_sum
+        ) VALUES (
+            {bucket}, {row}.app_type, {row}.provider_id, {row}.model, {row}.billing_currency,
+            {sign}1,
+            {sign}(CASE WHEN {row}.status_code >= 200 AND {row}.status_code < 300 THEN 1 ELSE 0 END),
+            {sign}{row}.input_tokens, {sign}{row}.output_tokens,
+            {sign}{row}.cache_read_tokens, {sign}{row}.cache_creation_tokens,
+            {sign}CAST({row}.total_cost_usd AS REAL), {sign}CAST({row}.billing_cost AS REAL),
+            {sign}{row}.latency_ms
+        )
+        ON CONFLICT(bucket_start, app_type, provider_id, model, billing_currency) DO UPDATE SET
+            request_count = request_count + excluded.request_count,
+            success_count = success_count + excluded.success_count,
+            input_tokens = input_tokens + excluded.input_tokens,
+            output_tokens = output_tokens + excluded.output_tokens,
+            cache_read_tokens = cache_read_tokens + excluded.cache_read_tokens,
+            cache_creation_tokens = cache_creation_tokens + excluded.cache_creation_tokens,
+            total_cost_usd = total_cost_usd + excluded.total_cost_usd,
+            billing_cost = billing_cost + excluded.billing_cost,
+            latency_ms_sum = latency_ms_sum + excluded.latency_ms_sum;"
+    )
+}
+
+impl Database {
+    /// 创建维护用量汇总的触发器
+    ///
+    /// 插入与更新（如重算成本）会同步到汇总表；删除不回退汇总，
+    /// 保留策略清理原始日志后历史统计仍然完整
+    pub(crate) fn create_usage_rollup_triggers(conn: &Connection) -> Result<(), AppError> {
+        let inserts: String = ROLLUP_BUCKETS
+            .iter()
+            .map(|(table, bucket)| rollup_upsert_sql(table, bucket, "NEW", "+"))
+            .collect();
+        let updates: String = ROLLUP_BUCKETS
+            .iter()
+            .map(|(table, bucket)| rollup_upsert_sql(table, bucket, "OLD", "-"))
+            .chain(
+                ROLLUP_BUCKETS
+                    .iter()
+                    .map(|(table, bucket)| rollup_upsert_sql(table, bucket, "NEW", "+")),
+            )
+            .collect();
+        conn.execute_batch(&format!(
+            "CREATE TRIGGER IF NOT EXISTS usage_rollup_after_insert
+             AFTER INSERT ON proxy_request_logs BEGIN {inserts} END;
+             CREATE TRIGGER IF NOT EXISTS usage_rollup_after_update
+             AFTER UPDATE ON proxy_request_logs BEGIN {updates} END;"
+        ))
+        .map_err(|e| AppError::Database(format!("创建用量汇总触发器失败: {e}")))
+    }
+
+    /// 由原始请求日志全量重建汇总表
+    pub(crate) fn rebuild_usage_rollups(conn: &Connection) -> Result<(), AppError> {
+        for (table, bucket) in ROLLUP_BUCKETS {
+            let bucket = bucket.replace("{row}", "l");
+            conn.execute(&format!("DELETE FROM {table}"), [])
+                .map_err(|e| AppError::Database(e.to_string()))?;
+            conn.execute(
+                &format!(
+                    "INSERT INTO {table} (
+                        bucket_start, app_type, provider_id, model, billing_currency,
+                        request_count, success_count, input_tokens, output_tokens,
+                        cache_read_tokens, cache_creation_tokens, total_cost_usd, billing_cost,
+                        latency_ms_sum
+                    )
+                    SELECT {bucket} AS bucket, l.app_type, l.provider_id, l.model, l.billing_currency,
+                        COUNT(*),
+                        SUM(CASE WHEN l.status_code >= 200 AND l.status_code < 300 THEN 1 ELSE 0 END),
+                        SUM(l.input_tokens), SUM(l.output_tokens),
+                        SUM(l.cache_read_tokens), SUM(l.cache_creation_tokens),
+                        SUM(CAST(l.total_cost_usd AS REAL)), SUM(CAST(l.billing_cost AS REAL)),
+                        SUM(l.latency_ms)
+                    FROM proxy_request_logs l
+                    GROUP BY bucket, l.app_type, l.provider_id, l.model, l.billing_currency"
+                ),
+                [],
+            )
+            .map_err(|e| AppError::Database(format!("重建用量汇总失败: {e}")))?;
+        }
+        Ok(())
+    }
+
+    /// 执行日志保留策略
+    ///
+    /// 先把早于保留期的原始日志分批写入压缩归档，成功后再删除；
+    /// 小时汇总超过保留天数的部分同样清理，更早的统计改由日汇总提供
+    pub fn apply_usage_retention(
+        &self,
+        config: &UsageRetentionConfig,
+    ) -> Result<UsageRetentionResult, AppError> {
+        config.validate()?;
+        let now = chrono::Local::now();
+        let cutoff = now.timestamp() - i64::from(config.retain_days) * 86_400;
+        let hourly_floor =
+            local_day_start(now.timestamp() - i64::from(config.hourly_days) * 86_400);
+
+        let mut result = UsageRetentionResult::default();
+
+        // 归档文件较大，分批读取并在写入归档期间释放数据库锁；
+        // 删除只覆盖已归档的 rowid，归档期间新写入的行不受影响
+        let (expired, columns) = {
+            let conn = lock_conn!(self.conn);
+            let expired: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM proxy_request_logs WHERE created_at < ?1",
+                [cutoff],
+                |row| row.get(0),
+            )?;
+            let stmt = conn.prepare("SELECT * FROM proxy_request_logs LIMIT 0")?;
+            let columns: Vec<String> = stmt.column_names().into_iter().map(String::from).collect();
+            (expired, columns)
+        };
+        if expired > 0 {
+            let mut last_rowid = 0_i64;
+            let path = write_archive(columns, config, &now, || {
+                let conn = lock_conn!(self.conn);
+                let mut stmt = conn.prepare(
+                    "SELECT rowid, * FROM proxy_request_logs
+                     WHERE created_at < ?1 AND rowid > ?2 ORDER BY rowid ASC LIMIT ?3",
+                )?;
+                let width = stmt.column_count();
+                let mut rows = stmt.query(params![cutoff, last_rowid, ARCHIVE_BATCH_SIZE])?;
+                let mut batch = Vec::new();
+                while let Some(row) = rows.next()? {
+                    last_rowid = row.get(0)?;
+                    batch.push(
+                        (1..width)
+                            .map(|idx| row.get::<_, Value>(idx))
+                            .collect::<Result<Vec<_>, _>>()?,
+                    );
+                }
+                Ok(batch)
+            })?;
+            result.archive_path = Some(path.to_string_lossy().to_string());
+            result.archived_rows = lock_conn!(self.conn)
+                .execute(
+                    "DELETE FROM proxy_request_logs WHERE created_at < ?1 AND rowid <= ?2",
+                    params![cutoff, last_rowid],
+                )
+                .map_err(|e| AppError::Database(format!("清理过期日志失败: {e}")))?
+                as u64;
+        }
+
+        let conn = lock_conn!(self.conn);
+        // 记录原始日志的完整起点，按范围统计时更早的边缘桶只能整桶计入
+        conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT(key) DO UPDATE SET value = MAX(CAST(value AS INTEGER), excluded.value)",
+            params![ARCHIVED_BEFORE_KEY, cutoff],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        // 下限只前移，避免缩短天数后又缺失已清理的小时数据
+        let current_floor: i64 = conn.query_row(
+            "SELECT COALESCE((SELECT CAST(value AS INTEGER) FROM settings WHERE key = ?1), 0)",
+            [HOURLY_FLOOR_KEY],
+            |row| row.get(0),
+        )?;
+        if hourly_floor > current_floor {
+            result.pruned_hourly_rows = conn
+                .execute(
+                    "DELETE FROM usage_rollup_hourly WHERE bucket_start < ?1",
+                    [hourly_floor],
+                )
+                .map_err(|e| AppError::Database(format!("清理小时汇总失败: {e}")))?
+                as u64;
+            conn.execute(
+                "INSERT OR REPLACE INTO settings (key, value) VALUES (?1, ?2)",
+                params![HOURLY_FLOOR_KEY, hourly_floor.to_string()],
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        }
+
+        if result.archived_rows > 0 {
+            log::info!(
+                "用量日志保留：已归档并删除 {} 条记录到 {}",
+                result.archived_rows,
+                result.archive_path.as_deref().unwrap_or_default()
+            );
+        }
+        Ok(result)
+    }
+
+    /// 获取日志保留配置
+    pub fn get_usage_retention_config(&self) -> Result<UsageRetentionConfig, AppError> {
+        match self.get_setting(USAGE_RETENTION_CONFIG_KEY)? {
+            Some(json) => serde_json::from_str(&json)
+                .map_err(|e| AppError::Message(format!("解析配置失败: {e}"))),
+            None => Ok(UsageRetentionConfig::default()),
+        }
+    }
+
+    /// 保存日志保留配置
+    pub fn save_usage_retention_config(
+        &self,
+        config: &UsageRetentionConfig,
+    ) -> Result<(), AppError> {
+        config.validate()?;
+        let json = serde_json::to_string(config)
+            .map_err(|e| AppError::Message(format!("序列化配置失败: {e}")))?;
+        self.set_setting(USAGE_RETENTION_CONFIG_KEY, &json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::currency::BASE_CURRENCY;
+    use crate::services::usage_export::UsageFileFormat;
+    use std::io::Read;
+
+    fn insert_log(
+        db: &Database,
+        request_id: &str,
+        cost: &str,
+        created_at: i64,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(db.conn);
+        conn.execute(
+            "INSERT INTO proxy_request_logs (
+                request_id, provider_id, app_type, model, input_tokens, output_tokens,
+                total_cost_usd, billing_cost, latency_ms, status_code, created_at
+            ) VALUES (?1, 'p1', 'claude', 'm', 100, 10, ?2, ?2, 100, 200, ?3)",
+            params![request_id, cost, created_at],
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_rollups_follow_inserts_and_updates() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        insert_log(&db, "r1", "1.5", 3_600 * 10 + 5)?;
+        insert_log(&db, "r2", "0.5", 3_600 * 10 + 50)?;
+        {
+            let conn = lock_conn!(db.conn);
+            conn.execute(
+                "UPDATE proxy_request_logs SET total_cost_usd = '1' WHERE request_id = 'r2'",
+                [],
+            )?;
+            let (count, cost): (i64, f64) = conn.query_row(
+                "SELECT request_count, total_cost_usd FROM usage_rollup_hourly
+                 WHERE bucket_start = 36000",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?;
+            assert_eq!((count, cost), (2, 2.5));
+
+            // 重建结果与触发器增量维护一致
+            Database::rebuild_usage_rollups(&conn)?;
+            let daily: f64 = conn.query_row(
+                "SELECT SUM(total_cost_usd) FROM usage_rollup_daily",
+                [],
+                |row| row.get(0),
+            )?;
+            assert_eq!(daily, 2.5);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_retention_archives_and_keeps_totals() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let dir = tempfile::tempdir().expect("temp dir");
+        let now = chrono::Utc::now().timestamp();
+        insert_log(&db, "old", "2", now - 40 * 86_400)?;
+        insert_log(&db, "new", "1", now - 60)?;
+
+        let config = UsageRetentionConfig {
+            enabled: true,
+            retain_days: 30,
+            hourly_days: 30,
+            archive_format: UsageFileFormat::Jsonl,
+            archive_dir: Some(dir.path().to_string_lossy().to_string()),
+        };
+        let result = db.apply_usage_retention(&config)?;
+        assert_eq!(result.archived_rows, 1);
+
+        let file = std::fs::File::open(result.archive_path.unwrap()).expect("open archive");
+        let mut archive = zip::ZipArchive::new(file).expect("read archive");
+        let mut content = String::new();
+        archive
+            .by_index(0)
+            .expect("archive entry")
+            .read_to_string(&mut content)
+            .expect("read entry");
+        let line: serde_json::Value = serde_json::from_str(content.trim()).expect("jsonl line");
+        assert_eq!(line["request_id"], "old");
+
+        // 原始日志已清理，但汇总统计仍包含归档部分
+        let summary = db.get_usage_summary(None, None, BASE_CURRENCY)?;
+        assert_eq!(summary.total_requests, 2);
+        assert_eq!(summary.total_cost, "3.000000");
+        let recent = db.get_usage_summary(Some(now - 86_400), Some(now), BASE_CURRENCY)?;
+        assert_eq!(recent.total_requests, 1);
+        Ok(())
+    }
+}
@@ -14,6 +14,7 @@
 //! ├── mod.rs        - Database 结构体 + 初始化
 //! ├── schema.rs     - 表结构定义 + Schema 迁移
 //! ├── backup.rs     - SQL 导入导出 + 快照备份
+//! ├── cache.rs      - 热路径读取的内存缓存
 //! ├── migration.rs  - JSON → SQLite 数据迁移
 //! └── dao/          - 数据访问对象
 //!     ├── providers.rs
@@ -24,6 +25,7 @@
 //! ```
 
 pub(crate) mod backup;
+mod cache;
 mod dao;
 mod migration;
 mod schema;
@@ -44,7 +46,7 @@ use std::sync::Mutex;
 
 /// 当前 Schema 版本号
 /// 每次修改表结构时递增，并在 schema.rs 中添加相应的迁移逻辑
-pub(crate) const SCHEMA_VERSION: i32 = 5;
+pub(crate) const SCHEMA_VERSION: i32 = 12;
 
 /// 安全地序列化 JSON，避免 unwrap panic
 pub(crate) fn to_json_string<T: Serialize>(value: &T) -> Result<String, AppError> {
@@ -70,6 +72,7 @@ pub(crate) use lock_conn;
 /// rusqlite::Connection 本身不是 Sync 的，因此需要这层包装。
 pub struct Database {
     pub(crate) conn: Mutex<Connection>,
+    pub(crate) cache: cache::DbCache,
 }
 
 fn register_db_change_hook(conn: &Connection) {
@@ -104,6 +107,7 @@ impl Database {
 
         let db = Self {
             conn: Mutex::new(conn),
+            cache: Default::default(),
         };
         db.create_tables()?;
 
@@ -139,6 +143,7 @@ impl Database {
 
         let db = Self {
             conn: Mutex::new(conn),
+            cache: Default::default(),
         };
         db.create_tables()?;
         db.ensure_model_pricing_seeded()?;
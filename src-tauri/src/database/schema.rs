@@ -46,6 +46,8 @@ impl Database {
                 app_type TEXT NOT NULL,
                 url TEXT NOT NULL,
                 added_at INTEGER,
+                latency_ms INTEGER,
+                last_tested_at INTEGER,
                 FOREIGN KEY (provider_id, app_type) REFERENCES providers(id, app_type) ON DELETE CASCADE
             )",
             [],
@@ -180,7 +182,11 @@ impl Database {
             total_cost_usd TEXT NOT NULL DEFAULT '0', latency_ms INTEGER NOT NULL, first_token_ms INTEGER,
             duration_ms INTEGER, status_code INTEGER NOT NULL, error_message TEXT, session_id TEXT,
             provider_type TEXT, is_streaming INTEGER NOT NULL DEFAULT 0,
-            cost_multiplier TEXT NOT NULL DEFAULT '1.0', created_at INTEGER NOT NULL
+            cost_multiplier TEXT NOT NULL DEFAULT '1.0', created_at INTEGER NOT NULL,
+            endpoint_url TEXT, cache_creation_1h_tokens INTEGER NOT NULL DEFAULT 0,
+            billing_currency TEXT NOT NULL DEFAULT 'USD', billing_cost TEXT NOT NULL DEFAULT '0',
+            source TEXT NOT NULL DEFAULT 'proxy', expected_model TEXT,
+            model_mismatch INTEGER NOT NULL DEFAULT 0
         )", []).map_err(|e| AppError::Database(e.to_string()))?;
 
         conn.execute("CREATE INDEX IF NOT EXISTS idx_request_logs_provider ON proxy_request_logs(provider_id, app_type)", [])
@@ -203,13 +209,209 @@ impl Database {
         )
         .map_err(|e| AppError::Database(e.to_string()))?;
 
+        // 10.5 用量汇总表（按小时 / 按本地自然日），由触发器随请求日志增量维护
+        for table in ["usage_rollup_hourly", "usage_rollup_daily"] {
+            conn.execute(
+                &format!(
+                    "CREATE TABLE IF NOT EXISTS {table} (
+                    bucket_start INTEGER NOT NULL, app_type TEXT NOT NULL,
+                    provider_id TEXT NOT NULL, model TEXT NOT NULL,
+                    billing_currency TEXT NOT NULL,
+                    request_count INTEGER NOT NULL DEFAULT 0,
+                    success_count INTEGER NOT NULL DEFAULT 0,
+                    input_tokens INTEGER NOT NULL DEFAULT 0,
+                    output_tokens INTEGER NOT NULL DEFAULT 0,
+                    cache_read_tokens INTEGER NOT NULL DEFAULT 0,
+                    cache_creation_tokens INTEGER NOT NULL DEFAULT 0,
+                    total_cost_usd REAL NOT NULL DEFAULT 0,
+                    billing_cost REAL NOT NULL DEFAULT 0,
+                    latency_ms_sum INTEGER NOT NULL DEFAULT 0,
+                    PRIMARY KEY (bucket_start, app_type, provider_id, model, billing_currency)
+                )"
+                ),
+                [],
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        }
+        // 旧版本数据库在迁移补齐列之后再创建触发器
+        if Self::has_column(conn, "proxy_request_logs", "source")? {
+            Self::create_usage_rollup_triggers(conn)?;
+        }
+
         // 11. Model Pricing 表
         conn.execute(
             "CREATE TABLE IF NOT EXISTS model_pricing (
             model_id TEXT PRIMARY KEY, display_name TEXT NOT NULL,
             input_cost_per_million TEXT NOT NULL, output_cost_per_million TEXT NOT NULL,
             cache_read_cost_per_million TEXT NOT NULL DEFAULT '0',
-            cache_creation_cost_per_million TEXT NOT NULL DEFAULT '0'
+            cache_creation_cost_per_million TEXT NOT NULL DEFAULT '0',
+            cache_creation_1h_cost_per_million TEXT, tiers TEXT,
+            effective_from INTEGER NOT NULL DEFAULT 0
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 11.1 Model Pricing 历史版本表（[effective_from, effective_to) 区间内生效）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS model_pricing_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, model_id TEXT NOT NULL,
+            input_cost_per_million TEXT NOT NULL, output_cost_per_million TEXT NOT NULL,
+            cache_read_cost_per_million TEXT NOT NULL DEFAULT '0',
+            cache_creation_cost_per_million TEXT NOT NULL DEFAULT '0',
+            cache_creation_1h_cost_per_million TEXT, tiers TEXT,
+            effective_from INTEGER NOT NULL, effective_to INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_model_pricing_history_model
+             ON model_pricing_history(model_id, effective_from)",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 11.2 汇率表（1 USD = rate 单位币种，自 effective_from 起生效）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS exchange_rates (
+            currency TEXT NOT NULL, rate TEXT NOT NULL,
+            effective_from INTEGER NOT NULL, source TEXT,
+            PRIMARY KEY (currency, effective_from)
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 11.3 模型定价别名表（请求模型名 → model_pricing.model_id，支持正则）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS model_pricing_aliases (
+            pattern TEXT PRIMARY KEY, model_id TEXT NOT NULL,
+            is_regex INTEGER NOT NULL DEFAULT 0, priority INTEGER NOT NULL DEFAULT 0,
+            created_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 11.4 会话记录导入进度表（每个会话文件的已读取偏移）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS transcript_ingest_state (
+            path TEXT PRIMARY KEY, app_type TEXT NOT NULL,
+            offset INTEGER NOT NULL DEFAULT 0, file_size INTEGER NOT NULL DEFAULT 0,
+            modified_at INTEGER NOT NULL DEFAULT 0, context TEXT,
+            updated_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 11.5 会话与项目目录的对应关系（用于按项目归属成本）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS session_projects (
+            session_id TEXT NOT NULL, app_type TEXT NOT NULL,
+            project_dir TEXT NOT NULL, title TEXT,
+            source TEXT NOT NULL, updated_at INTEGER NOT NULL,
+            PRIMARY KEY (session_id, app_type)
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 11.6 供应商余额快照（用量脚本查询结果，用于与计算成本对账）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS provider_balance_snapshots (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, app_type TEXT NOT NULL, provider_id TEXT NOT NULL,
+            plan_name TEXT NOT NULL DEFAULT '', used REAL, remaining REAL, total REAL, unit TEXT,
+            created_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_balance_snapshots_provider
+             ON provider_balance_snapshots(app_type, provider_id, plan_name, created_at)",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 11.7 模型指纹探测结果（比对请求模型与上游返回模型）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS model_fidelity_probes (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, app_type TEXT NOT NULL, provider_id TEXT NOT NULL,
+            prompt_index INTEGER NOT NULL DEFAULT 0, expected_model TEXT,
+            returned_model TEXT, success INTEGER NOT NULL, matched INTEGER,
+            message TEXT NOT NULL DEFAULT '', tested_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_model_fidelity_probes_provider
+             ON model_fidelity_probes(app_type, provider_id, tested_at)",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 11.8 会话全文索引（本机缓存，按文件路径 + 修改时间 + 大小增量更新）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS session_index_files (
+            source_path TEXT PRIMARY KEY, provider_id TEXT NOT NULL, session_id TEXT NOT NULL,
+            title TEXT, project_dir TEXT, created_at INTEGER, last_active_at INTEGER,
+            file_size INTEGER NOT NULL DEFAULT 0, modified_at INTEGER NOT NULL DEFAULT 0,
+            message_count INTEGER NOT NULL DEFAULT 0, indexed_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        Self::create_session_index_messages(conn)?;
+
+        // 11.9 会话自定义标题与标签（不修改 CLI 自身的会话文件）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS session_annotations (
+            provider_id TEXT NOT NULL, session_id TEXT NOT NULL, title TEXT,
+            tags TEXT NOT NULL DEFAULT '[]', updated_at INTEGER NOT NULL,
+            PRIMARY KEY (provider_id, session_id)
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 11.10 已归档会话（压缩包位于应用配置目录，可恢复）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS session_archives (
+            id TEXT PRIMARY KEY, provider_id TEXT NOT NULL, session_id TEXT NOT NULL,
+            title TEXT, project_dir TEXT, source_path TEXT NOT NULL, archive_path TEXT NOT NULL,
+            file_count INTEGER NOT NULL DEFAULT 0, original_bytes INTEGER NOT NULL DEFAULT 0,
+            archived_bytes INTEGER NOT NULL DEFAULT 0, last_active_at INTEGER,
+            archived_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 11.11 会话统计缓存（按文件大小与修改时间判断是否需要重新解析）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS session_analytics (
+            source_path TEXT PRIMARY KEY, provider_id TEXT NOT NULL,
+            file_size INTEGER NOT NULL DEFAULT 0, modified_at INTEGER NOT NULL DEFAULT 0,
+            version INTEGER NOT NULL DEFAULT 0, stats TEXT NOT NULL, computed_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 11.12 会话自动摘要（文件指纹用于判断会话是否变化；title 为空表示已跳过）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS session_summaries (
+            source_path TEXT PRIMARY KEY, provider_id TEXT NOT NULL, session_id TEXT NOT NULL,
+            title TEXT NOT NULL DEFAULT '', summary TEXT NOT NULL DEFAULT '',
+            keywords TEXT NOT NULL DEFAULT '[]', model TEXT NOT NULL DEFAULT '',
+            cost_usd TEXT NOT NULL DEFAULT '0', file_size INTEGER NOT NULL DEFAULT 0,
+            modified_at INTEGER NOT NULL DEFAULT 0, generated_at INTEGER NOT NULL
         )",
             [],
         )
@@ -230,6 +432,36 @@ impl Database {
         )
         .map_err(|e| AppError::Database(e.to_string()))?;
 
+        // 端点健康探测日志（后台定时测速）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS endpoint_probe_logs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT, provider_id TEXT NOT NULL, app_type TEXT NOT NULL,
+            url TEXT NOT NULL, latency_ms INTEGER, http_status INTEGER, success INTEGER NOT NULL,
+            error TEXT, tested_at INTEGER NOT NULL
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS idx_endpoint_probe_logs_provider
+             ON endpoint_probe_logs(app_type, provider_id, tested_at DESC)",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
+        // 供应商额度守护状态（由用量脚本结果驱动）
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS provider_quota_state (
+            app_type TEXT NOT NULL, provider_id TEXT NOT NULL, reason TEXT NOT NULL,
+            removed_from_queue INTEGER NOT NULL DEFAULT 0, switched_to TEXT,
+            exhausted_at INTEGER NOT NULL,
+            PRIMARY KEY (app_type, provider_id)
+        )",
+            [],
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+
         // 注意：circuit_breaker_config 已合并到 proxy_config 表中
 
         // 16. Proxy Live Backup 表 (Live 配置备份)
@@ -360,6 +592,41 @@ impl Database {
                         Self::migrate_v4_to_v5(conn)?;
                         Self::set_user_version(conn, 5)?;
                     }
+                    5 => {
+                        log::info!("迁移数据库从 v5 到 v6（端点级故障转移）");
+                        Self::migrate_v5_to_v6(conn)?;
+                        Self::set_user_version(conn, 6)?;
+                    }
+                    6 => {
+                        log::info!("迁移数据库从 v6 到 v7（阶梯与分时定价）");
+                        Self::migrate_v6_to_v7(conn)?;
+                        Self::set_user_version(conn, 7)?;
+                    }
+                    7 => {
+                        log::info!("迁移数据库从 v7 到 v8（多币种成本）");
+                        Self::migrate_v7_to_v8(conn)?;
+                        Self::set_user_version(conn, 8)?;
+                    }
+                    8 => {
+                        log::info!("迁移数据库从 v8 到 v9（用量来源）");
+                        Self::migrate_v8_to_v9(conn)?;
+                        Self::set_user_version(conn, 9)?;
+                    }
+                    9 => {
+                        log::info!("迁移数据库从 v9 到 v10（用量汇总表）");
+                        Self::migrate_v9_to_v10(conn)?;
+                        Self::set_user_version(conn, 10)?;
+                    }
+                    10 => {
+                        log::info!("迁移数据库从 v10 到 v11（模型替换检测）");
+                        Self::migrate_v10_to_v11(conn)?;
+                        Self::set_user_version(conn, 11)?;
+                    }
+                    11 => {
+                        log::info!("迁移数据库从 v11 到 v12（会话索引外部内容表）");
+                        Self::migrate_v11_to_v12(conn)?;
+                        Self::set_user_version(conn, 12)?;
+                    }
                     _ => {
                         return Err(AppError::Database(format!(
                             "未知的数据库版本 {version}，无法迁移到 {SCHEMA_VERSION}"
@@ -914,6 +1181,169 @@ impl Database {
         Ok(())
     }
 
+    /// v5 -> v6 迁移：端点测速结果持久化与请求实际使用的端点
+    fn migrate_v5_to_v6(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "provider_endpoints")? {
+            Self::add_column_if_missing(conn, "provider_endpoints", "latency_ms", "INTEGER")?;
+            Self::add_column_if_missing(conn, "provider_endpoints", "last_tested_at", "INTEGER")?;
+        }
+        if Self::table_exists(conn, "proxy_request_logs")? {
+            Self::add_column_if_missing(conn, "proxy_request_logs", "endpoint_url", "TEXT")?;
+        }
+
+        log::info!("v5 -> v6 迁移完成：已添加端点测速与请求端点字段");
+        Ok(())
+    }
+
+    /// v6 -> v7 迁移：阶梯定价、1 小时缓存写入价格与定价生效时间
+    fn migrate_v6_to_v7(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "model_pricing")? {
+            Self::add_column_if_missing(
+                conn,
+                "model_pricing",
+                "cache_creation_1h_cost_per_million",
+                "TEXT",
+            )?;
+            Self::add_column_if_missing(conn, "model_pricing", "tiers", "TEXT")?;
+            Self::add_column_if_missing(
+                conn,
+                "model_pricing",
+                "effective_from",
+                "INTEGER NOT NULL DEFAULT 0",
+            )?;
+        }
+        if Self::table_exists(conn, "proxy_request_logs")? {
+            Self::add_column_if_missing(
+                conn,
+                "proxy_request_logs",
+                "cache_creation_1h_tokens",
+                "INTEGER NOT NULL DEFAULT 0",
+            )?;
+        }
+
+        log::info!("v6 -> v7 迁移完成：已添加阶梯定价与缓存 TTL 字段");
+        Ok(())
+    }
+
+    /// v7 -> v8 迁移：请求日志记录计费币种与该币种下的成本
+    fn migrate_v7_to_v8(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_request_logs")? {
+            Self::add_column_if_missing(
+                conn,
+                "proxy_request_logs",
+                "billing_currency",
+                "TEXT NOT NULL DEFAULT 'USD'",
+            )?;
+            let added = Self::add_column_if_missing(
+                conn,
+                "proxy_request_logs",
+                "billing_cost",
+                "TEXT NOT NULL DEFAULT '0'",
+            )?;
+            // 历史记录均按 USD 计费
+            if added && Self::has_column(conn, "proxy_request_logs", "total_cost_usd")? {
+                conn.execute(
+                    "UPDATE proxy_request_logs SET billing_cost = total_cost_usd",
+                    [],
+                )
+                .map_err(|e| AppError::Database(format!("回填计费成本失败: {e}")))?;
+            }
+        }
+
+        log::info!("v7 -> v8 迁移完成：已添加计费币种字段");
+        Ok(())
+    }
+
+    /// v8 -> v9 迁移：请求日志区分代理记录与会话记录导入
+    fn migrate_v8_to_v9(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_request_logs")? {
+            Self::add_column_if_missing(
+                conn,
+                "proxy_request_logs",
+                "source",
+                "TEXT NOT NULL DEFAULT 'proxy'",
+            )?;
+        }
+
+        log::info!("v8 -> v9 迁移完成：已添加用量来源字段");
+        Ok(())
+    }
+
+    /// v9 -> v10 迁移：由现有请求日志重建用量汇总表
+    fn migrate_v9_to_v10(conn: &Connection) -> Result<(), AppError> {
+        // 汇总表由 create_tables 创建，仅在其存在时重建
+        if Self::table_exists(conn, "proxy_request_logs")?
+            && Self::table_exists(conn, "usage_rollup_hourly")?
+        {
+            Self::create_usage_rollup_triggers(conn)?;
+            Self::rebuild_usage_rollups(conn)?;
+        }
+
+        log::info!("v9 -> v10 迁移完成：已生成用量汇总");
+        Ok(())
+    }
+
+    /// v10 -> v11 迁移：请求日志记录期望模型与模型替换标记
+    fn migrate_v10_to_v11(conn: &Connection) -> Result<(), AppError> {
+        if Self::table_exists(conn, "proxy_request_logs")? {
+            Self::add_column_if_missing(conn, "proxy_request_logs", "expected_model", "TEXT")?;
+            Self::add_column_if_missing(
+                conn,
+                "proxy_request_logs",
+                "model_mismatch",
+                "INTEGER NOT NULL DEFAULT 0",
+            )?;
+        }
+
+        log::info!("v10 -> v11 迁移完成：已添加模型替换检测字段");
+        Ok(())
+    }
+
+    /// v11 -> v12 迁移：会话全文索引改为外部内容表，按文件路径删除时走普通索引
+    fn migrate_v11_to_v12(conn: &Connection) -> Result<(), AppError> {
+        // 索引是本机缓存，丢弃旧表后由下次刷新全量重建
+        conn.execute("DROP TABLE IF EXISTS session_index_fts", [])
+            .map_err(|e| AppError::Database(format!("清理旧会话索引失败: {e}")))?;
+        if Self::table_exists(conn, "session_index_files")? {
+            conn.execute("DELETE FROM session_index_files", [])
+                .map_err(|e| AppError::Database(format!("清理旧会话索引失败: {e}")))?;
+        }
+        Self::create_session_index_messages(conn)?;
+
+        log::info!("v11 -> v12 迁移完成：会话索引将重建");
+        Ok(())
+    }
+
+    /// 创建会话消息表及其全文索引
+    ///
+    /// 消息内容只保存在 `session_index_messages`（`source_path` 上有普通索引），
+    /// FTS5 表以其为外部内容、按 rowid 关联，由触发器同步
+    pub(crate) fn create_session_index_messages(conn: &Connection) -> Result<(), AppError> {
+        // trigram 分词支持中文等无空格文本的子串匹配
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS session_index_messages (
+                id INTEGER PRIMARY KEY, source_path TEXT NOT NULL,
+                message_index INTEGER NOT NULL, role TEXT, ts INTEGER, content TEXT NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_session_index_messages_path
+                ON session_index_messages(source_path);
+            CREATE VIRTUAL TABLE IF NOT EXISTS session_index_fts USING fts5(
+                content, content = 'session_index_messages', content_rowid = 'id',
+                tokenize = 'trigram'
+            );
+            CREATE TRIGGER IF NOT EXISTS session_index_messages_ai
+            AFTER INSERT ON session_index_messages BEGIN
+                INSERT INTO session_index_fts (rowid, content) VALUES (NEW.id, NEW.content);
+            END;
+            CREATE TRIGGER IF NOT EXISTS session_index_messages_ad
+            AFTER DELETE ON session_index_messages BEGIN
+                INSERT INTO session_index_fts (session_index_fts, rowid, content)
+                VALUES ('delete', OLD.id, OLD.content);
+            END;",
+        )
+        .map_err(|e| AppError::Database(format!("创建会话索引表失败: {e}")))
+    }
+
     /// 插入默认模型定价数据
     /// 格式: (model_id, display_name, input, output, cache_read, cache_creation)
     /// 注意: model_id 使用短横线格式（如 claude-haiku-4-5），与 API 返回的模型名称标准化后一致
@@ -1283,7 +1713,61 @@ impl Database {
 
     fn ensure_model_pricing_seeded_on_conn(conn: &Connection) -> Result<(), AppError> {
         // 每次启动都执行 INSERT OR IGNORE，增量追加新模型，已有数据不覆盖
-        Self::seed_model_pricing(conn)
+        Self::seed_model_pricing(conn)?;
+        Self::seed_model_pricing_extras(conn)
+    }
+
+    /// 补齐默认的 1 小时缓存写入价格与长上下文阶梯
+    /// 格式: (model_id, cache_creation_1h, tiers JSON)
+    /// 只填充仍为空的列，用户修改过的定价不覆盖
+    fn seed_model_pricing_extras(conn: &Connection) -> Result<(), AppError> {
+        const CLAUDE_SONNET_LONG_CONTEXT: &str = r#"[{"aboveInputTokens":200000,"inputCostPerMillion":"6","outputCostPerMillion":"22.50","cacheReadCostPerMillion":"0.60","cacheCreationCostPerMillion":"7.50","cacheCreation1hCostPerMillion":"12"}]"#;
+        const CLAUDE_OPUS_LONG_CONTEXT: &str = r#"[{"aboveInputTokens":200000,"inputCostPerMillion":"10","outputCostPerMillion":"37.50","cacheReadCostPerMillion":"1","cacheCreationCostPerMillion":"12.50","cacheCreation1hCostPerMillion":"20"}]"#;
+        const GEMINI_3_PRO_LONG_CONTEXT: &str = r#"[{"aboveInputTokens":200000,"inputCostPerMillion":"4","outputCostPerMillion":"18","cacheReadCostPerMillion":"0.4"}]"#;
+        const GEMINI_25_PRO_LONG_CONTEXT: &str = r#"[{"aboveInputTokens":200000,"inputCostPerMillion":"2.5","outputCostPerMillion":"15","cacheReadCostPerMillion":"0.25"}]"#;
+
+        let extras: [(&str, Option<&str>, Option<&str>); 11] = [
+            (
+                "claude-opus-4-6-20260206",
+                Some("10"),
+                Some(CLAUDE_OPUS_LONG_CONTEXT),
+            ),
+            ("claude-opus-4-5-20251101", Some("10"), None),
+            (
+                "claude-sonnet-4-5-20250929",
+                Some("6"),
+                Some(CLAUDE_SONNET_LONG_CONTEXT),
+            ),
+            ("claude-haiku-4-5-20251001", Some("2"), None),
+            ("claude-opus-4-20250514", Some("30"), None),
+            ("claude-opus-4-1-20250805", Some("30"), None),
+            (
+                "claude-sonnet-4-20250514",
+                Some("6"),
+                Some(CLAUDE_SONNET_LONG_CONTEXT),
+            ),
+            ("claude-3-5-haiku-20241022", Some("1.6"), None),
+            ("claude-3-5-sonnet-20241022", Some("6"), None),
+            (
+                "gemini-3-pro-preview",
+                None,
+                Some(GEMINI_3_PRO_LONG_CONTEXT),
+            ),
+            ("gemini-2.5-pro", None, Some(GEMINI_25_PRO_LONG_CONTEXT)),
+        ];
+
+        for (model_id, cache_creation_1h, tiers) in extras {
+            conn.execute(
+                "UPDATE model_pricing
+                 SET cache_creation_1h_cost_per_million = COALESCE(cache_creation_1h_cost_per_million, ?2),
+                     tiers = COALESCE(tiers, ?3)
+                 WHERE model_id = ?1",
+                rusqlite::params![model_id, cache_creation_1h, tiers],
+            )
+            .map_err(|e| AppError::Database(format!("补齐模型阶梯定价失败: {e}")))?;
+        }
+
+        Ok(())
     }
 
     // --- 辅助方法 ---
@@ -336,6 +336,55 @@ fn schema_migration_v4_adds_pricing_model_columns() {
     );
 }
 
+#[test]
+fn schema_migration_v6_adds_tiered_pricing_columns() {
+    let conn = Connection::open_in_memory().expect("open memory db");
+    conn.execute_batch(
+        r#"
+        CREATE TABLE model_pricing (
+            model_id TEXT PRIMARY KEY,
+            display_name TEXT NOT NULL,
+            input_cost_per_million TEXT NOT NULL,
+            output_cost_per_million TEXT NOT NULL
+        );
+        CREATE TABLE proxy_request_logs (request_id TEXT PRIMARY KEY, model TEXT NOT NULL);
+        "#,
+    )
+    .expect("seed v6 schema");
+
+    Database::set_user_version(&conn, 6).expect("set user_version=6");
+    Database::apply_schema_migrations_on_conn(&conn).expect("apply migrations");
+
+    let tiers = get_column_info(&conn, "model_pricing", "tiers");
+    assert_eq!(tiers.r#type, "TEXT");
+    let effective_from = get_column_info(&conn, "model_pricing", "effective_from");
+    assert_eq!(effective_from.notnull, 1);
+    assert_eq!(
+        normalize_default(&effective_from.default).as_deref(),
+        Some("0")
+    );
+    get_column_info(&conn, "model_pricing", "cache_creation_1h_cost_per_million");
+
+    let ttl_tokens = get_column_info(&conn, "proxy_request_logs", "cache_creation_1h_tokens");
+    assert_eq!(ttl_tokens.r#type, "INTEGER");
+    assert_eq!(ttl_tokens.notnull, 1);
+
+    let billing_currency = get_column_info(&conn, "proxy_request_logs", "billing_currency");
+    assert_eq!(
+        normalize_default(&billing_currency.default).as_deref(),
+        Some("USD")
+    );
+    get_column_info(&conn, "proxy_request_logs", "billing_cost");
+
+    let source = get_column_info(&conn, "proxy_request_logs", "source");
+    assert_eq!(normalize_default(&source.default).as_deref(), Some("proxy"));
+
+    assert_eq!(
+        Database::get_user_version(&conn).expect("version after migration"),
+        SCHEMA_VERSION
+    );
+}
+
 #[test]
 fn schema_create_tables_repairs_legacy_proxy_config_singleton_to_per_app() {
     let conn = Connection::open_in_memory().expect("open memory db");
@@ -633,3 +682,57 @@ fn schema_model_pricing_is_seeded_on_init() {
         gemini_count
     );
 }
+
+#[test]
+fn schema_migration_v11_rebuilds_session_index_as_external_content() {
+    let conn = Connection::open_in_memory().expect("open memory db");
+    conn.execute_batch(
+        r#"
+        CREATE TABLE session_index_files (
+            source_path TEXT PRIMARY KEY, provider_id TEXT NOT NULL, session_id TEXT NOT NULL,
+            title TEXT, project_dir TEXT, created_at INTEGER, last_active_at INTEGER,
+            file_size INTEGER NOT NULL DEFAULT 0, modified_at INTEGER NOT NULL DEFAULT 0,
+            message_count INTEGER NOT NULL DEFAULT 0, indexed_at INTEGER NOT NULL
+        );
+        CREATE VIRTUAL TABLE session_index_fts USING fts5(
+            content, source_path UNINDEXED, message_index UNINDEXED, role UNINDEXED, ts UNINDEXED,
+            tokenize = 'trigram'
+        );
+        INSERT INTO session_index_files (source_path, provider_id, session_id, indexed_at)
+        VALUES ('/a.jsonl', 'claude', 's1', 0);
+        INSERT INTO session_index_fts (content, source_path, message_index)
+        VALUES ('old content', '/a.jsonl', 0);
+        "#,
+    )
+    .expect("seed v11 session index");
+
+    Database::set_user_version(&conn, 11).expect("set user_version=11");
+    Database::apply_schema_migrations_on_conn(&conn).expect("apply migrations");
+
+    // 旧索引已清空，文件指纹一并清除以触发全量重建
+    let files: i64 = conn
+        .query_row("SELECT COUNT(*) FROM session_index_files", [], |row| {
+            row.get(0)
+        })
+        .expect("count files");
+    assert_eq!(files, 0);
+
+    conn.execute(
+        "INSERT INTO session_index_messages (source_path, message_index, content)
+         VALUES ('/a.jsonl', 0, 'fresh content')",
+        [],
+    )
+    .expect("insert message");
+    let hits: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM session_index_fts WHERE session_index_fts MATCH 'fresh'",
+            [],
+            |row| row.get(0),
+        )
+        .expect("search");
+    assert_eq!(hits, 1);
+    assert_eq!(
+        Database::get_user_version(&conn).expect("version after migration"),
+        SCHEMA_VERSION
+    );
+}
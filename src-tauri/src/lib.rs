@@ -3,6 +3,7 @@ mod app_store;
 mod auto_launch;
 mod claude_mcp;
 mod claude_plugin;
+mod cli;
 mod codex_config;
 mod commands;
 mod config;
@@ -29,6 +30,7 @@ mod tray;
 mod usage_script;
 
 pub use app_config::{AppType, McpApps, McpServer, MultiAppConfig};
+pub use cli::try_run_cli;
 pub use codex_config::{get_codex_auth_path, get_codex_config_path, write_codex_live_atomic};
 pub use commands::open_provider_terminal;
 pub use commands::*;
@@ -251,6 +253,7 @@ pub fn run() {
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_store::Builder::new().build())
         .setup(|app| {
             // 预先刷新 Store 覆盖配置，确保后续路径读取正确（日志/数据库等）
@@ -721,8 +724,21 @@ pub fn run() {
                 app_state.db.clone(),
                 app.handle().clone(),
             );
+            crate::services::notification::start_worker(
+                app_state.db.clone(),
+                app.handle().clone(),
+            );
             // 将同一个实例注入到全局状态，避免重复创建导致的不一致
             app.manage(app_state);
+            crate::services::health_probe::start_worker(app.handle().clone());
+            crate::services::usage_poller::start_worker(app.handle().clone());
+            crate::services::transcript_usage::start_worker(app.handle().clone());
+            crate::services::usage_rollup::start_worker(app.handle().clone());
+            crate::services::usage_forecast::start_worker(app.handle().clone());
+            crate::services::balance_reconciliation::start_worker(app.handle().clone());
+            crate::services::model_fidelity::start_worker(app.handle().clone());
+            crate::session_manager::live::start_worker(app.handle().clone());
+            crate::session_manager::summarize::start_worker(app.handle().clone());
 
             // 从数据库加载日志配置并应用
             {
@@ -905,6 +921,8 @@ pub fn run() {
             commands::validate_mcp_command,
             // usage query
             commands::queryProviderUsage,
+            commands::get_quota_exhaustions,
+            commands::clear_quota_exhaustion,
             commands::testUsageScript,
             // New MCP via config.json (SSOT)
             commands::get_mcp_config,
@@ -938,6 +956,9 @@ pub fn run() {
             // theirs: config import/export and dialogs
             commands::export_config_to_file,
             commands::import_config_from_file,
+            // config-as-code manifest
+            commands::plan_config_manifest,
+            commands::apply_config_manifest,
             commands::webdav_test_connection,
             commands::webdav_sync_upload,
             commands::webdav_sync_download,
@@ -1027,16 +1048,88 @@ pub fn run() {
             commands::get_model_pricing,
             commands::update_model_pricing,
             commands::delete_model_pricing,
+            commands::get_model_pricing_versions,
+            commands::add_model_pricing_history,
+            commands::delete_model_pricing_history,
+            commands::recompute_usage_costs,
+            commands::backfill_unpriced_usage_costs,
+            commands::preview_pricing_catalog,
+            commands::import_pricing_catalog,
+            commands::get_model_pricing_aliases,
+            commands::save_model_pricing_alias,
+            commands::delete_model_pricing_alias,
+            commands::get_exchange_rates,
+            commands::save_exchange_rate,
+            commands::delete_exchange_rate,
+            commands::get_reporting_currency,
+            commands::set_reporting_currency,
             commands::check_provider_limits,
             // Stream health check
             commands::stream_check_provider,
             commands::stream_check_all_providers,
             commands::get_stream_check_config,
             commands::save_stream_check_config,
+            // Scheduled health probes
+            commands::get_health_probe_config,
+            commands::save_health_probe_config,
+            commands::run_health_probes_now,
+            commands::get_provider_health_history,
+            // Upstream model substitution detection
+            commands::get_model_fidelity_config,
+            commands::save_model_fidelity_config,
+            commands::get_model_fidelity_stats,
+            commands::get_model_probe_results,
+            commands::run_model_fingerprint_probe,
+            // Transcript usage ingestion
+            commands::get_transcript_ingest_config,
+            commands::save_transcript_ingest_config,
+            commands::run_transcript_ingest_now,
+            commands::get_top_session_costs,
+            commands::get_project_costs,
+            commands::get_project_monthly_costs,
+            commands::get_usage_retention_config,
+            commands::save_usage_retention_config,
+            commands::run_usage_retention_now,
+            commands::get_usage_forecast_config,
+            commands::save_usage_forecast_config,
+            commands::get_usage_forecast,
+            commands::get_balance_reconciliation_config,
+            commands::save_balance_reconciliation_config,
+            commands::get_balance_snapshots,
+            commands::get_balance_reconciliation,
+            commands::export_usage,
+            // Notifications
+            commands::get_notification_config,
+            commands::save_notification_config,
+            commands::test_notification_sink,
             // Session manager
             commands::list_sessions,
             commands::get_session_messages,
             commands::launch_session_terminal,
+            commands::search_sessions,
+            commands::refresh_session_index,
+            commands::export_session,
+            commands::render_session_export,
+            commands::import_session_bundle,
+            commands::convert_session,
+            commands::get_session_annotations,
+            commands::set_session_annotation,
+            commands::delete_sessions,
+            commands::archive_sessions,
+            commands::list_archived_sessions,
+            commands::restore_archived_session,
+            commands::delete_archived_session,
+            commands::prune_sessions,
+            commands::watch_session,
+            commands::unwatch_session,
+            commands::get_live_sessions,
+            commands::set_live_session_discovery,
+            commands::get_session_analytics,
+            commands::get_project_analytics,
+            commands::get_session_summaries,
+            commands::summarize_session,
+            commands::get_session_summary_config,
+            commands::save_session_summary_config,
             commands::get_tool_versions,
             // Provider terminal
             commands::open_provider_terminal,
@@ -12,5 +12,11 @@ fn main() {
         }
     }
 
+    // 命令行子命令（如 export-usage）不启动界面
+    let args: Vec<String> = std::env::args().collect();
+    if let Some(code) = cc_switch_lib::try_run_cli(&args) {
+        std::process::exit(code);
+    }
+
     cc_switch_lib::run();
 }
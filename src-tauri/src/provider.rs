@@ -106,6 +106,52 @@ pub struct UsageScript {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(rename = "autoQueryInterval")]
     pub auto_query_interval: Option<u64>,
+    /// 额度守护：根据查询结果自动调整路由（移出故障转移队列 / 切换当前供应商）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(rename = "quotaGuard")]
+    pub quota_guard: Option<QuotaGuard>,
+}
+
+/// 额度守护规则
+///
+/// 任一阈值命中即视为“额度耗尽”；多套餐时需全部套餐耗尽才会触发。
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaGuard {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 剩余额度低于该值时视为耗尽
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remaining_below: Option<f64>,
+    /// 剩余比例低于该值时视为耗尽（0-100）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub percent_below: Option<f64>,
+    /// 脚本返回 isValid=false 时视为耗尽
+    #[serde(default = "default_true")]
+    pub on_invalid: bool,
+    /// 耗尽时执行的动作
+    #[serde(default)]
+    pub action: QuotaGuardAction,
+    /// 额度恢复后是否自动撤销动作
+    #[serde(default = "default_true")]
+    pub auto_restore: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 额度耗尽时的动作
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum QuotaGuardAction {
+    /// 仅降低代理路由优先级（排到故障转移队列末尾）
+    #[default]
+    Deprioritize,
+    /// 移出故障转移队列
+    RemoveFromQueue,
+    /// 若为当前供应商，切换到队列中的下一个
+    SwitchCurrent,
 }
 
 /// 用量数据
@@ -218,6 +264,13 @@ pub struct ProviderMeta {
     /// 计费模式来源（response/request）
     #[serde(rename = "pricingModelSource", skip_serializing_if = "Option::is_none")]
     pub pricing_model_source: Option<String>,
+    /// 计费币种（ISO 4217 代码，如 CNY），未设置视为 USD
+    #[serde(rename = "billingCurrency", skip_serializing_if = "Option::is_none")]
+    pub billing_currency: Option<String>,
+    /// 充值汇率：每 1 USD 额度实际支付的计费币种金额（如 1 元购买 1 美元额度时为 "1"）
+    /// 未设置时按汇率表换算
+    #[serde(rename = "rechargeRate", skip_serializing_if = "Option::is_none")]
+    pub recharge_rate: Option<String>,
     /// 每日消费限额（USD）
     #[serde(rename = "limitDailyUsd", skip_serializing_if = "Option::is_none")]
     pub limit_daily_usd: Option<String>,
@@ -235,6 +288,24 @@ pub struct ProviderMeta {
     /// - "openai_chat": OpenAI Chat Completions 格式，需要转换
     #[serde(rename = "apiFormat", skip_serializing_if = "Option::is_none")]
     pub api_format: Option<String>,
+    /// 主地址最近一次测速结果（主地址不属于自定义端点，单独记录）
+    #[serde(
+        rename = "primaryEndpointLatency",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub primary_endpoint_latency: Option<PrimaryEndpointLatency>,
+}
+
+/// 主地址测速结果，端点池据此将主地址与镜像一起排序
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PrimaryEndpointLatency {
+    /// 测速时的主地址，主地址变更后旧结果不再生效
+    pub url: String,
+    /// 测速失败时为空
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    pub tested_at: i64,
 }
 
 impl ProviderManager {
@@ -187,7 +187,9 @@ impl CircuitBreaker {
     }
 
     /// 记录成功
-    pub async fn record_success(&self, used_half_open_permit: bool) {
+    ///
+    /// 返回本次成功重置的连续失败数，事后判定该请求失败时传回 `record_late_failure`
+    pub async fn record_success(&self, used_half_open_permit: bool) -> u32 {
         let state = *self.state.read().await;
         let config = self.config.read().await;
 
@@ -196,7 +198,7 @@ impl CircuitBreaker {
         }
 
         // 重置失败计数
-        self.consecutive_failures.store(0, Ordering::SeqCst);
+        let cleared_failures = self.consecutive_failures.swap(0, Ordering::SeqCst);
         self.total_requests.fetch_add(1, Ordering::SeqCst);
 
         if state == CircuitState::HalfOpen {
@@ -211,6 +213,7 @@ impl CircuitBreaker {
                 self.transition_to_closed().await;
             }
         }
+        cleared_failures
     }
 
     /// 记录失败
@@ -274,6 +277,20 @@ impl CircuitBreaker {
         }
     }
 
+    /// 记录延迟判定的失败
+    ///
+    /// 请求已按成功计数，事后才发现结果无效（如上游替换了模型）：
+    /// 按该请求 `record_success` 返回的 `cleared_failures` 撤销其对连续失败计数的重置，
+    /// 再按普通失败处理。每个请求各自携带重置值，并发请求之间互不覆盖
+    pub async fn record_late_failure(&self, cleared_failures: u32) {
+        self.consecutive_failures
+            .fetch_max(cleared_failures, Ordering::SeqCst);
+        let _ = self
+            .total_requests
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |t| t.checked_sub(1));
+        self.record_failure(false).await;
+    }
+
     /// 获取当前状态
     #[allow(dead_code)]
     pub async fn get_state(&self) -> CircuitState {
@@ -299,6 +316,31 @@ impl CircuitBreaker {
         self.transition_to_closed().await;
     }
 
+    /// 应用后台健康探测结果
+    ///
+    /// - 探测失败：与真实请求失败一样计数，达到失败阈值或错误率阈值后才打开熔断器
+    /// - 探测成功：Open 提前进入 HalfOpen，由真实请求完成最终恢复判定
+    pub async fn apply_probe_result(&self, healthy: bool) {
+        let state = *self.state.read().await;
+        match (state, healthy) {
+            (CircuitState::Open, true) => {
+                log::info!(
+                    "[{}] 健康探测成功，熔断器 Open → HalfOpen",
+                    log_cb::PROBE_RECOVERED
+                );
+                self.transition_to_half_open().await;
+            }
+            (CircuitState::Closed | CircuitState::HalfOpen, false) => {
+                log::warn!(
+                    "[{}] 健康探测失败，计入熔断器失败次数 ({state})",
+                    log_cb::PROBE_TRIPPED
+                );
+                self.record_failure(false).await;
+            }
+            _ => {}
+        }
+    }
+
     fn allow_half_open_probe(&self) -> AllowResult {
         // 半开状态限流：只允许有限请求通过进行探测
         let max_half_open_requests = 1u32;
@@ -411,6 +453,30 @@ mod tests {
         assert!(!breaker.allow_request().await.allowed);
     }
 
+    #[tokio::test]
+    async fn test_late_failures_accumulate() {
+        let config = CircuitBreakerConfig {
+            failure_threshold: 3,
+            ..Default::default()
+        };
+        let breaker = CircuitBreaker::new(config);
+
+        // 每次请求先按成功记录，随后判定为失败：连续失败仍应累计
+        for _ in 0..2 {
+            let cleared = breaker.record_success(false).await;
+            breaker.record_late_failure(cleared).await;
+        }
+        let stats = breaker.get_stats().await;
+        assert_eq!(stats.consecutive_failures, 2);
+        assert_eq!((stats.total_requests, stats.failed_requests), (2, 2));
+
+        // 并发的另一次成功不影响该请求撤销的重置值
+        let cleared = breaker.record_success(false).await;
+        breaker.record_success(false).await;
+        breaker.record_late_failure(cleared).await;
+        assert_eq!(breaker.get_state().await, CircuitState::Open);
+    }
+
     #[tokio::test]
     async fn test_circuit_breaker_half_open_to_closed() {
         let config = CircuitBreakerConfig {
@@ -479,4 +545,26 @@ mod tests {
         assert_eq!(breaker.get_state().await, CircuitState::Closed);
         assert!(breaker.allow_request().await.allowed);
     }
+
+    #[tokio::test]
+    async fn test_probe_result_trips_and_recovers() {
+        let breaker = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 2,
+            ..Default::default()
+        });
+
+        breaker.apply_probe_result(true).await;
+        assert_eq!(breaker.get_state().await, CircuitState::Closed);
+
+        // 单次探测失败不足以打开熔断器，需达到失败阈值
+        breaker.apply_probe_result(false).await;
+        assert_eq!(breaker.get_state().await, CircuitState::Closed);
+        breaker.apply_probe_result(false).await;
+        assert_eq!(breaker.get_state().await, CircuitState::Open);
+        assert!(!breaker.allow_request().await.allowed);
+
+        breaker.apply_probe_result(true).await;
+        assert_eq!(breaker.get_state().await, CircuitState::HalfOpen);
+        assert!(breaker.allow_request().await.allowed);
+    }
 }
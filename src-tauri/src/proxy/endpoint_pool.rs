@@ -0,0 +1,222 @@
+//! 供应商内部端点池
+//!
+//! 许多中转服务为同一账号提供多个镜像域名。此模块将供应商的主 base URL
+//! 与 `meta.custom_endpoints` 合并为候选列表，并按最近一次测速延迟排序
+//! （主地址的测速结果记录在 `meta.primary_endpoint_latency`）：
+//! - 测速成功的端点按延迟升序；从未测速的主地址视为最快，保持优先
+//! - 未测速的镜像其次
+//! - 最近一次测速失败的端点排在最后
+//!
+//! `endpointAutoSelect` 显式关闭时，主地址固定排在首位，其余镜像仍按延迟排序。
+
+use super::ProxyError;
+use crate::provider::Provider;
+
+/// 构建供应商的端点池（至少包含主地址）
+pub fn build_endpoint_pool(primary: &str, provider: &Provider) -> Vec<String> {
+    let primary = normalize(primary);
+    let Some(meta) = provider.meta.as_ref() else {
+        return vec![primary];
+    };
+    if meta.custom_endpoints.is_empty() {
+        return vec![primary];
+    }
+
+    // (url, 排序层级, 延迟)
+    let mut candidates: Vec<(String, u8, u64)> = Vec::new();
+    let rank = |url: &str| -> (u8, u64) {
+        match meta.custom_endpoints.get(url) {
+            Some(ep) => match (ep.latency_ms, ep.last_tested_at) {
+                (Some(latency), _) => (0, latency),
+                (None, Some(_)) => (2, u64::MAX),
+                (None, None) => (1, u64::MAX),
+            },
+            None => (1, u64::MAX),
+        }
+    };
+
+    // 主地址尚无测速记录时不应被已测速的镜像抢先；主地址变更后旧记录不再生效
+    let primary_result = meta
+        .primary_endpoint_latency
+        .as_ref()
+        .filter(|result| normalize(&result.url) == primary);
+    let (tier, latency) = match (primary_result, meta.custom_endpoints.get(&primary)) {
+        (Some(result), _) => match result.latency_ms {
+            Some(latency) => (0, latency),
+            None => (2, u64::MAX),
+        },
+        (None, Some(_)) => rank(&primary),
+        (None, None) => (0, 0),
+    };
+    candidates.push((primary.clone(), tier, latency));
+
+    // HashMap 无序，先按 URL 排序保证结果稳定
+    let mut mirrors: Vec<&String> = meta.custom_endpoints.keys().collect();
+    mirrors.sort();
+    for raw in mirrors {
+        let url = normalize(raw);
+        if url.is_empty() || candidates.iter().any(|(u, _, _)| *u == url) {
+            continue;
+        }
+        let (tier, latency) = rank(&url);
+        candidates.push((url, tier, latency));
+    }
+
+    let pin_primary = meta.endpoint_auto_select == Some(false);
+    let start = usize::from(pin_primary);
+    // 稳定排序：同层级同延迟时保留原顺序（主地址在前）
+    candidates[start..].sort_by_key(|(_, tier, latency)| (*tier, *latency));
+
+    candidates.into_iter().map(|(url, _, _)| url).collect()
+}
+
+/// 判断错误是否应在同一供应商的下一个端点上重试
+///
+/// 仅连接失败、超时与 5xx 属于端点问题；4xx 通常与账号/请求有关，换镜像无意义。
+pub fn should_try_next_endpoint(error: &ProxyError) -> bool {
+    match error {
+        ProxyError::Timeout(_) | ProxyError::ForwardFailed(_) => true,
+        ProxyError::UpstreamError { status, .. } => *status >= 500,
+        _ => false,
+    }
+}
+
+fn normalize(url: &str) -> String {
+    url.trim().trim_end_matches('/').to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::{PrimaryEndpointLatency, ProviderMeta};
+    use crate::settings::CustomEndpoint;
+    use serde_json::json;
+
+    fn endpoint(url: &str, latency_ms: Option<u64>, tested: bool) -> (String, CustomEndpoint) {
+        (
+            url.to_string(),
+            CustomEndpoint {
+                url: url.to_string(),
+                added_at: 0,
+                last_used: None,
+                latency_ms,
+                last_tested_at: tested.then_some(1),
+            },
+        )
+    }
+
+    fn provider_with(endpoints: Vec<(String, CustomEndpoint)>, auto: Option<bool>) -> Provider {
+        let mut provider = Provider::with_id("p".into(), "P".into(), json!({}), None);
+        provider.meta = Some(ProviderMeta {
+            custom_endpoints: endpoints.into_iter().collect(),
+            endpoint_auto_select: auto,
+            ..Default::default()
+        });
+        provider
+    }
+
+    #[test]
+    fn pool_without_custom_endpoints_is_primary_only() {
+        let provider = Provider::with_id("p".into(), "P".into(), json!({}), None);
+        assert_eq!(
+            build_endpoint_pool("https://a.example.com/", &provider),
+            vec!["https://a.example.com".to_string()]
+        );
+    }
+
+    #[test]
+    fn pool_orders_by_latency_then_untested_then_failed() {
+        let provider = provider_with(
+            vec![
+                endpoint("https://a.example.com", Some(300), true),
+                endpoint("https://b.example.com", Some(80), true),
+                endpoint("https://c.example.com", None, true),
+                endpoint("https://d.example.com", None, false),
+            ],
+            None,
+        );
+        assert_eq!(
+            build_endpoint_pool("https://a.example.com", &provider),
+            vec![
+                "https://b.example.com",
+                "https://a.example.com",
+                "https://d.example.com",
+                "https://c.example.com",
+            ]
+        );
+    }
+
+    #[test]
+    fn untested_primary_stays_ahead_of_tested_mirrors() {
+        let provider = provider_with(
+            vec![
+                endpoint("https://b.example.com", Some(80), true),
+                endpoint("https://c.example.com", None, false),
+            ],
+            None,
+        );
+        assert_eq!(
+            build_endpoint_pool("https://a.example.com", &provider),
+            vec![
+                "https://a.example.com",
+                "https://b.example.com",
+                "https://c.example.com",
+            ]
+        );
+    }
+
+    #[test]
+    fn primary_latency_from_meta_ranks_primary() {
+        let mut provider = provider_with(
+            vec![endpoint("https://b.example.com", Some(80), true)],
+            None,
+        );
+        let meta = provider.meta.as_mut().unwrap();
+        meta.primary_endpoint_latency = Some(PrimaryEndpointLatency {
+            url: "https://a.example.com/".to_string(),
+            latency_ms: Some(300),
+            tested_at: 1,
+        });
+        assert_eq!(
+            build_endpoint_pool("https://a.example.com", &provider),
+            vec!["https://b.example.com", "https://a.example.com"]
+        );
+
+        // 主地址变更后旧测速结果不再生效
+        assert_eq!(
+            build_endpoint_pool("https://c.example.com", &provider),
+            vec!["https://c.example.com", "https://b.example.com"]
+        );
+    }
+
+    #[test]
+    fn pool_pins_primary_when_auto_select_disabled() {
+        let provider = provider_with(
+            vec![
+                endpoint("https://a.example.com", Some(300), true),
+                endpoint("https://b.example.com", Some(80), true),
+            ],
+            Some(false),
+        );
+        assert_eq!(
+            build_endpoint_pool("https://a.example.com", &provider),
+            vec!["https://a.example.com", "https://b.example.com"]
+        );
+    }
+
+    #[test]
+    fn only_connection_errors_and_5xx_retry_next_endpoint() {
+        assert!(should_try_next_endpoint(&ProxyError::ForwardFailed(
+            "conn".into()
+        )));
+        assert!(should_try_next_endpoint(&ProxyError::Timeout("t".into())));
+        assert!(should_try_next_endpoint(&ProxyError::UpstreamError {
+            status: 502,
+            body: None,
+        }));
+        assert!(!should_try_next_endpoint(&ProxyError::UpstreamError {
+            status: 401,
+            body: None,
+        }));
+    }
+}
@@ -4,7 +4,7 @@
 //! - 去重控制（避免多个请求同时触发）
 //! - 数据库更新
 //! - 托盘菜单更新
-//! - 前端事件发射
+//! - 前端事件发射与外部通知
 //! - Live 备份更新
 
 use crate::database::Database;
@@ -98,6 +98,14 @@ impl FailoverSwitchManager {
 
         log::info!("[FO-001] 切换: {app_type} → {provider_name}");
 
+        let previous_provider = self
+            .db
+            .get_current_provider(app_type)
+            .ok()
+            .flatten()
+            .and_then(|id| self.db.get_provider_by_id(&id, app_type).ok().flatten())
+            .map(|p| p.name);
+
         // 1. 更新数据库 is_current
         self.db.set_current_provider(app_type, provider_id)?;
 
@@ -106,6 +114,15 @@ impl FailoverSwitchManager {
             .map_err(|_| AppError::Message(format!("无效的应用类型: {app_type}")))?;
         crate::settings::set_current_provider(&app_type_enum, Some(provider_id))?;
 
+        crate::services::notification::notify(
+            crate::services::notification::NotificationEvent::FailoverSwitched {
+                app_type: app_type.to_string(),
+                from_provider: previous_provider,
+                to_provider_id: provider_id.to_string(),
+                to_provider: provider_name.to_string(),
+            },
+        );
+
         // 3. 更新托盘菜单和发射事件
         if let Some(app) = app_handle {
             // 更新托盘菜单
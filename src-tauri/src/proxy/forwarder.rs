@@ -4,8 +4,10 @@
 
 use super::{
     body_filter::filter_private_params_with_whitelist,
+    endpoint_pool::{build_endpoint_pool, should_try_next_endpoint},
     error::*,
     failover_switch::FailoverSwitchManager,
+    log_codes::fwd as log_fwd,
     provider_router::ProviderRouter,
     providers::{get_adapter, ProviderAdapter, ProviderType},
     thinking_budget_rectifier::{rectify_thinking_budget, should_rectify_thinking_budget},
@@ -72,11 +74,17 @@ const HEADER_BLACKLIST: &[&str] = &[
     // 客户端 IP 单独处理（默认透传）
     "x-forwarded-for",
     "x-real-ip",
+    // 成本归属用的项目标识，仅供本地统计
+    crate::services::cost_attribution::PROJECT_HEADER,
 ];
 
 pub struct ForwardResult {
     pub response: Response,
     pub provider: Provider,
+    /// 实际提供服务的端点（base URL）
+    pub endpoint: String,
+    /// 本次成功重置的熔断器连续失败数（事后判定失败时恢复）
+    pub cleared_failures: u32,
 }
 
 pub struct ForwardError {
@@ -199,9 +207,9 @@ impl RequestForwarder {
                 .forward(provider, endpoint, &body, &headers, adapter.as_ref())
                 .await
             {
-                Ok(response) => {
+                Ok((response, served_endpoint)) => {
                     // 成功：记录成功并更新熔断器
-                    let _ = self
+                    let cleared_failures = self
                         .router
                         .record_result(
                             &provider.id,
@@ -210,7 +218,8 @@ impl RequestForwarder {
                             true,
                             None,
                         )
-                        .await;
+                        .await
+                        .unwrap_or(0);
 
                     // 更新当前应用类型使用的 provider
                     {
@@ -253,6 +262,8 @@ impl RequestForwarder {
                     return Ok(ForwardResult {
                         response,
                         provider: provider.clone(),
+                        endpoint: served_endpoint,
+                        cleared_failures,
                     });
                 }
                 Err(e) => {
@@ -321,10 +332,10 @@ impl RequestForwarder {
                                     .forward(provider, endpoint, &body, &headers, adapter.as_ref())
                                     .await
                                 {
-                                    Ok(response) => {
+                                    Ok((response, served_endpoint)) => {
                                         log::info!("[{app_type_str}] [RECT-002] 整流重试成功");
                                         // 记录成功
-                                        let _ = self
+                                        let cleared_failures = self
                                             .router
                                             .record_result(
                                                 &provider.id,
@@ -333,7 +344,8 @@ impl RequestForwarder {
                                                 true,
                                                 None,
                                             )
-                                            .await;
+                                            .await
+                                            .unwrap_or(0);
 
                                         // 更新当前应用类型使用的 provider
                                         {
@@ -380,6 +392,8 @@ impl RequestForwarder {
                                         return Ok(ForwardResult {
                                             response,
                                             provider: provider.clone(),
+                                            endpoint: served_endpoint,
+                                            cleared_failures,
                                         });
                                     }
                                     Err(retry_err) => {
@@ -512,9 +526,9 @@ impl RequestForwarder {
                                 .forward(provider, endpoint, &body, &headers, adapter.as_ref())
                                 .await
                             {
-                                Ok(response) => {
+                                Ok((response, served_endpoint)) => {
                                     log::info!("[{app_type_str}] [RECT-011] budget 整流重试成功");
-                                    let _ = self
+                                    let cleared_failures = self
                                         .router
                                         .record_result(
                                             &provider.id,
@@ -523,7 +537,8 @@ impl RequestForwarder {
                                             true,
                                             None,
                                         )
-                                        .await;
+                                        .await
+                                        .unwrap_or(0);
 
                                     {
                                         let mut current_providers =
@@ -564,6 +579,8 @@ impl RequestForwarder {
                                     return Ok(ForwardResult {
                                         response,
                                         provider: provider.clone(),
+                                        endpoint: served_endpoint,
+                                        cleared_failures,
                                     });
                                 }
                                 Err(retry_err) => {
@@ -734,7 +751,10 @@ impl RequestForwarder {
         })
     }
 
-    /// 转发单个请求（使用适配器）
+    /// 转发单个请求（端点池内故障转移）
+    ///
+    /// 依次尝试供应商的端点池，连接失败/超时/5xx 时切换到下一个镜像，
+    /// 全部端点失败后才将错误交由上层计入熔断器。返回实际提供服务的端点。
     async fn forward(
         &self,
         provider: &Provider,
@@ -742,10 +762,50 @@ impl RequestForwarder {
         body: &Value,
         headers: &axum::http::HeaderMap,
         adapter: &dyn ProviderAdapter,
-    ) -> Result<Response, ProxyError> {
+    ) -> Result<(Response, String), ProxyError> {
         // 使用适配器提取 base_url
-        let base_url = adapter.extract_base_url(provider)?;
+        let primary = adapter.extract_base_url(provider)?;
+        let pool = build_endpoint_pool(&primary, provider);
+        let last_index = pool.len().saturating_sub(1);
 
+        for (index, base_url) in pool.into_iter().enumerate() {
+            match self
+                .forward_to_endpoint(provider, &base_url, endpoint, body, headers, adapter)
+                .await
+            {
+                Ok(response) => return Ok((response, base_url)),
+                Err(e) if index < last_index && should_try_next_endpoint(&e) => {
+                    log::warn!(
+                        "[{}] [{}] Provider {} 端点 {} 失败，切换下一个端点 ({}/{}): {}",
+                        adapter.name(),
+                        log_fwd::ENDPOINT_FAILED_RETRY,
+                        provider.name,
+                        base_url,
+                        index + 1,
+                        last_index + 1,
+                        e
+                    );
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(ProxyError::ConfigError(format!(
+            "Provider {} 没有可用端点",
+            provider.name
+        )))
+    }
+
+    /// 向指定端点转发单个请求（使用适配器）
+    async fn forward_to_endpoint(
+        &self,
+        provider: &Provider,
+        base_url: &str,
+        endpoint: &str,
+        body: &Value,
+        headers: &axum::http::HeaderMap,
+        adapter: &dyn ProviderAdapter,
+    ) -> Result<Response, ProxyError> {
         // 检查是否需要格式转换
         let needs_transform = adapter.needs_transform(provider);
 
@@ -757,7 +817,7 @@ impl RequestForwarder {
             };
 
         // 使用适配器构建 URL
-        let url = adapter.build_url(&base_url, effective_endpoint);
+        let url = adapter.build_url(base_url, effective_endpoint);
 
         // 应用模型映射（独立于格式转换）
         let (mapped_body, _original_model, _mapped_model) =
@@ -7,10 +7,14 @@ use crate::provider::Provider;
 use crate::proxy::{
     extract_session_id,
     forwarder::RequestForwarder,
+    model_mapper::{has_thinking_enabled, ModelMapping},
     server::ProxyState,
     types::{AppProxyConfig, RectifierConfig},
     ProxyError,
 };
+use crate::services::cost_attribution::{
+    normalize_project_dir, record_header_project, PROJECT_HEADER,
+};
 use axum::http::HeaderMap;
 use std::time::Instant;
 
@@ -59,6 +63,14 @@ pub struct RequestContext {
     pub session_id: String,
     /// 整流器配置
     pub rectifier_config: RectifierConfig,
+    /// 实际提供服务的端点（转发成功后填充）
+    pub endpoint: Option<String>,
+    /// 转发成功时重置的熔断器连续失败数（事后判定失败时恢复）
+    pub cleared_failures: u32,
+    /// 请求体是否携带模型字段（Gemini 模型在 URI 中，不参与模型映射）
+    body_has_model: bool,
+    /// 请求是否启用 thinking（影响模型映射结果）
+    thinking_enabled: bool,
 }
 
 impl RequestContext {
@@ -116,6 +128,18 @@ impl RequestContext {
             session_result.client_provided
         );
 
+        // 客户端声明了项目目录时，记录会话归属（用于按项目统计成本）
+        if let Some(project_dir) = headers
+            .get(PROJECT_HEADER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(normalize_project_dir)
+        {
+            if let Err(e) = record_header_project(&state.db, &session_id, app_type_str, project_dir)
+            {
+                log::warn!("[{tag}] 记录会话项目失败: {e}");
+            }
+        }
+
         // 使用共享的 ProviderRouter 选择 Provider（熔断器状态跨请求保持）
         // 注意：只在这里调用一次，结果传递给 forwarder，避免重复消耗 HalfOpen 名额
         let providers = state
@@ -144,6 +168,9 @@ impl RequestContext {
             session_id
         );
 
+        let body_has_model = body.get("model").and_then(|m| m.as_str()).is_some();
+        let thinking_enabled = has_thinking_enabled(body);
+
         Ok(Self {
             start_time,
             app_config,
@@ -156,6 +183,10 @@ impl RequestContext {
             app_type,
             session_id,
             rectifier_config,
+            endpoint: None,
+            cleared_failures: 0,
+            body_has_model,
+            thinking_enabled,
         })
     }
 
@@ -180,6 +211,15 @@ impl RequestContext {
         self
     }
 
+    /// 映射后发往当前 Provider 的模型名称（与转发时的模型映射一致）
+    pub fn upstream_model(&self) -> String {
+        if !self.body_has_model {
+            return self.request_model.clone();
+        }
+        ModelMapping::from_provider(&self.provider)
+            .map_model(&self.request_model, self.thinking_enabled)
+    }
+
     /// 创建 RequestForwarder
     ///
     /// 使用共享的 ProviderRouter，确保熔断器状态跨请求保持
@@ -14,7 +14,10 @@ use super::{
     },
     handler_context::RequestContext,
     providers::{get_adapter, streaming::create_anthropic_sse_stream, transform},
-    response_processor::{create_logged_passthrough_stream, process_response, SseUsageCollector},
+    response_processor::{
+        create_logged_passthrough_stream, process_response, report_model_substitution,
+        SseUsageCollector,
+    },
     server::ProxyState,
     types::*,
     usage::parser::TokenUsage,
@@ -90,6 +93,8 @@ pub async fn handle_messages(
     };
 
     ctx.provider = result.provider;
+    ctx.endpoint = Some(result.endpoint);
+    ctx.cleared_failures = result.cleared_failures;
     let response = result.response;
 
     // 检查是否需要格式转换（OpenRouter 等中转服务）
@@ -129,6 +134,8 @@ async fn handle_claude_transform(
             let model = ctx.request_model.clone();
             let status_code = status.as_u16();
             let start_time = ctx.start_time;
+            let endpoint_url = ctx.endpoint.clone();
+            let cleared_failures = ctx.cleared_failures;
 
             SseUsageCollector::new(start_time, move |events, first_token_ms| {
                 if let Some(usage) = TokenUsage::from_claude_stream_events(&events) {
@@ -136,6 +143,7 @@ async fn handle_claude_transform(
                     let state = state.clone();
                     let provider_id = provider_id.clone();
                     let model = model.clone();
+                    let endpoint_url = endpoint_url.clone();
 
                     tokio::spawn(async move {
                         log_usage(
@@ -149,6 +157,10 @@ async fn handle_claude_transform(
                             first_token_ms,
                             true,
                             status_code,
+                            endpoint_url,
+                            // 转换后的流不携带上游模型，无法比对
+                            None,
+                            cleared_failures,
                         )
                         .await;
                     });
@@ -215,10 +227,13 @@ async fn handle_claude_transform(
         let latency_ms = ctx.latency_ms();
 
         let request_model = ctx.request_model.clone();
+        let expected_model = ctx.upstream_model();
+        let cleared_failures = ctx.cleared_failures;
         tokio::spawn({
             let state = state.clone();
             let provider_id = ctx.provider.id.clone();
             let model = model.to_string();
+            let endpoint_url = ctx.endpoint.clone();
             async move {
                 log_usage(
                     &state,
@@ -231,6 +246,9 @@ async fn handle_claude_transform(
                     None,
                     false,
                     status.as_u16(),
+                    endpoint_url,
+                    Some(expected_model),
+                    cleared_failures,
                 )
                 .await;
             }
@@ -302,6 +320,8 @@ pub async fn handle_chat_completions(
     };
 
     ctx.provider = result.provider;
+    ctx.endpoint = Some(result.endpoint);
+    ctx.cleared_failures = result.cleared_failures;
     let response = result.response;
 
     process_response(response, &ctx, &state, &OPENAI_PARSER_CONFIG).await
@@ -343,6 +363,8 @@ pub async fn handle_responses(
     };
 
     ctx.provider = result.provider;
+    ctx.endpoint = Some(result.endpoint);
+    ctx.cleared_failures = result.cleared_failures;
     let response = result.response;
 
     process_response(response, &ctx, &state, &CODEX_PARSER_CONFIG).await
@@ -397,6 +419,8 @@ pub async fn handle_gemini(
     };
 
     ctx.provider = result.provider;
+    ctx.endpoint = Some(result.endpoint);
+    ctx.cleared_failures = result.cleared_failures;
     let response = result.response;
 
     process_response(response, &ctx, &state, &GEMINI_PARSER_CONFIG).await
@@ -430,6 +454,7 @@ fn log_forward_error(
         is_streaming,
         Some(ctx.session_id.clone()),
         None,
+        ctx.endpoint.clone(),
     ) {
         log::warn!("记录失败请求日志失败: {e}");
     }
@@ -448,10 +473,15 @@ async fn log_usage(
     first_token_ms: Option<u64>,
     is_streaming: bool,
     status_code: u16,
+    endpoint_url: Option<String>,
+    expected_model: Option<String>,
+    cleared_failures: u32,
 ) {
     use super::usage::logger::UsageLogger;
 
     let logger = UsageLogger::new(&state.db);
+    let fidelity = state.db.get_model_fidelity_config().unwrap_or_default();
+    let expected_model = expected_model.filter(|_| fidelity.detect);
 
     let (multiplier, pricing_model_source) =
         logger.resolve_pricing_config(provider_id, app_type).await;
@@ -463,7 +493,7 @@ async fn log_usage(
 
     let request_id = uuid::Uuid::new_v4().to_string();
 
-    if let Err(e) = logger.log_with_calculation(
+    match logger.log_with_calculation(
         request_id,
         provider_id.to_string(),
         app_type.to_string(),
@@ -478,7 +508,22 @@ async fn log_usage(
         None,
         None, // provider_type
         is_streaming,
+        endpoint_url,
+        expected_model.clone(),
     ) {
-        log::warn!("[USG-001] 记录使用量失败: {e}");
+        Ok(true) => {
+            report_model_substitution(
+                state,
+                provider_id,
+                app_type,
+                expected_model.as_deref().unwrap_or_default(),
+                model,
+                fidelity.treat_as_failure,
+                cleared_failures,
+            )
+            .await
+        }
+        Ok(false) => {}
+        Err(e) => log::warn!("[USG-001] 记录使用量失败: {e}"),
     }
 }
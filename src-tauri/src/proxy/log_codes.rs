@@ -18,6 +18,8 @@ pub mod cb {
     pub const TRIGGERED_FAILURES: &str = "CB-004";
     pub const TRIGGERED_ERROR_RATE: &str = "CB-005";
     pub const MANUAL_RESET: &str = "CB-006";
+    pub const PROBE_TRIPPED: &str = "CB-007";
+    pub const PROBE_RECOVERED: &str = "CB-008";
 }
 
 /// 服务器日志码
@@ -32,6 +34,7 @@ pub mod srv {
 pub mod fwd {
     pub const PROVIDER_FAILED_RETRY: &str = "FWD-001";
     pub const ALL_PROVIDERS_FAILED: &str = "FWD-002";
+    pub const ENDPOINT_FAILED_RETRY: &str = "FWD-003";
 }
 
 /// 故障转移日志码
@@ -4,6 +4,7 @@
 
 pub mod body_filter;
 pub mod circuit_breaker;
+pub mod endpoint_pool;
 pub mod error;
 pub mod error_mapper;
 pub(crate) mod failover_switch;
@@ -95,9 +95,25 @@ impl ProviderRouter {
             }
         }
 
+        // 额度耗尽的供应商（用量脚本守护）排到队列末尾，仅在其他供应商都不可用时使用
+        if result.len() > 1 {
+            if let Ok(exhausted) = self.db.get_exhausted_provider_ids(app_type) {
+                if !exhausted.is_empty() {
+                    let (available, depleted): (Vec<_>, Vec<_>) =
+                        result.into_iter().partition(|p| !exhausted.contains(&p.id));
+                    result = available.into_iter().chain(depleted).collect();
+                }
+            }
+        }
+
         if result.is_empty() {
             if total_providers > 0 && circuit_open_count == total_providers {
                 log::warn!("[{app_type}] [FO-004] 所有供应商均已熔断");
+                crate::services::notification::notify(
+                    crate::services::notification::NotificationEvent::AllCircuitsOpen {
+                        app_type: app_type.to_string(),
+                    },
+                );
                 return Err(AppError::AllProvidersCircuitOpen);
             } else {
                 log::warn!("[{app_type}] [FO-005] 未配置供应商");
@@ -123,6 +139,8 @@ impl ProviderRouter {
     }
 
     /// 记录供应商请求结果
+    ///
+    /// 返回成功请求重置的熔断器连续失败数（失败时为 0），用于事后判定失败时恢复
     pub async fn record_result(
         &self,
         provider_id: &str,
@@ -130,7 +148,7 @@ impl ProviderRouter {
         used_half_open_permit: bool,
         success: bool,
         error_msg: Option<String>,
-    ) -> Result<(), AppError> {
+    ) -> Result<u32, AppError> {
         // 1. 按应用独立获取熔断器配置
         let failure_threshold = match self.db.get_proxy_config_for_app(app_type).await {
             Ok(app_config) => app_config.circuit_failure_threshold,
@@ -141,11 +159,12 @@ impl ProviderRouter {
         let circuit_key = format!("{app_type}:{provider_id}");
         let breaker = self.get_or_create_circuit_breaker(&circuit_key).await;
 
-        if success {
-            breaker.record_success(used_half_open_permit).await;
+        let cleared_failures = if success {
+            breaker.record_success(used_half_open_permit).await
         } else {
             breaker.record_failure(used_half_open_permit).await;
-        }
+            0
+        };
 
         // 3. 更新数据库健康状态（使用配置的阈值）
         self.db
@@ -158,7 +177,35 @@ impl ProviderRouter {
             )
             .await?;
 
-        Ok(())
+        Ok(cleared_failures)
+    }
+
+    /// 记录延迟判定的失败（请求已按成功记录，如事后检测到上游模型替换）
+    pub async fn record_late_failure(
+        &self,
+        provider_id: &str,
+        app_type: &str,
+        cleared_failures: u32,
+        error_msg: String,
+    ) -> Result<(), AppError> {
+        let failure_threshold = match self.db.get_proxy_config_for_app(app_type).await {
+            Ok(app_config) => app_config.circuit_failure_threshold,
+            Err(_) => 5, // 默认值
+        };
+
+        let circuit_key = format!("{app_type}:{provider_id}");
+        let breaker = self.get_or_create_circuit_breaker(&circuit_key).await;
+        breaker.record_late_failure(cleared_failures).await;
+
+        self.db
+            .update_provider_health_with_threshold(
+                provider_id,
+                app_type,
+                false,
+                Some(error_msg),
+                failure_threshold,
+            )
+            .await
     }
 
     /// 重置熔断器（手动恢复）
@@ -175,6 +222,13 @@ impl ProviderRouter {
         self.reset_circuit_breaker(&circuit_key).await;
     }
 
+    /// 将后台健康探测结果应用到指定供应商的熔断器
+    pub async fn apply_probe_result(&self, provider_id: &str, app_type: &str, healthy: bool) {
+        let circuit_key = format!("{app_type}:{provider_id}");
+        let breaker = self.get_or_create_circuit_breaker(&circuit_key).await;
+        breaker.apply_probe_result(healthy).await;
+    }
+
     /// 仅释放 HalfOpen permit，不影响健康统计（neutral 接口）
     ///
     /// 用于整流器等场景：请求结果不应计入 Provider 健康度，
@@ -286,12 +286,15 @@ fn create_usage_collector(
     let state = state.clone();
     let provider_id = ctx.provider.id.clone();
     let request_model = ctx.request_model.clone();
+    let expected_model = ctx.upstream_model();
     let app_type_str = parser_config.app_type_str;
     let tag = ctx.tag;
     let start_time = ctx.start_time;
     let stream_parser = parser_config.stream_parser;
     let model_extractor = parser_config.model_extractor;
     let session_id = ctx.session_id.clone();
+    let endpoint_url = ctx.endpoint.clone();
+    let cleared_failures = ctx.cleared_failures;
 
     SseUsageCollector::new(start_time, move |events, first_token_ms| {
         if let Some(usage) = stream_parser(&events) {
@@ -302,6 +305,8 @@ fn create_usage_collector(
             let provider_id = provider_id.clone();
             let session_id = session_id.clone();
             let request_model = request_model.clone();
+            let endpoint_url = endpoint_url.clone();
+            let expected_model = expected_model.clone();
 
             tokio::spawn(async move {
                 log_usage_internal(
@@ -316,6 +321,9 @@ fn create_usage_collector(
                     true, // is_streaming
                     status_code,
                     Some(session_id),
+                    endpoint_url,
+                    Some(expected_model),
+                    cleared_failures,
                 )
                 .await;
             });
@@ -326,6 +334,8 @@ fn create_usage_collector(
             let provider_id = provider_id.clone();
             let session_id = session_id.clone();
             let request_model = request_model.clone();
+            let endpoint_url = endpoint_url.clone();
+            let expected_model = expected_model.clone();
 
             tokio::spawn(async move {
                 log_usage_internal(
@@ -340,6 +350,9 @@ fn create_usage_collector(
                     true, // is_streaming
                     status_code,
                     Some(session_id),
+                    endpoint_url,
+                    Some(expected_model),
+                    cleared_failures,
                 )
                 .await;
             });
@@ -365,6 +378,9 @@ fn spawn_log_usage(
     let request_model = request_model.to_string();
     let latency_ms = ctx.latency_ms();
     let session_id = ctx.session_id.clone();
+    let endpoint_url = ctx.endpoint.clone();
+    let cleared_failures = ctx.cleared_failures;
+    let expected_model = ctx.upstream_model();
 
     tokio::spawn(async move {
         log_usage_internal(
@@ -379,6 +395,9 @@ fn spawn_log_usage(
             is_streaming,
             status_code,
             Some(session_id),
+            endpoint_url,
+            Some(expected_model),
+            cleared_failures,
         )
         .await;
     });
@@ -398,10 +417,15 @@ async fn log_usage_internal(
     is_streaming: bool,
     status_code: u16,
     session_id: Option<String>,
+    endpoint_url: Option<String>,
+    expected_model: Option<String>,
+    cleared_failures: u32,
 ) {
     use super::usage::logger::UsageLogger;
 
     let logger = UsageLogger::new(&state.db);
+    let fidelity = state.db.get_model_fidelity_config().unwrap_or_default();
+    let expected_model = expected_model.filter(|_| fidelity.detect);
     let (multiplier, pricing_model_source) =
         logger.resolve_pricing_config(provider_id, app_type).await;
     let pricing_model = if pricing_model_source == "request" {
@@ -421,7 +445,7 @@ async fn log_usage_internal(
         usage.cache_creation_tokens
     );
 
-    if let Err(e) = logger.log_with_calculation(
+    match logger.log_with_calculation(
         request_id,
         provider_id.to_string(),
         app_type.to_string(),
@@ -436,8 +460,49 @@ async fn log_usage_internal(
         session_id,
         None, // provider_type
         is_streaming,
+        endpoint_url,
+        expected_model.clone(),
     ) {
-        log::warn!("[USG-001] 记录使用量失败: {e}");
+        Ok(true) => {
+            report_model_substitution(
+                state,
+                provider_id,
+                app_type,
+                expected_model.as_deref().unwrap_or_default(),
+                model,
+                fidelity.treat_as_failure,
+                cleared_failures,
+            )
+            .await
+        }
+        Ok(false) => {}
+        Err(e) => log::warn!("[USG-001] 记录使用量失败: {e}"),
+    }
+}
+
+/// 上游返回模型与期望不一致：记录告警，按配置计入熔断器失败
+pub(super) async fn report_model_substitution(
+    state: &ProxyState,
+    provider_id: &str,
+    app_type: &str,
+    expected_model: &str,
+    returned_model: &str,
+    treat_as_failure: bool,
+    cleared_failures: u32,
+) {
+    log::warn!(
+        "[{app_type}] 检测到上游模型替换: provider={provider_id}, expected={expected_model}, returned={returned_model}"
+    );
+    if !treat_as_failure {
+        return;
+    }
+    let message = format!("模型替换: 期望 {expected_model}，实际返回 {returned_model}");
+    if let Err(e) = state
+        .provider_router
+        .record_late_failure(provider_id, app_type, cleared_failures, message)
+        .await
+    {
+        log::warn!("[{app_type}] 记录模型替换失败状态失败: {e}");
     }
 }
 
@@ -570,6 +635,7 @@ mod tests {
     use crate::proxy::failover_switch::FailoverSwitchManager;
     use crate::proxy::provider_router::ProviderRouter;
     use crate::proxy::types::{ProxyConfig, ProxyStatus};
+    use crate::services::model_fidelity::ModelFidelityConfig;
     use rust_decimal::Decimal;
     use std::collections::HashMap;
     use std::str::FromStr;
@@ -644,6 +710,7 @@ mod tests {
             output_tokens: 0,
             cache_read_tokens: 0,
             cache_creation_tokens: 0,
+            cache_creation_1h_tokens: 0,
             model: None,
         };
 
@@ -659,6 +726,9 @@ mod tests {
             false,
             200,
             None,
+            None,
+            None,
+            0,
         )
         .await;
 
@@ -685,6 +755,50 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_model_substitution_counts_as_failure_when_enabled() -> Result<(), AppError> {
+        let db = Arc::new(Database::memory()?);
+        insert_provider(&db, "provider-3", "claude", ProviderMeta::default())?;
+        db.save_model_fidelity_config(&ModelFidelityConfig {
+            treat_as_failure: true,
+            ..Default::default()
+        })?;
+
+        let state = build_state(db.clone());
+        log_usage_internal(
+            &state,
+            "provider-3",
+            "claude",
+            "resp-model",
+            "req-model",
+            TokenUsage::default(),
+            10,
+            None,
+            false,
+            200,
+            None,
+            None,
+            Some("mapped-model".to_string()),
+            0,
+        )
+        .await;
+
+        let conn = crate::database::lock_conn!(db.conn);
+        let (mismatch, failures): (bool, i64) = conn
+            .query_row(
+                "SELECT l.model_mismatch, h.consecutive_failures
+                 FROM proxy_request_logs l
+                 JOIN provider_health h ON h.provider_id = l.provider_id AND h.app_type = l.app_type
+                 WHERE l.provider_id = ?1",
+                ["provider-3"],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|e| AppError::Database(e.to_string()))?;
+        assert!(mismatch);
+        assert_eq!(failures, 1);
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_log_usage_falls_back_to_global_defaults() -> Result<(), AppError> {
         let db = Arc::new(Database::memory()?);
@@ -703,6 +817,7 @@ mod tests {
             output_tokens: 0,
             cache_read_tokens: 0,
             cache_creation_tokens: 0,
+            cache_creation_1h_tokens: 0,
             model: None,
         };
 
@@ -718,6 +833,9 @@ mod tests {
             false,
             200,
             None,
+            None,
+            None,
+            0,
         )
         .await;
 
@@ -268,4 +268,12 @@ impl ProxyServer {
             .reset_provider_breaker(provider_id, app_type)
             .await;
     }
+
+    /// 应用后台健康探测结果到指定 Provider 的熔断器
+    pub async fn apply_probe_result(&self, provider_id: &str, app_type: &str, healthy: bool) {
+        self.state
+            .provider_router
+            .apply_probe_result(provider_id, app_type, healthy)
+            .await;
+    }
 }
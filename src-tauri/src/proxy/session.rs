@@ -258,6 +258,26 @@ pub fn extract_session_id(
     generate_new_session_id()
 }
 
+/// 代理为 Codex 会话 ID 添加的前缀
+pub const CODEX_SESSION_PREFIX: &str = "codex_";
+
+/// 还原 CLI 会话文件中的原始会话 ID（去掉代理添加的前缀）
+pub fn cli_session_id(session_id: &str) -> &str {
+    session_id
+        .strip_prefix(CODEX_SESSION_PREFIX)
+        .unwrap_or(session_id)
+}
+
+/// [`cli_session_id`] 的 SQL 表达式，用于按原始会话 ID 关联请求日志
+pub fn cli_session_id_sql(column: &str) -> String {
+    let len = CODEX_SESSION_PREFIX.len();
+    format!(
+        "CASE WHEN substr({column}, 1, {len}) = '{CODEX_SESSION_PREFIX}' \
+         THEN substr({column}, {}) ELSE {column} END",
+        len + 1
+    )
+}
+
 /// 提取 Codex Session ID
 fn extract_codex_session(headers: &HeaderMap, body: &serde_json::Value) -> Option<SessionIdResult> {
     // 1. 从 headers 提取
@@ -267,7 +287,7 @@ fn extract_codex_session(headers: &HeaderMap, body: &serde_json::Value) -> Optio
                 // Codex Session ID 通常较长（UUID 格式）
                 if session_id.len() > 20 {
                     return Some(SessionIdResult {
-                        session_id: format!("codex_{session_id}"),
+                        session_id: format!("{CODEX_SESSION_PREFIX}{session_id}"),
                         source: SessionIdSource::Header,
                         client_provided: true,
                     });
@@ -284,7 +304,7 @@ fn extract_codex_session(headers: &HeaderMap, body: &serde_json::Value) -> Optio
     {
         if session_id.len() > 10 {
             return Some(SessionIdResult {
-                session_id: format!("codex_{session_id}"),
+                session_id: format!("{CODEX_SESSION_PREFIX}{session_id}"),
                 source: SessionIdSource::MetadataSessionId,
                 client_provided: true,
             });
@@ -295,7 +315,7 @@ fn extract_codex_session(headers: &HeaderMap, body: &serde_json::Value) -> Optio
     if let Some(prev_id) = body.get("previous_response_id").and_then(|v| v.as_str()) {
         if prev_id.len() > 10 {
             return Some(SessionIdResult {
-                session_id: format!("codex_{prev_id}"),
+                session_id: format!("{CODEX_SESSION_PREFIX}{prev_id}"),
                 source: SessionIdSource::PreviousResponseId,
                 client_provided: true,
             });
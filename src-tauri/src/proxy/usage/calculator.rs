@@ -4,6 +4,7 @@
 
 use super::parser::TokenUsage;
 use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 
 /// 成本明细
@@ -22,7 +23,55 @@ pub struct ModelPricing {
     pub input_cost_per_million: Decimal,
     pub output_cost_per_million: Decimal,
     pub cache_read_cost_per_million: Decimal,
+    /// 缓存写入价格（5 分钟 TTL）
     pub cache_creation_cost_per_million: Decimal,
+    /// 1 小时 TTL 缓存写入价格，未配置时按 5 分钟价格计费
+    pub cache_creation_1h_cost_per_million: Option<Decimal>,
+    /// 长上下文阶梯价格，按阈值升序排列
+    pub tiers: Vec<PricingTier>,
+}
+
+/// 长上下文阶梯：提示词 token 数超过阈值后，整个请求按该档价格计费
+#[derive(Debug, Clone)]
+pub struct PricingTier {
+    pub above_input_tokens: u64,
+    pub pricing: ModelPricing,
+}
+
+/// 阶梯定价的存储格式（model_pricing.tiers 列中的 JSON 数组元素）
+///
+/// 缓存相关价格缺省时沿用基础档价格
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingTierConfig {
+    pub above_input_tokens: u64,
+    pub input_cost_per_million: String,
+    pub output_cost_per_million: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_read_cost_per_million: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_creation_cost_per_million: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_creation_1h_cost_per_million: Option<String>,
+}
+
+/// Token 用量口径：input_tokens 是否已包含缓存命中
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageDialect {
+    /// Claude：input_tokens 不含缓存读取与缓存写入
+    Claude,
+    /// OpenAI / Codex / Gemini：input_tokens 已包含缓存命中
+    OpenAi,
+}
+
+impl UsageDialect {
+    /// 按应用类型确定用量口径
+    pub fn for_app_type(app_type: &str) -> Self {
+        match app_type {
+            "claude" => Self::Claude,
+            _ => Self::OpenAi,
+        }
+    }
 }
 
 /// 成本计算器
@@ -35,21 +84,29 @@ impl CostCalculator {
     /// - `usage`: Token 使用量
     /// - `pricing`: 模型定价
     /// - `cost_multiplier`: 成本倍数 (provider 自定义)
+    /// - `dialect`: 用量口径，决定提示词 token 数的统计方式
     ///
     /// # 计算逻辑
-    /// - input_cost: (input_tokens - cache_read_tokens) × 输入价格
+    /// - 先按提示词 token 数选择阶梯价格，见 [`Self::prompt_tokens`]
+    /// - input_cost: 非缓存输入 token × 输入价格；OpenAI 口径需先减去 cache_read_tokens，
+    ///   避免缓存部分被重复计费，Claude 口径的 input_tokens 本就不含缓存
     /// - cache_read_cost: cache_read_tokens × 缓存读取价格
-    /// - 这样避免缓存部分被重复计费
+    /// - cache_creation_cost: 5 分钟与 1 小时 TTL 的缓存写入分别计价
     /// - total_cost: 各项成本之和 × 倍率（倍率只作用于最终总价）
     pub fn calculate(
         usage: &TokenUsage,
         pricing: &ModelPricing,
         cost_multiplier: Decimal,
+        dialect: UsageDialect,
     ) -> CostBreakdown {
         let million = Decimal::from(1_000_000);
+        let pricing = pricing.tier_for(Self::prompt_tokens(usage, dialect));
 
-        // 计算实际需要按输入价格计费的 token 数（减去缓存命中部分）
-        let billable_input_tokens = usage.input_tokens.saturating_sub(usage.cache_read_tokens);
+        // 计算实际需要按输入价格计费的 token 数（不含缓存命中部分）
+        let billable_input_tokens = match dialect {
+            UsageDialect::Claude => usage.input_tokens,
+            UsageDialect::OpenAi => usage.input_tokens.saturating_sub(usage.cache_read_tokens),
+        };
 
         // 各项基础成本（不含倍率）
         let input_cost =
@@ -58,8 +115,16 @@ impl CostCalculator {
             Decimal::from(usage.output_tokens) * pricing.output_cost_per_million / million;
         let cache_read_cost =
             Decimal::from(usage.cache_read_tokens) * pricing.cache_read_cost_per_million / million;
-        let cache_creation_cost = Decimal::from(usage.cache_creation_tokens)
+        let cache_creation_1h_tokens = usage
+            .cache_creation_1h_tokens
+            .min(usage.cache_creation_tokens);
+        let cache_creation_5m_tokens = usage.cache_creation_tokens - cache_creation_1h_tokens;
+        let cache_creation_1h_price = pricing
+            .cache_creation_1h_cost_per_million
+            .unwrap_or(pricing.cache_creation_cost_per_million);
+        let cache_creation_cost = (Decimal::from(cache_creation_5m_tokens)
             * pricing.cache_creation_cost_per_million
+            + Decimal::from(cache_creation_1h_tokens) * cache_creation_1h_price)
             / million;
 
         // 总成本 = 各项基础成本之和 × 倍率
@@ -75,13 +140,26 @@ impl CostCalculator {
         }
     }
 
+    /// 提示词总 token 数，用于选择长上下文阶梯
+    ///
+    /// Claude 口径的 input_tokens 不含缓存，需要加上缓存读取；
+    /// OpenAI 口径的 input_tokens 已包含缓存命中，只加缓存写入
+    pub fn prompt_tokens(usage: &TokenUsage, dialect: UsageDialect) -> u64 {
+        let cache_read = match dialect {
+            UsageDialect::Claude => u64::from(usage.cache_read_tokens),
+            UsageDialect::OpenAi => 0,
+        };
+        u64::from(usage.input_tokens) + cache_read + u64::from(usage.cache_creation_tokens)
+    }
+
     /// 尝试计算成本，如果模型未知则返回 None
     pub fn try_calculate(
         usage: &TokenUsage,
         pricing: Option<&ModelPricing>,
         cost_multiplier: Decimal,
+        dialect: UsageDialect,
     ) -> Option<CostBreakdown> {
-        pricing.map(|p| Self::calculate(usage, p, cost_multiplier))
+        pricing.map(|p| Self::calculate(usage, p, cost_multiplier, dialect))
     }
 }
 
@@ -98,8 +176,70 @@ impl ModelPricing {
             output_cost_per_million: Decimal::from_str(output)?,
             cache_read_cost_per_million: Decimal::from_str(cache_read)?,
             cache_creation_cost_per_million: Decimal::from_str(cache_creation)?,
+            cache_creation_1h_cost_per_million: None,
+            tiers: Vec::new(),
         })
     }
+
+    /// 附加 1 小时缓存写入价格与阶梯定价（均为数据库中的可选列）
+    pub fn with_extras(
+        mut self,
+        cache_creation_1h: Option<&str>,
+        tiers: &[PricingTierConfig],
+    ) -> Result<Self, rust_decimal::Error> {
+        self.cache_creation_1h_cost_per_million = cache_creation_1h
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(Decimal::from_str)
+            .transpose()?;
+
+        let mut parsed = Vec::with_capacity(tiers.len());
+        for tier in tiers {
+            let parse_or = |value: &Option<String>, fallback: Decimal| {
+                value
+                    .as_deref()
+                    .map(Decimal::from_str)
+                    .transpose()
+                    .map(|v| v.unwrap_or(fallback))
+            };
+            let cache_creation = parse_or(
+                &tier.cache_creation_cost_per_million,
+                self.cache_creation_cost_per_million,
+            )?;
+            parsed.push(PricingTier {
+                above_input_tokens: tier.above_input_tokens,
+                pricing: ModelPricing {
+                    input_cost_per_million: Decimal::from_str(&tier.input_cost_per_million)?,
+                    output_cost_per_million: Decimal::from_str(&tier.output_cost_per_million)?,
+                    cache_read_cost_per_million: parse_or(
+                        &tier.cache_read_cost_per_million,
+                        self.cache_read_cost_per_million,
+                    )?,
+                    cache_creation_cost_per_million: cache_creation,
+                    cache_creation_1h_cost_per_million: match &tier
+                        .cache_creation_1h_cost_per_million
+                    {
+                        Some(value) => Some(Decimal::from_str(value)?),
+                        None => self.cache_creation_1h_cost_per_million,
+                    },
+                    tiers: Vec::new(),
+                },
+            });
+        }
+        parsed.sort_by_key(|tier| tier.above_input_tokens);
+        self.tiers = parsed;
+        Ok(self)
+    }
+
+    /// 选出适用于给定提示词 token 数的价格档位
+    pub fn tier_for(&self, prompt_tokens: u64) -> &ModelPricing {
+        self.tiers
+            .iter()
+            .rev()
+            .find(|tier| prompt_tokens > tier.above_input_tokens)
+            .map(|tier| &tier.pricing)
+            .unwrap_or(self)
+    }
 }
 
 #[cfg(test)]
@@ -113,13 +253,14 @@ mod tests {
             output_tokens: 500,
             cache_read_tokens: 200,
             cache_creation_tokens: 100,
+            cache_creation_1h_tokens: 0,
             model: None,
         };
 
         let pricing = ModelPricing::from_strings("3.0", "15.0", "0.3", "3.75").unwrap();
         let multiplier = Decimal::from_str("1.0").unwrap();
 
-        let cost = CostCalculator::calculate(&usage, &pricing, multiplier);
+        let cost = CostCalculator::calculate(&usage, &pricing, multiplier, UsageDialect::OpenAi);
 
         // input: (1000 - 200) * 3.0 / 1M = 0.0024 (只计算非缓存部分)
         assert_eq!(cost.input_cost, Decimal::from_str("0.0024").unwrap());
@@ -143,13 +284,14 @@ mod tests {
             output_tokens: 0,
             cache_read_tokens: 0,
             cache_creation_tokens: 0,
+            cache_creation_1h_tokens: 0,
             model: None,
         };
 
         let pricing = ModelPricing::from_strings("3.0", "15.0", "0", "0").unwrap();
         let multiplier = Decimal::from_str("1.5").unwrap();
 
-        let cost = CostCalculator::calculate(&usage, &pricing, multiplier);
+        let cost = CostCalculator::calculate(&usage, &pricing, multiplier, UsageDialect::OpenAi);
 
         // input_cost: 基础价格（不含倍率）= 1000 * 3.0 / 1M = 0.003
         assert_eq!(cost.input_cost, Decimal::from_str("0.003").unwrap());
@@ -164,11 +306,12 @@ mod tests {
             output_tokens: 500,
             cache_read_tokens: 0,
             cache_creation_tokens: 0,
+            cache_creation_1h_tokens: 0,
             model: None,
         };
 
         let multiplier = Decimal::from_str("1.0").unwrap();
-        let cost = CostCalculator::try_calculate(&usage, None, multiplier);
+        let cost = CostCalculator::try_calculate(&usage, None, multiplier, UsageDialect::OpenAi);
 
         assert!(cost.is_none());
     }
@@ -180,16 +323,121 @@ mod tests {
             output_tokens: 1,
             cache_read_tokens: 1,
             cache_creation_tokens: 1,
+            cache_creation_1h_tokens: 0,
             model: None,
         };
 
         let pricing = ModelPricing::from_strings("0.075", "0.3", "0.01875", "0.075").unwrap();
         let multiplier = Decimal::from_str("1.0").unwrap();
 
-        let cost = CostCalculator::calculate(&usage, &pricing, multiplier);
+        let cost = CostCalculator::calculate(&usage, &pricing, multiplier, UsageDialect::OpenAi);
 
         // 验证高精度计算
         assert!(cost.total_cost > Decimal::ZERO);
         assert!(cost.total_cost.to_string().len() > 2); // 确保保留了小数位
     }
+
+    fn long_context_pricing() -> ModelPricing {
+        ModelPricing::from_strings("3", "15", "0.3", "3.75")
+            .unwrap()
+            .with_extras(
+                Some("6"),
+                &[PricingTierConfig {
+                    above_input_tokens: 200_000,
+                    input_cost_per_million: "6".to_string(),
+                    output_cost_per_million: "22.5".to_string(),
+                    cache_read_cost_per_million: Some("0.6".to_string()),
+                    cache_creation_cost_per_million: Some("7.5".to_string()),
+                    cache_creation_1h_cost_per_million: Some("12".to_string()),
+                }],
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_long_context_tier_applies_to_whole_request() {
+        let pricing = long_context_pricing();
+        let multiplier = Decimal::ONE;
+
+        let below = TokenUsage {
+            input_tokens: 200_000,
+            output_tokens: 1_000,
+            ..Default::default()
+        };
+        let cost = CostCalculator::calculate(&below, &pricing, multiplier, UsageDialect::OpenAi);
+        // 恰好等于阈值时仍按基础档：200k * 3 / 1M = 0.6
+        assert_eq!(cost.input_cost, Decimal::from_str("0.6").unwrap());
+        assert_eq!(cost.output_cost, Decimal::from_str("0.015").unwrap());
+
+        let above = TokenUsage {
+            input_tokens: 150_000,
+            output_tokens: 1_000,
+            cache_creation_tokens: 60_000,
+            ..Default::default()
+        };
+        let cost = CostCalculator::calculate(&above, &pricing, multiplier, UsageDialect::OpenAi);
+        // input + cache_creation = 210k > 200k，全部按长上下文档计费
+        assert_eq!(cost.input_cost, Decimal::from_str("0.9").unwrap());
+        assert_eq!(cost.output_cost, Decimal::from_str("0.0225").unwrap());
+        assert_eq!(cost.cache_creation_cost, Decimal::from_str("0.45").unwrap());
+    }
+
+    #[test]
+    fn test_claude_cache_reads_count_towards_long_context_tier() {
+        let pricing = long_context_pricing();
+        // Claude 口径：input_tokens 不含缓存，仅缓存读取就使提示词超过 200k
+        let usage = TokenUsage {
+            input_tokens: 5_000,
+            output_tokens: 1_000,
+            cache_read_tokens: 190_000,
+            cache_creation_tokens: 10_000,
+            ..Default::default()
+        };
+        assert_eq!(
+            CostCalculator::prompt_tokens(&usage, UsageDialect::Claude),
+            205_000
+        );
+        let cost = CostCalculator::calculate(&usage, &pricing, Decimal::ONE, UsageDialect::Claude);
+        // input_tokens 本身不含缓存，全部按长上下文输入价计费：5k * 6 / 1M = 0.03
+        assert_eq!(cost.input_cost, Decimal::from_str("0.03").unwrap());
+        // 190k * 0.6 / 1M = 0.114
+        assert_eq!(cost.cache_read_cost, Decimal::from_str("0.114").unwrap());
+        assert_eq!(cost.output_cost, Decimal::from_str("0.0225").unwrap());
+
+        // 同样的数字按 OpenAI 口径（input 已含缓存）则未超过阈值
+        assert_eq!(
+            CostCalculator::prompt_tokens(&usage, UsageDialect::OpenAi),
+            15_000
+        );
+        let cost = CostCalculator::calculate(&usage, &pricing, Decimal::ONE, UsageDialect::OpenAi);
+        // 缓存读取超过 input_tokens 时不产生负的输入成本
+        assert_eq!(cost.input_cost, Decimal::ZERO);
+        assert_eq!(cost.cache_read_cost, Decimal::from_str("0.057").unwrap());
+    }
+
+    #[test]
+    fn test_cache_creation_ttl_rates() {
+        let pricing = long_context_pricing();
+        let usage = TokenUsage {
+            input_tokens: 1_000,
+            cache_creation_tokens: 3_000,
+            cache_creation_1h_tokens: 1_000,
+            ..Default::default()
+        };
+
+        let cost = CostCalculator::calculate(&usage, &pricing, Decimal::ONE, UsageDialect::OpenAi);
+        // 5m: 2000 * 3.75 / 1M = 0.0075；1h: 1000 * 6 / 1M = 0.006
+        assert_eq!(
+            cost.cache_creation_cost,
+            Decimal::from_str("0.0135").unwrap()
+        );
+
+        // 未配置 1h 价格时回退到 5m 价格
+        let flat = ModelPricing::from_strings("3", "15", "0.3", "3.75").unwrap();
+        let cost = CostCalculator::calculate(&usage, &flat, Decimal::ONE, UsageDialect::OpenAi);
+        assert_eq!(
+            cost.cache_creation_cost,
+            Decimal::from_str("0.01125").unwrap()
+        );
+    }
 }
@@ -1,10 +1,12 @@
 //! Usage Logger - 记录 API 请求使用情况
 
-use super::calculator::{CostBreakdown, CostCalculator, ModelPricing};
+use super::calculator::{CostBreakdown, CostCalculator, ModelPricing, UsageDialect};
 use super::parser::TokenUsage;
 use crate::database::Database;
 use crate::error::AppError;
-use crate::services::usage_stats::find_model_pricing_row;
+use crate::services::currency::{load_billing_profile, settle, Settlement, BASE_CURRENCY};
+use crate::services::model_fidelity::is_substitution;
+use crate::services::pricing::find_model_pricing_at;
 use rust_decimal::Decimal;
 use std::{str::FromStr, time::SystemTime};
 
@@ -29,6 +31,12 @@ pub struct RequestLog {
     pub is_streaming: bool,
     /// 成本倍数
     pub cost_multiplier: String,
+    /// 实际提供服务的端点（base URL）
+    pub endpoint_url: Option<String>,
+    /// 映射后发往上游的模型（未启用替换检测时为空）
+    pub expected_model: Option<String>,
+    /// 上游返回模型与期望模型不一致
+    pub model_mismatch: bool,
 }
 
 /// 使用量记录器
@@ -45,14 +53,21 @@ impl<'a> UsageLogger<'a> {
     pub fn log_request(&self, log: &RequestLog) -> Result<(), AppError> {
         let conn = crate::database::lock_conn!(self.db.conn);
 
-        let (input_cost, output_cost, cache_read_cost, cache_creation_cost, total_cost) =
+        let created_at = now_secs();
+
+        // 按供应商计费币种结算：total_cost_usd 保存折算后的 USD，billing_cost 保存计费币种金额
+        let (input_cost, output_cost, cache_read_cost, cache_creation_cost, settlement) =
             if let Some(cost) = &log.cost {
+                let profile = load_billing_profile(&conn, &log.provider_id, &log.app_type)?;
+                let settlement = settle(&conn, cost.total_cost, &profile, created_at)?;
+                let scale =
+                    |component| settlement.scale_usd(cost.total_cost, component).to_string();
                 (
-                    cost.input_cost.to_string(),
-                    cost.output_cost.to_string(),
-                    cost.cache_read_cost.to_string(),
-                    cost.cache_creation_cost.to_string(),
-                    cost.total_cost.to_string(),
+                    scale(cost.input_cost),
+                    scale(cost.output_cost),
+                    scale(cost.cache_read_cost),
+                    scale(cost.cache_creation_cost),
+                    settlement,
                 )
             } else {
                 (
@@ -60,17 +75,14 @@ impl<'a> UsageLogger<'a> {
                     "0".to_string(),
                     "0".to_string(),
                     "0".to_string(),
-                    "0".to_string(),
+                    Settlement {
+                        currency: BASE_CURRENCY.to_string(),
+                        billing_cost: Decimal::ZERO,
+                        total_usd: Decimal::ZERO,
+                    },
                 )
             };
-
-        let created_at = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .map(|d| d.as_secs() as i64)
-            .unwrap_or_else(|e| {
-                log::warn!("SystemTime is before UNIX_EPOCH, falling back to 0: {e}");
-                0
-            });
+        let total_cost = settlement.total_usd.to_string();
 
         conn.execute(
             "INSERT INTO proxy_request_logs (
@@ -78,8 +90,9 @@ impl<'a> UsageLogger<'a> {
                 input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens,
                 input_cost_usd, output_cost_usd, cache_read_cost_usd, cache_creation_cost_usd, total_cost_usd,
                 latency_ms, first_token_ms, status_code, error_message, session_id,
-                provider_type, is_streaming, cost_multiplier, created_at
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)",
+                provider_type, is_streaming, cost_multiplier, created_at, endpoint_url,
+                cache_creation_1h_tokens, billing_currency, billing_cost, expected_model, model_mismatch
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28, ?29)",
             rusqlite::params![
                 log.request_id,
                 log.provider_id,
@@ -104,6 +117,12 @@ impl<'a> UsageLogger<'a> {
                 log.is_streaming as i64,
                 log.cost_multiplier,
                 created_at,
+                log.endpoint_url,
+                log.usage.cache_creation_1h_tokens,
+                settlement.currency,
+                settlement.billing_cost.to_string(),
+                log.expected_model,
+                log.model_mismatch,
             ],
         )
         .map_err(|e| AppError::Database(format!("记录请求日志失败: {e}")))?;
@@ -142,6 +161,9 @@ impl<'a> UsageLogger<'a> {
             provider_type: None,
             is_streaming: false,
             cost_multiplier: "1.0".to_string(),
+            endpoint_url: None,
+            expected_model: None,
+            model_mismatch: false,
         };
 
         self.log_request(&log)
@@ -163,6 +185,7 @@ impl<'a> UsageLogger<'a> {
         is_streaming: bool,
         session_id: Option<String>,
         provider_type: Option<String>,
+        endpoint_url: Option<String>,
     ) -> Result<(), AppError> {
         let request_model = model.clone();
         let log = RequestLog {
@@ -181,23 +204,22 @@ impl<'a> UsageLogger<'a> {
             provider_type,
             is_streaming,
             cost_multiplier: "1.0".to_string(),
+            endpoint_url,
+            expected_model: None,
+            model_mismatch: false,
         };
 
         self.log_request(&log)
     }
 
-    /// 获取模型定价
-    pub fn get_model_pricing(&self, model_id: &str) -> Result<Option<ModelPricing>, AppError> {
+    /// 获取模型在指定时间（秒）生效的定价
+    pub fn get_model_pricing_at(
+        &self,
+        model_id: &str,
+        timestamp: i64,
+    ) -> Result<Option<ModelPricing>, AppError> {
         let conn = crate::database::lock_conn!(self.db.conn);
-        let row = find_model_pricing_row(&conn, model_id)?;
-        match row {
-            Some((input, output, cache_read, cache_creation)) => {
-                ModelPricing::from_strings(&input, &output, &cache_read, &cache_creation)
-                    .map(Some)
-                    .map_err(|e| AppError::Database(format!("解析定价数据失败: {e}")))
-            }
-            None => Ok(None),
-        }
+        find_model_pricing_at(&conn, model_id, timestamp)
     }
 
     /// 获取有效的倍率与计费模式来源（供应商优先，未配置则回退全局默认）
@@ -283,6 +305,8 @@ impl<'a> UsageLogger<'a> {
     }
 
     /// 计算并记录请求
+    ///
+    /// 传入 `expected_model` 时比对上游返回模型，返回是否检测到模型替换
     #[allow(clippy::too_many_arguments)]
     pub fn log_with_calculation(
         &self,
@@ -300,14 +324,26 @@ impl<'a> UsageLogger<'a> {
         session_id: Option<String>,
         provider_type: Option<String>,
         is_streaming: bool,
-    ) -> Result<(), AppError> {
-        let pricing = self.get_model_pricing(&pricing_model)?;
+        endpoint_url: Option<String>,
+        expected_model: Option<String>,
+    ) -> Result<bool, AppError> {
+        let pricing = self.get_model_pricing_at(&pricing_model, now_secs())?;
 
         if pricing.is_none() {
             log::warn!("[USG-002] 模型定价未找到，成本将记录为 0: {pricing_model}");
         }
 
-        let cost = CostCalculator::try_calculate(&usage, pricing.as_ref(), cost_multiplier);
+        let cost = CostCalculator::try_calculate(
+            &usage,
+            pricing.as_ref(),
+            cost_multiplier,
+            UsageDialect::for_app_type(&app_type),
+        );
+
+        // 响应未声明模型时 model 会回退为请求模型，此时无法判断是否被替换
+        let model_mismatch = expected_model
+            .as_deref()
+            .is_some_and(|expected| model != request_model && is_substitution(expected, &model));
 
         let log = RequestLog {
             request_id,
@@ -325,12 +361,26 @@ impl<'a> UsageLogger<'a> {
             provider_type,
             is_streaming,
             cost_multiplier: cost_multiplier.to_string(),
+            endpoint_url,
+            expected_model,
+            model_mismatch,
         };
 
-        self.log_request(&log)
+        self.log_request(&log)?;
+        Ok(model_mismatch)
     }
 }
 
+fn now_secs() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or_else(|e| {
+            log::warn!("SystemTime is before UNIX_EPOCH, falling back to 0: {e}");
+            0
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -357,6 +407,7 @@ mod tests {
             output_tokens: 500,
             cache_read_tokens: 0,
             cache_creation_tokens: 0,
+            cache_creation_1h_tokens: 0,
             model: None,
         };
 
@@ -375,19 +426,65 @@ mod tests {
             None,
             Some("claude".to_string()),
             false,
+            Some("https://mirror.example.com".to_string()),
+            None,
         )?;
 
         // 验证记录已插入
         let conn = crate::database::lock_conn!(db.conn);
-        let (count, request_model): (i64, String) = conn
+        let (count, request_model, endpoint_url): (i64, String, Option<String>) = conn
             .query_row(
-                "SELECT COUNT(*), request_model FROM proxy_request_logs WHERE request_id = 'req-123'",
+                "SELECT COUNT(*), request_model, endpoint_url FROM proxy_request_logs WHERE request_id = 'req-123'",
                 [],
-                |row| Ok((row.get(0)?, row.get(1)?)),
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
             )
             .unwrap();
         assert_eq!(count, 1);
         assert_eq!(request_model, "req-model");
+        assert_eq!(endpoint_url.as_deref(), Some("https://mirror.example.com"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_log_flags_model_substitution() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let logger = UsageLogger::new(&db);
+        let log = |request_id: &str, model: &str| {
+            logger.log_with_calculation(
+                request_id.to_string(),
+                "provider-1".to_string(),
+                "claude".to_string(),
+                model.to_string(),
+                "claude-opus-4-1".to_string(),
+                model.to_string(),
+                TokenUsage::default(),
+                Decimal::from(1),
+                100,
+                None,
+                200,
+                None,
+                None,
+                false,
+                None,
+                Some("glm-4.6".to_string()),
+            )
+        };
+
+        assert!(!log("req-same", "glm-4.6")?);
+        assert!(log("req-swapped", "glm-4.5-air")?);
+        // 响应未声明模型时回退为请求模型，不判定为替换
+        assert!(!log("req-fallback", "claude-opus-4-1")?);
+
+        let conn = crate::database::lock_conn!(db.conn);
+        let (mismatches, expected): (i64, Option<String>) = conn
+            .query_row(
+                "SELECT SUM(model_mismatch), MAX(expected_model) FROM proxy_request_logs",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(mismatches, 1);
+        assert_eq!(expected.as_deref(), Some("glm-4.6"));
         Ok(())
     }
 
@@ -16,6 +16,9 @@ pub struct TokenUsage {
     pub output_tokens: u32,
     pub cache_read_tokens: u32,
     pub cache_creation_tokens: u32,
+    /// 缓存写入中使用 1 小时 TTL 的部分（已包含在 cache_creation_tokens 内）
+    #[serde(default)]
+    pub cache_creation_1h_tokens: u32,
     /// 从响应中提取的实际模型名称（如果可用）
     pub model: Option<String>,
 }
@@ -51,6 +54,7 @@ impl TokenUsage {
                 .get("cache_creation_input_tokens")
                 .and_then(|v| v.as_u64())
                 .unwrap_or(0) as u32,
+            cache_creation_1h_tokens: claude_cache_creation_1h(usage),
             model,
         })
     }
@@ -90,6 +94,7 @@ impl TokenUsage {
                                 .and_then(|v| v.as_u64())
                                 .unwrap_or(0)
                                 as u32;
+                            usage.cache_creation_1h_tokens = claude_cache_creation_1h(msg_usage);
                         }
                     }
                     "message_delta" => {
@@ -133,6 +138,7 @@ impl TokenUsage {
             output_tokens: usage.get("completion_tokens")?.as_u64()? as u32,
             cache_read_tokens: 0,
             cache_creation_tokens: 0,
+            cache_creation_1h_tokens: 0,
             model: None,
         })
     }
@@ -182,6 +188,7 @@ impl TokenUsage {
                 .get("cache_creation_input_tokens")
                 .and_then(|v| v.as_u64())
                 .unwrap_or(0) as u32,
+            cache_creation_1h_tokens: 0,
             model,
         })
     }
@@ -225,6 +232,7 @@ impl TokenUsage {
                 .get("cache_creation_input_tokens")
                 .and_then(|v| v.as_u64())
                 .unwrap_or(0) as u32,
+            cache_creation_1h_tokens: 0,
             model,
         })
     }
@@ -319,6 +327,7 @@ impl TokenUsage {
             output_tokens: completion_tokens as u32,
             cache_read_tokens: cached_tokens,
             cache_creation_tokens: 0,
+            cache_creation_1h_tokens: 0,
             model,
         })
     }
@@ -363,6 +372,7 @@ impl TokenUsage {
                 .and_then(|v| v.as_u64())
                 .unwrap_or(0) as u32,
             cache_creation_tokens: 0,
+            cache_creation_1h_tokens: 0,
             model,
         })
     }
@@ -413,6 +423,7 @@ impl TokenUsage {
                 output_tokens: total_output,
                 cache_read_tokens: total_cache_read,
                 cache_creation_tokens: 0,
+                cache_creation_1h_tokens: 0,
                 model,
             })
         } else {
@@ -421,6 +432,15 @@ impl TokenUsage {
     }
 }
 
+/// 读取 Claude usage.cache_creation.ephemeral_1h_input_tokens
+fn claude_cache_creation_1h(usage: &Value) -> u32 {
+    usage
+        .get("cache_creation")
+        .and_then(|c| c.get("ephemeral_1h_input_tokens"))
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as u32
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -465,6 +485,25 @@ mod tests {
         assert_eq!(usage.model, None);
     }
 
+    #[test]
+    fn test_claude_response_parsing_cache_ttl() {
+        let response = json!({
+            "usage": {
+                "input_tokens": 100,
+                "output_tokens": 50,
+                "cache_creation_input_tokens": 30,
+                "cache_creation": {
+                    "ephemeral_5m_input_tokens": 10,
+                    "ephemeral_1h_input_tokens": 20
+                }
+            }
+        });
+
+        let usage = TokenUsage::from_claude_response(&response).unwrap();
+        assert_eq!(usage.cache_creation_tokens, 30);
+        assert_eq!(usage.cache_creation_1h_tokens, 20);
+    }
+
     #[test]
     fn test_claude_stream_parsing() {
         let events = vec![
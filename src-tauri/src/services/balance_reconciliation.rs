@@ -0,0 +1,422 @@
+//! 供应商余额对账
+//!
+//! 用量脚本返回的是中转站自己记录的余额（`used` / `remaining`），请求日志中是按模型定价计算的成本。
+//! - 每次查询用量脚本时保存余额快照，共用的用量轮询（`usage_poller`）按快照间隔补充查询
+//! - 对账报告把相邻快照间的余额消耗与同一区间内的计算成本比较，给出实际倍率
+//! - 消耗明显高于计算成本说明中转可能多扣费；明显低于则可能替换了更便宜的模型或价格配置偏高
+//!
+//! 计算成本来自原始请求日志，超出日志保留期的区间无法对账。
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+
+use crate::database::Database;
+use crate::error::AppError;
+use crate::provider::UsageResult;
+use crate::store::AppState;
+
+/// 关闭时重新读取配置的间隔
+const DISABLED_RECHECK_SECS: u64 = 60;
+/// 过期快照的清理间隔
+const PRUNE_INTERVAL_SECS: u64 = 6 * 60 * 60;
+/// 相同余额的快照在该时间内不重复保存（前端自动查询可能很频繁）
+const SNAPSHOT_DEDUPE_SECS: i64 = 300;
+
+/// 对账配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BalanceReconciliationConfig {
+    /// 是否保存余额快照并在后台定时查询
+    pub enabled: bool,
+    /// 后台查询用量脚本的间隔（分钟，由共用的用量轮询执行）
+    pub snapshot_interval_mins: u32,
+    /// 余额消耗与计算成本的允许偏差（0.1 表示 ±10%）
+    pub tolerance: f64,
+    /// 计算成本低于该值时不做判断
+    pub min_computed_cost: f64,
+    /// 快照保留天数
+    pub keep_days: u32,
+}
+
+impl Default for BalanceReconciliationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            snapshot_interval_mins: 60,
+            tolerance: 0.1,
+            min_computed_cost: 0.5,
+            keep_days: 90,
+        }
+    }
+}
+
+impl BalanceReconciliationConfig {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.snapshot_interval_mins == 0 || self.keep_days == 0 {
+            return Err(AppError::InvalidInput(
+                "查询间隔与保留天数必须大于 0".to_string(),
+            ));
+        }
+        if !(0.0..1.0).contains(&self.tolerance) {
+            return Err(AppError::InvalidInput(
+                "允许偏差必须在 0 到 1 之间".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 余额快照
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceSnapshot {
+    #[serde(default)]
+    pub id: i64,
+    pub app_type: String,
+    pub provider_id: String,
+    /// 套餐名称（单套餐为空字符串）
+    pub plan_name: String,
+    pub used: Option<f64>,
+    pub remaining: Option<f64>,
+    pub total: Option<f64>,
+    pub unit: Option<String>,
+    pub created_at: i64,
+}
+
+/// 对账结论
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ReconciliationStatus {
+    /// 余额消耗与计算成本在允许偏差内
+    Consistent,
+    /// 余额消耗高于计算成本
+    Overcharged,
+    /// 余额消耗低于计算成本（可能替换了模型）
+    Undercharged,
+    /// 数据不足，无法判断
+    Insufficient,
+}
+
+/// 单个供应商套餐的对账结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BalanceReconciliation {
+    pub app_type: String,
+    pub provider_id: String,
+    pub provider_name: Option<String>,
+    pub plan_name: String,
+    /// 用量脚本返回的单位
+    pub unit: Option<String>,
+    pub period_start: i64,
+    pub period_end: i64,
+    pub snapshot_count: u32,
+    /// 参与对账的快照区间数
+    pub interval_count: u32,
+    /// 余额增加（充值 / 重置）或数据缺失而跳过的区间数
+    pub skipped_intervals: u32,
+    pub request_count: u64,
+    /// 用量脚本报告的消耗
+    pub reported_usage: f64,
+    /// 按当前倍率计算的成本
+    pub computed_cost: f64,
+    /// 按原价（倍率为 1）计算的成本
+    pub base_cost: f64,
+    /// computed_cost / base_cost 使用的币种
+    pub cost_currency: String,
+    /// 实际倍率：报告消耗 / 原价成本（两者同为 `cost_currency`）
+    ///
+    /// 原价成本由 USD 按计费设置或汇率折算，折算比例与中转站的充值比例一致时才可直接作为供应商的成本倍率；
+    /// 用量脚本单位无法识别或无法按该币种统计时为 None
+    pub effective_multiplier: Option<f64>,
+    pub status: ReconciliationStatus,
+    pub message: String,
+}
+
+/// 相邻快照之间的余额消耗
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConsumptionInterval {
+    pub start: i64,
+    pub end: i64,
+    pub consumed: f64,
+}
+
+/// 用量脚本单位对应的币种（无法识别时返回 None，按 USD 成本对比）
+pub(crate) fn unit_currency(unit: Option<&str>) -> Option<String> {
+    let unit = unit?.trim();
+    match unit.to_ascii_uppercase().as_str() {
+        "$" | "USD" | "美元" => Some("USD".to_string()),
+        "¥" | "￥" | "元" | "RMB" | "CNY" | "人民币" => Some("CNY".to_string()),
+        code if code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase()) => {
+            Some(code.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// 把同一套餐按时间排序的快照转换为消耗区间，返回（区间，跳过的区间数）
+///
+/// 两端都有 `used` 时取其差值，否则取 `remaining` 的减少量；余额增加视为充值或重置，跳过该区间
+pub fn consumption_intervals(snapshots: &[BalanceSnapshot]) -> (Vec<ConsumptionInterval>, u32) {
+    let mut intervals = Vec::new();
+    let mut skipped = 0;
+    for pair in snapshots.windows(2) {
+        let (prev, cur) = (&pair[0], &pair[1]);
+        let consumed = match (prev.used, cur.used, prev.remaining, cur.remaining) {
+            (Some(before), Some(after), _, _) => Some(after - before),
+            (_, _, Some(before), Some(after)) => Some(before - after),
+            _ => None,
+        };
+        match consumed {
+            Some(consumed) if consumed >= 0.0 => intervals.push(ConsumptionInterval {
+                start: prev.created_at,
+                end: cur.created_at,
+                consumed,
+            }),
+            _ => skipped += 1,
+        }
+    }
+    (intervals, skipped)
+}
+
+/// 根据报告消耗与计算成本给出结论与说明
+pub fn classify(
+    reported: f64,
+    computed: f64,
+    config: &BalanceReconciliationConfig,
+) -> (ReconciliationStatus, String) {
+    if computed < config.min_computed_cost {
+        return (
+            ReconciliationStatus::Insufficient,
+            "对账区间内的计算成本不足，无法判断".to_string(),
+        );
+    }
+    let deviation = reported / computed - 1.0;
+    let percent = deviation.abs() * 100.0;
+    if deviation > config.tolerance {
+        (
+            ReconciliationStatus::Overcharged,
+            format!("余额消耗比计算成本高 {percent:.1}%，中转可能多扣费用"),
+        )
+    } else if deviation < -config.tolerance {
+        (
+            ReconciliationStatus::Undercharged,
+            format!("余额消耗比计算成本低 {percent:.1}%，可能替换了更便宜的模型或倍率配置偏高"),
+        )
+    } else {
+        (
+            ReconciliationStatus::Consistent,
+            "余额消耗与计算成本一致".to_string(),
+        )
+    }
+}
+
+/// 保存一次用量查询结果的余额快照（未启用对账时忽略），返回新增的快照数
+pub fn record_snapshots(
+    db: &Database,
+    app_type: &str,
+    provider_id: &str,
+    result: &UsageResult,
+) -> Result<usize, AppError> {
+    if !result.success || !db.get_balance_reconciliation_config()?.enabled {
+        return Ok(0);
+    }
+    let now = chrono::Utc::now().timestamp();
+    let mut saved = 0;
+    for data in result.data.iter().flatten() {
+        if data.used.is_none() && data.remaining.is_none() {
+            continue;
+        }
+        let snapshot = BalanceSnapshot {
+            id: 0,
+            app_type: app_type.to_string(),
+            provider_id: provider_id.to_string(),
+            plan_name: data.plan_name.clone().unwrap_or_default(),
+            used: data.used,
+            remaining: data.remaining,
+            total: data.total,
+            unit: data.unit.clone(),
+            created_at: now,
+        };
+        if db.insert_balance_snapshot(&snapshot, SNAPSHOT_DEDUPE_SECS)? {
+            saved += 1;
+        }
+    }
+    Ok(saved)
+}
+
+/// 生成对账报告
+pub fn build_report(
+    db: &Database,
+    start_date: Option<i64>,
+    end_date: Option<i64>,
+    app_type: Option<&str>,
+) -> Result<Vec<BalanceReconciliation>, AppError> {
+    let config = db.get_balance_reconciliation_config()?;
+    let mut report = Vec::new();
+    for key in db.list_balance_snapshot_keys(app_type)? {
+        let snapshots = db.list_balance_snapshots(
+            &key.app_type,
+            &key.provider_id,
+            &key.plan_name,
+            start_date,
+            end_date,
+        )?;
+        let (Some(first), Some(last)) = (snapshots.first(), snapshots.last()) else {
+            continue;
+        };
+        let unit = last.unit.clone();
+        let (intervals, skipped_intervals) = consumption_intervals(&snapshots);
+        let currency = unit_currency(unit.as_deref());
+        let costs = db.sum_interval_costs(
+            &key.app_type,
+            &key.provider_id,
+            &intervals,
+            currency.as_deref(),
+        )?;
+        let reported_usage: f64 = intervals.iter().map(|i| i.consumed).sum();
+        let (status, message) = classify(reported_usage, costs.computed_cost, &config);
+        // 报告消耗与成本币种不一致时比值没有倍率含义
+        let effective_multiplier = (status != ReconciliationStatus::Insufficient
+            && costs.base_cost > 0.0
+            && currency.as_deref() == Some(costs.currency.as_str()))
+        .then(|| reported_usage / costs.base_cost);
+
+        report.push(BalanceReconciliation {
+            app_type: key.app_type,
+            provider_id: key.provider_id,
+            provider_name: key.provider_name,
+            plan_name: key.plan_name,
+            unit,
+            period_start: first.created_at,
+            period_end: last.created_at,
+            snapshot_count: snapshots.len() as u32,
+            interval_count: intervals.len() as u32,
+            skipped_intervals,
+            request_count: costs.request_count,
+            reported_usage,
+            computed_cost: costs.computed_cost,
+            base_cost: costs.base_cost,
+            cost_currency: costs.currency,
+            effective_multiplier,
+            status,
+            message,
+        });
+    }
+    Ok(report)
+}
+
+/// 启动后台快照清理任务
+///
+/// 快照由共用的用量轮询在查询流程中保存，这里只按保留天数清理过期快照。
+pub fn start_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let state = app.state::<AppState>();
+            let config = match state.db.get_balance_reconciliation_config() {
+                Ok(config) => config,
+                Err(e) => {
+                    log::warn!("[Reconcile] 读取配置失败: {e}");
+                    BalanceReconciliationConfig::default()
+                }
+            };
+
+            if !config.enabled {
+                tokio::time::sleep(Duration::from_secs(DISABLED_RECHECK_SECS)).await;
+                continue;
+            }
+
+            let cutoff = chrono::Utc::now().timestamp() - i64::from(config.keep_days) * 86_400;
+            if let Err(e) = state.db.prune_balance_snapshots(cutoff) {
+                log::warn!("[Reconcile] 清理过期快照失败: {e}");
+            }
+
+            tokio::time::sleep(Duration::from_secs(PRUNE_INTERVAL_SECS)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(created_at: i64, used: Option<f64>, remaining: Option<f64>) -> BalanceSnapshot {
+        BalanceSnapshot {
+            id: 0,
+            app_type: "claude".to_string(),
+            provider_id: "p1".to_string(),
+            plan_name: String::new(),
+            used,
+            remaining,
+            total: None,
+            unit: Some("USD".to_string()),
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_consumption_intervals_skip_recharge() {
+        let snapshots = [
+            snapshot(100, None, Some(50.0)),
+            snapshot(200, None, Some(45.0)),
+            // 充值
+            snapshot(300, None, Some(95.0)),
+            snapshot(400, None, Some(90.5)),
+            snapshot(500, None, None),
+        ];
+        let (intervals, skipped) = consumption_intervals(&snapshots);
+        assert_eq!(skipped, 2);
+        assert_eq!(
+            intervals,
+            vec![
+                ConsumptionInterval {
+                    start: 100,
+                    end: 200,
+                    consumed: 5.0
+                },
+                ConsumptionInterval {
+                    start: 300,
+                    end: 400,
+                    consumed: 4.5
+                },
+            ]
+        );
+
+        // 两端都有 used 时优先使用 used
+        let (intervals, _) = consumption_intervals(&[
+            snapshot(100, Some(10.0), Some(90.0)),
+            snapshot(200, Some(12.0), Some(90.0)),
+        ]);
+        assert_eq!(intervals[0].consumed, 2.0);
+    }
+
+    #[test]
+    fn test_classify() {
+        let config = BalanceReconciliationConfig::default();
+        assert_eq!(
+            classify(10.0, 0.1, &config).0,
+            ReconciliationStatus::Insufficient
+        );
+        assert_eq!(
+            classify(10.5, 10.0, &config).0,
+            ReconciliationStatus::Consistent
+        );
+        assert_eq!(
+            classify(15.0, 10.0, &config).0,
+            ReconciliationStatus::Overcharged
+        );
+        assert_eq!(
+            classify(5.0, 10.0, &config).0,
+            ReconciliationStatus::Undercharged
+        );
+    }
+
+    #[test]
+    fn test_unit_currency() {
+        assert_eq!(unit_currency(Some("$")).as_deref(), Some("USD"));
+        assert_eq!(unit_currency(Some("元")).as_deref(), Some("CNY"));
+        assert_eq!(unit_currency(Some("eur")).as_deref(), Some("EUR"));
+        assert_eq!(unit_currency(Some("credits")), None);
+        assert_eq!(unit_currency(None), None);
+    }
+}
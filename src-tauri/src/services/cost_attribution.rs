@@ -0,0 +1,200 @@
+//! 按会话与项目归属成本
+//!
+//! 请求日志只记录 session_id，项目目录来自两处：
+//! - 会话管理器扫描到的 `SessionMeta.project_dir`（Claude / Codex 会话记录中的 cwd）
+//! - 客户端通过 `x-cc-switch-project` 请求头显式声明（优先级更高，不会被扫描结果覆盖）
+//!
+//! 两者统一写入 session_projects，统计时按 (session_id, app_type) 关联；
+//! session_id 统一记为 CLI 会话文件中的原始 ID（代理为 Codex 会话添加的前缀会被去掉）
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::database::Database;
+use crate::error::AppError;
+use crate::proxy::session::cli_session_id;
+use crate::session_manager;
+
+/// 客户端声明项目目录的请求头
+pub const PROJECT_HEADER: &str = "x-cc-switch-project";
+
+/// 项目来源：会话管理器扫描
+pub const PROJECT_SOURCE_SESSION: &str = "session";
+/// 项目来源：请求头声明
+pub const PROJECT_SOURCE_HEADER: &str = "header";
+
+/// 会话扫描结果的刷新间隔
+const REFRESH_INTERVAL: Duration = Duration::from_secs(300);
+
+/// 已记录的请求头项目上限，超过后清空重新记录
+const RECORDED_HEADER_PROJECTS_MAX: usize = 4096;
+
+static LAST_REFRESH: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// 已写入的请求头项目 (session_id, app_type, project_dir)，避免每个请求都写库
+static RECORDED_HEADER_PROJECTS: LazyLock<Mutex<HashSet<(String, String, String)>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+/// 会话与项目目录的对应关系
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionProject {
+    pub session_id: String,
+    pub app_type: String,
+    pub project_dir: String,
+    pub title: Option<String>,
+    pub source: &'static str,
+}
+
+/// 单个会话的成本汇总
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionCostStats {
+    pub session_id: String,
+    pub app_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub request_count: u64,
+    pub total_input_tokens: u64,
+    pub total_output_tokens: u64,
+    pub total_cache_read_tokens: u64,
+    pub total_cache_creation_tokens: u64,
+    pub total_cost: String,
+    pub currency: String,
+    pub first_request_at: i64,
+    pub last_request_at: i64,
+}
+
+/// 单个项目的成本汇总（project_dir 为空表示无法归属的请求）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectCostStats {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_dir: Option<String>,
+    pub session_count: u64,
+    pub request_count: u64,
+    pub total_tokens: u64,
+    pub total_cost: String,
+    pub currency: String,
+}
+
+/// 项目的月度成本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectMonthlyCost {
+    /// 本地时间的月份（YYYY-MM）
+    pub month: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_dir: Option<String>,
+    pub request_count: u64,
+    pub total_tokens: u64,
+    pub total_cost: String,
+    pub currency: String,
+}
+
+/// 规范化项目目录：去掉首尾空白与末尾路径分隔符
+pub fn normalize_project_dir(raw: &str) -> Option<String> {
+    let trimmed = raw.trim();
+    let normalized = trimmed.trim_end_matches(['/', '\\']);
+    match (trimmed.is_empty(), normalized.is_empty()) {
+        (true, _) => None,
+        // 根目录本身
+        (false, true) => Some(trimmed[..1].to_string()),
+        (false, false) => Some(normalized.to_string()),
+    }
+}
+
+/// 记录请求头声明的会话项目，同一会话与目录只写入一次
+pub fn record_header_project(
+    db: &Database,
+    session_id: &str,
+    app_type: &str,
+    project_dir: String,
+) -> Result<(), AppError> {
+    let key = (
+        cli_session_id(session_id).to_string(),
+        app_type.to_string(),
+        project_dir,
+    );
+    if RECORDED_HEADER_PROJECTS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .contains(&key)
+    {
+        return Ok(());
+    }
+
+    db.save_session_projects(&[SessionProject {
+        session_id: key.0.clone(),
+        app_type: key.1.clone(),
+        project_dir: key.2.clone(),
+        title: None,
+        source: PROJECT_SOURCE_HEADER,
+    }])?;
+
+    let mut recorded = RECORDED_HEADER_PROJECTS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    if recorded.len() >= RECORDED_HEADER_PROJECTS_MAX {
+        recorded.clear();
+    }
+    recorded.insert(key);
+    Ok(())
+}
+
+/// 距上次扫描超过刷新间隔时，在后台刷新会话与项目的对应关系（不阻塞统计查询）
+pub fn refresh_session_projects_in_background(db: Arc<Database>) {
+    {
+        let mut last = LAST_REFRESH.lock().unwrap_or_else(|e| e.into_inner());
+        if last.is_some_and(|t| t.elapsed() < REFRESH_INTERVAL) {
+            return;
+        }
+        *last = Some(Instant::now());
+    }
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = refresh_session_projects(&db) {
+            log::warn!("刷新会话项目失败: {e}");
+        }
+    });
+}
+
+/// 用会话管理器的扫描结果刷新会话与项目的对应关系，返回写入的会话数
+pub fn refresh_session_projects(db: &Database) -> Result<usize, AppError> {
+    let projects: Vec<SessionProject> = session_manager::scan_sessions()
+        .into_iter()
+        .filter_map(|meta| {
+            let project_dir = normalize_project_dir(meta.project_dir.as_deref()?)?;
+            Some(SessionProject {
+                session_id: meta.session_id,
+                app_type: meta.provider_id,
+                project_dir,
+                title: meta.title,
+                source: PROJECT_SOURCE_SESSION,
+            })
+        })
+        .collect();
+    db.save_session_projects(&projects)?;
+    Ok(projects.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_project_dir() {
+        assert_eq!(
+            normalize_project_dir(" /home/me/repo/ ").as_deref(),
+            Some("/home/me/repo")
+        );
+        assert_eq!(
+            normalize_project_dir("C:\\work\\repo\\").as_deref(),
+            Some("C:\\work\\repo")
+        );
+        assert_eq!(normalize_project_dir("/").as_deref(), Some("/"));
+        assert_eq!(normalize_project_dir("  "), None);
+    }
+}
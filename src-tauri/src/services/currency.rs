@@ -0,0 +1,339 @@
+//! 多币种成本核算
+//!
+//! - 汇率表 exchange_rates 记录「1 USD = rate 单位币种」，按 effective_from 分段生效
+//! - 请求成本同时保存两份：供应商计费币种下的实际花费（billing_cost）与折算后的 USD（total_cost_usd）
+//! - 统计接口按所选报表币种折算，与计费币种一致时直接使用 billing_cost
+
+use rusqlite::{params, Connection, OptionalExtension};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::str::FromStr;
+
+use crate::error::AppError;
+
+/// 成本归一化的基准币种
+pub const BASE_CURRENCY: &str = "USD";
+
+/// 汇率记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExchangeRate {
+    pub currency: String,
+    /// 1 USD 可兑换的该币种数量
+    pub rate: String,
+    /// 生效起点（秒）
+    pub effective_from: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+}
+
+/// 供应商的计费设置
+#[derive(Debug, Clone, PartialEq)]
+pub struct BillingProfile {
+    pub currency: String,
+    /// 每 1 USD 额度实际支付的计费币种金额
+    pub recharge_rate: Option<Decimal>,
+}
+
+impl Default for BillingProfile {
+    fn default() -> Self {
+        Self {
+            currency: BASE_CURRENCY.to_string(),
+            recharge_rate: None,
+        }
+    }
+}
+
+/// 一次请求的结算结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settlement {
+    pub currency: String,
+    /// 计费币种下的实际花费
+    pub billing_cost: Decimal,
+    /// 折算为 USD 的实际花费
+    pub total_usd: Decimal,
+}
+
+impl Settlement {
+    /// 按与总价相同的折算比例换算成本分项（`charged_usd` 为结算前的总额度）
+    pub fn scale_usd(&self, charged_usd: Decimal, component: Decimal) -> Decimal {
+        if charged_usd.is_zero() {
+            component
+        } else {
+            component * self.total_usd / charged_usd
+        }
+    }
+}
+
+/// 规范化币种代码（ISO 4217 三位字母，统一大写）
+pub fn normalize_currency(code: &str) -> Result<String, AppError> {
+    let code = code.trim().to_ascii_uppercase();
+    if code.len() == 3 && code.chars().all(|c| c.is_ascii_uppercase()) {
+        Ok(code)
+    } else {
+        Err(AppError::InvalidInput(format!("无效的币种代码: {code}")))
+    }
+}
+
+/// 读取供应商的计费设置，未配置或配置无效时按 USD 计费
+pub(crate) fn load_billing_profile(
+    conn: &Connection,
+    provider_id: &str,
+    app_type: &str,
+) -> Result<BillingProfile, AppError> {
+    let meta = conn
+        .query_row(
+            "SELECT meta FROM providers WHERE id = ?1 AND app_type = ?2",
+            params![provider_id, app_type],
+            |row| row.get::<_, Option<String>>(0),
+        )
+        .optional()?
+        .flatten()
+        .and_then(|meta| serde_json::from_str::<Value>(&meta).ok());
+    let Some(meta) = meta else {
+        return Ok(BillingProfile::default());
+    };
+
+    let currency = meta
+        .get("billingCurrency")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.trim().is_empty())
+        .map(|code| {
+            normalize_currency(code).unwrap_or_else(|e| {
+                log::warn!("[USG-004] 供应商计费币种无效 (provider_id={provider_id}): {e}");
+                BASE_CURRENCY.to_string()
+            })
+        })
+        .unwrap_or_else(|| BASE_CURRENCY.to_string());
+    let recharge_rate = meta
+        .get("rechargeRate")
+        .and_then(|v| v.as_str())
+        .filter(|s| !s.trim().is_empty())
+        .and_then(|raw| match Decimal::from_str(raw.trim()) {
+            Ok(rate) if rate > Decimal::ZERO => Some(rate),
+            _ => {
+                log::warn!("[USG-004] 供应商充值汇率无效 (provider_id={provider_id}): {raw}");
+                None
+            }
+        });
+
+    Ok(BillingProfile {
+        currency,
+        recharge_rate,
+    })
+}
+
+/// 获取指定时间（秒）生效的汇率
+///
+/// 早于所有记录时使用最早的一条；USD 恒为 1；未配置该币种时返回 None
+pub(crate) fn rate_at(
+    conn: &Connection,
+    currency: &str,
+    timestamp: i64,
+) -> Result<Option<Decimal>, AppError> {
+    if currency == BASE_CURRENCY {
+        return Ok(Some(Decimal::ONE));
+    }
+    let latest: Option<String> = conn
+        .query_row(
+            "SELECT rate FROM exchange_rates
+             WHERE currency = ?1 AND effective_from <= ?2
+             ORDER BY effective_from DESC LIMIT 1",
+            params![currency, timestamp],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let raw = match latest {
+        Some(raw) => Some(raw),
+        None => conn
+            .query_row(
+                "SELECT rate FROM exchange_rates
+                 WHERE currency = ?1
+                 ORDER BY effective_from ASC LIMIT 1",
+                [currency],
+                |row| row.get(0),
+            )
+            .optional()?,
+    };
+    Ok(raw.and_then(|raw| Decimal::from_str(&raw).ok()))
+}
+
+/// 结算一次请求
+///
+/// `charged_usd` 为按模型定价 × 倍率得到的 USD 额度消耗：
+/// - USD 计费：两份成本相同
+/// - 配置了充值汇率：计费币种花费 = 额度 × 充值汇率，再按汇率表折回 USD
+/// - 未配置充值汇率：计费币种花费 = 额度 × 汇率表汇率
+///
+/// 缺少汇率时无法折算，按 USD 记录
+pub(crate) fn settle(
+    conn: &Connection,
+    charged_usd: Decimal,
+    profile: &BillingProfile,
+    timestamp: i64,
+) -> Result<Settlement, AppError> {
+    let usd = || Settlement {
+        currency: BASE_CURRENCY.to_string(),
+        billing_cost: charged_usd,
+        total_usd: charged_usd,
+    };
+    if profile.currency == BASE_CURRENCY {
+        return Ok(usd());
+    }
+
+    let fx = rate_at(conn, &profile.currency, timestamp)?.filter(|r| *r > Decimal::ZERO);
+    let settlement = match (profile.recharge_rate, fx) {
+        (Some(recharge), fx) => {
+            let billing_cost = charged_usd * recharge;
+            let total_usd = match fx {
+                Some(fx) => billing_cost / fx,
+                None => {
+                    log::warn!(
+                        "[USG-004] 缺少 {} 汇率，USD 成本按额度记录",
+                        profile.currency
+                    );
+                    charged_usd
+                }
+            };
+            Settlement {
+                currency: profile.currency.clone(),
+                billing_cost,
+                total_usd,
+            }
+        }
+        (None, Some(fx)) => Settlement {
+            currency: profile.currency.clone(),
+            billing_cost: charged_usd * fx,
+            total_usd: charged_usd,
+        },
+        (None, None) => {
+            log::warn!(
+                "[USG-004] 缺少 {} 汇率且未配置充值汇率，按 USD 记录",
+                profile.currency
+            );
+            usd()
+        }
+    };
+    Ok(settlement)
+}
+
+/// 生成把单条请求成本折算为报表币种的 SQL 表达式
+///
+/// `prefix` 为 proxy_request_logs 的表别名前缀（如 "l."），`currency` 必须已规范化
+pub(crate) fn report_cost_expr(prefix: &str, currency: &str) -> String {
+    report_cost_expr_at(prefix, "created_at", currency)
+}
+
+/// 同 [`report_cost_expr`]，汇率按 `time_column` 列的时间选取（用于用量汇总表）
+pub(crate) fn report_cost_expr_at(prefix: &str, time_column: &str, currency: &str) -> String {
+    if currency == BASE_CURRENCY {
+        return format!("CAST({prefix}total_cost_usd AS REAL)");
+    }
+    format!(
+        "CASE WHEN {prefix}billing_currency = '{currency}' THEN CAST({prefix}billing_cost AS REAL)
+         ELSE CAST({prefix}total_cost_usd AS REAL) * COALESCE(
+            (SELECT CAST(r.rate AS REAL) FROM exchange_rates r
+             WHERE r.currency = '{currency}' AND r.effective_from <= {prefix}{time_column}
+             ORDER BY r.effective_from DESC LIMIT 1),
+            (SELECT CAST(r.rate AS REAL) FROM exchange_rates r
+             WHERE r.currency = '{currency}' ORDER BY r.effective_from ASC LIMIT 1))
+         END"
+    )
+}
+
+/// 校验报表币种可用（非 USD 时必须配置过汇率）
+pub(crate) fn ensure_reportable(conn: &Connection, currency: &str) -> Result<String, AppError> {
+    let currency = normalize_currency(currency)?;
+    if currency != BASE_CURRENCY && rate_at(conn, &currency, 0)?.is_none() {
+        return Err(AppError::InvalidInput(format!(
+            "未配置 {currency} 汇率，无法按该币种统计"
+        )));
+    }
+    Ok(currency)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{lock_conn, Database};
+
+    fn insert_rate(conn: &Connection, currency: &str, rate: &str, from: i64) {
+        conn.execute(
+            "INSERT INTO exchange_rates (currency, rate, effective_from) VALUES (?1, ?2, ?3)",
+            params![currency, rate, from],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_rate_at_picks_effective_entry() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let conn = lock_conn!(db.conn);
+        insert_rate(&conn, "CNY", "7.2", 1000);
+        insert_rate(&conn, "CNY", "7.0", 2000);
+
+        assert_eq!(
+            rate_at(&conn, "CNY", 1500)?,
+            Some(Decimal::from_str("7.2").unwrap())
+        );
+        assert_eq!(
+            rate_at(&conn, "CNY", 2500)?,
+            Some(Decimal::from_str("7.0").unwrap())
+        );
+        // 早于所有记录时使用最早的汇率
+        assert_eq!(
+            rate_at(&conn, "CNY", 10)?,
+            Some(Decimal::from_str("7.2").unwrap())
+        );
+        assert_eq!(rate_at(&conn, "EUR", 1500)?, None);
+        assert_eq!(rate_at(&conn, "USD", 1500)?, Some(Decimal::ONE));
+        Ok(())
+    }
+
+    #[test]
+    fn test_settle_with_recharge_rate() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let conn = lock_conn!(db.conn);
+        insert_rate(&conn, "CNY", "8", 0);
+
+        // 中转站 1 元 = 1 美元额度：消耗 $10 额度，实际花费 ¥10，折合 $1.25
+        let relay = BillingProfile {
+            currency: "CNY".to_string(),
+            recharge_rate: Some(Decimal::ONE),
+        };
+        let settlement = settle(&conn, Decimal::from(10), &relay, 100)?;
+        assert_eq!(settlement.billing_cost, Decimal::from(10));
+        assert_eq!(settlement.total_usd, Decimal::from_str("1.25").unwrap());
+        // 分项按同一比例折算：$4 额度 → $0.5
+        assert_eq!(
+            settlement.scale_usd(Decimal::from(10), Decimal::from(4)),
+            Decimal::from_str("0.5").unwrap()
+        );
+
+        // 未配置充值汇率时按市场汇率换算
+        let market = BillingProfile {
+            currency: "CNY".to_string(),
+            recharge_rate: None,
+        };
+        let settlement = settle(&conn, Decimal::from(10), &market, 100)?;
+        assert_eq!(settlement.billing_cost, Decimal::from(80));
+        assert_eq!(settlement.total_usd, Decimal::from(10));
+
+        // 缺少汇率时退回 USD
+        let unknown = BillingProfile {
+            currency: "EUR".to_string(),
+            recharge_rate: None,
+        };
+        let settlement = settle(&conn, Decimal::from(10), &unknown, 100)?;
+        assert_eq!(settlement.currency, BASE_CURRENCY);
+        Ok(())
+    }
+
+    #[test]
+    fn test_normalize_currency() {
+        assert_eq!(normalize_currency(" cny ").unwrap(), "CNY");
+        assert!(normalize_currency("RMB1").is_err());
+        assert!(normalize_currency("人民币").is_err());
+    }
+}
@@ -0,0 +1,375 @@
+//! 后台健康探测服务
+//!
+//! 按固定间隔（带随机抖动）对故障转移队列中的供应商执行端点测速，
+//! 可选追加流式检查。探测结果会：
+//! - 写入延迟/可用率历史（`endpoint_probe_logs` / `stream_check_logs`）
+//! - 更新自定义端点的延迟，供代理端点池排序使用
+//! - 同步到运行中的熔断器，提前熔断不可用的供应商
+
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::proxy::endpoint_pool::build_endpoint_pool;
+use crate::proxy::providers::get_adapter;
+use crate::services::provider::ProviderService;
+use crate::services::speedtest::{EndpointLatency, SpeedtestService};
+use crate::services::stream_check::{StreamCheckResult, StreamCheckService};
+use crate::services::webdav_auto_sync::AutoSyncSuppressionGuard;
+use crate::store::AppState;
+
+/// 参与后台探测的应用（与代理故障转移支持的应用一致）
+const PROBE_APPS: [AppType; 3] = [AppType::Claude, AppType::Codex, AppType::Gemini];
+
+/// 探测关闭时重新读取配置的间隔
+const DISABLED_RECHECK_SECS: u64 = 60;
+const MIN_INTERVAL_SECS: u64 = 60;
+
+/// 后台健康探测配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HealthProbeConfig {
+    pub enabled: bool,
+    /// 探测间隔（秒）
+    pub interval_secs: u64,
+    /// 每轮额外随机延迟上限（秒），避免多实例同时打到上游
+    pub jitter_secs: u64,
+    /// 安静模式：供应商近期有真实流量时跳过探测
+    pub quiet_mode: bool,
+    /// 安静模式判定窗口（秒）
+    pub quiet_window_secs: u64,
+    /// 是否追加流式检查（会消耗少量 token）
+    pub stream_check: bool,
+    /// 探测失败是否计入熔断器失败次数（按 `circuit_failure_threshold` 打开）
+    pub open_circuit_on_failure: bool,
+    /// 端点测速超时（秒）
+    pub timeout_secs: u64,
+    /// 探测历史保留天数
+    pub retention_days: u32,
+}
+
+impl Default for HealthProbeConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 300,
+            jitter_secs: 30,
+            quiet_mode: true,
+            quiet_window_secs: 60,
+            stream_check: false,
+            open_circuit_on_failure: true,
+            timeout_secs: 8,
+            retention_days: 30,
+        }
+    }
+}
+
+/// 健康历史中的一个时间桶
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct HealthHistoryPoint {
+    pub bucket_start: i64,
+    /// 端点 URL；为空表示流式检查序列
+    pub endpoint: Option<String>,
+    pub samples: u32,
+    pub successes: u32,
+    /// 可用率 (0.0-1.0)
+    pub availability: f64,
+    pub avg_latency_ms: Option<u64>,
+}
+
+/// 单个供应商的探测结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderProbeResult {
+    pub app_type: String,
+    pub provider_id: String,
+    pub provider_name: String,
+    pub healthy: bool,
+    pub endpoints: Vec<EndpointLatency>,
+    pub stream_check: Option<StreamCheckResult>,
+    pub tested_at: i64,
+}
+
+/// 后台健康探测业务
+pub struct HealthProbeService;
+
+impl HealthProbeService {
+    /// 执行一轮探测
+    ///
+    /// `force` 为 true 时忽略安静模式（用于手动触发）。
+    pub async fn run_once(
+        state: &AppState,
+        config: &HealthProbeConfig,
+        force: bool,
+    ) -> Result<Vec<ProviderProbeResult>, AppError> {
+        let now = chrono::Utc::now().timestamp();
+        let mut results = Vec::new();
+
+        for app_type in PROBE_APPS.iter() {
+            let providers = state.db.get_failover_providers(app_type.as_str())?;
+            for provider in providers {
+                if !force && config.quiet_mode {
+                    let last = state
+                        .db
+                        .get_last_proxy_request_at(app_type.as_str(), &provider.id)?;
+                    if has_recent_traffic(last, now, config.quiet_window_secs) {
+                        log::debug!(
+                            "[HealthProbe] 跳过 {}/{}：近期有真实流量",
+                            app_type.as_str(),
+                            provider.id
+                        );
+                        continue;
+                    }
+                }
+
+                match Self::probe_provider(state, app_type, &provider, config).await {
+                    Ok(result) => results.push(result),
+                    Err(e) => log::warn!(
+                        "[HealthProbe] 探测 {}/{} 失败: {e}",
+                        app_type.as_str(),
+                        provider.id
+                    ),
+                }
+            }
+        }
+
+        let cutoff = now - i64::from(config.retention_days) * 86_400;
+        if let Err(e) = state.db.cleanup_endpoint_probe_logs(cutoff) {
+            log::warn!("[HealthProbe] 清理历史探测记录失败: {e}");
+        }
+
+        Ok(results)
+    }
+
+    /// 探测单个供应商并应用结果
+    pub async fn probe_provider(
+        state: &AppState,
+        app_type: &AppType,
+        provider: &Provider,
+        config: &HealthProbeConfig,
+    ) -> Result<ProviderProbeResult, AppError> {
+        let adapter = get_adapter(app_type);
+        let primary = adapter
+            .extract_base_url(provider)
+            .map_err(|e| AppError::Message(e.to_string()))?;
+        let pool = build_endpoint_pool(&primary, provider);
+
+        let endpoints = SpeedtestService::test_endpoints(pool, Some(config.timeout_secs)).await?;
+        let tested_at = chrono::Utc::now().timestamp();
+
+        state.db.save_endpoint_probe_logs(
+            app_type.as_str(),
+            &provider.id,
+            &endpoints,
+            tested_at,
+        )?;
+        {
+            // 延迟回写属于运行时数据，不应触发 WebDAV 自动同步
+            let _guard = AutoSyncSuppressionGuard::new();
+            ProviderService::record_endpoint_latencies(
+                state,
+                app_type.clone(),
+                &provider.id,
+                &endpoints,
+            )?;
+        }
+
+        let stream_check = if config.stream_check {
+            let check_config = state.db.get_stream_check_config()?;
+            let result =
+                StreamCheckService::check_with_retry(app_type, provider, &check_config).await?;
+            let _ = state.db.save_stream_check_log(
+                &provider.id,
+                &provider.name,
+                app_type.as_str(),
+                &result,
+            );
+            Some(result)
+        } else {
+            None
+        };
+
+        let healthy = is_healthy(&endpoints, stream_check.as_ref());
+        if healthy || config.open_circuit_on_failure {
+            state
+                .proxy_service
+                .apply_probe_result(&provider.id, app_type.as_str(), healthy)
+                .await;
+        }
+
+        Ok(ProviderProbeResult {
+            app_type: app_type.as_str().to_string(),
+            provider_id: provider.id.clone(),
+            provider_name: provider.name.clone(),
+            healthy,
+            endpoints,
+            stream_check,
+            tested_at,
+        })
+    }
+}
+
+/// 启动后台探测任务
+///
+/// 每轮重新读取配置，开关与间隔修改无需重启。
+pub fn start_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let state = app.state::<AppState>();
+            let config = match state.db.get_health_probe_config() {
+                Ok(config) => config,
+                Err(e) => {
+                    log::warn!("[HealthProbe] 读取配置失败: {e}");
+                    HealthProbeConfig::default()
+                }
+            };
+
+            if !config.enabled {
+                tokio::time::sleep(Duration::from_secs(DISABLED_RECHECK_SECS)).await;
+                continue;
+            }
+
+            match HealthProbeService::run_once(&state, &config, false).await {
+                Ok(results) if !results.is_empty() => {
+                    if let Err(e) = app.emit("health-probe-completed", &results) {
+                        log::debug!("[HealthProbe] 发送探测事件失败: {e}");
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => log::warn!("[HealthProbe] 本轮探测失败: {e}"),
+            }
+
+            tokio::time::sleep(next_delay(&config, jitter_seed())).await;
+        }
+    });
+}
+
+/// 供应商在安静窗口内是否有真实请求
+fn has_recent_traffic(last_request_at: Option<i64>, now: i64, window_secs: u64) -> bool {
+    last_request_at.is_some_and(|ts| now - ts < window_secs as i64)
+}
+
+/// 判定健康：执行了流式检查则以其为准，否则任一端点可达且未返回 5xx 即视为健康
+///
+/// 测速对任何 HTTP 响应都会记录延迟，失效的中转返回 502/503 时同样“可达”
+fn is_healthy(endpoints: &[EndpointLatency], stream_check: Option<&StreamCheckResult>) -> bool {
+    match stream_check {
+        Some(result) => result.success,
+        None => endpoints
+            .iter()
+            .any(|e| e.latency.is_some() && e.status.is_none_or(|s| s < 500)),
+    }
+}
+
+fn next_delay(config: &HealthProbeConfig, seed: u64) -> Duration {
+    let base_ms = config.interval_secs.max(MIN_INTERVAL_SECS) * 1000;
+    let jitter_ms = match config.jitter_secs {
+        0 => 0,
+        secs => seed % (secs * 1000 + 1),
+    };
+    Duration::from_millis(base_ms + jitter_ms)
+}
+
+fn jitter_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.subsec_nanos() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn latency(url: &str, latency: Option<u128>) -> EndpointLatency {
+        EndpointLatency {
+            url: url.to_string(),
+            latency,
+            status: latency.map(|_| 200),
+            error: latency.is_none().then(|| "timeout".to_string()),
+        }
+    }
+
+    #[test]
+    fn config_defaults_are_conservative() {
+        let config: HealthProbeConfig = serde_json::from_str("{\"enabled\":true}").unwrap();
+        assert!(config.enabled);
+        assert!(config.quiet_mode);
+        assert!(!config.stream_check);
+        assert_eq!(config.interval_secs, 300);
+    }
+
+    #[test]
+    fn delay_respects_minimum_and_jitter_bound() {
+        let config = HealthProbeConfig {
+            interval_secs: 5,
+            jitter_secs: 10,
+            ..Default::default()
+        };
+        assert_eq!(next_delay(&config, 0), Duration::from_secs(60));
+        assert!(next_delay(&config, u64::MAX) <= Duration::from_secs(70));
+    }
+
+    #[test]
+    fn quiet_mode_window() {
+        assert!(has_recent_traffic(Some(950), 1000, 60));
+        assert!(!has_recent_traffic(Some(900), 1000, 60));
+        assert!(!has_recent_traffic(None, 1000, 60));
+    }
+
+    #[test]
+    fn health_prefers_stream_check_result() {
+        let reachable = vec![latency("https://a", Some(100))];
+        let dead = vec![latency("https://a", None), latency("https://b", None)];
+        assert!(is_healthy(&reachable, None));
+        assert!(!is_healthy(&dead, None));
+
+        let unavailable = vec![EndpointLatency {
+            status: Some(503),
+            ..latency("https://a", Some(100))
+        }];
+        assert!(!is_healthy(&unavailable, None));
+
+        let failed = StreamCheckResult {
+            status: crate::services::stream_check::HealthStatus::Failed,
+            success: false,
+            message: "401".into(),
+            response_time_ms: None,
+            http_status: Some(401),
+            model_used: String::new(),
+            returned_model: None,
+            tested_at: 0,
+            retry_count: 0,
+        };
+        assert!(!is_healthy(&reachable, Some(&failed)));
+    }
+
+    #[test]
+    fn history_buckets_endpoint_and_stream_samples() {
+        let db = crate::database::Database::memory().unwrap();
+        db.save_endpoint_probe_logs(
+            "claude",
+            "p1",
+            &[latency("https://a/", Some(100)), latency("https://b", None)],
+            120,
+        )
+        .unwrap();
+        db.save_endpoint_probe_logs("claude", "p1", &[latency("https://a", Some(300))], 150)
+            .unwrap();
+
+        let history = db
+            .get_provider_health_history("claude", "p1", 0, 1000, 60)
+            .unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].endpoint.as_deref(), Some("https://a"));
+        assert_eq!(history[0].samples, 2);
+        assert_eq!(history[0].avg_latency_ms, Some(200));
+        assert_eq!(history[1].availability, 0.0);
+
+        assert_eq!(db.cleanup_endpoint_probe_logs(130).unwrap(), 2);
+    }
+}
@@ -0,0 +1,1275 @@
+//! 配置即代码（Config-as-code）
+//!
+//! 团队通过一份 YAML/TOML/JSON 清单声明供应商、统一供应商、MCP 服务器、Skills、
+//! 提示词、故障转移队列与代理配置：
+//! - `plan`：对比清单与数据库当前状态，输出可审阅的差异（只列字段名，不输出密钥值）
+//! - `apply`：通过既有 Service 逐项收敛到清单状态，重复执行结果一致（幂等）
+//!
+//! 字符串中的 `${ENV_VAR}` 会在解析时替换为环境变量，`$${...}` 保留字面量。
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use indexmap::IndexMap;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::app_config::{AppType, McpServer, SkillApps};
+use crate::database::Database;
+use crate::error::AppError;
+use crate::prompt::Prompt;
+use crate::provider::{Provider, UniversalProvider};
+use crate::services::mcp::McpService;
+use crate::services::prompt::PromptService;
+use crate::services::provider::ProviderService;
+use crate::services::skill::{SkillRepo, SkillService};
+use crate::store::AppState;
+
+/// 当前支持的清单版本
+pub const MANIFEST_VERSION: u32 = 1;
+
+fn default_manifest_version() -> u32 {
+    MANIFEST_VERSION
+}
+
+fn default_branch() -> String {
+    "main".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 清单文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl ManifestFormat {
+    /// 根据文件扩展名推断格式（默认 YAML）
+    pub fn from_path(path: &Path) -> Self {
+        match path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("toml") => Self::Toml,
+            Some("json") => Self::Json,
+            _ => Self::Yaml,
+        }
+    }
+}
+
+/// 团队配置清单
+///
+/// 各段落为 `None`（未声明）时不受管理；声明后（即使为空）视为该段落的完整期望状态，
+/// 开启 `prune` 时会删除清单中未出现的条目。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigManifest {
+    #[serde(default = "default_manifest_version")]
+    pub version: u32,
+    /// 是否删除清单中未声明的条目
+    #[serde(default)]
+    pub prune: bool,
+    /// 应用 -> 供应商列表（列表顺序即 sortIndex）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub providers: Option<IndexMap<String, Vec<ManifestProvider>>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub universal_providers: Option<Vec<UniversalProvider>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp_servers: Option<Vec<McpServer>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub skills: Option<ManifestSkills>,
+    /// 应用 -> 提示词列表
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompts: Option<IndexMap<String, Vec<Prompt>>>,
+    /// 应用 -> 故障转移队列中的供应商 ID（队列顺序跟随供应商 sortIndex）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub failover: Option<IndexMap<String, Vec<String>>>,
+    /// 应用 -> 代理配置（仅覆盖声明的字段）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<IndexMap<String, ManifestProxyConfig>>,
+}
+
+/// 清单中的供应商声明
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestProvider {
+    #[serde(flatten)]
+    pub provider: Provider,
+    /// 是否设为当前供应商（累加模式应用忽略）
+    #[serde(default)]
+    pub current: bool,
+}
+
+/// 清单中的 Skills 声明
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestSkills {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repos: Option<Vec<ManifestSkillRepo>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installed: Option<Vec<ManifestSkill>>,
+}
+
+/// Skill 仓库声明
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestSkillRepo {
+    pub owner: String,
+    pub name: String,
+    #[serde(default = "default_branch")]
+    pub branch: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+impl From<&ManifestSkillRepo> for SkillRepo {
+    fn from(repo: &ManifestSkillRepo) -> Self {
+        SkillRepo {
+            owner: repo.owner.clone(),
+            name: repo.name.clone(),
+            branch: repo.branch.clone(),
+            enabled: repo.enabled,
+        }
+    }
+}
+
+/// 已安装 Skill 声明（id 格式与 InstalledSkill 一致："owner/repo:directory"）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestSkill {
+    pub id: String,
+    #[serde(default)]
+    pub apps: Vec<String>,
+}
+
+/// 代理配置覆盖项（接管开关由代理服务管理，不在清单中声明）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestProxyConfig {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub auto_failover_enabled: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub streaming_first_byte_timeout: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub streaming_idle_timeout: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub non_streaming_timeout: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circuit_failure_threshold: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circuit_success_threshold: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circuit_timeout_seconds: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circuit_error_rate_threshold: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub circuit_min_requests: Option<u32>,
+}
+
+/// 资源类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ManifestResource {
+    Provider,
+    UniversalProvider,
+    McpServer,
+    SkillRepo,
+    Skill,
+    Prompt,
+    FailoverQueue,
+    ProxyConfig,
+}
+
+/// 变更动作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ManifestAction {
+    Create,
+    Update,
+    Delete,
+}
+
+/// 单条计划变更
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestChange {
+    pub resource: ManifestResource,
+    pub action: ManifestAction,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app: Option<String>,
+    pub id: String,
+    /// 发生变化的字段名（不包含字段值，避免泄露密钥）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<String>,
+}
+
+/// 计划结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestPlan {
+    pub changes: Vec<ManifestChange>,
+    /// 与清单一致、无需变更的条目数
+    pub unchanged: usize,
+    pub prune: bool,
+}
+
+/// 执行失败的变更
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestFailure {
+    pub change: ManifestChange,
+    pub error: String,
+}
+
+/// 应用结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestApplyResult {
+    pub applied: Vec<ManifestChange>,
+    pub failed: Vec<ManifestFailure>,
+    pub unchanged: usize,
+}
+
+/// 配置清单业务逻辑
+pub struct ManifestService;
+
+impl ManifestService {
+    /// 从文件读取并解析清单（环境变量取自当前进程）
+    pub fn load(path: &Path) -> Result<ConfigManifest, AppError> {
+        let content = std::fs::read_to_string(path).map_err(|e| AppError::io(path, e))?;
+        Self::parse(&content, ManifestFormat::from_path(path))
+    }
+
+    /// 解析清单文本
+    pub fn parse(content: &str, format: ManifestFormat) -> Result<ConfigManifest, AppError> {
+        Self::parse_with_env(content, format, |name| std::env::var(name).ok())
+    }
+
+    fn parse_with_env(
+        content: &str,
+        format: ManifestFormat,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<ConfigManifest, AppError> {
+        let mut raw: Value = match format {
+            ManifestFormat::Yaml => serde_yaml::from_str(content)
+                .map_err(|e| AppError::Config(format!("解析 YAML 清单失败: {e}")))?,
+            ManifestFormat::Toml => {
+                let value: toml::Value = toml::from_str(content)
+                    .map_err(|e| AppError::Config(format!("解析 TOML 清单失败: {e}")))?;
+                serde_json::to_value(value).map_err(|e| AppError::JsonSerialize { source: e })?
+            }
+            ManifestFormat::Json => serde_json::from_str(content)
+                .map_err(|e| AppError::Config(format!("解析 JSON 清单失败: {e}")))?,
+        };
+
+        let mut missing = Vec::new();
+        interpolate_env(&mut raw, &lookup, &mut missing);
+        if !missing.is_empty() {
+            missing.sort();
+            missing.dedup();
+            return Err(AppError::Config(format!(
+                "清单引用的环境变量未设置: {}",
+                missing.join(", ")
+            )));
+        }
+
+        let manifest: ConfigManifest = serde_json::from_value(raw)
+            .map_err(|e| AppError::Config(format!("清单结构无效: {e}")))?;
+        Self::validate(&manifest)?;
+        Ok(manifest)
+    }
+
+    fn validate(manifest: &ConfigManifest) -> Result<(), AppError> {
+        if manifest.version != MANIFEST_VERSION {
+            return Err(AppError::Config(format!(
+                "不支持的清单版本 {}，当前仅支持 {MANIFEST_VERSION}",
+                manifest.version
+            )));
+        }
+
+        let app_keys = manifest
+            .providers
+            .iter()
+            .flat_map(|m| m.keys())
+            .chain(manifest.prompts.iter().flat_map(|m| m.keys()));
+        for key in app_keys {
+            AppType::from_str(key)?;
+        }
+        let proxy_keys = manifest
+            .failover
+            .iter()
+            .flat_map(|m| m.keys())
+            .chain(manifest.proxy.iter().flat_map(|m| m.keys()));
+        for key in proxy_keys {
+            if !matches!(key.as_str(), "claude" | "codex" | "gemini") {
+                return Err(AppError::Config(format!(
+                    "故障转移/代理配置仅支持 claude、codex、gemini，收到: {key}"
+                )));
+            }
+        }
+
+        if let Some(providers) = &manifest.providers {
+            for (app, list) in providers {
+                let mut seen = HashSet::new();
+                for item in list {
+                    if !seen.insert(item.provider.id.as_str()) {
+                        return Err(AppError::Config(format!(
+                            "清单中 {app} 供应商 ID 重复: {}",
+                            item.provider.id
+                        )));
+                    }
+                }
+                if list.iter().filter(|p| p.current).count() > 1 {
+                    return Err(AppError::Config(format!(
+                        "清单中 {app} 只能有一个 current 供应商"
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 计算清单与当前状态的差异
+    pub async fn plan(
+        db: &Database,
+        manifest: &ConfigManifest,
+        prune_override: Option<bool>,
+    ) -> Result<ManifestPlan, AppError> {
+        let prune = prune_override.unwrap_or(manifest.prune);
+        let mut changes = Vec::new();
+        let mut unchanged = 0usize;
+        let mut record = |change: Option<ManifestChange>| match change {
+            Some(c) => changes.push(c),
+            None => unchanged += 1,
+        };
+
+        // Skill 仓库
+        if let Some(repos) = manifest.skills.as_ref().and_then(|s| s.repos.as_ref()) {
+            let current: HashMap<(String, String), SkillRepo> = db
+                .get_skill_repos()?
+                .into_iter()
+                .map(|r| ((r.owner.clone(), r.name.clone()), r))
+                .collect();
+            let mut declared = HashSet::new();
+            for repo in repos {
+                let key = (repo.owner.clone(), repo.name.clone());
+                declared.insert(key.clone());
+                let desired = json!({ "branch": repo.branch, "enabled": repo.enabled });
+                let existing = current
+                    .get(&key)
+                    .map(|r| json!({ "branch": r.branch, "enabled": r.enabled }));
+                record(diff_change(
+                    ManifestResource::SkillRepo,
+                    None,
+                    &format!("{}/{}", repo.owner, repo.name),
+                    &desired,
+                    existing.as_ref(),
+                ));
+            }
+            if prune {
+                for key in current.keys().filter(|k| !declared.contains(*k)) {
+                    record(Some(delete_change(
+                        ManifestResource::SkillRepo,
+                        None,
+                        &format!("{}/{}", key.0, key.1),
+                    )));
+                }
+            }
+        }
+
+        // 已安装 Skills
+        if let Some(skills) = manifest.skills.as_ref().and_then(|s| s.installed.as_ref()) {
+            let current = db.get_all_installed_skills()?;
+            let mut declared = HashSet::new();
+            for skill in skills {
+                declared.insert(skill.id.as_str());
+                let desired = json!({ "apps": SkillApps::from_labels(&skill.apps) });
+                let existing = current.get(&skill.id).map(|s| json!({ "apps": s.apps }));
+                record(diff_change(
+                    ManifestResource::Skill,
+                    None,
+                    &skill.id,
+                    &desired,
+                    existing.as_ref(),
+                ));
+            }
+            if prune {
+                for id in current.keys().filter(|id| !declared.contains(id.as_str())) {
+                    record(Some(delete_change(ManifestResource::Skill, None, id)));
+                }
+            }
+        }
+
+        // MCP 服务器
+        if let Some(servers) = &manifest.mcp_servers {
+            let current = db.get_all_mcp_servers()?;
+            let mut declared = HashSet::new();
+            for server in servers {
+                declared.insert(server.id.as_str());
+                let desired = to_value(server)?;
+                let existing = current.get(&server.id).map(to_value).transpose()?;
+                record(diff_change(
+                    ManifestResource::McpServer,
+                    None,
+                    &server.id,
+                    &desired,
+                    existing.as_ref(),
+                ));
+            }
+            if prune {
+                for id in current.keys().filter(|id| !declared.contains(id.as_str())) {
+                    record(Some(delete_change(ManifestResource::McpServer, None, id)));
+                }
+            }
+        }
+
+        // 统一供应商
+        if let Some(universal) = &manifest.universal_providers {
+            let current = db.get_all_universal_providers()?;
+            let mut declared = HashSet::new();
+            for provider in universal {
+                declared.insert(provider.id.as_str());
+                let desired = universal_view(provider, provider.meta.is_some())?;
+                let existing = current
+                    .get(&provider.id)
+                    .map(|p| universal_view(p, provider.meta.is_some()))
+                    .transpose()?;
+                record(diff_change(
+                    ManifestResource::UniversalProvider,
+                    None,
+                    &provider.id,
+                    &desired,
+                    existing.as_ref(),
+                ));
+            }
+            if prune {
+                for id in current.keys().filter(|id| !declared.contains(id.as_str())) {
+                    record(Some(delete_change(
+                        ManifestResource::UniversalProvider,
+                        None,
+                        id,
+                    )));
+                }
+            }
+        }
+
+        // 供应商
+        if let Some(providers) = &manifest.providers {
+            for (app, list) in providers {
+                let app_type = AppType::from_str(app)?;
+                let current = db.get_all_providers(app)?;
+                let current_id = if app_type.is_additive_mode() {
+                    None
+                } else {
+                    crate::settings::get_effective_current_provider(db, &app_type)?
+                };
+                for (index, item) in list.iter().enumerate() {
+                    let tracks_current = item.current && !app_type.is_additive_mode();
+                    let desired = provider_view(
+                        &item.provider,
+                        index,
+                        item.provider.meta.is_some(),
+                        tracks_current.then_some(true),
+                    )?;
+                    let existing = current
+                        .get(&item.provider.id)
+                        .map(|p| {
+                            provider_view(
+                                p,
+                                p.sort_index.unwrap_or(usize::MAX),
+                                item.provider.meta.is_some(),
+                                tracks_current
+                                    .then(|| current_id.as_deref() == Some(p.id.as_str())),
+                            )
+                        })
+                        .transpose()?;
+                    record(diff_change(
+                        ManifestResource::Provider,
+                        Some(app),
+                        &item.provider.id,
+                        &desired,
+                        existing.as_ref(),
+                    ));
+                }
+                if prune {
+                    let declared: HashSet<&str> =
+                        list.iter().map(|p| p.provider.id.as_str()).collect();
+                    for id in current.keys() {
+                        // 统一供应商生成的子供应商由 universalProviders 段管理
+                        if declared.contains(id.as_str()) || id.starts_with("universal-") {
+                            continue;
+                        }
+                        record(Some(delete_change(
+                            ManifestResource::Provider,
+                            Some(app),
+                            id,
+                        )));
+                    }
+                }
+            }
+        }
+
+        // 提示词
+        if let Some(prompts) = &manifest.prompts {
+            for (app, list) in prompts {
+                let current = db.get_prompts(app)?;
+                let mut declared = HashSet::new();
+                for prompt in list {
+                    declared.insert(prompt.id.as_str());
+                    let desired = prompt_view(prompt);
+                    let existing = current.get(&prompt.id).map(prompt_view);
+                    record(diff_change(
+                        ManifestResource::Prompt,
+                        Some(app),
+                        &prompt.id,
+                        &desired,
+                        existing.as_ref(),
+                    ));
+                }
+                if prune {
+                    for id in current.keys().filter(|id| !declared.contains(id.as_str())) {
+                        record(Some(delete_change(ManifestResource::Prompt, Some(app), id)));
+                    }
+                }
+            }
+        }
+
+        // 故障转移队列（声明即完整队列，无需 prune）
+        if let Some(failover) = &manifest.failover {
+            for (app, ids) in failover {
+                let declared: HashSet<&str> = ids.iter().map(String::as_str).collect();
+                let queued: HashSet<String> = db
+                    .get_failover_queue(app)?
+                    .into_iter()
+                    .map(|item| item.provider_id)
+                    .collect();
+                for id in ids {
+                    if queued.contains(id) {
+                        record(None);
+                    } else {
+                        record(Some(ManifestChange {
+                            resource: ManifestResource::FailoverQueue,
+                            action: ManifestAction::Create,
+                            app: Some(app.clone()),
+                            id: id.clone(),
+                            fields: Vec::new(),
+                        }));
+                    }
+                }
+                let mut removed: Vec<&String> = queued
+                    .iter()
+                    .filter(|id| !declared.contains(id.as_str()))
+                    .collect();
+                removed.sort();
+                for id in removed {
+                    record(Some(delete_change(
+                        ManifestResource::FailoverQueue,
+                        Some(app),
+                        id,
+                    )));
+                }
+            }
+        }
+
+        // 代理配置
+        if let Some(proxy) = &manifest.proxy {
+            for (app, overrides) in proxy {
+                let current = db.get_proxy_config_for_app(app).await?;
+                let desired = to_value(overrides)?;
+                let mut existing = to_value(&ManifestProxyConfig {
+                    auto_failover_enabled: Some(current.auto_failover_enabled),
+                    max_retries: Some(current.max_retries),
+                    streaming_first_byte_timeout: Some(current.streaming_first_byte_timeout),
+                    streaming_idle_timeout: Some(current.streaming_idle_timeout),
+                    non_streaming_timeout: Some(current.non_streaming_timeout),
+                    circuit_failure_threshold: Some(current.circuit_failure_threshold),
+                    circuit_success_threshold: Some(current.circuit_success_threshold),
+                    circuit_timeout_seconds: Some(current.circuit_timeout_seconds),
+                    circuit_error_rate_threshold: Some(current.circuit_error_rate_threshold),
+                    circuit_min_requests: Some(current.circuit_min_requests),
+                })?;
+                retain_keys(&mut existing, &desired);
+                record(diff_change(
+                    ManifestResource::ProxyConfig,
+                    Some(app),
+                    app,
+                    &desired,
+                    Some(&existing),
+                ));
+            }
+        }
+
+        Ok(ManifestPlan {
+            changes,
+            unchanged,
+            prune,
+        })
+    }
+
+    /// 将当前状态收敛到清单
+    ///
+    /// 单条变更失败不会中断后续变更，失败项记录在结果中。
+    pub async fn apply(
+        state: &AppState,
+        skill_service: &SkillService,
+        manifest: &ConfigManifest,
+        prune_override: Option<bool>,
+    ) -> Result<ManifestApplyResult, AppError> {
+        let plan = Self::plan(&state.db, manifest, prune_override).await?;
+        let mut applied = Vec::new();
+        let mut failed = Vec::new();
+
+        for change in plan.changes {
+            match Self::apply_change(state, skill_service, manifest, &change).await {
+                Ok(()) => applied.push(change),
+                Err(e) => {
+                    log::warn!(
+                        "[Manifest] 应用 {:?} {} 失败: {e}",
+                        change.resource,
+                        change.id
+                    );
+                    failed.push(ManifestFailure {
+                        change,
+                        error: e.to_string(),
+                    });
+                }
+            }
+        }
+
+        log::info!(
+            "[Manifest] 已应用 {} 项变更，失败 {} 项，未变更 {} 项",
+            applied.len(),
+            failed.len(),
+            plan.unchanged
+        );
+
+        Ok(ManifestApplyResult {
+            applied,
+            failed,
+            unchanged: plan.unchanged,
+        })
+    }
+
+    async fn apply_change(
+        state: &AppState,
+        skill_service: &SkillService,
+        manifest: &ConfigManifest,
+        change: &ManifestChange,
+    ) -> Result<(), AppError> {
+        let app = change.app.as_deref().unwrap_or_default();
+        match (change.resource, change.action) {
+            (ManifestResource::SkillRepo, ManifestAction::Delete) => {
+                let (owner, name) = split_repo_key(&change.id)?;
+                state.db.delete_skill_repo(owner, name)
+            }
+            (ManifestResource::SkillRepo, _) => {
+                let repo = manifest
+                    .skills
+                    .as_ref()
+                    .and_then(|s| s.repos.as_ref())
+                    .and_then(|repos| {
+                        repos
+                            .iter()
+                            .find(|r| format!("{}/{}", r.owner, r.name) == change.id)
+                    })
+                    .ok_or_else(|| missing_entry(change))?;
+                state.db.save_skill_repo(&SkillRepo::from(repo))
+            }
+            (ManifestResource::Skill, ManifestAction::Delete) => {
+                SkillService::uninstall(&state.db, &change.id)
+                    .map_err(|e| AppError::Message(e.to_string()))
+            }
+            (ManifestResource::Skill, action) => {
+                let skill = manifest
+                    .skills
+                    .as_ref()
+                    .and_then(|s| s.installed.as_ref())
+                    .and_then(|skills| skills.iter().find(|s| s.id == change.id))
+                    .ok_or_else(|| missing_entry(change))?;
+                let desired = SkillApps::from_labels(&skill.apps);
+                if action == ManifestAction::Create {
+                    Self::install_skill(state, skill_service, &skill.id, &desired).await?;
+                }
+                Self::converge_skill_apps(&state.db, &skill.id, &desired)
+            }
+            (ManifestResource::McpServer, ManifestAction::Delete) => {
+                McpService::delete_server(state, &change.id).map(|_| ())
+            }
+            (ManifestResource::McpServer, _) => {
+                let server = manifest
+                    .mcp_servers
+                    .as_ref()
+                    .and_then(|servers| servers.iter().find(|s| s.id == change.id))
+                    .ok_or_else(|| missing_entry(change))?;
+                McpService::upsert_server(state, server.clone())
+            }
+            (ManifestResource::UniversalProvider, ManifestAction::Delete) => {
+                ProviderService::delete_universal(state, &change.id).map(|_| ())
+            }
+            (ManifestResource::UniversalProvider, _) => {
+                let mut provider = manifest
+                    .universal_providers
+                    .as_ref()
+                    .and_then(|list| list.iter().find(|p| p.id == change.id))
+                    .cloned()
+                    .ok_or_else(|| missing_entry(change))?;
+                if let Some(existing) = state.db.get_universal_provider(&provider.id)? {
+                    provider.created_at = existing.created_at.or(provider.created_at);
+                    if provider.meta.is_none() {
+                        provider.meta = existing.meta;
+                    }
+                }
+                ProviderService::upsert_universal(state, provider)?;
+                ProviderService::sync_universal_to_apps(state, &change.id).map(|_| ())
+            }
+            (ManifestResource::Provider, ManifestAction::Delete) => {
+                ProviderService::delete(state, AppType::from_str(app)?, &change.id)
+            }
+            (ManifestResource::Provider, action) => {
+                let app_type = AppType::from_str(app)?;
+                let (index, item) = manifest
+                    .providers
+                    .as_ref()
+                    .and_then(|p| p.get(app))
+                    .and_then(|list| {
+                        list.iter()
+                            .enumerate()
+                            .find(|(_, p)| p.provider.id == change.id)
+                    })
+                    .ok_or_else(|| missing_entry(change))?;
+                let mut provider = item.provider.clone();
+                provider.sort_index = Some(index);
+
+                if action == ManifestAction::Create {
+                    provider.in_failover_queue = false;
+                    if provider.created_at.is_none() {
+                        provider.created_at = Some(chrono::Utc::now().timestamp_millis());
+                    }
+                    ProviderService::add(state, app_type.clone(), provider)?;
+                } else if change.fields.iter().any(|f| f != "current") {
+                    let existing = state
+                        .db
+                        .get_provider_by_id(&change.id, app)?
+                        .ok_or_else(|| missing_entry(change))?;
+                    provider.created_at = existing.created_at;
+                    provider.in_failover_queue = existing.in_failover_queue;
+                    if provider.meta.is_none() {
+                        provider.meta = existing.meta;
+                    }
+                    ProviderService::update(state, app_type.clone(), provider)?;
+                }
+
+                if item.current && !app_type.is_additive_mode() {
+                    let current = ProviderService::current(state, app_type.clone())?;
+                    if current != change.id {
+                        ProviderService::switch(state, app_type, &change.id)?;
+                    }
+                }
+                Ok(())
+            }
+            (ManifestResource::Prompt, ManifestAction::Delete) => {
+                PromptService::delete_prompt(state, AppType::from_str(app)?, &change.id)
+            }
+            (ManifestResource::Prompt, _) => {
+                let app_type = AppType::from_str(app)?;
+                let mut prompt = manifest
+                    .prompts
+                    .as_ref()
+                    .and_then(|p| p.get(app))
+                    .and_then(|list| list.iter().find(|p| p.id == change.id))
+                    .cloned()
+                    .ok_or_else(|| missing_entry(change))?;
+                let now = chrono::Utc::now().timestamp();
+                let existing = state.db.get_prompts(app)?.shift_remove(&change.id);
+                prompt.created_at = existing
+                    .as_ref()
+                    .and_then(|p| p.created_at)
+                    .or(prompt.created_at)
+                    .or(Some(now));
+                prompt.updated_at = Some(now);
+                let enabled = prompt.enabled;
+                PromptService::upsert_prompt(state, app_type.clone(), &change.id, prompt)?;
+                if enabled {
+                    // 启用需保证同应用仅有一个已启用提示词
+                    PromptService::enable_prompt(state, app_type, &change.id)?;
+                }
+                Ok(())
+            }
+            (ManifestResource::FailoverQueue, ManifestAction::Delete) => {
+                state.db.remove_from_failover_queue(app, &change.id)
+            }
+            (ManifestResource::FailoverQueue, _) => {
+                if state.db.get_provider_by_id(&change.id, app)?.is_none() {
+                    return Err(AppError::InvalidInput(format!(
+                        "故障转移队列引用了不存在的供应商: {app}/{}",
+                        change.id
+                    )));
+                }
+                state.db.add_to_failover_queue(app, &change.id)
+            }
+            (ManifestResource::ProxyConfig, _) => {
+                let overrides = manifest
+                    .proxy
+                    .as_ref()
+                    .and_then(|p| p.get(app))
+                    .ok_or_else(|| missing_entry(change))?;
+                let mut config = state.db.get_proxy_config_for_app(app).await?;
+                if let Some(v) = overrides.auto_failover_enabled {
+                    config.auto_failover_enabled = v;
+                }
+                if let Some(v) = overrides.max_retries {
+                    config.max_retries = v;
+                }
+                if let Some(v) = overrides.streaming_first_byte_timeout {
+                    config.streaming_first_byte_timeout = v;
+                }
+                if let Some(v) = overrides.streaming_idle_timeout {
+                    config.streaming_idle_timeout = v;
+                }
+                if let Some(v) = overrides.non_streaming_timeout {
+                    config.non_streaming_timeout = v;
+                }
+                if let Some(v) = overrides.circuit_failure_threshold {
+                    config.circuit_failure_threshold = v;
+                }
+                if let Some(v) = overrides.circuit_success_threshold {
+                    config.circuit_success_threshold = v;
+                }
+                if let Some(v) = overrides.circuit_timeout_seconds {
+                    config.circuit_timeout_seconds = v;
+                }
+                if let Some(v) = overrides.circuit_error_rate_threshold {
+                    config.circuit_error_rate_threshold = v;
+                }
+                if let Some(v) = overrides.circuit_min_requests {
+                    config.circuit_min_requests = v;
+                }
+                state.db.update_proxy_config_for_app(config).await
+            }
+        }
+    }
+
+    /// 从已配置的仓库中发现并安装 Skill
+    async fn install_skill(
+        state: &AppState,
+        skill_service: &SkillService,
+        id: &str,
+        desired: &SkillApps,
+    ) -> Result<(), AppError> {
+        let repos = state.db.get_skill_repos()?;
+        let available = skill_service
+            .discover_available(repos)
+            .await
+            .map_err(|e| AppError::Message(e.to_string()))?;
+        let skill = available
+            .into_iter()
+            .find(|s| s.key == id)
+            .ok_or_else(|| AppError::Message(format!("在已启用的 Skill 仓库中未找到 {id}")))?;
+        let first_app = desired
+            .enabled_apps()
+            .into_iter()
+            .next()
+            .unwrap_or(AppType::Claude);
+        skill_service
+            .install(&state.db, &skill, &first_app)
+            .await
+            .map_err(|e| AppError::Message(e.to_string()))?;
+        Ok(())
+    }
+
+    fn converge_skill_apps(
+        db: &Arc<Database>,
+        id: &str,
+        desired: &SkillApps,
+    ) -> Result<(), AppError> {
+        let current = db
+            .get_installed_skill(id)?
+            .ok_or_else(|| AppError::Message(format!("Skill 未安装: {id}")))?;
+        for app in [
+            AppType::Claude,
+            AppType::Codex,
+            AppType::Gemini,
+            AppType::OpenCode,
+        ] {
+            let want = desired.is_enabled_for(&app);
+            if current.apps.is_enabled_for(&app) != want {
+                SkillService::toggle_app(db, id, &app, want)
+                    .map_err(|e| AppError::Message(e.to_string()))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 递归替换字符串中的 `${VAR}` 占位符
+fn interpolate_env(
+    value: &mut Value,
+    lookup: &impl Fn(&str) -> Option<String>,
+    missing: &mut Vec<String>,
+) {
+    match value {
+        Value::String(s) if s.contains("${") => {
+            *s = interpolate_str(s, lookup, missing);
+        }
+        Value::Array(items) => {
+            for item in items {
+                interpolate_env(item, lookup, missing);
+            }
+        }
+        Value::Object(map) => {
+            for item in map.values_mut() {
+                interpolate_env(item, lookup, missing);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn interpolate_str(
+    input: &str,
+    lookup: &impl Fn(&str) -> Option<String>,
+    missing: &mut Vec<String>,
+) -> String {
+    static RE: once_cell::sync::Lazy<Regex> = once_cell::sync::Lazy::new(|| {
+        Regex::new(r"\$?\$\{([A-Za-z_][A-Za-z0-9_]*)\}").expect("valid env placeholder regex")
+    });
+
+    RE.replace_all(input, |caps: &regex::Captures| {
+        let whole = &caps[0];
+        if let Some(literal) = whole.strip_prefix('$').filter(|w| w.starts_with('$')) {
+            return literal.to_string();
+        }
+        let name = &caps[1];
+        lookup(name).unwrap_or_else(|| {
+            missing.push(name.to_string());
+            String::new()
+        })
+    })
+    .into_owned()
+}
+
+fn to_value<T: Serialize>(value: &T) -> Result<Value, AppError> {
+    serde_json::to_value(value).map_err(|e| AppError::JsonSerialize { source: e })
+}
+
+/// 仅保留 `template` 中存在的键
+fn retain_keys(value: &mut Value, template: &Value) {
+    if let (Value::Object(map), Value::Object(keys)) = (value, template) {
+        map.retain(|k, _| keys.contains_key(k));
+    }
+}
+
+/// 对比期望值与当前值，返回变更（一致时返回 None）
+fn diff_change(
+    resource: ManifestResource,
+    app: Option<&str>,
+    id: &str,
+    desired: &Value,
+    existing: Option<&Value>,
+) -> Option<ManifestChange> {
+    let (action, fields) = match existing {
+        None => (ManifestAction::Create, Vec::new()),
+        Some(current) => {
+            let fields = changed_fields(desired, current);
+            if fields.is_empty() {
+                return None;
+            }
+            (ManifestAction::Update, fields)
+        }
+    };
+    Some(ManifestChange {
+        resource,
+        action,
+        app: app.map(str::to_string),
+        id: id.to_string(),
+        fields,
+    })
+}
+
+fn delete_change(resource: ManifestResource, app: Option<&str>, id: &str) -> ManifestChange {
+    ManifestChange {
+        resource,
+        action: ManifestAction::Delete,
+        app: app.map(str::to_string),
+        id: id.to_string(),
+        fields: Vec::new(),
+    }
+}
+
+/// 顶层字段对比（null 与缺失视为相同）
+fn changed_fields(desired: &Value, current: &Value) -> Vec<String> {
+    match (desired, current) {
+        (Value::Object(d), Value::Object(c)) => {
+            let mut keys: Vec<&String> = d.keys().chain(c.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            keys.into_iter()
+                .filter(|k| {
+                    let left = d.get(*k).unwrap_or(&Value::Null);
+                    let right = c.get(*k).unwrap_or(&Value::Null);
+                    left != right
+                })
+                .cloned()
+                .collect()
+        }
+        _ if desired != current => vec!["value".to_string()],
+        _ => Vec::new(),
+    }
+}
+
+fn provider_view(
+    provider: &Provider,
+    sort_index: usize,
+    include_meta: bool,
+    current: Option<bool>,
+) -> Result<Value, AppError> {
+    let mut view = json!({
+        "name": provider.name,
+        "settingsConfig": provider.settings_config,
+        "websiteUrl": provider.website_url,
+        "category": provider.category,
+        "notes": provider.notes,
+        "icon": provider.icon,
+        "iconColor": provider.icon_color,
+        "sortIndex": sort_index,
+    });
+    if include_meta {
+        view["meta"] = to_value(&provider.meta)?;
+    }
+    if let Some(current) = current {
+        view["current"] = Value::Bool(current);
+    }
+    Ok(view)
+}
+
+fn universal_view(provider: &UniversalProvider, include_meta: bool) -> Result<Value, AppError> {
+    let mut view = to_value(provider)?;
+    if let Value::Object(map) = &mut view {
+        map.remove("createdAt");
+        map.remove("sortIndex");
+        if !include_meta {
+            map.remove("meta");
+        }
+    }
+    Ok(view)
+}
+
+fn prompt_view(prompt: &Prompt) -> Value {
+    json!({
+        "name": prompt.name,
+        "content": prompt.content,
+        "description": prompt.description,
+        "enabled": prompt.enabled,
+    })
+}
+
+fn split_repo_key(key: &str) -> Result<(&str, &str), AppError> {
+    key.split_once('/')
+        .ok_or_else(|| AppError::InvalidInput(format!("无效的 Skill 仓库标识: {key}")))
+}
+
+fn missing_entry(change: &ManifestChange) -> AppError {
+    AppError::Message(format!("清单中未找到 {:?} {}", change.resource, change.id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(name: &str) -> Option<String> {
+        match name {
+            "TEAM_RELAY_KEY" => Some("sk-team".to_string()),
+            _ => None,
+        }
+    }
+
+    const SAMPLE: &str = r#"
+version: 1
+providers:
+  claude:
+    - id: team-relay
+      name: Team Relay
+      current: true
+      settingsConfig:
+        env:
+          ANTHROPIC_BASE_URL: https://relay.example.com
+          ANTHROPIC_AUTH_TOKEN: ${TEAM_RELAY_KEY}
+mcpServers:
+  - id: fetch
+    name: fetch
+    server:
+      type: stdio
+      command: uvx
+      args: ["mcp-server-fetch"]
+    apps: { claude: true, codex: false, gemini: false }
+prompts:
+  claude:
+    - id: team
+      name: Team rules
+      content: "Literal $${HOME} stays"
+      enabled: false
+failover:
+  claude: [team-relay]
+proxy:
+  claude:
+    maxRetries: 2
+"#;
+
+    #[test]
+    fn parse_interpolates_env_and_keeps_escapes() -> Result<(), AppError> {
+        let manifest = ManifestService::parse_with_env(SAMPLE, ManifestFormat::Yaml, env)?;
+        let providers = manifest.providers.expect("providers");
+        let relay = &providers["claude"][0];
+        assert!(relay.current);
+        assert_eq!(
+            relay.provider.settings_config["env"]["ANTHROPIC_AUTH_TOKEN"],
+            "sk-team"
+        );
+        let prompts = manifest.prompts.expect("prompts");
+        assert_eq!(prompts["claude"][0].content, "Literal ${HOME} stays");
+        Ok(())
+    }
+
+    #[test]
+    fn parse_reports_missing_env_vars() {
+        let err = ManifestService::parse_with_env(SAMPLE, ManifestFormat::Yaml, |_| None)
+            .expect_err("missing env should fail");
+        assert!(err.to_string().contains("TEAM_RELAY_KEY"));
+    }
+
+    #[test]
+    fn parse_rejects_unknown_app_and_duplicate_ids() {
+        let unknown = "version: 1\nproviders:\n  cursor: []\n";
+        assert!(ManifestService::parse_with_env(unknown, ManifestFormat::Yaml, env).is_err());
+
+        let duplicate = r#"
+providers:
+  codex:
+    - { id: a, name: A, settingsConfig: {} }
+    - { id: a, name: B, settingsConfig: {} }
+"#;
+        assert!(ManifestService::parse_with_env(duplicate, ManifestFormat::Yaml, env).is_err());
+    }
+
+    #[test]
+    fn parse_toml_manifest() -> Result<(), AppError> {
+        let content = r#"
+version = 1
+
+[[mcpServers]]
+id = "fetch"
+name = "fetch"
+server = { type = "stdio", command = "uvx" }
+apps = { claude = true, codex = true, gemini = false }
+"#;
+        let manifest = ManifestService::parse_with_env(content, ManifestFormat::Toml, env)?;
+        assert_eq!(manifest.mcp_servers.expect("servers")[0].id, "fetch");
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn plan_reports_creates_then_unchanged() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let mut manifest = ManifestService::parse_with_env(SAMPLE, ManifestFormat::Yaml, env)?;
+        // current 依赖设备级 settings，单元测试中不参与对比
+        for item in manifest
+            .providers
+            .iter_mut()
+            .flat_map(|p| p.values_mut())
+            .flatten()
+        {
+            item.current = false;
+        }
+
+        let plan = ManifestService::plan(&db, &manifest, None).await?;
+        let resources: Vec<(ManifestResource, ManifestAction)> = plan
+            .changes
+            .iter()
+            .map(|c| (c.resource, c.action))
+            .collect();
+        assert!(resources.contains(&(ManifestResource::Provider, ManifestAction::Create)));
+        assert!(resources.contains(&(ManifestResource::McpServer, ManifestAction::Create)));
+        assert!(resources.contains(&(ManifestResource::Prompt, ManifestAction::Create)));
+        assert!(resources.contains(&(ManifestResource::FailoverQueue, ManifestAction::Create)));
+        assert!(resources.contains(&(ManifestResource::ProxyConfig, ManifestAction::Update)));
+
+        // 直接写入数据库模拟 apply 之后的状态
+        let providers = manifest.providers.as_ref().expect("providers");
+        let mut relay = providers["claude"][0].provider.clone();
+        relay.sort_index = Some(0);
+        db.save_provider("claude", &relay)?;
+        db.add_to_failover_queue("claude", "team-relay")?;
+        for server in manifest.mcp_servers.as_ref().expect("servers") {
+            db.save_mcp_server(server)?;
+        }
+        for prompt in &manifest.prompts.as_ref().expect("prompts")["claude"] {
+            db.save_prompt("claude", prompt)?;
+        }
+        let mut proxy = db.get_proxy_config_for_app("claude").await?;
+        proxy.max_retries = 2;
+        db.update_proxy_config_for_app(proxy).await?;
+
+        let plan = ManifestService::plan(&db, &manifest, None).await?;
+        assert!(
+            plan.changes.is_empty(),
+            "unexpected changes: {:?}",
+            plan.changes
+        );
+        assert_eq!(plan.unchanged, 5);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn plan_lists_changed_fields_and_prunes() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let mut stale = Provider::with_id(
+            "team-relay".to_string(),
+            "Old Name".to_string(),
+            json!({}),
+            None,
+        );
+        stale.sort_index = Some(0);
+        db.save_provider("claude", &stale)?;
+        db.save_provider(
+            "claude",
+            &Provider::with_id("legacy".to_string(), "Legacy".to_string(), json!({}), None),
+        )?;
+
+        let mut manifest = ManifestService::parse_with_env(SAMPLE, ManifestFormat::Yaml, env)?;
+        for item in manifest
+            .providers
+            .iter_mut()
+            .flat_map(|p| p.values_mut())
+            .flatten()
+        {
+            item.current = false;
+        }
+        let plan = ManifestService::plan(&db, &manifest, Some(true)).await?;
+
+        let update = plan
+            .changes
+            .iter()
+            .find(|c| c.resource == ManifestResource::Provider && c.id == "team-relay")
+            .expect("provider update");
+        assert_eq!(update.action, ManifestAction::Update);
+        assert!(update.fields.contains(&"name".to_string()));
+        assert!(update.fields.contains(&"settingsConfig".to_string()));
+
+        assert!(plan
+            .changes
+            .iter()
+            .any(|c| c.resource == ManifestResource::Provider
+                && c.action == ManifestAction::Delete
+                && c.id == "legacy"));
+        Ok(())
+    }
+}
@@ -1,14 +1,29 @@
+pub mod balance_reconciliation;
 pub mod config;
+pub mod cost_attribution;
+pub mod currency;
 pub mod env_checker;
 pub mod env_manager;
+pub mod health_probe;
+pub mod manifest;
 pub mod mcp;
+pub mod model_fidelity;
+pub mod notification;
 pub mod omo;
+pub mod pricing;
+pub mod pricing_catalog;
 pub mod prompt;
 pub mod provider;
 pub mod proxy;
+pub mod quota_guard;
 pub mod skill;
 pub mod speedtest;
 pub mod stream_check;
+pub mod transcript_usage;
+pub mod usage_export;
+pub mod usage_forecast;
+pub mod usage_poller;
+pub mod usage_rollup;
 pub mod usage_stats;
 pub mod webdav;
 pub mod webdav_auto_sync;
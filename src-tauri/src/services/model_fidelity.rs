@@ -0,0 +1,420 @@
+//! 上游模型替换检测
+//!
+//! 部分中转会悄悄用更便宜的模型响应请求。检测分两部分：
+//! - 代理请求：比较映射后发往上游的模型与响应中声明的模型，不一致时在请求日志中标记
+//! - 指纹探测：用一组固定提示词经 `StreamCheckService` 发起小请求，比较上游声明的模型
+//!
+//! 两者汇总为按供应商的"模型一致率"。响应未声明模型时无法判断，不计为替换。
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::app_config::AppType;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::services::stream_check::{StreamCheckConfig, StreamCheckService};
+use crate::store::AppState;
+
+/// 参与指纹探测的应用（与流式检查支持的应用一致）
+const PROBE_APPS: [AppType; 3] = [AppType::Claude, AppType::Codex, AppType::Gemini];
+/// 关闭时重新读取配置的间隔
+const DISABLED_RECHECK_SECS: u64 = 60;
+/// 探测记录保留天数
+const PROBE_KEEP_DAYS: i64 = 30;
+
+/// 指纹探测使用的固定提示词
+///
+/// 覆盖不同的请求形态，单次探测只读取首个流式事件，消耗很少
+pub const FINGERPRINT_PROMPTS: [&str; 3] = [
+    "Who are you?",
+    "Reply with the single word: ok",
+    "What is 17 * 23? Answer with the number only.",
+];
+
+/// 模型替换检测配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ModelFidelityConfig {
+    /// 是否在代理请求中比对返回模型
+    pub detect: bool,
+    /// 检测到替换时是否按失败计入熔断器
+    pub treat_as_failure: bool,
+    /// 是否在后台定时执行指纹探测
+    pub probe_enabled: bool,
+    /// 指纹探测间隔（分钟）
+    pub probe_interval_mins: u32,
+}
+
+impl Default for ModelFidelityConfig {
+    fn default() -> Self {
+        Self {
+            detect: true,
+            treat_as_failure: false,
+            probe_enabled: false,
+            probe_interval_mins: 360,
+        }
+    }
+}
+
+impl ModelFidelityConfig {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.probe_interval_mins < 10 {
+            return Err(AppError::InvalidInput(
+                "探测间隔不能小于 10 分钟".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 单次指纹探测结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelProbeResult {
+    pub app_type: String,
+    pub provider_id: String,
+    /// 使用的提示词序号（`FINGERPRINT_PROMPTS` 下标）
+    pub prompt_index: u32,
+    pub expected_model: Option<String>,
+    pub returned_model: Option<String>,
+    pub success: bool,
+    /// 返回模型是否与期望一致；请求失败或上游未声明模型时为空
+    pub matched: Option<bool>,
+    pub message: String,
+    pub tested_at: i64,
+}
+
+/// 一组"期望模型 → 实际返回模型"的替换记录
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelSubstitution {
+    pub expected_model: String,
+    pub returned_model: String,
+    pub request_count: u64,
+    pub last_seen_at: i64,
+}
+
+/// 供应商模型一致性统计
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelFidelityStats {
+    pub app_type: String,
+    pub provider_id: String,
+    pub provider_name: Option<String>,
+    /// 参与比对的请求数
+    pub checked_requests: u64,
+    /// 检测到替换的请求数
+    pub substituted_requests: u64,
+    /// 模型一致率（0-1），无比对数据时为空
+    pub fidelity: Option<f64>,
+    pub substitutions: Vec<ModelSubstitution>,
+    pub probe_count: u64,
+    /// 上游声明了模型、可以比对的探测次数
+    pub probe_verified: u64,
+    pub probe_mismatches: u64,
+    pub last_probe_at: Option<i64>,
+}
+
+impl ModelFidelityStats {
+    pub(crate) fn new(app_type: String, provider_id: String) -> Self {
+        Self {
+            app_type,
+            provider_id,
+            provider_name: None,
+            checked_requests: 0,
+            substituted_requests: 0,
+            fidelity: None,
+            substitutions: Vec::new(),
+            probe_count: 0,
+            probe_verified: 0,
+            probe_mismatches: 0,
+            last_probe_at: None,
+        }
+    }
+
+    /// 按请求与探测合计计算一致率
+    pub(crate) fn finish(&mut self) {
+        let total = self.checked_requests + self.probe_verified;
+        self.fidelity = (total > 0).then(|| {
+            let mismatched = self.substituted_requests + self.probe_mismatches;
+            (total - mismatched.min(total)) as f64 / total as f64
+        });
+    }
+}
+
+/// 归一化模型名，消除供应商前缀、区域前缀、日期版本等差异
+fn normalize_model(model: &str) -> String {
+    let mut name = model.trim().to_lowercase();
+    // openrouter 风格 "anthropic/claude-..."、Gemini "models/gemini-..."
+    if let Some(pos) = name.rfind('/') {
+        name = name[pos + 1..].to_string();
+    }
+    // Bedrock 风格 "us.anthropic.claude-...-v1:0"
+    for prefix in ["us.", "eu.", "apac.", "global."] {
+        if let Some(rest) = name.strip_prefix(prefix) {
+            name = rest.to_string();
+            break;
+        }
+    }
+    if let Some(rest) = name.strip_prefix("anthropic.") {
+        name = rest.to_string();
+    }
+    // ":0" / ":thinking" 等变体后缀，Vertex "@20250929" 版本后缀
+    if let Some(pos) = name.find([':', '@']) {
+        name.truncate(pos);
+    }
+    if let Some(rest) = name.strip_suffix("-latest") {
+        name = rest.to_string();
+    }
+    name = strip_version_suffix(&name);
+    name = strip_date_suffix(&name);
+    name.replace('.', "-")
+}
+
+/// 去掉日期之后的 "-v1" 版本后缀（Bedrock），"deepseek-v3" 之类的模型名保持不变
+fn strip_version_suffix(name: &str) -> String {
+    match name.rsplit_once("-v") {
+        Some((head, ver))
+            if !ver.is_empty()
+                && ver.chars().all(|c| c.is_ascii_digit())
+                && strip_date_suffix(head) != head =>
+        {
+            head.to_string()
+        }
+        _ => name.to_string(),
+    }
+}
+
+/// 去掉 "-20250929" 或 "-2024-08-06" 日期后缀
+fn strip_date_suffix(name: &str) -> String {
+    let is_digits = |s: &str, len: usize| s.len() == len && s.chars().all(|c| c.is_ascii_digit());
+    let parts: Vec<&str> = name.split('-').collect();
+    let n = parts.len();
+    if n > 1 && is_digits(parts[n - 1], 8) {
+        return parts[..n - 1].join("-");
+    }
+    if n > 3
+        && is_digits(parts[n - 3], 4)
+        && is_digits(parts[n - 2], 2)
+        && is_digits(parts[n - 1], 2)
+    {
+        return parts[..n - 3].join("-");
+    }
+    name.to_string()
+}
+
+/// 两个模型名是否指向同一模型
+pub fn models_match(expected: &str, returned: &str) -> bool {
+    normalize_model(expected) == normalize_model(returned)
+}
+
+/// 返回模型是否与期望模型不一致
+///
+/// 返回模型为空或 "unknown" 时无法判断，视为一致
+pub fn is_substitution(expected: &str, returned: &str) -> bool {
+    let returned = returned.trim();
+    if expected.trim().is_empty() || returned.is_empty() || returned == "unknown" {
+        return false;
+    }
+    !models_match(expected, returned)
+}
+
+/// 对单个供应商执行一轮指纹探测并保存结果
+pub async fn probe_provider(
+    db: &Database,
+    app_type: &AppType,
+    provider: &Provider,
+) -> Result<Vec<ModelProbeResult>, AppError> {
+    let base_config = db.get_stream_check_config()?;
+    let mut results = Vec::with_capacity(FINGERPRINT_PROMPTS.len());
+
+    for (index, prompt) in FINGERPRINT_PROMPTS.iter().enumerate() {
+        let config = StreamCheckConfig {
+            test_prompt: prompt.to_string(),
+            ..base_config.clone()
+        };
+        let result = match StreamCheckService::check_with_retry(app_type, provider, &config).await {
+            Ok(check) => {
+                let expected_model = Some(check.model_used).filter(|m| !m.is_empty());
+                let matched = match (&expected_model, &check.returned_model) {
+                    (Some(expected), Some(returned)) if check.success => {
+                        Some(!is_substitution(expected, returned))
+                    }
+                    _ => None,
+                };
+                ModelProbeResult {
+                    app_type: app_type.as_str().to_string(),
+                    provider_id: provider.id.clone(),
+                    prompt_index: index as u32,
+                    expected_model,
+                    returned_model: check.returned_model,
+                    success: check.success,
+                    matched,
+                    message: check.message,
+                    tested_at: check.tested_at,
+                }
+            }
+            Err(e) => ModelProbeResult {
+                app_type: app_type.as_str().to_string(),
+                provider_id: provider.id.clone(),
+                prompt_index: index as u32,
+                expected_model: None,
+                returned_model: None,
+                success: false,
+                matched: None,
+                message: e.to_string(),
+                tested_at: chrono::Utc::now().timestamp(),
+            },
+        };
+        db.save_model_probe_result(&result)?;
+        results.push(result);
+    }
+
+    Ok(results)
+}
+
+/// 需要探测的供应商：故障转移队列 + 当前供应商
+fn probe_targets(db: &Database, app_type: &AppType) -> Result<Vec<Provider>, AppError> {
+    let mut providers = db.get_failover_providers(app_type.as_str())?;
+    if let Some(current_id) = crate::settings::get_current_provider(app_type) {
+        if !providers.iter().any(|p| p.id == current_id) {
+            if let Some(current) = db.get_provider_by_id(&current_id, app_type.as_str())? {
+                providers.push(current);
+            }
+        }
+    }
+    Ok(providers)
+}
+
+/// 启动后台指纹探测任务
+///
+/// 每轮重新读取配置，开关与间隔修改无需重启。
+pub fn start_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let state = app.state::<AppState>();
+            let config = match state.db.get_model_fidelity_config() {
+                Ok(config) => config,
+                Err(e) => {
+                    log::warn!("[ModelFidelity] 读取配置失败: {e}");
+                    ModelFidelityConfig::default()
+                }
+            };
+
+            if !config.probe_enabled {
+                tokio::time::sleep(Duration::from_secs(DISABLED_RECHECK_SECS)).await;
+                continue;
+            }
+
+            let mut results = Vec::new();
+            for app_type in PROBE_APPS.iter() {
+                let providers = match probe_targets(&state.db, app_type) {
+                    Ok(providers) => providers,
+                    Err(e) => {
+                        log::debug!("[ModelFidelity] 读取 {} 供应商失败: {e}", app_type.as_str());
+                        continue;
+                    }
+                };
+                for provider in providers {
+                    match probe_provider(&state.db, app_type, &provider).await {
+                        Ok(mut probe) => results.append(&mut probe),
+                        Err(e) => log::warn!(
+                            "[ModelFidelity] 探测 {}/{} 失败: {e}",
+                            app_type.as_str(),
+                            provider.id
+                        ),
+                    }
+                }
+            }
+
+            if results.iter().any(|r| r.matched == Some(false)) {
+                log::warn!("[ModelFidelity] 指纹探测发现上游模型与请求不一致");
+            }
+            if !results.is_empty() {
+                if let Err(e) = app.emit("model-fidelity-probed", &results) {
+                    log::debug!("[ModelFidelity] 发送探测事件失败: {e}");
+                }
+            }
+
+            let cutoff = chrono::Utc::now().timestamp() - PROBE_KEEP_DAYS * 86_400;
+            if let Err(e) = state.db.prune_model_probe_results(cutoff) {
+                log::warn!("[ModelFidelity] 清理过期探测记录失败: {e}");
+            }
+
+            tokio::time::sleep(Duration::from_secs(
+                u64::from(config.probe_interval_mins) * 60,
+            ))
+            .await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_models_match_ignores_vendor_and_version_noise() {
+        assert!(models_match(
+            "claude-sonnet-4-5",
+            "claude-sonnet-4-5-20250929"
+        ));
+        assert!(models_match(
+            "anthropic/claude-sonnet-4.5",
+            "claude-sonnet-4-5-20250929"
+        ));
+        assert!(models_match(
+            "us.anthropic.claude-sonnet-4-5-20250929-v1:0",
+            "claude-sonnet-4-5"
+        ));
+        assert!(models_match("claude-opus-4-1@20250805", "claude-opus-4-1"));
+        assert!(models_match("gpt-4o", "gpt-4o-2024-08-06"));
+        assert!(models_match("models/gemini-2.5-pro", "gemini-2.5-pro"));
+        assert!(models_match(
+            "claude-3-5-haiku-latest",
+            "claude-3-5-haiku-20241022"
+        ));
+
+        assert!(!models_match("claude-opus-4-1", "claude-sonnet-4-5"));
+        assert!(!models_match("gpt-5", "gpt-5-mini"));
+        assert!(!models_match("glm-4.6", "glm-4.5-air"));
+        assert!(!models_match("deepseek-v3", "deepseek-v2"));
+    }
+
+    #[test]
+    fn test_is_substitution_skips_unknown_models() {
+        assert!(is_substitution(
+            "claude-opus-4-1",
+            "claude-3-5-haiku-20241022"
+        ));
+        assert!(!is_substitution("claude-opus-4-1", ""));
+        assert!(!is_substitution("claude-opus-4-1", "unknown"));
+        assert!(!is_substitution("", "claude-3-5-haiku"));
+    }
+
+    #[test]
+    fn test_fidelity_combines_requests_and_probes() {
+        let mut stats = ModelFidelityStats::new("claude".into(), "p1".into());
+        stats.finish();
+        assert_eq!(stats.fidelity, None);
+
+        stats.checked_requests = 8;
+        stats.substituted_requests = 2;
+        stats.probe_count = 3;
+        stats.probe_verified = 2;
+        stats.finish();
+        assert_eq!(stats.fidelity, Some(0.8));
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(ModelFidelityConfig::default().validate().is_ok());
+        let config = ModelFidelityConfig {
+            probe_interval_mins: 5,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
@@ -0,0 +1,662 @@
+//! 通知子系统
+//!
+//! 代理、同步与用量查询等模块通过 [`notify`] 投递事件（非阻塞），
+//! 后台 worker 按规则过滤、去重后分发到已配置的通知渠道（Webhook / IM 机器人 / 邮件 / 桌面通知）。
+
+mod sinks;
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::AppHandle;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+
+use crate::database::Database;
+use crate::error::AppError;
+use crate::services::usage_forecast::UsageAlert;
+
+pub use sinks::send_to_sink;
+
+/// 事件队列容量；队列满时直接丢弃，通知不应反压业务流程
+const EVENT_QUEUE_SIZE: usize = 64;
+/// 花费阈值检查间隔
+const SPEND_CHECK_INTERVAL_SECS: u64 = 300;
+
+static EVENT_TX: OnceLock<Sender<NotificationEvent>> = OnceLock::new();
+
+/// 通知配置（存储在 settings 表 `notification_config`）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationConfig {
+    pub enabled: bool,
+    pub sinks: Vec<NotificationSink>,
+    pub rules: NotificationRules,
+    /// 同一事件（同应用/同供应商）的最小重复通知间隔（秒）
+    pub cooldown_secs: u64,
+}
+
+impl Default for NotificationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sinks: Vec::new(),
+            rules: NotificationRules::default(),
+            cooldown_secs: 600,
+        }
+    }
+}
+
+/// 通知规则
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NotificationRules {
+    /// 代理发生故障转移切换
+    pub failover: bool,
+    /// 某应用所有供应商均已熔断
+    pub all_circuits_open: bool,
+    /// WebDAV 自动同步失败
+    pub webdav_sync_failed: bool,
+    /// 今日总花费阈值（美元）
+    pub daily_spend_usd: Option<f64>,
+    /// 本月总花费阈值（美元）
+    pub monthly_spend_usd: Option<f64>,
+    /// 用量脚本剩余额度低于该值时通知
+    pub quota_remaining_below: Option<f64>,
+    /// 用量脚本剩余比例低于该值时通知（0-100）
+    pub quota_percent_below: Option<f64>,
+    /// 用量脚本返回 isValid=false 时通知
+    pub quota_invalid: bool,
+    /// 花费预测与异常预警（见 `usage_forecast`）
+    pub usage_alerts: bool,
+}
+
+impl Default for NotificationRules {
+    fn default() -> Self {
+        Self {
+            failover: true,
+            all_circuits_open: true,
+            webdav_sync_failed: true,
+            daily_spend_usd: None,
+            monthly_spend_usd: None,
+            quota_remaining_below: None,
+            quota_percent_below: None,
+            quota_invalid: true,
+            usage_alerts: true,
+        }
+    }
+}
+
+/// 通知渠道
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationSink {
+    pub id: String,
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(flatten)]
+    pub kind: SinkKind,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 渠道类型
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum SinkKind {
+    /// 通用 Webhook，POST JSON 事件
+    #[serde(rename_all = "camelCase")]
+    Webhook {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+    },
+    Slack {
+        url: String,
+    },
+    Discord {
+        url: String,
+    },
+    Feishu {
+        url: String,
+    },
+    #[serde(rename = "dingtalk")]
+    DingTalk {
+        url: String,
+    },
+    /// SMTP 邮件
+    #[serde(rename_all = "camelCase")]
+    Email {
+        host: String,
+        port: u16,
+        #[serde(default)]
+        username: Option<String>,
+        #[serde(default)]
+        password: Option<String>,
+        #[serde(default)]
+        security: SmtpSecurity,
+        from: String,
+        to: Vec<String>,
+    },
+    /// 系统桌面通知
+    Desktop,
+}
+
+/// SMTP 连接加密方式
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SmtpSecurity {
+    /// 隐式 TLS（通常 465 端口）
+    Tls,
+    /// STARTTLS（通常 587 端口）
+    #[default]
+    Starttls,
+    /// 明文（仅用于本地中继）
+    None,
+}
+
+/// 业务事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+pub enum NotificationEvent {
+    /// 故障转移切换到新的供应商
+    #[serde(rename_all = "camelCase")]
+    FailoverSwitched {
+        app_type: String,
+        from_provider: Option<String>,
+        to_provider_id: String,
+        to_provider: String,
+    },
+    /// 某应用所有供应商均已熔断
+    #[serde(rename_all = "camelCase")]
+    AllCircuitsOpen { app_type: String },
+    /// WebDAV 自动同步失败
+    #[serde(rename_all = "camelCase")]
+    WebDavSyncFailed { error: String },
+    /// 当前花费快照（由 worker 定时采集）
+    #[serde(rename_all = "camelCase")]
+    SpendSnapshot { daily_usd: f64, monthly_usd: f64 },
+    /// 用量脚本查询结果
+    #[serde(rename_all = "camelCase")]
+    QuotaChecked {
+        app_type: String,
+        provider_id: String,
+        provider_name: String,
+        plan_name: Option<String>,
+        is_valid: Option<bool>,
+        remaining: Option<f64>,
+        total: Option<f64>,
+        unit: Option<String>,
+    },
+    /// 花费预测或异常检测产生的预警
+    #[serde(rename_all = "camelCase")]
+    UsageAlertRaised { alert: UsageAlert },
+}
+
+/// 通过规则过滤后待发送的通知
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Notification {
+    /// 规则类型（failover / allCircuitsOpen / spendThreshold / webdavSyncFailed / quotaLow / usageAlert）
+    pub kind: String,
+    pub title: String,
+    pub message: String,
+    pub timestamp: i64,
+    #[serde(skip)]
+    dedupe: Dedupe,
+}
+
+impl Notification {
+    fn new(kind: &str, title: String, message: String, dedupe: Dedupe) -> Self {
+        Self {
+            kind: kind.to_string(),
+            title,
+            message,
+            timestamp: chrono::Utc::now().timestamp(),
+            dedupe,
+        }
+    }
+
+    /// 构造测试通知
+    pub fn test() -> Self {
+        Self::new(
+            "test",
+            "CC Switch 测试通知".to_string(),
+            "通知渠道配置成功".to_string(),
+            Dedupe::None,
+        )
+    }
+}
+
+/// 去重策略
+#[derive(Debug, Clone, PartialEq)]
+enum Dedupe {
+    None,
+    /// 同 key 在冷却时间内只发送一次
+    Cooldown(String),
+    /// 同 key 只在首次进入该状态时发送，状态恢复后才会再次触发
+    Latch(String),
+}
+
+/// 投递事件（非阻塞；worker 未启动或队列已满时丢弃）
+pub fn notify(event: NotificationEvent) {
+    let Some(tx) = EVENT_TX.get() else {
+        return;
+    };
+    if tx.try_send(event).is_err() {
+        log::debug!("[Notify] 事件队列已满，丢弃通知事件");
+    }
+}
+
+/// 启动通知 worker
+pub fn start_worker(db: Arc<Database>, app: AppHandle) {
+    if EVENT_TX.get().is_some() {
+        return;
+    }
+    let (tx, rx) = channel::<NotificationEvent>(EVENT_QUEUE_SIZE);
+    if EVENT_TX.set(tx).is_err() {
+        return;
+    }
+
+    tauri::async_runtime::spawn(async move {
+        run_worker_loop(db, rx, app).await;
+    });
+}
+
+async fn run_worker_loop(db: Arc<Database>, mut rx: Receiver<NotificationEvent>, app: AppHandle) {
+    let mut dedupe = DedupeState::default();
+    let mut spend_timer = tokio::time::interval(Duration::from_secs(SPEND_CHECK_INTERVAL_SECS));
+
+    loop {
+        let event = tokio::select! {
+            received = rx.recv() => match received {
+                Some(event) => event,
+                None => return,
+            },
+            _ = spend_timer.tick() => match spend_snapshot(&db) {
+                Some(event) => event,
+                None => continue,
+            },
+        };
+
+        let config = match db.get_notification_config() {
+            Ok(config) => config,
+            Err(e) => {
+                log::warn!("[Notify] 读取通知配置失败: {e}");
+                continue;
+            }
+        };
+        if !config.enabled || config.sinks.iter().all(|s| !s.enabled) {
+            continue;
+        }
+
+        let notifications = evaluate(&event, &config.rules);
+        if notifications.is_empty() {
+            dedupe.clear_latch(&event);
+            continue;
+        }
+        for notification in notifications {
+            if !dedupe.should_send(
+                &notification.dedupe,
+                Duration::from_secs(config.cooldown_secs),
+            ) {
+                continue;
+            }
+
+            log::info!("[Notify] 发送通知: {}", notification.title);
+            for sink in config.sinks.iter().filter(|s| s.enabled) {
+                if let Err(e) = send_to_sink(&app, sink, &notification).await {
+                    log::warn!("[Notify] 渠道 {} 发送失败: {e}", sink.name);
+                }
+            }
+        }
+    }
+}
+
+/// 读取全局花费快照；通知未启用或未配置花费阈值时不查询数据库
+fn spend_snapshot(db: &Database) -> Option<NotificationEvent> {
+    let config = db.get_notification_config().ok()?;
+    let rules = &config.rules;
+    if !config.enabled || (rules.daily_spend_usd.is_none() && rules.monthly_spend_usd.is_none()) {
+        return None;
+    }
+    match db.get_global_spend_usd() {
+        Ok((daily_usd, monthly_usd)) => Some(NotificationEvent::SpendSnapshot {
+            daily_usd,
+            monthly_usd,
+        }),
+        Err(e) => {
+            log::debug!("[Notify] 读取花费统计失败: {e}");
+            None
+        }
+    }
+}
+
+#[derive(Default)]
+struct DedupeState {
+    last_sent: HashMap<String, Instant>,
+    latched: HashSet<String>,
+}
+
+impl DedupeState {
+    fn should_send(&mut self, dedupe: &Dedupe, cooldown: Duration) -> bool {
+        match dedupe {
+            Dedupe::None => true,
+            Dedupe::Cooldown(key) => {
+                let now = Instant::now();
+                match self.last_sent.get(key) {
+                    Some(last) if now.duration_since(*last) < cooldown => false,
+                    _ => {
+                        self.last_sent.insert(key.clone(), now);
+                        true
+                    }
+                }
+            }
+            Dedupe::Latch(key) => self.latched.insert(key.clone()),
+        }
+    }
+
+    /// 事件未命中规则时解除对应的状态锁存（例如额度已恢复）
+    fn clear_latch(&mut self, event: &NotificationEvent) {
+        if let NotificationEvent::QuotaChecked {
+            app_type,
+            provider_id,
+            plan_name,
+            ..
+        } = event
+        {
+            self.latched
+                .remove(&quota_key(app_type, provider_id, plan_name.as_deref()));
+        }
+    }
+}
+
+fn quota_key(app_type: &str, provider_id: &str, plan_name: Option<&str>) -> String {
+    format!("quota:{app_type}:{provider_id}:{}", plan_name.unwrap_or(""))
+}
+
+/// 按规则判断事件是否需要通知，并生成通知内容（花费快照可能同时触发日、月两条）
+fn evaluate(event: &NotificationEvent, rules: &NotificationRules) -> Vec<Notification> {
+    match event {
+        NotificationEvent::SpendSnapshot {
+            daily_usd,
+            monthly_usd,
+        } => spend_notifications(*daily_usd, *monthly_usd, rules),
+        _ => evaluate_event(event, rules).into_iter().collect(),
+    }
+}
+
+/// 日、月花费阈值各自独立判断与锁存
+fn spend_notifications(
+    daily_usd: f64,
+    monthly_usd: f64,
+    rules: &NotificationRules,
+) -> Vec<Notification> {
+    let now = chrono::Local::now();
+    let daily = rules
+        .daily_spend_usd
+        .filter(|l| daily_usd >= *l)
+        .map(|limit| {
+            Notification::new(
+                "spendThreshold",
+                "今日花费已超过阈值".to_string(),
+                format!("今日总花费 ${daily_usd:.2}，阈值 ${limit:.2}"),
+                Dedupe::Latch(format!("spend:daily:{}", now.format("%Y-%m-%d"))),
+            )
+        });
+    let monthly = rules
+        .monthly_spend_usd
+        .filter(|l| monthly_usd >= *l)
+        .map(|limit| {
+            Notification::new(
+                "spendThreshold",
+                "本月花费已超过阈值".to_string(),
+                format!("本月总花费 ${monthly_usd:.2}，阈值 ${limit:.2}"),
+                Dedupe::Latch(format!("spend:monthly:{}", now.format("%Y-%m"))),
+            )
+        });
+    daily.into_iter().chain(monthly).collect()
+}
+
+fn evaluate_event(event: &NotificationEvent, rules: &NotificationRules) -> Option<Notification> {
+    match event {
+        NotificationEvent::FailoverSwitched {
+            app_type,
+            from_provider,
+            to_provider_id,
+            to_provider,
+        } => rules.failover.then(|| {
+            let from = from_provider.as_deref().unwrap_or("-");
+            Notification::new(
+                "failover",
+                format!("[{app_type}] 已故障转移到 {to_provider}"),
+                format!("代理已将 {app_type} 的当前供应商从 {from} 切换为 {to_provider}"),
+                Dedupe::Cooldown(format!("failover:{app_type}:{to_provider_id}")),
+            )
+        }),
+        NotificationEvent::AllCircuitsOpen { app_type } => rules.all_circuits_open.then(|| {
+            Notification::new(
+                "allCircuitsOpen",
+                format!("[{app_type}] 所有供应商均已熔断"),
+                format!("{app_type} 故障转移队列中的供应商全部处于熔断状态，请求将直接失败"),
+                Dedupe::Cooldown(format!("circuit:{app_type}")),
+            )
+        }),
+        NotificationEvent::WebDavSyncFailed { error } => rules.webdav_sync_failed.then(|| {
+            Notification::new(
+                "webdavSyncFailed",
+                "WebDAV 自动同步失败".to_string(),
+                error.clone(),
+                Dedupe::Cooldown("webdav".to_string()),
+            )
+        }),
+        // 花费快照由 spend_notifications 处理
+        NotificationEvent::SpendSnapshot { .. } => None,
+        NotificationEvent::QuotaChecked {
+            app_type,
+            provider_id,
+            provider_name,
+            plan_name,
+            is_valid,
+            remaining,
+            total,
+            unit,
+        } => {
+            let reason = quota_low_reason(rules, *is_valid, *remaining, *total)?;
+            let plan = plan_name
+                .as_deref()
+                .map(|p| format!(" ({p})"))
+                .unwrap_or_default();
+            let remaining = remaining
+                .map(|r| format!("{r} {}", unit.as_deref().unwrap_or("")))
+                .unwrap_or_else(|| "-".to_string());
+            Some(Notification::new(
+                "quotaLow",
+                format!("[{app_type}] {provider_name}{plan} 额度不足"),
+                format!("{reason}，剩余 {}", remaining.trim_end()),
+                Dedupe::Latch(quota_key(app_type, provider_id, plan_name.as_deref())),
+            ))
+        }
+        NotificationEvent::UsageAlertRaised { alert } => rules.usage_alerts.then(|| {
+            Notification::new(
+                "usageAlert",
+                alert.title.clone(),
+                alert.message.clone(),
+                Dedupe::Latch(alert.key.clone()),
+            )
+        }),
+    }
+}
+
+/// 判断用量脚本结果是否触发低额度规则，返回原因描述
+fn quota_low_reason(
+    rules: &NotificationRules,
+    is_valid: Option<bool>,
+    remaining: Option<f64>,
+    total: Option<f64>,
+) -> Option<String> {
+    crate::services::quota_guard::threshold_reason(
+        is_valid,
+        remaining,
+        total,
+        rules.quota_invalid,
+        rules.quota_remaining_below,
+        rules.quota_percent_below,
+    )
+}
+
+/// 发送测试通知到指定渠道
+pub async fn send_test(app: &AppHandle, sink: &NotificationSink) -> Result<(), AppError> {
+    send_to_sink(app, sink, &Notification::test()).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quota_event(remaining: f64, total: f64, is_valid: Option<bool>) -> NotificationEvent {
+        NotificationEvent::QuotaChecked {
+            app_type: "claude".into(),
+            provider_id: "p1".into(),
+            provider_name: "Relay".into(),
+            plan_name: None,
+            is_valid,
+            remaining: Some(remaining),
+            total: Some(total),
+            unit: Some("USD".into()),
+        }
+    }
+
+    #[test]
+    fn sink_config_round_trips_with_type_tag() {
+        let json = r#"{"id":"s1","name":"Ops","type":"dingtalk","url":"https://oapi.dingtalk.com/robot/send"}"#;
+        let sink: NotificationSink = serde_json::from_str(json).unwrap();
+        assert!(sink.enabled);
+        assert!(matches!(sink.kind, SinkKind::DingTalk { .. }));
+
+        let email = r#"{"id":"s2","name":"Mail","type":"email","host":"smtp.example.com","port":465,"security":"tls","from":"a@example.com","to":["b@example.com"]}"#;
+        let sink: NotificationSink = serde_json::from_str(email).unwrap();
+        assert!(matches!(
+            sink.kind,
+            SinkKind::Email {
+                security: SmtpSecurity::Tls,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn disabled_rules_suppress_events() {
+        let rules = NotificationRules {
+            failover: false,
+            ..Default::default()
+        };
+        let event = NotificationEvent::FailoverSwitched {
+            app_type: "claude".into(),
+            from_provider: None,
+            to_provider_id: "b".into(),
+            to_provider: "B".into(),
+        };
+        assert!(evaluate(&event, &rules).is_empty());
+        assert_eq!(evaluate(&event, &NotificationRules::default()).len(), 1);
+    }
+
+    #[test]
+    fn spend_thresholds_fire_independently() {
+        let rules = NotificationRules {
+            daily_spend_usd: Some(10.0),
+            monthly_spend_usd: Some(100.0),
+            ..Default::default()
+        };
+        let below = NotificationEvent::SpendSnapshot {
+            daily_usd: 5.0,
+            monthly_usd: 50.0,
+        };
+        assert!(evaluate(&below, &rules).is_empty());
+
+        let crossed = NotificationEvent::SpendSnapshot {
+            daily_usd: 12.0,
+            monthly_usd: 150.0,
+        };
+        let titles: Vec<_> = evaluate(&crossed, &rules)
+            .into_iter()
+            .map(|n| n.title)
+            .collect();
+        assert_eq!(titles, ["今日花费已超过阈值", "本月花费已超过阈值"]);
+
+        let monthly_only = NotificationEvent::SpendSnapshot {
+            daily_usd: 2.0,
+            monthly_usd: 150.0,
+        };
+        let notifications = evaluate(&monthly_only, &rules);
+        assert_eq!(notifications.len(), 1);
+        assert_eq!(notifications[0].title, "本月花费已超过阈值");
+    }
+
+    #[test]
+    fn quota_rules_use_absolute_and_percent_thresholds() {
+        let rules = NotificationRules {
+            quota_remaining_below: Some(5.0),
+            quota_percent_below: Some(10.0),
+            ..Default::default()
+        };
+        assert!(evaluate(&quota_event(50.0, 100.0, Some(true)), &rules).is_empty());
+        assert!(!evaluate(&quota_event(8.0, 100.0, Some(true)), &rules).is_empty());
+        assert!(!evaluate(&quota_event(4.0, 1000.0, Some(true)), &rules).is_empty());
+        assert!(!evaluate(&quota_event(500.0, 1000.0, Some(false)), &rules).is_empty());
+    }
+
+    #[test]
+    fn usage_alerts_latch_on_alert_key() {
+        use crate::services::usage_forecast::{
+            UsageAlertKind, UsageAlertScope, UsageAlertSeverity,
+        };
+        let event = NotificationEvent::UsageAlertRaised {
+            alert: UsageAlert {
+                key: "forecast:cost:Model:claude:opus:2025-03-11".into(),
+                kind: UsageAlertKind::CostSpike,
+                severity: UsageAlertSeverity::Warning,
+                scope: UsageAlertScope::Model,
+                app_type: Some("claude".into()),
+                provider_id: None,
+                provider_name: None,
+                model: Some("opus".into()),
+                title: "[claude] opus 今日花费异常".into(),
+                message: "-".into(),
+                value: 12.0,
+                baseline: 2.0,
+                projected_date: None,
+            },
+        };
+        let n = evaluate(&event, &NotificationRules::default()).remove(0);
+        assert_eq!(n.kind, "usageAlert");
+        assert_eq!(
+            n.dedupe,
+            Dedupe::Latch("forecast:cost:Model:claude:opus:2025-03-11".into())
+        );
+
+        let rules = NotificationRules {
+            usage_alerts: false,
+            ..Default::default()
+        };
+        assert!(evaluate(&event, &rules).is_empty());
+    }
+
+    #[test]
+    fn latch_fires_once_until_cleared() {
+        let mut state = DedupeState::default();
+        let latch = Dedupe::Latch(quota_key("claude", "p1", None));
+        assert!(state.should_send(&latch, Duration::ZERO));
+        assert!(!state.should_send(&latch, Duration::ZERO));
+
+        state.clear_latch(&quota_event(90.0, 100.0, Some(true)));
+        assert!(state.should_send(&latch, Duration::ZERO));
+
+        let cooldown = Dedupe::Cooldown("circuit:claude".into());
+        assert!(state.should_send(&cooldown, Duration::from_secs(60)));
+        assert!(!state.should_send(&cooldown, Duration::from_secs(60)));
+    }
+}
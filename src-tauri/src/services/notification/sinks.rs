@@ -0,0 +1,165 @@
+//! 通知渠道投递实现
+
+use lettre::message::header::ContentType;
+use lettre::message::Mailbox;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use serde_json::{json, Value};
+use std::time::Duration;
+use tauri::AppHandle;
+use tauri_plugin_notification::NotificationExt;
+
+use super::{Notification, NotificationSink, SinkKind, SmtpSecurity};
+use crate::error::AppError;
+use crate::proxy::http_client;
+
+const WEBHOOK_TIMEOUT_SECS: u64 = 10;
+
+/// 发送通知到单个渠道
+pub async fn send_to_sink(
+    app: &AppHandle,
+    sink: &NotificationSink,
+    notification: &Notification,
+) -> Result<(), AppError> {
+    match &sink.kind {
+        SinkKind::Webhook { url, headers } => {
+            let payload = serde_json::to_value(notification)
+                .map_err(|e| AppError::Message(format!("序列化通知失败: {e}")))?;
+            post_json(url, headers.iter(), &payload).await
+        }
+        SinkKind::Slack { url } => {
+            let text = format!("*{}*\n{}", notification.title, notification.message);
+            post_json(url, std::iter::empty(), &json!({ "text": text })).await
+        }
+        SinkKind::Discord { url } => {
+            let content = format!("**{}**\n{}", notification.title, notification.message);
+            post_json(url, std::iter::empty(), &json!({ "content": content })).await
+        }
+        SinkKind::Feishu { url } => {
+            let payload = json!({
+                "msg_type": "text",
+                "content": { "text": plain_text(notification) },
+            });
+            post_json(url, std::iter::empty(), &payload).await
+        }
+        SinkKind::DingTalk { url } => {
+            let payload = json!({
+                "msgtype": "text",
+                "text": { "content": plain_text(notification) },
+            });
+            post_json(url, std::iter::empty(), &payload).await
+        }
+        SinkKind::Email {
+            host,
+            port,
+            username,
+            password,
+            security,
+            from,
+            to,
+        } => {
+            send_email(
+                host,
+                *port,
+                username.as_deref(),
+                password.as_deref(),
+                *security,
+                from,
+                to,
+                notification,
+            )
+            .await
+        }
+        SinkKind::Desktop => app
+            .notification()
+            .builder()
+            .title(&notification.title)
+            .body(&notification.message)
+            .show()
+            .map_err(|e| AppError::Message(format!("桌面通知失败: {e}"))),
+    }
+}
+
+fn plain_text(notification: &Notification) -> String {
+    format!("{}\n{}", notification.title, notification.message)
+}
+
+async fn post_json<'a>(
+    url: &str,
+    headers: impl Iterator<Item = (&'a String, &'a String)>,
+    payload: &Value,
+) -> Result<(), AppError> {
+    let mut request = http_client::get()
+        .post(url)
+        .timeout(Duration::from_secs(WEBHOOK_TIMEOUT_SECS))
+        .json(payload);
+    for (name, value) in headers {
+        request = request.header(name.as_str(), value.as_str());
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| AppError::Message(format!("Webhook 请求失败: {e}")))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(AppError::Message(format!(
+            "Webhook 返回 HTTP {status}: {}",
+            body.chars().take(200).collect::<String>()
+        )));
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn send_email(
+    host: &str,
+    port: u16,
+    username: Option<&str>,
+    password: Option<&str>,
+    security: SmtpSecurity,
+    from: &str,
+    to: &[String],
+    notification: &Notification,
+) -> Result<(), AppError> {
+    let parse_mailbox = |addr: &str| {
+        addr.parse::<Mailbox>()
+            .map_err(|e| AppError::Message(format!("邮件地址无效 {addr}: {e}")))
+    };
+
+    let mut builder = Message::builder()
+        .from(parse_mailbox(from)?)
+        .subject(&notification.title);
+    for addr in to {
+        builder = builder.to(parse_mailbox(addr)?);
+    }
+    let email = builder
+        .header(ContentType::TEXT_PLAIN)
+        .body(notification.message.clone())
+        .map_err(|e| AppError::Message(format!("构建邮件失败: {e}")))?;
+
+    let smtp_err = |e: lettre::transport::smtp::Error| AppError::Message(format!("SMTP 错误: {e}"));
+    let mut transport = match security {
+        SmtpSecurity::Tls => AsyncSmtpTransport::<Tokio1Executor>::relay(host).map_err(smtp_err)?,
+        SmtpSecurity::Starttls => {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host).map_err(smtp_err)?
+        }
+        SmtpSecurity::None => AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(host),
+    }
+    .port(port)
+    .timeout(Some(Duration::from_secs(WEBHOOK_TIMEOUT_SECS)));
+    if let Some(user) = username.filter(|u| !u.is_empty()) {
+        transport = transport.credentials(Credentials::new(
+            user.to_string(),
+            password.unwrap_or_default().to_string(),
+        ));
+    }
+
+    transport
+        .build()
+        .send(email)
+        .await
+        .map(|_| ())
+        .map_err(smtp_err)
+}
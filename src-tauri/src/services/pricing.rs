@@ -0,0 +1,438 @@
+//! 模型定价服务
+//!
+//! 支持长上下文阶梯、缓存 TTL 分档与按生效时间划分的历史定价：
+//! - 当前定价存放在 model_pricing，`effective_from` 记录其生效时间
+//! - 旧定价归档到 model_pricing_history，在 [effective_from, effective_to) 区间内生效
+//! - 计算成本时按请求的 created_at 选择当时生效的定价
+//! - 请求模型名无法直接匹配时，按 model_pricing_aliases 中的别名/正则映射到已有定价
+
+use regex::{Regex, RegexBuilder};
+use rusqlite::{Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+use crate::error::AppError;
+use crate::proxy::usage::calculator::{ModelPricing, PricingTierConfig};
+use crate::services::usage_stats::find_model_pricing_row;
+
+/// 某一时间区间内生效的定价
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPricingVersion {
+    /// 历史版本的行 ID，当前定价为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub model_id: String,
+    pub input_cost_per_million: String,
+    pub output_cost_per_million: String,
+    pub cache_read_cost_per_million: String,
+    pub cache_creation_cost_per_million: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_creation_1h_cost_per_million: Option<String>,
+    #[serde(default)]
+    pub tiers: Vec<PricingTierConfig>,
+    /// 生效起点（秒），0 表示不限
+    pub effective_from: i64,
+    /// 失效时间（秒，不含），当前定价为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effective_to: Option<i64>,
+}
+
+impl ModelPricingVersion {
+    /// 转换为计算器使用的定价结构
+    pub fn to_pricing(&self) -> Result<ModelPricing, AppError> {
+        ModelPricing::from_strings(
+            &self.input_cost_per_million,
+            &self.output_cost_per_million,
+            &self.cache_read_cost_per_million,
+            &self.cache_creation_cost_per_million,
+        )
+        .and_then(|pricing| {
+            pricing.with_extras(
+                self.cache_creation_1h_cost_per_million.as_deref(),
+                &self.tiers,
+            )
+        })
+        .map_err(|e| AppError::Database(format!("解析定价数据失败 ({}): {e}", self.model_id)))
+    }
+
+    /// 价格内容是否一致（忽略生效时间）
+    pub fn same_prices(&self, other: &Self) -> bool {
+        self.input_cost_per_million == other.input_cost_per_million
+            && self.output_cost_per_million == other.output_cost_per_million
+            && self.cache_read_cost_per_million == other.cache_read_cost_per_million
+            && self.cache_creation_cost_per_million == other.cache_creation_cost_per_million
+            && self.cache_creation_1h_cost_per_million == other.cache_creation_1h_cost_per_million
+            && self.tiers == other.tiers
+    }
+}
+
+/// 校验阶梯配置：阈值必须大于 0 且互不重复，价格必须是合法数字
+pub fn validate_tiers(tiers: &[PricingTierConfig]) -> Result<(), AppError> {
+    let mut seen = std::collections::HashSet::new();
+    for tier in tiers {
+        if tier.above_input_tokens == 0 {
+            return Err(AppError::InvalidInput("阶梯阈值必须大于 0".to_string()));
+        }
+        if !seen.insert(tier.above_input_tokens) {
+            return Err(AppError::InvalidInput(format!(
+                "阶梯阈值重复: {}",
+                tier.above_input_tokens
+            )));
+        }
+    }
+    ModelPricing::from_strings("0", "0", "0", "0")
+        .and_then(|p| p.with_extras(None, tiers))
+        .map(|_| ())
+        .map_err(|e| AppError::InvalidInput(format!("阶梯价格无效: {e}")))
+}
+
+/// 解析 tiers 列的 JSON，格式错误时记录警告并视为无阶梯
+pub(crate) fn parse_tiers(raw: Option<String>) -> Vec<PricingTierConfig> {
+    let Some(raw) = raw.filter(|s| !s.trim().is_empty()) else {
+        return Vec::new();
+    };
+    serde_json::from_str(&raw).unwrap_or_else(|e| {
+        log::warn!("阶梯定价 JSON 解析失败，已忽略: {e}");
+        Vec::new()
+    })
+}
+
+/// 序列化 tiers 列，空阶梯存为 NULL
+pub(crate) fn tiers_to_column(tiers: &[PricingTierConfig]) -> Result<Option<String>, AppError> {
+    if tiers.is_empty() {
+        return Ok(None);
+    }
+    crate::database::to_json_string(&tiers).map(Some)
+}
+
+/// 模型定价别名
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelPricingAlias {
+    /// 请求模型名（不区分大小写），或 `is_regex` 时的正则表达式
+    pub pattern: String,
+    /// 映射到的 model_pricing.model_id
+    pub model_id: String,
+    #[serde(default)]
+    pub is_regex: bool,
+    /// 多个别名同时匹配时优先级高者胜出
+    #[serde(default)]
+    pub priority: i64,
+    #[serde(default)]
+    pub created_at: i64,
+}
+
+/// 编译别名正则（不区分大小写，且必须匹配整个模型名）
+pub(crate) fn compile_alias_regex(pattern: &str) -> Result<Regex, AppError> {
+    RegexBuilder::new(&format!("^(?:{pattern})$"))
+        .case_insensitive(true)
+        .build()
+        .map_err(|e| AppError::InvalidInput(format!("别名正则无效: {e}")))
+}
+
+/// 已编译的别名正则（按 pattern 缓存，无效正则缓存为 None）
+static ALIAS_REGEX_CACHE: LazyLock<Mutex<HashMap<String, Option<Regex>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn cached_alias_regex(pattern: &str) -> Option<Regex> {
+    let mut cache = ALIAS_REGEX_CACHE.lock().unwrap_or_else(|e| e.into_inner());
+    cache
+        .entry(pattern.to_string())
+        .or_insert_with(|| match compile_alias_regex(pattern) {
+            Ok(regex) => Some(regex),
+            Err(e) => {
+                log::warn!("定价别名 {pattern} 已忽略: {e}");
+                None
+            }
+        })
+        .clone()
+}
+
+/// 按别名查找定价行：先精确别名，后正则别名；原始名与清洗后的名称均参与匹配
+pub(crate) fn resolve_pricing_alias(
+    conn: &Connection,
+    raw: &str,
+    cleaned: &str,
+) -> Result<Option<String>, AppError> {
+    let exact = conn
+        .query_row(
+            "SELECT a.model_id FROM model_pricing_aliases a
+             JOIN model_pricing p ON p.model_id = a.model_id
+             WHERE a.is_regex = 0 AND (lower(a.pattern) = lower(?1) OR lower(a.pattern) = lower(?2))
+             ORDER BY a.priority DESC LIMIT 1",
+            [raw, cleaned],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .map_err(|e| AppError::Database(format!("查询定价别名失败: {e}")))?;
+    if exact.is_some() {
+        return Ok(exact);
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT a.pattern, a.model_id FROM model_pricing_aliases a
+             JOIN model_pricing p ON p.model_id = a.model_id
+             WHERE a.is_regex = 1
+             ORDER BY a.priority DESC, a.pattern ASC",
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    let patterns = stmt
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .map_err(|e| AppError::Database(e.to_string()))?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    Ok(patterns
+        .into_iter()
+        .find(|(pattern, _)| {
+            cached_alias_regex(pattern).is_some_and(|re| re.is_match(raw) || re.is_match(cleaned))
+        })
+        .map(|(_, model_id)| model_id))
+}
+
+/// 读取模型的当前定价
+pub(crate) fn load_current_version(
+    conn: &Connection,
+    model_id: &str,
+) -> Result<Option<ModelPricingVersion>, AppError> {
+    conn.query_row(
+        "SELECT model_id, input_cost_per_million, output_cost_per_million,
+                cache_read_cost_per_million, cache_creation_cost_per_million,
+                cache_creation_1h_cost_per_million, tiers, effective_from
+         FROM model_pricing WHERE model_id = ?1",
+        [model_id],
+        |row| {
+            Ok(ModelPricingVersion {
+                id: None,
+                model_id: row.get(0)?,
+                input_cost_per_million: row.get(1)?,
+                output_cost_per_million: row.get(2)?,
+                cache_read_cost_per_million: row.get(3)?,
+                cache_creation_cost_per_million: row.get(4)?,
+                cache_creation_1h_cost_per_million: row.get(5)?,
+                tiers: parse_tiers(row.get(6)?),
+                effective_from: row.get(7)?,
+                effective_to: None,
+            })
+        },
+    )
+    .optional()
+    .map_err(|e| AppError::Database(format!("查询模型定价失败: {e}")))
+}
+
+/// 读取模型的全部历史定价（按生效时间升序）
+pub(crate) fn load_history_versions(
+    conn: &Connection,
+    model_id: &str,
+) -> Result<Vec<ModelPricingVersion>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, model_id, input_cost_per_million, output_cost_per_million,
+                    cache_read_cost_per_million, cache_creation_cost_per_million,
+                    cache_creation_1h_cost_per_million, tiers, effective_from, effective_to
+             FROM model_pricing_history
+             WHERE model_id = ?1
+             ORDER BY effective_from ASC",
+        )
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    let rows = stmt
+        .query_map([model_id], |row| {
+            Ok(ModelPricingVersion {
+                id: Some(row.get(0)?),
+                model_id: row.get(1)?,
+                input_cost_per_million: row.get(2)?,
+                output_cost_per_million: row.get(3)?,
+                cache_read_cost_per_million: row.get(4)?,
+                cache_creation_cost_per_million: row.get(5)?,
+                cache_creation_1h_cost_per_million: row.get(6)?,
+                tiers: parse_tiers(row.get(7)?),
+                effective_from: row.get(8)?,
+                effective_to: Some(row.get(9)?),
+            })
+        })
+        .map_err(|e| AppError::Database(e.to_string()))?;
+    rows.collect::<Result<Vec<_>, _>>()
+        .map_err(|e| AppError::Database(e.to_string()))
+}
+
+/// 单个模型的全部定价版本
+struct PricingTimeline {
+    current: ModelPricing,
+    /// (effective_from, effective_to, pricing)
+    history: Vec<(i64, i64, ModelPricing)>,
+}
+
+impl PricingTimeline {
+    /// 命中历史区间时使用该版本；早于所有历史版本时使用最早的版本，否则使用当前定价
+    fn at(&self, timestamp: i64) -> &ModelPricing {
+        if let Some((_, _, pricing)) = self
+            .history
+            .iter()
+            .rev()
+            .find(|(from, to, _)| *from <= timestamp && timestamp < *to)
+        {
+            return pricing;
+        }
+        self.history
+            .iter()
+            .min_by_key(|(from, _, _)| *from)
+            .filter(|(from, _, _)| timestamp < *from)
+            .map(|(_, _, pricing)| pricing)
+            .unwrap_or(&self.current)
+    }
+}
+
+/// 按模型缓存定价时间线，批量计算成本时避免逐条查询
+#[derive(Default)]
+pub(crate) struct PricingLookup {
+    cache: HashMap<String, Option<PricingTimeline>>,
+}
+
+impl PricingLookup {
+    /// 获取模型在指定时间（秒）生效的定价
+    ///
+    /// 命中历史区间时使用历史定价，早于所有历史版本时使用最早的版本，否则使用当前定价
+    pub fn pricing_at(
+        &mut self,
+        conn: &Connection,
+        model: &str,
+        timestamp: i64,
+    ) -> Result<Option<ModelPricing>, AppError> {
+        if !self.cache.contains_key(model) {
+            let timeline = Self::load_timeline(conn, model)?;
+            self.cache.insert(model.to_string(), timeline);
+        }
+        Ok(self
+            .cache
+            .get(model)
+            .and_then(|timeline| timeline.as_ref())
+            .map(|timeline| timeline.at(timestamp).clone()))
+    }
+
+    fn load_timeline(conn: &Connection, model: &str) -> Result<Option<PricingTimeline>, AppError> {
+        let Some(model_id) = find_model_pricing_row(conn, model)? else {
+            return Ok(None);
+        };
+        let Some(current) = load_current_version(conn, &model_id)? else {
+            return Ok(None);
+        };
+        let history = load_history_versions(conn, &model_id)?
+            .into_iter()
+            .map(|version| {
+                let pricing = version.to_pricing()?;
+                Ok((
+                    version.effective_from,
+                    version.effective_to.unwrap_or(i64::MAX),
+                    pricing,
+                ))
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+        Ok(Some(PricingTimeline {
+            current: current.to_pricing()?,
+            history,
+        }))
+    }
+}
+
+/// 获取模型在指定时间（秒）生效的定价
+pub(crate) fn find_model_pricing_at(
+    conn: &Connection,
+    model: &str,
+    timestamp: i64,
+) -> Result<Option<ModelPricing>, AppError> {
+    PricingLookup::default().pricing_at(conn, model, timestamp)
+}
+
+/// 重新计算历史请求成本的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecomputeCostsResult {
+    /// 扫描的请求数
+    pub scanned: u64,
+    /// 成本发生变化并已更新的请求数
+    pub updated: u64,
+    /// 未找到定价而跳过的请求数
+    pub missing_pricing: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::{lock_conn, Database};
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_pricing_at_uses_history_window() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let conn = lock_conn!(db.conn);
+        conn.execute(
+            "INSERT OR REPLACE INTO model_pricing (
+                model_id, display_name, input_cost_per_million, output_cost_per_million,
+                cache_read_cost_per_million, cache_creation_cost_per_million, effective_from
+            ) VALUES ('test-model', 'Test', '2', '8', '0', '0', 2000)",
+            [],
+        )?;
+        conn.execute(
+            "INSERT INTO model_pricing_history (
+                model_id, input_cost_per_million, output_cost_per_million,
+                effective_from, effective_to
+            ) VALUES ('test-model', '1', '4', 1000, 2000)",
+            [],
+        )?;
+
+        let mut lookup = PricingLookup::default();
+        let old = lookup
+            .pricing_at(&conn, "vendor/test-model", 1500)?
+            .unwrap();
+        assert_eq!(old.input_cost_per_million, Decimal::from_str("1").unwrap());
+
+        let new = lookup.pricing_at(&conn, "test-model", 2000)?.unwrap();
+        assert_eq!(new.input_cost_per_million, Decimal::from_str("2").unwrap());
+
+        // 早于所有历史区间时使用最早的版本
+        let oldest = lookup.pricing_at(&conn, "test-model", 10)?.unwrap();
+        assert_eq!(
+            oldest.input_cost_per_million,
+            Decimal::from_str("1").unwrap()
+        );
+
+        assert!(lookup.pricing_at(&conn, "unknown-model", 1500)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_seeded_long_context_tiers() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let conn = lock_conn!(db.conn);
+        let pricing = find_model_pricing_at(&conn, "claude-sonnet-4-5-20250929", 0)?.unwrap();
+        assert_eq!(pricing.tiers.len(), 1);
+        assert_eq!(
+            pricing.tier_for(300_000).input_cost_per_million,
+            Decimal::from_str("6").unwrap()
+        );
+        assert_eq!(
+            pricing.cache_creation_1h_cost_per_million,
+            Some(Decimal::from_str("6").unwrap())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_tiers_rejects_duplicates() {
+        let tier = PricingTierConfig {
+            above_input_tokens: 200_000,
+            input_cost_per_million: "6".to_string(),
+            output_cost_per_million: "22.5".to_string(),
+            cache_read_cost_per_million: None,
+            cache_creation_cost_per_million: None,
+            cache_creation_1h_cost_per_million: None,
+        };
+        assert!(validate_tiers(std::slice::from_ref(&tier)).is_ok());
+        assert!(validate_tiers(&[tier.clone(), tier]).is_err());
+    }
+}
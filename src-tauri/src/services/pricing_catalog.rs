@@ -0,0 +1,573 @@
+//! 模型定价目录导入
+//!
+//! 支持三种常见的公开价目格式：
+//! - LiteLLM `model_prices_and_context_window.json`（按 token 计价，含缓存与长上下文阶梯）
+//! - OpenRouter `/models` 接口返回的 JSON（按 token 计价，字符串形式）
+//! - 简单 CSV（按百万 token 计价）
+//!
+//! 解析结果统一为 [`CatalogEntry`]，模型 ID 按 `clean_model_id` 清洗（与计价时的匹配规则一致），
+//! 由 DAO 负责与现有定价比对与写入
+
+use regex::Regex;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use crate::error::AppError;
+use crate::proxy::usage::calculator::PricingTierConfig;
+use crate::services::pricing::{validate_tiers, ModelPricingVersion, RecomputeCostsResult};
+use crate::services::usage_stats::clean_model_id;
+
+/// 价目文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PricingCatalogFormat {
+    Litellm,
+    Openrouter,
+    Csv,
+}
+
+/// 目录中的一个模型定价
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CatalogEntry {
+    pub display_name: String,
+    pub pricing: ModelPricingVersion,
+}
+
+/// 解析时跳过的条目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SkippedCatalogEntry {
+    pub model_id: String,
+    pub reason: String,
+}
+
+/// 解析后的价目目录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingCatalog {
+    pub format: PricingCatalogFormat,
+    pub entries: Vec<CatalogEntry>,
+    pub skipped: Vec<SkippedCatalogEntry>,
+}
+
+/// 导入预览中单个模型的变更类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PricingChangeKind {
+    Added,
+    Updated,
+    Unchanged,
+}
+
+/// 导入预览中的单个模型
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingImportChange {
+    pub model_id: String,
+    pub display_name: String,
+    pub kind: PricingChangeKind,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub current: Option<ModelPricingVersion>,
+    pub incoming: ModelPricingVersion,
+    /// 当前记录中使用该模型、但尚未计价的请求数
+    pub unpriced_requests: u64,
+}
+
+/// 导入预览（应用前的差异）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingImportPreview {
+    pub format: PricingCatalogFormat,
+    pub added: u64,
+    pub updated: u64,
+    pub unchanged: u64,
+    pub changes: Vec<PricingImportChange>,
+    pub skipped: Vec<SkippedCatalogEntry>,
+}
+
+/// 导入选项
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingImportOptions {
+    /// 是否覆盖已有模型的定价（默认仅新增）
+    #[serde(default)]
+    pub overwrite_existing: bool,
+    /// 仅导入指定模型（为空时导入全部）
+    #[serde(default)]
+    pub model_ids: Option<Vec<String>>,
+    /// 覆盖定价的生效时间（秒），为空时从当前时间生效
+    #[serde(default)]
+    pub effective_from: Option<i64>,
+    /// 导入后是否为未计价的历史请求补算成本
+    #[serde(default)]
+    pub backfill: bool,
+}
+
+/// 导入结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PricingImportResult {
+    pub added: u64,
+    pub updated: u64,
+    pub unchanged: u64,
+    /// 价格不同但未开启覆盖而保留的已有定价
+    pub kept_existing: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backfill: Option<RecomputeCostsResult>,
+}
+
+/// 根据内容推断价目格式
+pub fn detect_format(content: &str) -> PricingCatalogFormat {
+    match serde_json::from_str::<Value>(content) {
+        Ok(Value::Object(map)) if map.get("data").is_some_and(Value::is_array) => {
+            PricingCatalogFormat::Openrouter
+        }
+        Ok(_) => PricingCatalogFormat::Litellm,
+        Err(_) => PricingCatalogFormat::Csv,
+    }
+}
+
+/// 解析价目文件，`format` 为空时自动推断
+pub fn parse_catalog(
+    content: &str,
+    format: Option<PricingCatalogFormat>,
+) -> Result<PricingCatalog, AppError> {
+    let format = format.unwrap_or_else(|| detect_format(content));
+    let mut catalog = PricingCatalog {
+        format,
+        entries: Vec::new(),
+        skipped: Vec::new(),
+    };
+    match format {
+        PricingCatalogFormat::Litellm => parse_litellm(content, &mut catalog)?,
+        PricingCatalogFormat::Openrouter => parse_openrouter(content, &mut catalog)?,
+        PricingCatalogFormat::Csv => parse_csv(content, &mut catalog)?,
+    }
+    Ok(catalog)
+}
+
+/// 解析价格数值（支持数字、字符串与科学计数法）
+fn parse_price(value: &Value) -> Option<Decimal> {
+    let raw = match value {
+        Value::Number(n) => n.to_string(),
+        Value::String(s) => s.trim().to_string(),
+        _ => return None,
+    };
+    Decimal::from_str(&raw)
+        .or_else(|_| Decimal::from_scientific(&raw))
+        .ok()
+}
+
+/// 每 token 价格换算为每百万 token 价格
+fn per_million(per_token: Decimal) -> String {
+    (per_token * Decimal::from(1_000_000))
+        .round_dp(6)
+        .normalize()
+        .to_string()
+}
+
+fn new_version(model_id: String) -> ModelPricingVersion {
+    ModelPricingVersion {
+        id: None,
+        model_id,
+        input_cost_per_million: "0".to_string(),
+        output_cost_per_million: "0".to_string(),
+        cache_read_cost_per_million: "0".to_string(),
+        cache_creation_cost_per_million: "0".to_string(),
+        cache_creation_1h_cost_per_million: None,
+        tiers: Vec::new(),
+        effective_from: 0,
+        effective_to: None,
+    }
+}
+
+/// 按清洗后的 ID 去重：同名模型优先采用规范条目，其余保留先出现的
+fn insert_deduped(
+    entries: &mut BTreeMap<String, (bool, CatalogEntry)>,
+    canonical: bool,
+    entry: CatalogEntry,
+) {
+    match entries.get(&entry.pricing.model_id) {
+        Some((existing_canonical, _)) if *existing_canonical || !canonical => {}
+        _ => {
+            entries.insert(entry.pricing.model_id.clone(), (canonical, entry));
+        }
+    }
+}
+
+fn parse_litellm(content: &str, catalog: &mut PricingCatalog) -> Result<(), AppError> {
+    let root: Map<String, Value> = serde_json::from_str(content)
+        .map_err(|e| AppError::InvalidInput(format!("LiteLLM 价目解析失败: {e}")))?;
+    let tier_key =
+        Regex::new(r"^input_cost_per_token_above_(\d+)k_tokens$").expect("tier key regex is valid");
+
+    let mut entries = BTreeMap::new();
+    for (raw_id, spec) in &root {
+        if raw_id == "sample_spec" {
+            continue;
+        }
+        let Some(spec) = spec.as_object() else {
+            continue;
+        };
+        // 仅导入对话类模型，跳过 embedding、图像、语音等
+        let mode = spec.get("mode").and_then(Value::as_str).unwrap_or("chat");
+        if !matches!(mode, "chat" | "completion" | "responses") {
+            continue;
+        }
+        let price = |key: &str| spec.get(key).and_then(parse_price);
+        let (Some(input), Some(output)) = (
+            price("input_cost_per_token"),
+            price("output_cost_per_token"),
+        ) else {
+            catalog.skipped.push(SkippedCatalogEntry {
+                model_id: raw_id.clone(),
+                reason: "缺少输入或输出价格".to_string(),
+            });
+            continue;
+        };
+
+        let mut version = new_version(clean_model_id(raw_id));
+        version.input_cost_per_million = per_million(input);
+        version.output_cost_per_million = per_million(output);
+        if let Some(cache_read) = price("cache_read_input_token_cost") {
+            version.cache_read_cost_per_million = per_million(cache_read);
+        }
+        if let Some(cache_creation) = price("cache_creation_input_token_cost") {
+            version.cache_creation_cost_per_million = per_million(cache_creation);
+        }
+        version.cache_creation_1h_cost_per_million =
+            price("cache_creation_input_token_cost_above_1hr").map(per_million);
+
+        for key in spec.keys() {
+            let Some(captures) = tier_key.captures(key) else {
+                continue;
+            };
+            let Ok(thousands) = captures[1].parse::<u64>() else {
+                continue;
+            };
+            let suffix = format!("above_{}k_tokens", &captures[1]);
+            let Some(tier_input) = price(key) else {
+                continue;
+            };
+            let tier_output = price(&format!("output_cost_per_token_{suffix}")).unwrap_or(output);
+            version.tiers.push(PricingTierConfig {
+                above_input_tokens: thousands * 1000,
+                input_cost_per_million: per_million(tier_input),
+                output_cost_per_million: per_million(tier_output),
+                cache_read_cost_per_million: price(&format!(
+                    "cache_read_input_token_cost_{suffix}"
+                ))
+                .map(per_million),
+                cache_creation_cost_per_million: price(&format!(
+                    "cache_creation_input_token_cost_{suffix}"
+                ))
+                .map(per_million),
+                cache_creation_1h_cost_per_million: None,
+            });
+        }
+        version.tiers.sort_by_key(|tier| tier.above_input_tokens);
+
+        if let Err(e) = validate_tiers(&version.tiers) {
+            catalog.skipped.push(SkippedCatalogEntry {
+                model_id: raw_id.clone(),
+                reason: e.to_string(),
+            });
+            continue;
+        }
+        let entry = CatalogEntry {
+            display_name: version.model_id.clone(),
+            pricing: version,
+        };
+        // 不带供应商前缀的条目为规范条目（如 bedrock/、vertex_ai/ 为转售价格）
+        insert_deduped(&mut entries, !raw_id.contains('/'), entry);
+    }
+
+    catalog
+        .entries
+        .extend(entries.into_values().map(|(_, entry)| entry));
+    Ok(())
+}
+
+fn parse_openrouter(content: &str, catalog: &mut PricingCatalog) -> Result<(), AppError> {
+    let root: Value = serde_json::from_str(content)
+        .map_err(|e| AppError::InvalidInput(format!("OpenRouter 价目解析失败: {e}")))?;
+    let models = root
+        .get("data")
+        .and_then(Value::as_array)
+        .ok_or_else(|| AppError::InvalidInput("OpenRouter 价目缺少 data 数组".to_string()))?;
+
+    let mut entries = BTreeMap::new();
+    for model in models {
+        let Some(raw_id) = model.get("id").and_then(Value::as_str) else {
+            continue;
+        };
+        let pricing = model.get("pricing");
+        let price = |key: &str| pricing.and_then(|p| p.get(key)).and_then(parse_price);
+        let (Some(input), Some(output)) = (price("prompt"), price("completion")) else {
+            catalog.skipped.push(SkippedCatalogEntry {
+                model_id: raw_id.to_string(),
+                reason: "缺少输入或输出价格".to_string(),
+            });
+            continue;
+        };
+        // 自动路由等模型的价格为 -1，无法用于计价
+        if input < Decimal::ZERO || output < Decimal::ZERO {
+            catalog.skipped.push(SkippedCatalogEntry {
+                model_id: raw_id.to_string(),
+                reason: "价格不固定".to_string(),
+            });
+            continue;
+        }
+
+        let mut version = new_version(clean_model_id(raw_id));
+        version.input_cost_per_million = per_million(input);
+        version.output_cost_per_million = per_million(output);
+        if let Some(cache_read) = price("input_cache_read") {
+            version.cache_read_cost_per_million = per_million(cache_read);
+        }
+        if let Some(cache_write) = price("input_cache_write") {
+            version.cache_creation_cost_per_million = per_million(cache_write);
+        }
+        let display_name = model
+            .get("name")
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| version.model_id.clone());
+        let entry = CatalogEntry {
+            display_name,
+            pricing: version,
+        };
+        // 免费版（:free）等变体与正式版清洗后同名，以正式版为准
+        insert_deduped(&mut entries, !raw_id.contains(':'), entry);
+    }
+
+    catalog
+        .entries
+        .extend(entries.into_values().map(|(_, entry)| entry));
+    Ok(())
+}
+
+/// 拆分一行 CSV，支持双引号包裹与 "" 转义
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field).trim().to_string()),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field.trim().to_string());
+    fields
+}
+
+/// CSV 表头：model_id,display_name,input,output,cache_read,cache_creation,cache_creation_1h
+/// 仅 model_id、input、output 为必填列，价格单位为每百万 token
+fn parse_csv(content: &str, catalog: &mut PricingCatalog) -> Result<(), AppError> {
+    let mut lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'));
+    let (_, header) = lines
+        .next()
+        .ok_or_else(|| AppError::InvalidInput("CSV 价目为空".to_string()))?;
+    let header: Vec<String> = split_csv_line(header.trim_start_matches('\u{feff}'))
+        .into_iter()
+        .map(|h| h.to_ascii_lowercase())
+        .collect();
+    let column = |names: &[&str]| header.iter().position(|h| names.contains(&h.as_str()));
+    let model_col = column(&["model_id", "model"])
+        .ok_or_else(|| AppError::InvalidInput("CSV 价目缺少 model_id 列".to_string()))?;
+    let input_col = column(&["input", "input_cost_per_million"])
+        .ok_or_else(|| AppError::InvalidInput("CSV 价目缺少 input 列".to_string()))?;
+    let output_col = column(&["output", "output_cost_per_million"])
+        .ok_or_else(|| AppError::InvalidInput("CSV 价目缺少 output 列".to_string()))?;
+    let name_col = column(&["display_name", "name"]);
+    let cache_read_col = column(&["cache_read", "cache_read_cost_per_million"]);
+    let cache_creation_col = column(&["cache_creation", "cache_creation_cost_per_million"]);
+    let cache_1h_col = column(&["cache_creation_1h", "cache_creation_1h_cost_per_million"]);
+
+    let mut entries = BTreeMap::new();
+    for (index, line) in lines {
+        let fields = split_csv_line(line);
+        let field = |col: Option<usize>| {
+            col.and_then(|c| fields.get(c))
+                .map(String::as_str)
+                .filter(|v| !v.is_empty())
+        };
+        let Some(raw_id) = field(Some(model_col)) else {
+            return Err(AppError::InvalidInput(format!(
+                "CSV 第 {} 行缺少 model_id",
+                index + 1
+            )));
+        };
+        let price = |col: Option<usize>| -> Result<Option<String>, AppError> {
+            field(col)
+                .map(|raw| {
+                    Decimal::from_str(raw)
+                        .ok()
+                        .filter(|value| *value >= Decimal::ZERO)
+                        .map(|value| value.normalize().to_string())
+                        .ok_or_else(|| {
+                            AppError::InvalidInput(format!(
+                                "CSV 第 {} 行价格无效: {raw}",
+                                index + 1
+                            ))
+                        })
+                })
+                .transpose()
+        };
+        let (Some(input), Some(output)) = (price(Some(input_col))?, price(Some(output_col))?)
+        else {
+            catalog.skipped.push(SkippedCatalogEntry {
+                model_id: raw_id.to_string(),
+                reason: "缺少输入或输出价格".to_string(),
+            });
+            continue;
+        };
+
+        let mut version = new_version(clean_model_id(raw_id));
+        version.input_cost_per_million = input;
+        version.output_cost_per_million = output;
+        if let Some(cache_read) = price(cache_read_col)? {
+            version.cache_read_cost_per_million = cache_read;
+        }
+        if let Some(cache_creation) = price(cache_creation_col)? {
+            version.cache_creation_cost_per_million = cache_creation;
+        }
+        version.cache_creation_1h_cost_per_million = price(cache_1h_col)?;
+        let display_name = field(name_col)
+            .map(str::to_string)
+            .unwrap_or_else(|| version.model_id.clone());
+        // CSV 中后出现的同名模型覆盖先出现的
+        entries.insert(
+            version.model_id.clone(),
+            (
+                true,
+                CatalogEntry {
+                    display_name,
+                    pricing: version,
+                },
+            ),
+        );
+    }
+
+    catalog
+        .entries
+        .extend(entries.into_values().map(|(_, entry)| entry));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry<'a>(catalog: &'a PricingCatalog, model_id: &str) -> &'a ModelPricingVersion {
+        &catalog
+            .entries
+            .iter()
+            .find(|e| e.pricing.model_id == model_id)
+            .unwrap_or_else(|| panic!("missing {model_id}"))
+            .pricing
+    }
+
+    #[test]
+    fn test_parse_litellm_with_tiers_and_prefix_dedup() {
+        let content = r#"{
+            "sample_spec": {"input_cost_per_token": 0},
+            "claude-sonnet-4-5": {
+                "mode": "chat",
+                "input_cost_per_token": 3e-06,
+                "output_cost_per_token": 1.5e-05,
+                "cache_read_input_token_cost": 3e-07,
+                "cache_creation_input_token_cost": 3.75e-06,
+                "cache_creation_input_token_cost_above_1hr": 6e-06,
+                "input_cost_per_token_above_200k_tokens": 6e-06,
+                "output_cost_per_token_above_200k_tokens": 2.25e-05
+            },
+            "bedrock/claude-sonnet-4-5": {
+                "input_cost_per_token": 9e-06,
+                "output_cost_per_token": 1e-05
+            },
+            "openrouter/moonshotai/kimi-k2": {
+                "input_cost_per_token": 6e-07,
+                "output_cost_per_token": 2.5e-06
+            },
+            "text-embedding-3-small": {"mode": "embedding", "input_cost_per_token": 2e-08},
+            "broken": {"mode": "chat"}
+        }"#;
+
+        let catalog = parse_catalog(content, None).unwrap();
+        assert_eq!(catalog.format, PricingCatalogFormat::Litellm);
+        assert_eq!(catalog.entries.len(), 2);
+
+        let sonnet = entry(&catalog, "claude-sonnet-4-5");
+        assert_eq!(sonnet.input_cost_per_million, "3");
+        assert_eq!(sonnet.output_cost_per_million, "15");
+        assert_eq!(sonnet.cache_read_cost_per_million, "0.3");
+        assert_eq!(sonnet.cache_creation_cost_per_million, "3.75");
+        assert_eq!(
+            sonnet.cache_creation_1h_cost_per_million.as_deref(),
+            Some("6")
+        );
+        assert_eq!(sonnet.tiers.len(), 1);
+        assert_eq!(sonnet.tiers[0].above_input_tokens, 200_000);
+        assert_eq!(sonnet.tiers[0].output_cost_per_million, "22.5");
+
+        assert_eq!(entry(&catalog, "kimi-k2").input_cost_per_million, "0.6");
+        assert_eq!(catalog.skipped.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_openrouter() {
+        let content = r#"{"data": [
+            {"id": "z-ai/glm-4.6", "name": "Z.AI: GLM 4.6",
+             "pricing": {"prompt": "0.0000006", "completion": "0.0000022", "input_cache_read": "0.00000011"}},
+            {"id": "openrouter/auto", "pricing": {"prompt": "-1", "completion": "-1"}}
+        ]}"#;
+
+        let catalog = parse_catalog(content, None).unwrap();
+        assert_eq!(catalog.format, PricingCatalogFormat::Openrouter);
+        assert_eq!(catalog.entries.len(), 1);
+        assert_eq!(catalog.entries[0].display_name, "Z.AI: GLM 4.6");
+        let glm = entry(&catalog, "glm-4.6");
+        assert_eq!(glm.output_cost_per_million, "2.2");
+        assert_eq!(glm.cache_read_cost_per_million, "0.11");
+        assert_eq!(catalog.skipped[0].model_id, "openrouter/auto");
+    }
+
+    #[test]
+    fn test_parse_csv() {
+        let content = "\u{feff}model_id,display_name,input,output,cache_read\n\
+                       # 国内模型\n\
+                       deepseek-chat,\"DeepSeek, V3\",0.27,1.1,0.07\n\
+                       qwen3-max,,1.2,6,\n";
+
+        let catalog = parse_catalog(content, None).unwrap();
+        assert_eq!(catalog.format, PricingCatalogFormat::Csv);
+        assert_eq!(catalog.entries.len(), 2);
+        assert_eq!(catalog.entries[0].display_name, "DeepSeek, V3");
+        assert_eq!(
+            entry(&catalog, "deepseek-chat").cache_read_cost_per_million,
+            "0.07"
+        );
+        assert_eq!(
+            entry(&catalog, "qwen3-max").cache_read_cost_per_million,
+            "0"
+        );
+
+        assert!(parse_catalog("model_id,input,output\nfoo,abc,1\n", None).is_err());
+        assert!(parse_catalog("name,price\n", Some(PricingCatalogFormat::Csv)).is_err());
+    }
+}
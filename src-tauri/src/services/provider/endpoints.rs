@@ -6,6 +6,8 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::app_config::AppType;
 use crate::error::AppError;
+use crate::proxy::providers::get_adapter;
+use crate::services::speedtest::EndpointLatency;
 use crate::settings::CustomEndpoint;
 use crate::store::AppState;
 
@@ -89,6 +91,50 @@ pub fn update_endpoint_last_used(
     Ok(())
 }
 
+/// Persist speedtest results for endpoints that belong to the provider
+///
+/// The proxy orders a provider's endpoint pool by these latencies. The
+/// primary base URL's result is kept in the provider meta rather than the
+/// custom endpoint table, so it is ranked against its mirrors without
+/// appearing as one.
+pub fn record_endpoint_latencies(
+    state: &AppState,
+    app_type: AppType,
+    provider_id: &str,
+    results: &[EndpointLatency],
+) -> Result<usize, AppError> {
+    let primary = state
+        .db
+        .get_provider_by_id(provider_id, app_type.as_str())?
+        .and_then(|provider| get_adapter(&app_type).extract_base_url(&provider).ok())
+        .map(|url| url.trim().trim_end_matches('/').to_string());
+
+    let mut updated = 0;
+    for result in results {
+        let normalized = result.url.trim().trim_end_matches('/');
+        // 请求失败（超时/连接失败）记为不可用
+        let latency = result.latency.map(|v| v as u64);
+        let mut recorded = state.db.update_custom_endpoint_latency(
+            app_type.as_str(),
+            provider_id,
+            normalized,
+            latency,
+        )?;
+        if primary.as_deref() == Some(normalized) {
+            recorded |= state.db.update_primary_endpoint_latency(
+                app_type.as_str(),
+                provider_id,
+                normalized,
+                latency,
+            )?;
+        }
+        if recorded {
+            updated += 1;
+        }
+    }
+    Ok(updated)
+}
+
 /// Get current timestamp in milliseconds
 fn now_millis() -> i64 {
     SystemTime::now()
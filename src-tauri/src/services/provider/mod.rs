@@ -855,6 +855,16 @@ impl ProviderService {
         endpoints::remove_custom_endpoint(state, app_type, provider_id, url)
     }
 
+    /// Persist endpoint speedtest results (re-export)
+    pub fn record_endpoint_latencies(
+        state: &AppState,
+        app_type: AppType,
+        provider_id: &str,
+        results: &[crate::services::speedtest::EndpointLatency],
+    ) -> Result<usize, AppError> {
+        endpoints::record_endpoint_latencies(state, app_type, provider_id, results)
+    }
+
     /// Update endpoint last used timestamp (re-export)
     pub fn update_endpoint_last_used(
         state: &AppState,
@@ -5,6 +5,9 @@
 use crate::app_config::AppType;
 use crate::error::AppError;
 use crate::provider::{UsageData, UsageResult, UsageScript};
+use crate::services::balance_reconciliation;
+use crate::services::notification::{self, NotificationEvent};
+use crate::services::usage_poller;
 use crate::settings;
 use crate::store::AppState;
 use crate::usage_script;
@@ -115,7 +118,16 @@ pub async fn query_usage(
     app_type: AppType,
     provider_id: &str,
 ) -> Result<UsageResult, AppError> {
-    let (script_code, timeout, api_key, base_url, access_token, user_id, template_type) = {
+    let (
+        provider_name,
+        script_code,
+        timeout,
+        api_key,
+        base_url,
+        access_token,
+        user_id,
+        template_type,
+    ) = {
         let providers = state.db.get_all_providers(app_type.as_str())?;
         let provider = providers.get(provider_id).ok_or_else(|| {
             AppError::localized(
@@ -160,6 +172,7 @@ pub async fn query_usage(
             .unwrap_or_default();
 
         (
+            provider.name.clone(),
             usage_script.code.clone(),
             usage_script.timeout.unwrap_or(10),
             api_key,
@@ -170,7 +183,8 @@ pub async fn query_usage(
         )
     };
 
-    execute_and_format_usage_result(
+    usage_poller::record_query(&app_type, provider_id);
+    let result = execute_and_format_usage_result(
         &script_code,
         &api_key,
         &base_url,
@@ -179,7 +193,28 @@ pub async fn query_usage(
         user_id.as_deref(),
         template_type.as_deref(),
     )
-    .await
+    .await?;
+
+    if let Err(e) =
+        balance_reconciliation::record_snapshots(&state.db, app_type.as_str(), provider_id, &result)
+    {
+        log::warn!("保存余额快照失败 (provider_id={provider_id}): {e}");
+    }
+
+    for data in result.data.iter().flatten() {
+        notification::notify(NotificationEvent::QuotaChecked {
+            app_type: app_type.as_str().to_string(),
+            provider_id: provider_id.to_string(),
+            provider_name: provider_name.clone(),
+            plan_name: data.plan_name.clone(),
+            is_valid: data.is_valid,
+            remaining: data.remaining,
+            total: data.total,
+            unit: data.unit.clone(),
+        });
+    }
+
+    Ok(result)
 }
 
 /// Test usage script (using temporary script content, not saved)
@@ -1909,6 +1909,17 @@ impl ProxyService {
         }
         Ok(())
     }
+
+    /// 将后台健康探测结果同步到运行中的熔断器
+    ///
+    /// 代理服务器未运行时没有内存中的熔断器，直接忽略
+    pub async fn apply_probe_result(&self, provider_id: &str, app_type: &str, healthy: bool) {
+        if let Some(server) = self.server.read().await.as_ref() {
+            server
+                .apply_probe_result(provider_id, app_type, healthy)
+                .await;
+        }
+    }
 }
 
 #[cfg(test)]
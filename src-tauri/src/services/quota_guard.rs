@@ -0,0 +1,401 @@
+//! 额度守护服务
+//!
+//! 将用量脚本的查询结果转为路由容量信号：
+//! - 额度耗尽：记录状态，按规则降低代理路由优先级 / 移出故障转移队列 / 切走当前供应商
+//! - 额度恢复：撤销上述动作（可配置）
+//!
+//! 用量脚本由 `usage_poller` 按各供应商的 `autoQueryInterval` 统一轮询，
+//! 前端手动查询的结果也会经过同一套判定。
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::provider::{QuotaGuard, QuotaGuardAction, UsageResult};
+use crate::services::provider::ProviderService;
+use crate::store::AppState;
+
+/// 供应商额度耗尽状态
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaExhaustion {
+    pub app_type: String,
+    pub provider_id: String,
+    pub reason: String,
+    /// 是否由守护移出了故障转移队列（恢复时需加回）
+    pub removed_from_queue: bool,
+    /// 守护切换到的供应商（恢复时若当前仍为它则切回）
+    pub switched_to: Option<String>,
+    pub exhausted_at: i64,
+}
+
+/// 一次判定带来的状态变化
+#[derive(Debug, Clone, Serialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "camelCase")]
+pub enum QuotaTransition {
+    Unchanged,
+    Exhausted { reason: String },
+    Restored,
+}
+
+/// 判断阈值是否命中，返回原因描述
+///
+/// 用量脚本与通知规则共用此判定。
+pub(crate) fn threshold_reason(
+    is_valid: Option<bool>,
+    remaining: Option<f64>,
+    total: Option<f64>,
+    on_invalid: bool,
+    remaining_below: Option<f64>,
+    percent_below: Option<f64>,
+) -> Option<String> {
+    if on_invalid && is_valid == Some(false) {
+        return Some("套餐已失效".to_string());
+    }
+    let remaining = remaining?;
+    if let Some(threshold) = remaining_below {
+        if remaining < threshold {
+            return Some(format!("剩余额度低于 {threshold}"));
+        }
+    }
+    if let (Some(threshold), Some(total)) = (percent_below, total) {
+        if total > 0.0 && remaining / total * 100.0 < threshold {
+            return Some(format!("剩余额度低于 {threshold}%"));
+        }
+    }
+    None
+}
+
+/// 额度守护业务
+pub struct QuotaGuardService;
+
+impl QuotaGuardService {
+    /// 根据用量查询结果判定额度状态
+    ///
+    /// - `None`：查询失败或无数据，状态未知，不做任何变更
+    /// - `Some(None)`：额度充足
+    /// - `Some(Some(reason))`：所有套餐均已耗尽
+    pub fn evaluate(guard: &QuotaGuard, result: &UsageResult) -> Option<Option<String>> {
+        if !result.success {
+            return None;
+        }
+        let plans = result.data.as_ref().filter(|d| !d.is_empty())?;
+
+        let mut reasons = Vec::with_capacity(plans.len());
+        for plan in plans {
+            match threshold_reason(
+                plan.is_valid,
+                plan.remaining,
+                plan.total,
+                guard.on_invalid,
+                guard.remaining_below,
+                guard.percent_below,
+            ) {
+                Some(reason) => reasons.push(reason),
+                None => return Some(None),
+            }
+        }
+        Some(reasons.into_iter().next())
+    }
+
+    /// 应用用量查询结果（未启用守护规则时直接返回 Unchanged）
+    pub async fn apply_usage_result(
+        app: &AppHandle,
+        app_type: &AppType,
+        provider_id: &str,
+        result: &UsageResult,
+    ) -> Result<QuotaTransition, AppError> {
+        let state = app.state::<AppState>();
+        let Some(guard) = Self::guard_for(&state, app_type, provider_id)? else {
+            return Ok(QuotaTransition::Unchanged);
+        };
+        let Some(verdict) = Self::evaluate(&guard, result) else {
+            return Ok(QuotaTransition::Unchanged);
+        };
+        let existing = state
+            .db
+            .get_quota_exhaustion(app_type.as_str(), provider_id)?;
+
+        let transition = match (verdict, existing) {
+            (Some(reason), None) => {
+                Self::mark_exhausted(app, app_type, provider_id, &guard, &reason).await?;
+                QuotaTransition::Exhausted { reason }
+            }
+            // 关闭自动恢复时保留耗尽状态，直到手动清除
+            (None, Some(exhaustion)) if guard.auto_restore => {
+                Self::restore(app, &exhaustion).await?;
+                QuotaTransition::Restored
+            }
+            _ => QuotaTransition::Unchanged,
+        };
+
+        if transition != QuotaTransition::Unchanged {
+            let payload = serde_json::json!({
+                "appType": app_type.as_str(),
+                "providerId": provider_id,
+                "transition": transition,
+            });
+            if let Err(e) = app.emit("quota-guard-changed", payload) {
+                log::debug!("[QuotaGuard] 发射事件失败: {e}");
+            }
+        }
+        Ok(transition)
+    }
+
+    /// 手动清除耗尽状态并撤销动作
+    pub async fn clear(
+        app: &AppHandle,
+        app_type: &AppType,
+        provider_id: &str,
+    ) -> Result<(), AppError> {
+        let state = app.state::<AppState>();
+        if let Some(exhaustion) = state
+            .db
+            .get_quota_exhaustion(app_type.as_str(), provider_id)?
+        {
+            Self::restore(app, &exhaustion).await?;
+        }
+        Ok(())
+    }
+
+    fn guard_for(
+        state: &AppState,
+        app_type: &AppType,
+        provider_id: &str,
+    ) -> Result<Option<QuotaGuard>, AppError> {
+        Ok(state
+            .db
+            .get_provider_by_id(provider_id, app_type.as_str())?
+            .and_then(|p| p.meta)
+            .and_then(|m| m.usage_script)
+            .filter(|s| s.enabled)
+            .and_then(|s| s.quota_guard)
+            .filter(|g| g.enabled))
+    }
+
+    async fn mark_exhausted(
+        app: &AppHandle,
+        app_type: &AppType,
+        provider_id: &str,
+        guard: &QuotaGuard,
+        reason: &str,
+    ) -> Result<(), AppError> {
+        let state = app.state::<AppState>();
+        let app_str = app_type.as_str();
+        log::warn!("[QuotaGuard] {app_str}/{provider_id} 额度耗尽: {reason}");
+
+        let mut exhaustion = QuotaExhaustion {
+            app_type: app_str.to_string(),
+            provider_id: provider_id.to_string(),
+            reason: reason.to_string(),
+            removed_from_queue: false,
+            switched_to: None,
+            exhausted_at: chrono::Utc::now().timestamp(),
+        };
+
+        match guard.action {
+            QuotaGuardAction::Deprioritize => {}
+            QuotaGuardAction::RemoveFromQueue => {
+                if state.db.is_in_failover_queue(app_str, provider_id)? {
+                    state.db.remove_from_failover_queue(app_str, provider_id)?;
+                    exhaustion.removed_from_queue = true;
+                }
+            }
+            QuotaGuardAction::SwitchCurrent => {
+                let current = state.db.get_current_provider(app_str)?;
+                if current.as_deref() == Some(provider_id) {
+                    let exhausted = state.db.get_exhausted_provider_ids(app_str)?;
+                    let queue: Vec<String> = state
+                        .db
+                        .get_failover_queue(app_str)?
+                        .into_iter()
+                        .map(|item| item.provider_id)
+                        .collect();
+                    match pick_next_provider(&queue, provider_id, |id| exhausted.contains(id)) {
+                        Some(next) => {
+                            Self::switch_current(app, app_type, &next).await?;
+                            exhaustion.switched_to = Some(next);
+                        }
+                        None => {
+                            log::warn!("[QuotaGuard] {app_str} 故障转移队列中没有可切换的供应商")
+                        }
+                    }
+                }
+            }
+        }
+
+        state.db.upsert_quota_exhaustion(&exhaustion)
+    }
+
+    /// 撤销守护动作并清除耗尽状态
+    async fn restore(app: &AppHandle, exhaustion: &QuotaExhaustion) -> Result<(), AppError> {
+        let state = app.state::<AppState>();
+        let app_str = exhaustion.app_type.as_str();
+        let provider_id = exhaustion.provider_id.as_str();
+        log::info!("[QuotaGuard] {app_str}/{provider_id} 额度已恢复");
+
+        if exhaustion.removed_from_queue {
+            state.db.add_to_failover_queue(app_str, provider_id)?;
+        }
+        if let Some(switched_to) = exhaustion.switched_to.as_deref() {
+            // 用户在此期间手动切换过则不再切回
+            if state.db.get_current_provider(app_str)?.as_deref() == Some(switched_to) {
+                let app_type = app_str
+                    .parse::<AppType>()
+                    .map_err(|e| AppError::Message(e.to_string()))?;
+                Self::switch_current(app, &app_type, provider_id).await?;
+            }
+        }
+
+        state.db.delete_quota_exhaustion(app_str, provider_id)
+    }
+
+    /// 切换当前供应商并刷新托盘/前端
+    async fn switch_current(
+        app: &AppHandle,
+        app_type: &AppType,
+        provider_id: &str,
+    ) -> Result<(), AppError> {
+        // ProviderService::switch 内部会阻塞等待异步锁，放到阻塞线程执行
+        let handle = app.clone();
+        let app_type_owned = app_type.clone();
+        let id = provider_id.to_string();
+        tauri::async_runtime::spawn_blocking(move || {
+            let state = handle.state::<AppState>();
+            ProviderService::switch(&state, app_type_owned, &id)
+        })
+        .await
+        .map_err(|e| AppError::Message(format!("切换任务失败: {e}")))??;
+
+        log::info!(
+            "[QuotaGuard] 已切换 {} 当前供应商 → {provider_id}",
+            app_type.as_str()
+        );
+
+        let state = app.state::<AppState>();
+        if let Ok(new_menu) = crate::tray::create_tray_menu(app, state.inner()) {
+            if let Some(tray) = app.tray_by_id("main") {
+                let _ = tray.set_menu(Some(new_menu));
+            }
+        }
+        let event_data = serde_json::json!({
+            "appType": app_type.as_str(),
+            "providerId": provider_id,
+            "source": "quota"
+        });
+        if let Err(e) = app.emit("provider-switched", event_data) {
+            log::error!("[QuotaGuard] 发射事件失败: {e}");
+        }
+        Ok(())
+    }
+}
+
+/// 在故障转移队列中选择下一个可用供应商（从当前位置向后循环查找）
+fn pick_next_provider(
+    queue: &[String],
+    current: &str,
+    is_exhausted: impl Fn(&str) -> bool,
+) -> Option<String> {
+    let start = queue
+        .iter()
+        .position(|id| id == current)
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    (0..queue.len())
+        .map(|offset| &queue[(start + offset) % queue.len()])
+        .find(|id| id.as_str() != current && !is_exhausted(id))
+        .cloned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::provider::UsageData;
+
+    fn plan(remaining: f64, total: f64, is_valid: Option<bool>) -> UsageData {
+        UsageData {
+            plan_name: None,
+            extra: None,
+            is_valid,
+            invalid_message: None,
+            total: Some(total),
+            used: None,
+            remaining: Some(remaining),
+            unit: None,
+        }
+    }
+
+    fn usage(plans: Vec<UsageData>) -> UsageResult {
+        UsageResult {
+            success: true,
+            data: Some(plans),
+            error: None,
+        }
+    }
+
+    fn guard() -> QuotaGuard {
+        QuotaGuard {
+            enabled: true,
+            remaining_below: Some(1.0),
+            percent_below: Some(5.0),
+            on_invalid: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn failed_query_leaves_state_unknown() {
+        let result = UsageResult {
+            success: false,
+            data: None,
+            error: Some("timeout".into()),
+        };
+        assert_eq!(QuotaGuardService::evaluate(&guard(), &result), None);
+    }
+
+    #[test]
+    fn exhausted_only_when_all_plans_hit_threshold() {
+        let g = guard();
+        assert_eq!(
+            QuotaGuardService::evaluate(&g, &usage(vec![plan(50.0, 100.0, Some(true))])),
+            Some(None)
+        );
+        assert!(matches!(
+            QuotaGuardService::evaluate(&g, &usage(vec![plan(0.5, 100.0, Some(true))])),
+            Some(Some(_))
+        ));
+        assert_eq!(
+            QuotaGuardService::evaluate(
+                &g,
+                &usage(vec![plan(0.5, 100.0, None), plan(80.0, 100.0, None)])
+            ),
+            Some(None)
+        );
+        assert!(matches!(
+            QuotaGuardService::evaluate(&g, &usage(vec![plan(90.0, 100.0, Some(false))])),
+            Some(Some(_))
+        ));
+    }
+
+    #[test]
+    fn next_provider_wraps_and_skips_exhausted() {
+        let queue = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        assert_eq!(pick_next_provider(&queue, "b", |_| false), Some("c".into()));
+        assert_eq!(
+            pick_next_provider(&queue, "b", |id| id == "c"),
+            Some("a".into())
+        );
+        assert_eq!(pick_next_provider(&queue, "a", |id| id != "a"), None);
+        assert_eq!(pick_next_provider(&queue, "x", |_| false), Some("a".into()));
+    }
+
+    #[test]
+    fn guard_config_defaults() {
+        let g: QuotaGuard =
+            serde_json::from_str(r#"{"enabled":true,"action":"switchCurrent"}"#).unwrap();
+        assert!(g.on_invalid);
+        assert!(g.auto_restore);
+        assert_eq!(g.action, QuotaGuardAction::SwitchCurrent);
+    }
+}
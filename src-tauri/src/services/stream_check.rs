@@ -69,6 +69,9 @@ pub struct StreamCheckResult {
     pub response_time_ms: Option<u64>,
     pub http_status: Option<u16>,
     pub model_used: String,
+    /// 上游响应中声明的模型（从首个流式事件解析，未声明时为空）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub returned_model: Option<String>,
     pub tested_at: i64,
     pub retry_count: u32,
 }
@@ -127,6 +130,7 @@ impl StreamCheckService {
             response_time_ms: None,
             http_status: None,
             model_used: String::new(),
+            returned_model: None,
             tested_at: chrono::Utc::now().timestamp(),
             retry_count: effective_config.max_retries,
         }))
@@ -254,7 +258,7 @@ impl StreamCheckService {
         let tested_at = chrono::Utc::now().timestamp();
 
         match result {
-            Ok((status_code, model)) => {
+            Ok((status_code, model, returned_model)) => {
                 let health_status =
                     Self::determine_status(response_time, config.degraded_threshold_ms);
                 Ok(StreamCheckResult {
@@ -264,6 +268,7 @@ impl StreamCheckService {
                     response_time_ms: Some(response_time),
                     http_status: Some(status_code),
                     model_used: model,
+                    returned_model,
                     tested_at,
                     retry_count: 0,
                 })
@@ -275,6 +280,7 @@ impl StreamCheckService {
                 response_time_ms: Some(response_time),
                 http_status: None,
                 model_used: String::new(),
+                returned_model: None,
                 tested_at,
                 retry_count: 0,
             }),
@@ -291,7 +297,7 @@ impl StreamCheckService {
         model: &str,
         test_prompt: &str,
         timeout: std::time::Duration,
-    ) -> Result<(u16, String), AppError> {
+    ) -> Result<(u16, String, Option<String>), AppError> {
         let base = base_url.trim_end_matches('/');
         // URL 必须包含 ?beta=true 参数（某些中转服务依赖此参数验证请求来源）
         let url = if base.ends_with("/v1") {
@@ -363,11 +369,15 @@ impl StreamCheckService {
             return Err(AppError::Message(format!("HTTP {status}: {error_text}")));
         }
 
-        // 流式读取：只需首个 chunk
+        // 流式读取：只需首个 chunk（message_start 事件携带上游模型）
         let mut stream = response.bytes_stream();
         if let Some(chunk) = stream.next().await {
             match chunk {
-                Ok(_) => Ok((status, model.to_string())),
+                Ok(bytes) => Ok((
+                    status,
+                    model.to_string(),
+                    Self::extract_stream_model(&bytes),
+                )),
                 Err(e) => Err(AppError::Message(format!("Stream read failed: {e}"))),
             }
         } else {
@@ -385,7 +395,7 @@ impl StreamCheckService {
         model: &str,
         test_prompt: &str,
         timeout: std::time::Duration,
-    ) -> Result<(u16, String), AppError> {
+    ) -> Result<(u16, String, Option<String>), AppError> {
         let base = base_url.trim_end_matches('/');
         // Codex CLI 的 base_url 语义：base_url 是 API base（可能已包含 /v1 或其他自定义前缀），
         // Responses 端点为 `/responses`。
@@ -450,7 +460,9 @@ impl StreamCheckService {
             let mut stream = response.bytes_stream();
             if let Some(chunk) = stream.next().await {
                 match chunk {
-                    Ok(_) => return Ok((status, actual_model)),
+                    Ok(bytes) => {
+                        return Ok((status, actual_model, Self::extract_stream_model(&bytes)))
+                    }
                     Err(e) => return Err(AppError::Message(format!("Stream read failed: {e}"))),
                 }
             }
@@ -473,7 +485,7 @@ impl StreamCheckService {
         model: &str,
         test_prompt: &str,
         timeout: std::time::Duration,
-    ) -> Result<(u16, String), AppError> {
+    ) -> Result<(u16, String, Option<String>), AppError> {
         let base = base_url.trim_end_matches('/');
         // Gemini 原生 API: /v1beta/models/{model}:streamGenerateContent?alt=sse
         // 智能处理 /v1beta 路径：如果 base_url 不包含版本路径，则添加 /v1beta
@@ -513,7 +525,11 @@ impl StreamCheckService {
         let mut stream = response.bytes_stream();
         if let Some(chunk) = stream.next().await {
             match chunk {
-                Ok(_) => Ok((status, model.to_string())),
+                Ok(bytes) => Ok((
+                    status,
+                    model.to_string(),
+                    Self::extract_stream_model(&bytes),
+                )),
                 Err(e) => Err(AppError::Message(format!("Stream read failed: {e}"))),
             }
         } else {
@@ -521,6 +537,31 @@ impl StreamCheckService {
         }
     }
 
+    /// 从首个 SSE chunk 中解析上游声明的模型
+    ///
+    /// 依次识别 Claude `message.model`、Responses API `response.model`、
+    /// Chat Completions `model` 与 Gemini `modelVersion`
+    pub(crate) fn extract_stream_model(chunk: &[u8]) -> Option<String> {
+        let text = String::from_utf8_lossy(chunk);
+        text.lines()
+            .filter_map(|line| line.trim().strip_prefix("data:"))
+            .filter_map(|data| serde_json::from_str::<serde_json::Value>(data.trim()).ok())
+            .find_map(|event| {
+                [
+                    event.pointer("/message/model"),
+                    event.pointer("/response/model"),
+                    event.get("model"),
+                    event.get("modelVersion"),
+                ]
+                .into_iter()
+                .flatten()
+                .find_map(|v| v.as_str())
+                .map(str::trim)
+                .filter(|m| !m.is_empty())
+                .map(str::to_string)
+            })
+    }
+
     fn determine_status(latency_ms: u64, threshold: u64) -> HealthStatus {
         if latency_ms <= threshold {
             HealthStatus::Operational
@@ -659,6 +700,29 @@ impl StreamCheckService {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_extract_stream_model() {
+        let claude = b"event: message_start\ndata: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"model\":\"claude-haiku-4-5-20251001\"}}\n\n";
+        assert_eq!(
+            StreamCheckService::extract_stream_model(claude).as_deref(),
+            Some("claude-haiku-4-5-20251001")
+        );
+        let codex = b"event: response.created\ndata: {\"type\":\"response.created\",\"response\":{\"model\":\"gpt-5.1-codex\"}}\n\n";
+        assert_eq!(
+            StreamCheckService::extract_stream_model(codex).as_deref(),
+            Some("gpt-5.1-codex")
+        );
+        let gemini = b"data: {\"candidates\":[],\"modelVersion\":\"gemini-2.5-flash\"}\n\n";
+        assert_eq!(
+            StreamCheckService::extract_stream_model(gemini).as_deref(),
+            Some("gemini-2.5-flash")
+        );
+        assert_eq!(
+            StreamCheckService::extract_stream_model(b"event: ping\n\n"),
+            None
+        );
+    }
+
     #[test]
     fn test_determine_status() {
         assert_eq!(
@@ -0,0 +1,517 @@
+//! 会话记录用量导入
+//!
+//! 直连使用 CLI（不经过代理）时，`proxy_request_logs` 没有任何记录。
+//! Claude Code / Codex / Gemini CLI 的会话文件中带有逐条消息的 usage 与模型名，
+//! 这里增量读取这些文件并写入请求日志（source = transcript）：
+//! - JSONL 文件按字节偏移增量读取，只处理完整的行
+//! - 以消息 ID 去重，重复读取同一文件不会重复计数
+//! - 已经由代理记录过的请求（同会话、相近时间、相同输出 token）会被跳过
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::Path;
+use std::time::{Duration, UNIX_EPOCH};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::database::{to_json_string, Database};
+use crate::error::AppError;
+use crate::proxy::usage::parser::TokenUsage;
+use crate::session_manager::providers::{claude, codex, gemini};
+use crate::store::AppState;
+
+/// 导入关闭时重新读取配置的间隔
+const DISABLED_RECHECK_SECS: u64 = 60;
+const MIN_INTERVAL_SECS: u64 = 60;
+
+/// 会话记录导入配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TranscriptIngestConfig {
+    pub enabled: bool,
+    /// 导入间隔（秒）
+    pub interval_secs: u64,
+}
+
+impl Default for TranscriptIngestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_secs: 600,
+        }
+    }
+}
+
+/// 一轮导入的结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptIngestResult {
+    /// 扫描的会话文件数
+    pub files_scanned: u64,
+    /// 有新内容的会话文件数
+    pub files_changed: u64,
+    /// 新增的用量记录数
+    pub inserted: u64,
+    /// 已存在但 usage 有增长而更新的记录数
+    pub updated: u64,
+    /// 已由代理记录而跳过的消息数
+    pub proxied: u64,
+    /// 读取失败的文件数
+    pub failed_files: u64,
+}
+
+impl TranscriptIngestResult {
+    fn merge(&mut self, other: &TranscriptIngestResult) {
+        self.inserted += other.inserted;
+        self.updated += other.updated;
+        self.proxied += other.proxied;
+    }
+}
+
+/// 从会话记录中提取的一条用量
+#[derive(Debug, Clone)]
+pub struct TranscriptUsageRecord {
+    pub app_type: &'static str,
+    /// 消息 ID（同一应用内唯一）
+    pub message_id: String,
+    pub session_id: Option<String>,
+    pub model: String,
+    pub usage: TokenUsage,
+    /// 消息时间（秒）
+    pub created_at: i64,
+}
+
+impl TranscriptUsageRecord {
+    /// 写入请求日志时使用的 request_id
+    pub fn request_id(&self) -> String {
+        format!("transcript:{}:{}", self.app_type, self.message_id)
+    }
+}
+
+/// 会话文件的读取进度
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TranscriptCursor {
+    /// 已处理到的字节偏移
+    pub offset: u64,
+    pub file_size: u64,
+    /// 文件修改时间（秒）
+    pub modified_at: i64,
+    /// 解析器需要跨增量保存的上下文（JSON）
+    pub context: Option<String>,
+}
+
+/// Codex 逐行解析的上下文：模型与会话 ID 只在 turn_context / session_meta 中出现
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CodexContext {
+    pub session_id: Option<String>,
+    pub model: Option<String>,
+    /// 上一次 token_count 事件的累计 token，用于跳过重复事件
+    pub last_total_tokens: u64,
+}
+
+fn timestamp_secs(value: &Value) -> Option<i64> {
+    let raw = value.get("timestamp")?.as_str()?;
+    chrono::DateTime::parse_from_rfc3339(raw)
+        .ok()
+        .map(|dt| dt.timestamp())
+}
+
+fn token(value: &Value, key: &str) -> u32 {
+    value.get(key).and_then(Value::as_u64).unwrap_or(0) as u32
+}
+
+/// 解析 Claude Code 会话中的一行（仅 assistant 消息带 usage）
+pub fn parse_claude_line(value: &Value) -> Option<TranscriptUsageRecord> {
+    if value.get("type").and_then(Value::as_str) != Some("assistant") {
+        return None;
+    }
+    let message = value.get("message")?;
+    let usage = TokenUsage::from_claude_response(message)?;
+    // 本地生成的占位消息（如中断、错误提示）不产生费用
+    let model = usage.model.clone().filter(|m| m != "<synthetic>")?;
+    let message_id = message
+        .get("id")
+        .or_else(|| value.get("uuid"))
+        .and_then(Value::as_str)?
+        .to_string();
+
+    Some(TranscriptUsageRecord {
+        app_type: "claude",
+        message_id,
+        session_id: value
+            .get("sessionId")
+            .and_then(Value::as_str)
+            .map(str::to_string),
+        model,
+        usage,
+        created_at: timestamp_secs(value)?,
+    })
+}
+
+/// 解析 Codex rollout 中的一行
+///
+/// 每轮结束时的 token_count 事件带有本轮用量（last_token_usage），
+/// 累计用量（total_token_usage）单调递增，可作为稳定的消息 ID
+pub fn parse_codex_line(
+    value: &Value,
+    context: &mut CodexContext,
+) -> Option<TranscriptUsageRecord> {
+    let payload = value.get("payload")?;
+    match value.get("type").and_then(Value::as_str)? {
+        "session_meta" => {
+            if let Some(id) = payload.get("id").and_then(Value::as_str) {
+                context.session_id = Some(id.to_string());
+            }
+            None
+        }
+        "turn_context" => {
+            if let Some(model) = payload.get("model").and_then(Value::as_str) {
+                context.model = Some(model.to_string());
+            }
+            None
+        }
+        "event_msg" if payload.get("type").and_then(Value::as_str) == Some("token_count") => {
+            let info = payload.get("info").filter(|info| !info.is_null())?;
+            let total = info
+                .get("total_token_usage")
+                .and_then(|t| t.get("total_tokens"))
+                .and_then(Value::as_u64)?;
+            if total <= context.last_total_tokens {
+                return None;
+            }
+            context.last_total_tokens = total;
+
+            let last = info.get("last_token_usage")?;
+            let session_id = context.session_id.clone()?;
+            let usage = TokenUsage {
+                // OpenAI 口径：input_tokens 已包含缓存命中，output_tokens 已包含推理
+                input_tokens: token(last, "input_tokens"),
+                output_tokens: token(last, "output_tokens"),
+                cache_read_tokens: token(last, "cached_input_tokens"),
+                cache_creation_tokens: 0,
+                cache_creation_1h_tokens: 0,
+                model: context.model.clone(),
+            };
+            Some(TranscriptUsageRecord {
+                app_type: "codex",
+                message_id: format!("{session_id}:{total}"),
+                session_id: Some(session_id),
+                model: context
+                    .model
+                    .clone()
+                    .unwrap_or_else(|| "unknown".to_string()),
+                usage,
+                created_at: timestamp_secs(value)?,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// 解析 Gemini CLI 的会话文件（整文件 JSON）
+pub fn parse_gemini_session(value: &Value) -> Vec<TranscriptUsageRecord> {
+    let session_id = value
+        .get("sessionId")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let Some(messages) = value.get("messages").and_then(Value::as_array) else {
+        return Vec::new();
+    };
+
+    messages
+        .iter()
+        .filter(|msg| msg.get("type").and_then(Value::as_str) == Some("gemini"))
+        .filter_map(|msg| {
+            let tokens = msg.get("tokens")?;
+            let model = msg.get("model").and_then(Value::as_str)?.to_string();
+            let usage = TokenUsage {
+                // 与代理口径一致：输出包含思考 token，输入包含缓存命中
+                input_tokens: token(tokens, "input"),
+                output_tokens: token(tokens, "output") + token(tokens, "thoughts"),
+                cache_read_tokens: token(tokens, "cached"),
+                cache_creation_tokens: 0,
+                cache_creation_1h_tokens: 0,
+                model: Some(model.clone()),
+            };
+            Some(TranscriptUsageRecord {
+                app_type: "gemini",
+                message_id: msg.get("id").and_then(Value::as_str)?.to_string(),
+                session_id: session_id.clone(),
+                model,
+                usage,
+                created_at: timestamp_secs(msg)?,
+            })
+        })
+        .collect()
+}
+
+/// 会话记录导入业务
+pub struct TranscriptUsageService;
+
+impl TranscriptUsageService {
+    /// 扫描全部会话文件并导入新增用量
+    pub fn run_once(db: &Database) -> Result<TranscriptIngestResult, AppError> {
+        let mut result = TranscriptIngestResult::default();
+        let sources: [(&'static str, Vec<std::path::PathBuf>); 3] = [
+            ("claude", claude::transcript_files()),
+            ("codex", codex::transcript_files()),
+            ("gemini", gemini::transcript_files()),
+        ];
+
+        for (app_type, files) in sources {
+            for path in files {
+                result.files_scanned += 1;
+                match Self::ingest_file(db, app_type, &path) {
+                    Ok(Some(file_result)) => {
+                        result.files_changed += 1;
+                        result.merge(&file_result);
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        result.failed_files += 1;
+                        log::warn!("[Transcript] 导入 {} 失败: {e}", path.display());
+                    }
+                }
+            }
+        }
+
+        if result.inserted > 0 || result.updated > 0 {
+            log::info!(
+                "[Transcript] 导入完成: 新增 {}, 更新 {}, 已由代理记录 {}",
+                result.inserted,
+                result.updated,
+                result.proxied
+            );
+        }
+        Ok(result)
+    }
+
+    /// 导入单个会话文件，文件未变化时返回 None
+    fn ingest_file(
+        db: &Database,
+        app_type: &'static str,
+        path: &Path,
+    ) -> Result<Option<TranscriptIngestResult>, AppError> {
+        let path_key = path.to_string_lossy().to_string();
+        let metadata = std::fs::metadata(path).map_err(|e| AppError::io(path, e))?;
+        let file_size = metadata.len();
+        let modified_at = metadata
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs() as i64)
+            .unwrap_or(0);
+
+        let previous = db.get_transcript_cursor(&path_key)?.unwrap_or_default();
+        if previous.file_size == file_size && previous.modified_at == modified_at {
+            return Ok(None);
+        }
+
+        let (records, cursor) = if app_type == "gemini" {
+            // Gemini 每次整体重写文件，只能全量解析，依靠消息 ID 去重
+            let data = std::fs::read_to_string(path).map_err(|e| AppError::io(path, e))?;
+            let value: Value = serde_json::from_str(&data)
+                .map_err(|e| AppError::Message(format!("解析会话文件失败: {e}")))?;
+            let cursor = TranscriptCursor {
+                offset: file_size,
+                file_size,
+                modified_at,
+                context: None,
+            };
+            (parse_gemini_session(&value), cursor)
+        } else {
+            Self::read_jsonl(app_type, path, previous, file_size, modified_at)?
+        };
+
+        let file_result = db.save_transcript_usage(&records)?;
+        db.save_transcript_cursor(&path_key, app_type, &cursor)?;
+        Ok(Some(file_result))
+    }
+
+    /// 从上次的偏移继续读取 JSONL，末尾不完整的行留到下次读取
+    fn read_jsonl(
+        app_type: &'static str,
+        path: &Path,
+        previous: TranscriptCursor,
+        file_size: u64,
+        modified_at: i64,
+    ) -> Result<(Vec<TranscriptUsageRecord>, TranscriptCursor), AppError> {
+        // 文件被截断或重写时从头读取
+        let (mut offset, context) = if file_size < previous.offset {
+            (0, None)
+        } else {
+            (previous.offset, previous.context)
+        };
+        let mut codex_context: CodexContext = context
+            .as_deref()
+            .and_then(|raw| serde_json::from_str(raw).ok())
+            .unwrap_or_default();
+
+        let mut file = File::open(path).map_err(|e| AppError::io(path, e))?;
+        file.seek(SeekFrom::Start(offset))
+            .map_err(|e| AppError::io(path, e))?;
+        let mut reader = BufReader::new(file);
+        let mut records = Vec::new();
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let read = reader
+                .read_until(b'\n', &mut line)
+                .map_err(|e| AppError::io(path, e))?;
+            if read == 0 || line.last() != Some(&b'\n') {
+                break;
+            }
+            offset += read as u64;
+
+            let Ok(value) = serde_json::from_slice::<Value>(&line) else {
+                continue;
+            };
+            let record = match app_type {
+                "claude" => parse_claude_line(&value),
+                _ => parse_codex_line(&value, &mut codex_context),
+            };
+            records.extend(record);
+        }
+
+        let context = match app_type {
+            "codex" => Some(to_json_string(&codex_context)?),
+            _ => None,
+        };
+        Ok((
+            records,
+            TranscriptCursor {
+                offset,
+                file_size,
+                modified_at,
+                context,
+            },
+        ))
+    }
+}
+
+/// 启动后台导入任务
+///
+/// 每轮重新读取配置，开关与间隔修改无需重启。
+pub fn start_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let db = app.state::<AppState>().db.clone();
+            let config = match db.get_transcript_ingest_config() {
+                Ok(config) => config,
+                Err(e) => {
+                    log::warn!("[Transcript] 读取配置失败: {e}");
+                    TranscriptIngestConfig::default()
+                }
+            };
+
+            if !config.enabled {
+                tokio::time::sleep(Duration::from_secs(DISABLED_RECHECK_SECS)).await;
+                continue;
+            }
+
+            let outcome =
+                tauri::async_runtime::spawn_blocking(move || TranscriptUsageService::run_once(&db))
+                    .await;
+            match outcome {
+                Ok(Ok(result)) if result.inserted > 0 || result.updated > 0 => {
+                    if let Err(e) = app.emit("transcript-usage-ingested", &result) {
+                        log::debug!("[Transcript] 发送导入事件失败: {e}");
+                    }
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => log::warn!("[Transcript] 本轮导入失败: {e}"),
+                Err(e) => log::warn!("[Transcript] 导入任务异常: {e}"),
+            }
+
+            tokio::time::sleep(Duration::from_secs(
+                config.interval_secs.max(MIN_INTERVAL_SECS),
+            ))
+            .await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_claude_line() {
+        let line = json!({
+            "type": "assistant",
+            "sessionId": "s1",
+            "timestamp": "2025-01-01T00:00:10Z",
+            "message": {
+                "id": "msg_1",
+                "model": "claude-sonnet-4-5-20250929",
+                "usage": {
+                    "input_tokens": 10,
+                    "output_tokens": 20,
+                    "cache_read_input_tokens": 300,
+                    "cache_creation_input_tokens": 40
+                }
+            }
+        });
+        let record = parse_claude_line(&line).unwrap();
+        assert_eq!(record.request_id(), "transcript:claude:msg_1");
+        assert_eq!(record.session_id.as_deref(), Some("s1"));
+        assert_eq!(record.usage.cache_read_tokens, 300);
+        assert_eq!(record.created_at, 1735689610);
+
+        let synthetic = json!({
+            "type": "assistant",
+            "timestamp": "2025-01-01T00:00:10Z",
+            "message": {"id": "msg_2", "model": "<synthetic>", "usage": {"input_tokens": 0, "output_tokens": 0}}
+        });
+        assert!(parse_claude_line(&synthetic).is_none());
+        assert!(parse_claude_line(&json!({"type": "user", "message": {}})).is_none());
+    }
+
+    #[test]
+    fn test_parse_codex_lines_skip_repeated_token_count() {
+        let mut context = CodexContext::default();
+        let lines = [
+            json!({"type": "session_meta", "timestamp": "2025-01-01T00:00:00Z", "payload": {"id": "c1"}}),
+            json!({"type": "turn_context", "timestamp": "2025-01-01T00:00:01Z", "payload": {"model": "gpt-5-codex"}}),
+            json!({"type": "event_msg", "timestamp": "2025-01-01T00:00:02Z", "payload": {"type": "token_count", "info": null}}),
+            json!({"type": "event_msg", "timestamp": "2025-01-01T00:00:03Z", "payload": {"type": "token_count", "info": {
+                "total_token_usage": {"total_tokens": 1200},
+                "last_token_usage": {"input_tokens": 1000, "cached_input_tokens": 800, "output_tokens": 200}
+            }}}),
+            json!({"type": "event_msg", "timestamp": "2025-01-01T00:00:04Z", "payload": {"type": "token_count", "info": {
+                "total_token_usage": {"total_tokens": 1200},
+                "last_token_usage": {"input_tokens": 1000, "cached_input_tokens": 800, "output_tokens": 200}
+            }}}),
+        ];
+
+        let records: Vec<_> = lines
+            .iter()
+            .filter_map(|line| parse_codex_line(line, &mut context))
+            .collect();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].message_id, "c1:1200");
+        assert_eq!(records[0].model, "gpt-5-codex");
+        assert_eq!(records[0].usage.cache_read_tokens, 800);
+        assert_eq!(context.last_total_tokens, 1200);
+    }
+
+    #[test]
+    fn test_parse_gemini_session() {
+        let session = json!({
+            "sessionId": "g1",
+            "messages": [
+                {"id": "u1", "type": "user", "content": "hi", "timestamp": "2025-01-01T00:00:00Z"},
+                {"id": "m1", "type": "gemini", "model": "gemini-2.5-pro", "timestamp": "2025-01-01T00:00:05Z",
+                 "tokens": {"input": 100, "output": 20, "cached": 50, "thoughts": 30, "total": 150}}
+            ]
+        });
+        let records = parse_gemini_session(&session);
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].request_id(), "transcript:gemini:m1");
+        assert_eq!(records[0].usage.output_tokens, 50);
+        assert_eq!(records[0].usage.cache_read_tokens, 50);
+    }
+}
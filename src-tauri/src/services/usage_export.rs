@@ -0,0 +1,485 @@
+//! 用量导出（CSV / JSONL）
+//!
+//! 请求日志与各统计视图（汇总、趋势、供应商、模型）按与界面相同的过滤条件导出，
+//! 支持会话与项目过滤。请求日志按 (created_at, request_id) 分批读取并逐行写出，
+//! 每批之间释放数据库锁，百万级记录也不会整体载入内存或长时间阻塞代理写日志。
+
+use rusqlite::types::{Value, ValueRef};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::services::currency::{report_cost_expr, report_cost_expr_at};
+use crate::services::usage_rollup::range_source_sql;
+use crate::services::usage_stats::{log_filter_conditions, log_filter_joins, LogFilters};
+
+/// 每批读取的请求日志条数
+const LOG_BATCH_SIZE: i64 = 5_000;
+
+/// 导出文件格式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageFileFormat {
+    #[default]
+    Csv,
+    Jsonl,
+}
+
+impl UsageFileFormat {
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Jsonl => "jsonl",
+        }
+    }
+}
+
+/// 导出内容
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum UsageExportKind {
+    /// 请求日志明细
+    Logs,
+    Summary,
+    /// 按本地自然日的趋势
+    Trends,
+    Providers,
+    Models,
+}
+
+/// 导出请求
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageExportRequest {
+    pub kind: UsageExportKind,
+    #[serde(default)]
+    pub format: UsageFileFormat,
+    #[serde(default)]
+    pub filters: LogFilters,
+    /// 报表币种，未指定时使用默认报表币种
+    #[serde(default)]
+    pub currency: Option<String>,
+}
+
+/// 导出结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageExportResult {
+    pub rows: u64,
+    pub path: String,
+}
+
+/// 逐行写出 CSV / JSONL
+pub(crate) struct RowWriter<W: Write> {
+    out: W,
+    format: UsageFileFormat,
+    columns: Vec<String>,
+    rows: u64,
+}
+
+impl<W: Write> RowWriter<W> {
+    /// 创建写入器，CSV 会先写出表头
+    pub(crate) fn new(
+        out: W,
+        format: UsageFileFormat,
+        columns: Vec<String>,
+    ) -> Result<Self, AppError> {
+        let mut writer = Self {
+            out,
+            format,
+            columns,
+            rows: 0,
+        };
+        if format == UsageFileFormat::Csv {
+            let header = format!("{}\n", writer.columns.join(","));
+            writer.write_raw(&header)?;
+        }
+        Ok(writer)
+    }
+
+    pub(crate) fn column_count(&self) -> usize {
+        self.columns.len()
+    }
+
+    pub(crate) fn write_row(&mut self, values: &[ValueRef<'_>]) -> Result<(), AppError> {
+        let line = match self.format {
+            UsageFileFormat::Csv => csv_line(values),
+            UsageFileFormat::Jsonl => jsonl_line(&self.columns, values)?,
+        };
+        self.write_raw(&line)?;
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// 刷新缓冲并返回写出的行数
+    pub(crate) fn finish(mut self) -> Result<u64, AppError> {
+        self.out.flush().map_err(write_error)?;
+        Ok(self.rows)
+    }
+
+    fn write_raw(&mut self, text: &str) -> Result<(), AppError> {
+        self.out.write_all(text.as_bytes()).map_err(write_error)
+    }
+}
+
+fn write_error(source: std::io::Error) -> AppError {
+    AppError::IoContext {
+        context: "写入导出文件失败".to_string(),
+        source,
+    }
+}
+
+fn csv_line(values: &[ValueRef<'_>]) -> String {
+    let fields: Vec<String> = values
+        .iter()
+        .map(|value| match value {
+            ValueRef::Null | ValueRef::Blob(_) => String::new(),
+            ValueRef::Integer(i) => i.to_string(),
+            ValueRef::Real(f) => f.to_string(),
+            ValueRef::Text(t) => {
+                let text = String::from_utf8_lossy(t);
+                if text.contains([',', '"', '\n', '\r']) {
+                    format!("\"{}\"", text.replace('"', "\"\""))
+                } else {
+                    text.into_owned()
+                }
+            }
+        })
+        .collect();
+    format!("{}\n", fields.join(","))
+}
+
+fn jsonl_line(columns: &[String], values: &[ValueRef<'_>]) -> Result<String, AppError> {
+    let object: serde_json::Map<String, serde_json::Value> = columns
+        .iter()
+        .zip(values)
+        .map(|(column, value)| {
+            let json = match value {
+                ValueRef::Null | ValueRef::Blob(_) => serde_json::Value::Null,
+                ValueRef::Integer(i) => (*i).into(),
+                ValueRef::Real(f) => (*f).into(),
+                ValueRef::Text(t) => String::from_utf8_lossy(t).into_owned().into(),
+            };
+            (column.clone(), json)
+        })
+        .collect();
+    let line = crate::database::to_json_string(&object)?;
+    Ok(format!("{line}\n"))
+}
+
+/// 汇总表能否满足过滤条件（汇总表不含状态码、来源与会话维度）
+fn rollup_supports(filters: &LogFilters) -> bool {
+    filters.status_code.is_none()
+        && filters.source.is_none()
+        && filters.session_id.is_none()
+        && filters.project_dir.is_none()
+}
+
+impl Database {
+    /// 按请求导出用量数据到 `out`，返回写出的行数
+    pub fn export_usage<W: Write>(
+        &self,
+        request: &UsageExportRequest,
+        out: W,
+    ) -> Result<u64, AppError> {
+        let currency = self.resolve_reporting_currency(request.currency.as_deref())?;
+        match request.kind {
+            UsageExportKind::Logs => self.export_request_logs(request, &currency, out),
+            _ => self.export_stats_view(request, &currency, out),
+        }
+    }
+
+    fn export_request_logs<W: Write>(
+        &self,
+        request: &UsageExportRequest,
+        currency: &str,
+        out: W,
+    ) -> Result<u64, AppError> {
+        let (mut conditions, _) = log_filter_conditions(&request.filters);
+        conditions.push("(l.created_at > ? OR (l.created_at = ? AND l.request_id > ?))");
+        let cost_expr = report_cost_expr("l.", currency);
+        let joins = log_filter_joins();
+        let sql = format!(
+            "SELECT l.request_id, l.created_at,
+                    datetime(l.created_at, 'unixepoch', 'localtime') AS created_at_local,
+                    l.app_type, l.provider_id, p.name AS provider_name, l.model, l.request_model,
+                    l.session_id, sp.project_dir, l.source, l.status_code,
+                    l.input_tokens, l.output_tokens, l.cache_read_tokens, l.cache_creation_tokens,
+                    l.cache_creation_1h_tokens, l.input_cost_usd, l.output_cost_usd,
+                    l.cache_read_cost_usd, l.cache_creation_cost_usd, l.total_cost_usd,
+                    l.billing_currency, l.billing_cost,
+                    {cost_expr} AS report_cost, '{currency}' AS report_currency,
+                    l.cost_multiplier, l.is_streaming, l.latency_ms, l.first_token_ms,
+                    l.duration_ms, l.error_message, l.endpoint_url
+             FROM proxy_request_logs l
+             {joins}
+             WHERE {}
+             ORDER BY l.created_at ASC, l.request_id ASC
+             LIMIT ?",
+            conditions.join(" AND ")
+        );
+
+        let columns = {
+            let conn = lock_conn!(self.conn);
+            let stmt = conn.prepare(&sql)?;
+            stmt.column_names().into_iter().map(String::from).collect()
+        };
+        let mut writer = RowWriter::new(out, request.format, columns)?;
+        let mut cursor: (i64, String) = (i64::MIN, String::new());
+        loop {
+            // 每批重新加锁，批次之间代理仍可写入日志
+            let batch: Vec<Vec<Value>> = {
+                let conn = lock_conn!(self.conn);
+                let mut stmt = conn.prepare(&sql)?;
+                let (_, mut params) = log_filter_conditions(&request.filters);
+                params.push(Box::new(cursor.0));
+                params.push(Box::new(cursor.0));
+                params.push(Box::new(cursor.1.clone()));
+                params.push(Box::new(LOG_BATCH_SIZE));
+                let params: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+
+                let column_count = stmt.column_count();
+                let rows = stmt.query_map(params.as_slice(), |row| {
+                    (0..column_count)
+                        .map(|idx| row.get::<_, Value>(idx))
+                        .collect::<Result<Vec<_>, _>>()
+                })?;
+                rows.collect::<Result<Vec<_>, _>>()?
+            };
+
+            for row in &batch {
+                let values: Vec<ValueRef<'_>> = row.iter().map(ValueRef::from).collect();
+                writer.write_row(&values)?;
+            }
+
+            match batch.last() {
+                Some(last) if batch.len() as i64 == LOG_BATCH_SIZE => {
+                    let (Value::Text(request_id), Value::Integer(created_at)) =
+                        (&last[0], &last[1])
+                    else {
+                        return Err(AppError::Database("请求日志主键格式异常".to_string()));
+                    };
+                    cursor = (*created_at, request_id.clone());
+                }
+                _ => break,
+            }
+        }
+
+        writer.finish()
+    }
+
+    fn export_stats_view<W: Write>(
+        &self,
+        request: &UsageExportRequest,
+        currency: &str,
+        out: W,
+    ) -> Result<u64, AppError> {
+        let use_rollup = rollup_supports(&request.filters);
+        let (source, joins, time_column, mut conditions, params) = if use_rollup {
+            // 时间范围由数据源精确切分，其余条件与请求日志一致
+            let filters = LogFilters {
+                start_date: None,
+                end_date: None,
+                ..request.filters.clone()
+            };
+            let (conditions, params) = log_filter_conditions(&filters);
+            let source = {
+                let conn = lock_conn!(self.conn);
+                range_source_sql(&conn, request.filters.start_date, request.filters.end_date)?
+            };
+            (
+                source,
+                "LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type"
+                    .to_string(),
+                "l.bucket_start",
+                conditions,
+                params,
+            )
+        } else {
+            let (conditions, params) = log_filter_conditions(&request.filters);
+            (
+                "proxy_request_logs".to_string(),
+                log_filter_joins(),
+                "l.created_at",
+                conditions,
+                params,
+            )
+        };
+        if conditions.is_empty() {
+            conditions.push("1 = 1");
+        }
+
+        let measures = if use_rollup {
+            format!(
+                "COALESCE(SUM(l.request_count), 0) AS request_count,
+                 COALESCE(SUM(l.success_count), 0) AS success_count,
+                 COALESCE(SUM(l.input_tokens), 0) AS input_tokens,
+                 COALESCE(SUM(l.output_tokens), 0) AS output_tokens,
+                 COALESCE(SUM(l.cache_read_tokens), 0) AS cache_read_tokens,
+                 COALESCE(SUM(l.cache_creation_tokens), 0) AS cache_creation_tokens,
+                 COALESCE(SUM({}), 0) AS total_cost,
+                 '{currency}' AS currency,
+                 COALESCE(CAST(SUM(l.latency_ms_sum) AS REAL) / SUM(l.request_count), 0)
+                    AS avg_latency_ms",
+                report_cost_expr_at("l.", "bucket_start", currency)
+            )
+        } else {
+            format!(
+                "COUNT(*) AS request_count,
+                 COALESCE(SUM(CASE WHEN l.status_code >= 200 AND l.status_code < 300
+                    THEN 1 ELSE 0 END), 0) AS success_count,
+                 COALESCE(SUM(l.input_tokens), 0) AS input_tokens,
+                 COALESCE(SUM(l.output_tokens), 0) AS output_tokens,
+                 COALESCE(SUM(l.cache_read_tokens), 0) AS cache_read_tokens,
+                 COALESCE(SUM(l.cache_creation_tokens), 0) AS cache_creation_tokens,
+                 COALESCE(SUM({}), 0) AS total_cost,
+                 '{currency}' AS currency,
+                 COALESCE(AVG(l.latency_ms), 0) AS avg_latency_ms",
+                report_cost_expr("l.", currency)
+            )
+        };
+
+        let (keys, group_order) = match request.kind {
+            UsageExportKind::Summary => (String::new(), String::new()),
+            UsageExportKind::Trends => (
+                format!("date({time_column}, 'unixepoch', 'localtime') AS date,"),
+                "GROUP BY date ORDER BY date ASC".to_string(),
+            ),
+            UsageExportKind::Providers => (
+                "l.provider_id, l.app_type, COALESCE(p.name, l.provider_id) AS provider_name,"
+                    .to_string(),
+                "GROUP BY l.provider_id, l.app_type ORDER BY total_cost DESC".to_string(),
+            ),
+            UsageExportKind::Models => (
+                "l.model,".to_string(),
+                "GROUP BY l.model ORDER BY total_cost DESC".to_string(),
+            ),
+            UsageExportKind::Logs => unreachable!("请求日志走逐批导出"),
+        };
+        let sql = format!(
+            "SELECT {keys} {measures}
+             FROM {source} l
+             {joins}
+             WHERE {}
+             {group_order}",
+            conditions.join(" AND ")
+        );
+
+        let conn = lock_conn!(self.conn);
+        let mut stmt = conn.prepare(&sql)?;
+        let columns = stmt.column_names().into_iter().map(String::from).collect();
+        let mut writer = RowWriter::new(out, request.format, columns)?;
+        let params: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+        let mut rows = stmt.query(params.as_slice())?;
+        while let Some(row) = rows.next()? {
+            let values = (0..writer.column_count())
+                .map(|idx| row.get_ref(idx))
+                .collect::<Result<Vec<_>, _>>()?;
+            writer.write_row(&values)?;
+        }
+        writer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::params;
+
+    fn insert_log(
+        db: &Database,
+        request_id: &str,
+        session_id: &str,
+        status: i64,
+        created_at: i64,
+    ) -> Result<(), AppError> {
+        let conn = lock_conn!(db.conn);
+        conn.execute(
+            "INSERT INTO proxy_request_logs (
+                request_id, provider_id, app_type, model, input_tokens, output_tokens,
+                total_cost_usd, billing_cost, latency_ms, status_code, session_id, created_at
+            ) VALUES (?1, 'p1', 'claude', 'm', 100, 10, '0.5', '0.5', 100, ?2, ?3, ?4)",
+            params![request_id, status, session_id, created_at],
+        )?;
+        Ok(())
+    }
+
+    fn export(db: &Database, request: &UsageExportRequest) -> Result<String, AppError> {
+        let mut buf = Vec::new();
+        db.export_usage(request, &mut buf)?;
+        Ok(String::from_utf8(buf).expect("utf8 export"))
+    }
+
+    #[test]
+    fn test_csv_line_escaping() {
+        let line = csv_line(&[
+            ValueRef::Integer(1),
+            ValueRef::Text(b"a,b"),
+            ValueRef::Text(b"say \"hi\""),
+            ValueRef::Null,
+        ]);
+        assert_eq!(line, "1,\"a,b\",\"say \"\"hi\"\"\",\n");
+    }
+
+    #[test]
+    fn test_export_logs_in_batches_with_filters() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        // 同一时间戳的多条记录跨批次时不能遗漏或重复
+        for i in 0..(LOG_BATCH_SIZE + 3) {
+            insert_log(&db, &format!("r{i:05}"), "s1", 200, 1_000 + i / 2)?;
+        }
+        insert_log(&db, "other", "s2", 500, 5_000)?;
+
+        let request = UsageExportRequest {
+            kind: UsageExportKind::Logs,
+            format: UsageFileFormat::Csv,
+            filters: LogFilters {
+                session_id: Some("s1".to_string()),
+                ..Default::default()
+            },
+            currency: None,
+        };
+        let csv = export(&db, &request)?;
+        let mut lines = csv.lines();
+        assert!(lines.next().unwrap().starts_with("request_id,created_at,"));
+        assert_eq!(lines.count() as i64, LOG_BATCH_SIZE + 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_stats_views() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        insert_log(&db, "r1", "s1", 200, 1_000)?;
+        insert_log(&db, "r2", "s2", 500, 2_000)?;
+
+        // 汇总表路径
+        let summary = UsageExportRequest {
+            kind: UsageExportKind::Summary,
+            format: UsageFileFormat::Jsonl,
+            filters: LogFilters::default(),
+            currency: None,
+        };
+        let line: serde_json::Value =
+            serde_json::from_str(export(&db, &summary)?.trim()).expect("jsonl");
+        assert_eq!(line["request_count"], 2);
+        assert_eq!(line["success_count"], 1);
+        assert_eq!(line["total_cost"], 1.0);
+
+        // 按状态码过滤时回退到原始日志
+        let models = UsageExportRequest {
+            kind: UsageExportKind::Models,
+            format: UsageFileFormat::Jsonl,
+            filters: LogFilters {
+                status_code: Some(500),
+                ..Default::default()
+            },
+            currency: None,
+        };
+        let line: serde_json::Value =
+            serde_json::from_str(export(&db, &models)?.trim()).expect("jsonl");
+        assert_eq!(line["model"], "m");
+        assert_eq!(line["request_count"], 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,898 @@
+//! 花费预测与异常预警
+//!
+//! 基于日汇总表（`usage_rollup_daily`）计算：
+//! - 燃烧速率与月末预计花费，对比全局月度阈值和供应商 `limitMonthlyUsd`，预测超限日期
+//! - 按供应商 / 模型检测今日花费相对历史中位数的突增，以及错误率的突增
+//!
+//! 结果以结构化的 [`UsageAlert`] 返回，托盘与通知子系统直接展示。
+
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::RwLock;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::database::Database;
+use crate::error::AppError;
+use crate::services::notification::{notify, NotificationEvent};
+use crate::store::AppState;
+
+/// 预警关闭时重新读取配置的间隔
+const DISABLED_RECHECK_SECS: u64 = 60;
+/// 预测的执行间隔
+const FORECAST_INTERVAL_SECS: u64 = 30 * 60;
+const DAY_SECS: i64 = 86_400;
+
+/// 最近一轮计算出的预警（供托盘菜单读取）
+static LATEST_ALERTS: RwLock<Vec<UsageAlert>> = RwLock::new(Vec::new());
+
+/// 预测与预警配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UsageForecastConfig {
+    /// 是否在后台定时计算并推送预警
+    pub enabled: bool,
+    /// 异常检测的历史窗口（天）
+    pub lookback_days: u32,
+    /// 计算燃烧速率使用的最近完整天数
+    pub burn_window_days: u32,
+    /// 今日花费达到历史中位数的该倍数时预警
+    pub cost_spike_ratio: f64,
+    /// 今日花费低于该值（美元）时不做突增判断，避免小额噪声
+    pub min_spike_cost_usd: f64,
+    /// 今日错误率比历史错误率高出该值（0-1）时预警
+    pub error_rate_increase: f64,
+    /// 今日请求数低于该值时不判断错误率
+    pub min_requests: u32,
+    /// 历史中有花费的天数少于该值时不判断花费突增
+    pub min_baseline_days: u32,
+}
+
+impl Default for UsageForecastConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            lookback_days: 30,
+            burn_window_days: 7,
+            cost_spike_ratio: 3.0,
+            min_spike_cost_usd: 1.0,
+            error_rate_increase: 0.2,
+            min_requests: 20,
+            min_baseline_days: 5,
+        }
+    }
+}
+
+impl UsageForecastConfig {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.lookback_days == 0 || self.burn_window_days == 0 {
+            return Err(AppError::InvalidInput("统计天数必须大于 0".to_string()));
+        }
+        if self.cost_spike_ratio <= 1.0 {
+            return Err(AppError::InvalidInput("花费突增倍数必须大于 1".to_string()));
+        }
+        if !(0.0..=1.0).contains(&self.error_rate_increase) {
+            return Err(AppError::InvalidInput(
+                "错误率增幅必须在 0 到 1 之间".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// 预警类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum UsageAlertKind {
+    /// 按当前速率将在月底前超过月度限额
+    LimitProjected,
+    /// 本月花费已达到月度限额
+    LimitReached,
+    /// 今日花费相对历史中位数突增
+    CostSpike,
+    /// 今日错误率相对历史突增
+    ErrorRateSpike,
+}
+
+/// 预警级别
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "camelCase")]
+pub enum UsageAlertSeverity {
+    Warning,
+    Critical,
+}
+
+/// 预警范围
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum UsageAlertScope {
+    /// 所有应用的总花费
+    Global,
+    Provider,
+    Model,
+}
+
+/// 结构化预警
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageAlert {
+    /// 去重键（同一周期内同一对象的同类预警相同）
+    pub key: String,
+    pub kind: UsageAlertKind,
+    pub severity: UsageAlertSeverity,
+    pub scope: UsageAlertScope,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub app_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    pub title: String,
+    pub message: String,
+    /// 观测值：预计月末花费 / 今日花费（美元）或今日错误率（0-1）
+    pub value: f64,
+    /// 对比基准：月度限额 / 历史中位数 / 历史错误率
+    pub baseline: f64,
+    /// 预计超限日期（YYYY-MM-DD，仅限额预测）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub projected_date: Option<String>,
+}
+
+impl UsageAlert {
+    /// 是否推送到通知子系统
+    ///
+    /// 全局月度阈值的实际超限由通知规则 `monthlySpendUsd` 负责推送，
+    /// 预测只推送超限预测，避免同一阈值重复通知；面板与托盘仍展示该预警
+    fn should_notify(&self) -> bool {
+        !(self.kind == UsageAlertKind::LimitReached && self.scope == UsageAlertScope::Global)
+    }
+}
+
+/// 花费预测结果（美元）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageForecast {
+    pub generated_at: i64,
+    pub today_spend_usd: f64,
+    pub month_to_date_usd: f64,
+    /// 每日燃烧速率（最近完整天数的平均花费）
+    pub daily_burn_rate_usd: f64,
+    pub projected_month_end_usd: f64,
+    /// 本月剩余天数（含今天剩余部分）
+    pub days_remaining: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub monthly_threshold_usd: Option<f64>,
+    /// 按当前速率达到全局月度阈值的日期
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub projected_threshold_date: Option<String>,
+    pub alerts: Vec<UsageAlert>,
+}
+
+/// 日汇总中的一个数据点（按 日期 + 应用 + 供应商 + 模型 聚合）
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyUsagePoint {
+    pub bucket_start: i64,
+    pub app_type: String,
+    pub provider_id: String,
+    pub provider_name: Option<String>,
+    pub model: String,
+    pub cost_usd: f64,
+    pub request_count: u64,
+    pub success_count: u64,
+}
+
+/// 设置了月度限额的供应商
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProviderMonthlyLimit {
+    pub app_type: String,
+    pub provider_id: String,
+    pub provider_name: String,
+    pub limit_usd: f64,
+}
+
+/// 单日累计值
+#[derive(Debug, Clone, Copy, Default)]
+struct DayTotals {
+    cost: f64,
+    requests: u64,
+    successes: u64,
+}
+
+impl DayTotals {
+    fn add(&mut self, point: &DailyUsagePoint) {
+        self.cost += point.cost_usd;
+        self.requests += point.request_count;
+        self.successes += point.success_count;
+    }
+
+    fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            1.0 - self.successes as f64 / self.requests as f64
+        }
+    }
+}
+
+/// 某个对象（全局 / 供应商 / 模型）按天的时间序列
+#[derive(Debug, Default)]
+struct Series {
+    days: BTreeMap<i64, DayTotals>,
+    provider_name: Option<String>,
+}
+
+/// 计算时刻的本地日历信息
+struct Calendar {
+    now: DateTime<Local>,
+    today_start: i64,
+    month_start: i64,
+    next_month_start: i64,
+}
+
+impl Calendar {
+    fn new(now: DateTime<Local>) -> Self {
+        let date = now.date_naive();
+        let first = date.with_day(1).unwrap_or(date);
+        let next_first = if first.month() == 12 {
+            NaiveDate::from_ymd_opt(first.year() + 1, 1, 1)
+        } else {
+            NaiveDate::from_ymd_opt(first.year(), first.month() + 1, 1)
+        }
+        .unwrap_or(first);
+        Self {
+            now,
+            today_start: local_midnight(date),
+            month_start: local_midnight(first),
+            next_month_start: local_midnight(next_first),
+        }
+    }
+
+    fn days_remaining(&self) -> f64 {
+        (self.next_month_start - self.now.timestamp()).max(0) as f64 / DAY_SECS as f64
+    }
+
+    fn today(&self) -> String {
+        self.now.format("%Y-%m-%d").to_string()
+    }
+
+    fn month(&self) -> String {
+        self.now.format("%Y-%m").to_string()
+    }
+}
+
+fn local_midnight(date: NaiveDate) -> i64 {
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|t| Local.from_local_datetime(&t).earliest())
+        .map(|t| t.timestamp())
+        .unwrap_or_default()
+}
+
+/// 中位数（空切片返回 0）
+pub(crate) fn median(values: &[f64]) -> f64 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let mid = sorted.len() / 2;
+    if sorted.len() % 2 == 0 {
+        (sorted[mid - 1] + sorted[mid]) / 2.0
+    } else {
+        sorted[mid]
+    }
+}
+
+/// 月度花费预测
+struct MonthProjection {
+    today: f64,
+    month_to_date: f64,
+    burn_rate: f64,
+    projected: f64,
+}
+
+impl MonthProjection {
+    /// 燃烧速率取最近 `window` 个完整天的平均值；历史不足一个窗口时按实际天数平均，
+    /// 没有任何历史时以今日花费估计
+    fn new(series: &Series, calendar: &Calendar, window: u32) -> Self {
+        let today = series
+            .days
+            .get(&calendar.today_start)
+            .map(|d| d.cost)
+            .unwrap_or_default();
+        let month_to_date = series
+            .days
+            .range(calendar.month_start..)
+            .map(|(_, d)| d.cost)
+            .sum::<f64>();
+
+        let window_start = calendar.today_start - i64::from(window) * DAY_SECS;
+        let history = series.days.range(window_start..calendar.today_start);
+        let first_day = history.clone().next().map(|(day, _)| *day);
+        let burn_rate = match first_day {
+            Some(first) => {
+                let days = ((calendar.today_start - first) as f64 / DAY_SECS as f64)
+                    .round()
+                    .max(1.0);
+                history.map(|(_, d)| d.cost).sum::<f64>() / days
+            }
+            None => today,
+        };
+
+        Self {
+            today,
+            month_to_date,
+            burn_rate,
+            projected: month_to_date + burn_rate * calendar.days_remaining(),
+        }
+    }
+
+    /// 按当前速率达到限额的日期（已超过或月底前达不到时返回 None）
+    fn exceed_date(&self, limit: f64, calendar: &Calendar) -> Option<String> {
+        if self.month_to_date >= limit || self.projected < limit || self.burn_rate <= 0.0 {
+            return None;
+        }
+        let secs = (limit - self.month_to_date) / self.burn_rate * DAY_SECS as f64;
+        let date = calendar.now + chrono::Duration::seconds(secs as i64);
+        Some(date.format("%Y-%m-%d").to_string())
+    }
+}
+
+/// 对象的展示名称与标识
+struct Subject<'a> {
+    scope: UsageAlertScope,
+    app_type: Option<&'a str>,
+    provider_id: Option<&'a str>,
+    provider_name: Option<&'a str>,
+    model: Option<&'a str>,
+}
+
+impl Subject<'_> {
+    fn label(&self) -> String {
+        match self.scope {
+            UsageAlertScope::Global => "总花费".to_string(),
+            UsageAlertScope::Provider => format!(
+                "[{}] {}",
+                self.app_type.unwrap_or(""),
+                self.provider_name.or(self.provider_id).unwrap_or("")
+            ),
+            UsageAlertScope::Model => format!(
+                "[{}] {}",
+                self.app_type.unwrap_or(""),
+                self.model.unwrap_or("")
+            ),
+        }
+    }
+
+    fn key(&self, kind: &str, period: &str) -> String {
+        let id = match self.scope {
+            UsageAlertScope::Global => "",
+            UsageAlertScope::Provider => self.provider_id.unwrap_or(""),
+            UsageAlertScope::Model => self.model.unwrap_or(""),
+        };
+        format!(
+            "forecast:{kind}:{:?}:{}:{id}:{period}",
+            self.scope,
+            self.app_type.unwrap_or("")
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn alert(
+        &self,
+        kind: UsageAlertKind,
+        severity: UsageAlertSeverity,
+        key: String,
+        title: String,
+        message: String,
+        value: f64,
+        baseline: f64,
+        projected_date: Option<String>,
+    ) -> UsageAlert {
+        UsageAlert {
+            key,
+            kind,
+            severity,
+            scope: self.scope,
+            app_type: self.app_type.map(str::to_string),
+            provider_id: self.provider_id.map(str::to_string),
+            provider_name: self.provider_name.map(str::to_string),
+            model: self.model.map(str::to_string),
+            title,
+            message,
+            value,
+            baseline,
+            projected_date,
+        }
+    }
+}
+
+/// 月度限额预警
+fn limit_alert(
+    subject: &Subject<'_>,
+    projection: &MonthProjection,
+    limit: f64,
+    calendar: &Calendar,
+) -> Option<UsageAlert> {
+    let label = subject.label();
+    if projection.month_to_date >= limit {
+        return Some(subject.alert(
+            UsageAlertKind::LimitReached,
+            UsageAlertSeverity::Critical,
+            subject.key("limit-reached", &calendar.month()),
+            format!("{label} 已达到月度限额"),
+            format!(
+                "本月已花费 ${:.2}，月度限额 ${limit:.2}",
+                projection.month_to_date
+            ),
+            projection.month_to_date,
+            limit,
+            None,
+        ));
+    }
+    let date = projection.exceed_date(limit, calendar)?;
+    Some(subject.alert(
+        UsageAlertKind::LimitProjected,
+        UsageAlertSeverity::Warning,
+        subject.key("limit-projected", &calendar.month()),
+        format!("{label} 预计 {date} 超过月度限额"),
+        format!(
+            "按每日 ${:.2} 的速率，月末预计花费 ${:.2}，月度限额 ${limit:.2}",
+            projection.burn_rate, projection.projected
+        ),
+        projection.projected,
+        limit,
+        Some(date),
+    ))
+}
+
+/// 今日花费突增预警（基准为历史窗口内有花费的天的中位数）
+fn cost_spike_alert(
+    subject: &Subject<'_>,
+    series: &Series,
+    calendar: &Calendar,
+    config: &UsageForecastConfig,
+) -> Option<UsageAlert> {
+    let today = series.days.get(&calendar.today_start)?.cost;
+    if today < config.min_spike_cost_usd {
+        return None;
+    }
+    let history: Vec<f64> = history_days(series, calendar, config.lookback_days)
+        .map(|d| d.cost)
+        .filter(|cost| *cost > 0.0)
+        .collect();
+    if history.len() < config.min_baseline_days as usize {
+        return None;
+    }
+    let baseline = median(&history);
+    if baseline <= 0.0 || today < baseline * config.cost_spike_ratio {
+        return None;
+    }
+    let ratio = today / baseline;
+    let severity = if ratio >= config.cost_spike_ratio * 2.0 {
+        UsageAlertSeverity::Critical
+    } else {
+        UsageAlertSeverity::Warning
+    };
+    Some(subject.alert(
+        UsageAlertKind::CostSpike,
+        severity,
+        subject.key("cost", &calendar.today()),
+        format!("{} 今日花费异常", subject.label()),
+        format!(
+            "今日花费 ${today:.2}，是近 {} 天中位数 ${baseline:.2} 的 {ratio:.1} 倍",
+            config.lookback_days
+        ),
+        today,
+        baseline,
+        None,
+    ))
+}
+
+/// 今日错误率突增预警（基准为历史窗口内的整体错误率）
+fn error_rate_alert(
+    subject: &Subject<'_>,
+    series: &Series,
+    calendar: &Calendar,
+    config: &UsageForecastConfig,
+) -> Option<UsageAlert> {
+    let today = series.days.get(&calendar.today_start)?;
+    if today.requests < u64::from(config.min_requests) {
+        return None;
+    }
+    let mut history = DayTotals::default();
+    for day in history_days(series, calendar, config.lookback_days) {
+        history.requests += day.requests;
+        history.successes += day.successes;
+    }
+    let rate = today.error_rate();
+    let baseline = history.error_rate();
+    if rate - baseline < config.error_rate_increase {
+        return None;
+    }
+    let severity = if rate >= 0.5 {
+        UsageAlertSeverity::Critical
+    } else {
+        UsageAlertSeverity::Warning
+    };
+    Some(subject.alert(
+        UsageAlertKind::ErrorRateSpike,
+        severity,
+        subject.key("error", &calendar.today()),
+        format!("{} 今日错误率异常", subject.label()),
+        format!(
+            "今日 {} 次请求错误率 {:.1}%，近 {} 天为 {:.1}%",
+            today.requests,
+            rate * 100.0,
+            config.lookback_days,
+            baseline * 100.0
+        ),
+        rate,
+        baseline,
+        None,
+    ))
+}
+
+fn history_days<'a>(
+    series: &'a Series,
+    calendar: &Calendar,
+    lookback_days: u32,
+) -> impl Iterator<Item = &'a DayTotals> {
+    let start = calendar.today_start - i64::from(lookback_days) * DAY_SECS;
+    series
+        .days
+        .range(start..calendar.today_start)
+        .map(|(_, d)| d)
+}
+
+/// 根据日汇总数据计算预测与预警
+pub fn compute_forecast(
+    points: &[DailyUsagePoint],
+    limits: &[ProviderMonthlyLimit],
+    monthly_threshold_usd: Option<f64>,
+    config: &UsageForecastConfig,
+    now: DateTime<Local>,
+) -> UsageForecast {
+    let calendar = Calendar::new(now);
+
+    let mut global = Series::default();
+    let mut providers: HashMap<(String, String), Series> = HashMap::new();
+    let mut models: HashMap<(String, String), Series> = HashMap::new();
+    for point in points {
+        global
+            .days
+            .entry(point.bucket_start)
+            .or_default()
+            .add(point);
+
+        let provider = providers
+            .entry((point.app_type.clone(), point.provider_id.clone()))
+            .or_default();
+        provider
+            .days
+            .entry(point.bucket_start)
+            .or_default()
+            .add(point);
+        if provider.provider_name.is_none() {
+            provider.provider_name = point.provider_name.clone();
+        }
+
+        models
+            .entry((point.app_type.clone(), point.model.clone()))
+            .or_default()
+            .days
+            .entry(point.bucket_start)
+            .or_default()
+            .add(point);
+    }
+
+    let mut alerts = Vec::new();
+
+    let projection = MonthProjection::new(&global, &calendar, config.burn_window_days);
+    let global_subject = Subject {
+        scope: UsageAlertScope::Global,
+        app_type: None,
+        provider_id: None,
+        provider_name: None,
+        model: None,
+    };
+    let threshold = monthly_threshold_usd.filter(|l| *l > 0.0);
+    if let Some(limit) = threshold {
+        alerts.extend(limit_alert(&global_subject, &projection, limit, &calendar));
+    }
+
+    let empty = Series::default();
+    for limit in limits.iter().filter(|l| l.limit_usd > 0.0) {
+        let series = providers
+            .get(&(limit.app_type.clone(), limit.provider_id.clone()))
+            .unwrap_or(&empty);
+        let subject = Subject {
+            scope: UsageAlertScope::Provider,
+            app_type: Some(&limit.app_type),
+            provider_id: Some(&limit.provider_id),
+            provider_name: Some(&limit.provider_name),
+            model: None,
+        };
+        let provider_projection = MonthProjection::new(series, &calendar, config.burn_window_days);
+        alerts.extend(limit_alert(
+            &subject,
+            &provider_projection,
+            limit.limit_usd,
+            &calendar,
+        ));
+    }
+
+    for ((app_type, provider_id), series) in &providers {
+        let subject = Subject {
+            scope: UsageAlertScope::Provider,
+            app_type: Some(app_type),
+            provider_id: Some(provider_id),
+            provider_name: series.provider_name.as_deref(),
+            model: None,
+        };
+        alerts.extend(cost_spike_alert(&subject, series, &calendar, config));
+        alerts.extend(error_rate_alert(&subject, series, &calendar, config));
+    }
+    for ((app_type, model), series) in &models {
+        let subject = Subject {
+            scope: UsageAlertScope::Model,
+            app_type: Some(app_type),
+            provider_id: None,
+            provider_name: None,
+            model: Some(model),
+        };
+        alerts.extend(cost_spike_alert(&subject, series, &calendar, config));
+        alerts.extend(error_rate_alert(&subject, series, &calendar, config));
+    }
+
+    alerts.sort_by(|a, b| b.severity.cmp(&a.severity).then_with(|| a.key.cmp(&b.key)));
+
+    UsageForecast {
+        generated_at: now.timestamp(),
+        today_spend_usd: projection.today,
+        month_to_date_usd: projection.month_to_date,
+        daily_burn_rate_usd: projection.burn_rate,
+        projected_month_end_usd: projection.projected,
+        days_remaining: calendar.days_remaining(),
+        monthly_threshold_usd: threshold,
+        projected_threshold_date: threshold.and_then(|l| projection.exceed_date(l, &calendar)),
+        alerts,
+    }
+}
+
+/// 历史数据需要覆盖的最早时间（本月初与异常检测窗口取较早者）
+pub(crate) fn forecast_since(config: &UsageForecastConfig, now: DateTime<Local>) -> i64 {
+    let calendar = Calendar::new(now);
+    let lookback = config.lookback_days.max(config.burn_window_days);
+    calendar
+        .month_start
+        .min(calendar.today_start - i64::from(lookback) * DAY_SECS)
+}
+
+/// 按当前配置计算一次预测
+pub fn run_once(db: &Database) -> Result<UsageForecast, AppError> {
+    let config = db.get_usage_forecast_config()?;
+    let now = Local::now();
+    let points = db.get_daily_usage_points(forecast_since(&config, now))?;
+    let limits = db.get_provider_monthly_limits()?;
+    let threshold = db.get_notification_config()?.rules.monthly_spend_usd;
+    Ok(compute_forecast(&points, &limits, threshold, &config, now))
+}
+
+/// 最近一轮后台计算出的预警
+pub fn latest_alerts() -> Vec<UsageAlert> {
+    LATEST_ALERTS
+        .read()
+        .map(|alerts| alerts.clone())
+        .unwrap_or_default()
+}
+
+/// 更新预警缓存，返回预警集合是否发生变化
+fn store_alerts(alerts: &[UsageAlert]) -> bool {
+    let Ok(mut latest) = LATEST_ALERTS.write() else {
+        return false;
+    };
+    let changed =
+        latest.len() != alerts.len() || latest.iter().zip(alerts).any(|(a, b)| a.key != b.key);
+    *latest = alerts.to_vec();
+    changed
+}
+
+fn refresh_tray(app: &AppHandle) {
+    let state = app.state::<AppState>();
+    if let Ok(new_menu) = crate::tray::create_tray_menu(app, state.inner()) {
+        if let Some(tray) = app.tray_by_id("main") {
+            let _ = tray.set_menu(Some(new_menu));
+        }
+    }
+}
+
+/// 启动后台预测任务
+///
+/// 每轮重新读取配置，开关修改无需重启；新预警推送到通知子系统并刷新托盘。
+pub fn start_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let db = app.state::<AppState>().db.clone();
+            let config = match db.get_usage_forecast_config() {
+                Ok(config) => config,
+                Err(e) => {
+                    log::warn!("[Forecast] 读取配置失败: {e}");
+                    UsageForecastConfig::default()
+                }
+            };
+
+            if !config.enabled {
+                if store_alerts(&[]) {
+                    refresh_tray(&app);
+                }
+                tokio::time::sleep(Duration::from_secs(DISABLED_RECHECK_SECS)).await;
+                continue;
+            }
+
+            match tauri::async_runtime::spawn_blocking(move || run_once(&db)).await {
+                Ok(Ok(forecast)) => {
+                    for alert in forecast.alerts.iter().filter(|a| a.should_notify()) {
+                        notify(NotificationEvent::UsageAlertRaised {
+                            alert: alert.clone(),
+                        });
+                    }
+                    if store_alerts(&forecast.alerts) {
+                        refresh_tray(&app);
+                    }
+                    if let Err(e) = app.emit("usage-forecast-updated", &forecast) {
+                        log::debug!("[Forecast] 发送事件失败: {e}");
+                    }
+                }
+                Ok(Err(e)) => log::warn!("[Forecast] 本轮计算失败: {e}"),
+                Err(e) => log::warn!("[Forecast] 任务异常: {e}"),
+            }
+
+            tokio::time::sleep(Duration::from_secs(FORECAST_INTERVAL_SECS)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(y: i32, m: u32, d: u32, h: u32) -> DateTime<Local> {
+        Local
+            .from_local_datetime(
+                &NaiveDate::from_ymd_opt(y, m, d)
+                    .unwrap()
+                    .and_hms_opt(h, 0, 0)
+                    .unwrap(),
+            )
+            .earliest()
+            .unwrap()
+    }
+
+    fn point(
+        day: NaiveDate,
+        provider_id: &str,
+        model: &str,
+        cost: f64,
+        requests: u64,
+        successes: u64,
+    ) -> DailyUsagePoint {
+        DailyUsagePoint {
+            bucket_start: local_midnight(day),
+            app_type: "claude".to_string(),
+            provider_id: provider_id.to_string(),
+            provider_name: Some(format!("{provider_id}-name")),
+            model: model.to_string(),
+            cost_usd: cost,
+            request_count: requests,
+            success_count: successes,
+        }
+    }
+
+    fn day(d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, d).unwrap()
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&[]), 0.0);
+        assert_eq!(median(&[3.0, 1.0, 2.0]), 2.0);
+        assert_eq!(median(&[4.0, 1.0, 3.0, 2.0]), 2.5);
+    }
+
+    #[test]
+    fn test_projection_and_limit_date() {
+        // 3 月 1-10 日每天 $10，11 日中午计算
+        let points: Vec<_> = (1..=10)
+            .map(|d| point(day(d), "p1", "sonnet", 10.0, 10, 10))
+            .collect();
+        let limits = [ProviderMonthlyLimit {
+            app_type: "claude".to_string(),
+            provider_id: "p1".to_string(),
+            provider_name: "Relay".to_string(),
+            limit_usd: 200.0,
+        }];
+        let config = UsageForecastConfig::default();
+        let forecast =
+            compute_forecast(&points, &limits, Some(1000.0), &config, at(2025, 3, 11, 12));
+
+        assert_eq!(forecast.month_to_date_usd, 100.0);
+        assert_eq!(forecast.daily_burn_rate_usd, 10.0);
+        assert!((forecast.projected_month_end_usd - 305.0).abs() < 0.5);
+        assert_eq!(forecast.projected_threshold_date, None);
+
+        let alert = &forecast.alerts[0];
+        assert_eq!(alert.kind, UsageAlertKind::LimitProjected);
+        assert_eq!(alert.scope, UsageAlertScope::Provider);
+        assert_eq!(alert.projected_date.as_deref(), Some("2025-03-21"));
+        assert_eq!(alert.title, "[claude] Relay 预计 2025-03-21 超过月度限额");
+
+        let reached = compute_forecast(&points, &limits, Some(50.0), &config, at(2025, 3, 11, 12));
+        assert!(reached
+            .alerts
+            .iter()
+            .any(|a| a.kind == UsageAlertKind::LimitReached
+                && a.scope == UsageAlertScope::Global
+                && a.severity == UsageAlertSeverity::Critical
+                && !a.should_notify()));
+
+        // 预测与实际超限使用不同的去重键，超限后仍会再次通知
+        assert!(forecast.alerts[0]
+            .key
+            .starts_with("forecast:limit-projected:"));
+        assert!(reached
+            .alerts
+            .iter()
+            .any(|a| a.key.starts_with("forecast:limit-reached:")));
+    }
+
+    #[test]
+    fn test_cost_and_error_spikes() {
+        let mut points: Vec<_> = (1..=10)
+            .map(|d| point(day(d), "p1", "opus", 2.0, 50, 49))
+            .collect();
+        points.push(point(day(11), "p1", "opus", 10.0, 40, 20));
+        let config = UsageForecastConfig::default();
+        let forecast = compute_forecast(&points, &[], None, &config, at(2025, 3, 11, 18));
+
+        let spike = forecast
+            .alerts
+            .iter()
+            .find(|a| a.kind == UsageAlertKind::CostSpike && a.scope == UsageAlertScope::Model)
+            .unwrap();
+        assert_eq!(spike.model.as_deref(), Some("opus"));
+        assert_eq!((spike.value, spike.baseline), (10.0, 2.0));
+        assert_eq!(spike.severity, UsageAlertSeverity::Warning);
+        assert!(spike.key.ends_with("2025-03-11"));
+
+        let errors = forecast
+            .alerts
+            .iter()
+            .find(|a| {
+                a.kind == UsageAlertKind::ErrorRateSpike && a.scope == UsageAlertScope::Provider
+            })
+            .unwrap();
+        assert_eq!(errors.value, 0.5);
+        assert_eq!(errors.severity, UsageAlertSeverity::Critical);
+
+        // 历史天数不足时不判断花费突增
+        let sparse = vec![
+            point(day(9), "p1", "opus", 1.0, 1, 1),
+            point(day(11), "p1", "opus", 50.0, 1, 1),
+        ];
+        let forecast = compute_forecast(&sparse, &[], None, &config, at(2025, 3, 11, 18));
+        assert!(forecast.alerts.is_empty());
+    }
+
+    #[test]
+    fn test_forecast_config_validation() {
+        assert!(UsageForecastConfig::default().validate().is_ok());
+        let config = UsageForecastConfig {
+            cost_spike_ratio: 1.0,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
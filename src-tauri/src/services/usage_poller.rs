@@ -0,0 +1,133 @@
+//! 用量脚本统一轮询
+//!
+//! 额度守护、余额对账等后台消费方共用同一个轮询：每个供应商按各消费方要求的最短间隔执行一次脚本，
+//! 结果交给所有消费方处理（余额快照在查询流程中保存）。任何来源的查询（包括前端手动查询）都会记录查询时间，
+//! 间隔内不会重复执行脚本。
+
+use std::collections::HashMap;
+use std::sync::{LazyLock, RwLock};
+use std::time::{Duration, Instant};
+
+use tauri::{AppHandle, Manager};
+
+use crate::app_config::AppType;
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::services::provider::ProviderService;
+use crate::services::quota_guard::QuotaGuardService;
+use crate::store::AppState;
+
+/// worker 扫描间隔
+const SCAN_INTERVAL_SECS: u64 = 60;
+
+/// `app_type:provider_id` → 最近一次查询时间
+static LAST_QUERIED: LazyLock<RwLock<HashMap<String, Instant>>> =
+    LazyLock::new(|| RwLock::new(HashMap::new()));
+
+fn cache_key(app_type: &AppType, provider_id: &str) -> String {
+    format!("{}:{provider_id}", app_type.as_str())
+}
+
+/// 记录一次用量脚本执行（由 `query_usage` 调用，失败也计入间隔）
+pub fn record_query(app_type: &AppType, provider_id: &str) {
+    if let Ok(mut cache) = LAST_QUERIED.write() {
+        cache.insert(cache_key(app_type, provider_id), Instant::now());
+    }
+}
+
+fn queried_within(app_type: &AppType, provider_id: &str, interval: Duration) -> bool {
+    LAST_QUERIED
+        .read()
+        .ok()
+        .and_then(|cache| cache.get(&cache_key(app_type, provider_id)).copied())
+        .is_some_and(|at| at.elapsed() < interval)
+}
+
+/// 供应商的轮询间隔：取各消费方要求的最短间隔，没有消费方时不轮询
+///
+/// `reconcile_interval` 为余额对账的快照间隔（未启用对账时为 None），适用于所有启用了用量脚本的供应商
+fn poll_interval(provider: &Provider, reconcile_interval: Option<Duration>) -> Option<Duration> {
+    let script = provider
+        .meta
+        .as_ref()?
+        .usage_script
+        .as_ref()
+        .filter(|s| s.enabled)?;
+    let guard_enabled = script.quota_guard.as_ref().is_some_and(|g| g.enabled);
+    let interval_mins = script.auto_query_interval.unwrap_or(0);
+    let guard_interval =
+        (guard_enabled && interval_mins > 0).then(|| Duration::from_secs(interval_mins * 60));
+    [guard_interval, reconcile_interval]
+        .into_iter()
+        .flatten()
+        .min()
+}
+
+/// 已到达轮询间隔的供应商
+fn due_providers(
+    state: &AppState,
+    app_type: &AppType,
+    reconcile_interval: Option<Duration>,
+) -> Result<Vec<String>, AppError> {
+    Ok(state
+        .db
+        .get_all_providers(app_type.as_str())?
+        .into_values()
+        .filter(|p| {
+            poll_interval(p, reconcile_interval)
+                .is_some_and(|interval| !queried_within(app_type, &p.id, interval))
+        })
+        .map(|p| p.id)
+        .collect())
+}
+
+/// 启动后台用量轮询
+pub fn start_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut ticker = tokio::time::interval(Duration::from_secs(SCAN_INTERVAL_SECS));
+        loop {
+            ticker.tick().await;
+            let state = app.state::<AppState>();
+            let reconcile_interval = match state.db.get_balance_reconciliation_config() {
+                Ok(config) => config
+                    .enabled
+                    .then(|| Duration::from_secs(u64::from(config.snapshot_interval_mins) * 60)),
+                Err(e) => {
+                    log::debug!("[UsagePoll] 读取对账配置失败: {e}");
+                    None
+                }
+            };
+            for app_type in AppType::all() {
+                let due = match due_providers(&state, &app_type, reconcile_interval) {
+                    Ok(ids) => ids,
+                    Err(e) => {
+                        log::debug!("[UsagePoll] 读取 {} 供应商失败: {e}", app_type.as_str());
+                        continue;
+                    }
+                };
+                for provider_id in due {
+                    let result =
+                        match ProviderService::query_usage(&state, app_type.clone(), &provider_id)
+                            .await
+                        {
+                            Ok(result) => result,
+                            Err(e) => {
+                                log::debug!("[UsagePoll] 查询 {provider_id} 用量失败: {e}");
+                                continue;
+                            }
+                        };
+                    if let Err(e) = QuotaGuardService::apply_usage_result(
+                        &app,
+                        &app_type,
+                        &provider_id,
+                        &result,
+                    )
+                    .await
+                    {
+                        log::warn!("[QuotaGuard] 应用 {provider_id} 额度结果失败: {e}");
+                    }
+                }
+            }
+        }
+    });
+}
@@ -0,0 +1,321 @@
+//! 用量汇总与日志保留
+//!
+//! `proxy_request_logs` 会无限增长，统计查询需要全表扫描并逐行转换 TEXT 成本。
+//! - 触发器把每条请求增量计入小时汇总与日汇总（见 `dao::usage_rollup`），统计接口只读汇总表
+//! - 保留策略把早于 N 天的原始日志归档为压缩的 CSV / JSONL 后删除，WebDAV 快照随之变小
+//! - 小时汇总只保留最近一段时间，更早的区间由日汇总提供（分界点记录在 settings 中）
+
+use chrono::{DateTime, Local, TimeZone};
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+use zip::write::SimpleFileOptions;
+
+use crate::config::get_app_config_dir;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::services::usage_export::{RowWriter, UsageFileFormat};
+use crate::store::AppState;
+
+/// 小时汇总的保留下限（早于该时间的区间使用日汇总）
+pub(crate) const HOURLY_FLOOR_KEY: &str = "usage_rollup_hourly_floor";
+/// 已归档清理的原始日志时间上限（此后的原始日志完整）
+pub(crate) const ARCHIVED_BEFORE_KEY: &str = "usage_logs_archived_before";
+
+/// 保留策略关闭时重新读取配置的间隔
+const DISABLED_RECHECK_SECS: u64 = 60;
+/// 保留策略的执行间隔
+const RETENTION_INTERVAL_SECS: u64 = 6 * 60 * 60;
+
+/// 日志保留配置
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UsageRetentionConfig {
+    pub enabled: bool,
+    /// 原始请求日志保留天数
+    pub retain_days: u32,
+    /// 小时汇总保留天数
+    pub hourly_days: u32,
+    pub archive_format: UsageFileFormat,
+    /// 归档目录，未设置时使用 `~/.cc-switch/usage-archive`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archive_dir: Option<String>,
+}
+
+impl Default for UsageRetentionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            retain_days: 90,
+            hourly_days: 90,
+            archive_format: UsageFileFormat::default(),
+            archive_dir: None,
+        }
+    }
+}
+
+impl UsageRetentionConfig {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if self.retain_days == 0 || self.hourly_days == 0 {
+            return Err(AppError::InvalidInput("保留天数必须大于 0".to_string()));
+        }
+        Ok(())
+    }
+
+    fn archive_dir(&self) -> PathBuf {
+        match self.archive_dir.as_deref().map(str::trim) {
+            Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+            _ => get_app_config_dir().join("usage-archive"),
+        }
+    }
+}
+
+/// 保留策略执行结果
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageRetentionResult {
+    pub archived_rows: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub archive_path: Option<String>,
+    pub pruned_hourly_rows: u64,
+}
+
+/// 汇总表与按范围读取的原始日志共用的列
+const ROLLUP_COLUMNS: &str = "bucket_start, app_type, provider_id, model, billing_currency,
+    request_count, success_count, input_tokens, output_tokens, cache_read_tokens,
+    cache_creation_tokens, total_cost_usd, billing_cost, latency_ms_sum";
+
+/// 统计查询使用的汇总数据源（子查询）
+///
+/// 小时汇总覆盖分界点之后的区间，日汇总覆盖之前的区间；
+/// `bucket_secs` 为桶宽度，用于按时间范围过滤
+pub(crate) fn rollup_source_sql() -> String {
+    format!(
+        "(SELECT {ROLLUP_COLUMNS}, 3600 AS bucket_secs FROM usage_rollup_hourly
+          WHERE bucket_start >= COALESCE(
+            (SELECT CAST(value AS INTEGER) FROM settings WHERE key = '{HOURLY_FLOOR_KEY}'), 0)
+          UNION ALL
+          SELECT {ROLLUP_COLUMNS}, 86400 AS bucket_secs FROM usage_rollup_daily
+          WHERE bucket_start < COALESCE(
+            (SELECT CAST(value AS INTEGER) FROM settings WHERE key = '{HOURLY_FLOOR_KEY}'), 0))"
+    )
+}
+
+/// 按时间范围（闭区间）统计使用的数据源（子查询），列与 [`rollup_source_sql`] 一致
+///
+/// 完整落在范围内的桶读汇总表；首尾只部分重叠的桶改读原始日志并按 `created_at` 精确过滤，
+/// 这些行的 `bucket_start` 为请求时间、`bucket_secs` 为 0。
+/// 边缘桶的原始日志已被保留策略清理时无法切分，该桶整桶计入
+pub(crate) fn range_source_sql(
+    conn: &Connection,
+    start: Option<i64>,
+    end: Option<i64>,
+) -> Result<String, AppError> {
+    let setting = |key: &str| -> Result<i64, AppError> {
+        Ok(conn.query_row(
+            "SELECT COALESCE((SELECT CAST(value AS INTEGER) FROM settings WHERE key = ?1), 0)",
+            [key],
+            |row| row.get(0),
+        )?)
+    };
+    let hourly_floor = setting(HOURLY_FLOOR_KEY)?;
+    let raw_from = setting(ARCHIVED_BEFORE_KEY)?;
+    let bucket_of = |ts: i64| {
+        if ts >= hourly_floor {
+            let bucket = ts - ts.rem_euclid(3600);
+            (bucket, bucket + 3600)
+        } else {
+            let bucket = local_day_start(ts);
+            (bucket, local_day_start(bucket + 30 * 3600))
+        }
+    };
+
+    // 汇总部分与原始日志部分均为左闭右开区间，None 表示不限
+    let end = end.map(|end| end + 1);
+    let head = start
+        .map(|start| (start, bucket_of(start)))
+        .filter(|(start, (bucket, _))| start != bucket);
+    let tail = end
+        .map(|end| (end, bucket_of(end - 1)))
+        .filter(|(end, (_, bucket_end))| end != bucket_end);
+
+    let mut rollup = Some((start, end));
+    let mut raw = Vec::new();
+    match (head, tail) {
+        // 范围落在同一个桶内
+        (Some((start, bucket)), Some((end, _))) if bucket == bucket_of(end - 1) => {
+            if start >= raw_from {
+                rollup = None;
+                raw.push((start, end));
+            } else {
+                rollup = Some((Some(bucket.0), Some(bucket.1)));
+            }
+        }
+        (head, tail) => {
+            if let Some((start, (bucket_start, bucket_end))) = head {
+                if start >= raw_from {
+                    raw.push((start, bucket_end));
+                    rollup = rollup.map(|(_, hi)| (Some(bucket_end), hi));
+                } else {
+                    rollup = rollup.map(|(_, hi)| (Some(bucket_start), hi));
+                }
+            }
+            if let Some((end, (bucket_start, bucket_end))) = tail {
+                if bucket_start >= raw_from {
+                    raw.push((bucket_start, end));
+                    rollup = rollup.map(|(lo, _)| (lo, Some(bucket_start)));
+                } else {
+                    rollup = rollup.map(|(lo, _)| (lo, Some(bucket_end)));
+                }
+            }
+        }
+    }
+
+    let mut parts = Vec::new();
+    if let Some((lo, hi)) = rollup {
+        let mut conditions = vec!["1 = 1".to_string()];
+        if let Some(lo) = lo {
+            conditions.push(format!("r.bucket_start >= {lo}"));
+        }
+        if let Some(hi) = hi {
+            conditions.push(format!("r.bucket_start + r.bucket_secs <= {hi}"));
+        }
+        parts.push(format!(
+            "SELECT r.* FROM {} r WHERE {}",
+            rollup_source_sql(),
+            conditions.join(" AND ")
+        ));
+    }
+    for (lo, hi) in raw {
+        parts.push(format!(
+            "SELECT created_at AS bucket_start, app_type, provider_id, model, billing_currency,
+                1 AS request_count,
+                CASE WHEN status_code >= 200 AND status_code < 300 THEN 1 ELSE 0 END
+                    AS success_count,
+                input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens,
+                CAST(total_cost_usd AS REAL) AS total_cost_usd,
+                CAST(billing_cost AS REAL) AS billing_cost,
+                latency_ms AS latency_ms_sum, 0 AS bucket_secs
+             FROM proxy_request_logs WHERE created_at >= {lo} AND created_at < {hi}"
+        ));
+    }
+    Ok(format!("({})", parts.join(" UNION ALL ")))
+}
+
+/// 时间戳所在本地自然日的零点
+pub(crate) fn local_day_start(ts: i64) -> i64 {
+    chrono::Local
+        .timestamp_opt(ts, 0)
+        .single()
+        .and_then(|t| t.date_naive().and_hms_opt(0, 0, 0))
+        .and_then(|t| t.and_local_timezone(chrono::Local).earliest())
+        .map(|t| t.timestamp())
+        .unwrap_or(ts - ts.rem_euclid(86_400))
+}
+
+/// 把分批读取的日志写入压缩归档，返回归档文件路径
+///
+/// `next_batch` 返回空批次时结束；调用方在每批读取后即可释放数据库锁
+pub(crate) fn write_archive(
+    columns: Vec<String>,
+    config: &UsageRetentionConfig,
+    now: &DateTime<Local>,
+    mut next_batch: impl FnMut() -> Result<Vec<Vec<Value>>, AppError>,
+) -> Result<PathBuf, AppError> {
+    let dir = config.archive_dir();
+    std::fs::create_dir_all(&dir).map_err(|e| AppError::io(&dir, e))?;
+    let name = format!(
+        "usage-logs-{}.{}",
+        now.format("%Y%m%d-%H%M%S"),
+        config.archive_format.extension()
+    );
+    let path = dir.join(format!("{name}.zip"));
+    let file = std::fs::File::create(&path).map_err(|e| AppError::io(&path, e))?;
+
+    let archive_err = |e: zip::result::ZipError| AppError::Message(format!("写入归档失败: {e}"));
+    let mut zip = zip::ZipWriter::new(file);
+    zip.start_file(
+        name,
+        SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated),
+    )
+    .map_err(archive_err)?;
+
+    let mut writer = RowWriter::new(&mut zip, config.archive_format, columns)?;
+    loop {
+        let batch = next_batch()?;
+        if batch.is_empty() {
+            break;
+        }
+        for row in &batch {
+            let values: Vec<ValueRef<'_>> = row.iter().map(ValueRef::from).collect();
+            writer.write_row(&values)?;
+        }
+    }
+    writer.finish()?;
+
+    zip.finish().map_err(archive_err)?;
+    Ok(path)
+}
+
+/// 执行一轮保留策略（不受开关限制）
+pub fn run_once(db: &Database) -> Result<UsageRetentionResult, AppError> {
+    let config = db.get_usage_retention_config()?;
+    db.apply_usage_retention(&config)
+}
+
+/// 启动后台保留任务
+///
+/// 每轮重新读取配置，开关修改无需重启。
+pub fn start_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let db = app.state::<AppState>().db.clone();
+            let config = match db.get_usage_retention_config() {
+                Ok(config) => config,
+                Err(e) => {
+                    log::warn!("[Retention] 读取配置失败: {e}");
+                    UsageRetentionConfig::default()
+                }
+            };
+
+            if !config.enabled {
+                tokio::time::sleep(Duration::from_secs(DISABLED_RECHECK_SECS)).await;
+                continue;
+            }
+
+            let outcome =
+                tauri::async_runtime::spawn_blocking(move || db.apply_usage_retention(&config))
+                    .await;
+            match outcome {
+                Ok(Ok(result)) if result.archived_rows > 0 => {
+                    if let Err(e) = app.emit("usage-retention-applied", &result) {
+                        log::debug!("[Retention] 发送事件失败: {e}");
+                    }
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => log::warn!("[Retention] 本轮执行失败: {e}"),
+                Err(e) => log::warn!("[Retention] 任务异常: {e}"),
+            }
+
+            tokio::time::sleep(Duration::from_secs(RETENTION_INTERVAL_SECS)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retention_config_validation() {
+        assert!(UsageRetentionConfig::default().validate().is_ok());
+        let config = UsageRetentionConfig {
+            retain_days: 0,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+    }
+}
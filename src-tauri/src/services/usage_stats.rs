@@ -4,6 +4,12 @@
 
 use crate::database::{lock_conn, Database};
 use crate::error::AppError;
+use crate::proxy::session::cli_session_id_sql;
+use crate::proxy::usage::calculator::{CostBreakdown, CostCalculator, UsageDialect};
+use crate::proxy::usage::parser::TokenUsage;
+use crate::services::currency::{load_billing_profile, report_cost_expr_at, settle, BASE_CURRENCY};
+use crate::services::pricing::{resolve_pricing_alias, PricingLookup};
+use crate::services::usage_rollup::range_source_sql;
 use chrono::{Local, TimeZone};
 use rusqlite::{params, Connection, OptionalExtension};
 use serde::{Deserialize, Serialize};
@@ -11,12 +17,26 @@ use serde_json::Value;
 use std::collections::HashMap;
 use std::str::FromStr;
 
+/// 日汇总中今天（本地时间）的桶起点
+const LOCAL_TODAY_START: &str = "CAST(strftime('%s', date('now', 'localtime'), 'utc') AS INTEGER)";
+/// 日汇总的桶属于本月（本地时间）
+const IN_LOCAL_MONTH: &str = "strftime('%Y-%m', bucket_start, 'unixepoch', 'localtime') = strftime('%Y-%m', 'now', 'localtime')";
+
+/// 经代理转发记录的用量
+pub const USAGE_SOURCE_PROXY: &str = "proxy";
+/// 从 CLI 会话记录导入的用量
+pub const USAGE_SOURCE_TRANSCRIPT: &str = "transcript";
+/// 会话记录导入的用量归属的虚拟供应商 ID（直连使用，无法对应到具体供应商）
+pub const TRANSCRIPT_PROVIDER_ID: &str = "_transcript";
+
 /// 使用量汇总
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct UsageSummary {
     pub total_requests: u64,
     pub total_cost: String,
+    /// total_cost 的币种
+    pub currency: String,
     pub total_input_tokens: u64,
     pub total_output_tokens: u64,
     pub total_cache_creation_tokens: u64,
@@ -47,6 +67,8 @@ pub struct ProviderStats {
     pub request_count: u64,
     pub total_tokens: u64,
     pub total_cost: String,
+    /// total_cost 的币种
+    pub currency: String,
     pub success_rate: f32,
     pub avg_latency_ms: u64,
 }
@@ -72,6 +94,68 @@ pub struct LogFilters {
     pub status_code: Option<u16>,
     pub start_date: Option<i64>,
     pub end_date: Option<i64>,
+    /// 用量来源（proxy / transcript）
+    pub source: Option<String>,
+    pub session_id: Option<String>,
+    /// 会话所属项目目录（见 session_projects）
+    pub project_dir: Option<String>,
+}
+
+/// 请求日志过滤条件依赖的关联（供应商名称、会话项目）
+pub(crate) fn log_filter_joins() -> String {
+    format!(
+        "LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
+         LEFT JOIN session_projects sp
+         ON sp.session_id = {} AND sp.app_type = l.app_type",
+        cli_session_id_sql("l.session_id")
+    )
+}
+
+/// 把日志过滤器转换为 SQL 条件（表别名 l / p / sp，见 [`log_filter_joins`]）
+pub(crate) fn log_filter_conditions(
+    filters: &LogFilters,
+) -> (Vec<&'static str>, Vec<Box<dyn rusqlite::ToSql>>) {
+    let mut conditions = Vec::new();
+    let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+    if let Some(ref app_type) = filters.app_type {
+        conditions.push("l.app_type = ?");
+        params.push(Box::new(app_type.clone()));
+    }
+    if let Some(ref provider_name) = filters.provider_name {
+        conditions.push("p.name LIKE ?");
+        params.push(Box::new(format!("%{provider_name}%")));
+    }
+    if let Some(ref model) = filters.model {
+        conditions.push("l.model LIKE ?");
+        params.push(Box::new(format!("%{model}%")));
+    }
+    if let Some(status) = filters.status_code {
+        conditions.push("l.status_code = ?");
+        params.push(Box::new(status as i64));
+    }
+    if let Some(start) = filters.start_date {
+        conditions.push("l.created_at >= ?");
+        params.push(Box::new(start));
+    }
+    if let Some(end) = filters.end_date {
+        conditions.push("l.created_at <= ?");
+        params.push(Box::new(end));
+    }
+    if let Some(ref source) = filters.source {
+        conditions.push("l.source = ?");
+        params.push(Box::new(source.clone()));
+    }
+    if let Some(ref session_id) = filters.session_id {
+        conditions.push("l.session_id = ?");
+        params.push(Box::new(session_id.clone()));
+    }
+    if let Some(ref project_dir) = filters.project_dir {
+        conditions.push("sp.project_dir = ?");
+        params.push(Box::new(project_dir.clone()));
+    }
+
+    (conditions, params)
 }
 
 /// 分页请求日志响应
@@ -101,11 +185,20 @@ pub struct RequestLogDetail {
     pub output_tokens: u32,
     pub cache_read_tokens: u32,
     pub cache_creation_tokens: u32,
+    /// 缓存写入中使用 1 小时 TTL 的部分
+    #[serde(default)]
+    pub cache_creation_1h_tokens: u32,
     pub input_cost_usd: String,
     pub output_cost_usd: String,
     pub cache_read_cost_usd: String,
     pub cache_creation_cost_usd: String,
     pub total_cost_usd: String,
+    /// 供应商计费币种
+    #[serde(default = "default_billing_currency")]
+    pub billing_currency: String,
+    /// 计费币种下的实际花费
+    #[serde(default)]
+    pub billing_cost: String,
     pub is_streaming: bool,
     pub latency_ms: u64,
     pub first_token_ms: Option<u64>,
@@ -113,49 +206,59 @@ pub struct RequestLogDetail {
     pub status_code: u16,
     pub error_message: Option<String>,
     pub created_at: i64,
+    /// 实际提供服务的端点（端点池故障转移时可能不是主地址）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub endpoint_url: Option<String>,
+    /// 用量来源：proxy（代理记录）或 transcript（会话记录导入）
+    #[serde(default = "default_usage_source")]
+    pub source: String,
+    /// 映射后发往上游的模型（启用模型替换检测后记录）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_model: Option<String>,
+    /// 上游返回模型与期望模型不一致
+    #[serde(default)]
+    pub model_mismatch: bool,
+}
+
+fn default_billing_currency() -> String {
+    BASE_CURRENCY.to_string()
+}
+
+fn default_usage_source() -> String {
+    USAGE_SOURCE_PROXY.to_string()
 }
 
 impl Database {
-    /// 获取使用量汇总
+    /// 获取使用量汇总，成本按 `currency` 折算
     pub fn get_usage_summary(
         &self,
         start_date: Option<i64>,
         end_date: Option<i64>,
+        currency: &str,
     ) -> Result<UsageSummary, AppError> {
         let conn = lock_conn!(self.conn);
 
-        let (where_clause, params_vec) = if start_date.is_some() || end_date.is_some() {
-            let mut conditions = Vec::new();
-            let mut params = Vec::new();
-
-            if let Some(start) = start_date {
-                conditions.push("created_at >= ?");
-                params.push(start);
-            }
-            if let Some(end) = end_date {
-                conditions.push("created_at <= ?");
-                params.push(end);
-            }
-
-            (format!("WHERE {}", conditions.join(" AND ")), params)
+        // 不限时间范围时直接读日汇总，否则完整的桶读汇总、首尾不完整的桶读原始日志
+        let source = if start_date.is_some() || end_date.is_some() {
+            range_source_sql(&conn, start_date, end_date)?
         } else {
-            (String::new(), Vec::new())
+            "usage_rollup_daily".to_string()
         };
 
+        let cost_expr = report_cost_expr_at("u.", "bucket_start", currency);
         let sql = format!(
             "SELECT
-                COUNT(*) as total_requests,
-                COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0) as total_cost,
-                COALESCE(SUM(input_tokens), 0) as total_input_tokens,
-                COALESCE(SUM(output_tokens), 0) as total_output_tokens,
-                COALESCE(SUM(cache_creation_tokens), 0) as total_cache_creation_tokens,
-                COALESCE(SUM(cache_read_tokens), 0) as total_cache_read_tokens,
-                COALESCE(SUM(CASE WHEN status_code >= 200 AND status_code < 300 THEN 1 ELSE 0 END), 0) as success_count
-             FROM proxy_request_logs
-             {where_clause}"
+                COALESCE(SUM(u.request_count), 0) as total_requests,
+                COALESCE(SUM({cost_expr}), 0) as total_cost,
+                COALESCE(SUM(u.input_tokens), 0) as total_input_tokens,
+                COALESCE(SUM(u.output_tokens), 0) as total_output_tokens,
+                COALESCE(SUM(u.cache_creation_tokens), 0) as total_cache_creation_tokens,
+                COALESCE(SUM(u.cache_read_tokens), 0) as total_cache_read_tokens,
+                COALESCE(SUM(u.success_count), 0) as success_count
+             FROM {source} u"
         );
 
-        let result = conn.query_row(&sql, rusqlite::params_from_iter(params_vec), |row| {
+        let result = conn.query_row(&sql, [], |row| {
             let total_requests: i64 = row.get(0)?;
             let total_cost: f64 = row.get(1)?;
             let total_input_tokens: i64 = row.get(2)?;
@@ -173,6 +276,7 @@ impl Database {
             Ok(UsageSummary {
                 total_requests: total_requests as u64,
                 total_cost: format!("{total_cost:.6}"),
+                currency: currency.to_string(),
                 total_input_tokens: total_input_tokens as u64,
                 total_output_tokens: total_output_tokens as u64,
                 total_cache_creation_tokens: total_cache_creation_tokens as u64,
@@ -189,6 +293,7 @@ impl Database {
         &self,
         start_date: Option<i64>,
         end_date: Option<i64>,
+        currency: &str,
     ) -> Result<Vec<DailyStats>, AppError> {
         let conn = lock_conn!(self.conn);
 
@@ -220,23 +325,28 @@ impl Database {
             bucket_count = 1;
         }
 
-        let sql = "
+        // 汇总桶与趋势桶按起点对齐，整桶计入且起点早于窗口的边缘桶计入第一个趋势桶
+        let source = range_source_sql(&conn, Some(start_ts), Some(end_ts))?;
+        let cost_expr = report_cost_expr_at("u.", "bucket_start", currency);
+        let sql = format!(
+            "
             SELECT
-                CAST((created_at - ?1) / ?3 AS INTEGER) as bucket_idx,
-                COUNT(*) as request_count,
-                COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0) as total_cost,
-                COALESCE(SUM(input_tokens + output_tokens), 0) as total_tokens,
-                COALESCE(SUM(input_tokens), 0) as total_input_tokens,
-                COALESCE(SUM(output_tokens), 0) as total_output_tokens,
-                COALESCE(SUM(cache_creation_tokens), 0) as total_cache_creation_tokens,
-                COALESCE(SUM(cache_read_tokens), 0) as total_cache_read_tokens
-            FROM proxy_request_logs
-            WHERE created_at >= ?1 AND created_at <= ?2
+                MAX(CAST((u.bucket_start - ?1) / ?2 AS INTEGER), 0) as bucket_idx,
+                COALESCE(SUM(u.request_count), 0) as request_count,
+                COALESCE(SUM({cost_expr}), 0) as total_cost,
+                COALESCE(SUM(u.input_tokens + u.output_tokens), 0) as total_tokens,
+                COALESCE(SUM(u.input_tokens), 0) as total_input_tokens,
+                COALESCE(SUM(u.output_tokens), 0) as total_output_tokens,
+                COALESCE(SUM(u.cache_creation_tokens), 0) as total_cache_creation_tokens,
+                COALESCE(SUM(u.cache_read_tokens), 0) as total_cache_read_tokens
+            FROM {source} u
             GROUP BY bucket_idx
-            ORDER BY bucket_idx ASC";
+            HAVING SUM(u.request_count) > 0
+            ORDER BY bucket_idx ASC"
+        );
 
-        let mut stmt = conn.prepare(sql)?;
-        let rows = stmt.query_map(params![start_ts, end_ts, bucket_seconds], |row| {
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(params![start_ts, bucket_seconds], |row| {
             Ok((
                 row.get::<_, i64>(0)?,
                 DailyStats {
@@ -295,23 +405,27 @@ impl Database {
     }
 
     /// 获取 Provider 统计
-    pub fn get_provider_stats(&self) -> Result<Vec<ProviderStats>, AppError> {
+    pub fn get_provider_stats(&self, currency: &str) -> Result<Vec<ProviderStats>, AppError> {
         let conn = lock_conn!(self.conn);
 
-        let sql = "SELECT
-                l.provider_id,
+        let cost_expr = report_cost_expr_at("u.", "bucket_start", currency);
+        let sql = format!(
+            "SELECT
+                u.provider_id,
                 p.name as provider_name,
-                COUNT(*) as request_count,
-                COALESCE(SUM(l.input_tokens + l.output_tokens), 0) as total_tokens,
-                COALESCE(SUM(CAST(l.total_cost_usd AS REAL)), 0) as total_cost,
-                COALESCE(SUM(CASE WHEN l.status_code >= 200 AND l.status_code < 300 THEN 1 ELSE 0 END), 0) as success_count,
-                COALESCE(AVG(l.latency_ms), 0) as avg_latency
-             FROM proxy_request_logs l
-             LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
-             GROUP BY l.provider_id, l.app_type
-             ORDER BY total_cost DESC";
+                SUM(u.request_count) as request_count,
+                COALESCE(SUM(u.input_tokens + u.output_tokens), 0) as total_tokens,
+                COALESCE(SUM({cost_expr}), 0) as total_cost,
+                COALESCE(SUM(u.success_count), 0) as success_count,
+                COALESCE(CAST(SUM(u.latency_ms_sum) AS REAL) / SUM(u.request_count), 0) as avg_latency
+             FROM usage_rollup_daily u
+             LEFT JOIN providers p ON u.provider_id = p.id AND u.app_type = p.app_type
+             GROUP BY u.provider_id, u.app_type
+             HAVING SUM(u.request_count) > 0
+             ORDER BY total_cost DESC"
+        );
 
-        let mut stmt = conn.prepare(sql)?;
+        let mut stmt = conn.prepare(&sql)?;
         let rows = stmt.query_map([], |row| {
             let request_count: i64 = row.get(2)?;
             let success_count: i64 = row.get(5)?;
@@ -321,14 +435,23 @@ impl Database {
                 0.0
             };
 
+            let provider_id: String = row.get(0)?;
+            // 会话记录导入的用量没有对应的供应商
+            let fallback_name = if provider_id == TRANSCRIPT_PROVIDER_ID {
+                "Direct (transcript)"
+            } else {
+                "Unknown"
+            };
+
             Ok(ProviderStats {
-                provider_id: row.get(0)?,
                 provider_name: row
                     .get::<_, Option<String>>(1)?
-                    .unwrap_or_else(|| "Unknown".to_string()),
+                    .unwrap_or_else(|| fallback_name.to_string()),
+                provider_id,
                 request_count: request_count as u64,
                 total_tokens: row.get::<_, i64>(3)? as u64,
                 total_cost: format!("{:.6}", row.get::<_, f64>(4)?),
+                currency: currency.to_string(),
                 success_rate,
                 avg_latency_ms: row.get::<_, f64>(6)? as u64,
             })
@@ -343,19 +466,23 @@ impl Database {
     }
 
     /// 获取模型统计
-    pub fn get_model_stats(&self) -> Result<Vec<ModelStats>, AppError> {
+    pub fn get_model_stats(&self, currency: &str) -> Result<Vec<ModelStats>, AppError> {
         let conn = lock_conn!(self.conn);
 
-        let sql = "SELECT
-                model,
-                COUNT(*) as request_count,
-                COALESCE(SUM(input_tokens + output_tokens), 0) as total_tokens,
-                COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0) as total_cost
-             FROM proxy_request_logs
-             GROUP BY model
-             ORDER BY total_cost DESC";
+        let cost_expr = report_cost_expr_at("u.", "bucket_start", currency);
+        let sql = format!(
+            "SELECT
+                u.model,
+                SUM(u.request_count) as request_count,
+                COALESCE(SUM(u.input_tokens + u.output_tokens), 0) as total_tokens,
+                COALESCE(SUM({cost_expr}), 0) as total_cost
+             FROM usage_rollup_daily u
+             GROUP BY u.model
+             HAVING SUM(u.request_count) > 0
+             ORDER BY total_cost DESC"
+        );
 
-        let mut stmt = conn.prepare(sql)?;
+        let mut stmt = conn.prepare(&sql)?;
         let rows = stmt.query_map([], |row| {
             let request_count: i64 = row.get(1)?;
             let total_cost: f64 = row.get(3)?;
@@ -391,33 +518,7 @@ impl Database {
     ) -> Result<PaginatedLogs, AppError> {
         let conn = lock_conn!(self.conn);
 
-        let mut conditions = Vec::new();
-        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-
-        if let Some(ref app_type) = filters.app_type {
-            conditions.push("l.app_type = ?");
-            params.push(Box::new(app_type.clone()));
-        }
-        if let Some(ref provider_name) = filters.provider_name {
-            conditions.push("p.name LIKE ?");
-            params.push(Box::new(format!("%{provider_name}%")));
-        }
-        if let Some(ref model) = filters.model {
-            conditions.push("l.model LIKE ?");
-            params.push(Box::new(format!("%{model}%")));
-        }
-        if let Some(status) = filters.status_code {
-            conditions.push("l.status_code = ?");
-            params.push(Box::new(status as i64));
-        }
-        if let Some(start) = filters.start_date {
-            conditions.push("l.created_at >= ?");
-            params.push(Box::new(start));
-        }
-        if let Some(end) = filters.end_date {
-            conditions.push("l.created_at <= ?");
-            params.push(Box::new(end));
-        }
+        let (conditions, mut params) = log_filter_conditions(filters);
 
         let where_clause = if conditions.is_empty() {
             String::new()
@@ -426,9 +527,10 @@ impl Database {
         };
 
         // 获取总数
+        let joins = log_filter_joins();
         let count_sql = format!(
             "SELECT COUNT(*) FROM proxy_request_logs l
-             LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
+             {joins}
              {where_clause}"
         );
         let count_params: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
@@ -447,9 +549,11 @@ impl Database {
                     l.input_tokens, l.output_tokens, l.cache_read_tokens, l.cache_creation_tokens,
                     l.input_cost_usd, l.output_cost_usd, l.cache_read_cost_usd, l.cache_creation_cost_usd, l.total_cost_usd,
                     l.is_streaming, l.latency_ms, l.first_token_ms, l.duration_ms,
-                    l.status_code, l.error_message, l.created_at
+                    l.status_code, l.error_message, l.created_at, l.endpoint_url,
+                    l.cache_creation_1h_tokens, l.billing_currency, l.billing_cost, l.source,
+                    l.expected_model, l.model_mismatch
              FROM proxy_request_logs l
-             LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
+             {joins}
              {where_clause}
              ORDER BY l.created_at DESC
              LIMIT ? OFFSET ?"
@@ -472,11 +576,15 @@ impl Database {
                 output_tokens: row.get::<_, i64>(8)? as u32,
                 cache_read_tokens: row.get::<_, i64>(9)? as u32,
                 cache_creation_tokens: row.get::<_, i64>(10)? as u32,
+                cache_creation_1h_tokens: row.get::<_, i64>(24)? as u32,
                 input_cost_usd: row.get(11)?,
                 output_cost_usd: row.get(12)?,
                 cache_read_cost_usd: row.get(13)?,
                 cache_creation_cost_usd: row.get(14)?,
                 total_cost_usd: row.get(15)?,
+                billing_currency: row.get(25)?,
+                billing_cost: row.get(26)?,
+                source: row.get(27)?,
                 is_streaming: row.get::<_, i64>(16)? != 0,
                 latency_ms: row.get::<_, i64>(17)? as u64,
                 first_token_ms: row.get::<_, Option<i64>>(18)?.map(|v| v as u64),
@@ -484,12 +592,15 @@ impl Database {
                 status_code: row.get::<_, i64>(20)? as u16,
                 error_message: row.get(21)?,
                 created_at: row.get(22)?,
+                endpoint_url: row.get(23)?,
+                expected_model: row.get(28)?,
+                model_mismatch: row.get::<_, i64>(29)? != 0,
             })
         })?;
 
         let mut logs = Vec::new();
         let mut provider_cache = HashMap::new();
-        let mut pricing_cache = HashMap::new();
+        let mut pricing_cache = PricingLookup::default();
 
         for row in rows {
             let mut log = row?;
@@ -523,7 +634,9 @@ impl Database {
                     input_tokens, output_tokens, cache_read_tokens, cache_creation_tokens,
                     input_cost_usd, output_cost_usd, cache_read_cost_usd, cache_creation_cost_usd, total_cost_usd,
                     is_streaming, latency_ms, first_token_ms, duration_ms,
-                    status_code, error_message, created_at
+                    status_code, error_message, created_at, endpoint_url,
+                    cache_creation_1h_tokens, billing_currency, billing_cost, source,
+                    expected_model, model_mismatch
              FROM proxy_request_logs l
              LEFT JOIN providers p ON l.provider_id = p.id AND l.app_type = p.app_type
              WHERE l.request_id = ?",
@@ -541,11 +654,15 @@ impl Database {
                     output_tokens: row.get::<_, i64>(8)? as u32,
                     cache_read_tokens: row.get::<_, i64>(9)? as u32,
                     cache_creation_tokens: row.get::<_, i64>(10)? as u32,
+                    cache_creation_1h_tokens: row.get::<_, i64>(24)? as u32,
                     input_cost_usd: row.get(11)?,
                     output_cost_usd: row.get(12)?,
                     cache_read_cost_usd: row.get(13)?,
                     cache_creation_cost_usd: row.get(14)?,
                     total_cost_usd: row.get(15)?,
+                    billing_currency: row.get(25)?,
+                    billing_cost: row.get(26)?,
+                    source: row.get(27)?,
                     is_streaming: row.get::<_, i64>(16)? != 0,
                     latency_ms: row.get::<_, i64>(17)? as u64,
                     first_token_ms: row.get::<_, Option<i64>>(18)?.map(|v| v as u64),
@@ -553,6 +670,9 @@ impl Database {
                     status_code: row.get::<_, i64>(20)? as u16,
                     error_message: row.get(21)?,
                     created_at: row.get(22)?,
+                    endpoint_url: row.get(23)?,
+                    expected_model: row.get(28)?,
+                    model_mismatch: row.get::<_, i64>(29)? != 0,
                 })
             },
         );
@@ -560,7 +680,7 @@ impl Database {
         match result {
             Ok(mut detail) => {
                 let mut provider_cache = HashMap::new();
-                let mut pricing_cache = HashMap::new();
+                let mut pricing_cache = PricingLookup::default();
                 Self::maybe_backfill_log_costs(
                     &conn,
                     &mut detail,
@@ -574,6 +694,24 @@ impl Database {
         }
     }
 
+    /// 汇总所有应用今日与本月的花费（美元）
+    pub fn get_global_spend_usd(&self) -> Result<(f64, f64), AppError> {
+        let conn = lock_conn!(self.conn);
+        conn.query_row(
+            &format!(
+                "SELECT
+                    COALESCE(SUM(CASE WHEN bucket_start = {LOCAL_TODAY_START}
+                        THEN total_cost_usd ELSE 0 END), 0),
+                    COALESCE(SUM(total_cost_usd), 0)
+                 FROM usage_rollup_daily
+                 WHERE {IN_LOCAL_MONTH}"
+            ),
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .map_err(|e| AppError::Database(e.to_string()))
+    }
+
     /// 检查 Provider 使用限额
     pub fn check_provider_limits(
         &self,
@@ -610,10 +748,11 @@ impl Database {
         // 计算今日使用量
         let daily_usage: f64 = conn
             .query_row(
-                "SELECT COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0)
-             FROM proxy_request_logs
-             WHERE provider_id = ? AND app_type = ?
-               AND date(datetime(created_at, 'unixepoch', 'localtime')) = date('now', 'localtime')",
+                &format!(
+                    "SELECT COALESCE(SUM(total_cost_usd), 0)
+                     FROM usage_rollup_daily
+                     WHERE provider_id = ? AND app_type = ? AND bucket_start = {LOCAL_TODAY_START}"
+                ),
                 params![provider_id, app_type],
                 |row| row.get(0),
             )
@@ -622,10 +761,11 @@ impl Database {
         // 计算本月使用量
         let monthly_usage: f64 = conn
             .query_row(
-                "SELECT COALESCE(SUM(CAST(total_cost_usd AS REAL)), 0)
-             FROM proxy_request_logs
-             WHERE provider_id = ? AND app_type = ?
-               AND strftime('%Y-%m', datetime(created_at, 'unixepoch', 'localtime')) = strftime('%Y-%m', 'now', 'localtime')",
+                &format!(
+                    "SELECT COALESCE(SUM(total_cost_usd), 0)
+                     FROM usage_rollup_daily
+                     WHERE provider_id = ? AND app_type = ? AND {IN_LOCAL_MONTH}"
+                ),
                 params![provider_id, app_type],
                 |row| row.get(0),
             )
@@ -663,20 +803,12 @@ pub struct ProviderLimitStatus {
     pub monthly_exceeded: bool,
 }
 
-#[derive(Clone)]
-struct PricingInfo {
-    input: rust_decimal::Decimal,
-    output: rust_decimal::Decimal,
-    cache_read: rust_decimal::Decimal,
-    cache_creation: rust_decimal::Decimal,
-}
-
 impl Database {
     fn maybe_backfill_log_costs(
         conn: &Connection,
         log: &mut RequestLogDetail,
         provider_cache: &mut HashMap<(String, String), rust_decimal::Decimal>,
-        pricing_cache: &mut HashMap<String, PricingInfo>,
+        pricing_cache: &mut PricingLookup,
     ) -> Result<(), AppError> {
         let total_cost = rust_decimal::Decimal::from_str(&log.total_cost_usd)
             .unwrap_or(rust_decimal::Decimal::ZERO);
@@ -690,8 +822,8 @@ impl Database {
             return Ok(());
         }
 
-        let pricing = match Self::get_model_pricing_cached(conn, pricing_cache, &log.model)? {
-            Some(info) => info,
+        let pricing = match pricing_cache.pricing_at(conn, &log.model, log.created_at)? {
+            Some(pricing) => pricing,
             None => return Ok(()),
         };
         let multiplier = Self::get_cost_multiplier_cached(
@@ -701,33 +833,43 @@ impl Database {
             &log.app_type,
         )?;
 
-        let million = rust_decimal::Decimal::from(1_000_000u64);
-
-        // 与 CostCalculator::calculate 保持一致的计算逻辑：
-        // 1. input_cost 需要扣除 cache_read_tokens（避免缓存部分被重复计费）
-        // 2. 各项成本是基础成本（不含倍率）
-        // 3. 倍率只作用于最终总价
-        let billable_input_tokens =
-            (log.input_tokens as u64).saturating_sub(log.cache_read_tokens as u64);
-        let input_cost =
-            rust_decimal::Decimal::from(billable_input_tokens) * pricing.input / million;
-        let output_cost =
-            rust_decimal::Decimal::from(log.output_tokens as u64) * pricing.output / million;
-        let cache_read_cost = rust_decimal::Decimal::from(log.cache_read_tokens as u64)
-            * pricing.cache_read
-            / million;
-        let cache_creation_cost = rust_decimal::Decimal::from(log.cache_creation_tokens as u64)
-            * pricing.cache_creation
-            / million;
-        // 总成本 = 基础成本之和 × 倍率
-        let base_total = input_cost + output_cost + cache_read_cost + cache_creation_cost;
-        let total_cost = base_total * multiplier;
+        let usage = TokenUsage {
+            input_tokens: log.input_tokens,
+            output_tokens: log.output_tokens,
+            cache_read_tokens: log.cache_read_tokens,
+            cache_creation_tokens: log.cache_creation_tokens,
+            cache_creation_1h_tokens: log.cache_creation_1h_tokens,
+            model: None,
+        };
+        let CostBreakdown {
+            input_cost,
+            output_cost,
+            cache_read_cost,
+            cache_creation_cost,
+            total_cost,
+        } = CostCalculator::calculate(
+            &usage,
+            &pricing,
+            multiplier,
+            UsageDialect::for_app_type(&log.app_type),
+        );
+
+        let profile = load_billing_profile(conn, &log.provider_id, &log.app_type)?;
+        let settlement = settle(conn, total_cost, &profile, log.created_at)?;
+        let input_cost = settlement.scale_usd(total_cost, input_cost);
+        let output_cost = settlement.scale_usd(total_cost, output_cost);
+        let cache_read_cost = settlement.scale_usd(total_cost, cache_read_cost);
+        let cache_creation_cost = settlement.scale_usd(total_cost, cache_creation_cost);
+        let total_cost = settlement.total_usd;
+        let billing_cost = settlement.billing_cost;
 
         log.input_cost_usd = format!("{input_cost:.6}");
         log.output_cost_usd = format!("{output_cost:.6}");
         log.cache_read_cost_usd = format!("{cache_read_cost:.6}");
         log.cache_creation_cost_usd = format!("{cache_creation_cost:.6}");
         log.total_cost_usd = format!("{total_cost:.6}");
+        log.billing_currency = settlement.currency;
+        log.billing_cost = format!("{billing_cost:.6}");
 
         conn.execute(
             "UPDATE proxy_request_logs
@@ -735,14 +877,18 @@ impl Database {
                  output_cost_usd = ?2,
                  cache_read_cost_usd = ?3,
                  cache_creation_cost_usd = ?4,
-                 total_cost_usd = ?5
-             WHERE request_id = ?6",
+                 total_cost_usd = ?5,
+                 billing_currency = ?6,
+                 billing_cost = ?7
+             WHERE request_id = ?8",
             params![
                 log.input_cost_usd,
                 log.output_cost_usd,
                 log.cache_read_cost_usd,
                 log.cache_creation_cost_usd,
                 log.total_cost_usd,
+                log.billing_currency,
+                log.billing_cost,
                 log.request_id
             ],
         )
@@ -783,71 +929,41 @@ impl Database {
         cache.insert(key, multiplier);
         Ok(multiplier)
     }
-
-    fn get_model_pricing_cached(
-        conn: &Connection,
-        cache: &mut HashMap<String, PricingInfo>,
-        model: &str,
-    ) -> Result<Option<PricingInfo>, AppError> {
-        if let Some(info) = cache.get(model) {
-            return Ok(Some(info.clone()));
-        }
-
-        let row = find_model_pricing_row(conn, model)?;
-        let Some((input, output, cache_read, cache_creation)) = row else {
-            return Ok(None);
-        };
-
-        let pricing = PricingInfo {
-            input: rust_decimal::Decimal::from_str(&input)
-                .map_err(|e| AppError::Database(format!("解析输入价格失败: {e}")))?,
-            output: rust_decimal::Decimal::from_str(&output)
-                .map_err(|e| AppError::Database(format!("解析输出价格失败: {e}")))?,
-            cache_read: rust_decimal::Decimal::from_str(&cache_read)
-                .map_err(|e| AppError::Database(format!("解析缓存读取价格失败: {e}")))?,
-            cache_creation: rust_decimal::Decimal::from_str(&cache_creation)
-                .map_err(|e| AppError::Database(format!("解析缓存写入价格失败: {e}")))?,
-        };
-
-        cache.insert(model.to_string(), pricing.clone());
-        Ok(Some(pricing))
-    }
 }
 
-pub(crate) fn find_model_pricing_row(
-    conn: &Connection,
-    model_id: &str,
-) -> Result<Option<(String, String, String, String)>, AppError> {
-    // 清洗模型名称：去前缀(/)、去后缀(:)、@ 替换为 -
-    // 例如 moonshotai/gpt-5.2-codex@low:v2 → gpt-5.2-codex-low
-    let cleaned = model_id
+/// 清洗模型名称：去前缀(/)、去后缀(:)、@ 替换为 -
+/// 例如 moonshotai/gpt-5.2-codex@low:v2 → gpt-5.2-codex-low
+pub(crate) fn clean_model_id(model_id: &str) -> String {
+    model_id
         .rsplit_once('/')
         .map_or(model_id, |(_, r)| r)
         .split(':')
         .next()
         .unwrap_or(model_id)
         .trim()
-        .replace('@', "-");
+        .replace('@', "-")
+}
+
+/// 查找模型对应的定价行，返回匹配到的 model_id
+pub(crate) fn find_model_pricing_row(
+    conn: &Connection,
+    model_id: &str,
+) -> Result<Option<String>, AppError> {
+    let cleaned = clean_model_id(model_id);
 
-    // 精确匹配清洗后的名称
+    // 精确匹配清洗后的名称，未命中时再查别名
     let exact = conn
         .query_row(
-            "SELECT input_cost_per_million, output_cost_per_million,
-                    cache_read_cost_per_million, cache_creation_cost_per_million
-             FROM model_pricing
-             WHERE model_id = ?1",
+            "SELECT model_id FROM model_pricing WHERE model_id = ?1",
             [&cleaned],
-            |row| {
-                Ok((
-                    row.get::<_, String>(0)?,
-                    row.get::<_, String>(1)?,
-                    row.get::<_, String>(2)?,
-                    row.get::<_, String>(3)?,
-                ))
-            },
+            |row| row.get::<_, String>(0),
         )
         .optional()
         .map_err(|e| AppError::Database(format!("查询模型定价失败: {e}")))?;
+    let exact = match exact {
+        Some(model_id) => Some(model_id),
+        None => resolve_pricing_alias(conn, model_id, &cleaned)?,
+    };
 
     if exact.is_none() {
         log::warn!("模型 {model_id}（清洗后: {cleaned}）未找到定价信息，成本将记录为 0");
@@ -885,13 +1001,75 @@ mod tests {
             )?;
         }
 
-        let summary = db.get_usage_summary(None, None)?;
+        let summary = db.get_usage_summary(None, None, BASE_CURRENCY)?;
         assert_eq!(summary.total_requests, 2);
         assert_eq!(summary.success_rate, 100.0);
 
         Ok(())
     }
 
+    #[test]
+    fn test_usage_summary_splits_partial_buckets() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        {
+            let conn = lock_conn!(db.conn);
+            // 同一小时桶内的两条请求，以及完整落在范围内的下一个小时桶
+            for (id, created_at) in [("a", 36_010), ("b", 38_000), ("c", 40_000)] {
+                conn.execute(
+                    "INSERT INTO proxy_request_logs (
+                        request_id, provider_id, app_type, model, total_cost_usd,
+                        latency_ms, status_code, created_at
+                    ) VALUES (?1, 'p1', 'claude', 'm', '1', 100, 200, ?2)",
+                    params![id, created_at],
+                )?;
+            }
+        }
+
+        let summary = db.get_usage_summary(Some(37_000), Some(43_199), BASE_CURRENCY)?;
+        assert_eq!(summary.total_requests, 2);
+        assert_eq!(summary.total_cost, "2.000000");
+
+        // 范围落在同一个桶内时只读原始日志
+        let summary = db.get_usage_summary(Some(36_000), Some(37_000), BASE_CURRENCY)?;
+        assert_eq!(summary.total_requests, 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_usage_summary_in_reporting_currency() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        {
+            let conn = lock_conn!(db.conn);
+            conn.execute(
+                "INSERT INTO exchange_rates (currency, rate, effective_from) VALUES ('CNY', '8', 0)",
+                [],
+            )?;
+            // CNY 计费的请求直接使用计费币种金额，USD 计费的请求按汇率折算
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model, total_cost_usd,
+                    billing_currency, billing_cost, latency_ms, status_code, created_at
+                ) VALUES ('cny', 'p1', 'claude', 'm', '1.25', 'CNY', '10', 100, 200, 1000)",
+                [],
+            )?;
+            conn.execute(
+                "INSERT INTO proxy_request_logs (
+                    request_id, provider_id, app_type, model, total_cost_usd,
+                    billing_currency, billing_cost, latency_ms, status_code, created_at
+                ) VALUES ('usd', 'p2', 'claude', 'm', '2', 'USD', '2', 100, 200, 1000)",
+                [],
+            )?;
+        }
+
+        let usd = db.get_usage_summary(None, None, BASE_CURRENCY)?;
+        assert_eq!(usd.total_cost, "3.250000");
+        let cny = db.get_usage_summary(None, None, "CNY")?;
+        assert_eq!(cny.total_cost, "26.000000");
+        assert_eq!(cny.currency, "CNY");
+        Ok(())
+    }
+
     #[test]
     fn test_get_model_stats() -> Result<(), AppError> {
         let db = Database::memory()?;
@@ -920,7 +1098,7 @@ mod tests {
             )?;
         }
 
-        let stats = db.get_model_stats()?;
+        let stats = db.get_model_stats(BASE_CURRENCY)?;
         assert_eq!(stats.len(), 1);
         assert_eq!(stats[0].model, "claude-3-sonnet");
         assert_eq!(stats[0].request_count, 1);
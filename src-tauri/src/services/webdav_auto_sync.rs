@@ -192,6 +192,11 @@ async fn run_worker_loop(
 
         if let Err(err) = run_auto_sync_upload(&db, &app).await {
             log::warn!("[WebDAV][AutoSync] Upload failed: {err}");
+            crate::services::notification::notify(
+                crate::services::notification::NotificationEvent::WebDavSyncFailed {
+                    error: err.to_string(),
+                },
+            );
         }
     }
 }
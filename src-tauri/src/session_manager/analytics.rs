@@ -0,0 +1,852 @@
+//! Per-session statistics and per-project rollups.
+//!
+//! Stats are derived from the parsed transcript and cached in the database by
+//! file fingerprint, so only sessions that changed are parsed again. Costs are
+//! not cached: they are priced on every request with the current pricing table.
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::database::{lock_conn, Database};
+use crate::error::AppError;
+use crate::proxy::usage::calculator::{CostCalculator, ModelPricing, UsageDialect};
+use crate::proxy::usage::parser::TokenUsage;
+use crate::services::pricing::PricingLookup;
+
+use super::convert::is_injected;
+use super::search::fingerprint;
+use super::{find_session, load_messages, scan_sessions, ContentPart, MessageUsage};
+use super::{SessionMessage, SessionMeta};
+
+/// Bump when the computed stats change shape or meaning, so cached rows are rebuilt.
+pub(crate) const STATS_VERSION: u32 = 2;
+
+/// Gaps between messages longer than this count as idle, not active time.
+const ACTIVE_GAP_MS: i64 = 5 * 60_000;
+
+/// Sessions listed per project in [`ProjectAnalytics::top_sessions`].
+const TOP_SESSIONS: usize = 5;
+
+/// Tool input keys that name a file.
+const PATH_KEYS: &[&str] = &[
+    "file_path",
+    "filePath",
+    "absolute_path",
+    "notebook_path",
+    "path",
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ToolCallStats {
+    pub name: String,
+    pub calls: u32,
+    pub errors: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FileTouch {
+    pub path: String,
+    pub reads: u32,
+    pub writes: u32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ModelUsage {
+    pub model: String,
+    pub usage: MessageUsage,
+    /// Usage of each message, priced one by one so long-context tiers apply
+    /// per request rather than to the turn total.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub messages: Vec<MessageUsage>,
+}
+
+/// One user prompt and everything the agent did until the next prompt.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TurnStats {
+    pub index: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<i64>,
+    /// First line of the prompt, shortened.
+    pub prompt: String,
+    pub tool_calls: u32,
+    pub errors: u32,
+    pub usage: MessageUsage,
+    /// Usage split by model, which is what gets priced.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub models: Vec<ModelUsage>,
+    /// Unset when none of the models has a price.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionStats {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<i64>,
+    pub wall_time_ms: i64,
+    pub active_time_ms: i64,
+    pub user_turns: u32,
+    /// Model responses; consecutive assistant messages count once.
+    pub assistant_turns: u32,
+    /// Most used first.
+    pub tool_calls: Vec<ToolCallStats>,
+    pub files_touched: Vec<FileTouch>,
+    pub usage: MessageUsage,
+    /// Tool calls whose result was flagged as an error.
+    pub errors: u32,
+    pub turns: Vec<TurnStats>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionAnalytics {
+    pub provider_id: String,
+    pub session_id: String,
+    pub source_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_session_id: Option<String>,
+    #[serde(flatten)]
+    pub stats: SessionStats,
+    pub total_cost_usd: String,
+    /// Models without pricing; their tokens are not in the cost.
+    pub unpriced_models: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProjectAnalyticsQuery {
+    pub provider_id: Option<String>,
+    /// Only sessions last active in this range.
+    pub start_ms: Option<i64>,
+    pub end_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectAnalytics {
+    /// Unset for sessions without a known working directory.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_dir: Option<String>,
+    /// Sub-agent sessions are included in the totals but not counted here.
+    pub session_count: u32,
+    pub wall_time_ms: i64,
+    pub active_time_ms: i64,
+    pub user_turns: u32,
+    pub assistant_turns: u32,
+    pub tool_calls: Vec<ToolCallStats>,
+    pub files_touched: u32,
+    pub usage: MessageUsage,
+    pub errors: u32,
+    pub total_cost_usd: String,
+    pub unpriced_models: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_active_at: Option<i64>,
+    /// Most expensive sessions first, at most [`TOP_SESSIONS`].
+    pub top_sessions: Vec<SessionCostEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionCostEntry {
+    pub provider_id: String,
+    pub session_id: String,
+    pub source_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    pub wall_time_ms: i64,
+    pub total_cost_usd: String,
+}
+
+/// Stats of one session, read from the cache when the file is unchanged.
+pub fn session_analytics(
+    db: &Database,
+    provider_id: &str,
+    source_path: &str,
+) -> Result<SessionAnalytics, AppError> {
+    let meta = find_session(provider_id, source_path)
+        .ok_or_else(|| AppError::Message(format!("Session not found: {source_path}")))?;
+    let stats = cached_stats(db, &meta, source_path, None)?;
+    let mut sessions = [with_meta(meta, source_path, stats)];
+    apply_costs(db, &mut sessions)?;
+    let [session] = sessions;
+    Ok(session)
+}
+
+/// Totals per project directory, most expensive first.
+pub fn project_analytics(
+    db: &Database,
+    query: &ProjectAnalyticsQuery,
+) -> Result<Vec<ProjectAnalytics>, AppError> {
+    let mut cached = db.get_session_stats_cache(STATS_VERSION)?;
+    let mut seen = HashSet::new();
+    let mut sessions = Vec::new();
+
+    for meta in scan_sessions() {
+        let Some(source_path) = meta.source_path.clone() else {
+            continue;
+        };
+        if !seen.insert(source_path.clone()) {
+            continue;
+        }
+        let last_active = meta.last_active_at.or(meta.created_at);
+        let in_range = query
+            .provider_id
+            .as_deref()
+            .is_none_or(|id| id == meta.provider_id)
+            && query
+                .start_ms
+                .is_none_or(|start| last_active.is_some_and(|ts| ts >= start))
+            && query
+                .end_ms
+                .is_none_or(|end| last_active.is_some_and(|ts| ts <= end));
+        if !in_range {
+            cached.remove(&source_path);
+            continue;
+        }
+        let stats = match cached_stats(db, &meta, &source_path, cached.remove(&source_path)) {
+            Ok(stats) => stats,
+            Err(e) => {
+                log::debug!("Skipping session {source_path} in analytics: {e}");
+                continue;
+            }
+        };
+        sessions.push(with_meta(meta, &source_path, stats));
+    }
+
+    // Whatever is left in the cache no longer exists on disk.
+    let stale: Vec<String> = cached.into_keys().collect();
+    db.remove_session_stats(&stale)?;
+
+    apply_costs(db, &mut sessions)?;
+    Ok(aggregate_projects(sessions))
+}
+
+/// Compute the stats of a parsed transcript.
+pub fn compute_stats(messages: &[SessionMessage]) -> SessionStats {
+    let mut stats = SessionStats::default();
+    let mut tools: HashMap<String, ToolCallStats> = HashMap::new();
+    let mut files: BTreeMap<String, FileTouch> = BTreeMap::new();
+    let mut call_names: HashMap<String, String> = HashMap::new();
+    let mut last_ts: Option<i64> = None;
+    let mut previous_role = "";
+    let mut current: Option<TurnStats> = None;
+
+    for message in messages {
+        if let Some(ts) = message.ts {
+            stats.started_at = Some(stats.started_at.map_or(ts, |s| s.min(ts)));
+            stats.ended_at = Some(stats.ended_at.map_or(ts, |e| e.max(ts)));
+            if let Some(last) = last_ts {
+                let gap = ts - last;
+                if (0..=ACTIVE_GAP_MS).contains(&gap) {
+                    stats.active_time_ms += gap;
+                }
+            }
+            last_ts = Some(ts);
+        }
+
+        let prompt = (message.role == "user" && !message.is_sidechain)
+            .then(|| prompt_text(message))
+            .flatten();
+        if let Some(prompt) = prompt {
+            stats.turns.extend(current.take().filter(has_activity));
+            stats.user_turns += 1;
+            current = Some(TurnStats {
+                index: stats.user_turns,
+                started_at: message.ts,
+                ended_at: message.ts,
+                prompt,
+                ..Default::default()
+            });
+        }
+        let turn = current.get_or_insert_with(|| TurnStats {
+            started_at: message.ts,
+            ..Default::default()
+        });
+        if message.ts.is_some() {
+            turn.ended_at = message.ts;
+        }
+
+        if message.role == "assistant" {
+            if previous_role != "assistant" && !message.is_sidechain {
+                stats.assistant_turns += 1;
+            }
+            if let Some(usage) = &message.usage {
+                add_usage(&mut stats.usage, usage);
+                add_usage(&mut turn.usage, usage);
+                let model = message.model.as_deref().unwrap_or("unknown");
+                match turn.models.iter_mut().find(|m| m.model == model) {
+                    Some(entry) => {
+                        add_usage(&mut entry.usage, usage);
+                        entry.messages.push(usage.clone());
+                    }
+                    None => turn.models.push(ModelUsage {
+                        model: model.to_string(),
+                        usage: usage.clone(),
+                        messages: vec![usage.clone()],
+                    }),
+                }
+            }
+        }
+        if !message.is_sidechain {
+            previous_role = message.role.as_str();
+        }
+
+        for part in &message.parts {
+            match part {
+                ContentPart::ToolUse {
+                    id, name, input, ..
+                } => {
+                    tools
+                        .entry(name.clone())
+                        .or_insert_with(|| ToolCallStats {
+                            name: name.clone(),
+                            ..Default::default()
+                        })
+                        .calls += 1;
+                    turn.tool_calls += 1;
+                    if let Some(id) = id {
+                        call_names.insert(id.clone(), name.clone());
+                    }
+                    let writes = is_write_tool(name);
+                    for path in touched_paths(input) {
+                        let entry = files.entry(path.clone()).or_insert_with(|| FileTouch {
+                            path,
+                            ..Default::default()
+                        });
+                        if writes {
+                            entry.writes += 1;
+                        } else {
+                            entry.reads += 1;
+                        }
+                    }
+                }
+                ContentPart::ToolResult {
+                    tool_use_id,
+                    name,
+                    is_error: true,
+                    ..
+                } => {
+                    stats.errors += 1;
+                    turn.errors += 1;
+                    let name = name.clone().or_else(|| {
+                        tool_use_id
+                            .as_ref()
+                            .and_then(|id| call_names.get(id))
+                            .cloned()
+                    });
+                    if let Some(tool) = name.and_then(|name| tools.get_mut(&name)) {
+                        tool.errors += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    stats.turns.extend(current.filter(has_activity));
+
+    if let (Some(start), Some(end)) = (stats.started_at, stats.ended_at) {
+        stats.wall_time_ms = end - start;
+    }
+    stats.tool_calls = sorted_tools(tools.into_values());
+    stats.files_touched = files.into_values().collect();
+    stats
+}
+
+/// Keeps the turn before the first prompt only if the agent did something in it.
+fn has_activity(turn: &TurnStats) -> bool {
+    turn.index > 0 || turn.tool_calls > 0 || !turn.usage.is_empty()
+}
+
+/// The prompt a user typed, or `None` for tool results and injected context.
+fn prompt_text(message: &SessionMessage) -> Option<String> {
+    let text = message.parts.iter().find_map(|part| match part {
+        ContentPart::Text { text } if !is_injected(text) => Some(text.as_str()),
+        _ => None,
+    })?;
+    let line = text.trim().lines().next().unwrap_or_default();
+    Some(match line.char_indices().nth(80) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line.to_string(),
+    })
+}
+
+fn is_write_tool(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    [
+        "write", "edit", "replace", "patch", "create", "delete", "move",
+    ]
+    .iter()
+    .any(|verb| name.contains(verb))
+}
+
+/// Files named by a tool call, including those in an `apply_patch` body.
+fn touched_paths(input: &Value) -> Vec<String> {
+    let mut paths = Vec::new();
+    match input {
+        Value::String(text) => paths.extend(patch_paths(text)),
+        Value::Object(map) => {
+            for key in PATH_KEYS {
+                if let Some(path) = map.get(*key).and_then(Value::as_str) {
+                    if !path.trim().is_empty() {
+                        paths.push(path.to_string());
+                    }
+                }
+            }
+            for value in map.values() {
+                match value {
+                    Value::String(text) => paths.extend(patch_paths(text)),
+                    Value::Array(items) => items
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .for_each(|text| paths.extend(patch_paths(text))),
+                    _ => {}
+                }
+            }
+        }
+        _ => {}
+    }
+    paths
+}
+
+fn patch_paths(text: &str) -> Vec<String> {
+    if !text.contains("*** Begin Patch") {
+        return Vec::new();
+    }
+    text.lines()
+        .filter_map(|line| {
+            [
+                "*** Add File: ",
+                "*** Update File: ",
+                "*** Delete File: ",
+                "*** Move to: ",
+            ]
+            .iter()
+            .find_map(|prefix| line.strip_prefix(prefix))
+        })
+        .map(|path| path.trim().to_string())
+        .collect()
+}
+
+fn add_usage(total: &mut MessageUsage, usage: &MessageUsage) {
+    total.input_tokens += usage.input_tokens;
+    total.output_tokens += usage.output_tokens;
+    total.cache_read_tokens += usage.cache_read_tokens;
+    total.cache_creation_tokens += usage.cache_creation_tokens;
+    total.reasoning_tokens += usage.reasoning_tokens;
+}
+
+fn sorted_tools(tools: impl IntoIterator<Item = ToolCallStats>) -> Vec<ToolCallStats> {
+    let mut tools: Vec<_> = tools.into_iter().collect();
+    tools.sort_by(|a, b| b.calls.cmp(&a.calls).then_with(|| a.name.cmp(&b.name)));
+    tools
+}
+
+/// Reuse `cached` (or the stored row) when the file is unchanged, otherwise reparse.
+fn cached_stats(
+    db: &Database,
+    meta: &SessionMeta,
+    source_path: &str,
+    cached: Option<(i64, i64, SessionStats)>,
+) -> Result<SessionStats, AppError> {
+    let (file_size, modified_at) = fingerprint(Path::new(source_path))
+        .ok_or_else(|| AppError::Message(format!("Session file not readable: {source_path}")))?;
+    let cached = match cached {
+        Some(cached) => Some(cached),
+        None => db.get_session_stats(source_path, STATS_VERSION)?,
+    };
+    if let Some((size, modified, stats)) = cached {
+        if (size, modified) == (file_size, modified_at) {
+            return Ok(stats);
+        }
+    }
+
+    let messages = load_messages(&meta.provider_id, source_path).map_err(AppError::Message)?;
+    let stats = compute_stats(&messages);
+    db.save_session_stats(
+        &meta.provider_id,
+        source_path,
+        file_size,
+        modified_at,
+        STATS_VERSION,
+        &stats,
+    )?;
+    Ok(stats)
+}
+
+fn with_meta(meta: SessionMeta, source_path: &str, stats: SessionStats) -> SessionAnalytics {
+    SessionAnalytics {
+        provider_id: meta.provider_id,
+        session_id: meta.session_id,
+        source_path: source_path.to_string(),
+        title: meta.title,
+        project_dir: meta.project_dir,
+        parent_session_id: meta.parent_session_id,
+        stats,
+        total_cost_usd: String::new(),
+        unpriced_models: Vec::new(),
+    }
+}
+
+/// Price every turn at the official rate in effect when the turn started.
+fn apply_costs(db: &Database, sessions: &mut [SessionAnalytics]) -> Result<(), AppError> {
+    let now = chrono::Utc::now().timestamp();
+    let turn_time = |turn: &TurnStats| turn.started_at.map_or(now, |ms| ms / 1000);
+
+    // Resolve every price up front so the connection is not held while pricing
+    let mut prices: HashMap<(String, i64), Option<ModelPricing>> = HashMap::new();
+    {
+        let conn = lock_conn!(db.conn);
+        let mut lookup = PricingLookup::default();
+        for turn in sessions.iter().flat_map(|s| &s.stats.turns) {
+            let at = turn_time(turn);
+            for entry in &turn.models {
+                if let Entry::Vacant(slot) = prices.entry((entry.model.clone(), at)) {
+                    slot.insert(lookup.pricing_at(&conn, &entry.model, at)?);
+                }
+            }
+        }
+    }
+
+    for session in sessions.iter_mut() {
+        let mut total = Decimal::ZERO;
+        let mut unpriced = HashSet::new();
+        for turn in &mut session.stats.turns {
+            let at = turn_time(turn);
+            let mut cost: Option<Decimal> = None;
+            for entry in &turn.models {
+                let Some(Some(pricing)) = prices.get(&(entry.model.clone(), at)) else {
+                    unpriced.insert(entry.model.clone());
+                    continue;
+                };
+                *cost.get_or_insert(Decimal::ZERO) += model_cost(entry, pricing);
+            }
+            if let Some(cost) = cost {
+                total += cost;
+                turn.cost_usd = Some(format!("{cost:.6}"));
+            }
+        }
+        session.total_cost_usd = format!("{total:.6}");
+        session.unpriced_models = unpriced.into_iter().collect();
+        session.unpriced_models.sort();
+    }
+    Ok(())
+}
+
+/// Each message is its own request, so each gets its own long-context tier.
+fn model_cost(entry: &ModelUsage, pricing: &ModelPricing) -> Decimal {
+    entry
+        .messages
+        .iter()
+        .map(|usage| {
+            CostCalculator::calculate(
+                &token_usage(usage),
+                pricing,
+                Decimal::ONE,
+                UsageDialect::OpenAi,
+            )
+            .total_cost
+        })
+        .sum()
+}
+
+/// The calculator expects input tokens to include cache hits.
+fn token_usage(usage: &MessageUsage) -> TokenUsage {
+    let clamp = |tokens: u64| u32::try_from(tokens).unwrap_or(u32::MAX);
+    TokenUsage {
+        input_tokens: clamp(usage.input_tokens + usage.cache_read_tokens),
+        output_tokens: clamp(usage.output_tokens),
+        cache_read_tokens: clamp(usage.cache_read_tokens),
+        cache_creation_tokens: clamp(usage.cache_creation_tokens),
+        cache_creation_1h_tokens: 0,
+        model: None,
+    }
+}
+
+fn aggregate_projects(sessions: Vec<SessionAnalytics>) -> Vec<ProjectAnalytics> {
+    struct Acc {
+        project: ProjectAnalytics,
+        tools: HashMap<String, ToolCallStats>,
+        files: HashSet<String>,
+        cost: Decimal,
+        unpriced: HashSet<String>,
+        sessions: Vec<(Decimal, SessionCostEntry)>,
+    }
+
+    let mut groups: HashMap<Option<String>, Acc> = HashMap::new();
+    for session in sessions {
+        let acc = groups
+            .entry(session.project_dir.clone())
+            .or_insert_with(|| Acc {
+                project: ProjectAnalytics {
+                    project_dir: session.project_dir.clone(),
+                    ..Default::default()
+                },
+                tools: HashMap::new(),
+                files: HashSet::new(),
+                cost: Decimal::ZERO,
+                unpriced: HashSet::new(),
+                sessions: Vec::new(),
+            });
+        let stats = &session.stats;
+        let project = &mut acc.project;
+        if session.parent_session_id.is_none() {
+            project.session_count += 1;
+        }
+        project.wall_time_ms += stats.wall_time_ms;
+        project.active_time_ms += stats.active_time_ms;
+        project.user_turns += stats.user_turns;
+        project.assistant_turns += stats.assistant_turns;
+        project.errors += stats.errors;
+        add_usage(&mut project.usage, &stats.usage);
+        project.last_active_at = project.last_active_at.max(stats.ended_at);
+        for tool in &stats.tool_calls {
+            let entry = acc
+                .tools
+                .entry(tool.name.clone())
+                .or_insert_with(|| ToolCallStats {
+                    name: tool.name.clone(),
+                    ..Default::default()
+                });
+            entry.calls += tool.calls;
+            entry.errors += tool.errors;
+        }
+        acc.files
+            .extend(stats.files_touched.iter().map(|file| file.path.clone()));
+        acc.unpriced.extend(session.unpriced_models.iter().cloned());
+
+        let cost: Decimal = session.total_cost_usd.parse().unwrap_or(Decimal::ZERO);
+        acc.cost += cost;
+        acc.sessions.push((
+            cost,
+            SessionCostEntry {
+                provider_id: session.provider_id,
+                session_id: session.session_id,
+                source_path: session.source_path,
+                title: session.title,
+                wall_time_ms: stats.wall_time_ms,
+                total_cost_usd: session.total_cost_usd,
+            },
+        ));
+    }
+
+    let mut projects: Vec<(Decimal, ProjectAnalytics)> = groups
+        .into_values()
+        .map(|mut acc| {
+            acc.sessions.sort_by(|a, b| b.0.cmp(&a.0));
+            let mut project = acc.project;
+            project.tool_calls = sorted_tools(acc.tools.into_values());
+            project.files_touched = acc.files.len() as u32;
+            project.total_cost_usd = format!("{:.6}", acc.cost);
+            project.unpriced_models = acc.unpriced.into_iter().collect();
+            project.unpriced_models.sort();
+            project.top_sessions = acc
+                .sessions
+                .into_iter()
+                .take(TOP_SESSIONS)
+                .map(|(_, entry)| entry)
+                .collect();
+            (acc.cost, project)
+        })
+        .collect();
+    projects.sort_by(|a, b| {
+        b.0.cmp(&a.0)
+            .then_with(|| b.1.last_active_at.cmp(&a.1.last_active_at))
+    });
+    projects.into_iter().map(|(_, project)| project).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::usage::calculator::PricingTier;
+    use serde_json::json;
+
+    fn message(role: &str, ts: i64, parts: Vec<ContentPart>) -> SessionMessage {
+        SessionMessage {
+            role: role.to_string(),
+            ts: Some(ts),
+            parts,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compute_stats() {
+        let usage = MessageUsage {
+            input_tokens: 100,
+            output_tokens: 20,
+            cache_read_tokens: 1000,
+            ..Default::default()
+        };
+        let patch = "*** Begin Patch\n*** Update File: src/lib.rs\n@@\n-a\n+b\n*** End Patch";
+        let messages = vec![
+            message(
+                "user",
+                0,
+                vec![ContentPart::text(
+                    "<environment_context>cwd</environment_context>",
+                )],
+            ),
+            message(
+                "user",
+                1_000,
+                vec![ContentPart::text("fix the build\nplease")],
+            ),
+            SessionMessage {
+                model: Some("claude-sonnet-4".to_string()),
+                usage: Some(usage.clone()),
+                ..message(
+                    "assistant",
+                    2_000,
+                    vec![
+                        ContentPart::text("Looking"),
+                        ContentPart::tool_use(
+                            Some("t1".to_string()),
+                            "Read",
+                            json!({"file_path": "/w/src/lib.rs"}),
+                        ),
+                    ],
+                )
+            },
+            message(
+                "user",
+                3_000,
+                vec![ContentPart::tool_result(
+                    Some("t1".to_string()),
+                    None,
+                    "nope",
+                    true,
+                )],
+            ),
+            SessionMessage {
+                model: Some("claude-sonnet-4".to_string()),
+                usage: Some(usage),
+                ..message(
+                    "assistant",
+                    4_000,
+                    vec![ContentPart::tool_use(
+                        Some("t2".to_string()),
+                        "apply_patch",
+                        json!({"input": patch}),
+                    )],
+                )
+            },
+            // Ten idle minutes do not count as active time
+            message("user", 604_000, vec![ContentPart::text("thanks")]),
+            message("assistant", 605_000, vec![ContentPart::text("Done.")]),
+        ];
+
+        let stats = compute_stats(&messages);
+        assert_eq!(stats.wall_time_ms, 605_000);
+        assert_eq!(stats.active_time_ms, 5_000);
+        assert_eq!(stats.user_turns, 2);
+        assert_eq!(stats.assistant_turns, 3);
+        assert_eq!(stats.errors, 1);
+        assert_eq!(stats.usage.input_tokens, 200);
+        assert_eq!(
+            stats.tool_calls,
+            vec![
+                ToolCallStats {
+                    name: "Read".to_string(),
+                    calls: 1,
+                    errors: 1
+                },
+                ToolCallStats {
+                    name: "apply_patch".to_string(),
+                    calls: 1,
+                    errors: 0
+                },
+            ]
+        );
+        assert_eq!(
+            stats.files_touched,
+            vec![
+                FileTouch {
+                    path: "/w/src/lib.rs".to_string(),
+                    reads: 1,
+                    writes: 0
+                },
+                FileTouch {
+                    path: "src/lib.rs".to_string(),
+                    reads: 0,
+                    writes: 1
+                },
+            ]
+        );
+
+        // Injected context before the first prompt is not a turn
+        assert_eq!(stats.turns.len(), 2);
+        let first = &stats.turns[0];
+        assert_eq!(first.prompt, "fix the build");
+        assert_eq!(first.tool_calls, 2);
+        assert_eq!(first.models.len(), 1);
+        assert_eq!(first.models[0].usage.output_tokens, 40);
+        assert_eq!(stats.turns[1].usage, MessageUsage::default());
+    }
+
+    #[test]
+    fn test_model_cost_prices_each_message_in_its_own_tier() {
+        let flat = |input: i64, output: i64| ModelPricing {
+            input_cost_per_million: Decimal::from(input),
+            output_cost_per_million: Decimal::from(output),
+            cache_read_cost_per_million: Decimal::ZERO,
+            cache_creation_cost_per_million: Decimal::ZERO,
+            cache_creation_1h_cost_per_million: None,
+            tiers: Vec::new(),
+        };
+        let pricing = ModelPricing {
+            tiers: vec![PricingTier {
+                above_input_tokens: 200_000,
+                pricing: flat(6, 0),
+            }],
+            ..flat(3, 0)
+        };
+        let message = MessageUsage {
+            input_tokens: 150_000,
+            ..Default::default()
+        };
+        let entry = ModelUsage {
+            model: "claude-sonnet-4".to_string(),
+            usage: MessageUsage {
+                input_tokens: 300_000,
+                ..Default::default()
+            },
+            messages: vec![message.clone(), message],
+        };
+
+        // Two 150k requests stay in the base tier; their 300k sum would not
+        assert_eq!(model_cost(&entry, &pricing), Decimal::new(9, 1));
+    }
+
+    #[test]
+    fn test_token_usage_includes_cache_hits_in_input() {
+        let usage = token_usage(&MessageUsage {
+            input_tokens: 10,
+            output_tokens: 5,
+            cache_read_tokens: 90,
+            cache_creation_tokens: 3,
+            reasoning_tokens: 2,
+        });
+        assert_eq!(usage.input_tokens, 100);
+        assert_eq!(usage.cache_read_tokens, 90);
+        assert_eq!(usage.output_tokens, 5);
+    }
+}
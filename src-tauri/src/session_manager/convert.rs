@@ -0,0 +1,841 @@
+//! Convert a session into another CLI's native format so it can be resumed there.
+//!
+//! Any of the five readable formats can be converted; the targets are Claude Code
+//! transcripts, Codex rollouts and Gemini CLI checkpoints. Thinking blocks are
+//! dropped (they cannot be replayed to another vendor) and tool calls are kept
+//! as native call/result pairs where both halves are present, otherwise as text.
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+
+use super::message::value_to_text;
+use super::{find_session, load_messages, ContentPart, SessionMessage};
+
+/// Reported as the producing CLI version in written transcripts.
+const CONVERTER_VERSION: &str = "cc-switch";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ConvertTarget {
+    Claude,
+    Codex,
+    Gemini,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvertRequest {
+    pub provider_id: String,
+    pub source_path: String,
+    pub target: ConvertTarget,
+    /// Overrides the session's project directory.
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Write every tool call as plain text instead of native tool blocks.
+    #[serde(default)]
+    pub tool_calls_as_text: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConvertResult {
+    pub target: ConvertTarget,
+    pub session_id: String,
+    pub path: String,
+    pub cwd: String,
+    pub resume_command: String,
+    /// Typed inside the CLI once it is running (Gemini checkpoints).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub follow_up: Option<String>,
+    pub message_count: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Speaker {
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Item {
+    Text(String),
+    Call {
+        id: String,
+        name: String,
+        input: Value,
+    },
+    Result {
+        id: String,
+        name: String,
+        output: String,
+        is_error: bool,
+    },
+}
+
+/// Consecutive content from one side of the conversation.
+#[derive(Debug, Clone)]
+struct Turn {
+    speaker: Speaker,
+    items: Vec<Item>,
+    ts: Option<i64>,
+    model: Option<String>,
+}
+
+/// Convert a session and write it where the target CLI looks for it.
+pub fn convert_session(request: &ConvertRequest) -> Result<ConvertResult, String> {
+    let messages = load_messages(&request.provider_id, &request.source_path)?;
+    let meta = find_session(&request.provider_id, &request.source_path);
+    let cwd = request
+        .cwd
+        .clone()
+        .filter(|dir| !dir.trim().is_empty())
+        .or_else(|| meta.and_then(|m| m.project_dir))
+        .unwrap_or_else(|| crate::config::get_home_dir().to_string_lossy().to_string());
+
+    let turns = build_turns(&messages, request.target, request.tool_calls_as_text);
+    if turns.is_empty() {
+        return Err("Session has no messages to convert".to_string());
+    }
+
+    let id = uuid::Uuid::new_v4().to_string();
+    let (path, session_id, resume_command, follow_up) = match request.target {
+        ConvertTarget::Claude => {
+            let path = write_claude(&crate::config::get_claude_config_dir(), &cwd, &id, &turns)?;
+            let command = format!("claude --resume {id}");
+            (path, id, command, None)
+        }
+        ConvertTarget::Codex => {
+            let root = crate::codex_config::get_codex_config_dir();
+            let path = write_codex(&root, &cwd, &id, Local::now(), &turns)?;
+            let command = format!("codex resume {id}");
+            (path, id, command, None)
+        }
+        ConvertTarget::Gemini => {
+            let tag = format!("cc-switch-{}", &id[..8]);
+            let path = write_gemini(&crate::gemini_config::get_gemini_dir(), &cwd, &tag, &turns)?;
+            let follow_up = format!("/chat resume {tag}");
+            (path, tag, "gemini".to_string(), Some(follow_up))
+        }
+    };
+
+    Ok(ConvertResult {
+        target: request.target,
+        session_id,
+        path: path.to_string_lossy().to_string(),
+        cwd,
+        resume_command,
+        follow_up,
+        message_count: turns.len(),
+    })
+}
+
+// ---------------------------------------------------------------------------
+// Normalization
+// ---------------------------------------------------------------------------
+
+fn build_turns(messages: &[SessionMessage], target: ConvertTarget, as_text: bool) -> Vec<Turn> {
+    let mut turns: Vec<Turn> = Vec::new();
+    let mut generated = 0usize;
+    // Calls not yet answered, for results that carry no call id
+    let mut pending: Vec<(String, String)> = Vec::new();
+
+    for message in messages {
+        let speaker = match message.role.as_str() {
+            "user" | "tool" => Speaker::User,
+            "assistant" | "model" | "gemini" => Speaker::Assistant,
+            _ => continue,
+        };
+
+        let mut items: Vec<(Speaker, Item)> = Vec::new();
+        if message.parts.is_empty() && !message.content.trim().is_empty() {
+            items.push((speaker, Item::Text(message.content.clone())));
+        }
+        for part in &message.parts {
+            match part {
+                ContentPart::Text { text } if !text.trim().is_empty() && !is_injected(text) => {
+                    items.push((speaker, Item::Text(text.clone())))
+                }
+                ContentPart::ToolUse {
+                    id, name, input, ..
+                } => {
+                    let id = id.as_deref().map(sanitize_id).unwrap_or_else(|| {
+                        generated += 1;
+                        format!("call_{generated}")
+                    });
+                    pending.push((id.clone(), name.clone()));
+                    let (name, input) = map_tool(name, input, target);
+                    items.push((speaker, Item::Call { id, name, input }));
+                }
+                ContentPart::ToolResult {
+                    tool_use_id,
+                    name,
+                    content,
+                    is_error,
+                    ..
+                } => {
+                    let id = match tool_use_id.as_deref() {
+                        Some(id) => sanitize_id(id),
+                        None => pending
+                            .iter()
+                            .find(|(_, call)| name.as_deref().is_none_or(|n| n == call))
+                            .map(|(id, _)| id.clone())
+                            .unwrap_or_default(),
+                    };
+                    let call_name = pending
+                        .iter()
+                        .position(|(call_id, _)| *call_id == id)
+                        .map(|index| pending.remove(index).1);
+                    let name = name.clone().or(call_name).unwrap_or_default();
+                    let (name, _) = map_tool(&name, &Value::Null, target);
+                    // Results always answer from the user side, even when the
+                    // source (Gemini) stores them on the assistant message
+                    items.push((
+                        Speaker::User,
+                        Item::Result {
+                            id,
+                            name,
+                            output: content.clone(),
+                            is_error: *is_error,
+                        },
+                    ));
+                }
+                ContentPart::Image { .. } => {
+                    items.push((speaker, Item::Text("[image]".to_string())))
+                }
+                _ => {}
+            }
+        }
+
+        for (speaker, item) in items {
+            match turns.last_mut() {
+                Some(turn) if turn.speaker == speaker => turn.items.push(item),
+                _ => turns.push(Turn {
+                    speaker,
+                    items: vec![item],
+                    ts: message.ts,
+                    model: None,
+                }),
+            }
+            if speaker == Speaker::Assistant {
+                if let Some(turn) = turns.last_mut() {
+                    turn.model = turn.model.take().or_else(|| message.model.clone());
+                }
+            }
+        }
+    }
+
+    pair_tool_calls(&mut turns, as_text);
+
+    // Every target expects the conversation to open with the user
+    if turns
+        .first()
+        .is_some_and(|turn| turn.speaker == Speaker::Assistant)
+    {
+        turns.insert(
+            0,
+            Turn {
+                speaker: Speaker::User,
+                items: vec![Item::Text("(continued conversation)".to_string())],
+                ts: turns[0].ts,
+                model: None,
+            },
+        );
+    }
+    turns
+}
+
+/// Keep a call native only when the very next user turn answers it, and a
+/// result only when it answers a call from the turn before. Everything else
+/// becomes text, which every target accepts anywhere.
+fn pair_tool_calls(turns: &mut [Turn], as_text: bool) {
+    for index in 0..turns.len() {
+        if turns[index].speaker != Speaker::Assistant {
+            continue;
+        }
+        let answered: HashSet<String> = match turns.get(index + 1) {
+            Some(next) if !as_text && next.speaker == Speaker::User => next
+                .items
+                .iter()
+                .filter_map(|item| match item {
+                    Item::Result { id, .. } => Some(id.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => HashSet::new(),
+        };
+        for item in &mut turns[index].items {
+            if matches!(item, Item::Call { id, .. } if !answered.contains(id)) {
+                *item = item_as_text(item);
+            }
+        }
+    }
+
+    for index in 0..turns.len() {
+        if turns[index].speaker != Speaker::User {
+            continue;
+        }
+        let calls: HashSet<String> = match index.checked_sub(1).map(|i| &turns[i]) {
+            Some(prev) if prev.speaker == Speaker::Assistant => prev
+                .items
+                .iter()
+                .filter_map(|item| match item {
+                    Item::Call { id, .. } => Some(id.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => HashSet::new(),
+        };
+        let mut seen = HashSet::new();
+        for item in &mut turns[index].items {
+            let keep = matches!(item, Item::Result { id, .. } if calls.contains(id) && seen.insert(id.clone()));
+            if matches!(item, Item::Result { .. }) && !keep {
+                *item = item_as_text(item);
+            }
+        }
+        // Tool results must come before any text in the answering turn
+        turns[index]
+            .items
+            .sort_by_key(|item| !matches!(item, Item::Result { .. }));
+    }
+}
+
+fn item_as_text(item: &Item) -> Item {
+    match item {
+        Item::Call { name, input, .. } => {
+            let input = match input {
+                Value::Object(map) if map.is_empty() => String::new(),
+                other => format!("\n{}", value_to_text(other)),
+            };
+            Item::Text(format!("[Tool call: {name}]{input}"))
+        }
+        Item::Result {
+            name,
+            output,
+            is_error,
+            ..
+        } => {
+            let label = if *is_error {
+                "Tool error"
+            } else {
+                "Tool result"
+            };
+            Item::Text(format!("[{label}: {name}]\n{output}"))
+        }
+        Item::Text(text) => Item::Text(text.clone()),
+    }
+}
+
+/// Context blocks a CLI injects into the transcript itself; the target CLI
+/// adds its own.
+pub(super) fn is_injected(text: &str) -> bool {
+    let text = text.trim_start();
+    ["<environment_context>", "<user_instructions>"]
+        .iter()
+        .any(|tag| text.starts_with(tag))
+}
+
+/// Claude only accepts `[A-Za-z0-9_-]` in tool ids.
+fn sanitize_id(id: &str) -> String {
+    id.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Shell commands exist in every CLI under different names and shapes.
+fn shell_command(name: &str, input: &Value) -> Option<String> {
+    if !matches!(
+        name,
+        "Bash" | "bash" | "shell" | "local_shell" | "exec_command" | "run_shell_command"
+    ) {
+        return None;
+    }
+    match input.get("command").or_else(|| input.get("cmd"))? {
+        Value::String(command) => Some(command.clone()),
+        Value::Array(argv) => {
+            let argv: Vec<&str> = argv.iter().filter_map(Value::as_str).collect();
+            match argv.as_slice() {
+                [shell, flag, script]
+                    if matches!(*shell, "bash" | "sh" | "zsh") && matches!(*flag, "-lc" | "-c") =>
+                {
+                    Some(script.to_string())
+                }
+                _ => Some(argv.join(" ")),
+            }
+        }
+        _ => None,
+    }
+}
+
+fn map_tool(name: &str, input: &Value, target: ConvertTarget) -> (String, Value) {
+    let is_shell = shell_command(name, &json!({"command": ""})).is_some();
+    if !is_shell {
+        return (name.to_string(), input.clone());
+    }
+    let command = shell_command(name, input).unwrap_or_default();
+    match target {
+        ConvertTarget::Claude => ("Bash".to_string(), json!({ "command": command })),
+        ConvertTarget::Codex => (
+            "shell".to_string(),
+            json!({ "command": ["bash", "-lc", command] }),
+        ),
+        ConvertTarget::Gemini => (
+            "run_shell_command".to_string(),
+            json!({ "command": command }),
+        ),
+    }
+}
+
+/// Tool inputs must be JSON objects for Claude and Gemini.
+fn object_input(input: &Value) -> Value {
+    match input {
+        Value::Object(_) => input.clone(),
+        Value::Null => json!({}),
+        other => json!({ "input": other }),
+    }
+}
+
+fn iso_timestamp(ts: Option<i64>) -> String {
+    ts.and_then(|ms| Utc.timestamp_millis_opt(ms).single())
+        .unwrap_or_else(Utc::now)
+        .format("%Y-%m-%dT%H:%M:%S%.3fZ")
+        .to_string()
+}
+
+fn write_file(path: &Path, content: &str) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create {}: {e}", parent.display()))?;
+    }
+    std::fs::write(path, content).map_err(|e| format!("Failed to write {}: {e}", path.display()))
+}
+
+// ---------------------------------------------------------------------------
+// Writers
+// ---------------------------------------------------------------------------
+
+/// Claude Code keys project folders by the cwd with every non-alphanumeric replaced.
+fn claude_project_dir_name(cwd: &str) -> String {
+    cwd.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+fn write_claude(
+    root: &Path,
+    cwd: &str,
+    session_id: &str,
+    turns: &[Turn],
+) -> Result<PathBuf, String> {
+    let path = root
+        .join("projects")
+        .join(claude_project_dir_name(cwd))
+        .join(format!("{session_id}.jsonl"));
+
+    let mut lines = Vec::with_capacity(turns.len());
+    let mut parent: Option<String> = None;
+    for turn in turns {
+        let uuid = uuid::Uuid::new_v4().to_string();
+        let content: Vec<Value> = turn
+            .items
+            .iter()
+            .map(|item| match item {
+                Item::Text(text) => json!({ "type": "text", "text": text }),
+                Item::Call { id, name, input } => json!({
+                    "type": "tool_use",
+                    "id": id,
+                    "name": name,
+                    "input": object_input(input),
+                }),
+                Item::Result {
+                    id,
+                    output,
+                    is_error,
+                    ..
+                } => json!({
+                    "type": "tool_result",
+                    "tool_use_id": id,
+                    "content": output,
+                    "is_error": is_error,
+                }),
+            })
+            .collect();
+
+        let (kind, message) = match turn.speaker {
+            Speaker::User => ("user", json!({ "role": "user", "content": content })),
+            Speaker::Assistant => {
+                let has_calls = turn.items.iter().any(|i| matches!(i, Item::Call { .. }));
+                (
+                    "assistant",
+                    json!({
+                        "id": format!("msg_{}", uuid.replace('-', "")),
+                        "type": "message",
+                        "role": "assistant",
+                        "model": turn.model.as_deref().unwrap_or("<synthetic>"),
+                        "content": content,
+                        "stop_reason": if has_calls { "tool_use" } else { "end_turn" },
+                        "stop_sequence": null,
+                        "usage": { "input_tokens": 0, "output_tokens": 0 },
+                    }),
+                )
+            }
+        };
+
+        lines.push(
+            json!({
+                "parentUuid": parent,
+                "isSidechain": false,
+                "userType": "external",
+                "cwd": cwd,
+                "sessionId": session_id,
+                "version": CONVERTER_VERSION,
+                "type": kind,
+                "message": message,
+                "uuid": uuid,
+                "timestamp": iso_timestamp(turn.ts),
+            })
+            .to_string(),
+        );
+        parent = Some(uuid);
+    }
+
+    write_file(&path, &(lines.join("\n") + "\n"))?;
+    Ok(path)
+}
+
+fn write_codex(
+    root: &Path,
+    cwd: &str,
+    session_id: &str,
+    started: DateTime<Local>,
+    turns: &[Turn],
+) -> Result<PathBuf, String> {
+    let path = root
+        .join("sessions")
+        .join(started.format("%Y").to_string())
+        .join(started.format("%m").to_string())
+        .join(started.format("%d").to_string())
+        .join(format!(
+            "rollout-{}-{session_id}.jsonl",
+            started.format("%Y-%m-%dT%H-%M-%S")
+        ));
+
+    let started_at = iso_timestamp(Some(started.timestamp_millis()));
+    let mut lines = vec![json!({
+        "timestamp": started_at,
+        "type": "session_meta",
+        "payload": {
+            "id": session_id,
+            "timestamp": started_at,
+            "cwd": cwd,
+            "originator": CONVERTER_VERSION,
+            "cli_version": "0.0.0",
+            "instructions": null,
+            "source": "cli",
+        },
+    })];
+
+    for turn in turns {
+        let timestamp = iso_timestamp(turn.ts);
+        let mut push = |kind: &str, payload: Value| {
+            lines.push(json!({ "timestamp": timestamp, "type": kind, "payload": payload }));
+        };
+        for item in &turn.items {
+            match (turn.speaker, item) {
+                (Speaker::User, Item::Text(text)) => {
+                    push(
+                        "response_item",
+                        json!({
+                            "type": "message",
+                            "role": "user",
+                            "content": [{ "type": "input_text", "text": text }],
+                        }),
+                    );
+                    push(
+                        "event_msg",
+                        json!({ "type": "user_message", "message": text }),
+                    );
+                }
+                (Speaker::Assistant, Item::Text(text)) => {
+                    push(
+                        "response_item",
+                        json!({
+                            "type": "message",
+                            "role": "assistant",
+                            "content": [{ "type": "output_text", "text": text }],
+                        }),
+                    );
+                    push(
+                        "event_msg",
+                        json!({ "type": "agent_message", "message": text }),
+                    );
+                }
+                (_, Item::Call { id, name, input }) => push(
+                    "response_item",
+                    json!({
+                        "type": "function_call",
+                        "name": name,
+                        "arguments": input.to_string(),
+                        "call_id": id,
+                    }),
+                ),
+                (_, Item::Result { id, output, .. }) => push(
+                    "response_item",
+                    json!({
+                        "type": "function_call_output",
+                        "call_id": id,
+                        "output": output,
+                    }),
+                ),
+            }
+        }
+    }
+
+    let content = lines
+        .iter()
+        .map(Value::to_string)
+        .collect::<Vec<_>>()
+        .join("\n");
+    write_file(&path, &(content + "\n"))?;
+    Ok(path)
+}
+
+/// Gemini CLI keys per-project data by the SHA-256 of the project root.
+fn gemini_project_hash(cwd: &str) -> String {
+    format!("{:x}", Sha256::digest(cwd.as_bytes()))
+}
+
+fn write_gemini(root: &Path, cwd: &str, tag: &str, turns: &[Turn]) -> Result<PathBuf, String> {
+    let path = root
+        .join("tmp")
+        .join(gemini_project_hash(cwd))
+        .join(format!("checkpoint-{tag}.json"));
+
+    let history: Vec<Value> = turns
+        .iter()
+        .map(|turn| {
+            let parts: Vec<Value> = turn
+                .items
+                .iter()
+                .map(|item| match item {
+                    Item::Text(text) => json!({ "text": text }),
+                    Item::Call { id, name, input } => json!({
+                        "functionCall": { "id": id, "name": name, "args": object_input(input) },
+                    }),
+                    Item::Result {
+                        id,
+                        name,
+                        output,
+                        is_error,
+                    } => {
+                        let key = if *is_error { "error" } else { "output" };
+                        json!({
+                            "functionResponse": { "id": id, "name": name, "response": { key: output } },
+                        })
+                    }
+                })
+                .collect();
+            let role = match turn.speaker {
+                Speaker::User => "user",
+                Speaker::Assistant => "model",
+            };
+            json!({ "role": role, "parts": parts })
+        })
+        .collect();
+
+    let content = serde_json::to_string_pretty(&history)
+        .map_err(|e| format!("Failed to serialize checkpoint: {e}"))?;
+    write_file(&path, &content)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session_manager::providers::{claude, codex};
+
+    fn sample_messages() -> Vec<SessionMessage> {
+        vec![
+            SessionMessage {
+                role: "user".to_string(),
+                content: "list files".to_string(),
+                parts: vec![ContentPart::text("list files")],
+                ts: Some(1_700_000_000_000),
+                ..Default::default()
+            },
+            SessionMessage {
+                role: "assistant".to_string(),
+                model: Some("gpt-5-codex".to_string()),
+                parts: vec![
+                    ContentPart::thinking("use ls"),
+                    ContentPart::tool_use(
+                        Some("call_1".to_string()),
+                        "shell",
+                        json!({"command": ["bash", "-lc", "ls"]}),
+                    ),
+                    // Never answered: must degrade to text
+                    ContentPart::tool_use(Some("call_2".to_string()), "apply_patch", json!({})),
+                ],
+                ..Default::default()
+            },
+            SessionMessage {
+                role: "tool".to_string(),
+                content: "README.md".to_string(),
+                parts: vec![ContentPart::tool_result(
+                    Some("call_1".to_string()),
+                    None,
+                    "README.md",
+                    false,
+                )],
+                ..Default::default()
+            },
+            SessionMessage {
+                role: "assistant".to_string(),
+                content: "There is a README.".to_string(),
+                parts: vec![ContentPart::text("There is a README.")],
+                ..Default::default()
+            },
+        ]
+    }
+
+    #[test]
+    fn test_build_turns_pairs_tool_calls() {
+        let turns = build_turns(&sample_messages(), ConvertTarget::Claude, false);
+        assert_eq!(turns.len(), 4);
+        assert_eq!(turns[1].model.as_deref(), Some("gpt-5-codex"));
+        assert_eq!(
+            turns[1].items[0],
+            Item::Call {
+                id: "call_1".to_string(),
+                name: "Bash".to_string(),
+                input: json!({"command": "ls"}),
+            }
+        );
+        assert_eq!(
+            turns[1].items[1],
+            Item::Text("[Tool call: apply_patch]".to_string())
+        );
+        assert!(matches!(&turns[2].items[0], Item::Result { name, .. } if name == "Bash"));
+
+        let as_text = build_turns(&sample_messages(), ConvertTarget::Claude, true);
+        assert!(as_text
+            .iter()
+            .flat_map(|turn| &turn.items)
+            .all(|item| matches!(item, Item::Text(_))));
+    }
+
+    #[test]
+    fn test_gemini_results_move_to_user_turn() {
+        let messages = vec![
+            SessionMessage {
+                role: "user".to_string(),
+                content: "hi".to_string(),
+                ..Default::default()
+            },
+            SessionMessage {
+                role: "assistant".to_string(),
+                parts: vec![
+                    ContentPart::tool_use(
+                        Some("c1".to_string()),
+                        "run_shell_command",
+                        json!({"command": "pwd"}),
+                    ),
+                    ContentPart::tool_result(Some("c1".to_string()), None, "/tmp", false),
+                    ContentPart::text("You are in /tmp."),
+                ],
+                ..Default::default()
+            },
+        ];
+        let turns = build_turns(&messages, ConvertTarget::Codex, false);
+        let speakers: Vec<Speaker> = turns.iter().map(|t| t.speaker).collect();
+        assert_eq!(
+            speakers,
+            vec![
+                Speaker::User,
+                Speaker::Assistant,
+                Speaker::User,
+                Speaker::Assistant
+            ]
+        );
+        assert!(matches!(&turns[1].items[0], Item::Call { name, .. } if name == "shell"));
+    }
+
+    #[test]
+    fn test_write_claude_round_trip() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let turns = build_turns(&sample_messages(), ConvertTarget::Claude, false);
+        let path = write_claude(dir.path(), "/work/my.app", "s-1", &turns).expect("write");
+        assert!(path.ends_with("projects/-work-my-app/s-1.jsonl"));
+
+        let mut messages = claude::load_messages(&path).expect("load");
+        crate::session_manager::link_tool_calls(&mut messages);
+        let roles: Vec<&str> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(roles, vec!["user", "assistant", "user", "assistant"]);
+        assert_eq!(messages[1].parent_id, messages[0].id);
+        assert!(matches!(
+            &messages[2].parts[0],
+            ContentPart::ToolResult {
+                use_index: Some(1),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_write_codex_round_trip() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let turns = build_turns(&sample_messages(), ConvertTarget::Codex, false);
+        let started = Local.with_ymd_and_hms(2025, 3, 4, 5, 6, 7).unwrap();
+        let path = write_codex(dir.path(), "/work/app", "abc", started, &turns).expect("write");
+        assert!(path.ends_with("sessions/2025/03/04/rollout-2025-03-04T05-06-07-abc.jsonl"));
+
+        let messages = codex::load_messages(&path).expect("load");
+        assert_eq!(
+            messages.first().map(|m| m.content.as_str()),
+            Some("list files")
+        );
+        assert!(messages.iter().any(|m| matches!(
+            m.parts.first(),
+            Some(ContentPart::ToolUse { name, input, .. })
+                if name == "shell" && input["command"][2] == "ls"
+        )));
+        assert_eq!(
+            messages.last().map(|m| m.content.as_str()),
+            Some("There is a README.")
+        );
+    }
+
+    #[test]
+    fn test_write_gemini_checkpoint() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let turns = build_turns(&sample_messages(), ConvertTarget::Gemini, false);
+        let path = write_gemini(dir.path(), "/work/app", "t1", &turns).expect("write");
+        assert_eq!(
+            path.parent().and_then(|p| p.file_name()).map(|n| n.len()),
+            Some(64)
+        );
+
+        let history: Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).expect("read")).expect("json");
+        assert_eq!(history[0]["role"], "user");
+        assert_eq!(history[1]["role"], "model");
+        assert_eq!(
+            history[1]["parts"][0]["functionCall"]["name"],
+            "run_shell_command"
+        );
+        assert_eq!(
+            history[2]["parts"][0]["functionResponse"]["response"]["output"],
+            "README.md"
+        );
+    }
+}
@@ -0,0 +1,671 @@
+//! Live tailing of running Claude Code and Codex sessions.
+//!
+//! Watched transcripts are polled and only the JSONL lines appended since the
+//! previous poll are parsed. New messages, status changes and newly created
+//! sessions are pushed to the frontend as Tauri events.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::path::PathBuf;
+use std::sync::{LazyLock, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter};
+
+use super::providers::{claude, codex};
+use super::search::fingerprint;
+use super::{find_session, ContentPart, SessionMessage, SessionMeta};
+
+pub const EVENT_MESSAGES: &str = "session-live-messages";
+pub const EVENT_STATUS: &str = "session-live-status";
+pub const EVENT_DISCOVERED: &str = "session-live-discovered";
+
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+const DISCOVERY_INTERVAL: Duration = Duration::from_secs(10);
+/// Quiet time after a finished assistant reply before the session counts as waiting.
+const TURN_SETTLE_MS: i64 = 3_000;
+/// A tool call without a result for this long is most likely an approval prompt.
+const TOOL_WAIT_MS: i64 = 20_000;
+const IDLE_MS: i64 = 5 * 60_000;
+/// Sessions picked up by discovery stop being watched after this long idle.
+const AUTO_UNWATCH_MS: i64 = 30 * 60_000;
+
+static TAILER: LazyLock<Mutex<LiveTailer>> = LazyLock::new(|| Mutex::new(LiveTailer::default()));
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum LiveStatus {
+    Active,
+    WaitingForInput,
+    Idle,
+    /// The transcript was deleted or became unreadable; no further events follow.
+    Closed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveSessionState {
+    pub provider_id: String,
+    pub source_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    pub status: LiveStatus,
+    pub last_activity_at: i64,
+    /// Name of the tool call still waiting for its result.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pending_tool: Option<String>,
+    /// Watched because discovery saw it change, not by explicit request.
+    pub auto: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LiveMessages {
+    pub provider_id: String,
+    pub source_path: String,
+    pub messages: Vec<SessionMessage>,
+}
+
+#[derive(Debug, Clone)]
+pub enum LiveEvent {
+    Messages(LiveMessages),
+    Status(LiveSessionState),
+    Discovered(SessionMeta),
+}
+
+#[derive(Debug)]
+enum LineParser {
+    Claude(claude::LineParser),
+    Codex(codex::LineParser),
+}
+
+impl LineParser {
+    fn new(provider_id: &str) -> Result<Self, String> {
+        match provider_id {
+            "claude" => Ok(Self::Claude(Default::default())),
+            "codex" => Ok(Self::Codex(Default::default())),
+            other => Err(format!("Live tailing is not supported for {other}")),
+        }
+    }
+
+    fn push_line(&mut self, value: &Value, messages: &mut Vec<SessionMessage>) {
+        match self {
+            Self::Claude(parser) => parser.push_line(value, messages),
+            Self::Codex(parser) => parser.push_line(value, messages),
+        }
+    }
+}
+
+/// One watched transcript.
+#[derive(Debug)]
+struct Tail {
+    provider_id: String,
+    source_path: String,
+    session_id: Option<String>,
+    parser: LineParser,
+    /// End of the last complete line read.
+    offset: u64,
+    last_activity_at: i64,
+    /// Tool calls without a result yet: (call id, tool name).
+    pending_tools: Vec<(String, String)>,
+    /// The last message was an assistant reply with no tool call in it.
+    turn_ended: bool,
+    auto: bool,
+    last_state: Option<LiveSessionState>,
+}
+
+impl Tail {
+    /// Read the whole transcript to build parser and status state. Messages
+    /// from lines starting at or after `emit_from` are returned as new.
+    fn open(
+        provider_id: &str,
+        source_path: &str,
+        emit_from: u64,
+        auto: bool,
+    ) -> Result<(Self, Vec<SessionMessage>), String> {
+        let mut tail = Self {
+            provider_id: provider_id.to_string(),
+            source_path: source_path.to_string(),
+            session_id: find_session(provider_id, source_path).map(|meta| meta.session_id),
+            parser: LineParser::new(provider_id)?,
+            offset: 0,
+            last_activity_at: 0,
+            pending_tools: Vec::new(),
+            turn_ended: false,
+            auto,
+            last_state: None,
+        };
+        let messages = tail.read_from(emit_from)?;
+        Ok((tail, messages))
+    }
+
+    /// Parse lines appended since the last read.
+    fn read_appended(&mut self) -> Result<Vec<SessionMessage>, String> {
+        let size = std::fs::metadata(&self.source_path)
+            .map_err(|e| format!("Failed to read {}: {e}", self.source_path))?
+            .len();
+        if size < self.offset {
+            // Rewritten in place: rebuild state without re-sending old messages
+            self.parser = LineParser::new(&self.provider_id)?;
+            self.offset = 0;
+            self.pending_tools.clear();
+            return self.read_from(size);
+        }
+        if size == self.offset {
+            return Ok(Vec::new());
+        }
+        self.read_from(self.offset)
+    }
+
+    fn read_from(&mut self, emit_from: u64) -> Result<Vec<SessionMessage>, String> {
+        let path = PathBuf::from(&self.source_path);
+        let mut file =
+            File::open(&path).map_err(|e| format!("Failed to open session file: {e}"))?;
+        file.seek(SeekFrom::Start(self.offset))
+            .map_err(|e| format!("Failed to read session file: {e}"))?;
+        let mut reader = BufReader::new(file);
+        let mut line = Vec::new();
+        let mut emitted = Vec::new();
+        loop {
+            line.clear();
+            let read = reader
+                .read_until(b'\n', &mut line)
+                .map_err(|e| format!("Failed to read session file: {e}"))?;
+            // An unterminated last line is still being written
+            if read == 0 || line.last() != Some(&b'\n') {
+                break;
+            }
+            let line_start = self.offset;
+            self.offset += read as u64;
+            let Ok(value) = serde_json::from_slice::<Value>(&line) else {
+                continue;
+            };
+
+            let mut parsed = Vec::new();
+            self.parser.push_line(&value, &mut parsed);
+            for message in &parsed {
+                self.observe(message);
+            }
+            if line_start >= emit_from {
+                emitted.extend(parsed);
+            }
+        }
+        if let Some((_, modified)) = fingerprint(&path) {
+            self.last_activity_at = self.last_activity_at.max(modified);
+        }
+        Ok(emitted)
+    }
+
+    fn observe(&mut self, message: &SessionMessage) {
+        let mut has_call = false;
+        let mut has_text = false;
+        for part in &message.parts {
+            match part {
+                ContentPart::ToolUse { id, name, .. } => {
+                    has_call = true;
+                    self.pending_tools
+                        .push((id.clone().unwrap_or_default(), name.clone()));
+                }
+                ContentPart::ToolResult { tool_use_id, .. } => {
+                    let id = tool_use_id.as_deref().unwrap_or_default();
+                    if let Some(index) = self.pending_tools.iter().position(|(call, _)| call == id)
+                    {
+                        self.pending_tools.remove(index);
+                    }
+                }
+                ContentPart::Text { .. } => has_text = true,
+                _ => {}
+            }
+        }
+        if message.is_sidechain {
+            return;
+        }
+        self.turn_ended = message.role == "assistant" && has_text && !has_call;
+        if message.role == "user" && has_text {
+            // A new prompt supersedes calls that were interrupted
+            self.pending_tools.clear();
+        }
+    }
+
+    fn status(&self, now: i64) -> LiveStatus {
+        let quiet = now - self.last_activity_at;
+        if quiet >= IDLE_MS {
+            LiveStatus::Idle
+        } else if !self.pending_tools.is_empty() {
+            if quiet >= TOOL_WAIT_MS {
+                LiveStatus::WaitingForInput
+            } else {
+                LiveStatus::Active
+            }
+        } else if self.turn_ended && quiet >= TURN_SETTLE_MS {
+            LiveStatus::WaitingForInput
+        } else {
+            LiveStatus::Active
+        }
+    }
+
+    fn state(&self, now: i64) -> LiveSessionState {
+        LiveSessionState {
+            provider_id: self.provider_id.clone(),
+            source_path: self.source_path.clone(),
+            session_id: self.session_id.clone(),
+            status: self.status(now),
+            last_activity_at: self.last_activity_at,
+            pending_tool: self.pending_tools.last().map(|(_, name)| name.clone()),
+            auto: self.auto,
+        }
+    }
+
+    fn closed_state(&self) -> LiveSessionState {
+        LiveSessionState {
+            status: LiveStatus::Closed,
+            pending_tool: None,
+            ..self.state(self.last_activity_at)
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Discovery {
+    /// Transcript sizes as of the last scan.
+    known: HashMap<PathBuf, u64>,
+    last_scan: Option<Instant>,
+}
+
+#[derive(Debug, Default)]
+pub struct LiveTailer {
+    tails: HashMap<String, Tail>,
+    discovery: Option<Discovery>,
+}
+
+impl LiveTailer {
+    fn is_busy(&self) -> bool {
+        !self.tails.is_empty() || self.discovery.is_some()
+    }
+
+    /// Adopt a freshly opened explicit watch, unless one raced it in.
+    fn insert_watch(&mut self, mut tail: Tail, now: i64) -> LiveSessionState {
+        if let Some(existing) = self.tails.get_mut(&tail.source_path) {
+            existing.auto = false;
+            return existing.state(now);
+        }
+        let state = tail.state(now);
+        tail.last_state = Some(state.clone());
+        self.tails.insert(tail.source_path.clone(), tail);
+        state
+    }
+
+    fn states(&self, now: i64) -> Vec<LiveSessionState> {
+        let mut states: Vec<_> = self.tails.values().map(|tail| tail.state(now)).collect();
+        states.sort_by_key(|state| std::cmp::Reverse(state.last_activity_at));
+        states
+    }
+
+    /// `known` is the current transcript scan when enabling.
+    fn set_discovery(&mut self, known: Option<HashMap<PathBuf, u64>>) {
+        match (known, self.discovery.is_some()) {
+            (Some(known), false) => {
+                // Start from what exists now; only later changes are reported
+                self.discovery = Some(Discovery {
+                    known,
+                    last_scan: Some(Instant::now()),
+                });
+            }
+            (None, true) => {
+                self.discovery = None;
+                self.tails.retain(|_, tail| !tail.auto);
+            }
+            _ => {}
+        }
+    }
+
+    fn poll(&mut self, now: i64, events: &mut Vec<LiveEvent>) {
+        let mut closed = Vec::new();
+        for (key, tail) in self.tails.iter_mut() {
+            match tail.read_appended() {
+                Ok(messages) if !messages.is_empty() => {
+                    events.push(LiveEvent::Messages(LiveMessages {
+                        provider_id: tail.provider_id.clone(),
+                        source_path: tail.source_path.clone(),
+                        messages,
+                    }))
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    log::debug!("Stopped tailing {key}: {e}");
+                    events.push(LiveEvent::Status(tail.closed_state()));
+                    closed.push(key.clone());
+                    continue;
+                }
+            }
+
+            let state = tail.state(now);
+            if tail.last_state.as_ref() != Some(&state) {
+                tail.last_state = Some(state.clone());
+                events.push(LiveEvent::Status(state));
+            }
+            if tail.auto && now - tail.last_activity_at >= AUTO_UNWATCH_MS {
+                closed.push(key.clone());
+            }
+        }
+        for key in closed {
+            self.tails.remove(&key);
+        }
+    }
+
+    /// Whether a discovery scan is due; marks it as started.
+    fn start_discovery_scan(&mut self) -> bool {
+        let Some(discovery) = self.discovery.as_mut() else {
+            return false;
+        };
+        if discovery
+            .last_scan
+            .is_some_and(|t| t.elapsed() < DISCOVERY_INTERVAL)
+        {
+            return false;
+        }
+        discovery.last_scan = Some(Instant::now());
+        true
+    }
+
+    /// Compare a transcript scan with the previous one and return the
+    /// unwatched transcripts that were created or grew.
+    fn diff_scan(&mut self, scan: Vec<(&'static str, PathBuf, u64)>) -> Vec<Discovered> {
+        let Some(discovery) = self.discovery.as_mut() else {
+            return Vec::new();
+        };
+        let mut seen = HashSet::new();
+        let mut changed = Vec::new();
+        for (provider_id, path, size) in scan {
+            seen.insert(path.clone());
+            let previous = discovery.known.insert(path.clone(), size);
+            let source_path = path.to_string_lossy().to_string();
+            let emit_from = match previous {
+                None => 0,
+                Some(old) if size > old => old,
+                _ => continue,
+            };
+            if self.tails.contains_key(&source_path) {
+                continue;
+            }
+            changed.push(Discovered {
+                provider_id,
+                source_path,
+                emit_from,
+                created: previous.is_none(),
+            });
+        }
+        discovery.known.retain(|path, _| seen.contains(path));
+        changed
+    }
+
+    /// Watch tails opened by discovery, skipping any watched in the meantime.
+    fn adopt(&mut self, opened: Vec<(Tail, Vec<SessionMessage>)>, events: &mut Vec<LiveEvent>) {
+        if self.discovery.is_none() {
+            return;
+        }
+        for (tail, messages) in opened {
+            if self.tails.contains_key(&tail.source_path) {
+                continue;
+            }
+            if !messages.is_empty() {
+                events.push(LiveEvent::Messages(LiveMessages {
+                    provider_id: tail.provider_id.clone(),
+                    source_path: tail.source_path.clone(),
+                    messages,
+                }));
+            }
+            if let Some(state) = tail.last_state.clone() {
+                events.push(LiveEvent::Status(state));
+            }
+            self.tails.insert(tail.source_path.clone(), tail);
+        }
+    }
+}
+
+/// A transcript discovery saw being created or growing.
+#[derive(Debug)]
+struct Discovered {
+    provider_id: &'static str,
+    source_path: String,
+    emit_from: u64,
+    created: bool,
+}
+
+/// Current size of every known transcript.
+fn scan_transcripts() -> Vec<(&'static str, PathBuf, u64)> {
+    transcript_files()
+        .into_iter()
+        .filter_map(|(provider_id, path)| {
+            let size = std::fs::metadata(&path).ok()?.len();
+            Some((provider_id, path, size))
+        })
+        .collect()
+}
+
+/// Open discovered transcripts; the file I/O happens outside the tailer lock.
+fn open_discovered(
+    changed: Vec<Discovered>,
+    now: i64,
+    events: &mut Vec<LiveEvent>,
+) -> Vec<(Tail, Vec<SessionMessage>)> {
+    let mut opened = Vec::new();
+    for found in changed {
+        if found.created {
+            if let Some(meta) = find_session(found.provider_id, &found.source_path) {
+                events.push(LiveEvent::Discovered(meta));
+            }
+        }
+        match Tail::open(found.provider_id, &found.source_path, found.emit_from, true) {
+            Ok((mut tail, messages)) => {
+                tail.last_state = Some(tail.state(now));
+                opened.push((tail, messages));
+            }
+            Err(e) => log::debug!("Failed to watch {}: {e}", found.source_path),
+        }
+    }
+    opened
+}
+
+fn transcript_files() -> Vec<(&'static str, PathBuf)> {
+    let claude = claude::transcript_files()
+        .into_iter()
+        .map(|p| ("claude", p));
+    let codex = codex::transcript_files().into_iter().map(|p| ("codex", p));
+    claude.chain(codex).collect()
+}
+
+fn with_tailer<T>(f: impl FnOnce(&mut LiveTailer) -> T) -> T {
+    let mut tailer = TAILER.lock().unwrap_or_else(|e| e.into_inner());
+    f(&mut tailer)
+}
+
+fn now_ms() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
+
+/// Start tailing a session; only lines appended from now on are reported.
+pub fn watch(provider_id: &str, source_path: &str) -> Result<LiveSessionState, String> {
+    let existing = with_tailer(|tailer| {
+        let tail = tailer.tails.get_mut(source_path)?;
+        tail.auto = false;
+        Some(tail.state(now_ms()))
+    });
+    if let Some(state) = existing {
+        return Ok(state);
+    }
+
+    // Read the transcript before taking the lock so polling is not held up
+    let size = std::fs::metadata(source_path)
+        .map_err(|e| format!("Failed to read {source_path}: {e}"))?
+        .len();
+    let (tail, _) = Tail::open(provider_id, source_path, size, false)?;
+    Ok(with_tailer(|tailer| tailer.insert_watch(tail, now_ms())))
+}
+
+/// Stop tailing a session, returning whether it was watched.
+pub fn unwatch(source_path: &str) -> bool {
+    with_tailer(|tailer| tailer.tails.remove(source_path).is_some())
+}
+
+/// Current state of every watched session, most recently active first.
+pub fn states() -> Vec<LiveSessionState> {
+    with_tailer(|tailer| tailer.states(now_ms()))
+}
+
+/// Toggle discovery of new and newly active sessions.
+pub fn set_discovery(enabled: bool) {
+    let known = (enabled && !with_tailer(|tailer| tailer.discovery.is_some())).then(|| {
+        scan_transcripts()
+            .into_iter()
+            .map(|(_, path, size)| (path, size))
+            .collect()
+    });
+    if enabled && known.is_none() {
+        return;
+    }
+    with_tailer(|tailer| tailer.set_discovery(known))
+}
+
+/// Poll watched sessions once. Discovery scans and opens new transcripts
+/// without holding the tailer lock.
+fn poll() -> Vec<LiveEvent> {
+    let mut events = Vec::new();
+    if with_tailer(LiveTailer::start_discovery_scan) {
+        let scan = scan_transcripts();
+        let changed = with_tailer(|tailer| tailer.diff_scan(scan));
+        let opened = open_discovered(changed, now_ms(), &mut events);
+        with_tailer(|tailer| tailer.adopt(opened, &mut events));
+    }
+    with_tailer(|tailer| tailer.poll(now_ms(), &mut events));
+    events
+}
+
+/// Poll watched sessions in the background and forward changes as events.
+pub fn start_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            if !with_tailer(|tailer| tailer.is_busy()) {
+                continue;
+            }
+
+            let events = match tauri::async_runtime::spawn_blocking(poll).await {
+                Ok(events) => events,
+                Err(e) => {
+                    log::warn!("Session live poll failed: {e}");
+                    continue;
+                }
+            };
+            for event in events {
+                let result = match &event {
+                    LiveEvent::Messages(payload) => app.emit(EVENT_MESSAGES, payload),
+                    LiveEvent::Status(payload) => app.emit(EVENT_STATUS, payload),
+                    LiveEvent::Discovered(payload) => app.emit(EVENT_DISCOVERED, payload),
+                };
+                if let Err(e) = result {
+                    log::debug!("Failed to emit session live event: {e}");
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::path::Path;
+
+    fn append(path: &Path, lines: &[&str]) {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        for line in lines {
+            writeln!(file, "{line}").unwrap();
+        }
+    }
+
+    const USER: &str =
+        r#"{"type":"user","uuid":"u1","message":{"role":"user","content":"run the tests"}}"#;
+    const CALL: &str = r#"{"type":"assistant","uuid":"a1","message":{"id":"m1","role":"assistant","content":[{"type":"tool_use","id":"t1","name":"Bash","input":{"command":"cargo test"}}]}}"#;
+    const RESULT: &str = r#"{"type":"user","uuid":"u2","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"t1","content":"ok"}]}}"#;
+    const REPLY: &str = r#"{"type":"assistant","uuid":"a2","message":{"id":"m2","role":"assistant","content":[{"type":"text","text":"All tests pass."}]}}"#;
+
+    #[test]
+    fn test_tail_reads_only_appended_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("s1.jsonl");
+        append(&path, &[USER]);
+        let source = path.to_string_lossy().to_string();
+
+        let size = std::fs::metadata(&path).unwrap().len();
+        let (mut tail, initial) = Tail::open("claude", &source, size, false).unwrap();
+        assert!(initial.is_empty());
+        assert!(tail.read_appended().unwrap().is_empty());
+
+        append(&path, &[CALL]);
+        // Partially written line is left for the next read
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(&RESULT.as_bytes()[..20])
+            .unwrap();
+        let messages = tail.read_appended().unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            tail.pending_tools,
+            vec![("t1".to_string(), "Bash".to_string())]
+        );
+
+        let active = tail.last_activity_at;
+        assert_eq!(tail.status(active + 1_000), LiveStatus::Active);
+        assert_eq!(
+            tail.status(active + TOOL_WAIT_MS),
+            LiveStatus::WaitingForInput
+        );
+
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(&RESULT.as_bytes()[20..])
+            .unwrap();
+        append(&path, &["", REPLY]);
+        let messages = tail.read_appended().unwrap();
+        assert_eq!(
+            messages.iter().map(|m| m.role.as_str()).collect::<Vec<_>>(),
+            vec!["user", "assistant"]
+        );
+        assert!(tail.pending_tools.is_empty());
+        let active = tail.last_activity_at;
+        assert_eq!(tail.status(active + 1_000), LiveStatus::Active);
+        assert_eq!(
+            tail.status(active + TURN_SETTLE_MS),
+            LiveStatus::WaitingForInput
+        );
+        assert_eq!(tail.status(active + IDLE_MS), LiveStatus::Idle);
+    }
+
+    #[test]
+    fn test_tail_open_emits_from_offset() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("s2.jsonl");
+        append(&path, &[USER]);
+        let old_size = std::fs::metadata(&path).unwrap().len();
+        append(&path, &[REPLY]);
+
+        let source = path.to_string_lossy().to_string();
+        let (tail, messages) = Tail::open("claude", &source, old_size, true).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content, "All tests pass.");
+        assert!(tail.turn_ended);
+        assert!(Tail::open("gemini", &source, 0, true).is_err());
+    }
+}
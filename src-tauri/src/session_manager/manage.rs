@@ -0,0 +1,825 @@
+//! Delete, archive and restore sessions, annotate them, and prune in bulk.
+//!
+//! A session's files are only ever removed or restored as a whole, and only
+//! inside the owning CLI's data directory. Custom titles and tags are kept in
+//! cc-switch's database so the CLIs' own files are never rewritten.
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use zip::write::SimpleFileOptions;
+
+use crate::database::Database;
+use crate::error::AppError;
+
+use super::providers::{get_source, SessionSource};
+use super::search::{ensure_fresh, fingerprint};
+use super::{find_session, load_messages, scan_sessions, SessionMeta};
+
+/// Manifest stored inside every archive, next to the session files.
+const ARCHIVE_MANIFEST: &str = "cc-switch-session.json";
+const ARCHIVE_VERSION: u32 = 1;
+
+/// Sessions active this recently are never pruned; the CLI may still be writing them.
+const PRUNE_GRACE_MS: i64 = 60 * 60 * 1000;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// Identifies a session the same way `load_messages` does.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionRef {
+    pub provider_id: String,
+    pub source_path: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionAnnotation {
+    pub provider_id: String,
+    pub session_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub updated_at: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchivedSession {
+    pub id: String,
+    pub provider_id: String,
+    pub session_id: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_dir: Option<String>,
+    /// Where the session lived, and where restoring puts it back.
+    pub source_path: String,
+    pub archive_path: String,
+    pub file_count: u32,
+    pub original_bytes: u64,
+    pub archived_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_active_at: Option<i64>,
+    pub archived_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionActionFailure {
+    pub source_path: String,
+    pub error: String,
+}
+
+/// Outcome of a bulk delete or archive; one failure does not stop the rest.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionActionReport {
+    pub succeeded: Vec<String>,
+    pub failed: Vec<SessionActionFailure>,
+    /// Bytes removed from the CLIs' data directories.
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PruneAction {
+    #[default]
+    Archive,
+    Delete,
+}
+
+/// Bulk prune rules. A session is selected when it matches any enabled rule.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionPruneRules {
+    /// Last activity at least this many days ago.
+    pub older_than_days: Option<u32>,
+    /// Fewer messages than this.
+    pub min_messages: Option<u32>,
+    /// The recorded project directory no longer exists.
+    pub missing_project: bool,
+    /// Limit to these providers; empty means all.
+    pub provider_ids: Vec<String>,
+    /// Skip sessions that have a custom title or tags.
+    pub keep_annotated: bool,
+    pub action: PruneAction,
+    /// Only report what would be pruned.
+    pub dry_run: bool,
+}
+
+impl Default for SessionPruneRules {
+    fn default() -> Self {
+        Self {
+            older_than_days: None,
+            min_messages: None,
+            missing_project: false,
+            provider_ids: Vec::new(),
+            keep_annotated: true,
+            action: PruneAction::Archive,
+            dry_run: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PruneReason {
+    Inactive,
+    FewMessages,
+    MissingProject,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PruneCandidate {
+    pub provider_id: String,
+    pub session_id: String,
+    pub source_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_active_at: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message_count: Option<u32>,
+    pub bytes: u64,
+    pub reasons: Vec<PruneReason>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionPruneReport {
+    pub dry_run: bool,
+    pub action: PruneAction,
+    pub candidates: Vec<PruneCandidate>,
+    pub total_bytes: u64,
+    /// Present when the action was actually carried out.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<SessionActionReport>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ArchiveManifest {
+    version: u32,
+    provider_id: String,
+    session: SessionMeta,
+    /// Archived files, relative to the provider's data directory.
+    files: Vec<String>,
+}
+
+/// The validated set of paths making up one session.
+#[derive(Debug)]
+struct SessionFiles {
+    root: PathBuf,
+    /// Top-level files and directories, removed as a whole.
+    paths: Vec<PathBuf>,
+    /// Every regular file below `paths`.
+    files: Vec<PathBuf>,
+    bytes: u64,
+}
+
+/// Discovery root of a provider that `source` lives under.
+fn provider_root(provider_id: &str, source: &Path) -> Result<PathBuf, AppError> {
+    let roots = session_source(provider_id)?.roots();
+    roots
+        .iter()
+        .find(|root| source.starts_with(root))
+        .or(roots.first())
+        .cloned()
+        .ok_or_else(|| AppError::InvalidInput(format!("No {provider_id} data directory found")))
+}
+
+fn session_source(provider_id: &str) -> Result<&'static dyn SessionSource, AppError> {
+    get_source(provider_id)
+        .ok_or_else(|| AppError::InvalidInput(format!("Unsupported provider: {provider_id}")))
+}
+
+fn resolve_files(provider_id: &str, source_path: &str) -> Result<SessionFiles, AppError> {
+    resolve_files_with(provider_id, source_path, None)
+}
+
+/// `children` are the session's child-session files when already known from a
+/// scan; otherwise the provider looks them up.
+fn resolve_files_with(
+    provider_id: &str,
+    source_path: &str,
+    children: Option<&[PathBuf]>,
+) -> Result<SessionFiles, AppError> {
+    let source = Path::new(source_path);
+    resolve_files_in(
+        &provider_root(provider_id, source)?,
+        provider_id,
+        source,
+        children,
+    )
+}
+
+fn resolve_files_in(
+    root: &Path,
+    provider_id: &str,
+    source: &Path,
+    children: Option<&[PathBuf]>,
+) -> Result<SessionFiles, AppError> {
+    let session_source = session_source(provider_id)?;
+    if session_source.shares_storage() {
+        return Err(AppError::InvalidInput(format!(
+            "{provider_id} sessions share one file and cannot be removed individually"
+        )));
+    }
+    let root = root.canonicalize().map_err(|e| AppError::io(root, e))?;
+    let source = source.canonicalize().map_err(|e| AppError::io(source, e))?;
+    if !source.starts_with(&root) || source == root {
+        return Err(AppError::InvalidInput(format!(
+            "Refusing to touch {} outside the {provider_id} data directory",
+            source.display()
+        )));
+    }
+
+    let mut paths: Vec<PathBuf> = Vec::new();
+    let mut files = Vec::new();
+    let children = match children {
+        Some(children) => children.to_vec(),
+        None => session_source.child_session_paths(&source),
+    };
+    for path in session_source
+        .session_paths(&source)
+        .into_iter()
+        .chain(children)
+    {
+        let Ok(path) = path.canonicalize() else {
+            continue;
+        };
+        if !path.starts_with(&root) || path == root || paths.iter().any(|p| path.starts_with(p)) {
+            continue;
+        }
+        collect_files(&path, &mut files);
+        paths.push(path);
+    }
+    let bytes = files
+        .iter()
+        .filter_map(|f| fs::metadata(f).ok())
+        .map(|m| m.len())
+        .sum();
+    Ok(SessionFiles {
+        root,
+        paths,
+        files,
+        bytes,
+    })
+}
+
+/// Regular files below `path`; symlinks are neither followed nor archived.
+fn collect_files(path: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(meta) = fs::symlink_metadata(path) else {
+        return;
+    };
+    if meta.is_dir() {
+        let mut entries: Vec<PathBuf> = fs::read_dir(path)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .collect();
+        entries.sort();
+        for entry in entries {
+            collect_files(&entry, files);
+        }
+    } else if meta.is_file() {
+        files.push(path.to_path_buf());
+    }
+}
+
+fn remove_paths(paths: &[PathBuf]) -> Result<(), AppError> {
+    for path in paths {
+        let result = if path.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        };
+        match result {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(AppError::io(path, e)),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Archive entry name: path relative to the data directory, `/`-separated.
+fn entry_name(root: &Path, file: &Path) -> Option<String> {
+    let relative = file.strip_prefix(root).ok()?;
+    let parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Some(parts.join("/"))
+}
+
+/// Write the session files into a zip archive, returning its size.
+fn write_archive(files: &SessionFiles, meta: &SessionMeta, path: &Path) -> Result<u64, AppError> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| AppError::io(parent, e))?;
+    }
+    let archive_err =
+        |e: zip::result::ZipError| AppError::Message(format!("Failed to write archive: {e}"));
+    let options = SimpleFileOptions::default().compression_method(zip::CompressionMethod::Deflated);
+
+    let result = (|| {
+        let file = File::create(path).map_err(|e| AppError::io(path, e))?;
+        let mut zip = zip::ZipWriter::new(file);
+        let mut names = Vec::with_capacity(files.files.len());
+        for file in &files.files {
+            let Some(name) = entry_name(&files.root, file) else {
+                continue;
+            };
+            zip.start_file(name.as_str(), options)
+                .map_err(archive_err)?;
+            let mut reader = File::open(file).map_err(|e| AppError::io(file, e))?;
+            io::copy(&mut reader, &mut zip).map_err(|e| AppError::io(file, e))?;
+            names.push(name);
+        }
+
+        let manifest = ArchiveManifest {
+            version: ARCHIVE_VERSION,
+            provider_id: meta.provider_id.clone(),
+            session: meta.clone(),
+            files: names,
+        };
+        zip.start_file(ARCHIVE_MANIFEST, options)
+            .map_err(archive_err)?;
+        let data = serde_json::to_vec_pretty(&manifest)
+            .map_err(|e| AppError::Message(format!("Failed to serialize manifest: {e}")))?;
+        zip.write_all(&data).map_err(|e| AppError::io(path, e))?;
+        zip.finish().map_err(archive_err)?;
+        fs::metadata(path)
+            .map(|m| m.len())
+            .map_err(|e| AppError::io(path, e))
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(path);
+    }
+    result
+}
+
+/// Extract an archive back under `root`. Nothing is written if any file
+/// already exists, so a restore never overwrites newer data.
+fn restore_archive_into(root: &Path, archive_path: &Path) -> Result<usize, AppError> {
+    let read_err =
+        |e: zip::result::ZipError| AppError::Message(format!("Failed to read archive: {e}"));
+    let file = File::open(archive_path).map_err(|e| AppError::io(archive_path, e))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(read_err)?;
+
+    let mut targets = Vec::new();
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index).map_err(read_err)?;
+        if entry.is_dir() || entry.name() == ARCHIVE_MANIFEST {
+            continue;
+        }
+        let relative = entry.enclosed_name().ok_or_else(|| {
+            AppError::InvalidInput(format!("Unsafe path in archive: {}", entry.name()))
+        })?;
+        let target = root.join(relative);
+        if target.exists() {
+            return Err(AppError::InvalidInput(format!(
+                "Cannot restore: {} already exists",
+                target.display()
+            )));
+        }
+        targets.push((index, target));
+    }
+
+    for (index, target) in &targets {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| AppError::io(parent, e))?;
+        }
+        let mut entry = archive.by_index(*index).map_err(read_err)?;
+        let mut out = File::create(target).map_err(|e| AppError::io(target, e))?;
+        io::copy(&mut entry, &mut out).map_err(|e| AppError::io(target, e))?;
+    }
+    Ok(targets.len())
+}
+
+/// Permanently delete a session, returning the bytes freed.
+pub fn delete_session(db: &Database, session: &SessionRef) -> Result<u64, AppError> {
+    let meta = find_session(&session.provider_id, &session.source_path);
+    let files = resolve_files(&session.provider_id, &session.source_path)?;
+    delete_files(db, session, meta.as_ref(), &files)
+}
+
+fn delete_files(
+    db: &Database,
+    session: &SessionRef,
+    meta: Option<&SessionMeta>,
+    files: &SessionFiles,
+) -> Result<u64, AppError> {
+    remove_paths(&files.paths)?;
+
+    db.remove_session_index(std::slice::from_ref(&session.source_path))?;
+    if let Some(meta) = meta {
+        db.delete_session_annotation(&meta.provider_id, &meta.session_id)?;
+    }
+    Ok(files.bytes)
+}
+
+/// Move a session into a compressed archive under the app config directory.
+pub fn archive_session(db: &Database, session: &SessionRef) -> Result<ArchivedSession, AppError> {
+    let meta = find_session(&session.provider_id, &session.source_path).ok_or_else(|| {
+        AppError::InvalidInput(format!("Session not found: {}", session.source_path))
+    })?;
+    let files = resolve_files(&session.provider_id, &session.source_path)?;
+    archive_files(db, session, &meta, &files)
+}
+
+fn archive_files(
+    db: &Database,
+    session: &SessionRef,
+    meta: &SessionMeta,
+    files: &SessionFiles,
+) -> Result<ArchivedSession, AppError> {
+    let now = chrono::Local::now();
+    let id = uuid::Uuid::new_v4().to_string();
+    let safe_id: String = meta
+        .session_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    // The record id keeps names unique when one session is archived twice within a second
+    let archive_path = crate::config::get_app_config_dir()
+        .join("session-archive")
+        .join(format!(
+            "{}-{safe_id}-{}-{id}.zip",
+            meta.provider_id,
+            now.format("%Y%m%d-%H%M%S")
+        ));
+    let archived_bytes = write_archive(files, meta, &archive_path)?;
+
+    let record = ArchivedSession {
+        id,
+        provider_id: meta.provider_id.clone(),
+        session_id: meta.session_id.clone(),
+        title: meta.title.clone(),
+        project_dir: meta.project_dir.clone(),
+        source_path: session.source_path.clone(),
+        archive_path: archive_path.to_string_lossy().to_string(),
+        file_count: files.files.len() as u32,
+        original_bytes: files.bytes,
+        archived_bytes,
+        last_active_at: meta.last_active_at,
+        archived_at: now.timestamp_millis(),
+    };
+    db.insert_session_archive(&record)?;
+    remove_paths(&files.paths)?;
+    db.remove_session_index(std::slice::from_ref(&session.source_path))?;
+    Ok(record)
+}
+
+/// Put an archived session back where it was and drop the archive.
+pub fn restore_session(db: &Database, archive_id: &str) -> Result<ArchivedSession, AppError> {
+    let record = db
+        .get_session_archive(archive_id)?
+        .ok_or_else(|| AppError::InvalidInput(format!("Archive not found: {archive_id}")))?;
+    let root = provider_root(&record.provider_id, Path::new(&record.source_path))?;
+    restore_archive_into(&root, Path::new(&record.archive_path))?;
+
+    match fs::remove_file(&record.archive_path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+            log::warn!(
+                "Failed to remove restored archive {}: {e}",
+                record.archive_path
+            )
+        }
+        _ => {}
+    }
+    db.delete_session_archive(archive_id)?;
+    Ok(record)
+}
+
+/// Permanently delete an archived session.
+pub fn delete_archive(db: &Database, archive_id: &str) -> Result<(), AppError> {
+    let Some(record) = db.get_session_archive(archive_id)? else {
+        return Ok(());
+    };
+    match fs::remove_file(&record.archive_path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => {
+            return Err(AppError::io(&record.archive_path, e))
+        }
+        _ => {}
+    }
+    db.delete_session_archive(archive_id)?;
+    db.delete_session_annotation(&record.provider_id, &record.session_id)?;
+    Ok(())
+}
+
+pub fn delete_sessions(db: &Database, sessions: &[SessionRef]) -> SessionActionReport {
+    run_bulk(sessions, |session| delete_session(db, session))
+}
+
+pub fn archive_sessions(db: &Database, sessions: &[SessionRef]) -> SessionActionReport {
+    run_bulk(sessions, |session| {
+        archive_session(db, session).map(|record| record.original_bytes)
+    })
+}
+
+fn run_bulk(
+    sessions: &[SessionRef],
+    mut action: impl FnMut(&SessionRef) -> Result<u64, AppError>,
+) -> SessionActionReport {
+    let mut report = SessionActionReport::default();
+    for session in sessions {
+        match action(session) {
+            Ok(bytes) => {
+                report.bytes += bytes;
+                report.succeeded.push(session.source_path.clone());
+            }
+            Err(e) => report.failed.push(SessionActionFailure {
+                source_path: session.source_path.clone(),
+                error: e.to_string(),
+            }),
+        }
+    }
+    report
+}
+
+/// Set a session's custom title and tags; clearing both removes the record.
+pub fn set_annotation(
+    db: &Database,
+    provider_id: &str,
+    session_id: &str,
+    title: Option<String>,
+    tags: Vec<String>,
+) -> Result<Option<SessionAnnotation>, AppError> {
+    let title = title
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty());
+    let mut seen = HashSet::new();
+    let tags: Vec<String> = tags
+        .into_iter()
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty() && seen.insert(t.to_lowercase()))
+        .collect();
+
+    if title.is_none() && tags.is_empty() {
+        db.delete_session_annotation(provider_id, session_id)?;
+        return Ok(None);
+    }
+    let annotation = SessionAnnotation {
+        provider_id: provider_id.to_string(),
+        session_id: session_id.to_string(),
+        title,
+        tags,
+        updated_at: chrono::Utc::now().timestamp_millis(),
+    };
+    db.upsert_session_annotation(&annotation)?;
+    Ok(Some(annotation))
+}
+
+/// Find sessions matching the prune rules and, unless it is a dry run,
+/// archive or delete them.
+///
+/// Sub-agent sessions are never selected on their own; they go with their parent.
+pub fn prune(db: &Database, rules: &SessionPruneRules) -> Result<SessionPruneReport, AppError> {
+    let now = chrono::Utc::now().timestamp_millis();
+    let annotated: HashSet<(String, String)> = if rules.keep_annotated {
+        db.list_session_annotations()?
+            .into_iter()
+            .map(|a| (a.provider_id, a.session_id))
+            .collect()
+    } else {
+        HashSet::new()
+    };
+    let counts = if rules.min_messages.is_some() {
+        ensure_fresh(db)?;
+        db.get_session_index_message_counts()?
+    } else {
+        Default::default()
+    };
+
+    let sessions = scan_sessions();
+    // Sub-agent files by (provider, directory, parent session), built once so
+    // resolving each candidate does not re-parse every sibling transcript
+    let mut children: HashMap<(String, PathBuf, String), Vec<PathBuf>> = HashMap::new();
+    for meta in &sessions {
+        if let (Some(parent), Some(path)) = (&meta.parent_session_id, &meta.source_path) {
+            let path = PathBuf::from(path);
+            let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+            children
+                .entry((meta.provider_id.clone(), dir, parent.clone()))
+                .or_default()
+                .push(path);
+        }
+    }
+
+    let mut candidates = Vec::new();
+    let mut resolved: HashMap<String, (SessionMeta, SessionFiles)> = HashMap::new();
+    for meta in sessions {
+        let Some(source_path) = meta.source_path.clone() else {
+            continue;
+        };
+        // Sessions sharing a file with others cannot be removed on their own
+        if meta.parent_session_id.is_some()
+            || get_source(&meta.provider_id).is_none_or(|source| source.shares_storage())
+            || (!rules.provider_ids.is_empty() && !rules.provider_ids.contains(&meta.provider_id))
+            || annotated.contains(&(meta.provider_id.clone(), meta.session_id.clone()))
+        {
+            continue;
+        }
+        let last_active_at = meta
+            .last_active_at
+            .or(meta.created_at)
+            .or_else(|| fingerprint(Path::new(&source_path)).map(|(_, modified)| modified));
+        if last_active_at.is_some_and(|t| now - t < PRUNE_GRACE_MS) {
+            continue;
+        }
+
+        let mut reasons = Vec::new();
+        if let (Some(days), Some(last)) = (rules.older_than_days, last_active_at) {
+            if now - last >= i64::from(days) * DAY_MS {
+                reasons.push(PruneReason::Inactive);
+            }
+        }
+        let mut message_count = None;
+        if let Some(min) = rules.min_messages {
+            message_count = counts.get(&source_path).copied().or_else(|| {
+                load_messages(&meta.provider_id, &source_path)
+                    .ok()
+                    .map(|m| m.len() as u32)
+            });
+            if message_count.is_some_and(|count| count < min) {
+                reasons.push(PruneReason::FewMessages);
+            }
+        }
+        if rules.missing_project
+            && meta
+                .project_dir
+                .as_deref()
+                .is_some_and(|dir| !dir.trim().is_empty() && !Path::new(dir).exists())
+        {
+            reasons.push(PruneReason::MissingProject);
+        }
+        if reasons.is_empty() {
+            continue;
+        }
+
+        let dir = Path::new(&source_path)
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let agents = children
+            .get(&(meta.provider_id.clone(), dir, meta.session_id.clone()))
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let files = resolve_files_with(&meta.provider_id, &source_path, Some(agents)).ok();
+        candidates.push(PruneCandidate {
+            provider_id: meta.provider_id.clone(),
+            session_id: meta.session_id.clone(),
+            source_path: source_path.clone(),
+            title: meta.title.clone(),
+            project_dir: meta.project_dir.clone(),
+            last_active_at,
+            message_count,
+            bytes: files.as_ref().map_or(0, |f| f.bytes),
+            reasons,
+        });
+        if let Some(files) = files {
+            resolved.insert(source_path, (meta, files));
+        }
+    }
+    candidates.sort_by_key(|c| c.last_active_at.unwrap_or(0));
+
+    let result = (!rules.dry_run).then(|| {
+        let sessions: Vec<SessionRef> = candidates
+            .iter()
+            .map(|c| SessionRef {
+                provider_id: c.provider_id.clone(),
+                source_path: c.source_path.clone(),
+            })
+            .collect();
+        // Failed resolutions go through the regular path to report their error
+        run_bulk(&sessions, |session| {
+            match (rules.action, resolved.get(&session.source_path)) {
+                (PruneAction::Archive, Some((meta, files))) => {
+                    archive_files(db, session, meta, files).map(|record| record.original_bytes)
+                }
+                (PruneAction::Delete, Some((meta, files))) => {
+                    delete_files(db, session, Some(meta), files)
+                }
+                (PruneAction::Archive, None) => {
+                    archive_session(db, session).map(|record| record.original_bytes)
+                }
+                (PruneAction::Delete, None) => delete_session(db, session),
+            }
+        })
+    });
+
+    Ok(SessionPruneReport {
+        dry_run: rules.dry_run,
+        action: rules.action,
+        total_bytes: candidates.iter().map(|c| c.bytes).sum(),
+        candidates,
+        result,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, content: &str) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, content).unwrap();
+    }
+
+    fn meta(source: &Path) -> SessionMeta {
+        SessionMeta {
+            provider_id: "claude".to_string(),
+            session_id: "s1".to_string(),
+            title: None,
+            summary: None,
+            project_dir: None,
+            created_at: None,
+            last_active_at: None,
+            source_path: Some(source.to_string_lossy().to_string()),
+            resume_command: None,
+            parent_session_id: None,
+        }
+    }
+
+    #[test]
+    fn test_archive_and_restore_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("projects");
+        let source = root.join("-work-app").join("s1.jsonl");
+        write(&source, r#"{"sessionId":"s1","type":"user"}"#);
+        write(
+            &root
+                .join("-work-app")
+                .join("s1")
+                .join("tool-results")
+                .join("a.txt"),
+            "output",
+        );
+        write(&root.join("-work-app").join("other.jsonl"), "{}");
+
+        let files = resolve_files_in(&root, "claude", &source, None).unwrap();
+        assert_eq!(files.paths.len(), 2);
+        assert_eq!(files.files.len(), 2);
+
+        let archive = dir.path().join("archive").join("s1.zip");
+        assert!(write_archive(&files, &meta(&source), &archive).unwrap() > 0);
+        remove_paths(&files.paths).unwrap();
+        assert!(!source.exists());
+        assert!(root.join("-work-app").join("other.jsonl").exists());
+
+        assert_eq!(restore_archive_into(&root, &archive).unwrap(), 2);
+        assert_eq!(
+            fs::read_to_string(root.join("-work-app/s1/tool-results/a.txt")).unwrap(),
+            "output"
+        );
+        // A second restore would overwrite the restored files
+        assert!(restore_archive_into(&root, &archive).is_err());
+    }
+
+    #[test]
+    fn test_rejects_paths_outside_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("sessions");
+        fs::create_dir_all(&root).unwrap();
+        let outside = dir.path().join("notes.jsonl");
+        write(&outside, "{}");
+
+        assert!(resolve_files_in(&root, "codex", &outside, None).is_err());
+        assert!(resolve_files_in(&root, "codex", &root, None).is_err());
+    }
+
+    #[test]
+    fn test_set_annotation_normalizes() -> Result<(), AppError> {
+        let db = Database::memory()?;
+        let saved = set_annotation(
+            &db,
+            "codex",
+            "s1",
+            Some("  Release prep ".to_string()),
+            vec!["ship".to_string(), " Ship".to_string(), "".to_string()],
+        )?
+        .expect("annotation");
+        assert_eq!(saved.title.as_deref(), Some("Release prep"));
+        assert_eq!(saved.tags, vec!["ship"]);
+
+        assert!(set_annotation(&db, "codex", "s1", Some(" ".to_string()), vec![])?.is_none());
+        assert!(db.list_session_annotations()?.is_empty());
+        Ok(())
+    }
+}
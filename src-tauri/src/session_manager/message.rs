@@ -0,0 +1,243 @@
+//! Typed content of parsed session messages.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::SessionMessage;
+
+/// One block of a message, in the order it appeared in the transcript.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ContentPart {
+    Text {
+        text: String,
+    },
+    Thinking {
+        text: String,
+        /// The provider only kept an encrypted/redacted form.
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        redacted: bool,
+    },
+    #[serde(rename_all = "camelCase")]
+    ToolUse {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        id: Option<String>,
+        name: String,
+        #[serde(default)]
+        input: Value,
+        /// Index of the message holding the matching result (set by [`link_tool_calls`]).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        result_index: Option<usize>,
+    },
+    #[serde(rename_all = "camelCase")]
+    ToolResult {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tool_use_id: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        name: Option<String>,
+        content: String,
+        #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+        is_error: bool,
+        /// Index of the message holding the matching call (set by [`link_tool_calls`]).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        use_index: Option<usize>,
+    },
+    /// Inline image data is dropped; only what identifies the image is kept.
+    #[serde(rename_all = "camelCase")]
+    Image {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        media_type: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        url: Option<String>,
+    },
+}
+
+impl ContentPart {
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::Text { text: text.into() }
+    }
+
+    pub fn thinking(text: impl Into<String>) -> Self {
+        Self::Thinking {
+            text: text.into(),
+            redacted: false,
+        }
+    }
+
+    pub fn tool_use(id: Option<String>, name: impl Into<String>, input: Value) -> Self {
+        Self::ToolUse {
+            id,
+            name: name.into(),
+            input,
+            result_index: None,
+        }
+    }
+
+    pub fn tool_result(
+        tool_use_id: Option<String>,
+        name: Option<String>,
+        content: impl Into<String>,
+        is_error: bool,
+    ) -> Self {
+        Self::ToolResult {
+            tool_use_id,
+            name,
+            content: content.into(),
+            is_error,
+            use_index: None,
+        }
+    }
+}
+
+/// Token usage reported for one assistant turn.
+///
+/// Normalized across providers: `input_tokens` excludes cache hits and
+/// `output_tokens` includes reasoning.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MessageUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub cache_creation_tokens: u64,
+    /// Part of `output_tokens` spent on reasoning, when reported.
+    pub reasoning_tokens: u64,
+}
+
+impl MessageUsage {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// Read a token count that may be missing or negative.
+pub(crate) fn token_count(value: &Value, key: &str) -> u64 {
+    value.get(key).and_then(Value::as_u64).unwrap_or(0)
+}
+
+/// Render a tool input/output value as text (strings are used verbatim).
+pub(crate) fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Point every tool call at its result and back, matching on the call id.
+///
+/// Results without a name inherit it from the call.
+pub fn link_tool_calls(messages: &mut [SessionMessage]) {
+    let mut uses: HashMap<String, (usize, String)> = HashMap::new();
+    for (index, message) in messages.iter().enumerate() {
+        for part in &message.parts {
+            if let ContentPart::ToolUse {
+                id: Some(id), name, ..
+            } = part
+            {
+                uses.entry(id.clone()).or_insert((index, name.clone()));
+            }
+        }
+    }
+    if uses.is_empty() {
+        return;
+    }
+
+    let mut results: HashMap<String, usize> = HashMap::new();
+    for (index, message) in messages.iter_mut().enumerate() {
+        for part in &mut message.parts {
+            if let ContentPart::ToolResult {
+                tool_use_id: Some(id),
+                name,
+                use_index,
+                ..
+            } = part
+            {
+                if let Some((call_index, call_name)) = uses.get(id.as_str()) {
+                    *use_index = Some(*call_index);
+                    name.get_or_insert_with(|| call_name.clone());
+                    results.entry(id.clone()).or_insert(index);
+                }
+            }
+        }
+    }
+
+    for message in messages.iter_mut() {
+        for part in &mut message.parts {
+            if let ContentPart::ToolUse {
+                id: Some(id),
+                result_index,
+                ..
+            } = part
+            {
+                *result_index = results.get(id.as_str()).copied();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_link_tool_calls() {
+        let mut messages = vec![
+            SessionMessage {
+                role: "assistant".to_string(),
+                parts: vec![ContentPart::tool_use(
+                    Some("call_1".to_string()),
+                    "Bash",
+                    json!({"command": "ls"}),
+                )],
+                ..Default::default()
+            },
+            SessionMessage {
+                role: "tool".to_string(),
+                content: "README.md".to_string(),
+                parts: vec![
+                    ContentPart::tool_result(Some("call_1".to_string()), None, "README.md", false),
+                    ContentPart::tool_result(Some("missing".to_string()), None, "", true),
+                ],
+                ..Default::default()
+            },
+        ];
+        link_tool_calls(&mut messages);
+
+        assert!(matches!(
+            &messages[0].parts[0],
+            ContentPart::ToolUse {
+                result_index: Some(1),
+                ..
+            }
+        ));
+        match &messages[1].parts[0] {
+            ContentPart::ToolResult {
+                name, use_index, ..
+            } => {
+                assert_eq!(name.as_deref(), Some("Bash"));
+                assert_eq!(*use_index, Some(0));
+            }
+            other => panic!("unexpected part: {other:?}"),
+        }
+        assert!(matches!(
+            &messages[1].parts[1],
+            ContentPart::ToolResult {
+                use_index: None,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_part_serialization() {
+        let part = ContentPart::tool_result(Some("t1".to_string()), None, "ok", false);
+        assert_eq!(
+            serde_json::to_value(&part).unwrap(),
+            json!({"type": "toolResult", "toolUseId": "t1", "content": "ok"})
+        );
+    }
+}
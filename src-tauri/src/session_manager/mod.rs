@@ -1,12 +1,21 @@
+pub mod analytics;
+pub mod convert;
+pub mod export;
+pub mod live;
+pub mod manage;
+pub mod message;
 pub mod providers;
+pub mod search;
+pub mod summarize;
 pub mod terminal;
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::path::Path;
 
-use providers::{claude, codex, gemini, openclaw, opencode};
+pub use message::{link_tool_calls, ContentPart, MessageUsage};
+use providers::{get_source, sources};
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct SessionMeta {
     pub provider_id: String,
@@ -25,40 +34,52 @@ pub struct SessionMeta {
     pub source_path: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub resume_command: Option<String>,
+    /// Session that spawned this one (Claude sub-agent sidechains).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_session_id: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
 pub struct SessionMessage {
     pub role: String,
+    /// Plain text of the message: text blocks plus tool result output.
     pub content: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ts: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<ContentPart>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<MessageUsage>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub is_sidechain: bool,
+}
+
+impl SessionMessage {
+    /// Whether the message has anything worth showing.
+    pub fn is_empty(&self) -> bool {
+        self.content.trim().is_empty() && self.parts.is_empty()
+    }
 }
 
 pub fn scan_sessions() -> Vec<SessionMeta> {
-    let (r1, r2, r3, r4, r5) = std::thread::scope(|s| {
-        let h1 = s.spawn(codex::scan_sessions);
-        let h2 = s.spawn(claude::scan_sessions);
-        let h3 = s.spawn(opencode::scan_sessions);
-        let h4 = s.spawn(openclaw::scan_sessions);
-        let h5 = s.spawn(gemini::scan_sessions);
-        (
-            h1.join().unwrap_or_default(),
-            h2.join().unwrap_or_default(),
-            h3.join().unwrap_or_default(),
-            h4.join().unwrap_or_default(),
-            h5.join().unwrap_or_default(),
-        )
+    let mut sessions: Vec<SessionMeta> = std::thread::scope(|s| {
+        let handles: Vec<_> = sources()
+            .iter()
+            .map(|source| s.spawn(|| source.scan()))
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap_or_default())
+            .collect()
     });
 
-    let mut sessions = Vec::new();
-    sessions.extend(r1);
-    sessions.extend(r2);
-    sessions.extend(r3);
-    sessions.extend(r4);
-    sessions.extend(r5);
-
     sessions.sort_by(|a, b| {
         let a_ts = a.last_active_at.or(a.created_at).unwrap_or(0);
         let b_ts = b.last_active_at.or(b.created_at).unwrap_or(0);
@@ -68,14 +89,15 @@ pub fn scan_sessions() -> Vec<SessionMeta> {
     sessions
 }
 
+/// Metadata of a single session.
+pub fn find_session(provider_id: &str, source_path: &str) -> Option<SessionMeta> {
+    get_source(provider_id)?.find(source_path)
+}
+
 pub fn load_messages(provider_id: &str, source_path: &str) -> Result<Vec<SessionMessage>, String> {
-    let path = Path::new(source_path);
-    match provider_id {
-        "codex" => codex::load_messages(path),
-        "claude" => claude::load_messages(path),
-        "opencode" => opencode::load_messages(path),
-        "openclaw" => openclaw::load_messages(path),
-        "gemini" => gemini::load_messages(path),
-        _ => Err(format!("Unsupported provider: {provider_id}")),
-    }
+    let source =
+        get_source(provider_id).ok_or_else(|| format!("Unsupported provider: {provider_id}"))?;
+    let mut messages = source.load(Path::new(source_path))?;
+    link_tool_calls(&mut messages);
+    Ok(messages)
 }
@@ -0,0 +1,311 @@
+//! Aider sessions.
+//!
+//! Aider appends every chat to `.aider.chat.history.md` in the project it was
+//! started in. Each chat opens with `# aider chat started at <time>`; prompts
+//! are `#### ` lines, Aider's own output is quoted with `> ` and everything
+//! else is the model's reply. A chat is addressed as `<file>#<start time>`.
+//!
+//! There is no central index, so history files are looked for in the home
+//! directory and the two levels below it (e.g. `~/code/<project>`), plus
+//! `$AIDER_CHAT_HISTORY_FILE`.
+
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use chrono::{Local, NaiveDateTime, TimeZone};
+use sha2::{Digest, Sha256};
+
+use crate::session_manager::{ContentPart, SessionMessage, SessionMeta};
+
+use super::utils::{path_basename, truncate_summary};
+use super::{split_source_path, SessionSource};
+
+const PROVIDER_ID: &str = "aider";
+const HISTORY_FILE: &str = ".aider.chat.history.md";
+const CHAT_HEADER: &str = "# aider chat started at ";
+const PROMPT_PREFIX: &str = "####";
+const TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const KEY_FORMAT: &str = "%Y%m%d%H%M%S";
+const SEARCH_DEPTH: usize = 2;
+
+pub struct AiderSource;
+
+impl SessionSource for AiderSource {
+    fn id(&self) -> &'static str {
+        PROVIDER_ID
+    }
+
+    fn roots(&self) -> Vec<PathBuf> {
+        dirs::home_dir().into_iter().collect()
+    }
+
+    fn scan(&self) -> Vec<SessionMeta> {
+        scan_sessions()
+    }
+
+    fn load(&self, path: &Path) -> Result<Vec<SessionMessage>, String> {
+        load_messages(path)
+    }
+
+    fn find(&self, source_path: &str) -> Option<SessionMeta> {
+        let (file, Some(key)) = split_source_path(source_path) else {
+            return None;
+        };
+        parse_history(Path::new(file))
+            .into_iter()
+            .find(|chat| chat.key == key)
+            .map(|chat| chat.meta)
+    }
+
+    /// Aider restores the project's whole history rather than a single chat.
+    fn resume_command(&self, _session_id: &str) -> Option<String> {
+        Some("aider --restore-chat-history".to_string())
+    }
+
+    fn shares_storage(&self) -> bool {
+        true
+    }
+}
+
+pub fn scan_sessions() -> Vec<SessionMeta> {
+    history_files()
+        .iter()
+        .flat_map(|path| parse_history(path))
+        .map(|chat| chat.meta)
+        .collect()
+}
+
+fn history_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    if let Some(home) = dirs::home_dir() {
+        collect_history_files(&home, SEARCH_DEPTH, &mut files);
+    }
+    if let Some(custom) = std::env::var_os("AIDER_CHAT_HISTORY_FILE").map(PathBuf::from) {
+        if custom.is_file() && !files.contains(&custom) {
+            files.push(custom);
+        }
+    }
+    files
+}
+
+fn collect_history_files(dir: &Path, depth: usize, files: &mut Vec<PathBuf>) {
+    let history = dir.join(HISTORY_FILE);
+    if history.is_file() {
+        files.push(history);
+    }
+    if depth == 0 {
+        return;
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let hidden = entry.file_name().to_string_lossy().starts_with('.');
+        if !hidden && entry.file_type().is_ok_and(|t| t.is_dir()) {
+            collect_history_files(&entry.path(), depth - 1, files);
+        }
+    }
+}
+
+pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
+    let source_path = path.to_string_lossy();
+    let (file, Some(key)) = split_source_path(&source_path) else {
+        return Err("Aider session path has no chat key".to_string());
+    };
+    let data =
+        std::fs::read_to_string(file).map_err(|e| format!("Failed to read chat history: {e}"))?;
+    split_chats(&data)
+        .into_iter()
+        .find(|chat| chat.started_at.format(KEY_FORMAT).to_string() == key)
+        .map(|chat| parse_messages(chat.body, to_ms(&chat.started_at)))
+        .ok_or_else(|| format!("Chat {key} not found in {file}"))
+}
+
+struct RawChat<'a> {
+    started_at: NaiveDateTime,
+    body: &'a str,
+}
+
+struct Chat {
+    key: String,
+    meta: SessionMeta,
+}
+
+fn split_chats(data: &str) -> Vec<RawChat<'_>> {
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    for line in data.split_inclusive('\n') {
+        if let Some(time) = line.strip_prefix(CHAT_HEADER) {
+            if let Ok(started_at) = NaiveDateTime::parse_from_str(time.trim(), TIME_FORMAT) {
+                starts.push((started_at, offset, offset + line.len()));
+            }
+        }
+        offset += line.len();
+    }
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, (started_at, _, body_start))| RawChat {
+            started_at: *started_at,
+            body: &data[*body_start..starts.get(i + 1).map_or(data.len(), |next| next.1)],
+        })
+        .collect()
+}
+
+fn to_ms(time: &NaiveDateTime) -> Option<i64> {
+    Local
+        .from_local_datetime(time)
+        .earliest()
+        .map(|dt| dt.timestamp_millis())
+}
+
+/// Consecutive lines of the same kind form one message.
+fn parse_messages(body: &str, started_at: Option<i64>) -> Vec<SessionMessage> {
+    let mut messages = Vec::new();
+    let mut current: Option<(&str, Vec<&str>)> = None;
+    let mut flush = |current: &mut Option<(&str, Vec<&str>)>| {
+        if let Some((role, lines)) = current.take() {
+            let text = lines.join("\n").trim().to_string();
+            if !text.is_empty() {
+                messages.push(SessionMessage {
+                    role: role.to_string(),
+                    content: text.clone(),
+                    ts: started_at,
+                    parts: vec![ContentPart::text(text)],
+                    ..Default::default()
+                });
+            }
+        }
+    };
+
+    for line in body.lines() {
+        let (role, text) = if let Some(prompt) = line.strip_prefix(PROMPT_PREFIX) {
+            ("user", prompt.strip_prefix(' ').unwrap_or(prompt))
+        } else if let Some(output) = line.strip_prefix('>') {
+            ("system", output.strip_prefix(' ').unwrap_or(output))
+        } else {
+            ("assistant", line)
+        };
+        match current.as_mut() {
+            Some((current_role, lines)) if *current_role == role => lines.push(text),
+            // Blank lines separate blocks; they never start a reply
+            _ if line.trim().is_empty() => {}
+            _ => {
+                flush(&mut current);
+                current = Some((role, vec![text]));
+            }
+        }
+    }
+    flush(&mut current);
+    messages
+}
+
+fn parse_history(path: &Path) -> Vec<Chat> {
+    let Ok(data) = std::fs::read_to_string(path) else {
+        return Vec::new();
+    };
+    let modified_at = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64);
+    let project_dir = path.parent().map(|p| p.to_string_lossy().to_string());
+    let file_hash = format!("{:x}", Sha256::digest(path.to_string_lossy().as_bytes()));
+
+    let chats = split_chats(&data);
+    let count = chats.len();
+    chats
+        .into_iter()
+        .enumerate()
+        .filter_map(|(i, chat)| {
+            let created_at = to_ms(&chat.started_at);
+            let messages = parse_messages(chat.body, created_at);
+            let summary = messages
+                .iter()
+                .find(|m| m.role == "user")
+                .map(|m| truncate_summary(&m.content, 160))?;
+            let key = chat.started_at.format(KEY_FORMAT).to_string();
+            let session_id = format!("{key}-{}", &file_hash[..8]);
+
+            let meta = SessionMeta {
+                provider_id: PROVIDER_ID.to_string(),
+                session_id: session_id.clone(),
+                title: project_dir.as_deref().and_then(path_basename),
+                summary: Some(summary),
+                project_dir: project_dir.clone(),
+                created_at,
+                // Only the latest chat can still be growing
+                last_active_at: if i + 1 == count {
+                    modified_at.or(created_at)
+                } else {
+                    created_at
+                },
+                source_path: Some(format!("{}#{key}", path.to_string_lossy())),
+                resume_command: AiderSource.resume_command(&session_id),
+                parent_session_id: None,
+            };
+            Some(Chat { key, meta })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HISTORY: &str = "
+# aider chat started at 2025-01-01 09:00:00
+
+> /usr/local/bin/aider --model sonnet
+> Aider v0.86.0
+
+# aider chat started at 2025-01-02 10:30:00
+
+> Aider v0.86.0
+
+#### add a footer
+#### with the copyright year
+
+I'll add the footer to the layout.
+
+layout.tsx
+```
+<footer>2025</footer>
+```
+
+> Applied edit to layout.tsx
+
+#### thanks
+
+You're welcome!
+";
+
+    #[test]
+    fn test_parse_history_file() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join(HISTORY_FILE);
+        std::fs::write(&path, HISTORY).expect("write");
+
+        // The first chat has no prompt and is not listed
+        let chats = parse_history(&path);
+        assert_eq!(chats.len(), 1);
+        let meta = &chats[0].meta;
+        assert_eq!(chats[0].key, "20250102103000");
+        assert_eq!(
+            meta.summary.as_deref(),
+            Some("add a footer\nwith the copyright year")
+        );
+        let source_path = meta.source_path.clone().expect("source path");
+        assert!(source_path.ends_with(".aider.chat.history.md#20250102103000"));
+
+        let messages = load_messages(Path::new(&source_path)).expect("load");
+        let roles: Vec<_> = messages.iter().map(|m| m.role.as_str()).collect();
+        assert_eq!(
+            roles,
+            ["system", "user", "assistant", "system", "user", "assistant"]
+        );
+        assert!(messages[2].content.contains("<footer>2025</footer>"));
+        assert_eq!(messages[3].content, "Applied edit to layout.tsx");
+    }
+}
@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
@@ -5,14 +6,53 @@ use std::path::{Path, PathBuf};
 use serde_json::Value;
 
 use crate::config::get_claude_config_dir;
-use crate::session_manager::{SessionMessage, SessionMeta};
+use crate::session_manager::message::token_count;
+use crate::session_manager::{ContentPart, MessageUsage, SessionMessage, SessionMeta};
 
 use super::utils::{
-    extract_text, parse_timestamp_to_ms, path_basename, read_head_tail_lines, truncate_summary,
+    extract_text, parse_timestamp_to_ms, path_basename, read_head_tail_lines, string_field,
+    truncate_summary,
 };
+use super::SessionSource;
 
 const PROVIDER_ID: &str = "claude";
 
+pub struct ClaudeSource;
+
+impl SessionSource for ClaudeSource {
+    fn id(&self) -> &'static str {
+        PROVIDER_ID
+    }
+
+    fn roots(&self) -> Vec<PathBuf> {
+        vec![get_claude_config_dir().join("projects")]
+    }
+
+    fn scan(&self) -> Vec<SessionMeta> {
+        scan_sessions()
+    }
+
+    fn load(&self, path: &Path) -> Result<Vec<SessionMessage>, String> {
+        load_messages(path)
+    }
+
+    fn find(&self, source_path: &str) -> Option<SessionMeta> {
+        parse_session(Path::new(source_path))
+    }
+
+    fn resume_command(&self, session_id: &str) -> Option<String> {
+        Some(format!("claude --resume {session_id}"))
+    }
+
+    fn session_paths(&self, path: &Path) -> Vec<PathBuf> {
+        session_paths(path)
+    }
+
+    fn child_session_paths(&self, path: &Path) -> Vec<PathBuf> {
+        agent_session_paths(path)
+    }
+}
+
 pub fn scan_sessions() -> Vec<SessionMeta> {
     let root = get_claude_config_dir().join("projects");
     let mut files = Vec::new();
@@ -28,10 +68,62 @@ pub fn scan_sessions() -> Vec<SessionMeta> {
     sessions
 }
 
+/// All transcript files, including sub-agent sessions (used for usage ingestion).
+pub fn transcript_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_jsonl_files(&get_claude_config_dir().join("projects"), &mut files);
+    files
+}
+
+/// Everything that belongs to a session: its transcript and the sibling
+/// `<session-id>/` directory. Sub-agent transcripts come from [`agent_session_paths`].
+pub fn session_paths(path: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![path.to_path_buf()];
+    if is_agent_session(path) {
+        return paths;
+    }
+    let (Some(dir), Some(stem)) = (path.parent(), path.file_stem().and_then(|s| s.to_str())) else {
+        return paths;
+    };
+    let companion = dir.join(stem);
+    if companion.is_dir() {
+        paths.push(companion);
+    }
+    paths
+}
+
+/// Sub-agent transcripts spawned by the session at `path`.
+///
+/// Parses every `agent-*.jsonl` sibling; callers handling many sessions should
+/// build the parent → agents map from one scan instead.
+pub fn agent_session_paths(path: &Path) -> Vec<PathBuf> {
+    if is_agent_session(path) {
+        return Vec::new();
+    }
+    let (Some(dir), Some(stem)) = (path.parent(), path.file_stem().and_then(|s| s.to_str())) else {
+        return Vec::new();
+    };
+    let session_id = parse_session(path)
+        .map(|meta| meta.session_id)
+        .unwrap_or_else(|| stem.to_string());
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|child| {
+            is_agent_session(child)
+                && parse_session(child).and_then(|meta| meta.parent_session_id)
+                    == Some(session_id.clone())
+        })
+        .collect()
+}
+
 pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
     let file = File::open(path).map_err(|e| format!("Failed to open session file: {e}"))?;
     let reader = BufReader::new(file);
     let mut messages = Vec::new();
+    let mut parser = LineParser::default();
 
     for line in reader.lines() {
         let line = match line {
@@ -42,14 +134,30 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
             Ok(parsed) => parsed,
             Err(_) => continue,
         };
+        parser.push_line(&value, &mut messages);
+    }
 
+    Ok(messages)
+}
+
+/// Parses a transcript one JSONL line at a time, so appended lines can be
+/// parsed without re-reading the file.
+#[derive(Debug, Default)]
+pub struct LineParser {
+    // Streamed responses repeat the same API message (and its usage) on several lines
+    usage_seen: HashSet<String>,
+}
+
+impl LineParser {
+    /// Append the message carried by `value`, if any, to `messages`.
+    pub fn push_line(&mut self, value: &Value, messages: &mut Vec<SessionMessage>) {
         if value.get("isMeta").and_then(Value::as_bool) == Some(true) {
-            continue;
+            return;
         }
 
         let message = match value.get("message") {
             Some(message) => message,
-            None => continue,
+            None => return,
         };
 
         let role = message
@@ -58,26 +166,111 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
             .unwrap_or("unknown")
             .to_string();
         let content = message.get("content").map(extract_text).unwrap_or_default();
-        if content.trim().is_empty() {
-            continue;
+        let parts = message
+            .get("content")
+            .map(parse_content_parts)
+            .unwrap_or_default();
+        if content.trim().is_empty() && parts.is_empty() {
+            return;
         }
 
         let ts = value.get("timestamp").and_then(parse_timestamp_to_ms);
+        let model = message
+            .get("model")
+            .and_then(Value::as_str)
+            .filter(|model| !model.is_empty() && *model != "<synthetic>")
+            .map(str::to_string);
+        let usage_key = message
+            .get("id")
+            .and_then(Value::as_str)
+            .map(str::to_string);
+        let usage = message
+            .get("usage")
+            .filter(|_| usage_key.is_none_or(|key| self.usage_seen.insert(key)))
+            .map(parse_usage)
+            .filter(|usage| !usage.is_empty());
 
-        messages.push(SessionMessage { role, content, ts });
+        messages.push(SessionMessage {
+            role,
+            content,
+            ts,
+            id: string_field(value, "uuid"),
+            parent_id: string_field(value, "parentUuid"),
+            parts,
+            model,
+            usage,
+            is_sidechain: value.get("isSidechain").and_then(Value::as_bool) == Some(true),
+        });
     }
+}
 
-    Ok(messages)
+fn parse_content_parts(content: &Value) -> Vec<ContentPart> {
+    let items = match content {
+        Value::String(text) if !text.trim().is_empty() => {
+            return vec![ContentPart::text(text.clone())]
+        }
+        Value::Array(items) => items,
+        _ => return Vec::new(),
+    };
+
+    items
+        .iter()
+        .filter_map(|item| match item.get("type").and_then(Value::as_str)? {
+            "text" => item
+                .get("text")
+                .and_then(Value::as_str)
+                .filter(|text| !text.trim().is_empty())
+                .map(ContentPart::text),
+            "thinking" => item
+                .get("thinking")
+                .and_then(Value::as_str)
+                .filter(|text| !text.trim().is_empty())
+                .map(ContentPart::thinking),
+            "redacted_thinking" => Some(ContentPart::Thinking {
+                text: String::new(),
+                redacted: true,
+            }),
+            "tool_use" | "server_tool_use" => Some(ContentPart::tool_use(
+                string_field(item, "id"),
+                item.get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown"),
+                item.get("input").cloned().unwrap_or(Value::Null),
+            )),
+            "tool_result" | "web_search_tool_result" => Some(ContentPart::tool_result(
+                string_field(item, "tool_use_id"),
+                None,
+                item.get("content").map(extract_text).unwrap_or_default(),
+                item.get("is_error").and_then(Value::as_bool) == Some(true),
+            )),
+            "image" => {
+                let source = item.get("source");
+                Some(ContentPart::Image {
+                    media_type: source.and_then(|s| string_field(s, "media_type")),
+                    url: source.and_then(|s| string_field(s, "url")),
+                })
+            }
+            _ => None,
+        })
+        .collect()
 }
 
-fn parse_session(path: &Path) -> Option<SessionMeta> {
-    if is_agent_session(path) {
-        return None;
+fn parse_usage(usage: &Value) -> MessageUsage {
+    MessageUsage {
+        input_tokens: token_count(usage, "input_tokens"),
+        output_tokens: token_count(usage, "output_tokens"),
+        cache_read_tokens: token_count(usage, "cache_read_input_tokens"),
+        cache_creation_tokens: token_count(usage, "cache_creation_input_tokens"),
+        reasoning_tokens: 0,
     }
+}
 
+pub(crate) fn parse_session(path: &Path) -> Option<SessionMeta> {
+    let is_agent = is_agent_session(path);
     let (head, tail) = read_head_tail_lines(path, 10, 30).ok()?;
 
     let mut session_id: Option<String> = None;
+    let mut agent_id: Option<String> = None;
     let mut project_dir: Option<String> = None;
     let mut created_at: Option<i64> = None;
 
@@ -93,6 +286,9 @@ fn parse_session(path: &Path) -> Option<SessionMeta> {
                 .and_then(Value::as_str)
                 .map(|s| s.to_string());
         }
+        if is_agent && agent_id.is_none() {
+            agent_id = string_field(&value, "agentId");
+        }
         if project_dir.is_none() {
             project_dir = value
                 .get("cwd")
@@ -132,8 +328,18 @@ fn parse_session(path: &Path) -> Option<SessionMeta> {
         }
     }
 
-    let session_id = session_id.or_else(|| infer_session_id_from_filename(path));
-    let session_id = session_id?;
+    // Sub-agent transcripts carry the parent's sessionId; identify them by agent instead
+    let (session_id, parent_session_id) = if is_agent {
+        let agent_id = agent_id
+            .map(|id| format!("agent-{id}"))
+            .or_else(|| infer_session_id_from_filename(path))?;
+        (agent_id, session_id)
+    } else {
+        (
+            session_id.or_else(|| infer_session_id_from_filename(path))?,
+            None,
+        )
+    };
 
     let title = project_dir
         .as_deref()
@@ -151,7 +357,12 @@ fn parse_session(path: &Path) -> Option<SessionMeta> {
         created_at,
         last_active_at,
         source_path: Some(path.to_string_lossy().to_string()),
-        resume_command: Some(format!("claude --resume {session_id}")),
+        resume_command: if is_agent {
+            None
+        } else {
+            ClaudeSource.resume_command(&session_id)
+        },
+        parent_session_id,
     })
 }
 
@@ -187,3 +398,66 @@ fn collect_jsonl_files(root: &Path, files: &mut Vec<PathBuf>) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    #[test]
+    fn test_load_messages_keeps_tool_calls_and_usage() {
+        let mut file = tempfile::NamedTempFile::new().expect("temp file");
+        let lines = [
+            r#"{"uuid":"u1","timestamp":"2025-01-01T00:00:00Z","message":{"role":"user","content":"list files"}}"#,
+            r#"{"uuid":"a1","parentUuid":"u1","message":{"id":"msg_1","role":"assistant","model":"claude-sonnet-4-5","content":[{"type":"thinking","thinking":"use ls"}],"usage":{"input_tokens":10,"output_tokens":5,"cache_read_input_tokens":100}}}"#,
+            r#"{"uuid":"a2","parentUuid":"a1","message":{"id":"msg_1","role":"assistant","model":"claude-sonnet-4-5","content":[{"type":"tool_use","id":"toolu_1","name":"Bash","input":{"command":"ls"}}],"usage":{"input_tokens":10,"output_tokens":5,"cache_read_input_tokens":100}}}"#,
+            r#"{"uuid":"r1","parentUuid":"a2","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"toolu_1","content":"README.md"}]}}"#,
+        ];
+        writeln!(file, "{}", lines.join("\n")).expect("write");
+
+        let mut messages = load_messages(file.path()).expect("load");
+        crate::session_manager::link_tool_calls(&mut messages);
+        assert_eq!(messages.len(), 4);
+
+        // Thinking-only and tool-only turns are no longer dropped
+        assert_eq!(messages[1].parts, vec![ContentPart::thinking("use ls")]);
+        assert_eq!(messages[1].model.as_deref(), Some("claude-sonnet-4-5"));
+        assert_eq!(
+            messages[1].usage.as_ref().map(|u| u.cache_read_tokens),
+            Some(100)
+        );
+        // Same API message id: usage is only counted once
+        assert!(messages[2].usage.is_none());
+        assert_eq!(messages[2].parent_id.as_deref(), Some("a1"));
+
+        assert_eq!(messages[3].content, "README.md");
+        match &messages[3].parts[0] {
+            ContentPart::ToolResult {
+                name, use_index, ..
+            } => {
+                assert_eq!(name.as_deref(), Some("Bash"));
+                assert_eq!(*use_index, Some(2));
+            }
+            other => panic!("unexpected part: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_agent_session_links_parent() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("agent-abc123.jsonl");
+        std::fs::write(
+            &path,
+            r#"{"sessionId":"parent-1","agentId":"abc123","isSidechain":true,"cwd":"/work/app","timestamp":"2025-01-01T00:00:00Z","message":{"role":"user","content":"explore"}}"#,
+        )
+        .expect("write");
+
+        let meta = parse_session(&path).expect("agent session");
+        assert_eq!(meta.session_id, "agent-abc123");
+        assert_eq!(meta.parent_session_id.as_deref(), Some("parent-1"));
+        assert!(meta.resume_command.is_none());
+
+        let messages = load_messages(&path).expect("load");
+        assert!(messages[0].is_sidechain);
+    }
+}
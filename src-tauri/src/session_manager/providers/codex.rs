@@ -7,14 +7,45 @@ use regex::Regex;
 use serde_json::Value;
 
 use crate::codex_config::get_codex_config_dir;
-use crate::session_manager::{SessionMessage, SessionMeta};
+use crate::session_manager::message::{token_count, value_to_text};
+use crate::session_manager::{ContentPart, MessageUsage, SessionMessage, SessionMeta};
 
 use super::utils::{
-    extract_text, parse_timestamp_to_ms, path_basename, read_head_tail_lines, truncate_summary,
+    extract_text, parse_timestamp_to_ms, path_basename, read_head_tail_lines, string_field,
+    truncate_summary,
 };
+use super::SessionSource;
 
 const PROVIDER_ID: &str = "codex";
 
+pub struct CodexSource;
+
+impl SessionSource for CodexSource {
+    fn id(&self) -> &'static str {
+        PROVIDER_ID
+    }
+
+    fn roots(&self) -> Vec<PathBuf> {
+        vec![get_codex_config_dir().join("sessions")]
+    }
+
+    fn scan(&self) -> Vec<SessionMeta> {
+        scan_sessions()
+    }
+
+    fn load(&self, path: &Path) -> Result<Vec<SessionMessage>, String> {
+        load_messages(path)
+    }
+
+    fn find(&self, source_path: &str) -> Option<SessionMeta> {
+        parse_session(Path::new(source_path))
+    }
+
+    fn resume_command(&self, session_id: &str) -> Option<String> {
+        Some(format!("codex resume {session_id}"))
+    }
+}
+
 static UUID_RE: LazyLock<Regex> = LazyLock::new(|| {
     Regex::new(r"[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}")
         .unwrap()
@@ -35,10 +66,18 @@ pub fn scan_sessions() -> Vec<SessionMeta> {
     sessions
 }
 
+/// All rollout files under the sessions directory (used for usage ingestion).
+pub fn transcript_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    collect_jsonl_files(&get_codex_config_dir().join("sessions"), &mut files);
+    files
+}
+
 pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
     let file = File::open(path).map_err(|e| format!("Failed to open session file: {e}"))?;
     let reader = BufReader::new(file);
-    let mut messages = Vec::new();
+    let mut messages: Vec<SessionMessage> = Vec::new();
+    let mut parser = LineParser::default();
 
     for line in reader.lines() {
         let line = match line {
@@ -49,39 +88,187 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
             Ok(parsed) => parsed,
             Err(_) => continue,
         };
+        parser.push_line(&value, &mut messages);
+    }
 
-        if value.get("type").and_then(Value::as_str) != Some("response_item") {
-            continue;
-        }
+    Ok(messages)
+}
 
+/// Parses a rollout one JSONL line at a time, so appended lines can be
+/// parsed without re-reading the file.
+#[derive(Debug, Default)]
+pub struct LineParser {
+    // Set by turn_context lines; applies to the assistant output that follows
+    model: Option<String>,
+}
+
+impl LineParser {
+    /// Append the message carried by `value`, if any, to `messages`.
+    ///
+    /// token_count lines attach usage to the latest assistant message in `messages`.
+    pub fn push_line(&mut self, value: &Value, messages: &mut Vec<SessionMessage>) {
         let payload = match value.get("payload") {
             Some(payload) => payload,
-            None => continue,
+            None => return,
         };
+        let ts = value.get("timestamp").and_then(parse_timestamp_to_ms);
 
-        if payload.get("type").and_then(Value::as_str) != Some("message") {
-            continue;
+        match value.get("type").and_then(Value::as_str) {
+            Some("turn_context") => {
+                if let Some(m) = payload.get("model").and_then(Value::as_str) {
+                    self.model = Some(m.to_string());
+                }
+                return;
+            }
+            Some("event_msg") => {
+                if payload.get("type").and_then(Value::as_str) == Some("token_count") {
+                    attach_usage(messages, payload);
+                }
+                return;
+            }
+            Some("response_item") => {}
+            _ => return,
         }
 
-        let role = payload
-            .get("role")
-            .and_then(Value::as_str)
-            .unwrap_or("unknown")
-            .to_string();
-        let content = payload.get("content").map(extract_text).unwrap_or_default();
-        if content.trim().is_empty() {
-            continue;
-        }
+        let message = match payload.get("type").and_then(Value::as_str) {
+            Some("message") => {
+                let role = payload
+                    .get("role")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown")
+                    .to_string();
+                let content = payload.get("content").map(extract_text).unwrap_or_default();
+                if content.trim().is_empty() {
+                    return;
+                }
+                SessionMessage {
+                    parts: vec![ContentPart::text(content.clone())],
+                    role,
+                    content,
+                    ..Default::default()
+                }
+            }
+            Some("reasoning") => {
+                let summary = payload.get("summary").map(extract_text).unwrap_or_default();
+                let part = if summary.trim().is_empty() {
+                    if payload.get("encrypted_content").is_none() {
+                        return;
+                    }
+                    ContentPart::Thinking {
+                        text: String::new(),
+                        redacted: true,
+                    }
+                } else {
+                    ContentPart::thinking(summary)
+                };
+                SessionMessage {
+                    role: "assistant".to_string(),
+                    parts: vec![part],
+                    ..Default::default()
+                }
+            }
+            Some("function_call") | Some("custom_tool_call") | Some("local_shell_call") => {
+                let name = payload
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or("shell");
+                // function_call arguments are a JSON-encoded string
+                let input = payload
+                    .get("arguments")
+                    .or_else(|| payload.get("input"))
+                    .or_else(|| payload.get("action"))
+                    .map(|raw| match raw {
+                        Value::String(text) => {
+                            serde_json::from_str(text).unwrap_or_else(|_| raw.clone())
+                        }
+                        other => other.clone(),
+                    })
+                    .unwrap_or(Value::Null);
+                SessionMessage {
+                    role: "assistant".to_string(),
+                    parts: vec![ContentPart::tool_use(call_id(payload), name, input)],
+                    ..Default::default()
+                }
+            }
+            Some("function_call_output") | Some("custom_tool_call_output") => {
+                let output = payload
+                    .get("output")
+                    .map(tool_output_text)
+                    .unwrap_or_default();
+                SessionMessage {
+                    role: "tool".to_string(),
+                    parts: vec![ContentPart::tool_result(
+                        call_id(payload),
+                        None,
+                        output.clone(),
+                        false,
+                    )],
+                    content: output,
+                    ..Default::default()
+                }
+            }
+            _ => return,
+        };
 
-        let ts = value.get("timestamp").and_then(parse_timestamp_to_ms);
+        let model = (message.role == "assistant")
+            .then(|| self.model.clone())
+            .flatten();
+        messages.push(SessionMessage {
+            ts,
+            id: string_field(payload, "id"),
+            model,
+            ..message
+        });
+    }
+}
+
+fn call_id(payload: &Value) -> Option<String> {
+    string_field(payload, "call_id").or_else(|| string_field(payload, "id"))
+}
 
-        messages.push(SessionMessage { role, content, ts });
+/// Tool output is either plain text or a JSON string wrapping `{output, metadata}`.
+fn tool_output_text(output: &Value) -> String {
+    if let Value::String(text) = output {
+        if let Ok(Value::Object(map)) = serde_json::from_str::<Value>(text) {
+            if let Some(inner) = map.get("output") {
+                return value_to_text(inner);
+            }
+        }
     }
+    value_to_text(output)
+}
 
-    Ok(messages)
+/// token_count events report the usage of the turn that just finished.
+fn attach_usage(messages: &mut [SessionMessage], payload: &Value) {
+    let Some(last) = payload
+        .get("info")
+        .and_then(|info| info.get("last_token_usage"))
+    else {
+        return;
+    };
+    let cached = token_count(last, "cached_input_tokens");
+    let usage = MessageUsage {
+        // Codex counts cached tokens inside input_tokens
+        input_tokens: token_count(last, "input_tokens").saturating_sub(cached),
+        output_tokens: token_count(last, "output_tokens"),
+        cache_read_tokens: cached,
+        cache_creation_tokens: 0,
+        reasoning_tokens: token_count(last, "reasoning_output_tokens"),
+    };
+    if usage.is_empty() {
+        return;
+    }
+    if let Some(message) = messages
+        .iter_mut()
+        .rev()
+        .take_while(|m| m.usage.is_none())
+        .find(|m| m.role == "assistant")
+    {
+        message.usage = Some(usage);
+    }
 }
 
-fn parse_session(path: &Path) -> Option<SessionMeta> {
+pub(crate) fn parse_session(path: &Path) -> Option<SessionMeta> {
     let (head, tail) = read_head_tail_lines(path, 10, 30).ok()?;
 
     let mut session_id: Option<String> = None;
@@ -164,7 +351,8 @@ fn parse_session(path: &Path) -> Option<SessionMeta> {
         created_at,
         last_active_at,
         source_path: Some(path.to_string_lossy().to_string()),
-        resume_command: Some(format!("codex resume {session_id}")),
+        resume_command: CodexSource.resume_command(&session_id),
+        parent_session_id: None,
     })
 }
 
@@ -0,0 +1,366 @@
+//! Crush sessions.
+//!
+//! Crush keeps every session of a project in one SQLite database,
+//! `<project>/.crush/crush.db`, and lists known projects in
+//! `~/.local/share/crush/projects.json`. A session is addressed as
+//! `<crush.db>#<session-id>`.
+
+use std::path::{Path, PathBuf};
+
+use rusqlite::{params, Connection, OpenFlags};
+use serde_json::Value;
+
+use crate::session_manager::message::value_to_text;
+use crate::session_manager::{ContentPart, SessionMessage, SessionMeta};
+
+use super::utils::{path_basename, string_field, truncate_summary};
+use super::{split_source_path, SessionSource};
+
+const PROVIDER_ID: &str = "crush";
+const DB_FILE: &str = "crush.db";
+
+pub struct CrushSource;
+
+impl SessionSource for CrushSource {
+    fn id(&self) -> &'static str {
+        PROVIDER_ID
+    }
+
+    fn roots(&self) -> Vec<PathBuf> {
+        project_data_dirs()
+            .into_iter()
+            .map(|(data_dir, _)| data_dir)
+            .collect()
+    }
+
+    fn scan(&self) -> Vec<SessionMeta> {
+        scan_sessions()
+    }
+
+    fn load(&self, path: &Path) -> Result<Vec<SessionMessage>, String> {
+        load_messages(path)
+    }
+
+    fn find(&self, source_path: &str) -> Option<SessionMeta> {
+        let (db_path, Some(session_id)) = split_source_path(source_path) else {
+            return None;
+        };
+        let db_path = Path::new(db_path);
+        let conn = open_db(db_path).ok()?;
+        let project_dir = project_dir_of(db_path);
+        query_sessions(&conn, db_path, project_dir.as_deref(), Some(session_id))
+            .ok()?
+            .into_iter()
+            .next()
+    }
+
+    /// Crush picks sessions interactively; there is no resume-by-id flag.
+    fn resume_command(&self, _session_id: &str) -> Option<String> {
+        None
+    }
+
+    fn shares_storage(&self) -> bool {
+        true
+    }
+}
+
+fn get_crush_data_dir() -> PathBuf {
+    if let Ok(xdg) = std::env::var("XDG_DATA_HOME") {
+        if !xdg.is_empty() {
+            return PathBuf::from(xdg).join("crush");
+        }
+    }
+    dirs::home_dir()
+        .map(|h| h.join(".local/share/crush"))
+        .unwrap_or_else(|| PathBuf::from(".local/share/crush"))
+}
+
+/// `(data directory, project directory)` of every project Crush has opened.
+fn project_data_dirs() -> Vec<(PathBuf, Option<String>)> {
+    let Ok(data) = std::fs::read_to_string(get_crush_data_dir().join("projects.json")) else {
+        return Vec::new();
+    };
+    let Ok(value) = serde_json::from_str::<Value>(&data) else {
+        return Vec::new();
+    };
+
+    let mut dirs: Vec<(PathBuf, Option<String>)> = Vec::new();
+    for project in value
+        .get("projects")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let path = string_field(project, "path");
+        let data_dir = string_field(project, "data_dir")
+            .map(PathBuf::from)
+            .or_else(|| path.as_ref().map(|p| Path::new(p).join(".crush")));
+        if let Some(data_dir) = data_dir {
+            if !dirs.iter().any(|(known, _)| *known == data_dir) {
+                dirs.push((data_dir, path));
+            }
+        }
+    }
+    dirs
+}
+
+fn open_db(path: &Path) -> Result<Connection, String> {
+    Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(|e| format!("Failed to open Crush database: {e}"))
+}
+
+/// Project of a `<project>/.crush/crush.db` database.
+fn project_dir_of(db_path: &Path) -> Option<String> {
+    let data_dir = db_path.parent()?;
+    project_data_dirs()
+        .into_iter()
+        .find(|(dir, _)| dir == data_dir)
+        .and_then(|(_, project)| project)
+        .or_else(|| {
+            let project = data_dir
+                .parent()
+                .filter(|_| data_dir.file_name() == Some(".crush".as_ref()))?;
+            Some(project.to_string_lossy().to_string())
+        })
+}
+
+pub fn scan_sessions() -> Vec<SessionMeta> {
+    let mut sessions = Vec::new();
+    for (data_dir, project_dir) in project_data_dirs() {
+        let db_path = data_dir.join(DB_FILE);
+        if !db_path.is_file() {
+            continue;
+        }
+        let project_dir = project_dir.or_else(|| project_dir_of(&db_path));
+        match open_db(&db_path)
+            .and_then(|conn| query_sessions(&conn, &db_path, project_dir.as_deref(), None))
+        {
+            Ok(found) => sessions.extend(found),
+            Err(e) => log::debug!("Skipping Crush database {}: {e}", db_path.display()),
+        }
+    }
+    sessions
+}
+
+/// Crush has stored both seconds and milliseconds over time.
+fn to_ms(ts: i64) -> i64 {
+    if ts < 100_000_000_000 {
+        ts * 1000
+    } else {
+        ts
+    }
+}
+
+fn query_sessions(
+    conn: &Connection,
+    db_path: &Path,
+    project_dir: Option<&str>,
+    session_id: Option<&str>,
+) -> Result<Vec<SessionMeta>, String> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT s.id, s.parent_session_id, s.title, s.created_at, s.updated_at,
+                    (SELECT m.parts FROM messages m
+                     WHERE m.session_id = s.id AND m.role = 'user'
+                     ORDER BY m.created_at LIMIT 1)
+             FROM sessions s WHERE ?1 IS NULL OR s.id = ?1",
+        )
+        .map_err(|e| format!("Failed to query Crush sessions: {e}"))?;
+    let rows = stmt
+        .query_map(params![session_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, Option<String>>(2)?,
+                row.get::<_, Option<i64>>(3)?,
+                row.get::<_, Option<i64>>(4)?,
+                row.get::<_, Option<String>>(5)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to query Crush sessions: {e}"))?;
+
+    let mut sessions = Vec::new();
+    for row in rows.flatten() {
+        let (id, parent_id, title, created_at, updated_at, first_prompt) = row;
+        let summary = first_prompt
+            .and_then(|parts| serde_json::from_str::<Value>(&parts).ok())
+            .map(|parts| parse_parts(&parts).0)
+            .filter(|text| !text.trim().is_empty())
+            .map(|text| truncate_summary(&text, 160));
+        let title = title
+            .filter(|t| !t.trim().is_empty())
+            .or_else(|| project_dir.and_then(path_basename));
+        let created_at = created_at.map(to_ms);
+
+        sessions.push(SessionMeta {
+            provider_id: PROVIDER_ID.to_string(),
+            session_id: id.clone(),
+            title,
+            summary,
+            project_dir: project_dir.map(str::to_string),
+            created_at,
+            last_active_at: updated_at.map(to_ms).or(created_at),
+            source_path: Some(format!("{}#{id}", db_path.to_string_lossy())),
+            resume_command: CrushSource.resume_command(&id),
+            parent_session_id: parent_id.filter(|p| !p.is_empty()),
+        });
+    }
+    Ok(sessions)
+}
+
+pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
+    let source_path = path.to_string_lossy();
+    let (db_path, Some(session_id)) = split_source_path(&source_path) else {
+        return Err("Crush session path has no session id".to_string());
+    };
+    let conn = open_db(Path::new(db_path))?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, role, parts, model, created_at FROM messages
+             WHERE session_id = ?1 ORDER BY created_at, rowid",
+        )
+        .map_err(|e| format!("Failed to query Crush messages: {e}"))?;
+    let rows = stmt
+        .query_map([session_id], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+                row.get::<_, Option<i64>>(4)?,
+            ))
+        })
+        .map_err(|e| format!("Failed to query Crush messages: {e}"))?;
+
+    let mut messages = Vec::new();
+    for row in rows.flatten() {
+        let (id, role, parts, model, created_at) = row;
+        let Ok(parts) = serde_json::from_str::<Value>(&parts) else {
+            continue;
+        };
+        let (content, parts) = parse_parts(&parts);
+        if parts.is_empty() {
+            continue;
+        }
+        messages.push(SessionMessage {
+            role,
+            content,
+            ts: created_at.map(to_ms),
+            id: Some(id),
+            parts,
+            model: model.filter(|m| !m.is_empty()),
+            ..Default::default()
+        });
+    }
+    Ok(messages)
+}
+
+/// Plain text and typed parts of a message's `parts` column.
+fn parse_parts(parts: &Value) -> (String, Vec<ContentPart>) {
+    let mut texts = Vec::new();
+    let mut result = Vec::new();
+    for part in parts.as_array().into_iter().flatten() {
+        let data = part.get("data").unwrap_or(&Value::Null);
+        match part.get("type").and_then(Value::as_str) {
+            Some("text") => {
+                if let Some(text) = string_field(data, "text") {
+                    texts.push(text.clone());
+                    result.push(ContentPart::text(text));
+                }
+            }
+            Some("reasoning") => {
+                if let Some(text) = string_field(data, "thinking") {
+                    result.push(ContentPart::thinking(text));
+                }
+            }
+            Some("tool_call") => {
+                // The input is stored as a JSON string
+                let input = data
+                    .get("input")
+                    .and_then(Value::as_str)
+                    .map(|raw| {
+                        serde_json::from_str(raw).unwrap_or_else(|_| Value::String(raw.into()))
+                    })
+                    .unwrap_or(Value::Null);
+                result.push(ContentPart::tool_use(
+                    string_field(data, "id"),
+                    data.get("name")
+                        .and_then(Value::as_str)
+                        .unwrap_or("unknown"),
+                    input,
+                ));
+            }
+            Some("tool_result") => {
+                let content = data.get("content").map(value_to_text).unwrap_or_default();
+                texts.push(content.clone());
+                result.push(ContentPart::tool_result(
+                    string_field(data, "tool_call_id"),
+                    string_field(data, "name"),
+                    content,
+                    data.get("is_error").and_then(Value::as_bool) == Some(true),
+                ));
+            }
+            Some("image_url") => result.push(ContentPart::Image {
+                media_type: None,
+                url: string_field(data, "url").filter(|url| !url.starts_with("data:")),
+            }),
+            Some("binary") => result.push(ContentPart::Image {
+                media_type: string_field(data, "mime_type"),
+                url: string_field(data, "path"),
+            }),
+            _ => {}
+        }
+    }
+    (texts.join("\n"), result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_sessions_from_database() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let db_path = dir.path().join(DB_FILE);
+        let conn = Connection::open(&db_path).expect("db");
+        conn.execute_batch(
+            r#"CREATE TABLE sessions (id TEXT PRIMARY KEY, parent_session_id TEXT, title TEXT,
+                   created_at INTEGER, updated_at INTEGER);
+               CREATE TABLE messages (id TEXT PRIMARY KEY, session_id TEXT, role TEXT,
+                   parts TEXT, model TEXT, created_at INTEGER);
+               INSERT INTO sessions VALUES ('s1', NULL, 'Fix tests', 1735689600, 1735689660);
+               INSERT INTO messages VALUES ('m1', 's1', 'user',
+                   '[{"type":"text","data":{"text":"run the tests"}}]', NULL, 1735689600);
+               INSERT INTO messages VALUES ('m2', 's1', 'assistant',
+                   '[{"type":"reasoning","data":{"thinking":"use go test"}},{"type":"tool_call","data":{"id":"c1","name":"bash","input":"{\"command\":\"go test\"}"}},{"type":"finish","data":{"reason":"tool_use"}}]',
+                   'gpt-5', 1735689601);
+               INSERT INTO messages VALUES ('m3', 's1', 'tool',
+                   '[{"type":"tool_result","data":{"tool_call_id":"c1","name":"bash","content":"ok","is_error":false}}]',
+                   NULL, 1735689602);"#,
+        )
+        .expect("schema");
+
+        let sessions = query_sessions(&conn, &db_path, Some("/work/api"), None).expect("query");
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].title.as_deref(), Some("Fix tests"));
+        assert_eq!(sessions[0].summary.as_deref(), Some("run the tests"));
+        assert_eq!(sessions[0].created_at, Some(1_735_689_600_000));
+        let source_path = sessions[0].source_path.clone().expect("source path");
+        assert!(source_path.ends_with("crush.db#s1"));
+
+        let messages = load_messages(Path::new(&source_path)).expect("load");
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1].parts.len(), 2);
+        match &messages[1].parts[1] {
+            ContentPart::ToolUse { name, input, .. } => {
+                assert_eq!(name, "bash");
+                assert_eq!(input["command"], "go test");
+            }
+            other => panic!("unexpected part: {other:?}"),
+        }
+        assert_eq!(messages[2].content, "ok");
+    }
+}
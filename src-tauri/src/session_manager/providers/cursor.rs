@@ -0,0 +1,347 @@
+//! Cursor CLI (`cursor-agent`) sessions.
+//!
+//! Each chat is a SQLite store at `~/.cursor/chats/<workspace-hash>/<chat-id>/store.db`:
+//! a `meta` table holding hex-encoded JSON about the chat and a content-addressed
+//! `blobs` table. Messages are the JSON blobs with a `role`, in insertion order.
+
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+use std::time::UNIX_EPOCH;
+
+use regex::Regex;
+use rusqlite::{Connection, OpenFlags, OptionalExtension};
+use serde_json::Value;
+
+use crate::session_manager::message::value_to_text;
+use crate::session_manager::{ContentPart, SessionMessage, SessionMeta};
+
+use super::utils::{extract_text, path_basename, string_field, truncate_summary};
+use super::SessionSource;
+
+const PROVIDER_ID: &str = "cursor";
+const STORE_FILE: &str = "store.db";
+/// Name Cursor gives chats until one is generated.
+const DEFAULT_NAME: &str = "New Agent";
+
+static USER_QUERY_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?s)<user_query>\s*(.*?)\s*</user_query>").unwrap());
+static WORKSPACE_RE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"Workspace Path:\s*(\S[^\r\n]*)").unwrap());
+
+pub struct CursorSource;
+
+impl SessionSource for CursorSource {
+    fn id(&self) -> &'static str {
+        PROVIDER_ID
+    }
+
+    fn roots(&self) -> Vec<PathBuf> {
+        vec![get_chats_dir()]
+    }
+
+    fn scan(&self) -> Vec<SessionMeta> {
+        scan_sessions()
+    }
+
+    fn load(&self, path: &Path) -> Result<Vec<SessionMessage>, String> {
+        load_messages(path)
+    }
+
+    fn find(&self, source_path: &str) -> Option<SessionMeta> {
+        parse_session(Path::new(source_path))
+    }
+
+    fn resume_command(&self, session_id: &str) -> Option<String> {
+        Some(format!("cursor-agent --resume {session_id}"))
+    }
+
+    /// The chat directory, including SQLite's journal files.
+    fn session_paths(&self, path: &Path) -> Vec<PathBuf> {
+        vec![path.parent().unwrap_or(path).to_path_buf()]
+    }
+}
+
+fn get_chats_dir() -> PathBuf {
+    dirs::home_dir()
+        .map(|h| h.join(".cursor").join("chats"))
+        .unwrap_or_else(|| PathBuf::from(".cursor").join("chats"))
+}
+
+pub fn scan_sessions() -> Vec<SessionMeta> {
+    let Ok(workspaces) = std::fs::read_dir(get_chats_dir()) else {
+        return Vec::new();
+    };
+    let mut sessions = Vec::new();
+    for workspace in workspaces.flatten() {
+        let Ok(chats) = std::fs::read_dir(workspace.path()) else {
+            continue;
+        };
+        for chat in chats.flatten() {
+            let store = chat.path().join(STORE_FILE);
+            if store.is_file() {
+                sessions.extend(parse_session(&store));
+            }
+        }
+    }
+    sessions
+}
+
+fn open_store(path: &Path) -> Result<Connection, String> {
+    Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .map_err(|e| format!("Failed to open Cursor chat store: {e}"))
+}
+
+/// Chat metadata; the value is hex-encoded JSON in current releases.
+fn read_meta(conn: &Connection) -> Option<Value> {
+    let raw: String = conn
+        .query_row("SELECT value FROM meta WHERE key = '0'", [], |row| {
+            row.get(0)
+        })
+        .optional()
+        .ok()??;
+    serde_json::from_str(&raw)
+        .ok()
+        .or_else(|| serde_json::from_slice(&decode_hex(&raw)?).ok())
+}
+
+fn decode_hex(raw: &str) -> Option<Vec<u8>> {
+    let raw = raw.trim();
+    if raw.len() % 2 != 0 {
+        return None;
+    }
+    (0..raw.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(raw.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// JSON message blobs in the order they were written.
+fn read_message_blobs(conn: &Connection) -> Result<Vec<Value>, String> {
+    let mut stmt = conn
+        .prepare("SELECT data FROM blobs ORDER BY rowid")
+        .map_err(|e| format!("Failed to query Cursor chat store: {e}"))?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, Vec<u8>>(0))
+        .map_err(|e| format!("Failed to query Cursor chat store: {e}"))?;
+    Ok(rows
+        .flatten()
+        .filter(|data| data.first() == Some(&b'{'))
+        .filter_map(|data| serde_json::from_slice::<Value>(&data).ok())
+        .filter(|value| value.get("role").and_then(Value::as_str).is_some())
+        .collect())
+}
+
+pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
+    let conn = open_store(path)?;
+    let mut messages = Vec::new();
+    for blob in read_message_blobs(&conn)? {
+        let role = blob.get("role").and_then(Value::as_str).unwrap_or_default();
+        if role == "system" {
+            continue;
+        }
+        let parts = parse_content(role, blob.get("content").unwrap_or(&Value::Null));
+        if parts.is_empty() {
+            continue;
+        }
+        let content = parts
+            .iter()
+            .filter_map(|part| match part {
+                ContentPart::Text { text } => Some(text.as_str()),
+                ContentPart::ToolResult { content, .. } => Some(content.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        messages.push(SessionMessage {
+            role: role.to_string(),
+            content,
+            id: string_field(&blob, "id"),
+            parts,
+            ..Default::default()
+        });
+    }
+    Ok(messages)
+}
+
+/// The prompt typed by the user, without the context Cursor wraps it in.
+fn user_query(text: &str) -> Option<String> {
+    match USER_QUERY_RE.captures(text) {
+        Some(caps) => Some(caps[1].to_string()),
+        // Messages that are pure context (attached files, rules) carry no query
+        None if text.trim_start().starts_with('<') => None,
+        None => Some(text.to_string()),
+    }
+}
+
+fn parse_content(role: &str, content: &Value) -> Vec<ContentPart> {
+    let items = match content {
+        Value::String(text) => vec![serde_json::json!({ "type": "text", "text": text })],
+        Value::Array(items) => items.clone(),
+        _ => Vec::new(),
+    };
+
+    let mut parts = Vec::new();
+    for item in &items {
+        match item.get("type").and_then(Value::as_str) {
+            Some("text") => {
+                let Some(text) = string_field(item, "text") else {
+                    continue;
+                };
+                let text = if role == "user" {
+                    match user_query(&text) {
+                        Some(query) => query,
+                        None => continue,
+                    }
+                } else {
+                    text
+                };
+                if !text.trim().is_empty() {
+                    parts.push(ContentPart::text(text));
+                }
+            }
+            Some("reasoning") => {
+                if let Some(text) = string_field(item, "text") {
+                    parts.push(ContentPart::thinking(text));
+                }
+            }
+            Some("tool-call") => parts.push(ContentPart::tool_use(
+                string_field(item, "toolCallId"),
+                item.get("toolName")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown"),
+                item.get("args").cloned().unwrap_or(Value::Null),
+            )),
+            Some("tool-result") => parts.push(ContentPart::tool_result(
+                string_field(item, "toolCallId"),
+                string_field(item, "toolName"),
+                item.get("result").map(value_to_text).unwrap_or_default(),
+                item.get("isError").and_then(Value::as_bool) == Some(true),
+            )),
+            Some("image") => parts.push(ContentPart::Image {
+                media_type: string_field(item, "mimeType"),
+                url: None,
+            }),
+            _ => {}
+        }
+    }
+    parts
+}
+
+/// Latest write to the store, including its write-ahead log.
+fn modified_ms(path: &Path) -> Option<i64> {
+    let mut wal = path.as_os_str().to_owned();
+    wal.push("-wal");
+    [path, Path::new(&wal)]
+        .iter()
+        .filter_map(|p| std::fs::metadata(p).ok()?.modified().ok())
+        .filter_map(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .max()
+}
+
+pub(crate) fn parse_session(path: &Path) -> Option<SessionMeta> {
+    let conn = open_store(path).ok()?;
+    let meta = read_meta(&conn)?;
+    let session_id = string_field(&meta, "agentId")
+        .or_else(|| path.parent()?.file_name()?.to_str().map(str::to_string))?;
+
+    let mut project_dir = None;
+    let mut summary = None;
+    for blob in read_message_blobs(&conn).ok()? {
+        if blob.get("role").and_then(Value::as_str) != Some("user") {
+            continue;
+        }
+        let text = blob.get("content").map(extract_text).unwrap_or_default();
+        if project_dir.is_none() {
+            project_dir = WORKSPACE_RE
+                .captures(&text)
+                .map(|caps| caps[1].trim().to_string());
+        }
+        if summary.is_none() {
+            summary = user_query(&text).filter(|query| !query.trim().is_empty());
+        }
+        if project_dir.is_some() && summary.is_some() {
+            break;
+        }
+    }
+
+    let title = string_field(&meta, "name")
+        .filter(|name| name != DEFAULT_NAME)
+        .or_else(|| project_dir.as_deref().and_then(path_basename));
+    let created_at = meta.get("createdAt").and_then(Value::as_i64);
+
+    Some(SessionMeta {
+        provider_id: PROVIDER_ID.to_string(),
+        session_id: session_id.clone(),
+        title,
+        summary: summary.map(|text| truncate_summary(&text, 160)),
+        project_dir,
+        created_at,
+        last_active_at: modified_ms(path).or(created_at),
+        source_path: Some(path.to_string_lossy().to_string()),
+        resume_command: CursorSource.resume_command(&session_id),
+        parent_session_id: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chat_store() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let chat_dir = dir.path().join("abc123");
+        std::fs::create_dir(&chat_dir).expect("chat dir");
+        let path = chat_dir.join(STORE_FILE);
+        let conn = Connection::open(&path).expect("store");
+        conn.execute_batch(
+            "CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT);
+             CREATE TABLE blobs (id TEXT PRIMARY KEY, data BLOB);",
+        )
+        .expect("schema");
+        let meta = r#"{"agentId":"chat-1","name":"New Agent","createdAt":1735689600000}"#;
+        let hex: String = meta.bytes().map(|b| format!("{b:02x}")).collect();
+        conn.execute("INSERT INTO meta VALUES ('0', ?1)", [hex])
+            .expect("meta");
+        let blobs = [
+            r#"{"role":"system","content":"You are a coding agent"}"#,
+            r#"{"role":"user","content":[{"type":"text","text":"<user_info>\nWorkspace Path: /work/site\n</user_info>"}]}"#,
+            r#"{"role":"user","content":[{"type":"text","text":"<user_query>\nadd a footer\n</user_query>"}]}"#,
+            r#"{"role":"assistant","id":"m1","content":[{"type":"reasoning","text":"edit layout"},{"type":"tool-call","toolCallId":"t1","toolName":"edit","args":{"path":"layout.tsx"}}]}"#,
+            r#"{"role":"tool","content":[{"type":"tool-result","toolCallId":"t1","toolName":"edit","result":"done"}]}"#,
+        ];
+        for (i, blob) in blobs.iter().enumerate() {
+            conn.execute(
+                "INSERT INTO blobs VALUES (?1, ?2)",
+                rusqlite::params![format!("b{i}"), blob.as_bytes()],
+            )
+            .expect("blob");
+        }
+        conn.execute(
+            "INSERT INTO blobs VALUES ('root', ?1)",
+            [vec![0x0a_u8, 0x20, 0x01]],
+        )
+        .expect("protobuf blob");
+        drop(conn);
+
+        let meta = parse_session(&path).expect("session");
+        assert_eq!(meta.session_id, "chat-1");
+        assert_eq!(meta.project_dir.as_deref(), Some("/work/site"));
+        assert_eq!(meta.title.as_deref(), Some("site"));
+        assert_eq!(meta.summary.as_deref(), Some("add a footer"));
+        assert_eq!(
+            meta.resume_command.as_deref(),
+            Some("cursor-agent --resume chat-1")
+        );
+
+        let messages = load_messages(&path).expect("load");
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].content, "add a footer");
+        assert_eq!(messages[1].parts[0], ContentPart::thinking("edit layout"));
+        assert_eq!(messages[2].content, "done");
+    }
+}
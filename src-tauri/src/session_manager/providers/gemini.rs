@@ -1,51 +1,74 @@
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use serde_json::Value;
 
-use crate::session_manager::{SessionMessage, SessionMeta};
+use crate::session_manager::message::{token_count, value_to_text};
+use crate::session_manager::{ContentPart, MessageUsage, SessionMessage, SessionMeta};
 
-use super::utils::{parse_timestamp_to_ms, truncate_summary};
+use super::utils::{extract_text, parse_timestamp_to_ms, string_field, truncate_summary};
+use super::SessionSource;
 
 const PROVIDER_ID: &str = "gemini";
 
-pub fn scan_sessions() -> Vec<SessionMeta> {
-    let gemini_dir = crate::gemini_config::get_gemini_dir();
-    let tmp_dir = gemini_dir.join("tmp");
-    if !tmp_dir.exists() {
-        return Vec::new();
+pub struct GeminiSource;
+
+impl SessionSource for GeminiSource {
+    fn id(&self) -> &'static str {
+        PROVIDER_ID
+    }
+
+    fn roots(&self) -> Vec<PathBuf> {
+        vec![crate::gemini_config::get_gemini_dir().join("tmp")]
+    }
+
+    fn scan(&self) -> Vec<SessionMeta> {
+        scan_sessions()
+    }
+
+    fn load(&self, path: &Path) -> Result<Vec<SessionMessage>, String> {
+        load_messages(path)
+    }
+
+    fn find(&self, source_path: &str) -> Option<SessionMeta> {
+        parse_session(Path::new(source_path))
     }
 
-    let mut sessions = Vec::new();
+    fn resume_command(&self, session_id: &str) -> Option<String> {
+        Some(format!("gemini --resume {session_id}"))
+    }
+}
+
+pub fn scan_sessions() -> Vec<SessionMeta> {
+    transcript_files()
+        .iter()
+        .filter_map(|path| parse_session(path))
+        .collect()
+}
 
-    // Iterate over project hash directories: tmp/<project_hash>/chats/session-*.json
+/// All chat files: tmp/<project_hash>/chats/session-*.json
+pub fn transcript_files() -> Vec<PathBuf> {
+    let tmp_dir = crate::gemini_config::get_gemini_dir().join("tmp");
     let project_dirs = match std::fs::read_dir(&tmp_dir) {
         Ok(entries) => entries,
         Err(_) => return Vec::new(),
     };
 
+    let mut files = Vec::new();
     for entry in project_dirs.flatten() {
         let chats_dir = entry.path().join("chats");
-        if !chats_dir.is_dir() {
-            continue;
-        }
-
         let chat_files = match std::fs::read_dir(&chats_dir) {
             Ok(entries) => entries,
             Err(_) => continue,
         };
-
         for file_entry in chat_files.flatten() {
             let path = file_entry.path();
-            if path.extension().and_then(|e| e.to_str()) != Some("json") {
-                continue;
-            }
-            if let Some(meta) = parse_session(&path) {
-                sessions.push(meta);
+            if path.extension().and_then(|e| e.to_str()) == Some("json") {
+                files.push(path);
             }
         }
     }
 
-    sessions
+    files
 }
 
 pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
@@ -60,11 +83,6 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
 
     let mut result = Vec::new();
     for msg in messages {
-        let content = match msg.get("content").and_then(Value::as_str) {
-            Some(c) if !c.trim().is_empty() => c.to_string(),
-            _ => continue,
-        };
-
         let role = match msg.get("type").and_then(Value::as_str) {
             Some("gemini") => "assistant".to_string(),
             Some("user") => "user".to_string(),
@@ -72,15 +90,110 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
             None => continue,
         };
 
-        let ts = msg.get("timestamp").and_then(parse_timestamp_to_ms);
+        let content = msg.get("content").map(extract_text).unwrap_or_default();
+        let mut parts = Vec::new();
+        if let Some(thoughts) = msg.get("thoughts").and_then(Value::as_array) {
+            parts.extend(thoughts.iter().filter_map(parse_thought));
+        }
+        if !content.trim().is_empty() {
+            parts.push(ContentPart::text(content.clone()));
+        }
+        if let Some(calls) = msg.get("toolCalls").and_then(Value::as_array) {
+            for call in calls {
+                parse_tool_call(call, &mut parts);
+            }
+        }
+        if parts.is_empty() {
+            continue;
+        }
 
-        result.push(SessionMessage { role, content, ts });
+        let usage = msg.get("tokens").map(|tokens| {
+            let cached = token_count(tokens, "cached");
+            let thoughts = token_count(tokens, "thoughts");
+            MessageUsage {
+                input_tokens: token_count(tokens, "input").saturating_sub(cached),
+                output_tokens: token_count(tokens, "output") + thoughts,
+                cache_read_tokens: cached,
+                cache_creation_tokens: 0,
+                reasoning_tokens: thoughts,
+            }
+        });
+
+        result.push(SessionMessage {
+            role,
+            content,
+            ts: msg.get("timestamp").and_then(parse_timestamp_to_ms),
+            id: string_field(msg, "id"),
+            parts,
+            model: string_field(msg, "model"),
+            usage: usage.filter(|usage| !usage.is_empty()),
+            ..Default::default()
+        });
     }
 
     Ok(result)
 }
 
-fn parse_session(path: &Path) -> Option<SessionMeta> {
+fn parse_thought(thought: &Value) -> Option<ContentPart> {
+    let subject = thought.get("subject").and_then(Value::as_str).unwrap_or("");
+    let description = thought
+        .get("description")
+        .and_then(Value::as_str)
+        .unwrap_or("");
+    let text = match (subject.trim(), description.trim()) {
+        ("", "") => return None,
+        (subject, "") => subject.to_string(),
+        ("", description) => description.to_string(),
+        (subject, description) => format!("{subject}\n{description}"),
+    };
+    Some(ContentPart::thinking(text))
+}
+
+/// Gemini stores each call together with its result on the assistant message.
+fn parse_tool_call(call: &Value, parts: &mut Vec<ContentPart>) {
+    let id = string_field(call, "id");
+    let name = call
+        .get("name")
+        .and_then(Value::as_str)
+        .unwrap_or("unknown")
+        .to_string();
+    parts.push(ContentPart::tool_use(
+        id.clone(),
+        name.clone(),
+        call.get("args").cloned().unwrap_or(Value::Null),
+    ));
+
+    let output = call
+        .get("result")
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(|item| item.pointer("/functionResponse/response"))
+                .map(|response| {
+                    response
+                        .get("output")
+                        .or_else(|| response.get("error"))
+                        .map(value_to_text)
+                        .unwrap_or_else(|| response.to_string())
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+        .filter(|text| !text.is_empty())
+        .or_else(|| call.get("resultDisplay").map(value_to_text));
+    let status = call.get("status").and_then(Value::as_str);
+    if output.is_some() || status.is_some() {
+        parts.push(ContentPart::tool_result(
+            id,
+            Some(name),
+            output.unwrap_or_default(),
+            matches!(status, Some("error") | Some("cancelled")),
+        ));
+    }
+}
+
+pub(crate) fn parse_session(path: &Path) -> Option<SessionMeta> {
     let data = std::fs::read_to_string(path).ok()?;
     let value: Value = serde_json::from_str(&data).ok()?;
 
@@ -112,6 +225,7 @@ fn parse_session(path: &Path) -> Option<SessionMeta> {
         created_at,
         last_active_at: last_active_at.or(created_at),
         source_path: Some(source_path),
-        resume_command: Some(format!("gemini --resume {session_id}")),
+        resume_command: GeminiSource.resume_command(&session_id),
+        parent_session_id: None,
     })
 }
@@ -1,6 +1,95 @@
+//! Session sources: one per agent CLI whose local transcripts can be read.
+//!
+//! Every source implements [`SessionSource`] and is listed in [`SOURCES`];
+//! scanning, loading and session file management dispatch through the
+//! registry, so adding a CLI only means adding its module here.
+
+pub mod aider;
 pub mod claude;
 pub mod codex;
+pub mod crush;
+pub mod cursor;
 pub mod gemini;
 pub mod openclaw;
 pub mod opencode;
+pub mod qwen;
 mod utils;
+
+use std::path::{Path, PathBuf};
+
+use super::{SessionMessage, SessionMeta};
+
+pub trait SessionSource: Sync {
+    /// Provider id stored on every session of this source.
+    fn id(&self) -> &'static str;
+
+    /// Directories sessions are discovered in. Session files are never
+    /// deleted, archived or restored outside them.
+    fn roots(&self) -> Vec<PathBuf>;
+
+    /// Every session currently on disk.
+    fn scan(&self) -> Vec<SessionMeta>;
+
+    /// Messages of the session stored at `path`.
+    fn load(&self, path: &Path) -> Result<Vec<SessionMessage>, String>;
+
+    /// Metadata of a single session, looked up in a full scan by default.
+    fn find(&self, source_path: &str) -> Option<SessionMeta> {
+        self.scan()
+            .into_iter()
+            .find(|session| session.source_path.as_deref() == Some(source_path))
+    }
+
+    /// Shell command that resumes the session in its CLI, if supported.
+    fn resume_command(&self, session_id: &str) -> Option<String>;
+
+    /// Everything that belongs to the session at `path`, except child sessions.
+    fn session_paths(&self, path: &Path) -> Vec<PathBuf> {
+        vec![path.to_path_buf()]
+    }
+
+    /// Files of child sessions (e.g. sub-agents) stored next to the session at
+    /// `path`, which go wherever their parent goes.
+    fn child_session_paths(&self, _path: &Path) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Whether several sessions share one file, addressed as `<file>#<key>`.
+    /// Such sessions can be read but not deleted or archived on their own.
+    fn shares_storage(&self) -> bool {
+        false
+    }
+}
+
+static SOURCES: &[&dyn SessionSource] = &[
+    &codex::CodexSource,
+    &claude::ClaudeSource,
+    &opencode::OpenCodeSource,
+    &openclaw::OpenClawSource,
+    &gemini::GeminiSource,
+    &qwen::QwenSource,
+    &crush::CrushSource,
+    &cursor::CursorSource,
+    &aider::AiderSource,
+];
+
+/// All registered session sources.
+pub fn sources() -> &'static [&'static dyn SessionSource] {
+    SOURCES
+}
+
+/// The source registered under `provider_id`.
+pub fn get_source(provider_id: &str) -> Option<&'static dyn SessionSource> {
+    SOURCES
+        .iter()
+        .copied()
+        .find(|source| source.id() == provider_id)
+}
+
+/// Split a `<file>#<key>` source path into the shared file and the key.
+pub fn split_source_path(source_path: &str) -> (&str, Option<&str>) {
+    match source_path.rsplit_once('#') {
+        Some((file, key)) if !file.is_empty() && !key.contains(['/', '\\']) => (file, Some(key)),
+        _ => (source_path, None),
+    }
+}
@@ -1,18 +1,50 @@
 use std::fs::File;
 use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use serde_json::Value;
 
 use crate::openclaw_config::get_openclaw_dir;
-use crate::session_manager::{SessionMessage, SessionMeta};
+use crate::session_manager::message::token_count;
+use crate::session_manager::{ContentPart, MessageUsage, SessionMessage, SessionMeta};
 
 use super::utils::{
-    extract_text, parse_timestamp_to_ms, path_basename, read_head_tail_lines, truncate_summary,
+    extract_text, parse_timestamp_to_ms, path_basename, read_head_tail_lines, string_field,
+    truncate_summary,
 };
+use super::SessionSource;
 
 const PROVIDER_ID: &str = "openclaw";
 
+pub struct OpenClawSource;
+
+impl SessionSource for OpenClawSource {
+    fn id(&self) -> &'static str {
+        PROVIDER_ID
+    }
+
+    fn roots(&self) -> Vec<PathBuf> {
+        vec![get_openclaw_dir().join("agents")]
+    }
+
+    fn scan(&self) -> Vec<SessionMeta> {
+        scan_sessions()
+    }
+
+    fn load(&self, path: &Path) -> Result<Vec<SessionMessage>, String> {
+        load_messages(path)
+    }
+
+    fn find(&self, source_path: &str) -> Option<SessionMeta> {
+        parse_session(Path::new(source_path))
+    }
+
+    /// OpenClaw sessions are gateway-managed, no CLI resume.
+    fn resume_command(&self, _session_id: &str) -> Option<String> {
+        None
+    }
+}
+
 pub fn scan_sessions() -> Vec<SessionMeta> {
     let agents_dir = get_openclaw_dir().join("agents");
     if !agents_dir.exists() {
@@ -103,19 +135,90 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
         };
 
         let content = message.get("content").map(extract_text).unwrap_or_default();
-        if content.trim().is_empty() {
+        let mut parts = message
+            .get("content")
+            .map(parse_content_parts)
+            .unwrap_or_default();
+        if raw_role == "toolResult" {
+            // Tool output is the whole message; its text blocks become one result
+            parts = vec![ContentPart::tool_result(
+                string_field(message, "toolCallId"),
+                string_field(message, "toolName"),
+                content.clone(),
+                message.get("isError").and_then(Value::as_bool) == Some(true),
+            )];
+        }
+        if content.trim().is_empty() && parts.is_empty() {
             continue;
         }
 
         let ts = value.get("timestamp").and_then(parse_timestamp_to_ms);
-
-        messages.push(SessionMessage { role, content, ts });
+        let usage = message
+            .get("usage")
+            .map(|usage| MessageUsage {
+                input_tokens: token_count(usage, "input"),
+                output_tokens: token_count(usage, "output"),
+                cache_read_tokens: token_count(usage, "cacheRead"),
+                cache_creation_tokens: token_count(usage, "cacheWrite"),
+                reasoning_tokens: 0,
+            })
+            .filter(|usage| !usage.is_empty());
+
+        messages.push(SessionMessage {
+            role,
+            content,
+            ts,
+            id: string_field(&value, "id"),
+            parent_id: string_field(&value, "parentId"),
+            parts,
+            model: string_field(message, "model"),
+            usage,
+            is_sidechain: false,
+        });
     }
 
     Ok(messages)
 }
 
-fn parse_session(path: &Path) -> Option<SessionMeta> {
+fn parse_content_parts(content: &Value) -> Vec<ContentPart> {
+    let items = match content {
+        Value::String(text) if !text.trim().is_empty() => {
+            return vec![ContentPart::text(text.clone())]
+        }
+        Value::Array(items) => items,
+        _ => return Vec::new(),
+    };
+
+    items
+        .iter()
+        .filter_map(|item| match item.get("type").and_then(Value::as_str)? {
+            "text" => item
+                .get("text")
+                .and_then(Value::as_str)
+                .filter(|text| !text.trim().is_empty())
+                .map(ContentPart::text),
+            "thinking" => item
+                .get("thinking")
+                .and_then(Value::as_str)
+                .filter(|text| !text.trim().is_empty())
+                .map(ContentPart::thinking),
+            "toolCall" => Some(ContentPart::tool_use(
+                string_field(item, "id"),
+                item.get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown"),
+                item.get("arguments").cloned().unwrap_or(Value::Null),
+            )),
+            "image" => Some(ContentPart::Image {
+                media_type: string_field(item, "mimeType"),
+                url: None,
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+pub(crate) fn parse_session(path: &Path) -> Option<SessionMeta> {
     let (head, tail) = read_head_tail_lines(path, 10, 30).ok()?;
 
     let mut session_id: Option<String> = None;
@@ -203,6 +306,7 @@ fn parse_session(path: &Path) -> Option<SessionMeta> {
         created_at,
         last_active_at,
         source_path: Some(path.to_string_lossy().to_string()),
-        resume_command: None, // OpenClaw sessions are gateway-managed, no CLI resume
+        resume_command: OpenClawSource.resume_command(&session_id),
+        parent_session_id: None,
     })
 }
@@ -2,17 +2,49 @@ use std::path::{Path, PathBuf};
 
 use serde_json::Value;
 
-use crate::session_manager::{SessionMessage, SessionMeta};
+use crate::session_manager::message::{token_count, value_to_text};
+use crate::session_manager::{ContentPart, MessageUsage, SessionMessage, SessionMeta};
 
-use super::utils::{parse_timestamp_to_ms, path_basename, truncate_summary};
+use super::utils::{parse_timestamp_to_ms, path_basename, string_field, truncate_summary};
+use super::SessionSource;
 
 const PROVIDER_ID: &str = "opencode";
 
+/// OpenCode spreads a session over several directories, so single sessions
+/// are looked up in a full scan.
+pub struct OpenCodeSource;
+
+impl SessionSource for OpenCodeSource {
+    fn id(&self) -> &'static str {
+        PROVIDER_ID
+    }
+
+    fn roots(&self) -> Vec<PathBuf> {
+        vec![get_opencode_data_dir()]
+    }
+
+    fn scan(&self) -> Vec<SessionMeta> {
+        scan_sessions()
+    }
+
+    fn load(&self, path: &Path) -> Result<Vec<SessionMessage>, String> {
+        load_messages(path)
+    }
+
+    fn resume_command(&self, session_id: &str) -> Option<String> {
+        Some(format!("opencode session resume {session_id}"))
+    }
+
+    fn session_paths(&self, path: &Path) -> Vec<PathBuf> {
+        session_paths(path)
+    }
+}
+
 /// Return the OpenCode data directory.
 ///
 /// Respects `XDG_DATA_HOME` on all platforms; falls back to
 /// `~/.local/share/opencode/storage/`.
-fn get_opencode_data_dir() -> PathBuf {
+pub(crate) fn get_opencode_data_dir() -> PathBuf {
     if let Ok(xdg) = std::env::var("XDG_DATA_HOME") {
         if !xdg.is_empty() {
             return PathBuf::from(xdg).join("opencode").join("storage");
@@ -42,6 +74,46 @@ pub fn scan_sessions() -> Vec<SessionMeta> {
     sessions
 }
 
+/// Everything OpenCode stores for a session, given its message directory:
+/// the session record, the messages, each message's parts and the diff.
+pub fn session_paths(path: &Path) -> Vec<PathBuf> {
+    let (Some(storage), Some(session_id)) = (
+        path.parent().and_then(|p| p.parent()),
+        path.file_name().and_then(|n| n.to_str()),
+    ) else {
+        return vec![path.to_path_buf()];
+    };
+
+    let mut paths = Vec::new();
+    let mut records = Vec::new();
+    collect_json_files(&storage.join("session"), &mut records);
+    paths.extend(
+        records
+            .into_iter()
+            .filter(|p| p.file_stem().and_then(|s| s.to_str()) == Some(session_id)),
+    );
+
+    let mut msg_files = Vec::new();
+    collect_json_files(path, &mut msg_files);
+    for msg_path in msg_files {
+        if let Some(msg_id) = msg_path.file_stem().and_then(|s| s.to_str()) {
+            let part_dir = storage.join("part").join(msg_id);
+            if part_dir.is_dir() {
+                paths.push(part_dir);
+            }
+        }
+    }
+    paths.push(path.to_path_buf());
+
+    let diff = storage
+        .join("session_diff")
+        .join(format!("{session_id}.json"));
+    if diff.is_file() {
+        paths.push(diff);
+    }
+    paths
+}
+
 pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
     // `path` is the message directory: storage/message/{sessionID}/
     if !path.is_dir() {
@@ -56,8 +128,8 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
     let mut msg_files = Vec::new();
     collect_json_files(path, &mut msg_files);
 
-    // Parse all messages and collect (created_ts, message_id, role, parts_text)
-    let mut entries: Vec<(i64, String, String, String)> = Vec::new();
+    // Parse all messages and collect (created_ts, message)
+    let mut entries: Vec<(i64, SessionMessage)> = Vec::new();
 
     for msg_path in &msg_files {
         let data = match std::fs::read_to_string(msg_path) {
@@ -86,29 +158,49 @@ pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
             .and_then(parse_timestamp_to_ms)
             .unwrap_or(0);
 
-        // Collect text parts from storage/part/{messageID}/
+        // Collect parts from storage/part/{messageID}/
         let part_dir = storage.join("part").join(&msg_id);
-        let text = collect_parts_text(&part_dir);
-        if text.trim().is_empty() {
+        let parts = collect_parts(&part_dir);
+        if parts.is_empty() {
             continue;
         }
-
-        entries.push((created_ts, msg_id, role, text));
+        let text = parts_text(&parts);
+
+        let usage = value
+            .get("tokens")
+            .map(|tokens| {
+                let reasoning = token_count(tokens, "reasoning");
+                let cache = tokens.get("cache").cloned().unwrap_or(Value::Null);
+                MessageUsage {
+                    input_tokens: token_count(tokens, "input"),
+                    output_tokens: token_count(tokens, "output") + reasoning,
+                    cache_read_tokens: token_count(&cache, "read"),
+                    cache_creation_tokens: token_count(&cache, "write"),
+                    reasoning_tokens: reasoning,
+                }
+            })
+            .filter(|usage| !usage.is_empty());
+
+        entries.push((
+            created_ts,
+            SessionMessage {
+                role,
+                content: text,
+                ts: (created_ts > 0).then_some(created_ts),
+                parent_id: string_field(&value, "parentID"),
+                parts,
+                model: string_field(&value, "modelID"),
+                usage,
+                id: Some(msg_id),
+                is_sidechain: false,
+            },
+        ));
     }
 
     // Sort by created timestamp
-    entries.sort_by_key(|(ts, _, _, _)| *ts);
-
-    let messages = entries
-        .into_iter()
-        .map(|(ts, _, role, content)| SessionMessage {
-            role,
-            content,
-            ts: if ts > 0 { Some(ts) } else { None },
-        })
-        .collect();
+    entries.sort_by_key(|(ts, _)| *ts);
 
-    Ok(messages)
+    Ok(entries.into_iter().map(|(_, message)| message).collect())
 }
 
 fn parse_session(storage: &Path, path: &Path) -> Option<SessionMeta> {
@@ -164,7 +256,8 @@ fn parse_session(storage: &Path, path: &Path) -> Option<SessionMeta> {
         created_at,
         last_active_at: updated_at.or(created_at),
         source_path: Some(source_path),
-        resume_command: Some(format!("opencode session resume {session_id}")),
+        resume_command: OpenCodeSource.resume_command(&session_id),
+        parent_session_id: None,
     })
 }
 
@@ -213,24 +306,25 @@ fn get_first_user_summary(storage: &Path, session_id: &str) -> Option<String> {
     // Take first user message and get its parts
     let (_, first_id) = user_msgs.first()?;
     let part_dir = storage.join("part").join(first_id);
-    let text = collect_parts_text(&part_dir);
+    let text = parts_text(&collect_parts(&part_dir));
     if text.trim().is_empty() {
         return None;
     }
     Some(truncate_summary(&text, 160))
 }
 
-/// Collect text content from all parts in a part directory.
-fn collect_parts_text(part_dir: &Path) -> String {
+/// Collect all parts of a message, in creation order (part IDs sort chronologically).
+fn collect_parts(part_dir: &Path) -> Vec<ContentPart> {
     if !part_dir.is_dir() {
-        return String::new();
+        return Vec::new();
     }
 
-    let mut parts = Vec::new();
-    collect_json_files(part_dir, &mut parts);
+    let mut part_files = Vec::new();
+    collect_json_files(part_dir, &mut part_files);
+    part_files.sort();
 
-    let mut texts = Vec::new();
-    for part_path in &parts {
+    let mut parts = Vec::new();
+    for part_path in &part_files {
         let data = match std::fs::read_to_string(part_path) {
             Ok(d) => d,
             Err(_) => continue,
@@ -240,19 +334,77 @@ fn collect_parts_text(part_dir: &Path) -> String {
             Err(_) => continue,
         };
 
-        // Only include text-type parts
-        if value.get("type").and_then(Value::as_str) != Some("text") {
-            continue;
-        }
-
-        if let Some(text) = value.get("text").and_then(Value::as_str) {
-            if !text.trim().is_empty() {
-                texts.push(text.to_string());
+        match value.get("type").and_then(Value::as_str) {
+            Some("text") => {
+                if let Some(text) = value.get("text").and_then(Value::as_str) {
+                    if !text.trim().is_empty() {
+                        parts.push(ContentPart::text(text));
+                    }
+                }
+            }
+            Some("reasoning") => {
+                if let Some(text) = value.get("text").and_then(Value::as_str) {
+                    if !text.trim().is_empty() {
+                        parts.push(ContentPart::thinking(text));
+                    }
+                }
             }
+            // A tool part holds both the call and, once finished, its result
+            Some("tool") => {
+                let id = string_field(&value, "callID");
+                let name = value
+                    .get("tool")
+                    .and_then(Value::as_str)
+                    .unwrap_or("unknown")
+                    .to_string();
+                let state = value.get("state").cloned().unwrap_or(Value::Null);
+                parts.push(ContentPart::tool_use(
+                    id.clone(),
+                    name.clone(),
+                    state.get("input").cloned().unwrap_or(Value::Null),
+                ));
+                match state.get("status").and_then(Value::as_str) {
+                    Some("completed") => parts.push(ContentPart::tool_result(
+                        id,
+                        Some(name),
+                        state.get("output").map(value_to_text).unwrap_or_default(),
+                        false,
+                    )),
+                    Some("error") => parts.push(ContentPart::tool_result(
+                        id,
+                        Some(name),
+                        state.get("error").map(value_to_text).unwrap_or_default(),
+                        true,
+                    )),
+                    _ => {}
+                }
+            }
+            Some("file") => {
+                let mime = string_field(&value, "mime");
+                if mime.as_deref().is_some_and(|m| m.starts_with("image/")) {
+                    parts.push(ContentPart::Image {
+                        media_type: mime,
+                        url: string_field(&value, "url").filter(|url| !url.starts_with("data:")),
+                    });
+                }
+            }
+            _ => {}
         }
     }
 
-    texts.join("\n")
+    parts
+}
+
+/// Text parts joined, used as the message's plain content.
+fn parts_text(parts: &[ContentPart]) -> String {
+    parts
+        .iter()
+        .filter_map(|part| match part {
+            ContentPart::Text { text } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 fn collect_json_files(root: &Path, files: &mut Vec<PathBuf>) {
@@ -0,0 +1,303 @@
+//! Qwen Code sessions.
+//!
+//! Current releases record every chat as JSONL under
+//! `~/.qwen/projects/<project>/chats/<session-id>.jsonl`, one Gemini-style
+//! `Content` per line. Older releases kept Gemini CLI's checkpoint files in
+//! `~/.qwen/tmp/<project_hash>/chats/`, which are read with the Gemini parser.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::session_manager::message::{token_count, value_to_text};
+use crate::session_manager::{ContentPart, MessageUsage, SessionMessage, SessionMeta};
+
+use super::gemini;
+use super::utils::{
+    extract_text, parse_timestamp_to_ms, path_basename, read_head_tail_lines, string_field,
+    truncate_summary,
+};
+use super::SessionSource;
+
+const PROVIDER_ID: &str = "qwen";
+
+pub struct QwenSource;
+
+impl SessionSource for QwenSource {
+    fn id(&self) -> &'static str {
+        PROVIDER_ID
+    }
+
+    fn roots(&self) -> Vec<PathBuf> {
+        vec![get_qwen_dir()]
+    }
+
+    fn scan(&self) -> Vec<SessionMeta> {
+        scan_sessions()
+    }
+
+    fn load(&self, path: &Path) -> Result<Vec<SessionMessage>, String> {
+        load_messages(path)
+    }
+
+    fn find(&self, source_path: &str) -> Option<SessionMeta> {
+        parse_session(Path::new(source_path))
+    }
+
+    fn resume_command(&self, session_id: &str) -> Option<String> {
+        Some(format!("qwen --resume {session_id}"))
+    }
+}
+
+fn get_qwen_dir() -> PathBuf {
+    dirs::home_dir()
+        .map(|h| h.join(".qwen"))
+        .unwrap_or_else(|| PathBuf::from(".qwen"))
+}
+
+pub fn scan_sessions() -> Vec<SessionMeta> {
+    let root = get_qwen_dir();
+    let mut files = Vec::new();
+    for dir in ["projects", "tmp"] {
+        collect_chat_files(&root.join(dir), &mut files);
+    }
+    files
+        .iter()
+        .filter_map(|path| parse_session(path))
+        .collect()
+}
+
+/// Chat files one level below each project directory: `<project>/chats/*`.
+fn collect_chat_files(root: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(projects) = std::fs::read_dir(root) else {
+        return;
+    };
+    for project in projects.flatten() {
+        let Ok(chats) = std::fs::read_dir(project.path().join("chats")) else {
+            continue;
+        };
+        for entry in chats.flatten() {
+            let path = entry.path();
+            if matches!(
+                path.extension().and_then(|ext| ext.to_str()),
+                Some("jsonl") | Some("json")
+            ) {
+                files.push(path);
+            }
+        }
+    }
+}
+
+fn is_checkpoint(path: &Path) -> bool {
+    path.extension().and_then(|ext| ext.to_str()) == Some("json")
+}
+
+pub fn load_messages(path: &Path) -> Result<Vec<SessionMessage>, String> {
+    if is_checkpoint(path) {
+        return gemini::load_messages(path);
+    }
+
+    let file = File::open(path).map_err(|e| format!("Failed to open session file: {e}"))?;
+    let reader = BufReader::new(file);
+
+    let mut messages = Vec::new();
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            continue;
+        };
+        let Ok(value) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        if let Some(message) = parse_record(&value) {
+            messages.push(message);
+        }
+    }
+    Ok(messages)
+}
+
+fn parse_record(value: &Value) -> Option<SessionMessage> {
+    let role = match value.get("type").and_then(Value::as_str)? {
+        "user" => "user",
+        "assistant" => "assistant",
+        "tool_result" => "tool",
+        _ => return None,
+    };
+    let parts: Vec<ContentPart> = value
+        .pointer("/message/parts")
+        .and_then(Value::as_array)
+        .map(|parts| parts.iter().filter_map(parse_part).collect())
+        .unwrap_or_default();
+    if parts.is_empty() {
+        return None;
+    }
+
+    let content = parts
+        .iter()
+        .filter_map(|part| match part {
+            ContentPart::Text { text } => Some(text.as_str()),
+            ContentPart::ToolResult { content, .. } => Some(content.as_str()),
+            _ => None,
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let usage = value.get("usageMetadata").map(parse_usage);
+
+    Some(SessionMessage {
+        role: role.to_string(),
+        content,
+        ts: value.get("timestamp").and_then(parse_timestamp_to_ms),
+        id: string_field(value, "uuid"),
+        parent_id: string_field(value, "parentUuid"),
+        parts,
+        model: string_field(value, "model"),
+        usage: usage.filter(|usage| !usage.is_empty()),
+        ..Default::default()
+    })
+}
+
+fn parse_part(part: &Value) -> Option<ContentPart> {
+    if let Some(call) = part.get("functionCall") {
+        return Some(ContentPart::tool_use(
+            string_field(call, "id"),
+            call.get("name")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown"),
+            call.get("args").cloned().unwrap_or(Value::Null),
+        ));
+    }
+    if let Some(response) = part.get("functionResponse") {
+        let body = response.get("response").unwrap_or(&Value::Null);
+        let error = body.get("error");
+        let output = body
+            .get("output")
+            .or(error)
+            .map(value_to_text)
+            .unwrap_or_else(|| value_to_text(body));
+        return Some(ContentPart::tool_result(
+            string_field(response, "id"),
+            string_field(response, "name"),
+            output,
+            error.is_some(),
+        ));
+    }
+    let text = part.get("text").and_then(Value::as_str)?;
+    if text.trim().is_empty() {
+        return None;
+    }
+    if part.get("thought").and_then(Value::as_bool) == Some(true) {
+        Some(ContentPart::thinking(text))
+    } else {
+        Some(ContentPart::text(text))
+    }
+}
+
+fn parse_usage(usage: &Value) -> MessageUsage {
+    let cached = token_count(usage, "cachedContentTokenCount");
+    let thoughts = token_count(usage, "thoughtsTokenCount");
+    MessageUsage {
+        input_tokens: token_count(usage, "promptTokenCount").saturating_sub(cached),
+        output_tokens: token_count(usage, "candidatesTokenCount") + thoughts,
+        cache_read_tokens: cached,
+        cache_creation_tokens: 0,
+        reasoning_tokens: thoughts,
+    }
+}
+
+pub(crate) fn parse_session(path: &Path) -> Option<SessionMeta> {
+    if is_checkpoint(path) {
+        let meta = gemini::parse_session(path)?;
+        return Some(SessionMeta {
+            provider_id: PROVIDER_ID.to_string(),
+            resume_command: QwenSource.resume_command(&meta.session_id),
+            ..meta
+        });
+    }
+
+    let (head, tail) = read_head_tail_lines(path, 10, 30).ok()?;
+
+    let mut session_id: Option<String> = None;
+    let mut project_dir: Option<String> = None;
+    let mut created_at: Option<i64> = None;
+    let mut summary: Option<String> = None;
+    for value in head
+        .iter()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+    {
+        if session_id.is_none() {
+            session_id = string_field(&value, "sessionId");
+        }
+        if project_dir.is_none() {
+            project_dir = string_field(&value, "cwd");
+        }
+        if created_at.is_none() {
+            created_at = value.get("timestamp").and_then(parse_timestamp_to_ms);
+        }
+        if summary.is_none() && value.get("type").and_then(Value::as_str) == Some("user") {
+            summary = value
+                .pointer("/message/parts")
+                .map(extract_text)
+                .filter(|text| !text.trim().is_empty());
+        }
+    }
+
+    let last_active_at = tail
+        .iter()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .find_map(|value| value.get("timestamp").and_then(parse_timestamp_to_ms));
+
+    let session_id = session_id.or_else(|| {
+        path.file_stem()
+            .and_then(|stem| stem.to_str())
+            .map(str::to_string)
+    })?;
+    let title = project_dir.as_deref().and_then(path_basename);
+
+    Some(SessionMeta {
+        provider_id: PROVIDER_ID.to_string(),
+        session_id: session_id.clone(),
+        title,
+        summary: summary.map(|text| truncate_summary(&text, 160)),
+        project_dir,
+        created_at,
+        last_active_at: last_active_at.or(created_at),
+        source_path: Some(path.to_string_lossy().to_string()),
+        resume_command: QwenSource.resume_command(&session_id),
+        parent_session_id: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_jsonl_session() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("s1.jsonl");
+        let lines = [
+            r#"{"uuid":"u1","parentUuid":null,"sessionId":"s1","timestamp":"2025-01-01T00:00:00Z","type":"user","cwd":"/work/app","message":{"role":"user","parts":[{"text":"read the readme"}]}}"#,
+            r#"{"uuid":"a1","parentUuid":"u1","sessionId":"s1","timestamp":"2025-01-01T00:00:05Z","type":"assistant","model":"qwen3-coder-plus","message":{"role":"model","parts":[{"text":"checking","thought":true},{"functionCall":{"id":"call_1","name":"read_file","args":{"path":"README.md"}}}]},"usageMetadata":{"promptTokenCount":120,"candidatesTokenCount":8,"cachedContentTokenCount":100}}"#,
+            r#"{"uuid":"t1","parentUuid":"a1","sessionId":"s1","timestamp":"2025-01-01T00:00:06Z","type":"tool_result","message":{"role":"user","parts":[{"functionResponse":{"id":"call_1","name":"read_file","response":{"output":"hello"}}}]}}"#,
+            r#"{"uuid":"x1","parentUuid":"t1","sessionId":"s1","timestamp":"2025-01-01T00:00:07Z","type":"system","subtype":"chat_compression"}"#,
+        ];
+        std::fs::write(&path, lines.join("\n")).expect("write");
+
+        let meta = parse_session(&path).expect("session");
+        assert_eq!(meta.session_id, "s1");
+        assert_eq!(meta.title.as_deref(), Some("app"));
+        assert_eq!(meta.summary.as_deref(), Some("read the readme"));
+        assert_eq!(meta.resume_command.as_deref(), Some("qwen --resume s1"));
+        assert!(meta.last_active_at > meta.created_at);
+
+        let messages = load_messages(&path).expect("load");
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1].parts[0], ContentPart::thinking("checking"));
+        let usage = messages[1].usage.as_ref().expect("usage");
+        assert_eq!((usage.input_tokens, usage.cache_read_tokens), (20, 100));
+        assert_eq!(messages[2].role, "tool");
+        assert_eq!(messages[2].content, "hello");
+    }
+}
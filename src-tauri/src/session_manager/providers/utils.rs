@@ -52,6 +52,15 @@ pub fn parse_timestamp_to_ms(value: &Value) -> Option<i64> {
         .map(|dt: DateTime<FixedOffset>| dt.timestamp_millis())
 }
 
+/// Non-empty string field of a JSON object.
+pub fn string_field(value: &Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(Value::as_str)
+        .filter(|s| !s.is_empty())
+        .map(str::to_string)
+}
+
 pub fn extract_text(content: &Value) -> String {
     match content {
         Value::String(text) => text.to_string(),
@@ -0,0 +1,384 @@
+//! Full-text search across CLI sessions.
+//!
+//! Message text is kept in a persistent SQLite FTS5 index. The index is
+//! refreshed incrementally: a session is only re-parsed when its source
+//! file's size or modification time changed since it was last indexed.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::database::Database;
+use crate::error::AppError;
+
+use super::providers::split_source_path;
+use super::{load_messages, scan_sessions};
+
+/// Marker inserted before a highlighted match (private use code point).
+pub const HIGHLIGHT_START: &str = "\u{E000}";
+/// Marker inserted after a highlighted match.
+pub const HIGHLIGHT_END: &str = "\u{E001}";
+
+/// Bump when parsing changes which messages a session yields, so stored
+/// message offsets are rebuilt.
+const INDEX_VERSION: u32 = 2;
+
+/// Searches reuse the index without rescanning for this long.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+static LAST_REFRESH: Mutex<Option<Instant>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionSearchQuery {
+    pub query: String,
+    pub provider_id: Option<String>,
+    /// Matches the directory itself and anything below it.
+    pub project_dir: Option<String>,
+    pub start_ms: Option<i64>,
+    pub end_ms: Option<i64>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+impl SessionSearchQuery {
+    /// Split the query into terms; double quotes keep a phrase together.
+    pub fn terms(&self) -> Vec<String> {
+        let mut terms = Vec::new();
+        let mut current = String::new();
+        let mut quoted = false;
+        for ch in self.query.chars() {
+            match ch {
+                '"' => {
+                    quoted = !quoted;
+                    push_term(&mut terms, &mut current);
+                }
+                c if c.is_whitespace() && !quoted => push_term(&mut terms, &mut current),
+                c => current.push(c),
+            }
+        }
+        push_term(&mut terms, &mut current);
+        terms
+    }
+}
+
+fn push_term(terms: &mut Vec<String>, current: &mut String) {
+    let term = current.trim();
+    if !term.is_empty() && !terms.iter().any(|t| t == term) {
+        terms.push(term.to_string());
+    }
+    current.clear();
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSearchHit {
+    pub provider_id: String,
+    pub session_id: String,
+    pub source_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_dir: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_active_at: Option<i64>,
+    /// Position of the message in `load_messages` output.
+    pub message_index: u32,
+    pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ts: Option<i64>,
+    pub snippet: String,
+    /// `[start, end)` ranges in `snippet`, counted in UTF-16 code units.
+    pub highlights: Vec<[u32; 2]>,
+    /// Relevance, higher is better (0 when only substring matching was used).
+    pub score: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionIndexStats {
+    pub scanned: usize,
+    pub indexed: usize,
+    pub unchanged: usize,
+    pub removed: usize,
+    pub failed: usize,
+    pub duration_ms: u64,
+}
+
+/// Session metadata stored alongside the indexed messages.
+#[derive(Debug, Clone)]
+pub struct IndexedSession {
+    pub source_path: String,
+    pub provider_id: String,
+    pub session_id: String,
+    pub title: Option<String>,
+    pub project_dir: Option<String>,
+    pub created_at: Option<i64>,
+    pub last_active_at: Option<i64>,
+    pub file_size: i64,
+    pub modified_at: i64,
+}
+
+/// Size and modification time (ms) of a session source.
+///
+/// OpenCode sessions are directories of message files, so directories
+/// report their total size and latest modification time. Sessions that
+/// share a file (`<file>#<key>`) report the shared file.
+pub fn fingerprint(path: &Path) -> Option<(i64, i64)> {
+    let meta = match std::fs::metadata(path) {
+        Ok(meta) => meta,
+        Err(_) => {
+            let (file, key) = split_source_path(path.to_str()?);
+            key?;
+            return fingerprint(Path::new(file));
+        }
+    };
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0);
+    if !meta.is_dir() {
+        return Some((meta.len() as i64, modified));
+    }
+
+    let (mut size, mut latest) = (0, modified);
+    for entry in std::fs::read_dir(path).ok()?.flatten() {
+        if let Some((s, m)) = fingerprint(&entry.path()) {
+            size += s;
+            latest = latest.max(m);
+        }
+    }
+    Some((size, latest))
+}
+
+/// Bring the index in line with the sessions currently on disk.
+pub fn refresh_index(db: &Database) -> Result<SessionIndexStats, AppError> {
+    let mut last = LAST_REFRESH.lock().unwrap_or_else(|e| e.into_inner());
+    let stats = refresh_locked(db)?;
+    *last = Some(Instant::now());
+    Ok(stats)
+}
+
+/// Refresh the index unless it was refreshed recently.
+pub fn ensure_fresh(db: &Database) -> Result<(), AppError> {
+    let mut last = LAST_REFRESH.lock().unwrap_or_else(|e| e.into_inner());
+    if last.is_some_and(|t| t.elapsed() < REFRESH_INTERVAL) {
+        return Ok(());
+    }
+    refresh_locked(db)?;
+    *last = Some(Instant::now());
+    Ok(())
+}
+
+/// Search indexed session messages.
+pub fn search(
+    db: &Database,
+    query: &SessionSearchQuery,
+) -> Result<Vec<SessionSearchHit>, AppError> {
+    if query.terms().is_empty() {
+        return Ok(Vec::new());
+    }
+    ensure_fresh(db)?;
+    db.search_session_index(query)
+}
+
+fn refresh_locked(db: &Database) -> Result<SessionIndexStats, AppError> {
+    let started = Instant::now();
+    let mut stats = SessionIndexStats::default();
+    db.reset_session_index_if_outdated(INDEX_VERSION)?;
+    let mut indexed = db.get_session_index_fingerprints()?;
+    let mut seen = HashSet::new();
+
+    for meta in scan_sessions() {
+        let Some(source_path) = meta.source_path.clone() else {
+            continue;
+        };
+        if !seen.insert(source_path.clone()) {
+            continue;
+        }
+        stats.scanned += 1;
+
+        let Some((file_size, modified_at)) = fingerprint(Path::new(&source_path)) else {
+            stats.failed += 1;
+            continue;
+        };
+        if indexed.remove(&source_path) == Some((file_size, modified_at)) {
+            stats.unchanged += 1;
+            continue;
+        }
+
+        let messages = match load_messages(&meta.provider_id, &source_path) {
+            Ok(messages) => messages,
+            Err(e) => {
+                log::debug!("Skipping session {source_path} while indexing: {e}");
+                stats.failed += 1;
+                continue;
+            }
+        };
+        let session = IndexedSession {
+            source_path,
+            provider_id: meta.provider_id,
+            session_id: meta.session_id,
+            title: meta.title,
+            project_dir: meta.project_dir,
+            created_at: meta.created_at,
+            last_active_at: meta.last_active_at,
+            file_size,
+            modified_at,
+        };
+        db.replace_session_index(&session, &messages)?;
+        stats.indexed += 1;
+    }
+
+    // Anything left was indexed before but no longer exists on disk.
+    let stale: Vec<String> = indexed.into_keys().collect();
+    stats.removed = db.remove_session_index(&stale)?;
+    stats.duration_ms = started.elapsed().as_millis() as u64;
+    log::debug!(
+        "Session index refreshed: {} indexed, {} unchanged, {} removed, {} failed in {}ms",
+        stats.indexed,
+        stats.unchanged,
+        stats.removed,
+        stats.failed,
+        stats.duration_ms
+    );
+    Ok(stats)
+}
+
+/// Build a marked snippet around the first case-insensitive match of any term.
+///
+/// Used when the query has no term long enough for the trigram index, so
+/// FTS5 `snippet()` is not available.
+pub fn mark_snippet(content: &str, terms: &[&String], context_chars: usize) -> String {
+    let haystack = content.to_lowercase();
+    // Lowercasing may change byte lengths outside ASCII; fall back to plain text then.
+    if haystack.len() != content.len() {
+        return truncate_chars(content, context_chars * 2);
+    }
+    let needles: Vec<String> = terms.iter().map(|t| t.to_lowercase()).collect();
+
+    let Some(first) = needles
+        .iter()
+        .filter_map(|n| haystack.find(n.as_str()))
+        .min()
+    else {
+        return truncate_chars(content, context_chars * 2);
+    };
+    let start = content[..first]
+        .char_indices()
+        .rev()
+        .nth(context_chars.saturating_sub(1))
+        .map(|(i, _)| i)
+        .unwrap_or(0);
+    let end = content[first..]
+        .char_indices()
+        .nth(context_chars)
+        .map(|(i, _)| first + i)
+        .unwrap_or(content.len());
+
+    let mut marked = String::new();
+    if start > 0 {
+        marked.push('…');
+    }
+    let mut pos = start;
+    while pos < end {
+        let next = needles
+            .iter()
+            .filter(|n| !n.is_empty())
+            .filter_map(|n| {
+                haystack[pos..end]
+                    .find(n.as_str())
+                    .map(|i| (pos + i, n.len()))
+            })
+            .min_by_key(|&(i, len)| (i, std::cmp::Reverse(len)));
+        let Some((at, len)) = next else {
+            break;
+        };
+        let stop = (at + len).min(end);
+        marked.push_str(&content[pos..at]);
+        marked.push_str(HIGHLIGHT_START);
+        marked.push_str(&content[at..stop]);
+        marked.push_str(HIGHLIGHT_END);
+        pos = stop;
+    }
+    marked.push_str(&content[pos..end]);
+    if end < content.len() {
+        marked.push('…');
+    }
+    marked
+}
+
+fn truncate_chars(content: &str, max: usize) -> String {
+    match content.char_indices().nth(max) {
+        Some((i, _)) => format!("{}…", &content[..i]),
+        None => content.to_string(),
+    }
+}
+
+/// Strip highlight markers, returning the clean text and UTF-16 match ranges.
+pub fn parse_marked_snippet(marked: &str) -> (String, Vec<[u32; 2]>) {
+    let mut text = String::with_capacity(marked.len());
+    let mut ranges = Vec::new();
+    let mut offset = 0u32;
+    let mut open = None;
+    for ch in marked.chars() {
+        match ch {
+            '\u{E000}' => open = Some(offset),
+            '\u{E001}' => {
+                if let Some(start) = open.take() {
+                    if offset > start {
+                        ranges.push([start, offset]);
+                    }
+                }
+            }
+            c => {
+                text.push(c);
+                offset += c.len_utf16() as u32;
+            }
+        }
+    }
+    (text, ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_terms() {
+        let query = SessionSearchQuery {
+            query: r#"  fix "flaky test"  fix 迁移 "#.to_string(),
+            ..Default::default()
+        };
+        assert_eq!(query.terms(), vec!["fix", "flaky test", "迁移"]);
+    }
+
+    #[test]
+    fn test_parse_marked_snippet() {
+        let marked = format!(
+            "…数据库{HIGHLIGHT_START}迁移{HIGHLIGHT_END} 😀 {HIGHLIGHT_START}ok{HIGHLIGHT_END}"
+        );
+        let (text, ranges) = parse_marked_snippet(&marked);
+        assert_eq!(text, "…数据库迁移 😀 ok");
+        // 😀 is two UTF-16 code units
+        assert_eq!(ranges, vec![[4, 6], [10, 12]]);
+    }
+
+    #[test]
+    fn test_mark_snippet() {
+        let term = "ab".to_string();
+        let marked = mark_snippet("xxxx AB yy ab zzzz", &[&term], 3);
+        let (text, ranges) = parse_marked_snippet(&marked);
+        assert_eq!(text, "…xx AB …");
+        assert_eq!(ranges, vec![[4, 6]]);
+
+        let (text, ranges) = parse_marked_snippet(&mark_snippet("no match", &[&term], 3));
+        assert_eq!(text, "no mat…");
+        assert!(ranges.is_empty());
+    }
+}
@@ -0,0 +1,809 @@
+//! Generated session titles, summaries and keywords.
+//!
+//! An opt-in background worker sends a compacted transcript of each idle
+//! session to a configured provider, through the same adapters, auth and HTTP
+//! clients the proxy uses. Every request is logged like a proxy request, and
+//! the spend of those logs is checked against a daily cap before each call.
+//! A session is summarized again only when its file changed.
+
+use std::path::Path;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use reqwest::Client;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter, Manager};
+
+use crate::app_config::AppType;
+use crate::database::Database;
+use crate::error::AppError;
+use crate::provider::Provider;
+use crate::proxy::providers::{get_adapter, AuthInfo, AuthStrategy};
+use crate::proxy::usage::logger::UsageLogger;
+use crate::proxy::usage::parser::TokenUsage;
+use crate::store::AppState;
+
+use super::convert::is_injected;
+use super::search::fingerprint;
+use super::{find_session, load_messages, scan_sessions, ContentPart, SessionMessage, SessionMeta};
+
+pub const EVENT_UPDATED: &str = "session-summary-updated";
+
+/// Prefix of the request ids logged for summaries; the spend cap sums these.
+pub const REQUEST_ID_PREFIX: &str = "session-summary-";
+
+/// How often the disabled worker checks whether it was turned on.
+const DISABLED_RECHECK: Duration = Duration::from_secs(60);
+/// Sessions written to this recently may still be running and are left for later.
+const SETTLE_MS: i64 = 10 * 60_000;
+/// Summaries generated per pass, so a first run over old sessions is spread out.
+const MAX_PER_PASS: usize = 10;
+/// Each message is cut to this many characters in the compacted transcript.
+const MESSAGE_CHARS: usize = 600;
+const MAX_OUTPUT_TOKENS: u32 = 400;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(90);
+
+const SYSTEM_PROMPT: &str =
+    "You summarize transcripts of coding-agent sessions so they can be found again later. \
+Reply with a single JSON object and nothing else: \
+{\"title\": \"at most 8 words\", \"summary\": \"2-3 sentences on the goal and the outcome\", \
+\"keywords\": [\"3 to 8 short lowercase keywords: technologies, files, problems\"]}. \
+Write in the language the user wrote in.";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SessionSummaryConfig {
+    pub enabled: bool,
+    /// App whose provider answers the requests: claude, codex or gemini.
+    pub app_type: String,
+    /// Unset to use the app's current provider.
+    pub provider_id: Option<String>,
+    /// Unset to use the stream-check model configured for the app.
+    pub model: Option<String>,
+    /// USD per day across all summary requests; "0" pauses generation.
+    pub daily_budget_usd: String,
+    pub interval_mins: u32,
+    /// Sessions with fewer user prompts are not summarized.
+    pub min_prompts: u32,
+    /// Character budget of the compacted transcript sent per session.
+    pub max_transcript_chars: u32,
+}
+
+impl Default for SessionSummaryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            app_type: "claude".to_string(),
+            provider_id: None,
+            model: None,
+            daily_budget_usd: "0.50".to_string(),
+            interval_mins: 30,
+            min_prompts: 2,
+            max_transcript_chars: 24_000,
+        }
+    }
+}
+
+impl SessionSummaryConfig {
+    pub fn validate(&self) -> Result<(), AppError> {
+        if !matches!(self.app_type.as_str(), "claude" | "codex" | "gemini") {
+            return Err(AppError::InvalidInput(format!(
+                "Unsupported app for session summaries: {}",
+                self.app_type
+            )));
+        }
+        if self.budget()?.is_sign_negative() {
+            return Err(AppError::InvalidInput(
+                "Daily budget cannot be negative".to_string(),
+            ));
+        }
+        if self.interval_mins < 5 {
+            return Err(AppError::InvalidInput(
+                "Summary interval cannot be shorter than 5 minutes".to_string(),
+            ));
+        }
+        if self.max_transcript_chars < 2_000 {
+            return Err(AppError::InvalidInput(
+                "Transcript budget cannot be below 2000 characters".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn budget(&self) -> Result<Decimal, AppError> {
+        Decimal::from_str(self.daily_budget_usd.trim()).map_err(|e| {
+            AppError::InvalidInput(format!(
+                "Invalid daily budget {}: {e}",
+                self.daily_budget_usd
+            ))
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+    pub provider_id: String,
+    pub session_id: String,
+    pub source_path: String,
+    pub title: String,
+    pub summary: String,
+    pub keywords: Vec<String>,
+    pub model: String,
+    /// Cost of the request as logged, in USD.
+    pub cost_usd: String,
+    /// Fingerprint of the session file the summary was generated from.
+    pub file_size: i64,
+    pub modified_at: i64,
+    pub generated_at: i64,
+}
+
+/// What the model is asked to return.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct GeneratedSummary {
+    title: String,
+    summary: String,
+    keywords: Vec<String>,
+}
+
+/// Summarize one session now, even if its summary is current.
+pub async fn summarize_session(
+    db: &Database,
+    provider_id: &str,
+    source_path: &str,
+) -> Result<SessionSummary, AppError> {
+    let config = db.get_session_summary_config()?;
+    let meta = find_session(provider_id, source_path)
+        .ok_or_else(|| AppError::Message(format!("Session not found: {source_path}")))?;
+    let target = Target::resolve(db, &config)?;
+    check_budget(db, &config)?;
+    generate(db, &config, &target, &meta)
+        .await?
+        .ok_or_else(|| AppError::Message("Session has too few prompts to summarize".to_string()))
+}
+
+/// Sessions whose summary is missing or older than the file, most recent first.
+fn pending_sessions(db: &Database) -> Result<Vec<SessionMeta>, AppError> {
+    let summarized = db.get_session_summary_fingerprints()?;
+    let settled_before = chrono::Utc::now().timestamp_millis() - SETTLE_MS;
+    Ok(scan_sessions()
+        .into_iter()
+        .filter(|meta| meta.parent_session_id.is_none())
+        .filter(|meta| {
+            meta.last_active_at
+                .or(meta.created_at)
+                .is_some_and(|ts| ts < settled_before)
+        })
+        .filter(|meta| {
+            let Some(path) = meta.source_path.as_deref() else {
+                return false;
+            };
+            let Some(current) = fingerprint(Path::new(path)) else {
+                return false;
+            };
+            summarized.get(path) != Some(&current)
+        })
+        .collect())
+}
+
+/// Run one pass of the background summarizer, returning the new summaries.
+async fn run_pass(
+    db: &Database,
+    config: &SessionSummaryConfig,
+) -> Result<Vec<SessionSummary>, AppError> {
+    let target = Target::resolve(db, config)?;
+    let mut summaries = Vec::new();
+    for meta in pending_sessions(db)? {
+        if summaries.len() >= MAX_PER_PASS {
+            break;
+        }
+        if let Err(e) = check_budget(db, config) {
+            log::info!("{e}");
+            break;
+        }
+        match generate(db, config, &target, &meta).await {
+            Ok(Some(summary)) => summaries.push(summary),
+            Ok(None) => {}
+            Err(e) => log::warn!(
+                "Session summary for {} failed: {e}",
+                meta.source_path.as_deref().unwrap_or_default()
+            ),
+        }
+    }
+    Ok(summaries)
+}
+
+/// Fail once today's summary requests have used up the daily budget.
+fn check_budget(db: &Database, config: &SessionSummaryConfig) -> Result<(), AppError> {
+    let spent = db.get_session_summary_spend_since(start_of_day())?;
+    let budget = config.budget()?;
+    if spent >= budget {
+        return Err(AppError::Message(format!(
+            "Daily session summary budget reached ({spent:.4} of {budget} USD)"
+        )));
+    }
+    Ok(())
+}
+
+async fn generate(
+    db: &Database,
+    config: &SessionSummaryConfig,
+    target: &Target,
+    meta: &SessionMeta,
+) -> Result<Option<SessionSummary>, AppError> {
+    let source_path = meta
+        .source_path
+        .clone()
+        .ok_or_else(|| AppError::Message("Session has no source file".to_string()))?;
+    let (file_size, modified_at) = fingerprint(Path::new(&source_path))
+        .ok_or_else(|| AppError::Message(format!("Session file not readable: {source_path}")))?;
+    let messages = load_messages(&meta.provider_id, &source_path).map_err(AppError::Message)?;
+    let prompts = messages.iter().filter(|m| is_prompt(m)).count() as u32;
+    if prompts < config.min_prompts.max(1) {
+        // Remember the fingerprint so the session is not loaded again until it changes
+        db.mark_session_summary_skipped(
+            &meta.provider_id,
+            &meta.session_id,
+            &source_path,
+            file_size,
+            modified_at,
+        )?;
+        return Ok(None);
+    }
+
+    let transcript = compact_transcript(&messages, config.max_transcript_chars as usize);
+    let prompt = match &meta.project_dir {
+        Some(dir) => format!("Project: {dir}\n\n{transcript}"),
+        None => transcript,
+    };
+    let request_id = format!("{REQUEST_ID_PREFIX}{}", uuid::Uuid::new_v4());
+    let reply = target.complete(db, &request_id, &prompt).await?;
+    let generated = parse_reply(&reply.text)
+        .ok_or_else(|| AppError::Message("Model did not return a summary".to_string()))?;
+
+    let summary = SessionSummary {
+        provider_id: meta.provider_id.clone(),
+        session_id: meta.session_id.clone(),
+        source_path,
+        title: generated.title,
+        summary: generated.summary,
+        keywords: generated.keywords,
+        model: reply.model,
+        cost_usd: db
+            .get_request_total_cost(&request_id)?
+            .unwrap_or_else(|| "0".to_string()),
+        file_size,
+        modified_at,
+        generated_at: chrono::Utc::now().timestamp_millis(),
+    };
+    db.upsert_session_summary(&summary)?;
+    Ok(Some(summary))
+}
+
+fn start_of_day() -> i64 {
+    let now = chrono::Local::now();
+    now.date_naive()
+        .and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(chrono::Local).earliest())
+        .map(|midnight| midnight.timestamp())
+        .unwrap_or_else(|| now.timestamp() - 86_400)
+}
+
+fn is_prompt(message: &SessionMessage) -> bool {
+    message.role == "user"
+        && !message.is_sidechain
+        && message
+            .parts
+            .iter()
+            .any(|part| matches!(part, ContentPart::Text { text } if !is_injected(text)))
+}
+
+/// Prompts, replies and one-line tool calls, each cut short. When the result
+/// is still over `max_chars`, the middle of the session is dropped.
+pub fn compact_transcript(messages: &[SessionMessage], max_chars: usize) -> String {
+    let mut lines = Vec::new();
+    for message in messages.iter().filter(|m| !m.is_sidechain) {
+        for part in &message.parts {
+            let line = match (message.role.as_str(), part) {
+                ("user", ContentPart::Text { text }) if !is_injected(text) => {
+                    format!("User: {}", clip(text, MESSAGE_CHARS))
+                }
+                ("assistant", ContentPart::Text { text }) => {
+                    format!("Assistant: {}", clip(text, MESSAGE_CHARS))
+                }
+                (_, ContentPart::ToolUse { name, input, .. }) => {
+                    format!("[{name}] {}", clip(&tool_hint(input), 120))
+                }
+                (
+                    _,
+                    ContentPart::ToolResult {
+                        is_error: true,
+                        content,
+                        ..
+                    },
+                ) => {
+                    format!("[error] {}", clip(content, 200))
+                }
+                _ => continue,
+            };
+            lines.push(line);
+        }
+    }
+
+    let total: usize = lines.iter().map(|line| line.chars().count() + 1).sum();
+    if total <= max_chars {
+        return lines.join("\n");
+    }
+
+    // Keep the opening (the goal) and the end (the outcome)
+    let half = max_chars / 2;
+    let mut head = Vec::new();
+    let mut used = 0;
+    for line in &lines {
+        used += line.chars().count() + 1;
+        if used > half {
+            break;
+        }
+        head.push(line.as_str());
+    }
+    let mut tail = Vec::new();
+    used = 0;
+    for line in lines[head.len()..].iter().rev() {
+        used += line.chars().count() + 1;
+        if used > half {
+            break;
+        }
+        tail.push(line.as_str());
+    }
+    tail.reverse();
+    let omitted = lines.len() - head.len() - tail.len();
+    format!(
+        "{}\n… {omitted} lines omitted …\n{}",
+        head.join("\n"),
+        tail.join("\n")
+    )
+}
+
+fn clip(text: &str, max: usize) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    match text.char_indices().nth(max) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+/// The argument that best describes a tool call: a command, path or query.
+fn tool_hint(input: &Value) -> String {
+    [
+        "command",
+        "file_path",
+        "path",
+        "pattern",
+        "query",
+        "url",
+        "description",
+    ]
+    .iter()
+    .find_map(|key| match input.get(*key)? {
+        Value::String(text) => Some(text.clone()),
+        Value::Array(items) => Some(
+            items
+                .iter()
+                .filter_map(Value::as_str)
+                .collect::<Vec<_>>()
+                .join(" "),
+        ),
+        _ => None,
+    })
+    .unwrap_or_default()
+}
+
+/// Read the JSON object out of a reply, tolerating code fences around it.
+fn parse_reply(text: &str) -> Option<GeneratedSummary> {
+    let start = text.find('{')?;
+    let end = text.rfind('}')?;
+    let mut generated: GeneratedSummary = serde_json::from_str(text.get(start..=end)?).ok()?;
+    generated.title = generated.title.trim().to_string();
+    generated.summary = generated.summary.trim().to_string();
+    generated.keywords = generated
+        .keywords
+        .into_iter()
+        .map(|keyword| keyword.trim().to_lowercase())
+        .filter(|keyword| !keyword.is_empty())
+        .take(8)
+        .collect();
+    (!generated.title.is_empty()).then_some(generated)
+}
+
+/// The provider, model and credentials requests are sent with.
+struct Target {
+    app_type: AppType,
+    provider: Provider,
+    model: String,
+    base_url: String,
+    auth: AuthInfo,
+    client: Client,
+}
+
+struct Reply {
+    text: String,
+    model: String,
+}
+
+impl Target {
+    fn resolve(db: &Database, config: &SessionSummaryConfig) -> Result<Self, AppError> {
+        config.validate()?;
+        let app_type = AppType::from_str(&config.app_type)?;
+        let provider_id = config
+            .provider_id
+            .clone()
+            .or_else(|| crate::settings::get_current_provider(&app_type))
+            .ok_or_else(|| {
+                AppError::Message(format!("No provider selected for {}", config.app_type))
+            })?;
+        let provider = db
+            .get_provider_by_id(&provider_id, app_type.as_str())?
+            .ok_or_else(|| AppError::Message(format!("Provider not found: {provider_id}")))?;
+
+        let adapter = get_adapter(&app_type);
+        let base_url = adapter
+            .extract_base_url(&provider)
+            .map_err(|e| AppError::Message(format!("Failed to extract base_url: {e}")))?;
+        let auth = adapter
+            .extract_auth(&provider)
+            .ok_or_else(|| AppError::Message("API Key not found".to_string()))?;
+        let proxy_config = provider.meta.as_ref().and_then(|m| m.proxy_config.as_ref());
+        let client = crate::proxy::http_client::get_for_provider(proxy_config);
+
+        let model = match config.model.as_deref().map(str::trim) {
+            Some(model) if !model.is_empty() => model.to_string(),
+            _ => {
+                let check = db.get_stream_check_config()?;
+                match app_type {
+                    AppType::Codex => check.codex_model,
+                    AppType::Gemini => check.gemini_model,
+                    _ => check.claude_model,
+                }
+            }
+        };
+
+        Ok(Self {
+            app_type,
+            provider,
+            model,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            auth,
+            client,
+        })
+    }
+
+    /// Send one request and log it (with its cost) like a proxy request.
+    ///
+    /// The log carries no session id: it would attribute the summary's cost to
+    /// the summarized session. The request id prefix identifies these rows.
+    async fn complete(
+        &self,
+        db: &Database,
+        request_id: &str,
+        prompt: &str,
+    ) -> Result<Reply, AppError> {
+        let started = Instant::now();
+        let (model, effort) = match self.model.split_once(['@', '#']) {
+            Some((model, effort)) => (model.to_string(), Some(effort.to_string())),
+            None => (self.model.clone(), None),
+        };
+        let result = match self.app_type {
+            AppType::Codex => self.send_codex(&model, effort.as_deref(), prompt).await,
+            AppType::Gemini => self.send_gemini(&model, prompt).await,
+            _ => self.send_claude(&model, prompt).await,
+        };
+        let latency_ms = started.elapsed().as_millis() as u64;
+        let logger = UsageLogger::new(db);
+        let app_type = self.app_type.as_str().to_string();
+
+        let (status, body) = match result {
+            Ok(response) => response,
+            Err(e) => {
+                logger.log_error_with_context(
+                    request_id.to_string(),
+                    self.provider.id.clone(),
+                    app_type,
+                    model,
+                    0,
+                    e.to_string(),
+                    latency_ms,
+                    false,
+                    None,
+                    None,
+                    Some(self.base_url.clone()),
+                )?;
+                return Err(e);
+            }
+        };
+
+        let usage = match self.app_type {
+            AppType::Codex => TokenUsage::from_codex_response_auto(&body),
+            AppType::Gemini => TokenUsage::from_gemini_response(&body),
+            _ => TokenUsage::from_claude_response(&body),
+        }
+        .unwrap_or_default();
+        let returned_model = usage
+            .model
+            .clone()
+            .filter(|m| !m.is_empty())
+            .unwrap_or_else(|| model.clone());
+        let (multiplier, pricing_source) = logger
+            .resolve_pricing_config(&self.provider.id, &app_type)
+            .await;
+        let pricing_model = if pricing_source == "request" {
+            model.clone()
+        } else {
+            returned_model.clone()
+        };
+        logger.log_with_calculation(
+            request_id.to_string(),
+            self.provider.id.clone(),
+            app_type,
+            returned_model.clone(),
+            model,
+            pricing_model,
+            usage,
+            multiplier,
+            latency_ms,
+            None,
+            status,
+            None,
+            None,
+            false,
+            Some(self.base_url.clone()),
+            None,
+        )?;
+
+        Ok(Reply {
+            text: reply_text(&self.app_type, &body),
+            model: returned_model,
+        })
+    }
+
+    async fn send_claude(&self, model: &str, prompt: &str) -> Result<(u16, Value), AppError> {
+        let url = if self.base_url.ends_with("/v1") {
+            format!("{}/messages", self.base_url)
+        } else {
+            format!("{}/v1/messages", self.base_url)
+        };
+        let body = json!({
+            "model": model,
+            "max_tokens": MAX_OUTPUT_TOKENS,
+            "system": SYSTEM_PROMPT,
+            "messages": [{ "role": "user", "content": prompt }],
+        });
+        let mut request = self
+            .client
+            .post(&url)
+            .header("authorization", format!("Bearer {}", self.auth.api_key))
+            .header("anthropic-version", "2023-06-01");
+        if self.auth.strategy == AuthStrategy::Anthropic {
+            request = request.header("x-api-key", &self.auth.api_key);
+        }
+        send(request.json(&body)).await
+    }
+
+    async fn send_codex(
+        &self,
+        model: &str,
+        effort: Option<&str>,
+        prompt: &str,
+    ) -> Result<(u16, Value), AppError> {
+        let url = if self.base_url.ends_with("/v1") {
+            format!("{}/responses", self.base_url)
+        } else {
+            format!("{}/v1/responses", self.base_url)
+        };
+        let mut body = json!({
+            "model": model,
+            "instructions": SYSTEM_PROMPT,
+            "input": [{ "role": "user", "content": prompt }],
+            "max_output_tokens": MAX_OUTPUT_TOKENS,
+            "stream": false,
+        });
+        if let Some(effort) = effort {
+            body["reasoning"] = json!({ "effort": effort });
+        }
+        let request = self
+            .client
+            .post(&url)
+            .header("authorization", format!("Bearer {}", self.auth.api_key))
+            .json(&body);
+        send(request).await
+    }
+
+    async fn send_gemini(&self, model: &str, prompt: &str) -> Result<(u16, Value), AppError> {
+        let url = if self.base_url.contains("/v1beta") || self.base_url.contains("/v1/") {
+            format!("{}/models/{model}:generateContent", self.base_url)
+        } else {
+            format!("{}/v1beta/models/{model}:generateContent", self.base_url)
+        };
+        let body = json!({
+            "systemInstruction": { "parts": [{ "text": SYSTEM_PROMPT }] },
+            "contents": [{ "role": "user", "parts": [{ "text": prompt }] }],
+            "generationConfig": {
+                "maxOutputTokens": MAX_OUTPUT_TOKENS,
+                "responseMimeType": "application/json",
+            },
+        });
+        let request = self
+            .client
+            .post(&url)
+            .header("x-goog-api-key", &self.auth.api_key)
+            .json(&body);
+        send(request).await
+    }
+}
+
+async fn send(request: reqwest::RequestBuilder) -> Result<(u16, Value), AppError> {
+    let response = request
+        .header("content-type", "application/json")
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| AppError::Message(format!("Request failed: {e}")))?;
+    let status = response.status().as_u16();
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(AppError::Message(format!("HTTP {status}: {error_text}")));
+    }
+    let body = response
+        .json::<Value>()
+        .await
+        .map_err(|e| AppError::Message(format!("Invalid response body: {e}")))?;
+    Ok((status, body))
+}
+
+/// Concatenated text of a non-streaming response.
+fn reply_text(app_type: &AppType, body: &Value) -> String {
+    let texts: Vec<&str> = match app_type {
+        AppType::Codex => body
+            .get("output")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|item| item.get("content").and_then(Value::as_array))
+            .flatten()
+            .filter_map(|part| part.get("text").and_then(Value::as_str))
+            .collect(),
+        AppType::Gemini => body
+            .pointer("/candidates/0/content/parts")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter(|part| part.get("thought").and_then(Value::as_bool) != Some(true))
+            .filter_map(|part| part.get("text").and_then(Value::as_str))
+            .collect(),
+        _ => body
+            .get("content")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter(|block| block.get("type").and_then(Value::as_str) == Some("text"))
+            .filter_map(|block| block.get("text").and_then(Value::as_str))
+            .collect(),
+    };
+    texts.join("")
+}
+
+/// Summarize idle sessions in the background while enabled.
+///
+/// The config is read again every pass, so changes apply without a restart.
+pub fn start_worker(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let state = app.state::<AppState>();
+            let config = match state.db.get_session_summary_config() {
+                Ok(config) => config,
+                Err(e) => {
+                    log::warn!("Failed to read session summary config: {e}");
+                    SessionSummaryConfig::default()
+                }
+            };
+            if !config.enabled {
+                tokio::time::sleep(DISABLED_RECHECK).await;
+                continue;
+            }
+
+            match run_pass(&state.db, &config).await {
+                Ok(summaries) => {
+                    for summary in &summaries {
+                        if let Err(e) = app.emit(EVENT_UPDATED, summary) {
+                            log::debug!("Failed to emit session summary event: {e}");
+                        }
+                    }
+                }
+                Err(e) => log::warn!("Session summary pass failed: {e}"),
+            }
+
+            tokio::time::sleep(Duration::from_secs(u64::from(config.interval_mins) * 60)).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(role: &str, text: &str) -> SessionMessage {
+        SessionMessage {
+            role: role.to_string(),
+            content: text.to_string(),
+            parts: vec![ContentPart::text(text)],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_compact_transcript_keeps_start_and_end() {
+        let mut messages = vec![
+            text("user", "<environment_context>cwd</environment_context>"),
+            text("user", "Migrate the settings page to the new form library"),
+            SessionMessage {
+                role: "assistant".to_string(),
+                parts: vec![ContentPart::tool_use(
+                    None,
+                    "Bash",
+                    serde_json::json!({"command": "pnpm test"}),
+                )],
+                ..Default::default()
+            },
+        ];
+        for i in 0..50 {
+            messages.push(text("assistant", &format!("step {i} {}", "x".repeat(100))));
+        }
+        messages.push(text("assistant", "All settings forms migrated."));
+
+        let short = compact_transcript(&messages[..3], 10_000);
+        assert_eq!(
+            short,
+            "User: Migrate the settings page to the new form library\n[Bash] pnpm test"
+        );
+
+        let long = compact_transcript(&messages, 2_000);
+        assert!(long.chars().count() <= 2_100);
+        assert!(long.starts_with("User: Migrate"));
+        assert!(long.contains("lines omitted"));
+        assert!(long.ends_with("Assistant: All settings forms migrated."));
+    }
+
+    #[test]
+    fn test_parse_reply() {
+        let reply = "```json\n{\"title\": \" Fix login redirect \", \"summary\": \"Fixed it.\", \"keywords\": [\"Auth\", \" \", \"nextjs\"]}\n```";
+        let parsed = parse_reply(reply).unwrap();
+        assert_eq!(parsed.title, "Fix login redirect");
+        assert_eq!(parsed.keywords, vec!["auth", "nextjs"]);
+
+        assert!(parse_reply("I cannot help with that").is_none());
+        assert!(parse_reply("{\"summary\": \"no title\"}").is_none());
+    }
+
+    #[test]
+    fn test_config_validation() {
+        assert!(SessionSummaryConfig::default().validate().is_ok());
+        for config in [
+            SessionSummaryConfig {
+                app_type: "opencode".to_string(),
+                ..Default::default()
+            },
+            SessionSummaryConfig {
+                daily_budget_usd: "-1".to_string(),
+                ..Default::default()
+            },
+            SessionSummaryConfig {
+                daily_budget_usd: "abc".to_string(),
+                ..Default::default()
+            },
+        ] {
+            assert!(config.validate().is_err());
+        }
+    }
+}
@@ -0,0 +1,208 @@
+//! Linux (and other non-macOS Unix) launchers.
+//!
+//! Every terminal runs the same `cd <cwd> && <command>` script through the
+//! user's login shell, then drops into an interactive shell so the window
+//! stays open after the CLI exits.
+//!
+//! The terminal table and PATH lookup are shared with the provider launcher in
+//! `commands::misc`, so both open terminals the same way.
+
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use super::build_shell_command;
+
+/// Tried in order when no terminal is configured or the configured one is missing.
+const FALLBACK_TERMINALS: &[&str] = &[
+    "x-terminal-emulator",
+    "gnome-terminal",
+    "konsole",
+    "xfce4-terminal",
+    "mate-terminal",
+    "lxterminal",
+    "kitty",
+    "wezterm",
+    "alacritty",
+    "foot",
+    "ghostty",
+];
+
+pub(super) fn launch(target: &str, command: &str, cwd: Option<&str>) -> Result<(), String> {
+    let shell = std::env::var("SHELL")
+        .ok()
+        .filter(|s| !s.trim().is_empty())
+        .unwrap_or_else(|| "/bin/sh".to_string());
+    let cwd = cwd.filter(|dir| !dir.trim().is_empty());
+    let script = format!(
+        "{}; exec {} -l",
+        build_shell_command(command, cwd),
+        super::shell_escape(&shell)
+    );
+    let program = [shell.as_str(), "-lc", script.as_str()];
+
+    // tmux opens a window in the running server instead of a new terminal
+    if target == "tmux" {
+        let argv = terminal_argv("tmux", cwd, &program)
+            .ok_or_else(|| "Unsupported terminal target: tmux".to_string())?;
+        let output = Command::new(&argv[0])
+            .args(&argv[1..])
+            .output()
+            .map_err(|e| format!("Failed to launch tmux: {e}"))?;
+        return if output.status.success() {
+            Ok(())
+        } else {
+            Err(format!(
+                "tmux new-window failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))
+        };
+    }
+
+    let preferred = (!matches!(target, "" | "terminal" | "auto")).then_some(target);
+    if preferred.is_some_and(|name| !is_supported(name)) {
+        return Err(format!("Unsupported terminal target: {target}"));
+    }
+
+    let mut last_error = "No supported terminal found".to_string();
+    for name in candidate_terminals(preferred) {
+        let Some(program_path) = find_in_path(&name) else {
+            continue;
+        };
+        let Some(argv) = terminal_argv(&name, cwd, &program) else {
+            continue;
+        };
+        let mut launcher = Command::new(program_path);
+        launcher
+            .args(&argv[1..])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        if let Some(dir) = cwd.filter(|dir| Path::new(dir).is_dir()) {
+            launcher.current_dir(dir);
+        }
+        match launcher.spawn() {
+            Ok(_) => {
+                if preferred.is_some_and(|target| target != name) {
+                    log::warn!("Terminal {target} not found, resumed session in {name}");
+                }
+                return Ok(());
+            }
+            Err(e) => last_error = format!("Failed to launch {name}: {e}"),
+        }
+    }
+    Err(last_error)
+}
+
+/// Terminals to try in order: `preferred`, then a supported `$TERMINAL`, then
+/// the fallbacks. tmux is only a fallback when already running inside tmux.
+pub(crate) fn candidate_terminals(preferred: Option<&str>) -> Vec<String> {
+    let mut candidates: Vec<String> = Vec::new();
+    let mut push = |name: &str| {
+        if !name.is_empty() && !candidates.iter().any(|c| c == name) {
+            candidates.push(name.to_string());
+        }
+    };
+    if let Some(name) = preferred {
+        push(name);
+    }
+    if let Some(name) = std::env::var("TERMINAL").ok().filter(|n| is_supported(n)) {
+        push(&name);
+    }
+    for name in FALLBACK_TERMINALS {
+        push(name);
+    }
+    if std::env::var_os("TMUX").is_some_and(|v| !v.is_empty()) {
+        push("tmux");
+    }
+    candidates
+}
+
+fn is_supported(terminal: &str) -> bool {
+    terminal_argv(terminal, None, &[]).is_some()
+}
+
+/// Full argv that opens `terminal` running `program` (with its arguments) in `cwd`.
+pub(crate) fn terminal_argv(
+    terminal: &str,
+    cwd: Option<&str>,
+    program: &[&str],
+) -> Option<Vec<String>> {
+    let mut argv: Vec<String> = vec![terminal.to_string()];
+    let mut push = |args: &[&str]| argv.extend(args.iter().map(|a| a.to_string()));
+    match (terminal, cwd) {
+        ("gnome-terminal", Some(dir)) => push(&[&format!("--working-directory={dir}"), "--"]),
+        ("gnome-terminal", None) => push(&["--"]),
+        ("konsole", Some(dir)) => push(&["--workdir", dir, "-e"]),
+        ("konsole", None) | ("x-terminal-emulator" | "lxterminal", _) => push(&["-e"]),
+        ("mate-terminal", _) => push(&["--"]),
+        // `-x` takes the rest of the command line; `-e` would want one string
+        ("xfce4-terminal", Some(dir)) => push(&[&format!("--working-directory={dir}"), "-x"]),
+        ("xfce4-terminal", None) => push(&["-x"]),
+        ("kitty", Some(dir)) => push(&["--directory", dir]),
+        ("kitty", None) | ("foot", None) => {}
+        ("wezterm", Some(dir)) => push(&["start", "--cwd", dir, "--"]),
+        ("wezterm", None) => push(&["start", "--"]),
+        ("alacritty", Some(dir)) => push(&["--working-directory", dir, "-e"]),
+        ("alacritty", None) => push(&["-e"]),
+        ("foot", Some(dir)) => push(&[&format!("--working-directory={dir}")]),
+        ("ghostty", Some(dir)) => push(&[&format!("--working-directory={dir}"), "-e"]),
+        ("ghostty", None) => push(&["-e"]),
+        ("tmux", Some(dir)) => push(&["new-window", "-c", dir]),
+        ("tmux", None) => push(&["new-window"]),
+        _ => return None,
+    }
+    push(program);
+    Some(argv)
+}
+
+pub(crate) fn find_in_path(program: &str) -> Option<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let paths = std::env::var_os("PATH")?;
+    std::env::split_paths(&paths)
+        .map(|dir| dir.join(program))
+        .find(|candidate| {
+            std::fs::metadata(candidate)
+                .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+                .unwrap_or(false)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_terminal_argv() {
+        let argv = terminal_argv(
+            "gnome-terminal",
+            Some("/w d"),
+            &["/bin/zsh", "-lc", "claude"],
+        )
+        .unwrap();
+        assert_eq!(
+            argv,
+            vec![
+                "gnome-terminal",
+                "--working-directory=/w d",
+                "--",
+                "/bin/zsh",
+                "-lc",
+                "claude"
+            ]
+        );
+
+        let argv = terminal_argv("tmux", None, &["/bin/bash", "-lc", "codex"]).unwrap();
+        assert_eq!(
+            argv,
+            vec!["tmux", "new-window", "/bin/bash", "-lc", "codex"]
+        );
+
+        let argv = terminal_argv("xfce4-terminal", None, &["bash", "run.sh"]).unwrap();
+        assert_eq!(argv, vec!["xfce4-terminal", "-x", "bash", "run.sh"]);
+        let argv = terminal_argv("kitty", None, &["bash", "run.sh"]).unwrap();
+        assert_eq!(argv, vec!["kitty", "bash", "run.sh"]);
+
+        assert!(terminal_argv("iterm", None, &["x"]).is_none());
+    }
+}
@@ -16,6 +16,12 @@ pub struct CustomEndpoint {
     pub added_at: i64,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub last_used: Option<i64>,
+    /// 最近一次测速延迟（毫秒），测速失败时为 None
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub latency_ms: Option<u64>,
+    /// 最近一次测速时间（毫秒时间戳）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_tested_at: Option<i64>,
 }
 
 fn default_true() -> bool {
@@ -15,6 +15,7 @@ pub struct TrayTexts {
     pub show_main: &'static str,
     pub no_provider_hint: &'static str,
     pub quit: &'static str,
+    pub usage_alerts: &'static str,
     pub _auto_label: &'static str,
 }
 
@@ -25,6 +26,7 @@ impl TrayTexts {
                 show_main: "Open main window",
                 no_provider_hint: "  (No providers yet, please add them from the main window)",
                 quit: "Quit",
+                usage_alerts: "Usage alerts",
                 _auto_label: "Auto (Failover)",
             },
             "ja" => Self {
@@ -32,12 +34,14 @@ impl TrayTexts {
                 no_provider_hint:
                     "  (プロバイダーがまだありません。メイン画面から追加してください)",
                 quit: "終了",
+                usage_alerts: "使用量アラート",
                 _auto_label: "自動 (フェイルオーバー)",
             },
             _ => Self {
                 show_main: "打开主界面",
                 no_provider_hint: "  (无供应商，请在主界面添加)",
                 quit: "退出",
+                usage_alerts: "用量预警",
                 _auto_label: "自动 (故障转移)",
             },
         }
@@ -346,7 +350,22 @@ pub fn create_tray_menu(
     let show_main_item =
         MenuItem::with_id(app, "show_main", tray_texts.show_main, true, None::<&str>)
             .map_err(|e| AppError::Message(format!("创建打开主界面菜单失败: {e}")))?;
-    menu_builder = menu_builder.item(&show_main_item).separator();
+    menu_builder = menu_builder.item(&show_main_item);
+
+    // 用量预警（最严重的一条 + 数量）
+    let alerts = crate::services::usage_forecast::latest_alerts();
+    if let Some(first) = alerts.first() {
+        let more = if alerts.len() > 1 {
+            format!(" (+{})", alerts.len() - 1)
+        } else {
+            String::new()
+        };
+        let label = format!("⚠ {}: {}{more}", tray_texts.usage_alerts, first.title);
+        let alerts_item = MenuItem::with_id(app, "usage_alerts", label, true, None::<&str>)
+            .map_err(|e| AppError::Message(format!("创建用量预警菜单失败: {e}")))?;
+        menu_builder = menu_builder.item(&alerts_item);
+    }
+    menu_builder = menu_builder.separator();
 
     // 直接添加所有供应商到主菜单（扁平化结构，更简单可靠）
     // Only add visible app sections
@@ -417,7 +436,7 @@ pub fn handle_tray_menu_event(app: &tauri::AppHandle, event_id: &str) {
     log::info!("处理托盘菜单事件: {event_id}");
 
     match event_id {
-        "show_main" => {
+        "show_main" | "usage_alerts" => {
             if let Some(window) = app.get_webview_window("main") {
                 #[cfg(target_os = "windows")]
                 {
@@ -431,6 +450,11 @@ pub fn handle_tray_menu_event(app: &tauri::AppHandle, event_id: &str) {
                     apply_tray_policy(app, true);
                 }
             }
+            if event_id == "usage_alerts" {
+                if let Err(e) = app.emit("open-usage-alerts", ()) {
+                    log::error!("发射 open-usage-alerts 事件失败: {e}");
+                }
+            }
         }
         "quit" => {
             log::info!("退出应用");
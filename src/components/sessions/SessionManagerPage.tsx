@@ -52,12 +52,20 @@ type ProviderFilter =
   | "claude"
   | "opencode"
   | "openclaw"
-  | "gemini";
+  | "gemini"
+  | "qwen"
+  | "crush"
+  | "cursor"
+  | "aider";
 
 export function SessionManagerPage({ appId }: { appId: string }) {
   const { t } = useTranslation();
   const { data, isLoading, refetch } = useSessionsQuery();
-  const sessions = data ?? [];
+  // 子代理会话不单独列出
+  const sessions = useMemo(
+    () => (data ?? []).filter((session) => !session.parentSessionId),
+    [data],
+  );
   const detailRef = useRef<HTMLDivElement | null>(null);
   const messagesEndRef = useRef<HTMLDivElement | null>(null);
   const messageRefs = useRef<Map<number, HTMLDivElement>>(new Map());
@@ -118,7 +126,11 @@ export function SessionManagerPage({ appId }: { appId: string }) {
   const userMessagesToc = useMemo(() => {
     return messages
       .map((msg, index) => ({ msg, index }))
-      .filter(({ msg }) => msg.role.toLowerCase() === "user")
+      .filter(
+        ({ msg }) =>
+          msg.role.toLowerCase() === "user" &&
+          !msg.parts?.every((part) => part.type === "toolResult"),
+      )
       .map(({ msg, index }) => ({
         index,
         preview:
@@ -345,6 +357,46 @@ export function SessionManagerPage({ appId }: { appId: string }) {
                               <span>Gemini CLI</span>
                             </div>
                           </SelectItem>
+                          <SelectItem value="qwen">
+                            <div className="flex items-center gap-2">
+                              <ProviderIcon
+                                icon="qwen"
+                                name="Qwen Code"
+                                size={14}
+                              />
+                              <span>Qwen Code</span>
+                            </div>
+                          </SelectItem>
+                          <SelectItem value="crush">
+                            <div className="flex items-center gap-2">
+                              <ProviderIcon
+                                icon="crush"
+                                name="Crush"
+                                size={14}
+                              />
+                              <span>Crush</span>
+                            </div>
+                          </SelectItem>
+                          <SelectItem value="cursor">
+                            <div className="flex items-center gap-2">
+                              <ProviderIcon
+                                icon="cursor"
+                                name="Cursor CLI"
+                                size={14}
+                              />
+                              <span>Cursor CLI</span>
+                            </div>
+                          </SelectItem>
+                          <SelectItem value="aider">
+                            <div className="flex items-center gap-2">
+                              <ProviderIcon
+                                icon="aider"
+                                name="Aider"
+                                size={14}
+                              />
+                              <span>Aider</span>
+                            </div>
+                          </SelectItem>
                         </SelectContent>
                       </Select>
 
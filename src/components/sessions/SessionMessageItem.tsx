@@ -8,7 +8,7 @@ import {
   TooltipTrigger,
 } from "@/components/ui/tooltip";
 import { cn } from "@/lib/utils";
-import type { SessionMessage } from "@/types";
+import type { SessionContentPart, SessionMessage } from "@/types";
 import { formatTimestamp, getRoleLabel, getRoleTone } from "./utils";
 
 interface SessionMessageItemProps {
@@ -67,9 +67,103 @@ export function SessionMessageItem({
           </span>
         )}
       </div>
-      <div className="whitespace-pre-wrap text-sm leading-relaxed">
-        {message.content}
-      </div>
+      {message.parts && message.parts.length > 0 ? (
+        <div className="space-y-1.5">
+          {message.parts.map((part, index) => (
+            <MessagePart key={index} part={part} />
+          ))}
+        </div>
+      ) : (
+        <div className="whitespace-pre-wrap text-sm leading-relaxed">
+          {message.content}
+        </div>
+      )}
+      {(message.model || message.usage) && (
+        <div className="mt-1.5 text-[11px] text-muted-foreground">
+          {[
+            message.model,
+            message.usage &&
+              t("sessionManager.tokenUsage", {
+                input: message.usage.inputTokens,
+                output: message.usage.outputTokens,
+                cache:
+                  message.usage.cacheReadTokens +
+                  message.usage.cacheCreationTokens,
+              }),
+          ]
+            .filter(Boolean)
+            .join(" · ")}
+        </div>
+      )}
     </div>
   );
 }
+
+function MessagePart({ part }: { part: SessionContentPart }) {
+  const { t } = useTranslation();
+
+  switch (part.type) {
+    case "text":
+      return (
+        <div className="whitespace-pre-wrap text-sm leading-relaxed">
+          {part.text}
+        </div>
+      );
+    case "thinking":
+      return (
+        <details className="text-xs text-muted-foreground">
+          <summary className="cursor-pointer select-none">
+            {part.redacted
+              ? t("sessionManager.thinkingRedacted")
+              : t("sessionManager.thinking")}
+          </summary>
+          {part.text && (
+            <div className="whitespace-pre-wrap mt-1 pl-3 border-l">
+              {part.text}
+            </div>
+          )}
+        </details>
+      );
+    case "toolUse":
+      return (
+        <details className="text-xs">
+          <summary className="cursor-pointer select-none font-mono text-amber-600">
+            {t("sessionManager.toolCall", { name: part.name })}
+          </summary>
+          <pre className="mt-1 whitespace-pre-wrap break-all rounded bg-muted/60 p-2 font-mono">
+            {typeof part.input === "string"
+              ? part.input
+              : JSON.stringify(part.input, null, 2)}
+          </pre>
+        </details>
+      );
+    case "toolResult":
+      return (
+        <details className="text-xs">
+          <summary
+            className={cn(
+              "cursor-pointer select-none font-mono",
+              part.isError ? "text-red-500" : "text-muted-foreground",
+            )}
+          >
+            {t(
+              part.isError
+                ? "sessionManager.toolError"
+                : "sessionManager.toolResult",
+              { name: part.name ?? "" },
+            )}
+          </summary>
+          <pre className="mt-1 max-h-80 overflow-auto whitespace-pre-wrap break-all rounded bg-muted/60 p-2 font-mono">
+            {part.content}
+          </pre>
+        </details>
+      );
+    case "image":
+      return (
+        <div className="text-xs text-muted-foreground">
+          [{t("sessionManager.image")}
+          {part.mediaType ? ` ${part.mediaType}` : ""}]
+        </div>
+      );
+  }
+}
@@ -40,6 +40,9 @@ const LINUX_TERMINALS = [
   { value: "alacritty", labelKey: "settings.terminal.options.linux.alacritty" },
   { value: "kitty", labelKey: "settings.terminal.options.linux.kitty" },
   { value: "ghostty", labelKey: "settings.terminal.options.linux.ghostty" },
+  { value: "wezterm", labelKey: "settings.terminal.options.linux.wezterm" },
+  { value: "foot", labelKey: "settings.terminal.options.linux.foot" },
+  { value: "tmux", labelKey: "settings.terminal.options.linux.tmux" },
 ] as const;
 
 // Get terminals for the current platform
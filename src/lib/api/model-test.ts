@@ -22,6 +22,7 @@ export interface StreamCheckResult {
   responseTimeMs?: number;
   httpStatus?: number;
   modelUsed: string;
+  returnedModel?: string;
   testedAt: number;
   retryCount: number;
 }
@@ -63,3 +64,178 @@ export async function saveStreamCheckConfig(
 ): Promise<void> {
   return invoke("save_stream_check_config", { config });
 }
+
+// ===== 后台健康探测 =====
+
+export interface HealthProbeConfig {
+  enabled: boolean;
+  intervalSecs: number;
+  jitterSecs: number;
+  quietMode: boolean;
+  quietWindowSecs: number;
+  streamCheck: boolean;
+  openCircuitOnFailure: boolean;
+  timeoutSecs: number;
+  retentionDays: number;
+}
+
+export interface EndpointProbeLatency {
+  url: string;
+  latency: number | null;
+  status: number | null;
+  error: string | null;
+}
+
+export interface ProviderProbeResult {
+  appType: string;
+  providerId: string;
+  providerName: string;
+  healthy: boolean;
+  endpoints: EndpointProbeLatency[];
+  streamCheck?: StreamCheckResult;
+  testedAt: number;
+}
+
+export interface HealthHistoryPoint {
+  bucketStart: number;
+  /** 端点 URL；为空表示流式检查序列 */
+  endpoint?: string;
+  samples: number;
+  successes: number;
+  availability: number;
+  avgLatencyMs?: number;
+}
+
+/**
+ * 获取后台健康探测配置
+ */
+export async function getHealthProbeConfig(): Promise<HealthProbeConfig> {
+  return invoke("get_health_probe_config");
+}
+
+/**
+ * 保存后台健康探测配置
+ */
+export async function saveHealthProbeConfig(
+  config: HealthProbeConfig,
+): Promise<void> {
+  return invoke("save_health_probe_config", { config });
+}
+
+/**
+ * 立即执行一轮健康探测
+ */
+export async function runHealthProbesNow(): Promise<ProviderProbeResult[]> {
+  return invoke("run_health_probes_now");
+}
+
+/**
+ * 查询供应商延迟/可用率历史
+ */
+export async function getProviderHealthHistory(
+  appType: AppId,
+  providerId: string,
+  startTs: number,
+  endTs: number,
+  bucketSecs?: number,
+): Promise<HealthHistoryPoint[]> {
+  return invoke("get_provider_health_history", {
+    appType,
+    providerId,
+    startTs,
+    endTs,
+    bucketSecs,
+  });
+}
+
+// ===== 上游模型替换检测 =====
+
+export interface ModelFidelityConfig {
+  detect: boolean;
+  treatAsFailure: boolean;
+  probeEnabled: boolean;
+  probeIntervalMins: number;
+}
+
+export interface ModelProbeResult {
+  appType: string;
+  providerId: string;
+  promptIndex: number;
+  expectedModel?: string;
+  returnedModel?: string;
+  success: boolean;
+  /** 请求失败或上游未声明模型时为空 */
+  matched?: boolean;
+  message: string;
+  testedAt: number;
+}
+
+export interface ModelSubstitution {
+  expectedModel: string;
+  returnedModel: string;
+  requestCount: number;
+  lastSeenAt: number;
+}
+
+export interface ModelFidelityStats {
+  appType: string;
+  providerId: string;
+  providerName?: string;
+  checkedRequests: number;
+  substitutedRequests: number;
+  /** 模型一致率 (0-1)，无比对数据时为空 */
+  fidelity?: number;
+  substitutions: ModelSubstitution[];
+  probeCount: number;
+  probeVerified: number;
+  probeMismatches: number;
+  lastProbeAt?: number;
+}
+
+/**
+ * 获取模型替换检测配置
+ */
+export async function getModelFidelityConfig(): Promise<ModelFidelityConfig> {
+  return invoke("get_model_fidelity_config");
+}
+
+/**
+ * 保存模型替换检测配置
+ */
+export async function saveModelFidelityConfig(
+  config: ModelFidelityConfig,
+): Promise<void> {
+  return invoke("save_model_fidelity_config", { config });
+}
+
+/**
+ * 按供应商统计模型一致率
+ */
+export async function getModelFidelityStats(
+  startDate?: number,
+  endDate?: number,
+  appType?: string,
+): Promise<ModelFidelityStats[]> {
+  return invoke("get_model_fidelity_stats", { startDate, endDate, appType });
+}
+
+/**
+ * 获取供应商最近的指纹探测结果
+ */
+export async function getModelProbeResults(
+  appType: AppId,
+  providerId: string,
+  limit?: number,
+): Promise<ModelProbeResult[]> {
+  return invoke("get_model_probe_results", { appType, providerId, limit });
+}
+
+/**
+ * 立即对供应商执行指纹探测
+ */
+export async function runModelFingerprintProbe(
+  appType: AppId,
+  providerId: string,
+): Promise<ModelProbeResult[]> {
+  return invoke("run_model_fingerprint_probe", { appType, providerId });
+}
@@ -0,0 +1,60 @@
+import { invoke } from "@tauri-apps/api/core";
+
+export type SmtpSecurity = "tls" | "starttls" | "none";
+
+export type NotificationSinkKind =
+  | { type: "webhook"; url: string; headers?: Record<string, string> }
+  | { type: "slack"; url: string }
+  | { type: "discord"; url: string }
+  | { type: "feishu"; url: string }
+  | { type: "dingtalk"; url: string }
+  | {
+      type: "email";
+      host: string;
+      port: number;
+      username?: string;
+      password?: string;
+      security?: SmtpSecurity;
+      from: string;
+      to: string[];
+    }
+  | { type: "desktop" };
+
+export type NotificationSink = {
+  id: string;
+  name: string;
+  enabled: boolean;
+} & NotificationSinkKind;
+
+export interface NotificationRules {
+  failover: boolean;
+  allCircuitsOpen: boolean;
+  webdavSyncFailed: boolean;
+  dailySpendUsd?: number | null;
+  monthlySpendUsd?: number | null;
+  quotaRemainingBelow?: number | null;
+  quotaPercentBelow?: number | null;
+  quotaInvalid: boolean;
+  usageAlerts: boolean;
+}
+
+export interface NotificationConfig {
+  enabled: boolean;
+  sinks: NotificationSink[];
+  rules: NotificationRules;
+  cooldownSecs: number;
+}
+
+export const notificationsApi = {
+  async getConfig(): Promise<NotificationConfig> {
+    return invoke<NotificationConfig>("get_notification_config");
+  },
+
+  async saveConfig(config: NotificationConfig): Promise<void> {
+    return invoke<void>("save_notification_config", { config });
+  },
+
+  async testSink(sink: NotificationSink): Promise<void> {
+    return invoke<void>("test_notification_sink", { sink });
+  },
+};
@@ -1,5 +1,28 @@
 import { invoke } from "@tauri-apps/api/core";
-import type { SessionMessage, SessionMeta } from "@/types";
+import type {
+  ArchivedSession,
+  LiveSessionState,
+  ProjectAnalytics,
+  ProjectAnalyticsQuery,
+  SessionActionReport,
+  SessionAnalytics,
+  SessionAnnotation,
+  SessionBundle,
+  SessionConvertRequest,
+  SessionConvertResult,
+  SessionExportOptions,
+  SessionExportResult,
+  SessionIndexStats,
+  SessionMessage,
+  SessionMeta,
+  SessionPruneReport,
+  SessionPruneRules,
+  SessionRef,
+  SessionSearchHit,
+  SessionSearchQuery,
+  SessionSummary,
+  SessionSummaryConfig,
+} from "@/types";
 
 export const sessionsApi = {
   async list(): Promise<SessionMeta[]> {
@@ -25,4 +48,139 @@ export const sessionsApi = {
       customConfig,
     });
   },
+
+  async search(query: SessionSearchQuery): Promise<SessionSearchHit[]> {
+    return await invoke("search_sessions", { query });
+  },
+
+  async refreshIndex(rebuild = false): Promise<SessionIndexStats> {
+    return await invoke("refresh_session_index", { rebuild });
+  },
+
+  async exportToFile(
+    providerId: string,
+    sourcePath: string,
+    options: SessionExportOptions,
+    filePath: string,
+  ): Promise<SessionExportResult> {
+    return await invoke("export_session", {
+      providerId,
+      sourcePath,
+      options,
+      filePath,
+    });
+  },
+
+  async renderExport(
+    providerId: string,
+    sourcePath: string,
+    options: SessionExportOptions,
+  ): Promise<string> {
+    return await invoke("render_session_export", {
+      providerId,
+      sourcePath,
+      options,
+    });
+  },
+
+  async importBundle(filePath: string): Promise<SessionBundle> {
+    return await invoke("import_session_bundle", { filePath });
+  },
+
+  async convert(request: SessionConvertRequest): Promise<SessionConvertResult> {
+    return await invoke("convert_session", { request });
+  },
+
+  async getAnnotations(): Promise<SessionAnnotation[]> {
+    return await invoke("get_session_annotations");
+  },
+
+  async setAnnotation(
+    providerId: string,
+    sessionId: string,
+    title: string | undefined,
+    tags: string[],
+  ): Promise<SessionAnnotation | null> {
+    return await invoke("set_session_annotation", {
+      providerId,
+      sessionId,
+      title,
+      tags,
+    });
+  },
+
+  async deleteSessions(sessions: SessionRef[]): Promise<SessionActionReport> {
+    return await invoke("delete_sessions", { sessions });
+  },
+
+  async archiveSessions(sessions: SessionRef[]): Promise<SessionActionReport> {
+    return await invoke("archive_sessions", { sessions });
+  },
+
+  async listArchived(): Promise<ArchivedSession[]> {
+    return await invoke("list_archived_sessions");
+  },
+
+  async restoreArchived(archiveId: string): Promise<ArchivedSession> {
+    return await invoke("restore_archived_session", { archiveId });
+  },
+
+  async deleteArchived(archiveId: string): Promise<boolean> {
+    return await invoke("delete_archived_session", { archiveId });
+  },
+
+  async prune(rules: SessionPruneRules): Promise<SessionPruneReport> {
+    return await invoke("prune_sessions", { rules });
+  },
+
+  async watch(
+    providerId: string,
+    sourcePath: string,
+  ): Promise<LiveSessionState> {
+    return await invoke("watch_session", { providerId, sourcePath });
+  },
+
+  async unwatch(sourcePath: string): Promise<boolean> {
+    return await invoke("unwatch_session", { sourcePath });
+  },
+
+  async listLive(): Promise<LiveSessionState[]> {
+    return await invoke("get_live_sessions");
+  },
+
+  async setLiveDiscovery(enabled: boolean): Promise<boolean> {
+    return await invoke("set_live_session_discovery", { enabled });
+  },
+
+  async getAnalytics(
+    providerId: string,
+    sourcePath: string,
+  ): Promise<SessionAnalytics> {
+    return await invoke("get_session_analytics", { providerId, sourcePath });
+  },
+
+  async getProjectAnalytics(
+    query?: ProjectAnalyticsQuery,
+  ): Promise<ProjectAnalytics[]> {
+    return await invoke("get_project_analytics", { query });
+  },
+
+  async getSummaries(): Promise<SessionSummary[]> {
+    return await invoke("get_session_summaries");
+  },
+
+  async summarize(
+    providerId: string,
+    sourcePath: string,
+  ): Promise<SessionSummary> {
+    return await invoke("summarize_session", { providerId, sourcePath });
+  },
+
+  async getSummaryConfig(): Promise<SessionSummaryConfig> {
+    return await invoke("get_session_summary_config");
+  },
+
+  async saveSummaryConfig(config: SessionSummaryConfig): Promise<boolean> {
+    return await invoke("save_session_summary_config", { config });
+  },
 };
@@ -2,15 +2,38 @@ import { invoke } from "@tauri-apps/api/core";
 import type {
   UsageSummary,
   DailyStats,
+  ExchangeRate,
   ProviderStats,
   ModelStats,
   RequestLog,
   LogFilters,
   ModelPricing,
+  ModelPricingVersion,
+  ModelPricingAlias,
+  PricingCatalogFormat,
+  PricingImportOptions,
+  PricingImportPreview,
+  PricingImportResult,
+  PricingTier,
+  RecomputeCostsResult,
   ProviderLimitStatus,
   PaginatedLogs,
+  ProjectCostStats,
+  ProjectMonthlyCost,
+  SessionCostStats,
+  TranscriptIngestConfig,
+  TranscriptIngestResult,
+  UsageExportRequest,
+  UsageExportResult,
+  UsageForecast,
+  UsageForecastConfig,
+  UsageRetentionConfig,
+  UsageRetentionResult,
+  BalanceReconciliation,
+  BalanceReconciliationConfig,
+  BalanceSnapshot,
 } from "@/types/usage";
-import type { UsageResult } from "@/types";
+import type { QuotaExhaustion, UsageResult } from "@/types";
 import type { AppId } from "./types";
 
 export const usageApi = {
@@ -19,6 +42,17 @@ export const usageApi = {
     return invoke("queryProviderUsage", { providerId, app: appId });
   },
 
+  getQuotaExhaustions: async (appId?: AppId): Promise<QuotaExhaustion[]> => {
+    return invoke("get_quota_exhaustions", { app: appId });
+  },
+
+  clearQuotaExhaustion: async (
+    providerId: string,
+    appId: AppId,
+  ): Promise<void> => {
+    return invoke("clear_quota_exhaustion", { providerId, app: appId });
+  },
+
   testScript: async (
     providerId: string,
     appId: AppId,
@@ -47,23 +81,25 @@ export const usageApi = {
   getUsageSummary: async (
     startDate?: number,
     endDate?: number,
+    currency?: string,
   ): Promise<UsageSummary> => {
-    return invoke("get_usage_summary", { startDate, endDate });
+    return invoke("get_usage_summary", { startDate, endDate, currency });
   },
 
   getUsageTrends: async (
     startDate?: number,
     endDate?: number,
+    currency?: string,
   ): Promise<DailyStats[]> => {
-    return invoke("get_usage_trends", { startDate, endDate });
+    return invoke("get_usage_trends", { startDate, endDate, currency });
   },
 
-  getProviderStats: async (): Promise<ProviderStats[]> => {
-    return invoke("get_provider_stats");
+  getProviderStats: async (currency?: string): Promise<ProviderStats[]> => {
+    return invoke("get_provider_stats", { currency });
   },
 
-  getModelStats: async (): Promise<ModelStats[]> => {
-    return invoke("get_model_stats");
+  getModelStats: async (currency?: string): Promise<ModelStats[]> => {
+    return invoke("get_model_stats", { currency });
   },
 
   getRequestLogs: async (
@@ -93,6 +129,11 @@ export const usageApi = {
     outputCost: string,
     cacheReadCost: string,
     cacheCreationCost: string,
+    extras?: {
+      cacheCreation1hCost?: string;
+      tiers?: PricingTier[];
+      effectiveFrom?: number;
+    },
   ): Promise<void> => {
     return invoke("update_model_pricing", {
       modelId,
@@ -101,9 +142,211 @@ export const usageApi = {
       outputCost,
       cacheReadCost,
       cacheCreationCost,
+      cacheCreation1hCost: extras?.cacheCreation1hCost,
+      tiers: extras?.tiers,
+      effectiveFrom: extras?.effectiveFrom,
+    });
+  },
+
+  getModelPricingVersions: async (
+    modelId: string,
+  ): Promise<ModelPricingVersion[]> => {
+    return invoke("get_model_pricing_versions", { modelId });
+  },
+
+  addModelPricingHistory: async (
+    version: ModelPricingVersion,
+  ): Promise<number> => {
+    return invoke("add_model_pricing_history", { version });
+  },
+
+  deleteModelPricingHistory: async (id: number): Promise<void> => {
+    return invoke("delete_model_pricing_history", { id });
+  },
+
+  recomputeUsageCosts: async (
+    startDate?: number,
+    endDate?: number,
+  ): Promise<RecomputeCostsResult> => {
+    return invoke("recompute_usage_costs", { startDate, endDate });
+  },
+
+  backfillUnpricedUsageCosts: async (): Promise<RecomputeCostsResult> => {
+    return invoke("backfill_unpriced_usage_costs");
+  },
+
+  previewPricingCatalog: async (
+    filePath: string,
+    format?: PricingCatalogFormat,
+  ): Promise<PricingImportPreview> => {
+    return invoke("preview_pricing_catalog", { filePath, format });
+  },
+
+  importPricingCatalog: async (
+    filePath: string,
+    format?: PricingCatalogFormat,
+    options?: PricingImportOptions,
+  ): Promise<PricingImportResult> => {
+    return invoke("import_pricing_catalog", { filePath, format, options });
+  },
+
+  getModelPricingAliases: async (): Promise<ModelPricingAlias[]> => {
+    return invoke("get_model_pricing_aliases");
+  },
+
+  saveModelPricingAlias: async (alias: ModelPricingAlias): Promise<void> => {
+    return invoke("save_model_pricing_alias", { alias });
+  },
+
+  deleteModelPricingAlias: async (pattern: string): Promise<void> => {
+    return invoke("delete_model_pricing_alias", { pattern });
+  },
+
+  getTranscriptIngestConfig: async (): Promise<TranscriptIngestConfig> => {
+    return invoke("get_transcript_ingest_config");
+  },
+
+  saveTranscriptIngestConfig: async (
+    config: TranscriptIngestConfig,
+  ): Promise<void> => {
+    return invoke("save_transcript_ingest_config", { config });
+  },
+
+  runTranscriptIngestNow: async (): Promise<TranscriptIngestResult> => {
+    return invoke("run_transcript_ingest_now");
+  },
+
+  getTopSessionCosts: async (
+    startDate?: number,
+    endDate?: number,
+    limit?: number,
+    currency?: string,
+  ): Promise<SessionCostStats[]> => {
+    return invoke("get_top_session_costs", {
+      startDate,
+      endDate,
+      limit,
+      currency,
     });
   },
 
+  getProjectCosts: async (
+    startDate?: number,
+    endDate?: number,
+    currency?: string,
+  ): Promise<ProjectCostStats[]> => {
+    return invoke("get_project_costs", { startDate, endDate, currency });
+  },
+
+  getProjectMonthlyCosts: async (
+    startDate?: number,
+    endDate?: number,
+    currency?: string,
+  ): Promise<ProjectMonthlyCost[]> => {
+    return invoke("get_project_monthly_costs", {
+      startDate,
+      endDate,
+      currency,
+    });
+  },
+
+  getUsageRetentionConfig: async (): Promise<UsageRetentionConfig> => {
+    return invoke("get_usage_retention_config");
+  },
+
+  saveUsageRetentionConfig: async (
+    config: UsageRetentionConfig,
+  ): Promise<void> => {
+    return invoke("save_usage_retention_config", { config });
+  },
+
+  runUsageRetentionNow: async (): Promise<UsageRetentionResult> => {
+    return invoke("run_usage_retention_now");
+  },
+
+  getUsageForecastConfig: async (): Promise<UsageForecastConfig> => {
+    return invoke("get_usage_forecast_config");
+  },
+
+  saveUsageForecastConfig: async (
+    config: UsageForecastConfig,
+  ): Promise<void> => {
+    return invoke("save_usage_forecast_config", { config });
+  },
+
+  getUsageForecast: async (): Promise<UsageForecast> => {
+    return invoke("get_usage_forecast");
+  },
+
+  getBalanceReconciliationConfig:
+    async (): Promise<BalanceReconciliationConfig> => {
+      return invoke("get_balance_reconciliation_config");
+    },
+
+  saveBalanceReconciliationConfig: async (
+    config: BalanceReconciliationConfig,
+  ): Promise<void> => {
+    return invoke("save_balance_reconciliation_config", { config });
+  },
+
+  getBalanceSnapshots: async (
+    appType: string,
+    providerId: string,
+    planName?: string,
+    startDate?: number,
+    endDate?: number,
+  ): Promise<BalanceSnapshot[]> => {
+    return invoke("get_balance_snapshots", {
+      appType,
+      providerId,
+      planName,
+      startDate,
+      endDate,
+    });
+  },
+
+  getBalanceReconciliation: async (
+    startDate?: number,
+    endDate?: number,
+    appType?: string,
+  ): Promise<BalanceReconciliation[]> => {
+    return invoke("get_balance_reconciliation", {
+      startDate,
+      endDate,
+      appType,
+    });
+  },
+
+  exportUsage: async (
+    request: UsageExportRequest,
+    filePath: string,
+  ): Promise<UsageExportResult> => {
+    return invoke("export_usage", { request, filePath });
+  },
+
+  getExchangeRates: async (currency?: string): Promise<ExchangeRate[]> => {
+    return invoke("get_exchange_rates", { currency });
+  },
+
+  saveExchangeRate: async (rate: ExchangeRate): Promise<void> => {
+    return invoke("save_exchange_rate", { rate });
+  },
+
+  deleteExchangeRate: async (
+    currency: string,
+    effectiveFrom: number,
+  ): Promise<void> => {
+    return invoke("delete_exchange_rate", { currency, effectiveFrom });
+  },
+
+  getReportingCurrency: async (): Promise<string> => {
+    return invoke("get_reporting_currency");
+  },
+
+  setReportingCurrency: async (currency: string): Promise<void> => {
+    return invoke("set_reporting_currency", { currency });
+  },
+
   deleteModelPricing: async (modelId: string): Promise<void> => {
     return invoke("delete_model_pricing", { modelId });
   },
@@ -40,6 +40,8 @@ export interface CustomEndpoint {
   url: string;
   addedAt: number;
   lastUsed?: number;
+  latencyMs?: number;
+  lastTestedAt?: number;
 }
 
 // 端点候选项（用于端点测速弹窗）
@@ -62,6 +64,7 @@ export interface UsageScript {
   userId?: string; // 用户ID（NewAPI 模板使用）
   autoQueryInterval?: number; // 自动查询间隔（单位：分钟，0 表示禁用）
   autoIntervalMinutes?: number; // 自动查询间隔（分钟）- 别名字段
+  quotaGuard?: QuotaGuard; // 额度守护：按查询结果自动调整路由
   request?: {
     // 请求配置
     url?: string; // 请求 URL
@@ -71,6 +74,32 @@ export interface UsageScript {
   };
 }
 
+// 额度耗尽时的动作
+export type QuotaGuardAction =
+  | "deprioritize" // 排到故障转移队列末尾
+  | "removeFromQueue" // 移出故障转移队列
+  | "switchCurrent"; // 切换当前供应商到队列中的下一个
+
+// 额度守护规则
+export interface QuotaGuard {
+  enabled: boolean;
+  remainingBelow?: number; // 剩余额度低于该值视为耗尽
+  percentBelow?: number; // 剩余比例低于该值视为耗尽（0-100）
+  onInvalid?: boolean; // isValid=false 视为耗尽（默认 true）
+  action?: QuotaGuardAction;
+  autoRestore?: boolean; // 额度恢复后自动撤销动作（默认 true）
+}
+
+// 额度耗尽状态
+export interface QuotaExhaustion {
+  appType: string;
+  providerId: string;
+  reason: string;
+  removedFromQueue: boolean;
+  switchedTo?: string | null;
+  exhaustedAt: number;
+}
+
 // 单个套餐用量数据
 export interface UsageData {
   planName?: string; // 套餐名称（可选）
@@ -142,6 +171,10 @@ export interface ProviderMeta {
   costMultiplier?: string;
   // 供应商计费模式来源
   pricingModelSource?: string;
+  // 供应商计费币种（ISO 4217，默认 USD）
+  billingCurrency?: string;
+  // 充值汇率：每 1 USD 额度实际支付的计费币种金额
+  rechargeRate?: string;
   // Claude API 格式（仅 Claude 供应商使用）
   // - "anthropic": 原生 Anthropic Messages API 格式，直接透传
   // - "openai_chat": OpenAI Chat Completions 格式，需要格式转换
@@ -262,7 +295,7 @@ export interface Settings {
   // 首选终端应用（可选，默认使用系统默认终端）
   // macOS: "terminal" | "iterm2" | "warp" | "alacritty" | "kitty" | "ghostty"
   // Windows: "cmd" | "powershell" | "wt"
-  // Linux: "gnome-terminal" | "konsole" | "xfce4-terminal" | "alacritty" | "kitty" | "ghostty"
+  // Linux: "gnome-terminal" | "konsole" | "xfce4-terminal" | "alacritty" | "kitty" | "ghostty" | "wezterm" | "foot" | "tmux"
   preferredTerminal?: string;
 }
 
@@ -276,12 +309,329 @@ export interface SessionMeta {
   lastActiveAt?: number;
   sourcePath?: string;
   resumeCommand?: string;
+  // 派生该会话的父会话（Claude 子代理）
+  parentSessionId?: string;
+}
+
+// 消息内容块（按原始顺序）
+export type SessionContentPart =
+  | { type: "text"; text: string }
+  | { type: "thinking"; text: string; redacted?: boolean }
+  | {
+      type: "toolUse";
+      id?: string;
+      name: string;
+      input: unknown;
+      resultIndex?: number; // 对应结果所在的消息下标
+    }
+  | {
+      type: "toolResult";
+      toolUseId?: string;
+      name?: string;
+      content: string;
+      isError?: boolean;
+      useIndex?: number; // 对应调用所在的消息下标
+    }
+  | { type: "image"; mediaType?: string; url?: string };
+
+// 单轮用量（输入不含缓存命中，输出含推理）
+export interface SessionMessageUsage {
+  inputTokens: number;
+  outputTokens: number;
+  cacheReadTokens: number;
+  cacheCreationTokens: number;
+  reasoningTokens: number;
 }
 
 export interface SessionMessage {
   role: string;
   content: string;
   ts?: number;
+  id?: string;
+  parentId?: string;
+  parts?: SessionContentPart[];
+  model?: string;
+  usage?: SessionMessageUsage;
+  isSidechain?: boolean;
+}
+
+export type SessionExportFormat = "markdown" | "html" | "json";
+
+export interface SessionExportOptions {
+  format: SessionExportFormat;
+  redactSecrets?: boolean; // 默认 true
+  redactHome?: boolean; // 默认 true，用 ~ 替换主目录
+  includeThinking?: boolean;
+  includeToolCalls?: boolean;
+}
+
+export interface SessionExportResult {
+  path: string;
+  bytes: number;
+  messageCount: number;
+}
+
+// 可无损重新导入的会话包
+export interface SessionBundle {
+  version: number;
+  exportedAt: number;
+  session: SessionMeta;
+  messages: SessionMessage[];
+}
+
+export type SessionConvertTarget = "claude" | "codex" | "gemini";
+
+// 将会话转换为另一个 CLI 的格式
+export interface SessionConvertRequest {
+  providerId: string;
+  sourcePath: string;
+  target: SessionConvertTarget;
+  cwd?: string;
+  toolCallsAsText?: boolean;
+}
+
+export interface SessionConvertResult {
+  target: SessionConvertTarget;
+  sessionId: string;
+  path: string;
+  cwd: string;
+  resumeCommand: string;
+  // 启动 CLI 后需要输入的命令（Gemini 检查点）
+  followUp?: string;
+  messageCount: number;
+}
+
+export interface SessionRef {
+  providerId: string;
+  sourcePath: string;
+}
+
+// 会话自定义标题与标签（保存在 cc-switch 数据库中）
+export interface SessionAnnotation {
+  providerId: string;
+  sessionId: string;
+  title?: string;
+  tags: string[];
+  updatedAt: number;
+}
+
+export interface ArchivedSession {
+  id: string;
+  providerId: string;
+  sessionId: string;
+  title?: string;
+  projectDir?: string;
+  sourcePath: string;
+  archivePath: string;
+  fileCount: number;
+  originalBytes: number;
+  archivedBytes: number;
+  lastActiveAt?: number;
+  archivedAt: number;
+}
+
+export interface SessionActionReport {
+  succeeded: string[];
+  failed: { sourcePath: string; error: string }[];
+  bytes: number;
+}
+
+export type SessionPruneAction = "archive" | "delete";
+
+// 批量清理规则：命中任一已启用规则即被选中
+export interface SessionPruneRules {
+  olderThanDays?: number;
+  minMessages?: number;
+  missingProject?: boolean;
+  providerIds?: string[];
+  keepAnnotated?: boolean;
+  action?: SessionPruneAction;
+  dryRun?: boolean;
+}
+
+export type SessionPruneReason = "inactive" | "fewMessages" | "missingProject";
+
+export interface SessionPruneCandidate {
+  providerId: string;
+  sessionId: string;
+  sourcePath: string;
+  title?: string;
+  projectDir?: string;
+  lastActiveAt?: number;
+  messageCount?: number;
+  bytes: number;
+  reasons: SessionPruneReason[];
+}
+
+export interface SessionPruneReport {
+  dryRun: boolean;
+  action: SessionPruneAction;
+  candidates: SessionPruneCandidate[];
+  totalBytes: number;
+  result?: SessionActionReport;
+}
+
+// 实时会话状态：closed 表示会话文件已删除，不再推送事件
+export type SessionLiveStatus = "active" | "waitingForInput" | "idle" | "closed";
+
+export interface LiveSessionState {
+  providerId: string;
+  sourcePath: string;
+  sessionId?: string;
+  status: SessionLiveStatus;
+  lastActivityAt: number;
+  pendingTool?: string;
+  // 由新会话发现自动监听，而非用户手动订阅
+  auto: boolean;
+}
+
+// session-live-messages 事件负载
+export interface LiveSessionMessages {
+  providerId: string;
+  sourcePath: string;
+  messages: SessionMessage[];
+}
+
+export interface SessionToolCallStats {
+  name: string;
+  calls: number;
+  errors: number;
+}
+
+export interface SessionFileTouch {
+  path: string;
+  reads: number;
+  writes: number;
+}
+
+// 单轮统计：一次用户提问到下一次提问之间的所有活动
+export interface SessionTurnStats {
+  index: number;
+  startedAt?: number;
+  endedAt?: number;
+  prompt: string;
+  toolCalls: number;
+  errors: number;
+  usage: SessionMessageUsage;
+  models?: { model: string; usage: SessionMessageUsage }[];
+  // 所有模型均无定价时为空
+  costUsd?: string;
+}
+
+export interface SessionAnalytics {
+  providerId: string;
+  sessionId: string;
+  sourcePath: string;
+  title?: string;
+  projectDir?: string;
+  parentSessionId?: string;
+  startedAt?: number;
+  endedAt?: number;
+  wallTimeMs: number;
+  // 消息间隔超过 5 分钟视为空闲，不计入活跃时长
+  activeTimeMs: number;
+  userTurns: number;
+  assistantTurns: number;
+  toolCalls: SessionToolCallStats[];
+  filesTouched: SessionFileTouch[];
+  usage: SessionMessageUsage;
+  errors: number;
+  turns: SessionTurnStats[];
+  totalCostUsd: string;
+  unpricedModels: string[];
+}
+
+export interface ProjectAnalyticsQuery {
+  providerId?: string;
+  startMs?: number;
+  endMs?: number;
+}
+
+export interface ProjectAnalytics {
+  projectDir?: string;
+  sessionCount: number;
+  wallTimeMs: number;
+  activeTimeMs: number;
+  userTurns: number;
+  assistantTurns: number;
+  toolCalls: SessionToolCallStats[];
+  filesTouched: number;
+  usage: SessionMessageUsage;
+  errors: number;
+  totalCostUsd: string;
+  unpricedModels: string[];
+  lastActiveAt?: number;
+  topSessions: {
+    providerId: string;
+    sessionId: string;
+    sourcePath: string;
+    title?: string;
+    wallTimeMs: number;
+    totalCostUsd: string;
+  }[];
+}
+
+// 会话自动摘要配置：dailyBudgetUsd 为当日所有摘要请求的花费上限
+export interface SessionSummaryConfig {
+  enabled: boolean;
+  appType: "claude" | "codex" | "gemini";
+  // 为空时使用该应用的当前供应商
+  providerId?: string;
+  // 为空时使用流式检查中配置的模型
+  model?: string;
+  dailyBudgetUsd: string;
+  intervalMins: number;
+  minPrompts: number;
+  maxTranscriptChars: number;
+}
+
+export interface SessionSummary {
+  providerId: string;
+  sessionId: string;
+  sourcePath: string;
+  title: string;
+  summary: string;
+  keywords: string[];
+  model: string;
+  costUsd: string;
+  fileSize: number;
+  modifiedAt: number;
+  generatedAt: number;
+}
+
+export interface SessionSearchQuery {
+  query: string;
+  providerId?: string;
+  projectDir?: string;
+  startMs?: number;
+  endMs?: number;
+  limit?: number;
+  offset?: number;
+}
+
+export interface SessionSearchHit {
+  providerId: string;
+  sessionId: string;
+  sourcePath: string;
+  title?: string;
+  projectDir?: string;
+  lastActiveAt?: number;
+  messageIndex: number;
+  role: string;
+  ts?: number;
+  snippet: string;
+  // [start, end) ranges in snippet (UTF-16 code units)
+  highlights: [number, number][];
+  score: number;
+}
+
+export interface SessionIndexStats {
+  scanned: number;
+  indexed: number;
+  unchanged: number;
+  removed: number;
+  failed: number;
+  durationMs: number;
 }
 
 // MCP 服务器连接参数（宽松：允许扩展字段）
@@ -7,6 +7,8 @@ export interface TokenUsage {
   cacheCreationTokens: number;
 }
 
+export type UsageSource = "proxy" | "transcript";
+
 export interface RequestLog {
   requestId: string;
   providerId: string;
@@ -19,11 +21,14 @@ export interface RequestLog {
   outputTokens: number;
   cacheReadTokens: number;
   cacheCreationTokens: number;
+  cacheCreation1hTokens?: number;
   inputCostUsd: string;
   outputCostUsd: string;
   cacheReadCostUsd: string;
   cacheCreationCostUsd: string;
   totalCostUsd: string;
+  billingCurrency?: string;
+  billingCost?: string;
   isStreaming: boolean;
   latencyMs: number;
   firstTokenMs?: number;
@@ -31,6 +36,14 @@ export interface RequestLog {
   statusCode: number;
   errorMessage?: string;
   createdAt: number;
+  endpointUrl?: string;
+  // 用量来源：proxy（代理记录）或 transcript（会话记录导入）
+  source?: UsageSource;
+  sessionId?: string;
+  projectDir?: string;
+  // 映射后发往上游的模型，及上游返回模型是否与之不一致
+  expectedModel?: string;
+  modelMismatch?: boolean;
 }
 
 export interface PaginatedLogs {
@@ -40,6 +53,15 @@ export interface PaginatedLogs {
   pageSize: number;
 }
 
+export interface PricingTier {
+  aboveInputTokens: number;
+  inputCostPerMillion: string;
+  outputCostPerMillion: string;
+  cacheReadCostPerMillion?: string;
+  cacheCreationCostPerMillion?: string;
+  cacheCreation1hCostPerMillion?: string;
+}
+
 export interface ModelPricing {
   modelId: string;
   displayName: string;
@@ -47,11 +69,91 @@ export interface ModelPricing {
   outputCostPerMillion: string;
   cacheReadCostPerMillion: string;
   cacheCreationCostPerMillion: string;
+  cacheCreation1hCostPerMillion?: string;
+  tiers?: PricingTier[];
+  effectiveFrom?: number;
+}
+
+export interface ModelPricingVersion {
+  id?: number;
+  modelId: string;
+  inputCostPerMillion: string;
+  outputCostPerMillion: string;
+  cacheReadCostPerMillion: string;
+  cacheCreationCostPerMillion: string;
+  cacheCreation1hCostPerMillion?: string;
+  tiers: PricingTier[];
+  effectiveFrom: number;
+  effectiveTo?: number;
+}
+
+export interface RecomputeCostsResult {
+  scanned: number;
+  updated: number;
+  missingPricing: number;
+}
+
+export type PricingCatalogFormat = "litellm" | "openrouter" | "csv";
+
+export interface SkippedCatalogEntry {
+  modelId: string;
+  reason: string;
+}
+
+export interface PricingImportChange {
+  modelId: string;
+  displayName: string;
+  kind: "added" | "updated" | "unchanged";
+  current?: ModelPricingVersion;
+  incoming: ModelPricingVersion;
+  // 使用该模型但尚未计价的请求数
+  unpricedRequests: number;
+}
+
+export interface PricingImportPreview {
+  format: PricingCatalogFormat;
+  added: number;
+  updated: number;
+  unchanged: number;
+  changes: PricingImportChange[];
+  skipped: SkippedCatalogEntry[];
+}
+
+export interface PricingImportOptions {
+  overwriteExisting?: boolean;
+  modelIds?: string[];
+  effectiveFrom?: number;
+  backfill?: boolean;
+}
+
+export interface PricingImportResult {
+  added: number;
+  updated: number;
+  unchanged: number;
+  keptExisting: number;
+  backfill?: RecomputeCostsResult;
+}
+
+export interface ModelPricingAlias {
+  pattern: string;
+  modelId: string;
+  isRegex: boolean;
+  priority: number;
+  createdAt?: number;
+}
+
+export interface ExchangeRate {
+  currency: string;
+  // 1 USD 可兑换的该币种数量
+  rate: string;
+  effectiveFrom: number;
+  source?: string;
 }
 
 export interface UsageSummary {
   totalRequests: number;
   totalCost: string;
+  currency: string;
   totalInputTokens: number;
   totalOutputTokens: number;
   totalCacheCreationTokens: number;
@@ -76,6 +178,7 @@ export interface ProviderStats {
   requestCount: number;
   totalTokens: number;
   totalCost: string;
+  currency: string;
   successRate: number;
   avgLatencyMs: number;
 }
@@ -95,6 +198,7 @@ export interface LogFilters {
   statusCode?: number;
   startDate?: number;
   endDate?: number;
+  source?: UsageSource;
 }
 
 export interface ProviderLimitStatus {
@@ -114,3 +218,182 @@ export interface StatsFilters {
   providerId?: string;
   appType?: string;
 }
+
+export interface TranscriptIngestConfig {
+  enabled: boolean;
+  intervalSecs: number;
+}
+
+export interface TranscriptIngestResult {
+  filesScanned: number;
+  filesChanged: number;
+  inserted: number;
+  updated: number;
+  proxied: number;
+  failedFiles: number;
+}
+
+export interface SessionCostStats {
+  sessionId: string;
+  appType: string;
+  projectDir?: string;
+  title?: string;
+  requestCount: number;
+  totalInputTokens: number;
+  totalOutputTokens: number;
+  totalCacheReadTokens: number;
+  totalCacheCreationTokens: number;
+  totalCost: string;
+  currency: string;
+  firstRequestAt: number;
+  lastRequestAt: number;
+}
+
+export interface ProjectCostStats {
+  projectDir?: string;
+  sessionCount: number;
+  requestCount: number;
+  totalTokens: number;
+  totalCost: string;
+  currency: string;
+}
+
+export interface ProjectMonthlyCost {
+  month: string;
+  projectDir?: string;
+  requestCount: number;
+  totalTokens: number;
+  totalCost: string;
+  currency: string;
+}
+
+export type UsageFileFormat = "csv" | "jsonl";
+
+export interface UsageRetentionConfig {
+  enabled: boolean;
+  retainDays: number;
+  hourlyDays: number;
+  archiveFormat: UsageFileFormat;
+  archiveDir?: string;
+}
+
+export interface UsageRetentionResult {
+  archivedRows: number;
+  archivePath?: string;
+  prunedHourlyRows: number;
+}
+
+export interface UsageForecastConfig {
+  enabled: boolean;
+  lookbackDays: number;
+  burnWindowDays: number;
+  costSpikeRatio: number;
+  minSpikeCostUsd: number;
+  errorRateIncrease: number;
+  minRequests: number;
+  minBaselineDays: number;
+}
+
+export type UsageAlertKind =
+  | "limitProjected"
+  | "limitReached"
+  | "costSpike"
+  | "errorRateSpike";
+
+export type UsageAlertSeverity = "warning" | "critical";
+
+export type UsageAlertScope = "global" | "provider" | "model";
+
+export interface UsageAlert {
+  key: string;
+  kind: UsageAlertKind;
+  severity: UsageAlertSeverity;
+  scope: UsageAlertScope;
+  appType?: string;
+  providerId?: string;
+  providerName?: string;
+  model?: string;
+  title: string;
+  message: string;
+  value: number;
+  baseline: number;
+  projectedDate?: string;
+}
+
+export interface UsageForecast {
+  generatedAt: number;
+  todaySpendUsd: number;
+  monthToDateUsd: number;
+  dailyBurnRateUsd: number;
+  projectedMonthEndUsd: number;
+  daysRemaining: number;
+  monthlyThresholdUsd?: number;
+  projectedThresholdDate?: string;
+  alerts: UsageAlert[];
+}
+
+export interface BalanceReconciliationConfig {
+  enabled: boolean;
+  snapshotIntervalMins: number;
+  tolerance: number;
+  minComputedCost: number;
+  keepDays: number;
+}
+
+export interface BalanceSnapshot {
+  id: number;
+  appType: string;
+  providerId: string;
+  planName: string;
+  used?: number;
+  remaining?: number;
+  total?: number;
+  unit?: string;
+  createdAt: number;
+}
+
+export type ReconciliationStatus =
+  | "consistent"
+  | "overcharged"
+  | "undercharged"
+  | "insufficient";
+
+export interface BalanceReconciliation {
+  appType: string;
+  providerId: string;
+  providerName?: string;
+  planName: string;
+  unit?: string;
+  periodStart: number;
+  periodEnd: number;
+  snapshotCount: number;
+  intervalCount: number;
+  skippedIntervals: number;
+  requestCount: number;
+  reportedUsage: number;
+  computedCost: number;
+  baseCost: number;
+  costCurrency: string;
+  effectiveMultiplier?: number;
+  status: ReconciliationStatus;
+  message: string;
+}
+
+export type UsageExportKind =
+  | "logs"
+  | "summary"
+  | "trends"
+  | "providers"
+  | "models";
+
+export interface UsageExportRequest {
+  kind: UsageExportKind;
+  format?: UsageFileFormat;
+  filters?: LogFilters;
+  currency?: string;
+}
+
+export interface UsageExportResult {
+  rows: number;
+  path: string;
+}